@@ -156,11 +156,11 @@ fn extract_coach_feedback_from_logs(
         .get_session_id()
         .ok_or_else(|| anyhow::anyhow!("Coach agent has no session ID"))?;
 
-    // Try new .g3/sessions/<session_id>/session.json path first
-    let log_file_path = g3_core::get_session_file(&session_id);
+    // Try new .g3/sessions/<session_id>/session.json(.zst) path first
+    let log_file_path = g3_core::find_session_log(&g3_core::get_session_file(&session_id));
     
     // Fall back to old logs/ path if new path doesn't exist
-    let log_file_path = if log_file_path.exists() {
+    let log_file_path = if let Some(log_file_path) = log_file_path {
         log_file_path
     } else {
         let logs_dir = std::path::Path::new("logs");
@@ -169,7 +169,7 @@ fn extract_coach_feedback_from_logs(
 
     // Read the coach agent's specific log file
     if log_file_path.exists() {
-        if let Ok(log_content) = std::fs::read_to_string(&log_file_path) {
+        if let Ok(log_content) = g3_core::read_session_log(&log_file_path) {
             if let Ok(log_json) = serde_json::from_str::<serde_json::Value>(&log_content) {
                 if let Some(context_window) = log_json.get("context_window") {
                     if let Some(conversation_history) = context_window.get("conversation_history") {
@@ -334,7 +334,7 @@ pub struct Cli {
     #[arg(long)]
     pub machine: bool,
 
-    /// Override the configured provider (anthropic, databricks, embedded, openai)
+    /// Override the configured provider (anthropic, databricks, embedded, gemini, ollama, openai)
     #[arg(long, value_name = "PROVIDER")]
     pub provider: Option<String>,
 
@@ -399,6 +399,18 @@ pub struct Cli {
     /// Skip session resumption and force a new session (for agent mode)
     #[arg(long)]
     pub new_session: bool,
+
+    /// Disable caching of read-only tool results (read_file, code_search, ...)
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Show the tool calls the agent would make without executing them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Append tool timing telemetry (JSONL) to this file instead of .g3/session/telemetry.jsonl
+    #[arg(long, value_name = "PATH")]
+    pub telemetry_file: Option<String>,
 }
 
 pub async fn run() -> Result<()> {
@@ -569,9 +581,24 @@ pub async fn run() -> Result<()> {
         config.agent.auto_compact = false;
     }
 
+    // Apply no-cache flag override
+    if cli.no_cache {
+        config.agent.tool_cache_ttl_seconds = 0;
+    }
+
+    // Apply dry-run flag override
+    if cli.dry_run {
+        config.agent.dry_run = true;
+    }
+
+    // Apply telemetry file override
+    if let Some(path) = &cli.telemetry_file {
+        config.agent.telemetry_file = Some(path.clone());
+    }
+
     // Validate provider if specified
     if let Some(ref provider) = cli.provider {
-        let valid_providers = ["anthropic", "databricks", "embedded", "openai"];
+        let valid_providers = ["anthropic", "databricks", "embedded", "gemini", "ollama", "openai"];
         if !valid_providers.contains(&provider.as_str()) {
             return Err(anyhow::anyhow!(
                 "Invalid provider '{}'. Valid options: {:?}",
@@ -1025,6 +1052,21 @@ async fn run_accumulative_mode(
                                 config.agent.auto_compact = false;
                             }
 
+                            // Apply no-cache flag override
+                            if cli.no_cache {
+                                config.agent.tool_cache_ttl_seconds = 0;
+                            }
+
+                            // Apply dry-run flag override
+                            if cli.dry_run {
+                                config.agent.dry_run = true;
+                            }
+
+                            // Apply telemetry file override
+                            if let Some(path) = &cli.telemetry_file {
+                                config.agent.telemetry_file = Some(path.clone());
+                            }
+
                             // Create agent for interactive mode with requirements context
                             let ui_writer = ConsoleUiWriter::new();
                             let agent = Agent::new_with_readme_and_quiet(
@@ -1127,6 +1169,21 @@ async fn run_accumulative_mode(
                     config.agent.auto_compact = false;
                 }
 
+                // Apply no-cache flag override
+                if cli.no_cache {
+                    config.agent.tool_cache_ttl_seconds = 0;
+                }
+
+                // Apply dry-run flag override
+                if cli.dry_run {
+                    config.agent.dry_run = true;
+                }
+
+                // Apply telemetry file override
+                if let Some(path) = &cli.telemetry_file {
+                    config.agent.telemetry_file = Some(path.clone());
+                }
+
                 // Create agent for this autonomous run
                 let ui_writer = ConsoleUiWriter::new();
                 let agent = Agent::new_autonomous_with_readme_and_quiet(
@@ -1675,6 +1732,10 @@ async fn run_interactive<W: UiWriter>(
                                 output.print("  /thinnify  - Trigger context thinning (replaces large tool results with file references)");
                                 output.print("  /skinnify  - Trigger full context thinning (like /thinnify but for entire context, not just first third)");
                                 output.print("  /clear     - Clear session and start fresh (discards continuation artifacts)");
+                                output.print("  /checkpoint <name> - Save the session as a named checkpoint (lists checkpoints without a name)");
+                                output.print("  /restore <name>    - Return to a saved checkpoint");
+                                output.print("  /undo      - Revert the last file write or str_replace");
+                                output.print("  /redo      - Re-apply the last undone file change");
                                 output.print(
                                     "  /readme    - Reload README.md and AGENTS.md from disk",
                                 );
@@ -1737,6 +1798,64 @@ async fn run_interactive<W: UiWriter>(
                                 output.print(&stats);
                                 continue;
                             }
+                            "/undo" => {
+                                match agent.rollback_last_file_operation() {
+                                    Ok(Some(operation)) => output.print(&format!(
+                                        "↩️ Undid {}",
+                                        describe_file_operation(&operation)
+                                    )),
+                                    Ok(None) => output.print("Nothing to undo"),
+                                    Err(e) => output.print(&format!("❌ Failed to undo: {:#}", e)),
+                                }
+                                continue;
+                            }
+                            "/redo" => {
+                                match agent.redo_last_file_operation() {
+                                    Ok(Some(operation)) => output.print(&format!(
+                                        "↪️ Redid {}",
+                                        describe_file_operation(&operation)
+                                    )),
+                                    Ok(None) => output.print("Nothing to redo"),
+                                    Err(e) => output.print(&format!("❌ Failed to redo: {:#}", e)),
+                                }
+                                continue;
+                            }
+                            cmd if cmd.starts_with("/checkpoint") || cmd.starts_with("/restore") => {
+                                let (command, name) = cmd.split_once(' ').unwrap_or((cmd, ""));
+                                let name = name.trim();
+                                if name.is_empty() {
+                                    print_checkpoints(&output);
+                                } else if command == "/checkpoint" {
+                                    match agent.save_checkpoint(name) {
+                                        Ok(path) => output.print(&format!(
+                                            "✅ Saved checkpoint '{}' to {}",
+                                            name,
+                                            path.display()
+                                        )),
+                                        Err(e) => output
+                                            .print(&format!("❌ Failed to save checkpoint: {}", e)),
+                                    }
+                                } else if command == "/restore" {
+                                    match agent.restore_checkpoint(name) {
+                                        Ok(true) => output.print(&format!(
+                                            "✅ Restored checkpoint '{}' with full context",
+                                            name
+                                        )),
+                                        Ok(false) => output.print(&format!(
+                                            "✅ Restored checkpoint '{}' from its summary",
+                                            name
+                                        )),
+                                        Err(e) => output
+                                            .print(&format!("❌ Failed to restore checkpoint: {}", e)),
+                                    }
+                                } else {
+                                    output.print(&format!(
+                                        "❌ Unknown command: {}. Type /help for available commands.",
+                                        input
+                                    ));
+                                }
+                                continue;
+                            }
                             _ => {
                                 output.print(&format!(
                                     "❌ Unknown command: {}. Type /help for available commands.",
@@ -1961,7 +2080,47 @@ async fn run_interactive_machine(
                         }
                         "/help" => {
                             println!("COMMAND: help");
-                            println!("AVAILABLE_COMMANDS: /compact /thinnify /skinnify /clear /readme /stats /help");
+                            println!("AVAILABLE_COMMANDS: /compact /thinnify /skinnify /clear /readme /stats /checkpoint /restore /undo /redo /help");
+                            continue;
+                        }
+                        "/undo" => {
+                            println!("COMMAND: undo");
+                            match agent.rollback_last_file_operation() {
+                                Ok(Some(operation)) => println!(
+                                    "RESULT: Undid {}",
+                                    describe_file_operation(&operation)
+                                ),
+                                Ok(None) => println!("RESULT: Nothing to undo"),
+                                Err(e) => println!("ERROR: {:#}", e),
+                            }
+                            continue;
+                        }
+                        "/redo" => {
+                            println!("COMMAND: redo");
+                            match agent.redo_last_file_operation() {
+                                Ok(Some(operation)) => println!(
+                                    "RESULT: Redid {}",
+                                    describe_file_operation(&operation)
+                                ),
+                                Ok(None) => println!("RESULT: Nothing to redo"),
+                                Err(e) => println!("ERROR: {:#}", e),
+                            }
+                            continue;
+                        }
+                        cmd if cmd.starts_with("/checkpoint ") => {
+                            println!("COMMAND: checkpoint");
+                            match agent.save_checkpoint(cmd["/checkpoint ".len()..].trim()) {
+                                Ok(path) => println!("RESULT: Checkpoint saved to {}", path.display()),
+                                Err(e) => println!("ERROR: {}", e),
+                            }
+                            continue;
+                        }
+                        cmd if cmd.starts_with("/restore ") => {
+                            println!("COMMAND: restore");
+                            match agent.restore_checkpoint(cmd["/restore ".len()..].trim()) {
+                                Ok(full) => println!("RESULT: Checkpoint restored (full_context: {})", full),
+                                Err(e) => println!("ERROR: {}", e),
+                            }
                             continue;
                         }
                         _ => {
@@ -2073,6 +2232,41 @@ async fn execute_task_machine(
     }
 }
 
+/// List the current session's checkpoints (for /checkpoint and /restore without a name)
+fn print_checkpoints(output: &SimpleOutput) {
+    match g3_core::list_checkpoints() {
+        Ok(checkpoints) if checkpoints.is_empty() => {
+            output.print("No checkpoints yet. Save one with /checkpoint <name>.");
+        }
+        Ok(checkpoints) => {
+            output.print("📌 Checkpoints:");
+            for checkpoint in checkpoints {
+                output.print(&format!(
+                    "  {:<20} {} ({:.0}% context)",
+                    checkpoint.name, checkpoint.created_at, checkpoint.context_percentage
+                ));
+            }
+        }
+        Err(e) => output.print(&format!("❌ Failed to list checkpoints: {}", e)),
+    }
+}
+
+/// e.g. "str_replace on src/main.rs (2m ago)" for /undo and /redo
+fn describe_file_operation(operation: &g3_core::file_history::FileOperation) -> String {
+    let elapsed = operation.timestamp.elapsed().as_secs();
+    let ago = if elapsed < 60 {
+        format!("{}s", elapsed)
+    } else {
+        format!("{}m", elapsed / 60)
+    };
+    format!(
+        "{} on {} ({} ago)",
+        operation.operation,
+        operation.path.display(),
+        ago
+    )
+}
+
 fn handle_execution_error(e: &anyhow::Error, input: &str, output: &SimpleOutput, attempt: u32) {
     // Enhanced error logging with detailed information
     error!("=== TASK EXECUTION ERROR ===");
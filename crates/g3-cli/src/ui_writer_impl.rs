@@ -228,7 +228,7 @@ impl UiWriter for ConsoleUiWriter {
         );
     }
 
-    fn print_tool_timing(&self, duration_str: &str, tokens_delta: u32, context_percentage: f32) {
+    fn print_tool_timing(&self, duration_str: &str, tokens_delta: u32, context_percentage: f32, session_cost_usd: f64) {
         // Parse the duration string to determine color
         // Format is like "1.5s", "500ms", "2m 30.0s"
         let color_code = if duration_str.ends_with("ms") {
@@ -270,7 +270,13 @@ impl UiWriter for ConsoleUiWriter {
             ""
         };
 
-        println!("└─ ⚡️ {}{}\x1b[0m  \x1b[2m{} ◉ | {:.0}%\x1b[0m", color_code, duration_str, tokens_delta, context_percentage);
+        // Unpriced models (e.g. local ones) never accumulate cost, so leave it off
+        let cost = if session_cost_usd > 0.0 {
+            format!(" | ${:.4}", session_cost_usd)
+        } else {
+            String::new()
+        };
+        println!("└─ ⚡️ {}{}\x1b[0m  \x1b[2m{} ◉ | {:.0}%{}\x1b[0m", color_code, duration_str, tokens_delta, context_percentage, cost);
         println!();
         
         // CRITICAL: Add explicit flush after tool timing to ensure all output is visible
@@ -13,7 +13,7 @@ pub use webdriver::{
 };
 
 // Re-export macax types for convenience
-pub use macax::{AXApplication, AXElement, MacAxController};
+pub use macax::{AXApplication, AXElement, MacAxController, WindowInfo};
 
 use anyhow::Result;
 use async_trait::async_trait;
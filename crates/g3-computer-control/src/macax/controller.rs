@@ -1,4 +1,5 @@
-use super::{AXApplication, AXElement};
+use super::key_sequence::{KeySequenceParser, KeySequencePart};
+use super::{AXApplication, AXElement, ElementSelector, WindowInfo};
 use anyhow::{Context, Result};
 use std::collections::HashMap;
 
@@ -253,6 +254,156 @@ impl MacAxController {
         anyhow::bail!("Not supported on this platform")
     }
 
+    /// List the top-level windows of an application
+    #[cfg(target_os = "macos")]
+    pub fn list_windows(&self, app_name: &str) -> Result<Vec<WindowInfo>> {
+        let windows = self.get_windows(app_name)?;
+
+        let mut result = Vec::new();
+        for (id, window) in windows.iter().enumerate() {
+            let title = window.title().map(|s| s.to_string()).unwrap_or_default();
+            let minimized = window.minimized().map(|b| b.into()).unwrap_or(false);
+            let (x, y) = Self::point_attribute(window, "AXPosition").unwrap_or((0.0, 0.0));
+            let (width, height) = Self::size_attribute(window, "AXSize").unwrap_or((0.0, 0.0));
+
+            result.push(WindowInfo {
+                id,
+                title,
+                minimized,
+                bounds: crate::types::Rect {
+                    x: x as i32,
+                    y: y as i32,
+                    width: width as i32,
+                    height: height as i32,
+                },
+            });
+        }
+
+        Ok(result)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn list_windows(&self, _app_name: &str) -> Result<Vec<WindowInfo>> {
+        anyhow::bail!("Not supported on this platform")
+    }
+
+    /// Bring a window (by `id` from `list_windows`) to the front
+    #[cfg(target_os = "macos")]
+    pub fn focus_window(&self, app_name: &str, window_id: usize) -> Result<()> {
+        use core_foundation::boolean::CFBoolean;
+
+        let window = self.get_window(app_name, window_id)?;
+
+        // Un-minimize first, otherwise raising has no visible effect
+        if window.minimized().map(bool::from).unwrap_or(false) {
+            let minimized = accessibility::AXAttribute::minimized();
+            window
+                .set_attribute(&minimized, CFBoolean::false_value())
+                .map_err(|e| anyhow::anyhow!("Failed to restore window: {:?}", e))?;
+        }
+
+        window
+            .perform_action(&CFString::new("AXRaise"))
+            .map_err(|e| anyhow::anyhow!("Failed to raise window: {:?}", e))?;
+        let _ = window.set_main(CFBoolean::true_value());
+
+        self.activate_app(app_name)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn focus_window(&self, _app_name: &str, _window_id: usize) -> Result<()> {
+        anyhow::bail!("Not supported on this platform")
+    }
+
+    /// Close a window (by `id` from `list_windows`) by pressing its close button
+    #[cfg(target_os = "macos")]
+    pub fn close_window(&self, app_name: &str, window_id: usize) -> Result<()> {
+        let window = self.get_window(app_name, window_id)?;
+
+        let close_button = window
+            .attribute(&accessibility::AXAttribute::new(&CFString::new(
+                "AXCloseButton",
+            )))
+            .ok()
+            .and_then(|button| button.downcast::<AXUIElement>())
+            .ok_or_else(|| anyhow::anyhow!("Window {} has no close button", window_id))?;
+
+        close_button
+            .perform_action(&CFString::new("AXPress"))
+            .map_err(|e| anyhow::anyhow!("Failed to close window: {:?}", e))?;
+
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn close_window(&self, _app_name: &str, _window_id: usize) -> Result<()> {
+        anyhow::bail!("Not supported on this platform")
+    }
+
+    #[cfg(target_os = "macos")]
+    fn get_windows(&self, app_name: &str) -> Result<Vec<AXUIElement>> {
+        let app_element = self.get_app_element(app_name)?;
+        let windows = app_element
+            .windows()
+            .map_err(|e| anyhow::anyhow!("Failed to get windows of '{}': {:?}", app_name, e))?;
+
+        Ok(windows.iter().map(|w| (*w).clone()).collect())
+    }
+
+    #[cfg(target_os = "macos")]
+    fn get_window(&self, app_name: &str, window_id: usize) -> Result<AXUIElement> {
+        let windows = self.get_windows(app_name)?;
+        let count = windows.len();
+        windows.into_iter().nth(window_id).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Window {} not found in '{}' ({} windows open)",
+                window_id,
+                app_name,
+                count
+            )
+        })
+    }
+
+    /// Read a CGPoint-valued attribute such as `AXPosition`
+    #[cfg(target_os = "macos")]
+    fn point_attribute(element: &AXUIElement, name: &str) -> Option<(f64, f64)> {
+        use accessibility_sys::{kAXValueTypeCGPoint, AXValueGetValue, AXValueRef};
+        use core_graphics::geometry::CGPoint;
+
+        let value = element
+            .attribute(&accessibility::AXAttribute::new(&CFString::new(name)))
+            .ok()?;
+        let mut point = CGPoint::new(0.0, 0.0);
+        let ok = unsafe {
+            AXValueGetValue(
+                value.as_CFTypeRef() as AXValueRef,
+                kAXValueTypeCGPoint,
+                &mut point as *mut CGPoint as *mut std::ffi::c_void,
+            )
+        };
+        ok.then_some((point.x, point.y))
+    }
+
+    /// Read a CGSize-valued attribute such as `AXSize`
+    #[cfg(target_os = "macos")]
+    fn size_attribute(element: &AXUIElement, name: &str) -> Option<(f64, f64)> {
+        use accessibility_sys::{kAXValueTypeCGSize, AXValueGetValue, AXValueRef};
+        use core_graphics::geometry::CGSize;
+
+        let value = element
+            .attribute(&accessibility::AXAttribute::new(&CFString::new(name)))
+            .ok()?;
+        let mut size = CGSize::new(0.0, 0.0);
+        let ok = unsafe {
+            AXValueGetValue(
+                value.as_CFTypeRef() as AXValueRef,
+                kAXValueTypeCGSize,
+                &mut size as *mut CGSize as *mut std::ffi::c_void,
+            )
+        };
+        ok.then_some((size.width, size.height))
+    }
+
     #[cfg(target_os = "macos")]
     fn build_ui_tree(
         element: &AXUIElement,
@@ -432,6 +583,87 @@ impl MacAxController {
         anyhow::bail!("Not supported on this platform")
     }
 
+    /// Find the first element matching `selector`, including its position and size
+    pub fn find_element_by_selector(
+        &self,
+        app_name: &str,
+        selector: &ElementSelector,
+    ) -> Result<AXElement> {
+        self.find_elements(app_name, Some(&selector.role), selector.title.as_deref(), None)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No element matches '{}' in '{}'", selector, app_name))
+    }
+
+    /// Drag from the middle of `from_element` and drop on the middle of `to_element`
+    pub fn drag_element(&self, from_element: &AXElement, to_element: &AXElement) -> Result<()> {
+        let from = Self::element_center(from_element)?;
+        let to = Self::element_center(to_element)?;
+        self.drag(from, to)
+    }
+
+    /// Drag from the middle of `from_element` and drop at screen point
+    /// (`x`, `y`), measured from the top-left of the main display
+    pub fn drag_element_to(&self, from_element: &AXElement, x: i32, y: i32) -> Result<()> {
+        let from = Self::element_center(from_element)?;
+        self.drag(from, (x as f64, y as f64))
+    }
+
+    fn element_center(element: &AXElement) -> Result<(f64, f64)> {
+        element.center().ok_or_else(|| {
+            anyhow::anyhow!("The position of {} is not available", element.role)
+        })
+    }
+
+    /// Press the left button at `from`, move to `to` in small steps so the
+    /// target sees the drag, and release
+    #[cfg(target_os = "macos")]
+    fn drag(&self, from: (f64, f64), to: (f64, f64)) -> Result<()> {
+        use core_graphics::event::{CGEvent, CGEventTapLocation, CGEventType, CGMouseButton};
+        use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+        use core_graphics::geometry::CGPoint;
+
+        const STEPS: u32 = 10;
+
+        let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
+            .ok()
+            .context("Failed to create event source")?;
+        let post = |event_type: CGEventType, (x, y): (f64, f64)| -> Result<()> {
+            CGEvent::new_mouse_event(
+                source.clone(),
+                event_type,
+                CGPoint::new(x, y),
+                CGMouseButton::Left,
+            )
+            .ok()
+            .context("Failed to create mouse event")?
+            .post(CGEventTapLocation::HID);
+            Ok(())
+        };
+
+        post(CGEventType::MouseMoved, from)?;
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        post(CGEventType::LeftMouseDown, from)?;
+        std::thread::sleep(std::time::Duration::from_millis(150));
+
+        for step in 1..=STEPS {
+            let t = step as f64 / STEPS as f64;
+            let point = (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+            post(CGEventType::LeftMouseDragged, point)?;
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        post(CGEventType::LeftMouseUp, to)?;
+
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    fn drag(&self, _from: (f64, f64), _to: (f64, f64)) -> Result<()> {
+        anyhow::bail!("Not supported on this platform")
+    }
+
     /// Set the value of a UI element
     #[cfg(target_os = "macos")]
     pub fn set_value(
@@ -505,10 +737,6 @@ impl MacAxController {
     /// Type text into the currently focused element (uses system text input)
     #[cfg(target_os = "macos")]
     pub fn type_text(&self, app_name: &str, text: &str) -> Result<()> {
-        use cocoa::base::{id, nil};
-        use cocoa::foundation::NSString;
-        use objc::{class, msg_send, sel, sel_impl};
-
         // First, make sure the app is active
         self.activate_app(app_name)?;
 
@@ -520,6 +748,39 @@ impl MacAxController {
         let _ = self.press_key(app_name, "tab", vec![]);
         std::thread::sleep(std::time::Duration::from_millis(800));
 
+        self.paste_text(app_name, text)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn type_text(&self, _app_name: &str, _text: &str) -> Result<()> {
+        anyhow::bail!("Not supported on this platform")
+    }
+
+    /// Type text containing special key tokens such as `{Enter}` or `{Tab}`
+    /// (see [`KeySequenceParser`]): text runs are pasted and keys pressed in
+    /// order. Unlike `type_text`, no Tab is sent first, so the focused
+    /// element must already be the right one or be reached with `{Tab}`.
+    pub fn type_text_with_keys(&self, app_name: &str, text: &str) -> Result<()> {
+        self.activate_app(app_name)?;
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        for part in KeySequenceParser::parse(text) {
+            match part {
+                KeySequencePart::Text(text) => self.paste_text(app_name, &text)?,
+                KeySequencePart::Key(key) => self.press_key(app_name, key, vec![])?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Paste `text` into the focused element through the clipboard, restoring
+    /// the clipboard afterwards.
+    #[cfg(target_os = "macos")]
+    fn paste_text(&self, app_name: &str, text: &str) -> Result<()> {
+        use cocoa::base::{id, nil};
+        use cocoa::foundation::NSString;
+        use objc::{class, msg_send, sel, sel_impl};
+
         // Save old clipboard, set new content, paste, then restore
         let old_content: id;
         unsafe {
@@ -561,7 +822,7 @@ impl MacAxController {
     }
 
     #[cfg(not(target_os = "macos"))]
-    pub fn type_text(&self, _app_name: &str, _text: &str) -> Result<()> {
+    fn paste_text(&self, _app_name: &str, _text: &str) -> Result<()> {
         anyhow::bail!("Not supported on this platform")
     }
 
@@ -702,6 +963,7 @@ impl MacAxController {
             "`" => Some(0x32),
             "delete" | "backspace" => Some(0x33),
             "escape" | "esc" => Some(0x35),
+            "forwarddelete" => Some(0x75),
             "f1" => Some(0x7A),
             "f2" => Some(0x78),
             "f3" => Some(0x63),
@@ -793,8 +1055,8 @@ impl<'a> TreeVisitor for ElementCollector<'a> {
                 identifier,
                 enabled,
                 focused,
-                position: None,
-                size: None,
+                position: MacAxController::point_attribute(element, "AXPosition"),
+                size: MacAxController::size_attribute(element, "AXSize"),
                 children_count,
             });
         }
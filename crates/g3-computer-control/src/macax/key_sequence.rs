@@ -0,0 +1,175 @@
+//! Parsing of text with special keys embedded as `{Key}` tokens.
+//!
+//! `"Hello{Enter}{Tab}World"` types "Hello", presses Return and Tab, then
+//! types "World". `{{` types a literal `{`; tokens that are not known keys,
+//! such as `{Unknown}`, are typed as they are.
+
+/// A run of text to type, or a special key to press.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeySequencePart {
+    Text(String),
+    /// Key name as understood by `MacAxController::press_key`
+    Key(&'static str),
+}
+
+/// Special key tokens and the `press_key` names they map to.
+const KEY_TOKENS: &[(&str, &str)] = &[
+    ("Enter", "return"),
+    ("Tab", "tab"),
+    ("Escape", "escape"),
+    ("Delete", "forwarddelete"),
+    ("BackSpace", "backspace"),
+    ("Left", "left"),
+    ("Right", "right"),
+    ("Up", "up"),
+    ("Down", "down"),
+];
+
+pub struct KeySequenceParser;
+
+impl KeySequenceParser {
+    /// Split `input` into text runs and special keys. Adjacent text is
+    /// merged and empty runs are left out.
+    pub fn parse(input: &str) -> Vec<KeySequencePart> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut rest = input;
+
+        while let Some(pos) = rest.find('{') {
+            text.push_str(&rest[..pos]);
+            rest = &rest[pos..];
+
+            if let Some(after) = rest.strip_prefix("{{") {
+                text.push('{');
+                rest = after;
+                continue;
+            }
+
+            let key = rest
+                .find('}')
+                .and_then(|end| Self::key_name(&rest[1..end]).map(|key| (key, end)));
+            match key {
+                Some((key, end)) => {
+                    if !text.is_empty() {
+                        parts.push(KeySequencePart::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(KeySequencePart::Key(key));
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    // Not a key token: keep the brace as text
+                    text.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+
+        text.push_str(rest);
+        if !text.is_empty() {
+            parts.push(KeySequencePart::Text(text));
+        }
+        parts
+    }
+
+    /// `press_key` name for a token, matched case-insensitively.
+    fn key_name(token: &str) -> Option<&'static str> {
+        KEY_TOKENS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(token))
+            .map(|(_, key)| *key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use KeySequencePart::{Key, Text};
+
+    fn text(s: &str) -> KeySequencePart {
+        Text(s.to_string())
+    }
+
+    #[test]
+    fn test_text_and_keys_interleaved() {
+        assert_eq!(
+            KeySequenceParser::parse("Hello\n{Tab}World{Enter}"),
+            vec![text("Hello\n"), Key("tab"), text("World"), Key("return")]
+        );
+        assert_eq!(
+            KeySequenceParser::parse("a{Escape}b{Delete}c{BackSpace}d"),
+            vec![
+                text("a"),
+                Key("escape"),
+                text("b"),
+                Key("forwarddelete"),
+                text("c"),
+                Key("backspace"),
+                text("d"),
+            ]
+        );
+        assert_eq!(
+            KeySequenceParser::parse("{Left}{Right}{Up}{Down}"),
+            vec![Key("left"), Key("right"), Key("up"), Key("down")]
+        );
+    }
+
+    #[test]
+    fn test_leading_and_trailing_keys() {
+        assert_eq!(
+            KeySequenceParser::parse("{Tab}name{Enter}"),
+            vec![Key("tab"), text("name"), Key("return")]
+        );
+        assert_eq!(
+            KeySequenceParser::parse("{Enter}{Enter}"),
+            vec![Key("return"), Key("return")]
+        );
+    }
+
+    #[test]
+    fn test_plain_and_empty_input() {
+        assert_eq!(KeySequenceParser::parse(""), vec![]);
+        assert_eq!(
+            KeySequenceParser::parse("no keys here"),
+            vec![text("no keys here")]
+        );
+    }
+
+    #[test]
+    fn test_doubled_brace_is_literal() {
+        assert_eq!(
+            KeySequenceParser::parse("fn main() {{}"),
+            vec![text("fn main() {}")]
+        );
+        // `{{Tab}` is a literal `{` followed by "Tab}"
+        assert_eq!(KeySequenceParser::parse("{{Tab}"), vec![text("{Tab}")]);
+        assert_eq!(
+            KeySequenceParser::parse("{{{Tab}"),
+            vec![text("{"), Key("tab")]
+        );
+    }
+
+    #[test]
+    fn test_unknown_and_unclosed_tokens_are_literal() {
+        assert_eq!(
+            KeySequenceParser::parse("a{Unknown}b"),
+            vec![text("a{Unknown}b")]
+        );
+        assert_eq!(
+            KeySequenceParser::parse("{}{Tab"),
+            vec![text("{}{Tab")]
+        );
+        assert_eq!(
+            KeySequenceParser::parse("{Unknown}{Tab}"),
+            vec![text("{Unknown}"), Key("tab")]
+        );
+        assert_eq!(KeySequenceParser::parse("}"), vec![text("}")]);
+    }
+
+    #[test]
+    fn test_key_names_are_case_insensitive() {
+        assert_eq!(
+            KeySequenceParser::parse("{enter}{TAB}{backspace}"),
+            vec![Key("return"), Key("tab"), Key("backspace")]
+        );
+    }
+}
@@ -1,7 +1,14 @@
 pub mod controller;
+pub mod key_sequence;
+pub mod selector;
+pub mod wait;
 
 pub use controller::MacAxController;
+pub use key_sequence::{KeySequenceParser, KeySequencePart};
+pub use selector::ElementSelector;
+pub use wait::{wait_for_element, ElementLookup};
 
+use crate::types::Rect;
 use serde::{Deserialize, Serialize};
 
 #[cfg(test)]
@@ -22,6 +29,16 @@ pub struct AXElement {
     pub children_count: usize,
 }
 
+/// Represents a top-level window of an application
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowInfo {
+    /// Index of the window in the application's window list
+    pub id: usize,
+    pub title: String,
+    pub minimized: bool,
+    pub bounds: Rect,
+}
+
 /// Represents a macOS application
 #[derive(Debug, Clone)]
 pub struct AXApplication {
@@ -31,6 +48,13 @@ pub struct AXApplication {
 }
 
 impl AXElement {
+    /// Screen point at the middle of the element, when its frame is known
+    pub fn center(&self) -> Option<(f64, f64)> {
+        let (x, y) = self.position?;
+        let (width, height) = self.size?;
+        Some((x + width / 2.0, y + height / 2.0))
+    }
+
     /// Convert to a human-readable string representation
     pub fn to_string(&self) -> String {
         let mut parts = vec![format!("Role: {}", self.role)];
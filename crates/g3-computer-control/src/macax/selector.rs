@@ -0,0 +1,83 @@
+//! Element selectors written as `role:title`.
+//!
+//! `"AXButton:Save"` selects a button titled "Save" and `"AXList"` the first
+//! list. Role and title are matched as substrings, like `find_elements`, so
+//! `"Button:Save"` works too. Everything after the first `:` is the title.
+
+use anyhow::{bail, Result};
+
+/// An element picked by role and optional title.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElementSelector {
+    pub role: String,
+    pub title: Option<String>,
+}
+
+impl ElementSelector {
+    pub fn parse(selector: &str) -> Result<Self> {
+        let (role, title) = match selector.split_once(':') {
+            Some((role, title)) => (role.trim(), Some(title.trim())),
+            None => (selector.trim(), None),
+        };
+        if role.is_empty() {
+            bail!(
+                "Invalid element selector '{}': expected 'role' or 'role:title', e.g. 'AXButton:Save'",
+                selector
+            );
+        }
+        if role.chars().any(char::is_whitespace) {
+            bail!("Invalid element selector '{}': role '{}' contains spaces", selector, role);
+        }
+        Ok(Self {
+            role: role.to_string(),
+            title: title.filter(|t| !t.is_empty()).map(str::to_string),
+        })
+    }
+}
+
+impl std::fmt::Display for ElementSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.title {
+            Some(title) => write!(f, "{}:{}", self.role, title),
+            None => write!(f, "{}", self.role),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_and_title() {
+        let selector = ElementSelector::parse("AXButton:Save").unwrap();
+        assert_eq!(selector.role, "AXButton");
+        assert_eq!(selector.title.as_deref(), Some("Save"));
+        assert_eq!(selector.to_string(), "AXButton:Save");
+    }
+
+    #[test]
+    fn test_role_only_and_whitespace() {
+        let selector = ElementSelector::parse(" AXList ").unwrap();
+        assert_eq!(selector, ElementSelector { role: "AXList".to_string(), title: None });
+        assert_eq!(ElementSelector::parse("AXRow: ").unwrap().title, None);
+        assert_eq!(
+            ElementSelector::parse("AXRow : Project Notes ").unwrap().title.as_deref(),
+            Some("Project Notes")
+        );
+    }
+
+    #[test]
+    fn test_title_may_contain_colons() {
+        let selector = ElementSelector::parse("AXStaticText:Time: 10:30").unwrap();
+        assert_eq!(selector.role, "AXStaticText");
+        assert_eq!(selector.title.as_deref(), Some("Time: 10:30"));
+    }
+
+    #[test]
+    fn test_invalid_selectors() {
+        assert!(ElementSelector::parse("").is_err());
+        assert!(ElementSelector::parse(":Save").is_err());
+        assert!(ElementSelector::parse("AX Button:Save").is_err());
+    }
+}
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
-    use crate::{AXElement, MacAxController};
+    use crate::types::Rect;
+    use crate::{AXElement, MacAxController, WindowInfo};
 
     #[test]
     fn test_ax_element_to_string() {
@@ -27,6 +28,26 @@ mod tests {
         assert!(string_repr.contains("Size: (80, 30)"));
     }
 
+    #[test]
+    fn test_ax_element_center() {
+        let mut element = AXElement {
+            role: "AXRow".to_string(),
+            title: None,
+            value: None,
+            label: None,
+            identifier: None,
+            enabled: true,
+            focused: false,
+            position: Some((100.0, 200.0)),
+            size: Some((80.0, 30.0)),
+            children_count: 0,
+        };
+        assert_eq!(element.center(), Some((140.0, 215.0)));
+
+        element.size = None;
+        assert_eq!(element.center(), None);
+    }
+
     #[test]
     fn test_controller_creation() {
         // Just test that we can create a controller
@@ -34,4 +55,99 @@ mod tests {
         let result = MacAxController::new();
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_window_info_serialization() {
+        let window = WindowInfo {
+            id: 1,
+            title: "Untitled".to_string(),
+            minimized: false,
+            bounds: Rect {
+                x: 10,
+                y: 20,
+                width: 800,
+                height: 600,
+            },
+        };
+
+        let json = serde_json::to_value(&window).unwrap();
+        assert_eq!(json["id"], 1);
+        assert_eq!(json["title"], "Untitled");
+        assert_eq!(json["minimized"], false);
+        assert_eq!(json["bounds"]["width"], 800);
+    }
+
+    /// Finds a button once it has been looked up `appears_after` times.
+    struct MockLookup {
+        appears_after: usize,
+        lookups: std::cell::Cell<usize>,
+    }
+
+    impl crate::macax::ElementLookup for MockLookup {
+        fn find_element(
+            &self,
+            _app_name: &str,
+            role: &str,
+            title: Option<&str>,
+        ) -> anyhow::Result<Option<AXElement>> {
+            self.lookups.set(self.lookups.get() + 1);
+            if self.lookups.get() < self.appears_after {
+                return Ok(None);
+            }
+            Ok(Some(AXElement {
+                role: role.to_string(),
+                title: title.map(str::to_string),
+                value: None,
+                label: None,
+                identifier: None,
+                enabled: true,
+                focused: false,
+                position: None,
+                size: None,
+                children_count: 0,
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_element_polls_until_found() {
+        use crate::macax::wait_for_element;
+        use std::time::Duration;
+
+        let lookup = MockLookup { appears_after: 3, lookups: Default::default() };
+        let (element, waited) = wait_for_element(
+            &lookup,
+            "Mail",
+            "AXButton",
+            Some("Send"),
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(lookup.lookups.get(), 3);
+        assert_eq!(element.title.as_deref(), Some("Send"));
+        assert!(waited >= Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_element_times_out() {
+        use crate::macax::wait_for_element;
+        use std::time::Duration;
+
+        let lookup = MockLookup { appears_after: usize::MAX, lookups: Default::default() };
+        let found = wait_for_element(
+            &lookup,
+            "Mail",
+            "AXButton",
+            None,
+            Duration::from_millis(50),
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap();
+        assert!(found.is_none());
+        assert!(lookup.lookups.get() >= 5);
+    }
 }
@@ -0,0 +1,63 @@
+//! Waiting for accessibility elements that appear asynchronously.
+//!
+//! Apps often add elements after data loads, like a page finishing in the
+//! browser. [`wait_for_element`] polls until an element shows up, the way
+//! `web_wait_for_element` polls the DOM.
+
+use anyhow::Result;
+use std::time::{Duration, Instant};
+
+use super::{AXElement, MacAxController};
+
+/// Default time `wait_for_element` waits for the element.
+pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default time between two lookups of `wait_for_element`.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Looks up one element of an app.
+pub trait ElementLookup {
+    /// The first element of `app_name` whose role contains `role` and, if
+    /// given, whose title contains `title`. `None` when there is none yet.
+    fn find_element(&self, app_name: &str, role: &str, title: Option<&str>)
+        -> Result<Option<AXElement>>;
+}
+
+impl ElementLookup for MacAxController {
+    fn find_element(
+        &self,
+        app_name: &str,
+        role: &str,
+        title: Option<&str>,
+    ) -> Result<Option<AXElement>> {
+        Ok(self
+            .find_elements(app_name, Some(role), title, None)?
+            .into_iter()
+            .next())
+    }
+}
+
+/// Look for the element every `poll_interval` until it is found, returning
+/// it with the time waited, or `None` once `timeout` has passed. Lookup
+/// errors, such as the app not running or accessibility being unsupported,
+/// end the wait.
+pub async fn wait_for_element<L: ElementLookup + ?Sized>(
+    lookup: &L,
+    app_name: &str,
+    role: &str,
+    title: Option<&str>,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> Result<Option<(AXElement, Duration)>> {
+    let start = Instant::now();
+    let mut interval = tokio::time::interval(poll_interval.max(Duration::from_millis(1)));
+    loop {
+        interval.tick().await;
+        if let Some(element) = lookup.find_element(app_name, role, title)? {
+            return Ok(Some((element, start.elapsed())));
+        }
+        if start.elapsed() >= timeout {
+            return Ok(None);
+        }
+    }
+}
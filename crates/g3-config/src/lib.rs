@@ -1,7 +1,7 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,7 +10,13 @@ pub struct Config {
     pub agent: AgentConfig,
     pub computer_control: ComputerControlConfig,
     pub webdriver: WebDriverConfig,
-
+    #[serde(default)]
+    pub code_search: CodeSearchConfig,
+    #[serde(default)]
+    pub ssh: SshConfig,
+    /// Encrypt session continuation files at rest; off when absent
+    #[serde(default)]
+    pub encryption: Option<EncryptionConfig>,
 }
 
 /// Provider configuration with named configs per provider type
@@ -27,6 +33,19 @@ pub struct ProvidersConfig {
     
     /// Provider for player in autonomous mode (optional, falls back to default_provider)
     pub player: Option<String>,
+
+    /// Provider to retry on when the default provider is rate limited, overloaded
+    /// or rejects a prompt as too long, in format "<provider_type>.<config_name>"
+    #[serde(default)]
+    pub fallback_provider: Option<String>,
+
+    /// Model to use with the fallback provider instead of the one in its config
+    #[serde(default)]
+    pub fallback_model: Option<String>,
+
+    /// Milliseconds to wait before retrying on the fallback provider
+    #[serde(default = "default_fallback_delay_ms")]
+    pub fallback_delay_ms: u64,
     
     /// Named Anthropic provider configs
     #[serde(default)]
@@ -47,15 +66,39 @@ pub struct ProvidersConfig {
     /// Multiple named OpenAI-compatible providers (e.g., openrouter, groq, etc.)
     #[serde(default)]
     pub openai_compatible: HashMap<String, OpenAIConfig>,
+
+    /// Named Ollama provider configs
+    #[serde(default)]
+    pub ollama: HashMap<String, OllamaConfig>,
+
+    /// Named Google Gemini provider configs
+    #[serde(default)]
+    pub gemini: HashMap<String, GeminiConfig>,
+}
+
+fn default_fallback_delay_ms() -> u64 {
+    1000
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenAIConfig {
+    /// May be empty for servers that need no authentication.
+    #[serde(default)]
     pub api_key: String,
     pub model: String,
+    /// API root without a trailing `/`, e.g. `http://localhost:8000/v1`.
     pub base_url: Option<String>,
     pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
+    /// Extra headers sent with every request (openai_compatible only).
+    #[serde(default)]
+    pub extra_headers: HashMap<String, String>,
+    /// Endpoint path overriding `/chat/completions` (openai_compatible only).
+    #[serde(default)]
+    pub chat_path: Option<String>,
+    /// Prompt caching TTL such as `"15m"`, sent in seconds with cached messages.
+    #[serde(default)]
+    pub cache_config: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +109,10 @@ pub struct AnthropicConfig {
     pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
     pub cache_config: Option<String>,
+    /// Minimum estimated size, in tokens, of the system prompt or tool
+    /// definitions before they are automatically marked for caching
+    #[serde(default)]
+    pub prompt_cache_min_tokens: Option<usize>,
     pub enable_1m_context: Option<bool>,
     pub thinking_budget_tokens: Option<u32>,
     #[serde(default)]
@@ -82,6 +129,44 @@ pub struct DatabricksConfig {
     pub use_oauth: Option<bool>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaConfig {
+    pub model: String,
+    /// Ollama server URL (default: http://localhost:11434)
+    pub base_url: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    /// Extra model options passed through as-is (num_ctx, top_p, stop, ...)
+    #[serde(default)]
+    pub options: toml::Table,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiConfig {
+    /// Google AI API key; read from `GEMINI_API_KEY` when empty.
+    #[serde(default)]
+    pub api_key: String,
+    pub model: String,
+    /// API root (default: https://generativelanguage.googleapis.com/v1beta)
+    pub base_url: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    /// Safety filter thresholds sent as `safetySettings`
+    #[serde(default)]
+    pub safety_settings: Vec<GeminiSafetySetting>,
+    /// Extra `generationConfig` fields passed through as-is (topP, topK, stopSequences, ...)
+    #[serde(default)]
+    pub generation_config: toml::Table,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiSafetySetting {
+    /// e.g. `HARM_CATEGORY_DANGEROUS_CONTENT`
+    pub category: String,
+    /// e.g. `BLOCK_ONLY_HIGH` or `BLOCK_NONE`
+    pub threshold: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddedConfig {
     pub model_path: String,
@@ -105,12 +190,71 @@ pub struct AgentConfig {
     pub autonomous_max_retry_attempts: u32,
     #[serde(default = "default_check_todo_staleness")]
     pub check_todo_staleness: bool,
+    /// Default timeout for a single tool call; tools may override it with `timeout_secs`.
+    #[serde(default)]
+    pub tool_timeout_seconds: Option<u64>,
+    /// Redact secrets from tool output before it reaches the LLM. File
+    /// contents, git output and download results are passed through as is.
+    #[serde(default = "default_redact_secrets")]
+    pub redact_secrets: bool,
+    /// Extra regex patterns whose matches are redacted from tool output.
+    #[serde(default)]
+    pub secret_patterns: Vec<String>,
+    /// How long read-only tool results (read_file, code_search, ...) are cached; 0 disables caching.
+    #[serde(default = "default_tool_cache_ttl_seconds")]
+    pub tool_cache_ttl_seconds: u64,
+    /// Report tool calls instead of executing them (final_output still runs).
+    #[serde(default)]
+    pub dry_run: bool,
+    /// JSONL file tool timing telemetry is appended to (default: .g3/session/telemetry.jsonl)
+    #[serde(default)]
+    pub telemetry_file: Option<String>,
+    /// JSONL file every tool call is recorded in for auditing (default: .g3/audit.jsonl)
+    #[serde(default)]
+    pub audit_log_file: Option<String>,
+    /// Default for read_file's `annotate_lines` argument
+    #[serde(default)]
+    pub read_file_annotate_lines: bool,
+    /// read_file truncates files larger than this unless called with `force`
+    #[serde(default = "default_max_file_read_bytes")]
+    pub max_file_read_bytes: usize,
+    /// Output lines kept in memory per background process; older lines are dropped
+    #[serde(default = "default_background_output_buffer_lines")]
+    pub background_output_buffer_lines: usize,
+    /// Re-request a response without streaming when the stream ends mid-tool-call
+    #[serde(default)]
+    pub retry_on_truncation: bool,
+    /// Only these tools may run (`final_output` always may)
+    #[serde(default)]
+    pub tool_allowlist: Option<Vec<String>>,
+    /// These tools may never run; takes precedence over the allowlist
+    #[serde(default)]
+    pub tool_blocklist: Option<Vec<String>>,
+    /// Run tool calls without checking their arguments against the tool's input schema
+    #[serde(default)]
+    pub skip_validation: bool,
 }
 
 fn default_check_todo_staleness() -> bool {
     true
 }
 
+fn default_redact_secrets() -> bool {
+    true
+}
+
+fn default_tool_cache_ttl_seconds() -> u64 {
+    60
+}
+
+fn default_background_output_buffer_lines() -> usize {
+    1000
+}
+
+fn default_max_file_read_bytes() -> usize {
+    1024 * 1024
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComputerControlConfig {
     pub enabled: bool,
@@ -126,6 +270,8 @@ pub enum WebDriverBrowser {
     Safari,
     #[serde(rename = "chrome-headless")]
     ChromeHeadless,
+    #[serde(rename = "firefox-headless")]
+    FirefoxHeadless,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -140,6 +286,90 @@ pub struct WebDriverConfig {
     pub chrome_binary: Option<String>,
     #[serde(default)]
     pub browser: WebDriverBrowser,
+    #[serde(default = "default_firefox_port")]
+    pub firefox_port: u16,
+    #[serde(default)]
+    /// Optional path to Firefox binary
+    /// If not set, GeckoDriver will use the default Firefox installation
+    pub firefox_binary: Option<String>,
+}
+
+/// GeckoDriver's own default is 4444, which Safari's driver also uses, so
+/// Firefox gets the next port to let both be configured side by side.
+fn default_firefox_port() -> u16 {
+    4445
+}
+
+/// Weights used to rank code_search results. Each component scores between
+/// 0 and 1 and is multiplied by its weight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CodeSearchConfig {
+    /// Match text equal to the search's `symbol`
+    pub name_match_weight: f32,
+    /// Recently modified files
+    pub recency_weight: f32,
+    /// Files close to ones recently read or edited in the session
+    pub proximity_weight: f32,
+    /// OpenAI-compatible embeddings URL for semantic search; without it a
+    /// built-in word-hashing embedding is used
+    pub embedding_endpoint: Option<String>,
+    /// Model to request from `embedding_endpoint`
+    pub embedding_model: Option<String>,
+    /// Environment variable holding the API key for `embedding_endpoint`
+    pub embedding_api_key_env: Option<String>,
+}
+
+impl Default for CodeSearchConfig {
+    fn default() -> Self {
+        Self {
+            name_match_weight: 3.0,
+            recency_weight: 1.0,
+            proximity_weight: 2.0,
+            embedding_endpoint: None,
+            embedding_model: None,
+            embedding_api_key_env: None,
+        }
+    }
+}
+
+/// Connection settings for tools that reach remote hosts over SSH
+/// (read_file's `ssh_host`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SshConfig {
+    /// User for hosts given without a `user@` prefix; defaults to $USER
+    pub user: Option<String>,
+    /// Private key to authenticate with; defaults to ~/.ssh/id_rsa
+    pub key_path: Option<String>,
+    /// Port for hosts given without a `:port` suffix
+    pub port: u16,
+    pub connect_timeout_seconds: u64,
+}
+
+/// Encryption of session continuation files (`latest.json`) at rest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    /// Cipher to use; only "aes-256-gcm" is supported
+    #[serde(default = "default_encryption_algorithm")]
+    pub algorithm: String,
+    /// Environment variable holding the passphrase the key is derived from
+    pub passphrase_env: String,
+}
+
+fn default_encryption_algorithm() -> String {
+    "aes-256-gcm".to_string()
+}
+
+impl Default for SshConfig {
+    fn default() -> Self {
+        Self {
+            user: None,
+            key_path: None,
+            port: 22,
+            connect_timeout_seconds: 10,
+        }
+    }
 }
 
 
@@ -152,6 +382,8 @@ impl Default for WebDriverConfig {
             chrome_port: 9515,
             chrome_binary: None,
             browser: WebDriverBrowser::Safari,
+            firefox_port: default_firefox_port(),
+            firefox_binary: None,
         }
     }
 }
@@ -187,11 +419,16 @@ impl Default for Config {
                 planner: None,
                 coach: None,
                 player: None,
+                fallback_provider: None,
+                fallback_model: None,
+                fallback_delay_ms: default_fallback_delay_ms(),
                 anthropic: HashMap::new(),
                 openai: HashMap::new(),
                 databricks: databricks_configs,
                 embedded: HashMap::new(),
                 openai_compatible: HashMap::new(),
+                ollama: HashMap::new(),
+                gemini: HashMap::new(),
             },
             agent: AgentConfig {
                 max_context_length: None,
@@ -203,10 +440,26 @@ impl Default for Config {
                 max_retry_attempts: 3,
                 autonomous_max_retry_attempts: 6,
                 check_todo_staleness: true,
+                tool_timeout_seconds: None,
+                redact_secrets: true,
+                secret_patterns: Vec::new(),
+                tool_cache_ttl_seconds: default_tool_cache_ttl_seconds(),
+                dry_run: false,
+                telemetry_file: None,
+                audit_log_file: None,
+                read_file_annotate_lines: false,
+                max_file_read_bytes: default_max_file_read_bytes(),
+                background_output_buffer_lines: default_background_output_buffer_lines(),
+                retry_on_truncation: false,
+                tool_allowlist: None,
+                tool_blocklist: None,
+                skip_validation: false,
             },
             computer_control: ComputerControlConfig::default(),
             webdriver: WebDriverConfig::default(),
-
+            code_search: CodeSearchConfig::default(),
+            ssh: SshConfig::default(),
+            encryption: None,
         }
     }
 }
@@ -317,6 +570,73 @@ impl Config {
         Ok(Self::default())
     }
 
+    /// Load the global config merged with any project configs.
+    ///
+    /// Project configs are `.g3/config.toml` files in the current directory
+    /// and its parents. See [`Config::load_merged_from`] for how they merge.
+    pub fn load_merged() -> Result<Self> {
+        Self::load_merged_from(None, &std::env::current_dir()?)
+    }
+
+    /// Load the global config (as [`Config::load`] does), then merge every
+    /// `.g3/config.toml` found in `start_dir` and its parents over it, from
+    /// the outermost directory inwards, so the nearest project config wins.
+    ///
+    /// Merging is per key: tables (`[agent]`, `[webdriver]`,
+    /// `[providers.anthropic.default]`, ...) are merged recursively, so a
+    /// project config only needs the settings it changes. Everything else,
+    /// including arrays, replaces the global value as a whole.
+    ///
+    /// A config file given explicitly as `config_path` is used as is, without
+    /// project configs.
+    pub fn load_merged_from(config_path: Option<&str>, start_dir: &Path) -> Result<Self> {
+        let global = Self::load(config_path)?;
+        if config_path.is_some() {
+            return Ok(global);
+        }
+        Self::merge_project_configs(global, start_dir)
+    }
+
+    /// Merge the project configs of `start_dir` over `global`.
+    ///
+    /// A project config that sets an endpoint (`base_url`, `host`,
+    /// `embedding_endpoint`) must also set the credential sent to it, so a
+    /// repository cannot point the user's API key at a host of its choosing.
+    fn merge_project_configs(global: Self, start_dir: &Path) -> Result<Self> {
+        let project_configs = Self::project_config_paths(start_dir);
+        if project_configs.is_empty() {
+            return Ok(global);
+        }
+
+        let mut merged = toml::Value::try_from(&global)?;
+        for path in project_configs {
+            let content = std::fs::read_to_string(&path)?;
+            if Self::is_old_format(&content) {
+                anyhow::bail!("{}: {}", path.display(), OLD_CONFIG_FORMAT_ERROR);
+            }
+            let project: toml::Value = toml::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("Invalid config {}: {}", path.display(), e))?;
+            check_endpoint_credentials(&merged, &project)
+                .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+            merge_toml(&mut merged, project);
+        }
+
+        let config: Config = merged.try_into()?;
+        config.validate_provider_reference(&config.providers.default_provider)?;
+        Ok(config)
+    }
+
+    /// `.g3/config.toml` files in `start_dir` and its parents, outermost first.
+    fn project_config_paths(start_dir: &Path) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = start_dir
+            .ancestors()
+            .map(|dir| dir.join(".g3").join("config.toml"))
+            .filter(|path| path.is_file())
+            .collect();
+        paths.reverse();
+        paths
+    }
+
     /// Check if the config content uses the old format
     fn is_old_format(content: &str) -> bool {
         // Old format has [providers.anthropic] with api_key directly
@@ -408,11 +728,29 @@ impl Config {
                     );
                 }
             }
+            "ollama" => {
+                if !self.providers.ollama.contains_key(config_name) {
+                    anyhow::bail!(
+                        "Provider config 'ollama.{}' not found. Available: {:?}",
+                        config_name,
+                        self.providers.ollama.keys().collect::<Vec<_>>()
+                    );
+                }
+            }
+            "gemini" => {
+                if !self.providers.gemini.contains_key(config_name) {
+                    anyhow::bail!(
+                        "Provider config 'gemini.{}' not found. Available: {:?}",
+                        config_name,
+                        self.providers.gemini.keys().collect::<Vec<_>>()
+                    );
+                }
+            }
             _ => {
                 // Check openai_compatible providers
                 if !self.providers.openai_compatible.contains_key(provider_type) {
                     anyhow::bail!(
-                        "Unknown provider type '{}'. Valid types: anthropic, openai, databricks, embedded, or openai_compatible names",
+                        "Unknown provider type '{}'. Valid types: anthropic, openai, databricks, embedded, ollama, gemini, or openai_compatible names",
                         provider_type
                     );
                 }
@@ -445,7 +783,7 @@ impl Config {
         provider_override: Option<String>,
         model_override: Option<String>,
     ) -> Result<Self> {
-        let mut config = Self::load(config_path)?;
+        let mut config = Self::load_merged_from(config_path, &std::env::current_dir()?)?;
 
         // Apply provider override
         if let Some(provider) = provider_override {
@@ -501,6 +839,26 @@ impl Config {
                         ));
                     }
                 }
+                "ollama" => {
+                    if let Some(ref mut ollama_config) = config.providers.ollama.get_mut(&config_name) {
+                        ollama_config.model = model;
+                    } else {
+                        return Err(anyhow::anyhow!(
+                            "Provider config 'ollama.{}' not found.",
+                            config_name
+                        ));
+                    }
+                }
+                "gemini" => {
+                    if let Some(ref mut gemini_config) = config.providers.gemini.get_mut(&config_name) {
+                        gemini_config.model = model;
+                    } else {
+                        return Err(anyhow::anyhow!(
+                            "Provider config 'gemini.{}' not found.",
+                            config_name
+                        ));
+                    }
+                }
                 _ => {
                     // Check openai_compatible
                     if let Some(ref mut compat_config) = config.providers.openai_compatible.get_mut(&provider_type) {
@@ -587,6 +945,16 @@ impl Config {
         self.providers.embedded.get(name)
     }
 
+    /// Get Ollama config by name
+    pub fn get_ollama_config(&self, name: &str) -> Option<&OllamaConfig> {
+        self.providers.ollama.get(name)
+    }
+
+    /// Get Gemini config by name
+    pub fn get_gemini_config(&self, name: &str) -> Option<&GeminiConfig> {
+        self.providers.gemini.get(name)
+    }
+
 
 
     /// Get the current default provider's config
@@ -616,6 +984,16 @@ impl Config {
                     .map(ProviderConfigRef::Embedded)
                     .ok_or_else(|| anyhow::anyhow!("Embedded config '{}' not found", config_name))
             }
+            "ollama" => {
+                self.providers.ollama.get(&config_name)
+                    .map(ProviderConfigRef::Ollama)
+                    .ok_or_else(|| anyhow::anyhow!("Ollama config '{}' not found", config_name))
+            }
+            "gemini" => {
+                self.providers.gemini.get(&config_name)
+                    .map(ProviderConfigRef::Gemini)
+                    .ok_or_else(|| anyhow::anyhow!("Gemini config '{}' not found", config_name))
+            }
             _ => {
                 self.providers.openai_compatible.get(&provider_type)
                     .map(ProviderConfigRef::OpenAICompatible)
@@ -632,8 +1010,77 @@ pub enum ProviderConfigRef<'a> {
     OpenAI(&'a OpenAIConfig),
     Databricks(&'a DatabricksConfig),
     Embedded(&'a EmbeddedConfig),
+    Ollama(&'a OllamaConfig),
+    Gemini(&'a GeminiConfig),
     OpenAICompatible(&'a OpenAIConfig),
 }
 
+/// Endpoint settings and the credential sent to each.
+const ENDPOINT_CREDENTIALS: &[(&str, &str)] = &[
+    ("base_url", "api_key"),
+    ("host", "token"),
+    ("embedding_endpoint", "embedding_api_key_env"),
+];
+
+/// Fail if `project` points an endpoint somewhere new while the credential
+/// for it would still come from `merged`, the configs loaded before it.
+fn check_endpoint_credentials(merged: &toml::Value, project: &toml::Value) -> Result<()> {
+    let mut tables: Vec<(String, &toml::Value)> = Vec::new();
+    if let Some(code_search) = project.get("code_search") {
+        tables.push(("code_search".to_string(), code_search));
+    }
+    if let Some(providers) = project.get("providers").and_then(|p| p.as_table()) {
+        for (provider_type, configs) in providers {
+            for (name, config) in configs.as_table().into_iter().flatten() {
+                tables.push((format!("providers.{}.{}", provider_type, name), config));
+            }
+        }
+    }
+
+    for (table_path, table) in tables {
+        let existing = table_path
+            .split('.')
+            .try_fold(merged, |value, key| value.get(key));
+        for (endpoint, credential) in ENDPOINT_CREDENTIALS {
+            let inherits_credential = existing
+                .and_then(|t| t.get(credential))
+                .and_then(|v| v.as_str())
+                .is_some_and(|v| !v.is_empty());
+            if table.get(endpoint).is_some()
+                && table.get(credential).is_none()
+                && inherits_credential
+            {
+                anyhow::bail!(
+                    "[{}] sets {} but not {}; a project config that changes {} must set {} as well",
+                    table_path,
+                    endpoint,
+                    credential,
+                    endpoint,
+                    credential
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Merge `overlay` into `base`: tables merge key by key, anything else in
+/// `overlay` replaces the value in `base`.
+fn merge_toml(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
 #[cfg(test)]
 mod tests;
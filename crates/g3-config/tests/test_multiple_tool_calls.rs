@@ -27,6 +27,20 @@ mod test_multiple_tool_calls {
             max_retry_attempts: 3,
             autonomous_max_retry_attempts: 6,
             check_todo_staleness: true,
+            tool_timeout_seconds: None,
+            redact_secrets: true,
+            secret_patterns: Vec::new(),
+            tool_cache_ttl_seconds: 60,
+            dry_run: false,
+            telemetry_file: None,
+            audit_log_file: None,
+            read_file_annotate_lines: false,
+            max_file_read_bytes: 1048576,
+            background_output_buffer_lines: 1000,
+            retry_on_truncation: false,
+            tool_allowlist: None,
+            tool_blocklist: None,
+            skip_validation: false,
         };
 
         // Test serialization
@@ -1,25 +1,44 @@
 //! Background process management for long-running tasks like game servers.
 //!
 //! This module provides a way to launch processes in the background with:
-//! - Automatic log capture to files (stdout/stderr combined)
+//! - Automatic log capture to files (one for stdout, one for stderr)
+//! - The most recent output lines kept in memory in a bounded [`RingBuffer`],
+//!   read back from the log files and tagged with their stream
 //! - Named process tracking for easy reference
 //! - Process lifecycle management (start, stop via shell)
 //!
-//! The design is intentionally minimal - only one tool (`background_process`) is exposed.
-//! Users can use the regular `shell` tool to:
-//! - Read logs: `cat /path/to/logs.txt` or `tail -100 /path/to/logs.txt`
-//! - Stop processes: `kill <pid>` or `pkill -f <name>`
-//! - Check status: `ps aux | grep <name>`
-
-use std::collections::HashMap;
-use std::fs::{self, OpenOptions};
-use std::io::Write;
+//! Processes are started with `background_process`, listed with
+//! `list_background_processes`, stopped with `stop_background_process`, and
+//! their new output is read with `get_background_process_output`.
+//! `process_output_wait` blocks until a line matching a pattern appears. The
+//! regular `shell` tool still works on the log files and PIDs directly.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use regex::Regex;
 use tracing::debug;
 
+/// Output lines kept in memory per process unless configured otherwise.
+pub const DEFAULT_OUTPUT_BUFFER_LINES: usize = 1000;
+
+/// Lines returned by one `read_new_output` call unless the caller asks for
+/// a different number.
+pub const DEFAULT_OUTPUT_LINES: usize = 50;
+
+/// How long `stop` waits for a process to exit after SIGTERM.
+const STOP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Bytes read from a log file at a time while tailing it.
+const TAIL_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Output without a newline is cut into lines of at most this many bytes.
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
 /// Information about a running background process
 #[derive(Debug, Clone)]
 pub struct ProcessInfo {
@@ -29,14 +48,272 @@ pub struct ProcessInfo {
     pub command: String,
     /// Process ID
     pub pid: u32,
-    /// Path to the log file (combined stdout/stderr)
+    /// Path to the log file (stdout, after a header)
     pub log_file: PathBuf,
+    /// Path to the log file for stderr
+    pub stderr_log_file: PathBuf,
     /// Timestamp when the process was started
     pub started_at: u64,
     /// Working directory where the process was started
     pub working_dir: PathBuf,
 }
 
+/// Fixed-capacity buffer that drops its oldest item once full.
+#[derive(Debug, Clone)]
+pub struct RingBuffer<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+    /// Items ever pushed, including dropped ones
+    total: u64,
+}
+
+/// How much a [`RingBuffer`] has seen and lost.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BufferStats {
+    pub total_lines: u64,
+    pub dropped_lines: u64,
+}
+
+impl<T> RingBuffer<T> {
+    /// A buffer holding at most `capacity` items (at least one).
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            items: VecDeque::with_capacity(capacity.min(DEFAULT_OUTPUT_BUFFER_LINES)),
+            capacity,
+            total: 0,
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+        }
+        self.items.push_back(item);
+        self.total += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of items ever pushed.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn stats(&self) -> BufferStats {
+        BufferStats {
+            total_lines: self.total,
+            dropped_lines: self.total - self.items.len() as u64,
+        }
+    }
+
+    /// The last `n` items, oldest first.
+    pub fn last(&self, n: usize) -> impl Iterator<Item = &T> {
+        self.items.iter().skip(self.items.len().saturating_sub(n))
+    }
+
+    /// Items pushed after the first `seen` that are still buffered, oldest first.
+    pub fn since(&self, seen: u64) -> impl Iterator<Item = &T> {
+        let new = self.total.saturating_sub(seen).min(self.items.len() as u64);
+        self.last(new as usize)
+    }
+}
+
+/// The stream a line of process output was written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Which streams to search when waiting for output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFilter {
+    Stdout,
+    Stderr,
+    Both,
+}
+
+impl StreamFilter {
+    /// Parse `stdout`, `stderr` or `both`, case-insensitively.
+    pub fn parse(stream: &str) -> Option<Self> {
+        match stream.trim().to_ascii_lowercase().as_str() {
+            "stdout" => Some(Self::Stdout),
+            "stderr" => Some(Self::Stderr),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+
+    pub fn matches(self, stream: OutputStream) -> bool {
+        match self {
+            StreamFilter::Stdout => stream == OutputStream::Stdout,
+            StreamFilter::Stderr => stream == OutputStream::Stderr,
+            StreamFilter::Both => true,
+        }
+    }
+}
+
+/// One line of process output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputLine {
+    pub stream: OutputStream,
+    pub text: String,
+}
+
+/// Follows one log file, splitting what is appended to it into lines.
+#[derive(Debug)]
+struct LogTail {
+    path: PathBuf,
+    /// Bytes of the log already read
+    offset: u64,
+    /// Start of a line whose newline has not been written yet
+    partial: Vec<u8>,
+}
+
+impl LogTail {
+    fn new(path: PathBuf, offset: u64) -> Self {
+        Self {
+            path,
+            offset,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Lines appended to the log since the last call. Once the process has
+    /// `exited`, a last line without a newline is returned too.
+    fn read_lines(&mut self, exited: bool) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Ok(mut file) = File::open(&self.path) {
+            if file.seek(SeekFrom::Start(self.offset)).is_ok() {
+                let mut chunk = vec![0; TAIL_CHUNK_BYTES];
+                loop {
+                    match file.read(&mut chunk) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => {
+                            self.offset += n as u64;
+                            self.push_bytes(&chunk[..n], &mut lines);
+                        }
+                    }
+                }
+            }
+        }
+        if exited && !self.partial.is_empty() {
+            lines.push(self.take_partial());
+        }
+        lines
+    }
+
+    fn push_bytes(&mut self, mut bytes: &[u8], lines: &mut Vec<String>) {
+        while let Some(newline) = bytes.iter().position(|b| *b == b'\n') {
+            self.partial.extend_from_slice(&bytes[..newline]);
+            lines.push(self.take_partial());
+            bytes = &bytes[newline + 1..];
+        }
+        self.partial.extend_from_slice(bytes);
+        if self.partial.len() >= MAX_LINE_BYTES {
+            lines.push(self.take_partial());
+        }
+    }
+
+    fn take_partial(&mut self) -> String {
+        let line = std::mem::take(&mut self.partial);
+        let text = String::from_utf8_lossy(&line);
+        text.trim_end_matches('\r').to_string()
+    }
+}
+
+/// The most recent output lines of a process, read from its log files.
+///
+/// The process writes straight to the files, so it keeps running and logging
+/// after g3 exits. Lines are read in when output is asked for; of the lines
+/// read at the same time, stdout ones come before stderr ones.
+#[derive(Debug)]
+struct ProcessLogs {
+    stdout: LogTail,
+    stderr: LogTail,
+    lines: RingBuffer<OutputLine>,
+}
+
+impl ProcessLogs {
+    fn refresh(&mut self, exited: bool) {
+        for (stream, tail) in [
+            (OutputStream::Stdout, &mut self.stdout),
+            (OutputStream::Stderr, &mut self.stderr),
+        ] {
+            for text in tail.read_lines(exited) {
+                self.lines.push(OutputLine { stream, text });
+            }
+        }
+    }
+}
+
+/// Output lines returned by [`BackgroundProcessManager::read_new_output`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessOutput {
+    pub lines: Vec<String>,
+    /// New lines that are not in `lines`, because they were dropped from the
+    /// buffer or were beyond the requested number of lines
+    pub skipped_lines: u64,
+    pub stats: BufferStats,
+}
+
+/// Whether a tracked process is still alive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessStatus {
+    Running,
+    /// Exited with this code, or `None` if it was killed by a signal
+    Exited(Option<i32>),
+}
+
+impl std::fmt::Display for ProcessStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessStatus::Running => write!(f, "running"),
+            ProcessStatus::Exited(Some(code)) => write!(f, "exited ({})", code),
+            ProcessStatus::Exited(None) => write!(f, "killed"),
+        }
+    }
+}
+
+/// Signal used to stop a background process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopSignal {
+    Term,
+    Kill,
+}
+
+impl StopSignal {
+    /// Parse `SIGTERM`/`TERM` or `SIGKILL`/`KILL`, case-insensitively.
+    pub fn parse(signal: &str) -> Option<Self> {
+        let signal = signal.trim().to_ascii_uppercase();
+        match signal.strip_prefix("SIG").unwrap_or(&signal) {
+            "TERM" => Some(Self::Term),
+            "KILL" => Some(Self::Kill),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for StopSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopSignal::Term => write!(f, "SIGTERM"),
+            StopSignal::Kill => write!(f, "SIGKILL"),
+        }
+    }
+}
+
 /// Manages background processes launched by the agent
 #[derive(Debug)]
 pub struct BackgroundProcessManager {
@@ -44,6 +321,12 @@ pub struct BackgroundProcessManager {
     processes: Arc<Mutex<HashMap<String, ProcessInfo>>>,
     /// Map of process name -> child handle (for cleanup)
     children: Arc<Mutex<HashMap<String, Child>>>,
+    /// Map of process name -> recent output lines, read from its log files
+    outputs: Arc<Mutex<HashMap<String, Arc<Mutex<ProcessLogs>>>>>,
+    /// Map of process name -> number of lines already returned by `read_new_output`
+    read_positions: Arc<Mutex<HashMap<String, u64>>>,
+    /// Output lines kept in memory per process
+    buffer_capacity: usize,
     /// Directory where log files are stored
     log_dir: PathBuf,
 }
@@ -59,10 +342,19 @@ impl BackgroundProcessManager {
         Self {
             processes: Arc::new(Mutex::new(HashMap::new())),
             children: Arc::new(Mutex::new(HashMap::new())),
+            outputs: Arc::new(Mutex::new(HashMap::new())),
+            read_positions: Arc::new(Mutex::new(HashMap::new())),
+            buffer_capacity: DEFAULT_OUTPUT_BUFFER_LINES,
             log_dir,
         }
     }
 
+    /// Keep at most `lines` output lines in memory per process.
+    pub fn with_buffer_capacity(mut self, lines: usize) -> Self {
+        self.buffer_capacity = lines;
+        self
+    }
+
     /// Start a new background process
     ///
     /// # Arguments
@@ -96,9 +388,10 @@ impl BackgroundProcessManager {
             .as_secs();
         let log_filename = format!("{}_{}.log", name, timestamp);
         let log_file = self.log_dir.join(&log_filename);
+        let stderr_log_file = self.log_dir.join(format!("{}_{}.stderr.log", name, timestamp));
 
-        // Open log file for writing
-        let log_handle = OpenOptions::new()
+        // Open log file for writing the header
+        let mut log_handle = OpenOptions::new()
             .create(true)
             .write(true)
             .truncate(true)
@@ -107,7 +400,7 @@ impl BackgroundProcessManager {
 
         // Write header to log file
         {
-            let mut file = &log_handle;
+            let file = &mut log_handle;
             writeln!(file, "=== Background Process Log ===").ok();
             writeln!(file, "Name: {}", name).ok();
             writeln!(file, "Command: {}", command).ok();
@@ -115,29 +408,47 @@ impl BackgroundProcessManager {
             writeln!(file, "Started: {}", timestamp).ok();
             writeln!(file, "================================\n").ok();
         }
+        let header_len = log_handle.stream_position().unwrap_or(0);
 
-        // Clone the file handle for stderr
-        let log_handle_stderr = log_handle
-            .try_clone()
-            .map_err(|e| format!("Failed to clone log file handle: {}", e))?;
+        // stdout appends to the log after the header; stderr gets its own file
+        let stdout = OpenOptions::new()
+            .append(true)
+            .open(&log_file)
+            .map_err(|e| format!("Failed to open log file: {}", e))?;
+        let stderr = File::create(&stderr_log_file)
+            .map_err(|e| format!("Failed to create log file: {}", e))?;
 
-        // Spawn the process
-        let child = Command::new("bash")
+        // Spawn the process in its own process group, so stopping it also
+        // stops whatever a compound command started
+        let mut command_builder = Command::new("bash");
+        command_builder
             .arg("-c")
             .arg(command)
             .current_dir(working_dir)
-            .stdout(Stdio::from(log_handle))
-            .stderr(Stdio::from(log_handle_stderr))
+            .stdout(Stdio::from(stdout))
+            .stderr(Stdio::from(stderr));
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command_builder.process_group(0);
+        }
+        let child = command_builder
             .spawn()
             .map_err(|e| format!("Failed to spawn process: {}", e))?;
 
         let pid = child.id();
+        let logs = ProcessLogs {
+            stdout: LogTail::new(log_file.clone(), header_len),
+            stderr: LogTail::new(stderr_log_file.clone(), 0),
+            lines: RingBuffer::new(self.buffer_capacity),
+        };
 
         let info = ProcessInfo {
             name: name.to_string(),
             command: command.to_string(),
             pid,
             log_file: log_file.clone(),
+            stderr_log_file,
             started_at: timestamp,
             working_dir: working_dir.clone(),
         };
@@ -151,6 +462,14 @@ impl BackgroundProcessManager {
             let mut children = self.children.lock().unwrap();
             children.insert(name.to_string(), child);
         }
+        {
+            let mut outputs = self.outputs.lock().unwrap();
+            outputs.insert(name.to_string(), Arc::new(Mutex::new(logs)));
+        }
+        {
+            let mut read_positions = self.read_positions.lock().unwrap();
+            read_positions.insert(name.to_string(), 0);
+        }
 
         debug!(
             "Started background process '{}' (PID: {}) with logs at {:?}",
@@ -174,16 +493,135 @@ impl BackgroundProcessManager {
 
     /// Check if a process is still running
     pub fn is_running(&self, name: &str) -> bool {
+        self.status(name) == Some(ProcessStatus::Running)
+    }
+
+    /// Current status of a tracked process, or `None` if it is not tracked
+    pub fn status(&self, name: &str) -> Option<ProcessStatus> {
         let mut children = self.children.lock().unwrap();
-        if let Some(child) = children.get_mut(name) {
-            match child.try_wait() {
-                Ok(Some(_)) => false, // Process has exited
-                Ok(None) => true,     // Still running
-                Err(_) => false,      // Error checking, assume not running
+        let child = children.get_mut(name)?;
+        Some(match child.try_wait() {
+            Ok(Some(status)) => ProcessStatus::Exited(status.code()),
+            Ok(None) => ProcessStatus::Running,
+            Err(_) => ProcessStatus::Exited(None), // Error checking, assume not running
+        })
+    }
+
+    /// Stop a process, along with the processes it started, and stop
+    /// tracking it.
+    ///
+    /// After SIGTERM the process gets a few seconds to exit; if it is still
+    /// running after that, it stays tracked and an error is returned so the
+    /// caller can retry with SIGKILL.
+    pub fn stop(&self, name: &str, signal: StopSignal) -> Result<ProcessInfo, String> {
+        let pid = {
+            let mut children = self.children.lock().unwrap();
+            let child = children
+                .get_mut(name)
+                .ok_or_else(|| format!("No background process named '{}'", name))?;
+            if !matches!(child.try_wait(), Ok(None)) {
+                None
+            } else {
+                signal_process_group(child.id(), signal)
+                    .map_err(|e| format!("Failed to send {}: {}", signal, e))?;
+                Some(child.id())
+            }
+        };
+
+        // Wait without holding the lock, so other tools can use the manager
+        if let Some(pid) = pid {
+            let deadline = Instant::now() + STOP_TIMEOUT;
+            while self.is_running(name) {
+                if Instant::now() >= deadline {
+                    return Err(format!(
+                        "Process '{}' (PID {}) is still running {}s after {}{}",
+                        name,
+                        pid,
+                        STOP_TIMEOUT.as_secs(),
+                        signal,
+                        match signal {
+                            StopSignal::Term => "; use SIGKILL to force it",
+                            StopSignal::Kill => "",
+                        }
+                    ));
+                }
+                std::thread::sleep(Duration::from_millis(50));
             }
-        } else {
-            false
         }
+
+        self.remove(name)
+            .ok_or_else(|| format!("No background process named '{}'", name))
+    }
+
+    /// The output of a process, with everything its logs have gained read in
+    fn refreshed_output(&self, name: &str) -> Result<Arc<Mutex<ProcessLogs>>, String> {
+        // Checked first, so all output written before the exit gets read
+        let exited = self
+            .status(name)
+            .is_some_and(|status| status != ProcessStatus::Running);
+        let logs = self
+            .outputs
+            .lock()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("No background process named '{}'", name))?;
+        logs.lock().unwrap().refresh(exited);
+        Ok(logs)
+    }
+
+    /// How many output lines a process has written and how many were dropped
+    /// from its buffer, or `None` if it is not tracked
+    pub fn output_stats(&self, name: &str) -> Option<BufferStats> {
+        let logs = self.refreshed_output(name).ok()?;
+        let stats = logs.lock().unwrap().lines.stats();
+        Some(stats)
+    }
+
+    /// Output lines the process has written since the last call (or since
+    /// it started), at most the last `max_lines` of them.
+    pub fn read_new_output(&self, name: &str, max_lines: usize) -> Result<ProcessOutput, String> {
+        let logs = self.refreshed_output(name)?;
+        let logs = logs.lock().unwrap();
+        let buffer = &logs.lines;
+        let mut read_positions = self.read_positions.lock().unwrap();
+        let seen = read_positions.get(name).copied().unwrap_or(0);
+
+        let available: Vec<&OutputLine> = buffer.since(seen).collect();
+        let start = available.len().saturating_sub(max_lines);
+        let lines: Vec<String> = available[start..].iter().map(|l| l.text.clone()).collect();
+        read_positions.insert(name.to_string(), buffer.total());
+
+        Ok(ProcessOutput {
+            skipped_lines: buffer.total() - seen - lines.len() as u64,
+            lines,
+            stats: buffer.stats(),
+        })
+    }
+
+    /// How many output lines `read_new_output` has already returned for a process
+    pub fn read_position(&self, name: &str) -> Option<u64> {
+        self.read_positions.lock().unwrap().get(name).copied()
+    }
+
+    /// The first line on `streams` matching `pattern` among the lines pushed
+    /// after the first `scanned` that are still buffered. Also returns the
+    /// number of lines pushed so far, to pass as `scanned` next time.
+    pub fn find_output(
+        &self,
+        name: &str,
+        pattern: &Regex,
+        streams: StreamFilter,
+        scanned: u64,
+    ) -> Result<(Option<String>, u64), String> {
+        let logs = self.refreshed_output(name)?;
+        let logs = logs.lock().unwrap();
+        let found = logs
+            .lines
+            .since(scanned)
+            .find(|line| streams.matches(line.stream) && pattern.is_match(&line.text))
+            .map(|line| line.text.clone());
+        Ok((found, logs.lines.total()))
     }
 
     /// Remove a process from tracking (call after it has been killed)
@@ -196,6 +634,14 @@ impl BackgroundProcessManager {
             let mut children = self.children.lock().unwrap();
             children.remove(name);
         }
+        {
+            let mut outputs = self.outputs.lock().unwrap();
+            outputs.remove(name);
+        }
+        {
+            let mut read_positions = self.read_positions.lock().unwrap();
+            read_positions.remove(name);
+        }
         info
     }
 
@@ -204,11 +650,32 @@ impl BackgroundProcessManager {
         let mut children = self.children.lock().unwrap();
         for (name, mut child) in children.drain() {
             debug!("Cleaning up background process '{}'", name);
-            let _ = child.kill();
+            if signal_process_group(child.id(), StopSignal::Kill).is_err() {
+                let _ = child.kill();
+            }
         }
     }
 }
 
+/// Send `signal` to the process group led by `pid`.
+fn signal_process_group(pid: u32, signal: StopSignal) -> std::io::Result<()> {
+    let flag = match signal {
+        StopSignal::Term => "-TERM",
+        StopSignal::Kill => "-KILL",
+    };
+    let status = Command::new("kill")
+        .arg(flag)
+        .arg("--")
+        .arg(format!("-{}", pid))
+        .stderr(Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!("kill exited with {}", status)))
+    }
+}
+
 impl Drop for BackgroundProcessManager {
     fn drop(&mut self) {
         self.cleanup();
@@ -237,6 +704,7 @@ mod tests {
         assert_eq!(info.name, "test_sleep");
         assert!(info.pid > 0);
         assert!(info.log_file.exists());
+        assert!(info.stderr_log_file.exists());
 
         // List should contain our process
         let list = manager.list();
@@ -276,4 +744,236 @@ mod tests {
         manager.cleanup();
         let _ = fs::remove_dir_all(&temp_dir);
     }
+
+    /// Poll `read_new_output` until it returns `expected`.
+    fn wait_for_output(
+        manager: &BackgroundProcessManager,
+        name: &str,
+        expected: &str,
+    ) -> Vec<String> {
+        let mut lines = Vec::new();
+        for _ in 0..100 {
+            lines.extend(manager.read_new_output(name, 50).unwrap().lines);
+            if lines.iter().any(|l| l == expected) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        lines
+    }
+
+    #[test]
+    fn test_stop_process() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().to_path_buf();
+        let manager = BackgroundProcessManager::new(dir.clone());
+
+        manager.start("term_me", "sleep 100", &dir).unwrap();
+        manager.start("kill_me", "sleep 100", &dir).unwrap();
+        assert_eq!(manager.status("term_me"), Some(ProcessStatus::Running));
+
+        let info = manager.stop("term_me", StopSignal::Term).unwrap();
+        assert_eq!(info.name, "term_me");
+        assert_eq!(manager.status("term_me"), None);
+
+        manager.stop("kill_me", StopSignal::Kill).unwrap();
+        assert!(manager.list().is_empty());
+
+        let err = manager.stop("kill_me", StopSignal::Kill).unwrap_err();
+        assert!(err.contains("No background process named 'kill_me'"));
+    }
+
+    #[test]
+    fn test_stop_also_stops_child_processes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().to_path_buf();
+        let manager = BackgroundProcessManager::new(dir.clone());
+
+        // The sleep runs in a child of bash, which waits for it
+        manager
+            .start("compound", "sleep 100 & echo $! > child.pid; wait", &dir)
+            .unwrap();
+        let pid_file = dir.join("child.pid");
+        for _ in 0..100 {
+            if fs::read_to_string(&pid_file).is_ok_and(|pid| pid.ends_with('\n')) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        let child_pid = fs::read_to_string(&pid_file).unwrap().trim().to_string();
+        let child_alive = || {
+            Command::new("kill")
+                .args(["-0", &child_pid])
+                .stderr(Stdio::null())
+                .status()
+                .unwrap()
+                .success()
+        };
+        assert!(child_alive());
+
+        manager.stop("compound", StopSignal::Term).unwrap();
+        for _ in 0..100 {
+            if !child_alive() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert!(!child_alive(), "child {} survived stop", child_pid);
+    }
+
+    #[test]
+    fn test_read_new_output_since_last_call() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().to_path_buf();
+        let manager = BackgroundProcessManager::new(dir.clone());
+
+        manager
+            .start(
+                "chatty",
+                "echo first; sleep 0.3; echo second; sleep 100",
+                &dir,
+            )
+            .unwrap();
+
+        let output = wait_for_output(&manager, "chatty", "first");
+        // The log header is not part of the output
+        assert_eq!(output, vec!["first"]);
+
+        let output = wait_for_output(&manager, "chatty", "second");
+        assert_eq!(output, vec!["second"]);
+        let output = manager.read_new_output("chatty", 50).unwrap();
+        assert!(output.lines.is_empty());
+        assert_eq!(
+            output.stats,
+            BufferStats {
+                total_lines: 2,
+                dropped_lines: 0
+            }
+        );
+
+        // The log file still gets everything
+        let log = fs::read_to_string(manager.get("chatty").unwrap().log_file).unwrap();
+        assert!(log.ends_with("first\nsecond\n"), "{}", log);
+
+        manager.cleanup();
+    }
+
+    #[test]
+    fn test_output_streams_and_last_line_without_newline() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().to_path_buf();
+        let manager = BackgroundProcessManager::new(dir.clone());
+
+        manager
+            .start("mixed", "echo out1; echo err1 >&2; echo out2; printf done", &dir)
+            .unwrap();
+        for _ in 0..100 {
+            if !manager.is_running("mixed") {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        // Read in one go, so stdout lines come first
+        let output = manager.read_new_output("mixed", 50).unwrap();
+        assert_eq!(output.lines, vec!["out1", "out2", "done", "err1"]);
+        let info = manager.get("mixed").unwrap();
+        let log = fs::read_to_string(&info.log_file).unwrap();
+        assert!(log.ends_with("\nout1\nout2\ndone"), "{}", log);
+        assert_eq!(fs::read_to_string(&info.stderr_log_file).unwrap(), "err1\n");
+
+        let any = Regex::new(r"\w+1").unwrap();
+        let (found, total) = manager.find_output("mixed", &any, StreamFilter::Stderr, 0).unwrap();
+        assert_eq!(found.as_deref(), Some("err1"));
+        assert_eq!(total, 4);
+        let (found, _) = manager.find_output("mixed", &any, StreamFilter::Stdout, 0).unwrap();
+        assert_eq!(found.as_deref(), Some("out1"));
+        let (found, _) = manager.find_output("mixed", &any, StreamFilter::Stdout, 1).unwrap();
+        assert_eq!(found, None);
+
+        assert_eq!(StreamFilter::parse("STDERR"), Some(StreamFilter::Stderr));
+        assert_eq!(StreamFilter::parse("stdin"), None);
+    }
+
+    #[test]
+    fn test_ring_buffer_drops_oldest() {
+        let mut buffer = RingBuffer::new(3);
+        for i in 1..=5 {
+            buffer.push(format!("line {}", i));
+        }
+
+        assert_eq!(buffer.len(), 3);
+        let kept: Vec<&String> = buffer.last(10).collect();
+        assert_eq!(kept, vec!["line 3", "line 4", "line 5"]);
+        assert_eq!(buffer.last(2).collect::<Vec<_>>(), vec!["line 4", "line 5"]);
+        assert_eq!(
+            buffer.stats(),
+            BufferStats {
+                total_lines: 5,
+                dropped_lines: 2
+            }
+        );
+
+        // Of the lines after the first 4, only line 5 is new
+        assert_eq!(buffer.since(4).collect::<Vec<_>>(), vec!["line 5"]);
+        // Lines 2 and 3 were pushed after the first one, but 2 is gone
+        assert_eq!(buffer.since(1).count(), 3);
+        assert_eq!(buffer.since(5).count(), 0);
+    }
+
+    #[test]
+    fn test_read_new_output_reports_dropped_lines() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().to_path_buf();
+        let manager = BackgroundProcessManager::new(dir.clone()).with_buffer_capacity(10);
+
+        manager.start("flood", "seq 1 25; sleep 100", &dir).unwrap();
+        for _ in 0..100 {
+            if manager.output_stats("flood").map(|s| s.total_lines) == Some(25) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        let output = manager.read_new_output("flood", 4).unwrap();
+        assert_eq!(output.lines, vec!["22", "23", "24", "25"]);
+        assert_eq!(output.skipped_lines, 21);
+        assert_eq!(
+            output.stats,
+            BufferStats {
+                total_lines: 25,
+                dropped_lines: 15
+            }
+        );
+
+        let output = manager.read_new_output("flood", 4).unwrap();
+        assert!(output.lines.is_empty());
+        assert_eq!(output.skipped_lines, 0);
+
+        manager.cleanup();
+    }
+
+    #[test]
+    fn test_exited_status_and_signal_parsing() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().to_path_buf();
+        let manager = BackgroundProcessManager::new(dir.clone());
+
+        manager.start("fails", "exit 3", &dir).unwrap();
+        for _ in 0..100 {
+            if !manager.is_running("fails") {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(
+            manager.status("fails"),
+            Some(ProcessStatus::Exited(Some(3)))
+        );
+        assert_eq!(ProcessStatus::Exited(Some(3)).to_string(), "exited (3)");
+
+        assert_eq!(StopSignal::parse("SIGTERM"), Some(StopSignal::Term));
+        assert_eq!(StopSignal::parse("kill"), Some(StopSignal::Kill));
+        assert_eq!(StopSignal::parse("SIGHUP"), None);
+    }
 }
@@ -0,0 +1,549 @@
+//! Function length and cyclomatic complexity for Rust and Python source.
+//!
+//! Rust files are parsed with `syn`. Python has no parser here, so its
+//! functions are found by indentation and its branches counted by keyword.
+//! Complexity is 1 plus one per `if`, loop condition (`for`/`while`), extra
+//! `match` arm (Python: `except`/`case`) and boolean `&&`/`||` (`and`/`or`).
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Serialize;
+use std::sync::OnceLock;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+/// Longest a function may be, matching the limit in the system prompt.
+pub const MAX_FUNCTION_LINES: u32 = 80;
+/// Highest cyclomatic complexity before a function is flagged.
+pub const MAX_COMPLEXITY: u32 = 10;
+
+/// Length and complexity of one function.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionComplexity {
+    pub name: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub lines: u32,
+    pub complexity: u32,
+    pub violations: Vec<String>,
+}
+
+impl FunctionComplexity {
+    fn new(name: String, start_line: u32, end_line: u32, complexity: u32) -> Self {
+        let lines = end_line.saturating_sub(start_line) + 1;
+        let mut violations = Vec::new();
+        if lines > MAX_FUNCTION_LINES {
+            violations.push(format!("{} lines (max {})", lines, MAX_FUNCTION_LINES));
+        }
+        if complexity > MAX_COMPLEXITY {
+            violations.push(format!(
+                "cyclomatic complexity {} (max {})",
+                complexity, MAX_COMPLEXITY
+            ));
+        }
+        Self {
+            name,
+            start_line,
+            end_line,
+            lines,
+            complexity,
+            violations,
+        }
+    }
+}
+
+/// Number of methods in an `impl` block (Rust) or class (Python).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImplBlock {
+    pub name: String,
+    pub line: u32,
+    pub methods: usize,
+}
+
+/// Functions of a file, most severe first, and its impl blocks.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ComplexityReport {
+    pub functions: Vec<FunctionComplexity>,
+    pub impl_blocks: Vec<ImplBlock>,
+}
+
+impl ComplexityReport {
+    /// Order functions by number of violations, then complexity, then length.
+    fn sort_by_severity(&mut self) {
+        self.functions.sort_by(|a, b| {
+            b.violations
+                .len()
+                .cmp(&a.violations.len())
+                .then(b.complexity.cmp(&a.complexity))
+                .then(b.lines.cmp(&a.lines))
+                .then(a.start_line.cmp(&b.start_line))
+        });
+    }
+}
+
+/// Analyze `source` written in `language` (`rust` or `python`).
+pub fn analyze(source: &str, language: &str) -> Result<ComplexityReport> {
+    match language {
+        "rust" => analyze_rust(source),
+        "python" => Ok(analyze_python(source)),
+        other => anyhow::bail!("Unsupported language '{}'. Use 'rust' or 'python'", other),
+    }
+}
+
+/// Analyze a Rust file. Methods are named `Type::method`, and functions in
+/// inline modules get the module path as a prefix.
+pub fn analyze_rust(source: &str) -> Result<ComplexityReport> {
+    let file = syn::parse_file(source).context("Failed to parse Rust source")?;
+    let mut collector = RustCollector::default();
+    collector.visit_file(&file);
+    collector.report.sort_by_severity();
+    Ok(collector.report)
+}
+
+#[derive(Default)]
+struct RustCollector {
+    path: Vec<String>,
+    report: ComplexityReport,
+}
+
+impl RustCollector {
+    fn add_function(&mut self, name: &syn::Ident, sig: &syn::Signature, block: &syn::Block) {
+        let mut counter = BranchCounter { branches: 0 };
+        counter.visit_block(block);
+        let mut full_name = self.path.clone();
+        full_name.push(name.to_string());
+        self.report.functions.push(FunctionComplexity::new(
+            full_name.join("::"),
+            sig.fn_token.span.start().line as u32,
+            block.brace_token.span.close().end().line as u32,
+            1 + counter.branches,
+        ));
+    }
+
+    fn add_impl_block(&mut self, name: String, line: usize, methods: usize) {
+        self.report.impl_blocks.push(ImplBlock {
+            name,
+            line: line as u32,
+            methods,
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for RustCollector {
+    fn visit_item_fn(&mut self, item: &'ast syn::ItemFn) {
+        self.add_function(&item.sig.ident, &item.sig, &item.block);
+        // Functions nested in the body are reported on their own
+        visit::visit_item_fn(self, item);
+    }
+
+    fn visit_item_mod(&mut self, item: &'ast syn::ItemMod) {
+        self.path.push(item.ident.to_string());
+        visit::visit_item_mod(self, item);
+        self.path.pop();
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast syn::ItemImpl) {
+        let name = type_name(&item.self_ty);
+        let methods = item
+            .items
+            .iter()
+            .filter(|i| matches!(i, syn::ImplItem::Fn(_)))
+            .count();
+        self.add_impl_block(name.clone(), item.impl_token.span.start().line, methods);
+        self.path.push(name);
+        visit::visit_item_impl(self, item);
+        self.path.pop();
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast syn::ImplItemFn) {
+        self.add_function(&item.sig.ident, &item.sig, &item.block);
+        visit::visit_impl_item_fn(self, item);
+    }
+
+    fn visit_item_trait(&mut self, item: &'ast syn::ItemTrait) {
+        self.path.push(item.ident.to_string());
+        visit::visit_item_trait(self, item);
+        self.path.pop();
+    }
+
+    fn visit_trait_item_fn(&mut self, item: &'ast syn::TraitItemFn) {
+        if let Some(block) = &item.default {
+            self.add_function(&item.sig.ident, &item.sig, block);
+        }
+        visit::visit_trait_item_fn(self, item);
+    }
+}
+
+/// Last path segment of an impl's self type, e.g. `Parser` for `crate::Parser<T>`.
+fn type_name(ty: &syn::Type) -> String {
+    match ty {
+        syn::Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .unwrap_or_default(),
+        syn::Type::Reference(reference) => type_name(&reference.elem),
+        other => format!("<type at line {}>", other.span().start().line),
+    }
+}
+
+/// Counts the decision points of one function body.
+struct BranchCounter {
+    branches: u32,
+}
+
+impl<'ast> Visit<'ast> for BranchCounter {
+    fn visit_expr_if(&mut self, expr: &'ast syn::ExprIf) {
+        self.branches += 1;
+        visit::visit_expr_if(self, expr);
+    }
+
+    fn visit_expr_match(&mut self, expr: &'ast syn::ExprMatch) {
+        self.branches += expr.arms.len().saturating_sub(1) as u32;
+        visit::visit_expr_match(self, expr);
+    }
+
+    fn visit_expr_for_loop(&mut self, expr: &'ast syn::ExprForLoop) {
+        self.branches += 1;
+        visit::visit_expr_for_loop(self, expr);
+    }
+
+    fn visit_expr_while(&mut self, expr: &'ast syn::ExprWhile) {
+        self.branches += 1;
+        visit::visit_expr_while(self, expr);
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast syn::ExprBinary) {
+        if matches!(expr.op, syn::BinOp::And(_) | syn::BinOp::Or(_)) {
+            self.branches += 1;
+        }
+        visit::visit_expr_binary(self, expr);
+    }
+
+    // Nested items are measured separately
+    fn visit_item(&mut self, _item: &'ast syn::Item) {}
+}
+
+/// A `def` or `class` line of a Python file.
+struct PythonBlock {
+    name: String,
+    is_class: bool,
+    indent: usize,
+    /// 0-based line indices of the header and the last line of the body
+    start: usize,
+    end: usize,
+}
+
+fn python_block_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(\s*)(?:async\s+)?(def|class)\s+(\w+)").unwrap())
+}
+
+fn python_branch_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\b(if|elif|for|while|except|case|and|or)\b").unwrap())
+}
+
+/// Analyze a Python file. Methods are named `Class.method`.
+pub fn analyze_python(source: &str) -> ComplexityReport {
+    let lines = python_code_lines(source);
+    let blocks = python_blocks(&lines);
+
+    let mut report = ComplexityReport::default();
+    for block in &blocks {
+        let parents: Vec<&str> = blocks
+            .iter()
+            .filter(|outer| outer.start < block.start && block.end <= outer.end)
+            .map(|outer| outer.name.as_str())
+            .collect();
+        let name = parents
+            .into_iter()
+            .chain(std::iter::once(block.name.as_str()))
+            .collect::<Vec<_>>()
+            .join(".");
+
+        if block.is_class {
+            let methods = blocks
+                .iter()
+                .filter(|inner| !inner.is_class && inner.start > block.start)
+                .filter(|inner| {
+                    inner.end <= block.end && nearest_parent(&blocks, inner) == Some(block.start)
+                })
+                .count();
+            report.impl_blocks.push(ImplBlock {
+                name,
+                line: block.start as u32 + 1,
+                methods,
+            });
+            continue;
+        }
+
+        let branches: usize = (block.start + 1..=block.end)
+            .filter(|&i| nearest_parent_of_line(&blocks, i) == Some(block.start))
+            .map(|i| python_branch_regex().find_iter(&lines[i]).count())
+            .sum();
+        report.functions.push(FunctionComplexity::new(
+            name,
+            block.start as u32 + 1,
+            block.end as u32 + 1,
+            1 + branches as u32,
+        ));
+    }
+    report.sort_by_severity();
+    report
+}
+
+/// Source lines with comments, docstrings and string contents blanked out,
+/// so keywords in them are not counted. Line numbers are kept.
+fn python_code_lines(source: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut in_triple: Option<&str> = None;
+    for line in source.lines() {
+        let mut code = String::new();
+        let mut rest = line;
+        while !rest.is_empty() {
+            if let Some(quote) = in_triple {
+                match rest.find(quote) {
+                    Some(end) => {
+                        rest = &rest[end + 3..];
+                        in_triple = None;
+                    }
+                    None => rest = "",
+                }
+                continue;
+            }
+            let Some(pos) = rest.find(['#', '"', '\'']) else {
+                code.push_str(rest);
+                break;
+            };
+            code.push_str(&rest[..pos]);
+            rest = &rest[pos..];
+            if rest.starts_with('#') {
+                break;
+            }
+            if let Some(quote) = ["\"\"\"", "'''"].into_iter().find(|q| rest.starts_with(q)) {
+                in_triple = Some(quote);
+                rest = &rest[3..];
+                continue;
+            }
+            // Single-line string: skip to the closing quote
+            let quote = &rest[..1];
+            code.push_str("\"\"");
+            rest = match rest[1..].find(quote) {
+                Some(end) => &rest[end + 2..],
+                None => "",
+            };
+        }
+        lines.push(code);
+    }
+    lines
+}
+
+/// Every `def` and `class` with the extent of its indented body.
+fn python_blocks(lines: &[String]) -> Vec<PythonBlock> {
+    let mut blocks = Vec::new();
+    for (start, line) in lines.iter().enumerate() {
+        let Some(caps) = python_block_regex().captures(line) else {
+            continue;
+        };
+        let indent = caps[1].len();
+        let mut end = start;
+        for (i, body_line) in lines.iter().enumerate().skip(start + 1) {
+            if body_line.trim().is_empty() {
+                continue;
+            }
+            if indentation(body_line) <= indent {
+                break;
+            }
+            end = i;
+        }
+        blocks.push(PythonBlock {
+            name: caps[3].to_string(),
+            is_class: &caps[2] == "class",
+            indent,
+            start,
+            end,
+        });
+    }
+    blocks
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Start line of the innermost block whose body contains line `i`.
+fn nearest_parent_of_line(blocks: &[PythonBlock], i: usize) -> Option<usize> {
+    blocks
+        .iter()
+        .filter(|b| b.start < i && i <= b.end)
+        .max_by_key(|b| b.indent)
+        .map(|b| b.start)
+}
+
+fn nearest_parent(blocks: &[PythonBlock], block: &PythonBlock) -> Option<usize> {
+    nearest_parent_of_line(blocks, block.start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rust source with one deliberately complex function after a short one.
+    const RUST_SOURCE: &str = r#"
+fn simple() -> u32 {
+    1
+}
+
+struct Classifier;
+
+impl Classifier {
+    fn new() -> Self {
+        Classifier
+    }
+
+    /// Complexity 1 + 4 ifs + 3 extra arms + 1 for + 1 while + 2 booleans = 12
+    fn classify(&self, values: &[i32]) -> &'static str {
+        let mut total = 0;
+        for v in values {
+            if *v > 0 && *v < 100 {
+                total += v;
+            } else if *v < 0 || *v > 1000 {
+                total -= 1;
+            }
+        }
+        while total > 10 {
+            total /= 2;
+        }
+        if total == 0 {
+            return "zero";
+        }
+        if total == 1 {
+            return "one";
+        }
+        match total {
+            2 => "two",
+            3 => "three",
+            4 => "four",
+            _ => "many",
+        }
+    }
+}
+
+mod nested {
+    pub fn outer(x: bool) -> u32 {
+        fn inner(y: bool) -> u32 {
+            if y { 1 } else { 2 }
+        }
+        inner(x)
+    }
+}
+"#;
+
+    #[test]
+    fn test_rust_complexity() {
+        let report = analyze_rust(RUST_SOURCE).unwrap();
+
+        let classify = &report.functions[0];
+        assert_eq!(classify.name, "Classifier::classify");
+        assert_eq!(classify.complexity, 12);
+        assert_eq!((classify.start_line, classify.end_line), (14, 38));
+        assert_eq!(classify.lines, 25);
+        assert_eq!(
+            classify.violations,
+            vec!["cyclomatic complexity 12 (max 10)".to_string()]
+        );
+
+        let find = |name: &str| report.functions.iter().find(|f| f.name == name).unwrap();
+        assert_eq!(find("simple").complexity, 1);
+        assert_eq!((find("simple").start_line, find("simple").lines), (2, 3));
+        assert_eq!(find("Classifier::new").complexity, 1);
+        // The nested function's `if` is not counted for its parent
+        assert_eq!(find("nested::outer").complexity, 1);
+        assert_eq!(find("nested::inner").complexity, 2);
+        assert!(report.functions[1..]
+            .iter()
+            .all(|f| f.violations.is_empty()));
+
+        assert_eq!(
+            report.impl_blocks,
+            vec![ImplBlock {
+                name: "Classifier".to_string(),
+                line: 8,
+                methods: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rust_long_function() {
+        let body = "    let _x = 1;\n".repeat(85);
+        let source = format!("fn long() {{\n{}}}\n", body);
+        let report = analyze_rust(&source).unwrap();
+        assert_eq!(report.functions[0].lines, 87);
+        assert_eq!(
+            report.functions[0].violations,
+            vec!["87 lines (max 80)".to_string()]
+        );
+
+        assert!(analyze_rust("fn broken( {").is_err());
+    }
+
+    #[test]
+    fn test_python_complexity() {
+        let source = r#"
+class Router:
+    """Routes requests if and only if they match."""
+
+    def route(self, request):
+        # if this comment counted, complexity would be off
+        if request.method == "GET" and request.path:
+            for handler in self.handlers:
+                if handler.matches(request) or handler.default:
+                    return handler
+        elif request.method == "POST":
+            while self.busy:
+                pass
+        try:
+            return self.fallback("if or and")
+        except KeyError:
+            return None
+
+    def name(self):
+        return "router"
+
+
+def helper(x):
+    def inner(y):
+        return y if y else 0
+    return inner(x)
+"#;
+        let report = analyze_python(source);
+
+        let route = &report.functions[0];
+        assert_eq!(route.name, "Router.route");
+        // 1 + if, and, for, if, or, elif, while, except
+        assert_eq!(route.complexity, 9);
+        assert_eq!((route.start_line, route.end_line), (5, 17));
+
+        let find = |name: &str| report.functions.iter().find(|f| f.name == name).unwrap();
+        assert_eq!(find("Router.name").complexity, 1);
+        assert_eq!(find("helper").complexity, 1);
+        assert_eq!(find("helper.inner").complexity, 2);
+
+        assert_eq!(
+            report.impl_blocks,
+            vec![ImplBlock {
+                name: "Router".to_string(),
+                line: 2,
+                methods: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_analyze_rejects_unknown_language() {
+        assert!(analyze("x", "cobol").is_err());
+    }
+}
@@ -1,20 +1,32 @@
-//! Code search functionality using tree-sitter for syntax-aware searches
+//! Code search functionality using tree-sitter for syntax-aware searches,
+//! plus an opt-in semantic mode backed by an embedding index (see [`semantic`]).
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+mod ranking;
 mod searcher;
+pub mod semantic;
+pub use ranking::Ranking;
 pub use searcher::TreeSitterSearcher;
+pub use semantic::{embedder_from_config, SemanticIndex, SemanticMatch};
 
 /// Request for batch code searches
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeSearchRequest {
+    #[serde(default)]
     pub searches: Vec<SearchSpec>,
     #[serde(default = "default_concurrency")]
     pub max_concurrency: usize,
     #[serde(default = "default_max_matches")]
     pub max_matches_per_search: usize,
+    /// Matches returned per search, best ranked first (default 20)
+    #[serde(default)]
+    pub max_results: Option<usize>,
+    /// Lines of context around each match, for searches that don't set their own (default 0)
+    #[serde(default)]
+    pub context_lines: Option<usize>,
 }
 
 fn default_concurrency() -> usize {
@@ -25,6 +37,8 @@ fn default_max_matches() -> usize {
     500
 }
 
+pub const DEFAULT_MAX_RESULTS: usize = 20;
+
 /// Individual search specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchSpec {
@@ -40,6 +54,9 @@ pub struct SearchSpec {
     /// Lines of context around each match
     #[serde(default)]
     pub context_lines: usize,
+    /// Symbol being looked for; matches with this exact name rank first
+    #[serde(default)]
+    pub symbol: Option<String>,
 }
 
 /// Response containing all search results
@@ -54,7 +71,9 @@ pub struct CodeSearchResponse {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchResult {
     pub name: String,
+    /// The best ranked matches, highest score first
     pub matches: Vec<Match>,
+    /// All matches found, including those cut by `max_results`
     pub match_count: usize,
     pub files_searched: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -66,16 +85,31 @@ pub struct SearchResult {
 pub struct Match {
     pub file: String,
     pub line: usize,
+    /// Last line of the matched node; equal to `line` for single-line matches
+    #[serde(default)]
+    pub end_line: usize,
     pub column: usize,
     pub text: String,
     #[serde(skip_serializing_if = "HashMap::is_empty")]
     pub captures: HashMap<String, String>,
+    /// Numbered source lines around the match, with `>>>` marking the matched lines
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context: Option<String>,
+    /// Relevance score used for ranking (see [`Ranking`])
+    #[serde(default)]
+    pub score: f32,
 }
 
 /// Main entry point for code search
 pub async fn execute_code_search(request: CodeSearchRequest) -> Result<CodeSearchResponse> {
-    let mut searcher = TreeSitterSearcher::new()?;
+    execute_ranked_code_search(request, Ranking::default()).await
+}
+
+/// Code search with matches ranked by `ranking`.
+pub async fn execute_ranked_code_search(
+    request: CodeSearchRequest,
+    ranking: Ranking,
+) -> Result<CodeSearchResponse> {
+    let mut searcher = TreeSitterSearcher::new()?.with_ranking(ranking);
     searcher.execute_search(request).await
 }
@@ -0,0 +1,174 @@
+//! Relevance ranking for code search matches.
+//!
+//! Each match is scored from three components, each between 0 and 1:
+//! whether its text is the symbol being looked for, how recently its file was
+//! modified, and how close its file is to files the session recently read or
+//! edited. The components are combined with the weights from
+//! [`CodeSearchConfig`].
+
+use g3_config::CodeSearchConfig;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use super::Match;
+
+/// What matches are ranked against.
+#[derive(Debug, Clone, Default)]
+pub struct Ranking {
+    pub weights: CodeSearchConfig,
+    /// Files recently read or edited, most recent last
+    pub recent_files: Vec<PathBuf>,
+}
+
+impl Ranking {
+    pub fn new(weights: CodeSearchConfig, recent_files: &[PathBuf]) -> Self {
+        Self {
+            weights,
+            recent_files: recent_files.iter().map(|p| canonical(p)).collect(),
+        }
+    }
+
+    /// Score `m`, found in `path` (modified at `modified`), for `symbol`.
+    pub fn score(
+        &self,
+        m: &Match,
+        symbol: Option<&str>,
+        path: &Path,
+        modified: Option<SystemTime>,
+        now: SystemTime,
+    ) -> f32 {
+        self.weights.name_match_weight * name_score(m, symbol)
+            + self.weights.recency_weight * recency_score(modified, now)
+            + self.weights.proximity_weight * self.proximity_score(path)
+    }
+
+    /// Closeness of `path` to the nearest recently accessed file: 1 for the
+    /// file itself, less the fewer leading directories they share.
+    pub fn proximity_score(&self, path: &Path) -> f32 {
+        let path = canonical(path);
+        let path_components: Vec<Component> = path.components().collect();
+        self.recent_files
+            .iter()
+            .map(|recent| {
+                let recent_components: Vec<Component> = recent.components().collect();
+                let shared = path_components
+                    .iter()
+                    .zip(&recent_components)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                let longest = path_components.len().max(recent_components.len());
+                if longest == 0 {
+                    0.0
+                } else {
+                    shared as f32 / longest as f32
+                }
+            })
+            .fold(0.0, f32::max)
+    }
+}
+
+/// 1 for an exact match on the symbol, 0.5 ignoring case, else 0.
+/// The `name` capture is preferred over the whole match text.
+fn name_score(m: &Match, symbol: Option<&str>) -> f32 {
+    let Some(symbol) = symbol.filter(|s| !s.is_empty()) else {
+        return 0.0;
+    };
+    let name = m.captures.get("name").unwrap_or(&m.text);
+    if name == symbol {
+        1.0
+    } else if name.eq_ignore_ascii_case(symbol) {
+        0.5
+    } else {
+        0.0
+    }
+}
+
+/// 1 for a file modified just now, falling as 1 / (1 + age in days).
+fn recency_score(modified: Option<SystemTime>, now: SystemTime) -> f32 {
+    let Some(modified) = modified else {
+        return 0.0;
+    };
+    let age_days = now
+        .duration_since(modified)
+        .map(|d| d.as_secs_f32() / 86_400.0)
+        .unwrap_or(0.0);
+    1.0 / (1.0 + age_days)
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn matched(text: &str) -> Match {
+        Match {
+            file: "src/lib.rs".to_string(),
+            line: 1,
+            end_line: 1,
+            column: 1,
+            text: text.to_string(),
+            captures: HashMap::new(),
+            context: None,
+            score: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_name_score() {
+        assert_eq!(
+            name_score(&matched("parse_config"), Some("parse_config")),
+            1.0
+        );
+        assert_eq!(
+            name_score(&matched("Parse_Config"), Some("parse_config")),
+            0.5
+        );
+        assert_eq!(name_score(&matched("parse"), Some("parse_config")), 0.0);
+        assert_eq!(name_score(&matched("parse_config"), None), 0.0);
+
+        let mut with_capture = matched("fn parse_config() {}");
+        with_capture
+            .captures
+            .insert("name".to_string(), "parse_config".to_string());
+        assert_eq!(name_score(&with_capture, Some("parse_config")), 1.0);
+    }
+
+    #[test]
+    fn test_recency_score_decays() {
+        let now = SystemTime::now();
+        let day = Duration::from_secs(86_400);
+        assert_eq!(recency_score(Some(now), now), 1.0);
+        assert_eq!(recency_score(Some(now - day), now), 0.5);
+        assert!(recency_score(Some(now - day * 30), now) < 0.05);
+        assert_eq!(recency_score(None, now), 0.0);
+    }
+
+    #[test]
+    fn test_proximity_score() {
+        let ranking = Ranking::new(
+            CodeSearchConfig::default(),
+            &[PathBuf::from("/repo/src/parser/mod.rs")],
+        );
+        assert_eq!(
+            ranking.proximity_score(Path::new("/repo/src/parser/mod.rs")),
+            1.0
+        );
+        let sibling = ranking.proximity_score(Path::new("/repo/src/parser/lexer.rs"));
+        let cousin = ranking.proximity_score(Path::new("/repo/src/main.rs"));
+        let elsewhere = ranking.proximity_score(Path::new("/other/lib.rs"));
+        assert!(
+            sibling > cousin && cousin > elsewhere,
+            "{sibling} {cousin} {elsewhere}"
+        );
+
+        assert_eq!(
+            Ranking::default().proximity_score(Path::new("/repo/a.rs")),
+            0.0
+        );
+    }
+}
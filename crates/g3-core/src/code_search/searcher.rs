@@ -1,8 +1,12 @@
-use super::{CodeSearchRequest, CodeSearchResponse, Match, SearchResult, SearchSpec};
+use super::{
+    CodeSearchRequest, CodeSearchResponse, Match, Ranking, SearchResult, SearchSpec,
+    DEFAULT_MAX_RESULTS,
+};
 use anyhow::{anyhow, Result};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::time::SystemTime;
 use streaming_iterator::StreamingIterator;
 use tree_sitter::{Language, Parser, Query, QueryCursor};
 use walkdir::WalkDir;
@@ -10,6 +14,7 @@ use walkdir::WalkDir;
 pub struct TreeSitterSearcher {
     parsers: HashMap<String, Parser>,
     languages: HashMap<String, Language>,
+    ranking: Ranking,
 }
 
 impl TreeSitterSearcher {
@@ -160,7 +165,17 @@ impl TreeSitterSearcher {
             ));
         }
 
-        Ok(Self { parsers, languages })
+        Ok(Self {
+            parsers,
+            languages,
+            ranking: Ranking::default(),
+        })
+    }
+
+    /// Rank matches with `ranking` instead of the default weights.
+    pub fn with_ranking(mut self, ranking: Ranking) -> Self {
+        self.ranking = ranking;
+        self
     }
 
     pub async fn execute_search(
@@ -171,16 +186,25 @@ impl TreeSitterSearcher {
         let mut total_matches = 0;
         let mut total_files = 0;
 
+        let max_results = request.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+        let default_context = request.context_lines.unwrap_or(0);
+        let mut context_per_result = Vec::new();
+
         // Execute searches sequentially (could parallelize with tokio::spawn if needed)
         for spec in request.searches {
             let result = self
-                .search_single(&spec, request.max_matches_per_search)
+                .search_single(&spec, request.max_matches_per_search, max_results)
                 .await;
             match result {
                 Ok(search_result) => {
                     total_matches += search_result.match_count;
                     total_files += search_result.files_searched;
                     all_results.push(search_result);
+                    context_per_result.push(if spec.context_lines > 0 {
+                        spec.context_lines
+                    } else {
+                        default_context
+                    });
                 }
                 Err(e) => {
                     all_results.push(SearchResult {
@@ -190,10 +214,22 @@ impl TreeSitterSearcher {
                         files_searched: 0,
                         error: Some(e.to_string()),
                     });
+                    context_per_result.push(0);
                 }
             }
         }
 
+        // Context is added after ranking so only the returned matches pay for it
+        let with_context = all_results
+            .iter_mut()
+            .zip(context_per_result)
+            .filter(|(_, context_lines)| *context_lines > 0)
+            .flat_map(|(result, context_lines)| {
+                result.matches.iter_mut().map(move |m| (m, context_lines))
+            })
+            .collect();
+        attach_context(with_context);
+
         Ok(CodeSearchResponse {
             searches: all_results,
             total_matches,
@@ -205,6 +241,7 @@ impl TreeSitterSearcher {
         &mut self,
         spec: &SearchSpec,
         max_matches: usize,
+        max_results: usize,
     ) -> Result<SearchResult> {
         // Get parser and language
         let parser = self
@@ -222,6 +259,7 @@ impl TreeSitterSearcher {
 
         let mut matches = Vec::new();
         let mut files_searched = 0;
+        let now = SystemTime::now();
 
         // Determine search paths
         let search_paths = if spec.paths.is_empty() {
@@ -252,6 +290,7 @@ impl TreeSitterSearcher {
                 }
 
                 files_searched += 1;
+                let modified = entry.metadata().ok().and_then(|m| m.modified().ok());
 
                 // Read and parse file
                 if let Ok(source_code) = fs::read_to_string(path) {
@@ -270,6 +309,7 @@ impl TreeSitterSearcher {
                             let mut captures_map = HashMap::new();
                             let mut match_text = String::new();
                             let mut match_line = 0;
+                            let mut match_end_line = 0;
                             let mut match_column = 0;
 
                             for capture in query_match.captures {
@@ -284,29 +324,29 @@ impl TreeSitterSearcher {
                                     match_text = text.to_string();
                                     let start = node.start_position();
                                     match_line = start.row + 1;
+                                    match_end_line = node.end_position().row + 1;
                                     match_column = start.column + 1;
                                 }
                             }
 
-                            // Get context if requested
-                            let context = if spec.context_lines > 0 {
-                                Some(Self::get_context(
-                                    &source_code,
-                                    match_line,
-                                    spec.context_lines,
-                                ))
-                            } else {
-                                None
-                            };
-
-                            matches.push(Match {
+                            let mut found = Match {
                                 file: path.display().to_string(),
                                 line: match_line,
+                                end_line: match_end_line,
                                 column: match_column,
                                 text: match_text,
                                 captures: captures_map,
-                                context,
-                            });
+                                context: None,
+                                score: 0.0,
+                            };
+                            found.score = self.ranking.score(
+                                &found,
+                                spec.symbol.as_deref(),
+                                path,
+                                modified,
+                                now,
+                            );
+                            matches.push(found);
 
                             query_matches.advance();
                         }
@@ -315,9 +355,14 @@ impl TreeSitterSearcher {
             }
         }
 
+        // Best first; equal scores keep the order they were found in
+        let match_count = matches.len();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(max_results);
+
         Ok(SearchResult {
             name: spec.name.clone(),
-            match_count: matches.len(),
+            match_count,
             files_searched,
             matches,
             error: None,
@@ -341,14 +386,69 @@ impl TreeSitterSearcher {
             _ => false,
         }
     }
+}
 
-    fn get_context(source: &str, line: usize, context_lines: usize) -> String {
+/// Fill in `context` for each match with its requested number of surrounding lines.
+///
+/// Matches are grouped by file first so each file is read once, however many
+/// of its matches were returned.
+fn attach_context(matches: Vec<(&mut Match, usize)>) {
+    let mut by_file: HashMap<String, Vec<(&mut Match, usize)>> = HashMap::new();
+    for (m, context_lines) in matches {
+        by_file.entry(m.file.clone()).or_default().push((m, context_lines));
+    }
+
+    for (file, matches) in by_file {
+        let Ok(source) = fs::read_to_string(&file) else {
+            continue;
+        };
         let lines: Vec<&str> = source.lines().collect();
-        // line is 1-indexed, convert to 0-indexed
-        let line_idx = line.saturating_sub(1);
-        // Get context_lines before and after
-        let start = line_idx.saturating_sub(context_lines);
-        let end = (line_idx + context_lines + 1).min(lines.len());
-        lines[start..end].join("\n")
+        for (m, context_lines) in matches {
+            m.context = Some(format_context(&lines, m.line, m.end_line, context_lines));
+        }
+    }
+}
+
+/// Render lines `first - context_lines ..= last + context_lines` (1-based) as a
+/// numbered code block, marking the matched lines with `>>>`.
+pub(crate) fn format_context(lines: &[&str], first: usize, last: usize, context_lines: usize) -> String {
+    let first = first.max(1);
+    let last = last.max(first);
+    let start = first.saturating_sub(context_lines).max(1);
+    let end = (last + context_lines).min(lines.len());
+    let width = end.to_string().len();
+
+    let mut out = String::from("```\n");
+    for number in start..=end {
+        let marker = if (first..=last).contains(&number) { ">>>" } else { "   " };
+        out.push_str(&format!("{} {:>width$} | {}\n", marker, number, lines[number - 1]));
+    }
+    out.push_str("```");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_context_marks_match_range() {
+        let source = "use std::fmt;\n\nstruct Point {\n    x: i32,\n}\n\nimpl Point {\n    fn x(&self) -> i32 {\n        self.x\n    }\n}";
+        let lines: Vec<&str> = source.lines().collect();
+
+        assert_eq!(
+            format_context(&lines, 8, 10, 1),
+            "```\n     7 | impl Point {\n>>>  8 |     fn x(&self) -> i32 {\n>>>  9 |         self.x\n>>> 10 |     }\n    11 | }\n```"
+        );
+    }
+
+    #[test]
+    fn test_format_context_clamps_to_file() {
+        let lines = vec!["fn main() {", "}"];
+
+        assert_eq!(
+            format_context(&lines, 1, 1, 5),
+            "```\n>>> 1 | fn main() {\n    2 | }\n```"
+        );
     }
 }
@@ -0,0 +1,668 @@
+//! Semantic code search: functions and classes are embedded as vectors and
+//! ranked by cosine similarity to a natural-language query.
+//!
+//! `build_semantic_index` extracts definitions with tree-sitter, embeds them
+//! and caches the result in `.g3/embeddings.bin`. Embeddings come from an
+//! OpenAI-compatible endpoint when `code_search.embedding_endpoint` is set.
+//! Otherwise [`HashingEmbedder`] is used, which needs no model but only
+//! matches on the words used in names, comments and code.
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use g3_config::CodeSearchConfig;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tree_sitter::{Language, Node, Parser};
+
+/// Marks an index file written by [`SemanticIndex::save`].
+const INDEX_MAGIC: &[u8] = b"G3EMB\x01";
+
+/// Matches returned by a semantic search unless the caller asks for more.
+pub const DEFAULT_TOP_K: usize = 10;
+
+/// Source text embedded per definition; longer definitions are cut.
+const MAX_CHUNK_CHARS: usize = 4000;
+
+/// Files larger than this are not indexed.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Texts sent to an embedding endpoint per request.
+const EMBED_BATCH_SIZE: usize = 64;
+
+const MAX_SIGNATURE_CHARS: usize = 200;
+
+/// Turns text into vectors whose cosine similarity reflects how related the texts are.
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Identifies the model. An index only answers queries embedded by the same one.
+    fn id(&self) -> String;
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Embedding without a model: each word of the text, split out of
+/// identifiers and lightly stemmed, is hashed into one of `dimensions` buckets.
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    dimensions: usize,
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(512)
+    }
+}
+
+impl HashingEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions: dimensions.max(1),
+        }
+    }
+
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimensions];
+        for word in words(text) {
+            let hash = fnv1a(word.as_bytes());
+            let sign = if hash >> 63 == 1 { -1.0 } else { 1.0 };
+            vector[(hash % self.dimensions as u64) as usize] += sign;
+        }
+        normalize(&mut vector);
+        vector
+    }
+}
+
+#[async_trait]
+impl Embedder for HashingEmbedder {
+    fn id(&self) -> String {
+        format!("hashing-{}", self.dimensions)
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed_one(text)).collect())
+    }
+}
+
+/// Words too common in code or queries to say anything about what code does.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "be", "by", "class", "const", "def", "else", "fn", "for",
+    "from", "function", "if", "impl", "in", "into", "is", "it", "let", "mut", "new", "none",
+    "null", "of", "on", "or", "pub", "return", "self", "static", "str", "string", "struct",
+    "that", "the", "this", "to", "true", "false", "use", "var", "void", "with",
+];
+
+/// Lowercase words in `text`, with `camelCase` and `snake_case` identifiers
+/// split apart and plural and verb endings dropped.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .flat_map(split_identifier)
+        .map(|word| stem(&word.to_lowercase()))
+        .filter(|word| word.len() > 1 && !STOP_WORDS.contains(&word.as_str()))
+}
+
+fn split_identifier(token: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let chars: Vec<(usize, char)> = token.char_indices().collect();
+    for window in chars.windows(2) {
+        let ((_, prev), (i, c)) = (window[0], window[1]);
+        if (prev.is_lowercase() && c.is_uppercase()) || prev.is_alphabetic() != c.is_alphabetic() {
+            parts.push(&token[start..i]);
+            start = i;
+        }
+    }
+    parts.push(&token[start..]);
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+fn stem(word: &str) -> String {
+    for suffix in ["ing", "ed", "es", "s"] {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if stem.len() >= 3 && !stem.ends_with('s') {
+                return stem.to_string();
+            }
+        }
+    }
+    word.to_string()
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Embeddings from an OpenAI-compatible `/embeddings` endpoint, such as
+/// OpenAI's or Ollama's `/v1/embeddings`.
+pub struct ApiEmbedder {
+    client: reqwest::Client,
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: usize,
+}
+
+impl ApiEmbedder {
+    pub fn new(endpoint: &str, model: &str, api_key: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: endpoint.to_string(),
+            model: model.to_string(),
+            api_key,
+        }
+    }
+}
+
+#[async_trait]
+impl Embedder for ApiEmbedder {
+    fn id(&self) -> String {
+        self.model.clone()
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(EMBED_BATCH_SIZE) {
+            let mut request = self
+                .client
+                .post(&self.endpoint)
+                .json(&serde_json::json!({ "model": self.model, "input": batch }));
+            if let Some(key) = &self.api_key {
+                request = request.bearer_auth(key);
+            }
+            let response = request
+                .send()
+                .await
+                .with_context(|| format!("Failed to reach embedding endpoint {}", self.endpoint))?;
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                bail!("Embedding endpoint returned {}: {}", status, body.trim());
+            }
+            let mut response: EmbeddingResponse = response
+                .json()
+                .await
+                .context("Invalid response from embedding endpoint")?;
+            if response.data.len() != batch.len() {
+                bail!(
+                    "Embedding endpoint returned {} embeddings for {} inputs",
+                    response.data.len(),
+                    batch.len()
+                );
+            }
+            response.data.sort_by_key(|d| d.index);
+            embeddings.extend(response.data.into_iter().map(|d| d.embedding));
+        }
+        Ok(embeddings)
+    }
+}
+
+/// The embedder `config` asks for: its endpoint if one is set, otherwise
+/// [`HashingEmbedder`].
+pub fn embedder_from_config(config: &CodeSearchConfig) -> Result<Box<dyn Embedder>> {
+    let Some(endpoint) = &config.embedding_endpoint else {
+        return Ok(Box::new(HashingEmbedder::default()));
+    };
+    let model = config
+        .embedding_model
+        .as_deref()
+        .ok_or_else(|| anyhow!("code_search.embedding_model must be set with embedding_endpoint"))?;
+    let api_key = match &config.embedding_api_key_env {
+        Some(var) => Some(
+            std::env::var(var).with_context(|| format!("{} is not set", var))?,
+        ),
+        None => None,
+    };
+    Ok(Box::new(ApiEmbedder::new(endpoint, model, api_key)))
+}
+
+/// A function, class or other definition in the index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeChunk {
+    /// Path relative to the index root
+    pub file: String,
+    pub line: usize,
+    pub end_line: usize,
+    /// tree-sitter node kind, e.g. `function_item`
+    pub kind: String,
+    pub name: Option<String>,
+    /// First line of the definition
+    pub signature: String,
+    pub embedding: Vec<f32>,
+}
+
+/// A definition ranked by [`SemanticIndex::search`].
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticMatch {
+    pub file: String,
+    pub line: usize,
+    pub end_line: usize,
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub signature: String,
+    /// Cosine similarity to the query
+    pub score: f32,
+}
+
+/// Embedded definitions of a project, cached in `.g3/embeddings.bin`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticIndex {
+    /// [`Embedder::id`] of the model the chunks were embedded with
+    pub model: String,
+    /// Directory the index was built from
+    pub root: PathBuf,
+    pub created_at: String,
+    pub files_indexed: usize,
+    pub chunks: Vec<CodeChunk>,
+}
+
+impl SemanticIndex {
+    /// Extract and embed every definition in the supported source files under `root`.
+    pub async fn build(root: &Path, embedder: &dyn Embedder) -> Result<Self> {
+        let (definitions, files_indexed) = extract_definitions(root)?;
+        let texts: Vec<String> = definitions.iter().map(|d| d.text.clone()).collect();
+        let embeddings = embedder.embed(&texts).await?;
+        if embeddings.len() != definitions.len() {
+            bail!(
+                "Got {} embeddings for {} definitions",
+                embeddings.len(),
+                definitions.len()
+            );
+        }
+
+        let chunks = definitions
+            .into_iter()
+            .zip(embeddings)
+            .map(|(d, embedding)| CodeChunk {
+                file: d.file,
+                line: d.line,
+                end_line: d.end_line,
+                kind: d.kind,
+                name: d.name,
+                signature: d.signature,
+                embedding,
+            })
+            .collect();
+        Ok(Self {
+            model: embedder.id(),
+            root: root.to_path_buf(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            files_indexed,
+            chunks,
+        })
+    }
+
+    /// Write the index to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_vec(self)?;
+        let mut data = INDEX_MAGIC.to_vec();
+        data.extend(zstd::encode_all(json.as_slice(), 3)?);
+        std::fs::write(path, data).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Read the index at `path`, or `None` if there is none.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let compressed = data
+            .strip_prefix(INDEX_MAGIC)
+            .ok_or_else(|| anyhow!("{} is not a semantic index", path.display()))?;
+        let json = zstd::decode_all(compressed)?;
+        let index = serde_json::from_slice(&json)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Some(index))
+    }
+
+    /// The `top_k` definitions most similar to `query`, best first.
+    pub async fn search(
+        &self,
+        query: &str,
+        embedder: &dyn Embedder,
+        top_k: usize,
+    ) -> Result<Vec<SemanticMatch>> {
+        if embedder.id() != self.model {
+            bail!(
+                "The semantic index was built with '{}' but the configured model is '{}'; rebuild it with build_semantic_index",
+                self.model,
+                embedder.id()
+            );
+        }
+        let query = embedder
+            .embed(&[query.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("No embedding returned for the query"))?;
+
+        let mut matches: Vec<SemanticMatch> = self
+            .chunks
+            .iter()
+            .map(|chunk| SemanticMatch {
+                file: chunk.file.clone(),
+                line: chunk.line,
+                end_line: chunk.end_line,
+                kind: chunk.kind.clone(),
+                name: chunk.name.clone(),
+                signature: chunk.signature.clone(),
+                score: cosine_similarity(&query, &chunk.embedding),
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(top_k);
+        Ok(matches)
+    }
+}
+
+/// A definition found in a source file, before it is embedded.
+struct Definition {
+    file: String,
+    line: usize,
+    end_line: usize,
+    kind: String,
+    name: Option<String>,
+    signature: String,
+    /// What gets embedded: kind, name, path, leading comments and source
+    text: String,
+}
+
+/// Parser language and the node kinds indexed for a file, by extension.
+fn language_for(path: &Path) -> Option<(Language, &'static [&'static str])> {
+    let ext = path.extension()?.to_str()?;
+    Some(match ext {
+        "rs" => (
+            tree_sitter_rust::LANGUAGE.into(),
+            &["function_item", "struct_item", "enum_item", "trait_item"][..],
+        ),
+        "py" => (
+            tree_sitter_python::LANGUAGE.into(),
+            &["function_definition", "class_definition"][..],
+        ),
+        "js" | "jsx" | "mjs" => (
+            tree_sitter_javascript::LANGUAGE.into(),
+            &["function_declaration", "class_declaration", "method_definition"][..],
+        ),
+        "ts" | "tsx" => (
+            tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            &[
+                "function_declaration",
+                "class_declaration",
+                "method_definition",
+                "interface_declaration",
+            ][..],
+        ),
+        "go" => (
+            tree_sitter_go::LANGUAGE.into(),
+            &["function_declaration", "method_declaration", "type_spec"][..],
+        ),
+        "java" => (
+            tree_sitter_java::LANGUAGE.into(),
+            &[
+                "class_declaration",
+                "interface_declaration",
+                "method_declaration",
+                "constructor_declaration",
+            ][..],
+        ),
+        "c" | "h" => (tree_sitter_c::LANGUAGE.into(), &["function_definition"][..]),
+        "cpp" | "cc" | "cxx" | "hpp" | "hxx" => (
+            tree_sitter_cpp::LANGUAGE.into(),
+            &["function_definition", "class_specifier"][..],
+        ),
+        _ => return None,
+    })
+}
+
+/// Definitions in the source files under `root` (honouring `.gitignore`),
+/// with the number of files they came from.
+fn extract_definitions(root: &Path) -> Result<(Vec<Definition>, usize)> {
+    let mut definitions = Vec::new();
+    let mut files = 0;
+    for entry in ignore::WalkBuilder::new(root).build() {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Some((language, kinds)) = language_for(path) else {
+            continue;
+        };
+        if entry.metadata().map(|m| m.len() > MAX_FILE_BYTES).unwrap_or(true) {
+            continue;
+        }
+        let Ok(source) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        let mut parser = Parser::new();
+        parser
+            .set_language(&language)
+            .map_err(|e| anyhow!("Failed to set parser language: {}", e))?;
+        let Some(tree) = parser.parse(&source, None) else {
+            continue;
+        };
+
+        files += 1;
+        let relative = path.strip_prefix(root).unwrap_or(path).display().to_string();
+        collect_definitions(tree.root_node(), &source, &relative, kinds, &mut definitions);
+    }
+    Ok((definitions, files))
+}
+
+fn collect_definitions(
+    node: Node,
+    source: &str,
+    file: &str,
+    kinds: &[&str],
+    definitions: &mut Vec<Definition>,
+) {
+    if kinds.contains(&node.kind()) {
+        let body = &source[node.byte_range()];
+        let name = node
+            .child_by_field_name("name")
+            .map(|n| source[n.byte_range()].to_string());
+        let signature: String = body
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .chars()
+            .take(MAX_SIGNATURE_CHARS)
+            .collect();
+
+        // Doc comments directly above the definition say what it is for
+        let mut comments = Vec::new();
+        let mut sibling = node.prev_sibling();
+        while let Some(s) = sibling.filter(|s| s.kind().contains("comment")) {
+            comments.push(&source[s.byte_range()]);
+            sibling = s.prev_sibling();
+        }
+        comments.reverse();
+
+        let mut text = format!(
+            "{} {} in {}\n{}\n",
+            node.kind(),
+            name.as_deref().unwrap_or_default(),
+            file,
+            comments.join("\n")
+        );
+        text.extend(body.chars().take(MAX_CHUNK_CHARS));
+
+        definitions.push(Definition {
+            file: file.to_string(),
+            line: node.start_position().row + 1,
+            end_line: node.end_position().row + 1,
+            kind: node.kind().to_string(),
+            name,
+            signature,
+            text,
+        });
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_definitions(child, source, file, kinds, definitions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn project() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/net.rs"),
+            "/// Send an HTTP request and wait for the response.\nfn send_http_request(url: &str) -> Response {\n    todo!()\n}\n\nstruct RetryPolicy {\n    attempts: u32,\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("src/settings.rs"),
+            "fn parse_config_file(path: &Path) -> Config {\n    toml::from_str(&read(path))\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("db.py"),
+            "class DatabaseConnection:\n    def open_connection(self, dsn):\n        pass\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("README.md"), "# Not code\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_words_split_identifiers() {
+        let words: Vec<String> = words("fn parseConfigFile(http_requests: &str)").collect();
+        assert_eq!(words, vec!["parse", "config", "file", "http", "request"]);
+    }
+
+    #[tokio::test]
+    async fn test_build_save_and_load_index() {
+        let dir = project();
+        let embedder = HashingEmbedder::default();
+        let index = SemanticIndex::build(dir.path(), &embedder).await.unwrap();
+
+        assert_eq!(index.files_indexed, 3);
+        let mut names: Vec<&str> = index.chunks.iter().filter_map(|c| c.name.as_deref()).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "DatabaseConnection",
+                "RetryPolicy",
+                "open_connection",
+                "parse_config_file",
+                "send_http_request"
+            ]
+        );
+        let send = index.chunks.iter().find(|c| c.name.as_deref() == Some("send_http_request")).unwrap();
+        assert_eq!(send.file, "src/net.rs");
+        assert_eq!((send.line, send.end_line), (2, 4));
+        assert_eq!(send.signature, "fn send_http_request(url: &str) -> Response {");
+
+        let path = dir.path().join(".g3/embeddings.bin");
+        assert!(SemanticIndex::load(&path).unwrap().is_none());
+        index.save(&path).unwrap();
+        let loaded = SemanticIndex::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.model, "hashing-512");
+        assert_eq!(loaded.chunks.len(), 5);
+        assert_eq!(loaded.chunks[0].embedding, index.chunks[0].embedding);
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_related_definitions_first() {
+        let dir = project();
+        let embedder = HashingEmbedder::default();
+        let index = SemanticIndex::build(dir.path(), &embedder).await.unwrap();
+
+        let top = |query: &'static str| {
+            let index = index.clone();
+            let embedder = embedder.clone();
+            async move {
+                let matches = index.search(query, &embedder, 2).await.unwrap();
+                assert_eq!(matches.len(), 2);
+                assert!(matches[0].score >= matches[1].score);
+                matches[0].name.clone().unwrap()
+            }
+        };
+        assert_eq!(top("wait for an http response").await, "send_http_request");
+        assert_eq!(top("load settings from a config file").await, "parse_config_file");
+        assert_eq!(top("database connections").await, "DatabaseConnection");
+
+        // Queries must use the model the index was built with
+        let err = index.search("http", &HashingEmbedder::new(64), 2).await.unwrap_err();
+        assert!(err.to_string().contains("rebuild"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_api_embedder() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/v1/embeddings", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !String::from_utf8_lossy(&request).contains("\"input\"") {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            // Out of order, as the index field allows
+            let body = r#"{"data":[{"index":1,"embedding":[0.0,1.0]},{"index":0,"embedding":[1.0,0.0]}]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        let embedder = ApiEmbedder::new(&endpoint, "test-model", Some("secret".to_string()));
+        let embeddings = embedder
+            .embed(&["first".to_string(), "second".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(embeddings, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+
+        let request = server.await.unwrap().to_lowercase();
+        assert!(request.contains("authorization: bearer secret"), "{}", request);
+        assert!(request.contains("\"model\":\"test-model\""), "{}", request);
+    }
+}
@@ -27,6 +27,24 @@ pub struct TextLocation {
     pub confidence: f32,
 }
 
+/// A browser cookie as exposed to the agent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub domain: Option<String>,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub secure: bool,
+    #[serde(default)]
+    pub http_only: bool,
+    /// Expiry as a Unix timestamp in seconds; `None` for session cookies
+    #[serde(default)]
+    pub expiry: Option<i64>,
+}
+
 /// WebDriver controller trait
 #[async_trait]
 pub trait WebDriverController: Send + Sync + Sized {
@@ -50,6 +68,14 @@ pub trait WebDriverController: Send + Sync + Sized {
         anyhow::bail!("WebDriver not supported in headless environment")
     }
     
+    async fn find_element_xpath(&mut self, _xpath: &str) -> Result<WebElement> {
+        anyhow::bail!("WebDriver not supported in headless environment")
+    }
+    
+    async fn find_elements_xpath(&mut self, _xpath: &str) -> Result<Vec<WebElement>> {
+        anyhow::bail!("WebDriver not supported in headless environment")
+    }
+    
     async fn execute_script(&mut self, _script: &str, _args: Vec<serde_json::Value>) -> Result<serde_json::Value> {
         anyhow::bail!("WebDriver not supported in headless environment")
     }
@@ -82,6 +108,18 @@ pub trait WebDriverController: Send + Sync + Sized {
     async fn refresh(&mut self) -> Result<()> {
         anyhow::bail!("WebDriver not supported in headless environment")
     }
+    
+    async fn window_handles(&self) -> Result<Vec<String>> {
+        anyhow::bail!("WebDriver not supported in headless environment")
+    }
+    
+    async fn get_all_cookies(&mut self) -> Result<Vec<Cookie>> {
+        anyhow::bail!("WebDriver not supported in headless environment")
+    }
+    
+    async fn add_cookie(&mut self, _cookie: Cookie) -> Result<()> {
+        anyhow::bail!("WebDriver not supported in headless environment")
+    }
 }
 
 /// Represents a web element in the DOM
@@ -112,6 +150,10 @@ impl WebElement {
         anyhow::bail!("WebDriver not supported in headless environment")
     }
     
+    pub async fn tag_name(&self) -> Result<String> {
+        anyhow::bail!("WebDriver not supported in headless environment")
+    }
+    
     pub async fn screenshot(&self, _path: &str) -> Result<()> {
         anyhow::bail!("WebDriver not supported in headless environment")
     }
@@ -210,15 +252,72 @@ impl ChromeDriver {
 #[async_trait]
 impl WebDriverController for ChromeDriver {}
 
+/// Firefox WebDriver implementation
+pub struct FirefoxDriver;
+
+impl FirefoxDriver {
+    pub async fn with_port_headless(_port: u16) -> Result<Self> {
+        anyhow::bail!("Firefox WebDriver not supported in headless environment")
+    }
+    
+    pub async fn with_port_headless_and_binary(_port: u16, _binary: Option<&str>) -> Result<Self> {
+        anyhow::bail!("Firefox WebDriver not supported in headless environment")
+    }
+}
+
+#[async_trait]
+impl WebDriverController for FirefoxDriver {}
+
 /// macOS-specific types (stubs)
 pub struct AXApplication;
 pub struct AXElement;
 pub struct MacAxController;
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowInfo {
+    pub id: usize,
+    pub title: String,
+    pub minimized: bool,
+    pub bounds: Rect,
+}
+
 impl MacAxController {
     pub fn new() -> Result<Self> {
         anyhow::bail!("macOS accessibility not supported in headless environment")
     }
+
+    pub fn list_windows(&self, _app_name: &str) -> Result<Vec<WindowInfo>> {
+        anyhow::bail!("macOS accessibility not supported in headless environment")
+    }
+
+    pub fn focus_window(&self, _app_name: &str, _window_id: usize) -> Result<()> {
+        anyhow::bail!("macOS accessibility not supported in headless environment")
+    }
+
+    pub fn close_window(&self, _app_name: &str, _window_id: usize) -> Result<()> {
+        anyhow::bail!("macOS accessibility not supported in headless environment")
+    }
+
+    pub fn type_text_with_keys(&self, _app_name: &str, _text: &str) -> Result<()> {
+        anyhow::bail!("macOS accessibility not supported in headless environment")
+    }
+
+    pub fn drag_element(&self, _from_element: &AXElement, _to_element: &AXElement) -> Result<()> {
+        anyhow::bail!("macOS accessibility not supported in headless environment")
+    }
+
+    pub fn drag_element_to(&self, _from_element: &AXElement, _x: i32, _y: i32) -> Result<()> {
+        anyhow::bail!("macOS accessibility not supported in headless environment")
+    }
+
+    pub fn find_element(
+        &self,
+        _app_name: &str,
+        _role: &str,
+        _title: Option<&str>,
+    ) -> Result<Option<AXElement>> {
+        anyhow::bail!("macOS accessibility not supported in headless environment")
+    }
 }
 
 /// Platform-specific constructor - returns error for all platforms
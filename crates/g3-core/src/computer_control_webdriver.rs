@@ -7,6 +7,7 @@ use anyhow::{Context, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use fantoccini::{Client, ClientBuilder};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 // Re-export types that are used in the API
@@ -28,6 +29,56 @@ pub struct TextLocation {
     pub confidence: f32,
 }
 
+/// A browser cookie as exposed to the agent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub domain: Option<String>,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub secure: bool,
+    #[serde(default)]
+    pub http_only: bool,
+    /// Expiry as a Unix timestamp in seconds; `None` for session cookies
+    #[serde(default)]
+    pub expiry: Option<i64>,
+}
+
+impl Cookie {
+    fn from_webdriver(cookie: &fantoccini::cookies::Cookie<'_>) -> Self {
+        Self {
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+            domain: cookie.domain().map(String::from),
+            path: cookie.path().map(String::from),
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+            expiry: cookie.expires_datetime().map(|dt| dt.unix_timestamp()),
+        }
+    }
+
+    fn into_webdriver(self) -> Result<fantoccini::cookies::Cookie<'static>> {
+        let mut builder = fantoccini::cookies::Cookie::build((self.name, self.value))
+            .secure(self.secure)
+            .http_only(self.http_only);
+        if let Some(domain) = self.domain {
+            builder = builder.domain(domain);
+        }
+        if let Some(path) = self.path {
+            builder = builder.path(path);
+        }
+        if let Some(expiry) = self.expiry {
+            let expires = cookie::time::OffsetDateTime::from_unix_timestamp(expiry)
+                .context("Invalid cookie expiry timestamp")?;
+            builder = builder.expires(expires);
+        }
+        Ok(builder.build())
+    }
+}
+
 /// WebDriver controller trait
 #[async_trait]
 pub trait WebDriverController: Send + Sync + Sized {
@@ -42,6 +93,12 @@ pub trait WebDriverController: Send + Sync + Sized {
     
     async fn find_elements(&mut self, selector: &str) -> Result<Vec<WebElement>>;
     
+    /// Find the first element matching an XPath expression
+    async fn find_element_xpath(&mut self, xpath: &str) -> Result<WebElement>;
+    
+    /// Find every element matching an XPath expression
+    async fn find_elements_xpath(&mut self, xpath: &str) -> Result<Vec<WebElement>>;
+    
     async fn execute_script(&mut self, script: &str, args: Vec<serde_json::Value>) -> Result<serde_json::Value>;
     
     async fn page_source(&self) -> Result<String>;
@@ -58,6 +115,18 @@ pub trait WebDriverController: Send + Sync + Sized {
     async fn forward(&mut self) -> Result<()>;
     
     async fn refresh(&mut self) -> Result<()>;
+    
+    async fn window_handles(&self) -> Result<Vec<String>>;
+    
+    /// Get all cookies visible to the current page
+    async fn get_all_cookies(&mut self) -> Result<Vec<Cookie>>;
+    
+    /// Add a cookie for the current domain
+    async fn add_cookie(&mut self, cookie: Cookie) -> Result<()>;
+    
+    /// Switch into the frame at `index` of the current browsing context,
+    /// or back to the top-level page when `index` is `None`
+    async fn switch_to_frame(&mut self, index: Option<u16>) -> Result<()>;
 }
 
 /// Represents a web element in the DOM
@@ -93,11 +162,22 @@ impl WebElement {
         Ok(self.inner.attr(name).await?)
     }
     
+    /// Lowercase tag name, e.g. `div`
+    pub async fn tag_name(&self) -> Result<String> {
+        Ok(self.inner.tag_name().await?.to_lowercase())
+    }
+    
     pub async fn screenshot(&self, path: &str) -> Result<()> {
         let png_data = self.inner.screenshot().await?;
         std::fs::write(path, png_data)?;
         Ok(())
     }
+    
+    /// Switch into the frame this `<iframe>` or `<frame>` element contains
+    pub async fn enter_frame(&self) -> Result<()> {
+        self.inner.enter_frame().await?;
+        Ok(())
+    }
 }
 
 /// Computer controller trait - stubbed for headless environment
@@ -141,6 +221,47 @@ pub trait ComputerController: Send + Sync {
     }
 }
 
+/// Known Chrome/Chromium install locations, checked in order.
+const CHROME_BINARY_CANDIDATES: &[&str] = &[
+    "/usr/bin/google-chrome",
+    "/usr/bin/google-chrome-stable",
+    "/usr/bin/chromium",
+    "/usr/bin/chromium-browser",
+    "/snap/bin/chromium",
+    "/opt/google/chrome/chrome",
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+];
+
+/// Find a Chrome or Chromium binary in one of the usual install locations.
+///
+/// ChromeDriver's own detection only looks at PATH, which misses snap
+/// installs and distributions that name the binary differently.
+pub fn find_chrome_binary() -> Option<PathBuf> {
+    find_binary_in(CHROME_BINARY_CANDIDATES.iter().map(Path::new))
+}
+
+/// First of `candidates` that is an executable file.
+fn find_binary_in<'a>(candidates: impl IntoIterator<Item = &'a Path>) -> Option<PathBuf> {
+    candidates
+        .into_iter()
+        .find(|path| is_executable(path))
+        .map(Path::to_path_buf)
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
 /// Chrome WebDriver implementation with headless support
 pub struct ChromeDriver {
     client: Client,
@@ -151,6 +272,8 @@ impl ChromeDriver {
         Self::with_port_headless_and_binary(port, None).await
     }
     
+    /// Without a binary path, Chrome is looked up with [`find_chrome_binary`],
+    /// falling back to ChromeDriver's own detection if none is found.
     pub async fn with_port_headless_and_binary(port: u16, chrome_binary: Option<&str>) -> Result<Self> {
         let url = format!("http://localhost:{}", port);
 
@@ -175,9 +298,13 @@ impl ChromeDriver {
             ]),
         );
 
-        // If a custom Chrome binary is specified, use it
-        if let Some(binary) = chrome_binary {
-            chrome_options.insert("binary".to_string(), serde_json::Value::String(binary.to_string()));
+        // Use the given Chrome binary, or one from a known install location
+        let binary = chrome_binary.map(PathBuf::from).or_else(find_chrome_binary);
+        if let Some(binary) = binary {
+            chrome_options.insert(
+                "binary".to_string(),
+                serde_json::Value::String(binary.to_string_lossy().into_owned()),
+            );
         }
 
         caps.insert(
@@ -240,6 +367,172 @@ impl WebDriverController for ChromeDriver {
         Ok(elems.into_iter().map(|inner| WebElement { inner }).collect())
     }
     
+    async fn find_element_xpath(&mut self, xpath: &str) -> Result<WebElement> {
+        let elem = self.client.find(fantoccini::Locator::XPath(xpath)).await?;
+        Ok(WebElement { inner: elem })
+    }
+    
+    async fn find_elements_xpath(&mut self, xpath: &str) -> Result<Vec<WebElement>> {
+        let elems = self.client.find_all(fantoccini::Locator::XPath(xpath)).await?;
+        Ok(elems.into_iter().map(|inner| WebElement { inner }).collect())
+    }
+    
+    async fn execute_script(&mut self, script: &str, args: Vec<serde_json::Value>) -> Result<serde_json::Value> {
+        Ok(self.client.execute(script, args).await?)
+    }
+    
+    async fn page_source(&self) -> Result<String> {
+        Ok(self.client.source().await?)
+    }
+    
+    async fn screenshot(&mut self, path: &str) -> Result<()> {
+        let png_data = self.client.screenshot().await?;
+        std::fs::write(path, png_data)?;
+        Ok(())
+    }
+    
+    async fn close(&mut self) -> Result<()> {
+        self.client.close_window().await?;
+        Ok(())
+    }
+    
+    async fn quit(mut self) -> Result<()> {
+        self.client.close().await?;
+        Ok(())
+    }
+    
+    async fn back(&mut self) -> Result<()> {
+        self.client.back().await?;
+        Ok(())
+    }
+    
+    async fn forward(&mut self) -> Result<()> {
+        self.client.forward().await?;
+        Ok(())
+    }
+    
+    async fn refresh(&mut self) -> Result<()> {
+        self.client.refresh().await?;
+        Ok(())
+    }
+    
+    async fn window_handles(&self) -> Result<Vec<String>> {
+        let handles = self.client.windows().await?;
+        Ok(handles.into_iter().map(String::from).collect())
+    }
+    
+    async fn get_all_cookies(&mut self) -> Result<Vec<Cookie>> {
+        let cookies = self.client.get_all_cookies().await?;
+        Ok(cookies.iter().map(Cookie::from_webdriver).collect())
+    }
+    
+    async fn add_cookie(&mut self, cookie: Cookie) -> Result<()> {
+        self.client.add_cookie(cookie.into_webdriver()?).await?;
+        Ok(())
+    }
+    
+    async fn switch_to_frame(&mut self, index: Option<u16>) -> Result<()> {
+        self.client.enter_frame(index).await?;
+        Ok(())
+    }
+}
+
+/// Firefox WebDriver implementation (via GeckoDriver) with headless support
+pub struct FirefoxDriver {
+    client: Client,
+}
+
+impl FirefoxDriver {
+    pub async fn with_port_headless(port: u16) -> Result<Self> {
+        Self::with_port_headless_and_binary(port, None).await
+    }
+
+    pub async fn with_port_headless_and_binary(port: u16, firefox_binary: Option<&str>) -> Result<Self> {
+        let url = format!("http://localhost:{}", port);
+
+        // Use a unique profile directory to avoid conflicts with running Firefox instances
+        let profile_dir = format!("/tmp/g3-firefox-{}", std::process::id());
+        std::fs::create_dir_all(&profile_dir)
+            .with_context(|| format!("Failed to create Firefox profile directory {}", profile_dir))?;
+
+        let mut caps = serde_json::Map::new();
+        caps.insert(
+            "browserName".to_string(),
+            serde_json::Value::String("firefox".to_string()),
+        );
+
+        // Set up Firefox options for headless mode
+        let mut firefox_options = serde_json::Map::new();
+        firefox_options.insert(
+            "args".to_string(),
+            serde_json::Value::Array(vec![
+                serde_json::Value::String("--headless".to_string()),
+                serde_json::Value::String("--window-size=1920,1080".to_string()),
+                serde_json::Value::String("-profile".to_string()),
+                serde_json::Value::String(profile_dir),
+            ]),
+        );
+
+        // If a custom Firefox binary is specified, use it
+        if let Some(binary) = firefox_binary {
+            firefox_options.insert("binary".to_string(), serde_json::Value::String(binary.to_string()));
+        }
+
+        caps.insert(
+            "moz:firefoxOptions".to_string(),
+            serde_json::Value::Object(firefox_options),
+        );
+
+        // Use a timeout for the connection attempt to avoid hanging indefinitely
+        let mut builder = ClientBuilder::native();
+        let connect_future = builder
+            .capabilities(caps)
+            .connect(&url);
+
+        let client = tokio::time::timeout(Duration::from_secs(30), connect_future)
+            .await
+            .context("Connection to GeckoDriver timed out after 30 seconds")?
+            .context("Failed to connect to GeckoDriver")?;
+
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl WebDriverController for FirefoxDriver {
+    async fn navigate(&mut self, url: &str) -> Result<()> {
+        self.client.goto(url).await?;
+        Ok(())
+    }
+    
+    async fn current_url(&self) -> Result<String> {
+        Ok(self.client.current_url().await?.to_string())
+    }
+    
+    async fn title(&self) -> Result<String> {
+        Ok(self.client.title().await?)
+    }
+    
+    async fn find_element(&mut self, selector: &str) -> Result<WebElement> {
+        let elem = self.client.find(fantoccini::Locator::Css(selector)).await?;
+        Ok(WebElement { inner: elem })
+    }
+    
+    async fn find_elements(&mut self, selector: &str) -> Result<Vec<WebElement>> {
+        let elems = self.client.find_all(fantoccini::Locator::Css(selector)).await?;
+        Ok(elems.into_iter().map(|inner| WebElement { inner }).collect())
+    }
+    
+    async fn find_element_xpath(&mut self, xpath: &str) -> Result<WebElement> {
+        let elem = self.client.find(fantoccini::Locator::XPath(xpath)).await?;
+        Ok(WebElement { inner: elem })
+    }
+    
+    async fn find_elements_xpath(&mut self, xpath: &str) -> Result<Vec<WebElement>> {
+        let elems = self.client.find_all(fantoccini::Locator::XPath(xpath)).await?;
+        Ok(elems.into_iter().map(|inner| WebElement { inner }).collect())
+    }
+    
     async fn execute_script(&mut self, script: &str, args: Vec<serde_json::Value>) -> Result<serde_json::Value> {
         Ok(self.client.execute(script, args).await?)
     }
@@ -278,6 +571,26 @@ impl WebDriverController for ChromeDriver {
         self.client.refresh().await?;
         Ok(())
     }
+    
+    async fn window_handles(&self) -> Result<Vec<String>> {
+        let handles = self.client.windows().await?;
+        Ok(handles.into_iter().map(String::from).collect())
+    }
+    
+    async fn get_all_cookies(&mut self) -> Result<Vec<Cookie>> {
+        let cookies = self.client.get_all_cookies().await?;
+        Ok(cookies.iter().map(Cookie::from_webdriver).collect())
+    }
+    
+    async fn add_cookie(&mut self, cookie: Cookie) -> Result<()> {
+        self.client.add_cookie(cookie.into_webdriver()?).await?;
+        Ok(())
+    }
+    
+    async fn switch_to_frame(&mut self, index: Option<u16>) -> Result<()> {
+        self.client.enter_frame(index).await?;
+        Ok(())
+    }
 }
 
 /// Safari WebDriver implementation - stubbed for Linux
@@ -323,6 +636,14 @@ impl WebDriverController for SafariDriver {
         anyhow::bail!("Safari WebDriver is not available on Linux. Use Chrome WebDriver instead.")
     }
     
+    async fn find_element_xpath(&mut self, _xpath: &str) -> Result<WebElement> {
+        anyhow::bail!("Safari WebDriver is not available on Linux. Use Chrome WebDriver instead.")
+    }
+    
+    async fn find_elements_xpath(&mut self, _xpath: &str) -> Result<Vec<WebElement>> {
+        anyhow::bail!("Safari WebDriver is not available on Linux. Use Chrome WebDriver instead.")
+    }
+    
     async fn execute_script(&mut self, _script: &str, _args: Vec<serde_json::Value>) -> Result<serde_json::Value> {
         anyhow::bail!("Safari WebDriver is not available on Linux. Use Chrome WebDriver instead.")
     }
@@ -354,6 +675,22 @@ impl WebDriverController for SafariDriver {
     async fn refresh(&mut self) -> Result<()> {
         anyhow::bail!("Safari WebDriver is not available on Linux. Use Chrome WebDriver instead.")
     }
+    
+    async fn window_handles(&self) -> Result<Vec<String>> {
+        anyhow::bail!("Safari WebDriver is not available on Linux. Use Chrome WebDriver instead.")
+    }
+    
+    async fn get_all_cookies(&mut self) -> Result<Vec<Cookie>> {
+        anyhow::bail!("Safari WebDriver is not available on Linux. Use Chrome WebDriver instead.")
+    }
+    
+    async fn add_cookie(&mut self, _cookie: Cookie) -> Result<()> {
+        anyhow::bail!("Safari WebDriver is not available on Linux. Use Chrome WebDriver instead.")
+    }
+    
+    async fn switch_to_frame(&mut self, _index: Option<u16>) -> Result<()> {
+        anyhow::bail!("Safari WebDriver is not available on Linux. Use Chrome WebDriver instead.")
+    }
 }
 
 /// macOS-specific types (stubs)
@@ -375,4 +712,54 @@ pub fn create_controller() -> Result<Box<dyn ComputerController>> {
 /// WebDriver types for convenience
 pub mod types {
     pub use super::{Rect, TextLocation};
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_executable(path: &Path) {
+        std::fs::write(path, "#!/bin/sh\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_find_binary_none_when_missing() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let candidates = [
+            temp_dir.path().join("chromium"),
+            temp_dir.path().join("google-chrome"),
+        ];
+        assert_eq!(
+            find_binary_in(candidates.iter().map(PathBuf::as_path)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_binary_returns_first_existing() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let chromium = temp_dir.path().join("chromium");
+        let chrome = temp_dir.path().join("google-chrome");
+        make_executable(&chromium);
+        make_executable(&chrome);
+
+        let candidates = [temp_dir.path().join("missing"), chromium.clone(), chrome];
+        assert_eq!(
+            find_binary_in(candidates.iter().map(PathBuf::as_path)),
+            Some(chromium)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_binary_skips_non_executable() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let chromium = temp_dir.path().join("chromium");
+        std::fs::write(&chromium, "not a binary").unwrap();
+        assert_eq!(find_binary_in([chromium.as_path()]), None);
+    }
+}
@@ -179,6 +179,11 @@ impl ContextWindow {
         self.last_thinning_percentage = 0;
     }
 
+    /// Check whether a request of `estimated_tokens` would not fit in the window
+    pub fn would_exceed(&self, estimated_tokens: usize) -> bool {
+        estimated_tokens > self.total_tokens as usize
+    }
+
     pub fn remaining_tokens(&self) -> u32 {
         self.total_tokens.saturating_sub(self.used_tokens)
     }
@@ -743,6 +748,13 @@ mod tests {
         assert_eq!(cw.remaining_tokens(), 70);
     }
 
+    #[test]
+    fn test_would_exceed() {
+        let cw = ContextWindow::new(100);
+        assert!(!cw.would_exceed(100));
+        assert!(cw.would_exceed(101));
+    }
+
     #[test]
     fn test_should_compact_at_80_percent() {
         let mut cw = ContextWindow::new(100);
@@ -0,0 +1,670 @@
+//! Import graphs of Rust and Python projects.
+//!
+//! Rust `use` declarations and Python `import` / `from X import Y` statements
+//! are parsed with tree-sitter and resolved to files of the project, giving an
+//! edge `a -> b` whenever `a` imports something from `b`. Imports that don't
+//! resolve to a project file (the standard library, external crates and
+//! packages) are left out. Rust `mod` declarations are not edges.
+
+use anyhow::{anyhow, bail, Result};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use tree_sitter::{Language, Node, Parser};
+
+/// Directed graph of project files, keyed by path relative to the project
+/// root with `/` separators.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    /// Every file, with the files it imports
+    pub edges: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl DependencyGraph {
+    pub fn file_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.values().map(BTreeSet::len).sum()
+    }
+
+    /// Only `entry` and the files reachable from it, at most `depth` imports
+    /// away (unlimited when `None`).
+    pub fn reachable_from(&self, entry: &str, depth: Option<usize>) -> DependencyGraph {
+        let mut hops: BTreeMap<&str, usize> = BTreeMap::from([(entry, 0)]);
+        let mut queue = VecDeque::from([entry]);
+        while let Some(file) = queue.pop_front() {
+            let hop = hops[file];
+            if depth.is_some_and(|depth| hop >= depth) {
+                continue;
+            }
+            for dependency in self.edges.get(file).into_iter().flatten() {
+                if !hops.contains_key(dependency.as_str()) {
+                    hops.insert(dependency, hop + 1);
+                    queue.push_back(dependency);
+                }
+            }
+        }
+
+        let edges = hops
+            .iter()
+            .map(|(file, hop)| {
+                let dependencies = if depth.is_some_and(|depth| *hop >= depth) {
+                    BTreeSet::new()
+                } else {
+                    self.edges.get(*file).cloned().unwrap_or_default()
+                };
+                (file.to_string(), dependencies)
+            })
+            .collect();
+        DependencyGraph { edges }
+    }
+
+    /// One shortest cycle per group of mutually dependent files, each listed
+    /// from its alphabetically first file and ending where it started.
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles = Vec::new();
+        let mut seen = BTreeSet::new();
+        for start in self.edges.keys() {
+            if seen.contains(start) {
+                continue;
+            }
+            if let Some(cycle) = self.shortest_cycle(start) {
+                seen.extend(cycle.iter().cloned());
+                cycles.push(cycle);
+            }
+        }
+        cycles
+    }
+
+    /// Shortest path from `start` back to itself, breadth first.
+    fn shortest_cycle(&self, start: &str) -> Option<Vec<String>> {
+        let mut previous: HashMap<&str, &str> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(file) = queue.pop_front() {
+            for dependency in self.edges.get(file).into_iter().flatten() {
+                if dependency == start {
+                    let mut cycle = vec![start.to_string()];
+                    let mut current = file;
+                    while current != start {
+                        cycle.push(current.to_string());
+                        current = previous[current];
+                    }
+                    cycle[1..].reverse();
+                    cycle.push(start.to_string());
+                    return Some(cycle);
+                }
+                if !previous.contains_key(dependency.as_str()) {
+                    previous.insert(dependency, file);
+                    queue.push_back(dependency);
+                }
+            }
+        }
+        None
+    }
+
+    /// Graphviz DOT, with the edges of circular dependencies in red.
+    pub fn to_dot(&self) -> String {
+        let cycles = self.cycles();
+        let cyclic: BTreeSet<(&str, &str)> = cycles
+            .iter()
+            .flat_map(|cycle| {
+                cycle
+                    .windows(2)
+                    .map(|pair| (pair[0].as_str(), pair[1].as_str()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let mut dot = String::from("digraph dependencies {\n    rankdir=LR;\n    node [shape=box];\n");
+        for file in self.edges.keys() {
+            dot.push_str(&format!("    {:?};\n", file));
+        }
+        for (file, dependencies) in &self.edges {
+            for dependency in dependencies {
+                let style = if cyclic.contains(&(file.as_str(), dependency.as_str())) {
+                    " [color=red]"
+                } else {
+                    ""
+                };
+                dot.push_str(&format!("    {:?} -> {:?}{};\n", file, dependency, style));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// `{"graph": {file: [dependencies]}, "cycles": [[a, b, a]]}`.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "graph": self.edges,
+            "cycles": self.cycles(),
+        })
+    }
+
+    /// One `file -> dependency, ...` line per file, sorted, then the cycles.
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!(
+            "{} files, {} dependencies",
+            self.file_count(),
+            self.edge_count()
+        )];
+        for (file, dependencies) in &self.edges {
+            if dependencies.is_empty() {
+                lines.push(format!("{} -> (none)", file));
+            } else {
+                let dependencies: Vec<&str> = dependencies.iter().map(String::as_str).collect();
+                lines.push(format!("{} -> {}", file, dependencies.join(", ")));
+            }
+        }
+        lines.push(format_cycles(&self.cycles()));
+        lines.join("\n")
+    }
+}
+
+/// `✅ No circular dependencies` or one `a -> b -> a` line per cycle.
+pub fn format_cycles(cycles: &[Vec<String>]) -> String {
+    if cycles.is_empty() {
+        return "✅ No circular dependencies".to_string();
+    }
+    let mut out = format!("⚠️ {} circular dependencies:", cycles.len());
+    for cycle in cycles {
+        out.push_str(&format!("\n  {}", cycle.join(" -> ")));
+    }
+    out
+}
+
+/// Build the import graph of the `language` files under `root`. Files
+/// ignored by `.gitignore` and hidden files are skipped.
+pub fn build_graph(root: &Path, language: &str) -> Result<DependencyGraph> {
+    match language.to_lowercase().as_str() {
+        "rust" | "rs" => RustProject::scan(root)?.graph(root),
+        "python" | "py" => PythonProject::scan(root)?.graph(root),
+        other => bail!("Unsupported language '{}': expected rust or python", other),
+    }
+}
+
+/// The project files with extension `extension` under `root`, sorted.
+fn project_files(root: &Path, extension: &str) -> Result<Vec<PathBuf>> {
+    let walker = ignore::WalkBuilder::new(root)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = entry?;
+        if entry.file_type().is_some_and(|t| t.is_file())
+            && entry.path().extension().is_some_and(|ext| ext == extension)
+        {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+/// `path` relative to `root`, with `/` separators.
+fn display_path(path: &Path, root: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn parse(source: &str, language: Language) -> Result<tree_sitter::Tree> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language)
+        .map_err(|e| anyhow!("Failed to set language: {}", e))?;
+    parser
+        .parse(source, None)
+        .ok_or_else(|| anyhow!("Failed to parse source"))
+}
+
+/// Every node of `tree` of one of `kinds`, in document order.
+fn nodes_of_kind<'t>(tree: &'t tree_sitter::Tree, kinds: &[&str]) -> Vec<Node<'t>> {
+    let mut nodes = Vec::new();
+    let mut cursor = tree.walk();
+    'walk: loop {
+        let node = cursor.node();
+        if kinds.contains(&node.kind()) {
+            nodes.push(node);
+        } else if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+    nodes
+}
+
+fn text<'s>(node: Node, source: &'s str) -> &'s str {
+    &source[node.byte_range()]
+}
+
+/// A Rust source file and the module it defines.
+struct RustModule {
+    file: PathBuf,
+    /// `src` directory of the crate the file belongs to
+    crate_dir: PathBuf,
+    /// Module path within the crate, empty for `lib.rs` and `main.rs`
+    path: Vec<String>,
+}
+
+/// Rust files of a project (one crate or a workspace) and where their
+/// modules live.
+struct RustProject {
+    files: Vec<RustModule>,
+    modules: HashMap<(PathBuf, Vec<String>), PathBuf>,
+    /// Crate names as used in paths (`-` replaced with `_`) to `src` directories
+    crates: HashMap<String, PathBuf>,
+}
+
+impl RustProject {
+    fn scan(root: &Path) -> Result<Self> {
+        let mut project = RustProject {
+            files: Vec::new(),
+            modules: HashMap::new(),
+            crates: HashMap::new(),
+        };
+        for file in project_files(root, "rs")? {
+            let crate_dir = file
+                .ancestors()
+                .skip(1)
+                .take_while(|dir| dir.starts_with(root))
+                .find(|dir| {
+                    dir.file_name().is_some_and(|name| name == "src")
+                        && dir.parent().is_some_and(|p| p.join("Cargo.toml").is_file())
+                })
+                .unwrap_or(root)
+                .to_path_buf();
+            let path = Self::module_path(&file, &crate_dir);
+            if let Some(name) = crate_dir.parent().and_then(Self::crate_name) {
+                project.crates.entry(name).or_insert_with(|| crate_dir.clone());
+            }
+            // lib.rs wins over main.rs as the crate root
+            let key = (crate_dir.clone(), path.clone());
+            if !project.modules.contains_key(&key) || file.ends_with("lib.rs") {
+                project.modules.insert(key, file.clone());
+            }
+            project.files.push(RustModule { file, crate_dir, path });
+        }
+        Ok(project)
+    }
+
+    /// `a/b.rs` and `a/b/mod.rs` are module `a::b`; `lib.rs` and `main.rs` the crate root.
+    fn module_path(file: &Path, crate_dir: &Path) -> Vec<String> {
+        let relative = file.strip_prefix(crate_dir).unwrap_or(file).with_extension("");
+        let mut path: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        if path.last().is_some_and(|last| last == "mod")
+            || (path.len() == 1 && (path[0] == "lib" || path[0] == "main"))
+        {
+            path.pop();
+        }
+        path
+    }
+
+    /// The `[package] name` of the crate at `dir`, as written in paths.
+    fn crate_name(dir: &Path) -> Option<String> {
+        let manifest = std::fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+        let package = manifest.split("[package]").nth(1)?;
+        let package = package.split("\n[").next()?;
+        package.lines().find_map(|line| {
+            let value = line.trim().strip_prefix("name")?.trim().strip_prefix('=')?;
+            Some(value.trim().trim_matches('"').replace('-', "_"))
+        })
+    }
+
+    fn graph(&self, root: &Path) -> Result<DependencyGraph> {
+        let mut graph = DependencyGraph::default();
+        for module in &self.files {
+            let from = display_path(&module.file, root);
+            let mut dependencies = BTreeSet::new();
+            if let Ok(source) = std::fs::read_to_string(&module.file) {
+                let tree = parse(&source, tree_sitter_rust::LANGUAGE.into())?;
+                for declaration in nodes_of_kind(&tree, &["use_declaration"]) {
+                    let Some(argument) = declaration.child_by_field_name("argument") else {
+                        continue;
+                    };
+                    let mut paths = Vec::new();
+                    Self::use_paths(argument, &source, &[], &mut paths);
+                    for path in paths {
+                        if let Some(target) = self.resolve(module, &path) {
+                            if target != &module.file {
+                                dependencies.insert(display_path(target, root));
+                            }
+                        }
+                    }
+                }
+            }
+            graph.edges.insert(from, dependencies);
+        }
+        Ok(graph)
+    }
+
+    /// Segments of a path such as `crate::a::b`.
+    fn path_segments(node: Node, source: &str) -> Vec<String> {
+        if node.kind() == "scoped_identifier" {
+            let mut segments = node
+                .child_by_field_name("path")
+                .map(|path| Self::path_segments(path, source))
+                .unwrap_or_default();
+            if let Some(name) = node.child_by_field_name("name") {
+                segments.push(text(name, source).to_string());
+            }
+            return segments;
+        }
+        text(node, source)
+            .split("::")
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+
+    /// Expand a use tree such as `crate::a::{b, c::d as e}` into full paths.
+    fn use_paths(node: Node, source: &str, prefix: &[String], paths: &mut Vec<Vec<String>>) {
+        let joined = |rest: Vec<String>| [prefix, &rest].concat();
+        match node.kind() {
+            "use_as_clause" => {
+                if let Some(path) = node.child_by_field_name("path") {
+                    Self::use_paths(path, source, prefix, paths);
+                }
+            }
+            "use_list" => {
+                let mut cursor = node.walk();
+                for child in node.named_children(&mut cursor) {
+                    Self::use_paths(child, source, prefix, paths);
+                }
+            }
+            "scoped_use_list" => {
+                let prefix = match node.child_by_field_name("path") {
+                    Some(path) => joined(Self::path_segments(path, source)),
+                    None => prefix.to_vec(),
+                };
+                if let Some(list) = node.child_by_field_name("list") {
+                    Self::use_paths(list, source, &prefix, paths);
+                }
+            }
+            "use_wildcard" => match node.named_child(0) {
+                Some(path) => paths.push(joined(Self::path_segments(path, source))),
+                None => paths.push(prefix.to_vec()),
+            },
+            // `use a::{self}` names `a` itself
+            "self" if !prefix.is_empty() => paths.push(prefix.to_vec()),
+            _ => paths.push(joined(Self::path_segments(node, source))),
+        }
+    }
+
+    /// The file defining the module `path` imports from, if it is in the project.
+    fn resolve(&self, module: &RustModule, path: &[String]) -> Option<&PathBuf> {
+        let (first, rest) = path.split_first()?;
+        let (crate_dir, base, explicit) = match first.as_str() {
+            "crate" => (&module.crate_dir, Vec::new(), true),
+            "self" => (&module.crate_dir, module.path.clone(), true),
+            "super" => {
+                let mut base = module.path.clone();
+                base.pop();
+                let mut rest = rest;
+                while rest.first().is_some_and(|s| s == "super") {
+                    base.pop();
+                    rest = &rest[1..];
+                }
+                return self.longest_module(&module.crate_dir, &[base.as_slice(), rest].concat(), base.len());
+            }
+            name => match self.crates.get(name) {
+                // Also how main.rs imports from its own lib.rs
+                Some(crate_dir) => (crate_dir, Vec::new(), true),
+                None => (&module.crate_dir, module.path.clone(), false),
+            },
+        };
+
+        if explicit {
+            let full = [base.as_slice(), rest].concat();
+            self.longest_module(crate_dir, &full, base.len())
+        } else {
+            // A path relative to the current module must name one of its submodules
+            let full = [base.as_slice(), path].concat();
+            self.longest_module(crate_dir, &full, base.len() + 1)
+        }
+    }
+
+    /// The module named by the longest prefix of `path`, at least `min_len` long.
+    fn longest_module(&self, crate_dir: &Path, path: &[String], min_len: usize) -> Option<&PathBuf> {
+        (min_len..=path.len())
+            .rev()
+            .find_map(|len| self.modules.get(&(crate_dir.to_path_buf(), path[..len].to_vec())))
+    }
+}
+
+/// Python files of a project and their module names.
+struct PythonProject {
+    /// Each file with its module name
+    files: Vec<(PathBuf, String)>,
+    modules: HashMap<String, PathBuf>,
+}
+
+impl PythonProject {
+    fn scan(root: &Path) -> Result<Self> {
+        let mut project = PythonProject {
+            files: Vec::new(),
+            modules: HashMap::new(),
+        };
+        let src = root.join("src");
+        for file in project_files(root, "py")? {
+            // With a src layout, modules are named from src/
+            let base = if file.starts_with(&src) { &src } else { root };
+            let name = Self::module_name(&file, base);
+            project.modules.entry(name.clone()).or_insert_with(|| file.clone());
+            project.files.push((file, name));
+        }
+        Ok(project)
+    }
+
+    /// `a/b.py` is module `a.b` and `a/__init__.py` package `a`.
+    fn module_name(file: &Path, base: &Path) -> String {
+        let relative = file.strip_prefix(base).unwrap_or(file).with_extension("");
+        let mut parts: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        if parts.last().is_some_and(|last| last == "__init__") {
+            parts.pop();
+        }
+        parts.join(".")
+    }
+
+    fn graph(&self, root: &Path) -> Result<DependencyGraph> {
+        let mut graph = DependencyGraph::default();
+        for (file, name) in &self.files {
+            let mut dependencies = BTreeSet::new();
+            if let Ok(source) = std::fs::read_to_string(file) {
+                let tree = parse(&source, tree_sitter_python::LANGUAGE.into())?;
+                let package = if file.ends_with("__init__.py") {
+                    name.clone()
+                } else {
+                    name.rsplit_once('.').map(|(p, _)| p.to_string()).unwrap_or_default()
+                };
+                for statement in nodes_of_kind(&tree, &["import_statement", "import_from_statement"]) {
+                    for target in self.imported_modules(statement, &source, &package) {
+                        if let Some(target) = self.longest_module(&target) {
+                            if target != file {
+                                dependencies.insert(display_path(target, root));
+                            }
+                        }
+                    }
+                }
+            }
+            graph.edges.insert(display_path(file, root), dependencies);
+        }
+        Ok(graph)
+    }
+
+    /// Module names an import statement imports from. `package` is the
+    /// package of the importing file.
+    fn imported_modules(&self, statement: Node, source: &str, package: &str) -> Vec<String> {
+        let mut cursor = statement.walk();
+        let names: Vec<String> = statement
+            .children_by_field_name("name", &mut cursor)
+            .map(|name| {
+                let name = match name.kind() {
+                    "aliased_import" => name.child_by_field_name("name").unwrap_or(name),
+                    _ => name,
+                };
+                text(name, source).to_string()
+            })
+            .collect();
+
+        if statement.kind() == "import_statement" {
+            return names;
+        }
+        let Some(module) = statement.child_by_field_name("module_name") else {
+            return Vec::new();
+        };
+        let module = match module.kind() {
+            "relative_import" => {
+                let module_text = text(module, source);
+                let dots = module_text.chars().take_while(|c| *c == '.').count();
+                let mut parts: Vec<&str> = package.split('.').filter(|p| !p.is_empty()).collect();
+                for _ in 1..dots {
+                    parts.pop();
+                }
+                let rest = module_text[dots..].trim();
+                if !rest.is_empty() {
+                    parts.push(rest);
+                }
+                parts.join(".")
+            }
+            _ => text(module, source).to_string(),
+        };
+
+        // `from a import *`
+        if names.is_empty() {
+            return vec![module];
+        }
+        // `from a import b` imports submodule a.b if there is one, else from a
+        names
+            .iter()
+            .map(|name| {
+                let submodule = match module.is_empty() {
+                    true => name.clone(),
+                    false => format!("{}.{}", module, name),
+                };
+                match self.modules.contains_key(&submodule) {
+                    true => submodule,
+                    false => module.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// The file of the longest prefix of the dotted `module` in the project.
+    fn longest_module(&self, module: &str) -> Option<&PathBuf> {
+        let parts: Vec<&str> = module.split('.').collect();
+        (1..=parts.len())
+            .rev()
+            .find_map(|len| self.modules.get(&parts[..len].join(".")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn deps<'g>(graph: &'g DependencyGraph, file: &str) -> Vec<&'g str> {
+        graph.edges[file].iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn test_rust_use_declarations() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "Cargo.toml", "[package]\nname = \"my-app\"\nversion = \"0.1.0\"\n");
+        write(root, "src/lib.rs", "pub mod config;\npub mod tools;\npub struct Agent;\n");
+        write(
+            root,
+            "src/config.rs",
+            "use std::collections::HashMap;\nuse crate::tools::{shell::run, Registry as R};\n",
+        );
+        write(root, "src/tools/mod.rs", "pub mod shell;\npub struct Registry;\n");
+        write(root, "src/tools/shell.rs", "use super::super::Agent;\nuse crate::config::*;\npub fn run() {}\n");
+        write(root, "src/main.rs", "use my_app::tools;\nfn main() {}\n");
+
+        let graph = build_graph(root, "rust").unwrap();
+        assert_eq!(graph.file_count(), 5);
+        assert_eq!(deps(&graph, "src/config.rs"), ["src/tools/mod.rs", "src/tools/shell.rs"]);
+        assert_eq!(deps(&graph, "src/tools/shell.rs"), ["src/config.rs", "src/lib.rs"]);
+        assert_eq!(deps(&graph, "src/main.rs"), ["src/tools/mod.rs"]);
+        assert!(deps(&graph, "src/lib.rs").is_empty());
+
+        assert_eq!(
+            graph.cycles(),
+            vec![vec!["src/config.rs", "src/tools/shell.rs", "src/config.rs"]]
+        );
+    }
+
+    #[test]
+    fn test_python_imports() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "app/__init__.py", "");
+        write(root, "app/main.py", "import os\nimport app.models as m\nfrom app.utils import helpers\n");
+        write(root, "app/models.py", "from . import utils\nfrom .utils.helpers import slugify\n");
+        write(root, "app/utils/__init__.py", "from ..models import *\n");
+        write(root, "app/utils/helpers.py", "def slugify(s): return s\n");
+
+        let graph = build_graph(root, "python").unwrap();
+        assert_eq!(deps(&graph, "app/main.py"), ["app/models.py", "app/utils/helpers.py"]);
+        assert_eq!(
+            deps(&graph, "app/models.py"),
+            ["app/utils/__init__.py", "app/utils/helpers.py"]
+        );
+        assert_eq!(deps(&graph, "app/utils/__init__.py"), ["app/models.py"]);
+        assert_eq!(graph.cycles().len(), 1);
+        assert!(graph.to_text().contains("⚠️ 1 circular dependencies:\n  app/models.py -> app/utils/__init__.py -> app/models.py"));
+    }
+
+    #[test]
+    fn test_depth_and_output_formats() {
+        let mut graph = DependencyGraph::default();
+        for (file, dependencies) in [("a", vec!["b"]), ("b", vec!["c"]), ("c", vec![]), ("d", vec!["a"])] {
+            graph
+                .edges
+                .insert(file.to_string(), dependencies.into_iter().map(String::from).collect());
+        }
+
+        let limited = graph.reachable_from("a", Some(1));
+        assert_eq!(limited.edges.keys().collect::<Vec<_>>(), ["a", "b"]);
+        assert!(limited.edges["b"].is_empty());
+        assert_eq!(graph.reachable_from("a", None).file_count(), 3);
+        assert!(graph.cycles().is_empty());
+
+        assert_eq!(
+            graph.to_text(),
+            "4 files, 3 dependencies\na -> b\nb -> c\nc -> (none)\nd -> a\n✅ No circular dependencies"
+        );
+        assert_eq!(graph.to_json()["graph"]["a"], serde_json::json!(["b"]));
+        assert_eq!(graph.to_json()["cycles"], serde_json::json!([]));
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph dependencies {"));
+        assert!(dot.contains("    \"d\" -> \"a\";\n"));
+
+        graph.edges.get_mut("c").unwrap().insert("a".to_string());
+        assert_eq!(graph.cycles(), vec![vec!["a", "b", "c", "a"]]);
+        assert!(graph.to_dot().contains("    \"c\" -> \"a\" [color=red];\n"));
+    }
+}
@@ -0,0 +1,114 @@
+//! Passphrase-based encryption for files g3 keeps at rest.
+//!
+//! Encrypted files are `MAGIC || salt || nonce || ciphertext`, where the
+//! ciphertext is AES-256-GCM (tag included) under a key derived from the
+//! passphrase and salt with PBKDF2-HMAC-SHA256.
+
+use anyhow::{anyhow, bail, Result};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::num::NonZeroU32;
+
+/// Marks a file written by [`encrypt`]. Plaintext JSON can never start with it.
+pub const MAGIC: &[u8] = b"G3ENC\x01";
+
+/// Algorithm names accepted in `[encryption] algorithm`.
+pub const SUPPORTED_ALGORITHMS: &[&str] = &["aes-256-gcm"];
+
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+const PBKDF2_ITERATIONS: u32 = 100_000;
+
+/// Whether `data` starts with the header written by [`encrypt`].
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<LessSafeKey> {
+    let mut key = [0u8; KEY_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PBKDF2_ITERATIONS).expect("iterations are non-zero"),
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| anyhow!("Invalid key length"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Encrypt `plaintext` with a key derived from `passphrase` and a fresh salt.
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let rng = SystemRandom::new();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt)
+        .and_then(|_| rng.fill(&mut nonce))
+        .map_err(|_| anyhow!("Failed to generate random salt and nonce"))?;
+
+    let key = derive_key(passphrase, &salt)?;
+    let mut ciphertext = plaintext.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(MAGIC),
+        &mut ciphertext,
+    )
+    .map_err(|_| anyhow!("Encryption failed"))?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Decrypt data written by [`encrypt`]. Fails if the passphrase is wrong or
+/// the data was modified.
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let Some(rest) = data.strip_prefix(MAGIC) else {
+        bail!("Data is not encrypted");
+    };
+    if rest.len() < SALT_LEN + NONCE_LEN {
+        bail!("Encrypted data is truncated");
+    }
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("Invalid nonce"))?;
+
+    let key = derive_key(passphrase, salt)?;
+    let mut buffer = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::from(MAGIC), &mut buffer)
+        .map_err(|_| anyhow!("Decryption failed: wrong passphrase or corrupted data"))?;
+    Ok(plaintext.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let data = encrypt(b"{\"secret\":true}", "correct horse").unwrap();
+        assert!(is_encrypted(&data));
+        assert!(!data.windows(6).any(|w| w == b"secret"));
+        assert_eq!(decrypt(&data, "correct horse").unwrap(), b"{\"secret\":true}");
+
+        // A fresh salt and nonce every time
+        assert_ne!(encrypt(b"same", "pass").unwrap(), encrypt(b"same", "pass").unwrap());
+    }
+
+    #[test]
+    fn test_wrong_passphrase_and_tampering_fail() {
+        let mut data = encrypt(b"hello", "right").unwrap();
+        assert!(decrypt(&data, "wrong").is_err());
+
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(decrypt(&data, "right").is_err());
+        assert!(decrypt(&data[..10], "right").is_err());
+        assert!(decrypt(b"{}", "right").is_err());
+    }
+}
@@ -8,6 +8,7 @@
 //!
 //! Used by both autonomous mode (g3-cli) and planning mode (g3-planner).
 
+use crate::session_continuation::{find_session_log, read_session_log};
 use crate::{logs_dir, Agent, TaskResult};
 use crate::ui_writer::UiWriter;
 use serde_json::Value;
@@ -139,11 +140,11 @@ fn try_extract_from_session_log(
     session_id: &str,
     config: &FeedbackExtractionConfig,
 ) -> Option<String> {
-    // Try new .g3/sessions/<session_id>/session.json path first
-    let log_file_path = crate::get_session_file(session_id);
+    // Try new .g3/sessions/<session_id>/session.json(.zst) path first
+    let log_file_path = find_session_log(&crate::get_session_file(session_id));
     
     // Fall back to old logs/ path if new path doesn't exist
-    let log_file_path = if log_file_path.exists() {
+    let log_file_path = if let Some(log_file_path) = log_file_path {
         log_file_path
     } else {
         let logs_path = config.logs_dir.clone().unwrap_or_else(logs_dir);
@@ -155,7 +156,7 @@ fn try_extract_from_session_log(
         return None;
     }
 
-    let log_content = std::fs::read_to_string(&log_file_path).ok()?;
+    let log_content = read_session_log(&log_file_path).ok()?;
     let log_json: Value = serde_json::from_str(&log_content).ok()?;
 
     // Try to get conversation history from context_window
@@ -283,11 +284,11 @@ fn try_extract_from_conversation_history(
     session_id: &str,
     config: &FeedbackExtractionConfig,
 ) -> Option<String> {
-    // Try new .g3/sessions/<session_id>/session.json path first
-    let log_file_path = crate::get_session_file(session_id);
+    // Try new .g3/sessions/<session_id>/session.json(.zst) path first
+    let log_file_path = find_session_log(&crate::get_session_file(session_id));
     
     // Fall back to old logs/ path if new path doesn't exist
-    let log_file_path = if log_file_path.exists() {
+    let log_file_path = if let Some(log_file_path) = log_file_path {
         log_file_path
     } else {
         let logs_path = config.logs_dir.clone().unwrap_or_else(logs_dir);
@@ -298,7 +299,7 @@ fn try_extract_from_conversation_history(
         return None;
     }
 
-    let log_content = std::fs::read_to_string(&log_file_path).ok()?;
+    let log_content = read_session_log(&log_file_path).ok()?;
     let log_json: Value = serde_json::from_str(&log_content).ok()?;
 
     // Check for tool_calls array in the log
@@ -0,0 +1,303 @@
+//! Undo and redo for file edits made by tools.
+//!
+//! Before `write_file`, `str_replace` or `restore_backup` changes a file, the
+//! dispatcher snapshots it; once the tool succeeds the snapshot is pushed onto
+//! the session's [`FileOperationHistory`]. `/undo` puts the file back the way
+//! it was and `/redo` re-applies the edit.
+//!
+//! Small files are stored whole. For files over [`FULL_CONTENT_LIMIT`] only a
+//! unified diff from the edited content back to the original is kept.
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::utils::apply_unified_diff_to_string;
+
+/// Number of file operations `/undo` can step back through by default.
+pub const DEFAULT_FILE_HISTORY_LIMIT: usize = 10;
+
+/// Files larger than this many bytes are stored as a diff rather than in full.
+pub const FULL_CONTENT_LIMIT: usize = 64 * 1024;
+
+/// How a file's earlier content is kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoredContent {
+    Full(Vec<u8>),
+    /// Unified diff that turns the content after the operation back into the
+    /// content before it.
+    Diff(String),
+}
+
+impl StoredContent {
+    /// Store `before`, as a diff against `after` when the file is large.
+    fn capture(before: Vec<u8>, after: Option<&[u8]>) -> Self {
+        if before.len() <= FULL_CONTENT_LIMIT {
+            return StoredContent::Full(before);
+        }
+        let (Ok(before_text), Some(Ok(after_text))) =
+            (std::str::from_utf8(&before), after.map(std::str::from_utf8))
+        else {
+            return StoredContent::Full(before);
+        };
+
+        let diff = similar::TextDiff::from_lines(after_text, before_text)
+            .unified_diff()
+            .context_radius(3)
+            .to_string();
+        // Only keep a diff that is smaller and that reproduces the file exactly
+        let round_trips = apply_unified_diff_to_string(after_text, &diff, None, None)
+            .is_ok_and(|restored| restored == before_text);
+        if diff.len() < before.len() && round_trips {
+            StoredContent::Diff(diff)
+        } else {
+            StoredContent::Full(before)
+        }
+    }
+
+    /// Bytes held in memory for this entry.
+    pub fn size(&self) -> usize {
+        match self {
+            StoredContent::Full(bytes) => bytes.len(),
+            StoredContent::Diff(diff) => diff.len(),
+        }
+    }
+}
+
+/// A file change that can be rolled back.
+#[derive(Debug, Clone)]
+pub struct FileOperation {
+    pub path: PathBuf,
+    /// Content before the operation, or `None` if the operation created the file.
+    pub previous_content: Option<StoredContent>,
+    /// Tool that made the change, e.g. `str_replace`.
+    pub operation: String,
+    pub timestamp: Instant,
+}
+
+impl FileOperation {
+    /// Record that `operation` changed `path` from `before` to its current content.
+    /// Returns `None` when the file did not actually change.
+    pub fn capture(path: &Path, operation: &str, before: Option<Vec<u8>>) -> Option<Self> {
+        let after = std::fs::read(path).ok();
+        if before == after {
+            return None;
+        }
+        Some(Self {
+            path: path.to_path_buf(),
+            previous_content: before.map(|b| StoredContent::capture(b, after.as_deref())),
+            operation: operation.to_string(),
+            timestamp: Instant::now(),
+        })
+    }
+
+    /// Put the file back to its earlier content, returning the operation that
+    /// reverses this one.
+    fn revert(&self) -> Result<FileOperation> {
+        let current = std::fs::read(&self.path).ok();
+        let target = match &self.previous_content {
+            None => None,
+            Some(StoredContent::Full(bytes)) => Some(bytes.clone()),
+            Some(StoredContent::Diff(diff)) => {
+                let current = current
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("{} no longer exists", self.path.display()))?;
+                let current = std::str::from_utf8(current)
+                    .with_context(|| format!("{} is no longer valid UTF-8", self.path.display()))?;
+                let restored = apply_unified_diff_to_string(current, diff, None, None)
+                    .with_context(|| {
+                        format!("{} has changed since it was edited", self.path.display())
+                    })?;
+                Some(restored.into_bytes())
+            }
+        };
+
+        match &target {
+            Some(bytes) => {
+                if let Some(parent) = self.path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&self.path, bytes)
+            }
+            None => std::fs::remove_file(&self.path),
+        }
+        .with_context(|| format!("Failed to restore {}", self.path.display()))?;
+
+        Ok(FileOperation {
+            path: self.path.clone(),
+            previous_content: current.map(|c| StoredContent::capture(c, target.as_deref())),
+            operation: self.operation.clone(),
+            timestamp: Instant::now(),
+        })
+    }
+}
+
+/// The most recent file operations of a session, for `/undo` and `/redo`.
+#[derive(Debug)]
+pub struct FileOperationHistory {
+    undo: VecDeque<FileOperation>,
+    redo: Vec<FileOperation>,
+    limit: usize,
+}
+
+impl Default for FileOperationHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_FILE_HISTORY_LIMIT)
+    }
+}
+
+impl FileOperationHistory {
+    /// History keeping the last `limit` operations.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit,
+        }
+    }
+
+    /// Add an operation made by a tool. A new edit discards anything undone.
+    pub fn push(&mut self, operation: FileOperation) {
+        self.redo.clear();
+        self.undo.push_back(operation);
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Revert the latest operation. Returns `None` when there is nothing to undo.
+    /// The operation stays in the history if it could not be reverted.
+    pub fn rollback_last_file_operation(&mut self) -> Result<Option<FileOperation>> {
+        let Some(operation) = self.undo.pop_back() else {
+            return Ok(None);
+        };
+        match operation.revert() {
+            Ok(inverse) => {
+                self.redo.push(inverse);
+                Ok(Some(operation))
+            }
+            Err(e) => {
+                self.undo.push_back(operation);
+                Err(e)
+            }
+        }
+    }
+
+    /// Re-apply the operation most recently undone. Returns `None` when there is
+    /// nothing to redo.
+    pub fn redo_last_file_operation(&mut self) -> Result<Option<FileOperation>> {
+        let Some(operation) = self.redo.pop() else {
+            return Ok(None);
+        };
+        match operation.revert() {
+            Ok(inverse) => {
+                self.undo.push_back(inverse);
+                Ok(Some(operation))
+            }
+            Err(e) => {
+                self.redo.push(operation);
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Run `edit` on `path` and record it the way the dispatcher does.
+    fn edit(history: &mut FileOperationHistory, path: &Path, edit: impl FnOnce(&Path)) {
+        let before = std::fs::read(path).ok();
+        edit(path);
+        history.push(FileOperation::capture(path, "write_file", before).unwrap());
+    }
+
+    #[test]
+    fn test_undo_and_redo_edits() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("notes.txt");
+        let mut history = FileOperationHistory::default();
+
+        edit(&mut history, &path, |p| std::fs::write(p, "one\n").unwrap());
+        edit(&mut history, &path, |p| std::fs::write(p, "two\n").unwrap());
+        assert_eq!(history.len(), 2);
+
+        let undone = history.rollback_last_file_operation().unwrap().unwrap();
+        assert_eq!(undone.path, path);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\n");
+
+        // Undoing the creation deletes the file
+        history.rollback_last_file_operation().unwrap().unwrap();
+        assert!(!path.exists());
+        assert!(history.rollback_last_file_operation().unwrap().is_none());
+
+        history.redo_last_file_operation().unwrap().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\n");
+        history.redo_last_file_operation().unwrap().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "two\n");
+        assert!(history.redo_last_file_operation().unwrap().is_none());
+
+        // A new edit after an undo drops the redo stack
+        history.rollback_last_file_operation().unwrap();
+        edit(&mut history, &path, |p| std::fs::write(p, "three\n").unwrap());
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_history_is_limited() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("counter.txt");
+        let mut history = FileOperationHistory::new(3);
+        for i in 0..5 {
+            edit(&mut history, &path, |p| std::fs::write(p, i.to_string()).unwrap());
+        }
+        assert_eq!(history.len(), 3);
+        while history.rollback_last_file_operation().unwrap().is_some() {}
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1");
+    }
+
+    #[test]
+    fn test_large_files_are_stored_as_diffs() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("big.rs");
+        let original: String = (0..10_000).map(|i| format!("let x{} = {};\n", i, i)).collect();
+        std::fs::write(&path, &original).unwrap();
+        let mut history = FileOperationHistory::default();
+
+        edit(&mut history, &path, |p| {
+            std::fs::write(p, original.replace("let x5000 = 5000;", "let x5000 = 0;")).unwrap()
+        });
+        let stored = history.undo.back().unwrap().previous_content.as_ref().unwrap();
+        assert!(matches!(stored, StoredContent::Diff(_)));
+        assert!(stored.size() < 1024, "diff is {} bytes", stored.size());
+
+        history.rollback_last_file_operation().unwrap().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), original);
+        history.redo_last_file_operation().unwrap().unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("let x5000 = 0;"));
+    }
+
+    #[test]
+    fn test_unchanged_file_is_not_recorded() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("same.txt");
+        std::fs::write(&path, "same").unwrap();
+        let before = std::fs::read(&path).ok();
+        assert!(FileOperation::capture(&path, "write_file", before).is_none());
+    }
+}
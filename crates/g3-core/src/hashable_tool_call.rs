@@ -0,0 +1,141 @@
+//! Hash/Eq wrapper for [`ToolCall`].
+//!
+//! `serde_json::Value` doesn't implement `Hash`, so tool calls can't go into a
+//! `HashSet` directly. [`HashableToolCall`] compares and hashes the tool name
+//! together with a canonical JSON rendering of the args in which object keys
+//! are sorted, so `{"a":1,"b":2}` and `{"b":2,"a":1}` are the same call.
+
+use std::hash::{Hash, Hasher};
+
+use serde_json::Value;
+
+use crate::ToolCall;
+
+#[derive(Debug, Clone)]
+pub struct HashableToolCall(pub ToolCall);
+
+impl From<ToolCall> for HashableToolCall {
+    fn from(call: ToolCall) -> Self {
+        Self(call)
+    }
+}
+
+impl PartialEq for HashableToolCall {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.tool == other.0.tool && canonical_json(&self.0.args) == canonical_json(&other.0.args)
+    }
+}
+
+impl Eq for HashableToolCall {}
+
+impl Hash for HashableToolCall {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.tool.hash(state);
+        canonical_json(&self.0.args).hash(state);
+    }
+}
+
+impl ToolCall {
+    /// Wrap a copy of this call so it can be used as a `HashSet`/`HashMap` key.
+    pub fn to_hashable(&self) -> HashableToolCall {
+        HashableToolCall(self.clone())
+    }
+}
+
+/// Serialize `value` with object keys sorted at every level.
+///
+/// This doesn't rely on `serde_json`'s map ordering, which changes if any
+/// crate in the build enables the `preserve_order` feature.
+pub fn canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_canonical(value, &mut out);
+    out
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+
+    fn call(tool: &str, args: Value) -> ToolCall {
+        ToolCall {
+            tool: tool.to_string(),
+            args,
+        }
+    }
+
+    fn hash_of(call: &HashableToolCall) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        call.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_key_order_does_not_matter() {
+        let mut first = serde_json::Map::new();
+        first.insert("path".to_string(), json!("src/main.rs"));
+        first.insert("options".to_string(), json!({"a": 1, "b": [1, 2]}));
+
+        let mut second = serde_json::Map::new();
+        second.insert("options".to_string(), json!({"b": [1, 2], "a": 1}));
+        second.insert("path".to_string(), json!("src/main.rs"));
+
+        let a = call("read_file", Value::Object(first)).to_hashable();
+        let b = call("read_file", Value::Object(second)).to_hashable();
+
+        assert_eq!(a, b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+    }
+
+    #[test]
+    fn test_different_calls_are_distinct() {
+        let mut set = HashSet::new();
+        assert!(set.insert(call("read_file", json!({"file_path": "a.rs"})).to_hashable()));
+        assert!(set.insert(call("read_file", json!({"file_path": "b.rs"})).to_hashable()));
+        assert!(set.insert(call("write_file", json!({"file_path": "a.rs"})).to_hashable()));
+        assert!(!set.insert(call("read_file", json!({"file_path": "a.rs"})).to_hashable()));
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn test_canonical_json_sorts_nested_keys() {
+        let value = json!({"z": {"y": 1, "x": [{"b": true, "a": null}]}, "a": "s\"q"});
+        assert_eq!(
+            canonical_json(&value),
+            r#"{"a":"s\"q","z":{"x":[{"a":null,"b":true}],"y":1}}"#
+        );
+    }
+}
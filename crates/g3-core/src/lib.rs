@@ -1,17 +1,27 @@
 pub mod context_window;
+pub mod audit;
 pub mod background_process;
+pub mod code_complexity;
 pub mod code_search;
+pub mod dependency_graph;
+pub mod encryption;
 pub mod error_handling;
 pub mod feedback_extraction;
+pub mod file_history;
+pub mod hashable_tool_call;
 pub mod paths;
 pub mod project;
 pub mod provider_registration;
 pub mod provider_config;
 pub mod retry;
+pub mod security;
 pub mod session;
 pub mod session_continuation;
+pub mod ssh_session;
 pub mod streaming_parser;
+pub mod symbol_rename;
 pub mod task_result;
+pub mod telemetry;
 pub mod tool_dispatch;
 pub mod tool_definitions;
 pub mod tools;
@@ -32,7 +42,7 @@ mod webdriver_test;
 pub use task_result::TaskResult;
 pub use retry::{RetryConfig, RetryResult, execute_with_retry, retry_operation};
 pub use feedback_extraction::{ExtractedFeedback, FeedbackSource, FeedbackExtractionConfig, extract_coach_feedback};
-pub use session_continuation::{SessionContinuation, load_continuation, save_continuation, clear_continuation, has_valid_continuation, get_session_dir, load_context_from_session_log, find_incomplete_agent_session};
+pub use session_continuation::{SessionContinuation, load_continuation, save_continuation, clear_continuation, has_valid_continuation, get_session_dir, load_context_from_session_log, find_session_log, read_session_log, find_incomplete_agent_session, list_checkpoints, CheckpointInfo};
 
 // Re-export context window types
 pub use context_window::{ContextWindow, ThinScope};
@@ -75,6 +85,8 @@ pub struct ToolCall {
     pub args: serde_json::Value, // Should be a JSON object with tool-specific arguments
 }
 
+pub use hashable_tool_call::HashableToolCall;
+
 
 // Re-export WebDriverSession from its own module
 pub use webdriver_session::WebDriverSession;
@@ -96,7 +108,7 @@ pub enum StreamState {
 
 
 // Re-export StreamingToolParser from its own module
-pub use streaming_parser::StreamingToolParser;
+pub use streaming_parser::{ParsedTurn, StreamingToolParser};
 
 pub struct Agent<W: UiWriter> {
     providers: ProviderRegistry,
@@ -109,6 +121,7 @@ pub struct Agent<W: UiWriter> {
     config: Config,
     session_id: Option<String>,
     tool_call_metrics: Vec<(String, Duration, bool)>, // (tool_name, duration, success)
+    tool_telemetry: telemetry::ToolTelemetryLog,
     ui_writer: W,
     is_autonomous: bool,
     quiet: bool,
@@ -120,14 +133,44 @@ pub struct Agent<W: UiWriter> {
         >,
     >,
     webdriver_process: std::sync::Arc<tokio::sync::RwLock<Option<tokio::process::Child>>>,
+    webdriver_state: std::sync::Arc<tokio::sync::RwLock<webdriver_session::WebDriverSessionState>>,
     tool_call_count: usize,
     requirements_sha: Option<String>,
     /// Working directory for tool execution (set by --codebase-fast-start)
     working_dir: Option<String>,
+    /// Directory tools resolve relative paths against, once changed by a `cd`
+    /// in a shell command or `set_working_directory`. `None` uses the process
+    /// current directory.
+    working_directory: Option<std::path::PathBuf>,
     background_process_manager: std::sync::Arc<background_process::BackgroundProcessManager>,
+    /// Persistent shell sessions started with shell_session_start
+    shell_sessions: std::sync::Arc<
+        tokio::sync::Mutex<std::collections::HashMap<String, tools::shell::ShellSession>>,
+    >,
+    /// SSH connections opened by read_file's ssh_host argument
+    ssh_sessions: std::sync::Arc<ssh_session::SshSessions>,
+    /// Redacts secrets from tool output before it reaches the LLM
+    secrets_redactor: security::SecretsRedactor,
+    tool_result_cache: Option<tools::executor::ToolResultCache>,
 
     /// Pending images to attach to the next user message
     pending_images: Vec<g3_providers::ImageContent>,
+    /// Files recently read or edited by tools, used to rank code_search results
+    recent_files: Vec<std::path::PathBuf>,
+    /// Environment variables set with shell_env for later shell commands
+    extra_env: std::collections::HashMap<String, String>,
+    /// Tool-call macros saved with macro_record
+    macros: tools::macros::MacroStore,
+    /// Recent file edits, for /undo and /redo
+    file_history: file_history::FileOperationHistory,
+    /// Checks tool call arguments against the tool schemas; `None` with `skip_validation`
+    tool_schema_validator: Option<tools::executor::ToolSchemaValidator>,
+    /// Append-only record of every tool call
+    audit_logger: audit::AuditLogger,
+    /// Detects streams that end mid-tool-call and counts them for get_stats
+    response_validator: g3_providers::ResponseValidator,
+    /// Token usage and estimated cost accumulated from provider responses
+    session_cost: g3_providers::SessionCost,
     /// Whether this agent is running in agent mode (--agent flag)
     is_agent_mode: bool,
     /// Name of the agent if running in agent mode (e.g., "fowler", "pike")
@@ -280,6 +323,35 @@ impl<W: UiWriter> Agent<W> {
         };
 
 
+        let secrets_redactor = if config.agent.redact_secrets {
+            security::SecretsRedactor::new(&config.agent.secret_patterns)?
+        } else {
+            security::SecretsRedactor::disabled()
+        };
+        session_continuation::configure_encryption(config.encryption.clone())?;
+        let tool_schema_validator = (!config.agent.skip_validation).then(|| {
+            tools::executor::ToolSchemaValidator::new(&tool_definitions::create_tool_definitions(
+                tool_definitions::ToolConfig::new(
+                    config.webdriver.enabled,
+                    config.computer_control.enabled,
+                    false,
+                ),
+            ))
+        });
+        let tool_cache_ttl = config.agent.tool_cache_ttl_seconds;
+        let background_output_buffer_lines = config.agent.background_output_buffer_lines;
+        let retry_on_truncation = config.agent.retry_on_truncation;
+        let tool_result_cache = (tool_cache_ttl > 0)
+            .then(|| tools::executor::ToolResultCache::new(Duration::from_secs(tool_cache_ttl)));
+        let telemetry_file = match &config.agent.telemetry_file {
+            Some(path) => std::path::PathBuf::from(shellexpand::tilde(path).as_ref()),
+            None => paths::get_telemetry_file(),
+        };
+        let audit_log_file = match &config.agent.audit_log_file {
+            Some(path) => std::path::PathBuf::from(shellexpand::tilde(path).as_ref()),
+            None => paths::get_audit_log_file(),
+        };
+
         Ok(Self {
             providers,
             context_window,
@@ -291,6 +363,7 @@ impl<W: UiWriter> Agent<W> {
             config,
             session_id: None,
             tool_call_metrics: Vec::new(),
+            tool_telemetry: telemetry::ToolTelemetryLog::new(telemetry_file),
             ui_writer,
             // TODO content starts empty - session-scoped TODOs are loaded via todo_read
             todo_content: std::sync::Arc::new(tokio::sync::RwLock::new(String::new())),
@@ -299,15 +372,32 @@ impl<W: UiWriter> Agent<W> {
             computer_controller,
             webdriver_session: std::sync::Arc::new(tokio::sync::RwLock::new(None)),
             webdriver_process: std::sync::Arc::new(tokio::sync::RwLock::new(None)),
+            webdriver_state: std::sync::Arc::new(tokio::sync::RwLock::new(Default::default())),
             tool_call_count: 0,
             requirements_sha: None,
             working_dir: None,
+            working_directory: None,
             background_process_manager: std::sync::Arc::new(
                 background_process::BackgroundProcessManager::new(
                     paths::get_logs_dir().join("background_processes")
-                )),
+                )
+                .with_buffer_capacity(background_output_buffer_lines)),
+            shell_sessions: std::sync::Arc::new(tokio::sync::Mutex::new(
+                std::collections::HashMap::new(),
+            )),
+            ssh_sessions: std::sync::Arc::new(ssh_session::SshSessions::new()),
+            secrets_redactor,
+            tool_result_cache,
 
             pending_images: Vec::new(),
+            recent_files: Vec::new(),
+            extra_env: std::collections::HashMap::new(),
+            macros: tools::macros::MacroStore::new(paths::get_macros_dir()),
+            file_history: file_history::FileOperationHistory::default(),
+            tool_schema_validator,
+            audit_logger: audit::AuditLogger::new(audit_log_file),
+            response_validator: g3_providers::ResponseValidator::new(retry_on_truncation),
+            session_cost: g3_providers::SessionCost::default(),
             is_agent_mode: false,
             agent_name: None,
 
@@ -348,18 +438,26 @@ impl<W: UiWriter> Agent<W> {
 
     /// Convert cache config string to CacheControl enum
     fn parse_cache_control(cache_config: &str) -> Option<CacheControl> {
-        match cache_config {
-            "ephemeral" => Some(CacheControl::ephemeral()),
-            "5minute" => Some(CacheControl::five_minute()),
-            "1hour" => Some(CacheControl::one_hour()),
-            _ => {
-                warn!(
-                    "Invalid cache_config value: '{}'. Valid values are: ephemeral, 5minute, 1hour",
-                    cache_config
-                );
-                None
-            }
+        let cache_control = CacheControl::from_config(cache_config);
+        if cache_control.is_none() {
+            warn!(
+                "Invalid cache_config value: '{}'. Valid values are: ephemeral, 5minute, 1hour, or a duration like 15m",
+                cache_config
+            );
         }
+        cache_control
+    }
+
+    /// The `cache_config` of the provider named `provider_name`, if it has one.
+    fn configured_cache_control(&self, provider_name: &str) -> Option<CacheControl> {
+        let (provider_type, config_name) = provider_config::parse_provider_ref(provider_name);
+        let providers = &self.config.providers;
+        let cache_config = match provider_type {
+            "anthropic" => providers.anthropic.get(config_name)?.cache_config.as_ref(),
+            "openai" => providers.openai.get(config_name)?.cache_config.as_ref(),
+            name => providers.openai_compatible.get(name)?.cache_config.as_ref(),
+        }?;
+        Self::parse_cache_control(cache_config)
     }
 
     /// Count how many cache_control annotations exist in the conversation history
@@ -590,6 +688,18 @@ impl<W: UiWriter> Agent<W> {
                     16384 // Conservative default for other Databricks models
                 }
             }
+            "gemini" => {
+                // Gemini 1.5 and later models have 1M token context windows
+                if let Some(max_tokens) = provider_config::get_max_tokens(config, provider_name) {
+                    warnings.push(format!(
+                        "Context length falling back to max_tokens ({}) for provider={}",
+                        max_tokens, provider_name
+                    ));
+                    max_tokens
+                } else {
+                    1048576
+                }
+            }
             _ => config.agent.fallback_default_max_tokens as u32,
         };
 
@@ -722,19 +832,7 @@ impl<W: UiWriter> Agent<W> {
             // Check if we should use cache control (every 10 tool calls)
             // But only if we haven't already added 4 cache_control annotations
             let provider = self.providers.get(None)?;
-            let provider_name = provider.name();
-            let (provider_type, config_name) = provider_config::parse_provider_ref(provider_name);
-            if let Some(cache_config) = match provider_type {
-                "anthropic" => {
-                    self.config
-                        .providers
-                        .anthropic
-                        .get(config_name)
-                        .and_then(|c| c.cache_config.as_ref())
-                        .and_then(|config| Self::parse_cache_control(config))
-                }
-                _ => None,
-            } {
+            if let Some(cache_config) = self.configured_cache_control(provider.name()) {
                 Message::with_cache_control_validated(
                     MessageRole::User,
                     format!("Task: {}", description),
@@ -858,6 +956,8 @@ impl<W: UiWriter> Agent<W> {
             prompt_tokens: 100,                                   // Estimate
             completion_tokens: response_content.len() as u32 / 4, // Rough estimate
             total_tokens: 100 + (response_content.len() as u32 / 4),
+            cache_read_tokens: 0,
+            cache_write_tokens: 0,
         };
 
         // Update context window with estimated token usage
@@ -1212,6 +1312,11 @@ impl<W: UiWriter> Agent<W> {
     }
 
     /// Get detailed context statistics
+    /// Estimated cost in USD of all provider responses in this session.
+    pub fn session_total_cost_usd(&self) -> f64 {
+        self.session_cost.total_cost_usd()
+    }
+
     pub fn get_stats(&self) -> String {
         let mut stats = String::new();
         use std::time::Duration;
@@ -1289,6 +1394,10 @@ impl<W: UiWriter> Agent<W> {
                 median_ttft.as_secs_f64()
             ));
         }
+        stats.push_str(&format!(
+            "   • Truncated Responses:        {:>6}\n",
+            self.response_validator.truncations()
+        ));
         stats.push('\n');
 
         // Conversation history
@@ -1371,10 +1480,52 @@ impl<W: UiWriter> Agent<W> {
         stats
     }
 
+    /// Number of streamed responses that ended mid-tool-call this session.
+    pub fn truncated_responses(&self) -> usize {
+        self.response_validator.truncations()
+    }
+
     pub fn get_tool_call_metrics(&self) -> &Vec<(String, Duration, bool)> {
         &self.tool_call_metrics
     }
 
+    /// Directory relative paths in tool calls are resolved against.
+    pub fn working_directory(&self) -> std::path::PathBuf {
+        self.working_directory
+            .clone()
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_default())
+    }
+
+    /// Run this session's tools in `dir` instead of the process current directory.
+    pub fn set_working_directory(&mut self, dir: std::path::PathBuf) {
+        self.working_directory = Some(dir);
+    }
+
+    /// Telemetry events for every tool call made in this session.
+    pub fn get_tool_telemetry(&self) -> &[telemetry::ToolTelemetry] {
+        self.tool_telemetry.events()
+    }
+
+    /// Per-tool call counts, failures, durations and output sizes for this session.
+    pub fn get_tool_telemetry_totals(
+        &self,
+    ) -> std::collections::BTreeMap<String, telemetry::ToolTotals> {
+        self.tool_telemetry.totals()
+    }
+
+    /// Record a finished tool call in the metrics and the telemetry file.
+    fn record_tool_call(&mut self, tool_name: &str, duration: Duration, result: &str) {
+        let event = telemetry::ToolTelemetry::new(
+            tool_name,
+            duration,
+            result,
+            self.session_id.as_deref(),
+        );
+        self.tool_call_metrics
+            .push((tool_name.to_string(), duration, event.success));
+        self.tool_telemetry.record(event);
+    }
+
     pub fn get_config(&self) -> &Config {
         &self.config
     }
@@ -1424,7 +1575,8 @@ impl<W: UiWriter> Agent<W> {
             working_directory,
         );
         
-        if let Err(e) = save_continuation(&continuation) {
+        let messages: Vec<&Message> = self.context_window.conversation_history.iter().collect();
+        if let Err(e) = save_continuation(&continuation, &messages) {
             error!("Failed to save session continuation: {}", e);
         } else {
             debug!("Saved session continuation artifact");
@@ -1448,6 +1600,53 @@ impl<W: UiWriter> Agent<W> {
         }
     }
 
+    /// Save the session as checkpoint `name` (for the /checkpoint command).
+    /// The session log and continuation are written first so the checkpoint
+    /// reflects the conversation so far, not just the last final_output.
+    pub fn save_checkpoint(&self, name: &str) -> Result<std::path::PathBuf> {
+        use crate::session_continuation::{load_continuation, save_checkpoint};
+
+        self.save_context_window("checkpoint");
+        let summary = load_continuation()
+            .ok()
+            .flatten()
+            .filter(|c| Some(&c.session_id) == self.session_id.as_ref())
+            .and_then(|c| c.final_output_summary);
+        self.save_session_continuation(summary);
+        save_checkpoint(name)
+    }
+
+    /// Return to checkpoint `name` (for the /restore command).
+    /// Returns true if the full context was restored, false if only its summary was.
+    pub fn restore_checkpoint(&mut self, name: &str) -> Result<bool> {
+        let continuation = crate::session_continuation::restore_checkpoint(name)?;
+        self.restore_from_continuation(&continuation)
+    }
+
+    /// Revert the latest file edit made by a tool (for the /undo command).
+    /// Returns `None` when there is nothing to undo.
+    pub fn rollback_last_file_operation(
+        &mut self,
+    ) -> Result<Option<file_history::FileOperation>> {
+        let undone = self.file_history.rollback_last_file_operation()?;
+        self.forget_cached_reads(undone.as_ref());
+        Ok(undone)
+    }
+
+    /// Re-apply the file edit most recently undone (for the /redo command).
+    /// Returns `None` when there is nothing to redo.
+    pub fn redo_last_file_operation(&mut self) -> Result<Option<file_history::FileOperation>> {
+        let redone = self.file_history.redo_last_file_operation()?;
+        self.forget_cached_reads(redone.as_ref());
+        Ok(redone)
+    }
+
+    fn forget_cached_reads(&mut self, operation: Option<&file_history::FileOperation>) {
+        if let (Some(cache), Some(operation)) = (self.tool_result_cache.as_mut(), operation) {
+            cache.invalidate_path(&operation.path);
+        }
+    }
+
     /// Clear session state and continuation artifacts (for /clear command)
     pub fn clear_session(&mut self) {
         use crate::session_continuation::clear_continuation;
@@ -1469,14 +1668,13 @@ impl<W: UiWriter> Agent<W> {
         &mut self,
         continuation: &crate::session_continuation::SessionContinuation,
     ) -> Result<bool> {
-        use std::path::PathBuf;
-        
-        let session_log_path = PathBuf::from(&continuation.session_log_path);
-        
         // If context < 80%, try to restore full context
-        if continuation.can_restore_full_context() && session_log_path.exists() {
-            // Load the session log
-            let json = std::fs::read_to_string(&session_log_path)?;
+        let session_log_path = continuation
+            .session_log()
+            .filter(|_| continuation.can_restore_full_context());
+        if let Some(session_log_path) = session_log_path {
+            // Load the session log (may be zstd-compressed)
+            let json = crate::session_continuation::read_session_log(&session_log_path)?;
             let session_data: serde_json::Value = serde_json::from_str(&json)?;
             
             // Extract conversation history
@@ -1555,6 +1753,22 @@ impl<W: UiWriter> Agent<W> {
     ) -> Result<g3_providers::CompletionStream> {
         use crate::error_handling::{calculate_retry_delay, classify_error, ErrorType};
 
+        let estimated_tokens = g3_providers::count_tokens(
+            &request.messages,
+            request.tools.as_deref().unwrap_or_default(),
+        );
+        if self.context_window.would_exceed(estimated_tokens) {
+            warn!(
+                "Request is estimated at {} tokens, which exceeds the context window of {}",
+                estimated_tokens, self.context_window.total_tokens
+            );
+        } else if estimated_tokens as f32 > self.context_window.total_tokens as f32 * 0.8 {
+            warn!(
+                "Request is estimated at {} tokens, above 80% of the context window of {}",
+                estimated_tokens, self.context_window.total_tokens
+            );
+        }
+
         let mut attempt = 0;
         let max_attempts = if self.is_autonomous {
             self.config.agent.autonomous_max_retry_attempts
@@ -1629,6 +1843,9 @@ impl<W: UiWriter> Agent<W> {
         let mut final_output_called = false; // Track if final_output was called across ALL iterations
         // Note: Session-level duplicate tracking was removed - we only prevent sequential duplicates (DUP IN CHUNK, DUP IN MSG)
         let mut turn_accumulated_usage: Option<g3_providers::Usage> = None; // Track token usage for timing footer
+        // Non-streaming response replacing a truncated stream, replayed on the next iteration
+        let mut truncation_retry: Option<g3_providers::CompletionResponse> = None;
+        let mut truncation_retried = false; // Only retry a truncated response once per turn
 
         // Check if we need to compact before starting
         if self.context_window.should_compact() {
@@ -1830,7 +2047,11 @@ impl<W: UiWriter> Agent<W> {
             );
 
             // Try to get stream with retry logic
-            let mut stream = match self.stream_with_retry(&request, &error_context).await {
+            let stream = match truncation_retry.take() {
+                Some(response) => Ok(g3_providers::ResponseValidator::response_stream(response)),
+                None => self.stream_with_retry(&request, &error_context).await,
+            };
+            let mut stream = match stream {
                 Ok(s) => s,
                 Err(e) => {
                     error!("Failed to start stream: {}", e);
@@ -1998,7 +2219,12 @@ impl<W: UiWriter> Agent<W> {
                                 // Reset the parser to clear any partial/polluted state.
                                 // This prevents "example" tool calls in markdown or LLM stuttering
                                 // from polluting subsequent parsing.
-                                parser.reset();
+                                let discarded = parser.reset();
+                                debug!(
+                                    "Discarded {} bytes of parser text and {} tool calls after duplicate",
+                                    discarded.text.len(),
+                                    discarded.tool_calls_count
+                                );
 
                                 continue; // Skip execution of duplicate
                             }
@@ -2122,12 +2348,7 @@ impl<W: UiWriter> Agent<W> {
                             let exec_duration = exec_start.elapsed();
 
                             // Track tool call metrics
-                            let tool_success = !tool_result.contains("❌");
-                            self.tool_call_metrics.push((
-                                tool_call.tool.clone(),
-                                exec_duration,
-                                tool_success,
-                            ));
+                            self.record_tool_call(&tool_call.tool, exec_duration, &tool_result);
 
                             // Display tool execution result with proper indentation
                             if tool_call.tool == "final_output" {
@@ -2144,11 +2365,14 @@ impl<W: UiWriter> Agent<W> {
                                 const MAX_LINE_WIDTH: usize = 80;
                                 let output_len = output_lines.len();
 
-                                // Skip printing for todo tools - they already print their content
-                                let is_todo_tool =
-                                    tool_call.tool == "todo_read" || tool_call.tool == "todo_write";
+                                // Skip printing for todo tools and successful shell commands -
+                                // they already printed their content while running
+                                let already_printed = tool_call.tool == "todo_read"
+                                    || tool_call.tool == "todo_write"
+                                    || (tool_call.tool == "shell"
+                                        && !tools::executor::is_error_result(&tool_result));
 
-                                if !is_todo_tool {
+                                if !already_printed {
                                     let max_lines_to_show = if wants_full { output_len } else { MAX_LINES };
 
                                     for (idx, line) in output_lines.iter().enumerate() {
@@ -2195,19 +2419,7 @@ impl<W: UiWriter> Agent<W> {
                                     && self.count_cache_controls_in_history() < 4
                                 {
                                     let provider = self.providers.get(None)?;
-                                    let provider_name = provider.name();
-                                    let (provider_type, config_name) = provider_config::parse_provider_ref(provider_name);
-                                    if let Some(cache_config) = match provider_type {
-                                        "anthropic" => {
-                                            self.config
-                                                .providers
-                                                .anthropic
-                                                .get(config_name)
-                                                .and_then(|c| c.cache_config.as_ref())
-                                                .and_then(|config| Self::parse_cache_control(config))
-                                        }
-                                        _ => None,
-                                    } {
+                                    if let Some(cache_config) = self.configured_cache_control(provider.name()) {
                                         Message::with_cache_control_validated(
                                             MessageRole::User,
                                             format!("Tool result: {}", tool_result),
@@ -2282,7 +2494,8 @@ impl<W: UiWriter> Agent<W> {
                                 self.ui_writer
                                     .print_tool_timing(&Self::format_duration(exec_duration),
                                         tokens_delta,
-                                        self.context_window.percentage_used());
+                                        self.context_window.percentage_used(),
+                                        self.session_total_cost_usd());
                                 self.ui_writer.print_agent_prompt();
                                 
                                 // CRITICAL: Ensure all tool output is flushed before continuing
@@ -2331,7 +2544,12 @@ impl<W: UiWriter> Agent<W> {
                                 parser.mark_tool_calls_consumed();
                             } else {
                                 // Reset parser for next iteration - this clears the text buffer
-                                parser.reset();
+                                let turn = parser.reset();
+                                debug!(
+                                    "Parser reset after turn: {} bytes of text, {} tool calls",
+                                    turn.text.len(),
+                                    turn.tool_calls_count
+                                );
                             }
 
                             // Clear current_response for next iteration to prevent buffered text
@@ -2551,6 +2769,7 @@ impl<W: UiWriter> Agent<W> {
             if let Some(usage) = accumulated_usage {
                 debug!("Updating context window with actual usage from stream");
                 self.context_window.update_usage_from_response(&usage);
+                self.session_cost.record(&provider_model, &usage);
             } else {
                 // Fall back to estimation if no usage data was provided
                 debug!("No usage data from stream, using estimation");
@@ -2581,6 +2800,27 @@ impl<W: UiWriter> Agent<W> {
                 };
                 let is_empty_response = streaming::is_empty_response(response_text);
 
+                // A stream that ended mid-tool-call (e.g. a dropped connection) can be
+                // re-requested without streaming when retry_on_truncation is set
+                let check = self.response_validator.validate(&parser);
+                if check == (g3_providers::ResponseCheck::Truncated { retry: true })
+                    && !truncation_retried
+                {
+                    truncation_retried = true;
+                    self.ui_writer.print_context_status(
+                        "\n🔄 Response was truncated. Retrying without streaming...\n",
+                    );
+                    let retry_request = g3_providers::ResponseValidator::retry_request(&request);
+                    let provider = self.providers.get(None)?;
+                    match provider.complete(retry_request).await {
+                        Ok(response) => {
+                            truncation_retry = Some(response);
+                            continue;
+                        }
+                        Err(e) => warn!("Retry of truncated response failed: {}", e),
+                    }
+                }
+
                 // Check if there's an incomplete tool call in the buffer
                 let has_incomplete_tool_call = parser.has_incomplete_tool_call();
 
@@ -3086,12 +3326,7 @@ impl<W: UiWriter> Agent<W> {
                                 let exec_duration = exec_start.elapsed();
 
                                 // Track tool call metrics
-                                let tool_success = !tool_result.contains("❌");
-                                self.tool_call_metrics.push((
-                                    tool_call.tool.clone(),
-                                    exec_duration,
-                                    tool_success,
-                                ));
+                                self.record_tool_call(&tool_call.tool, exec_duration, &tool_result);
 
                                 // Display tool execution result with proper indentation
                                 if tool_call.tool == "final_output" {
@@ -3108,11 +3343,14 @@ impl<W: UiWriter> Agent<W> {
                                     const MAX_LINE_WIDTH: usize = 80;
                                     let output_len = output_lines.len();
 
-                                    // Skip printing for todo tools - they already print their content
-                                    let is_todo_tool =
-                                        tool_call.tool == "todo_read" || tool_call.tool == "todo_write";
+                                    // Skip printing for todo tools and successful shell commands -
+                                    // they already printed their content while running
+                                    let already_printed = tool_call.tool == "todo_read"
+                                        || tool_call.tool == "todo_write"
+                                        || (tool_call.tool == "shell"
+                                            && !tools::executor::is_error_result(&tool_result));
 
-                                    if !is_todo_tool {
+                                    if !already_printed {
                                         let max_lines_to_show = if wants_full { output_len } else { MAX_LINES };
 
                                         for (idx, line) in output_lines.iter().enumerate() {
@@ -3156,7 +3394,8 @@ impl<W: UiWriter> Agent<W> {
                                     self.ui_writer
                                         .print_tool_timing(&Self::format_duration(exec_duration),
                                             0, // No token delta info in non-streaming
-                                            self.context_window.percentage_used());
+                                            self.context_window.percentage_used(),
+                                            self.session_total_cost_usd());
                                 }
 
                                 tool_executed = true;
@@ -3311,21 +3550,46 @@ impl<W: UiWriter> Agent<W> {
         );
         debug!("======================");
 
+        // A directory the session has moved to takes precedence over the
+        // one passed in, which is the starting point for discovery commands
+        let working_directory = match (&self.working_directory, working_dir) {
+            (Some(dir), _) => dir.clone(),
+            (None, Some(dir)) => std::path::PathBuf::from(shellexpand::tilde(dir).as_ref()),
+            (None, None) => std::env::current_dir().unwrap_or_default(),
+        };
+
         // Create tool context for dispatch
         let mut ctx = tools::executor::ToolContext {
             config: &self.config,
             ui_writer: &self.ui_writer,
             session_id: self.session_id.as_deref(),
-            working_dir,
+            working_directory: working_directory.clone(),
             computer_controller: self.computer_controller.as_ref(),
             webdriver_session: &self.webdriver_session,
             webdriver_process: &self.webdriver_process,
+            webdriver_state: &self.webdriver_state,
             background_process_manager: &self.background_process_manager,
+            shell_sessions: &self.shell_sessions,
+            ssh_sessions: &self.ssh_sessions,
+            secrets_redactor: &self.secrets_redactor,
             todo_content: &self.todo_content,
             pending_images: &mut self.pending_images,
+            recent_files: &mut self.recent_files,
+            extra_env: &mut self.extra_env,
             is_autonomous: self.is_autonomous,
             requirements_sha: self.requirements_sha.as_deref(),
-
+            default_tool_timeout: self
+                .config
+                .agent
+                .tool_timeout_seconds
+                .map(std::time::Duration::from_secs),
+
+            tool_cache: self.tool_result_cache.as_mut(),
+            dry_run: self.config.agent.dry_run,
+            macros: &mut self.macros,
+            file_history: &mut self.file_history,
+            schema_validator: self.tool_schema_validator.as_ref(),
+            audit_logger: Some(&self.audit_logger),
         };
 
         // Dispatch to the appropriate tool handler
@@ -3333,6 +3597,10 @@ impl<W: UiWriter> Agent<W> {
         let result = tool_dispatch::dispatch_tool(tool_call, &mut ctx).await?;
         debug!("Tool execution completed: {}", tool_call.tool);
 
+        if ctx.working_directory != working_directory {
+            self.working_directory = Some(ctx.working_directory);
+        }
+
         // Handle special case: final_output needs to save session continuation
         if tool_call.tool == "final_output" {
             let summary = tool_call.args.get("summary").and_then(|v| v.as_str());
@@ -3359,7 +3627,9 @@ impl<W: UiWriter> Agent<W> {
 
 
 // Re-export utility functions
-pub use utils::apply_unified_diff_to_string;
+pub use utils::{
+    apply_unified_diff_detailed, apply_unified_diff_to_string, DiffApplyError, DiffApplyOptions,
+};
 
 // Implement Drop to clean up safaridriver process
 impl<W: UiWriter> Drop for Agent<W> {
@@ -3377,6 +3647,31 @@ impl<W: UiWriter> Drop for Agent<W> {
             }
         }
 
+        // Close the browser session so the browser itself doesn't outlive the agent.
+        // We can't await in Drop, so hand the quit (and driver shutdown) to the runtime.
+        if let (Ok(handle), Ok(mut session_guard)) = (
+            tokio::runtime::Handle::try_current(),
+            self.webdriver_session.try_write(),
+        ) {
+            if let Some(session) = session_guard.take() {
+                let process = self
+                    .webdriver_process
+                    .try_write()
+                    .ok()
+                    .and_then(|mut guard| guard.take());
+                handle.spawn(async move {
+                    if let Ok(mutex) = std::sync::Arc::try_unwrap(session) {
+                        use crate::computer_control::WebDriverController;
+                        let _ = mutex.into_inner().quit().await;
+                    }
+                    if let Some(mut process) = process {
+                        let _ = process.kill().await;
+                    }
+                });
+                debug!("Scheduled WebDriver session shutdown on Agent drop");
+            }
+        }
+
         // Try to kill safaridriver process if it's still running
         // We need to use try_lock since we can't await in Drop
         if let Ok(mut process_guard) = self.webdriver_process.try_write() {
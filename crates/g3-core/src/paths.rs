@@ -97,6 +97,46 @@ pub fn get_context_summary_file(session_id: &str) -> PathBuf {
     get_session_logs_dir(session_id).join("context_summary.txt")
 }
 
+/// Get the path to the structured final output file.
+/// Returns .g3/session/final_output.json
+pub fn get_final_output_file() -> PathBuf {
+    get_g3_dir().join("session").join("final_output.json")
+}
+
+/// Get the path of the Graphviz file written by the dependency_graph tool.
+/// Returns .g3/sessions/<session_id>/dependency_graph.dot, or
+/// .g3/session/dependency_graph.dot when there is no session.
+pub fn get_dependency_graph_file(session_id: Option<&str>) -> PathBuf {
+    match session_id {
+        Some(session_id) => get_session_logs_dir(session_id).join("dependency_graph.dot"),
+        None => get_g3_dir().join("session").join("dependency_graph.dot"),
+    }
+}
+
+/// Get the default path of the tool telemetry file.
+/// Returns .g3/session/telemetry.jsonl
+pub fn get_telemetry_file() -> PathBuf {
+    get_g3_dir().join("session").join("telemetry.jsonl")
+}
+
+/// Get the default path of the tool audit log.
+/// Returns .g3/audit.jsonl
+pub fn get_audit_log_file() -> PathBuf {
+    get_g3_dir().join("audit.jsonl")
+}
+
+/// Get the directory holding recorded tool-call macros.
+/// Returns .g3/macros/
+pub fn get_macros_dir() -> PathBuf {
+    get_g3_dir().join("macros")
+}
+
+/// Get the path of the semantic code search index.
+/// Returns .g3/embeddings.bin
+pub fn get_embeddings_index_path() -> PathBuf {
+    get_g3_dir().join("embeddings.bin")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -31,6 +31,8 @@ pub fn get_max_tokens(config: &Config, provider_name: &str) -> Option<u32> {
         "openai" => config.providers.openai.get(config_name)?.max_tokens,
         "databricks" => config.providers.databricks.get(config_name)?.max_tokens,
         "embedded" => config.providers.embedded.get(config_name)?.max_tokens,
+        "ollama" => config.providers.ollama.get(config_name)?.max_tokens,
+        "gemini" => config.providers.gemini.get(config_name)?.max_tokens,
         _ => None,
     }
 }
@@ -44,6 +46,8 @@ pub fn get_temperature(config: &Config, provider_name: &str) -> Option<f32> {
         "openai" => config.providers.openai.get(config_name)?.temperature,
         "databricks" => config.providers.databricks.get(config_name)?.temperature,
         "embedded" => config.providers.embedded.get(config_name)?.temperature,
+        "ollama" => config.providers.ollama.get(config_name)?.temperature,
+        "gemini" => config.providers.gemini.get(config_name)?.temperature,
         _ => None,
     }
 }
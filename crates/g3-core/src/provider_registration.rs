@@ -1,12 +1,13 @@
 //! Provider registration logic for the Agent.
 //!
-//! This module handles the registration of LLM providers (Anthropic, OpenAI, Databricks, Embedded)
-//! based on configuration. It consolidates the duplicated registration patterns into a single
-//! cohesive module.
+//! This module handles the registration of LLM providers (Anthropic, OpenAI, Databricks,
+//! Embedded, Ollama, Gemini) based on configuration. It consolidates the duplicated registration
+//! patterns into a single cohesive module.
 
 use anyhow::Result;
 use g3_config::Config;
-use g3_providers::ProviderRegistry;
+use g3_providers::{CacheControl, FallbackProvider, PromptCacheOptimizer, ProviderRegistry};
+use std::time::Duration;
 use tracing::debug;
 
 /// Determines which providers should be registered based on mode and configuration.
@@ -50,24 +51,107 @@ fn should_register(providers_to_register: &[String], provider_type: &str, config
 pub async fn register_providers(
     config: &Config,
     providers_to_register: &[String],
+) -> Result<ProviderRegistry> {
+    let mut registry = build_registry(config, providers_to_register).await?;
+    register_fallback_provider(config, &mut registry).await?;
+
+    // Set default provider
+    debug!(
+        "Setting default provider to: {}",
+        config.providers.default_provider
+    );
+    registry.set_default(&config.providers.default_provider)?;
+    debug!("Default provider set successfully");
+
+    Ok(registry)
+}
+
+async fn build_registry(
+    config: &Config,
+    providers_to_register: &[String],
 ) -> Result<ProviderRegistry> {
     let mut registry = ProviderRegistry::new();
 
     register_embedded_providers(config, providers_to_register, &mut registry)?;
     register_openai_providers(config, providers_to_register, &mut registry)?;
     register_openai_compatible_providers(config, providers_to_register, &mut registry)?;
+    register_ollama_providers(config, providers_to_register, &mut registry)?;
+    register_gemini_providers(config, providers_to_register, &mut registry)?;
     register_anthropic_providers(config, providers_to_register, &mut registry)?;
     register_databricks_providers(config, providers_to_register, &mut registry).await?;
 
-    // Set default provider
+    Ok(registry)
+}
+
+/// Wrap the default provider in a [`FallbackProvider`] when
+/// `providers.fallback_provider` is set.
+///
+/// The wrapper keeps the default provider's name, so lookups are unchanged.
+async fn register_fallback_provider(
+    config: &Config,
+    registry: &mut ProviderRegistry,
+) -> Result<()> {
+    let Some(fallback_ref) = &config.providers.fallback_provider else {
+        return Ok(());
+    };
+    let default_ref = &config.providers.default_provider;
+    if fallback_ref == default_ref {
+        anyhow::bail!("providers.fallback_provider must differ from default_provider");
+    }
+
+    let mut fallback_config = config.clone();
+    if let Some(model) = &config.providers.fallback_model {
+        set_provider_model(&mut fallback_config, fallback_ref, model)?;
+    }
+
+    // Built separately so fallback_model doesn't change a provider that is
+    // also used as coach or player.
+    let mut fallback_registry =
+        build_registry(&fallback_config, std::slice::from_ref(fallback_ref)).await?;
+    let fallback = fallback_registry
+        .take(fallback_ref)
+        .ok_or_else(|| anyhow::anyhow!("Fallback provider '{}' is not configured", fallback_ref))?;
+    let primary = registry
+        .take(default_ref)
+        .ok_or_else(|| anyhow::anyhow!("Provider '{}' not found", default_ref))?;
+
     debug!(
-        "Setting default provider to: {}",
-        config.providers.default_provider
+        "Using {} ({}) as fallback for {}",
+        fallback_ref,
+        fallback.model(),
+        default_ref
     );
-    registry.set_default(&config.providers.default_provider)?;
-    debug!("Default provider set successfully");
+    registry.register(
+        FallbackProvider::new(primary, fallback)?
+            .with_delay(Duration::from_millis(config.providers.fallback_delay_ms)),
+    );
+    Ok(())
+}
 
-    Ok(registry)
+/// Override the model of the provider config named by `provider_ref`
+/// ("<provider_type>.<config_name>"). Missing configs are left for
+/// registration to report.
+fn set_provider_model(config: &mut Config, provider_ref: &str, model: &str) -> Result<()> {
+    let (provider_type, name) = provider_ref
+        .split_once('.')
+        .unwrap_or((provider_ref, "default"));
+    let providers = &mut config.providers;
+    let slot = match provider_type {
+        "anthropic" => providers.anthropic.get_mut(name).map(|c| &mut c.model),
+        "openai" => providers.openai.get_mut(name).map(|c| &mut c.model),
+        "databricks" => providers.databricks.get_mut(name).map(|c| &mut c.model),
+        "ollama" => providers.ollama.get_mut(name).map(|c| &mut c.model),
+        "gemini" => providers.gemini.get_mut(name).map(|c| &mut c.model),
+        "embedded" => anyhow::bail!("fallback_model is not supported for embedded providers"),
+        compat => providers
+            .openai_compatible
+            .get_mut(compat)
+            .map(|c| &mut c.model),
+    };
+    if let Some(slot) = slot {
+        *slot = model.to_string();
+    }
+    Ok(())
 }
 
 /// Register embedded providers from configuration.
@@ -108,30 +192,107 @@ fn register_openai_providers(
                 openai_config.base_url.clone(),
                 openai_config.max_tokens,
                 openai_config.temperature,
-            )?;
+            )?
+            .with_cache_control(openai_config.cache_config.is_some());
             registry.register(openai_provider);
         }
     }
     Ok(())
 }
 
-/// Register OpenAI-compatible providers (e.g., OpenRouter, Groq) from configuration.
+/// Register OpenAI-compatible providers (e.g., OpenRouter, Groq, vLLM) from configuration.
+///
+/// Each entry is referenced by its name, e.g. `default_provider = "openrouter"`.
+/// Without a `base_url` it talks to the OpenAI API.
 fn register_openai_compatible_providers(
     config: &Config,
     providers_to_register: &[String],
     registry: &mut ProviderRegistry,
 ) -> Result<()> {
-    for (name, openai_config) in &config.providers.openai_compatible {
-        if should_register(providers_to_register, name, "default") {
-            let openai_provider = g3_providers::OpenAIProvider::new_with_name(
+    for (name, compat_config) in &config.providers.openai_compatible {
+        if providers_to_register.contains(name)
+            || should_register(providers_to_register, name, "default")
+        {
+            let base_url = compat_config
+                .base_url
+                .clone()
+                .unwrap_or_else(|| g3_providers::openai::DEFAULT_BASE_URL.to_string());
+            let mut provider = g3_providers::OpenAICompatibleProvider::new(
                 name.clone(),
-                openai_config.api_key.clone(),
-                Some(openai_config.model.clone()),
-                openai_config.base_url.clone(),
-                openai_config.max_tokens,
-                openai_config.temperature,
+                base_url,
+                compat_config.api_key.clone(),
+                compat_config.model.clone(),
+                compat_config.max_tokens,
+                compat_config.temperature,
+                &compat_config.extra_headers,
             )?;
-            registry.register(openai_provider);
+            if let Some(path) = &compat_config.chat_path {
+                provider = provider.with_chat_path(path.clone());
+            }
+            provider = provider.with_cache_control(compat_config.cache_config.is_some());
+            registry.register(provider);
+        }
+    }
+    Ok(())
+}
+
+/// Register Ollama providers from configuration.
+fn register_ollama_providers(
+    config: &Config,
+    providers_to_register: &[String],
+    registry: &mut ProviderRegistry,
+) -> Result<()> {
+    for (name, ollama_config) in &config.providers.ollama {
+        if should_register(providers_to_register, "ollama", name) {
+            let options = match serde_json::to_value(&ollama_config.options)? {
+                serde_json::Value::Object(options) => options,
+                _ => serde_json::Map::new(),
+            };
+            let ollama_provider = g3_providers::OllamaProvider::new_with_name(
+                format!("ollama.{}", name),
+                ollama_config.model.clone(),
+                ollama_config.base_url.clone(),
+                ollama_config.max_tokens,
+                ollama_config.temperature,
+                options,
+            )?;
+            registry.register(ollama_provider);
+        }
+    }
+    Ok(())
+}
+
+/// Register Gemini providers from configuration.
+fn register_gemini_providers(
+    config: &Config,
+    providers_to_register: &[String],
+    registry: &mut ProviderRegistry,
+) -> Result<()> {
+    for (name, gemini_config) in &config.providers.gemini {
+        if should_register(providers_to_register, "gemini", name) {
+            let generation_config = match serde_json::to_value(&gemini_config.generation_config)? {
+                serde_json::Value::Object(generation_config) => generation_config,
+                _ => serde_json::Map::new(),
+            };
+            let safety_settings = gemini_config
+                .safety_settings
+                .iter()
+                .map(|setting| g3_providers::gemini::SafetySetting {
+                    category: setting.category.clone(),
+                    threshold: setting.threshold.clone(),
+                })
+                .collect();
+            let gemini_provider = g3_providers::GeminiProvider::new_with_name(
+                format!("gemini.{}", name),
+                gemini_config.api_key.clone(),
+                gemini_config.model.clone(),
+                gemini_config.base_url.clone(),
+                gemini_config.max_tokens,
+                gemini_config.temperature,
+            )?
+            .with_safety_settings(safety_settings)
+            .with_generation_config(generation_config);
+            registry.register(gemini_provider);
         }
     }
     Ok(())
@@ -172,6 +333,22 @@ fn register_anthropic_providers(
                     anthropic_config.thinking_budget_tokens,
                 )?
             };
+            // With caching enabled, also cache the system prompt and tool definitions
+            let anthropic_provider = match anthropic_config
+                .cache_config
+                .as_deref()
+                .and_then(CacheControl::from_config)
+            {
+                Some(cache_control) => {
+                    let mut optimizer =
+                        PromptCacheOptimizer::new().with_cache_control(cache_control);
+                    if let Some(min_tokens) = anthropic_config.prompt_cache_min_tokens {
+                        optimizer = optimizer.with_min_tokens(min_tokens);
+                    }
+                    anthropic_provider.with_prompt_cache(optimizer)
+                }
+                None => anthropic_provider,
+            };
             registry.register(anthropic_provider);
         }
     }
@@ -239,6 +416,143 @@ mod tests {
         assert!(!should_register(&providers, "anthropic", "default"));
     }
 
+    #[tokio::test]
+    async fn test_openai_compatible_provider_selected_from_config() {
+        let mut config = Config::default();
+        config.providers.openai_compatible.insert(
+            "vllm".to_string(),
+            g3_config::OpenAIConfig {
+                api_key: String::new(),
+                model: "Qwen/Qwen2.5-Coder-32B-Instruct".to_string(),
+                base_url: Some("http://localhost:8000/v1".to_string()),
+                max_tokens: Some(8192),
+                temperature: None,
+                extra_headers: [("X-Team".to_string(), "infra".to_string())].into(),
+                chat_path: None,
+                cache_config: None,
+            },
+        );
+        config.providers.default_provider = "vllm".to_string();
+
+        let providers = determine_providers_to_register(&config, false);
+        let registry = register_providers(&config, &providers).await.unwrap();
+
+        let provider = registry.get(None).unwrap();
+        assert_eq!(provider.name(), "vllm");
+        assert_eq!(provider.model(), "Qwen/Qwen2.5-Coder-32B-Instruct");
+        assert_eq!(provider.max_tokens(), 8192);
+    }
+
+    #[tokio::test]
+    async fn test_gemini_provider_selected_from_config() {
+        let mut config = Config::default();
+        config.providers.gemini.insert(
+            "default".to_string(),
+            g3_config::GeminiConfig {
+                api_key: "test-key".to_string(),
+                model: "gemini-2.5-pro".to_string(),
+                base_url: None,
+                max_tokens: Some(16384),
+                temperature: None,
+                safety_settings: vec![g3_config::GeminiSafetySetting {
+                    category: "HARM_CATEGORY_DANGEROUS_CONTENT".to_string(),
+                    threshold: "BLOCK_ONLY_HIGH".to_string(),
+                }],
+                generation_config: Default::default(),
+            },
+        );
+        config.providers.default_provider = "gemini.default".to_string();
+
+        let providers = determine_providers_to_register(&config, false);
+        let registry = register_providers(&config, &providers).await.unwrap();
+
+        let provider = registry.get(None).unwrap();
+        assert_eq!(provider.name(), "gemini.default");
+        assert_eq!(provider.model(), "gemini-2.5-pro");
+        assert_eq!(provider.max_tokens(), 16384);
+        assert!(provider.has_native_tool_calling());
+    }
+
+    fn compat_config(model: &str, base_url: &str) -> g3_config::OpenAIConfig {
+        g3_config::OpenAIConfig {
+            api_key: String::new(),
+            model: model.to_string(),
+            base_url: Some(base_url.to_string()),
+            max_tokens: Some(4096),
+            temperature: None,
+            extra_headers: Default::default(),
+            chat_path: None,
+            cache_config: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_openai_compatible_provider_without_base_url() {
+        let mut config = Config::default();
+        let mut openrouter = compat_config("gpt-4o", "unused");
+        openrouter.base_url = None;
+        config.providers.openai_compatible.insert("openrouter".to_string(), openrouter);
+        config.providers.default_provider = "openrouter".to_string();
+
+        let providers = determine_providers_to_register(&config, false);
+        let registry = register_providers(&config, &providers).await.unwrap();
+        assert_eq!(registry.get(None).unwrap().name(), "openrouter");
+    }
+
+    #[tokio::test]
+    async fn test_fallback_provider_wraps_default() {
+        let mut config = Config::default();
+        config.providers.openai_compatible.insert(
+            "vllm".to_string(),
+            compat_config("local-model", "http://localhost:8000/v1"),
+        );
+        config.providers.openai_compatible.insert(
+            "backup".to_string(),
+            compat_config("backup-model", "http://localhost:9000/v1"),
+        );
+        config.providers.default_provider = "vllm".to_string();
+        config.providers.fallback_provider = Some("backup".to_string());
+        config.providers.fallback_model = Some("backup-small".to_string());
+
+        let providers = determine_providers_to_register(&config, false);
+        let registry = register_providers(&config, &providers).await.unwrap();
+
+        // The wrapper reports the primary provider
+        let provider = registry.get(None).unwrap();
+        assert_eq!(provider.name(), "vllm");
+        assert_eq!(provider.model(), "local-model");
+
+        let mut fallback_config = config.clone();
+        set_provider_model(&mut fallback_config, "backup", "backup-small").unwrap();
+        assert_eq!(
+            fallback_config.providers.openai_compatible["backup"].model,
+            "backup-small"
+        );
+        assert_eq!(
+            fallback_config.providers.openai_compatible["vllm"].model,
+            "local-model"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fallback_provider_must_be_configured() {
+        let mut config = Config::default();
+        config.providers.openai_compatible.insert(
+            "vllm".to_string(),
+            compat_config("local-model", "http://localhost:8000/v1"),
+        );
+        config.providers.default_provider = "vllm".to_string();
+
+        config.providers.fallback_provider = Some("missing.default".to_string());
+        let providers = determine_providers_to_register(&config, false);
+        let err = register_providers(&config, &providers).await.err().unwrap();
+        assert!(err.to_string().contains("not configured"));
+
+        config.providers.fallback_provider = Some("vllm".to_string());
+        let err = register_providers(&config, &providers).await.err().unwrap();
+        assert!(err.to_string().contains("must differ"));
+    }
+
     #[test]
     fn test_determine_providers_normal_mode() {
         // Create a minimal config for testing
@@ -9,7 +9,7 @@ use crate::paths::{ensure_session_dir, get_context_summary_file, get_g3_dir, get
 use g3_providers::MessageRole;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
-use std::path::PathBuf;
+use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{debug, error};
 
@@ -135,6 +135,12 @@ pub fn save_context_window(
         Ok(json_content) => {
             if let Err(e) = std::fs::write(&filename, &json_content) {
                 error!("Failed to save context window to {:?}: {}", &filename, e);
+            } else {
+                // The plain log is now newer than any compressed copy
+                let compressed = crate::session_continuation::compressed_log_path(&filename);
+                if compressed.exists() {
+                    let _ = std::fs::remove_file(compressed);
+                }
             }
         }
         Err(e) => {
@@ -299,12 +305,12 @@ pub fn log_error_to_session(
 /// Restore conversation history from a session log file.
 ///
 /// Returns the messages to add to the context window, or None if restoration failed.
-pub fn restore_from_session_log(session_log_path: &PathBuf) -> Option<Vec<(MessageRole, String)>> {
+pub fn restore_from_session_log(session_log_path: &Path) -> Option<Vec<(MessageRole, String)>> {
     if !session_log_path.exists() {
         return None;
     }
 
-    let json = std::fs::read_to_string(session_log_path).ok()?;
+    let json = crate::session_continuation::read_session_log(session_log_path).ok()?;
     let session_data: serde_json::Value = serde_json::from_str(&json).ok()?;
 
     let context_window = session_data.get("context_window")?;
@@ -7,18 +7,126 @@
 //! - `.g3/session` is a symlink pointing to the current session directory
 //! - `latest.json` is stored inside each session directory (`.g3/sessions/<session_id>/latest.json`)
 //! - Following the symlink gives access to the current session's continuation data
+//!
+//! Checkpoints fork a session: `.g3/session/checkpoints/<name>/` holds a copy of
+//! `latest.json` and the session log, which can be restored later to return to
+//! that point.
+//!
+//! When `[encryption]` is configured, continuation files are written encrypted
+//! with a passphrase read from the configured environment variable (see
+//! [`crate::encryption`]). Plaintext files from before are still read.
 
 use anyhow::{Context, Result};
+use g3_config::EncryptionConfig;
+use g3_providers::{Message, MessageRole};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 use tracing::{debug, error, warn};
 
+use crate::encryption;
+
 /// Version of the session continuation format
 const CONTINUATION_VERSION: &str = "1.0";
 
 /// Name of the continuation file within each session directory
 const CONTINUATION_FILENAME: &str = "latest.json";
 
+/// Context usage above which the session log is compressed on save
+const COMPRESSION_THRESHOLD_PERCENT: f32 = 60.0;
+
+/// Extension appended to compressed session logs
+const COMPRESSED_EXTENSION: &str = "zst";
+
+/// Number of recent messages kept in the continuation's message summary
+const MESSAGE_SUMMARY_COUNT: usize = 20;
+
+/// Maximum characters of content kept per summarized message
+const MESSAGE_PREVIEW_CHARS: usize = 500;
+
+/// Upper bound on the serialized size of the message summary
+const MESSAGE_SUMMARY_MAX_BYTES: usize = 10 * 1024;
+
+/// Short record of a recent message, so a resumed session can see what
+/// happened even if `final_output` was never called.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageSummary {
+    pub role: String,
+    pub content_preview: String,
+    pub timestamp: String,
+    /// Names of tools called in an assistant message
+    pub tool_calls: Vec<String>,
+}
+
+impl MessageSummary {
+    /// Summarize a message. `fallback_timestamp` is used when the message id
+    /// does not carry its creation time.
+    pub fn from_message(message: &Message, fallback_timestamp: &str) -> Self {
+        let role = match message.role {
+            MessageRole::System => "system",
+            MessageRole::User => "user",
+            MessageRole::Assistant => "assistant",
+        };
+
+        let mut content_preview: String =
+            message.content.chars().take(MESSAGE_PREVIEW_CHARS).collect();
+        if content_preview.len() < message.content.len() {
+            content_preview.push('…');
+        }
+
+        // Message ids start with the local creation time as HHMMSS
+        let timestamp = match message.id.get(..6) {
+            Some(hms) if hms.bytes().all(|b| b.is_ascii_digit()) => {
+                format!("{}:{}:{}", &hms[..2], &hms[2..4], &hms[4..])
+            }
+            _ => fallback_timestamp.to_string(),
+        };
+
+        let tool_calls = if matches!(message.role, MessageRole::Assistant) {
+            extract_tool_names(&message.content)
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            role: role.to_string(),
+            content_preview,
+            timestamp,
+            tool_calls,
+        }
+    }
+}
+
+/// Find the names of JSON tool calls (`{"tool": "name", ...}`) in message content.
+fn extract_tool_names(content: &str) -> Vec<String> {
+    let re = regex::Regex::new(r#""tool"\s*:\s*"([^"]+)""#).expect("valid regex");
+    re.captures_iter(content)
+        .map(|cap| cap[1].to_string())
+        .collect()
+}
+
+/// Summarize the last non-system messages, dropping the oldest entries if the
+/// summary would exceed `MESSAGE_SUMMARY_MAX_BYTES`.
+pub fn summarize_messages(messages: &[&Message], fallback_timestamp: &str) -> Vec<MessageSummary> {
+    let recent: Vec<&&Message> = messages
+        .iter()
+        .filter(|m| !matches!(m.role, MessageRole::System))
+        .collect();
+    let start = recent.len().saturating_sub(MESSAGE_SUMMARY_COUNT);
+
+    let mut summary: Vec<MessageSummary> = recent[start..]
+        .iter()
+        .map(|m| MessageSummary::from_message(m, fallback_timestamp))
+        .collect();
+
+    while !summary.is_empty()
+        && serde_json::to_string(&summary).map(|s| s.len()).unwrap_or(0) > MESSAGE_SUMMARY_MAX_BYTES
+    {
+        summary.remove(0);
+    }
+    summary
+}
+
 /// Session continuation artifact containing all information needed to resume a session
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionContinuation {
@@ -42,6 +150,12 @@ pub struct SessionContinuation {
     pub todo_snapshot: Option<String>,
     /// Working directory where the session was running
     pub working_directory: String,
+    /// Whether `session_log_path` points to a zstd-compressed log
+    #[serde(default)]
+    pub compressed: bool,
+    /// Summary of the most recent messages in the session
+    #[serde(default)]
+    pub message_summary: Vec<MessageSummary>,
 }
 
 impl SessionContinuation {
@@ -67,9 +181,17 @@ impl SessionContinuation {
             context_percentage,
             todo_snapshot,
             working_directory,
+            compressed: false,
+            message_summary: Vec::new(),
         }
     }
 
+    /// The session log as it is on disk now. Logs rewritten after being
+    /// compressed are plain again, so `session_log_path` may be stale.
+    pub fn session_log(&self) -> Option<PathBuf> {
+        find_session_log(Path::new(&self.session_log_path))
+    }
+
     /// Check if the context can be fully restored (< 80% used)
     pub fn can_restore_full_context(&self) -> bool {
         self.context_percentage < 80.0
@@ -151,9 +273,89 @@ fn update_session_symlink(session_id: &str) -> Result<()> {
     Ok(())
 }
 
+/// Encryption settings for continuation files, set from the config at startup.
+static ENCRYPTION: RwLock<Option<EncryptionConfig>> = RwLock::new(None);
+
+/// Encrypt continuation files written from now on with `config`, or stop
+/// encrypting them when `None`.
+pub fn configure_encryption(config: Option<EncryptionConfig>) -> Result<()> {
+    if let Some(config) = &config {
+        if !encryption::SUPPORTED_ALGORITHMS.contains(&config.algorithm.as_str()) {
+            anyhow::bail!(
+                "Unsupported encryption algorithm '{}' (supported: {})",
+                config.algorithm,
+                encryption::SUPPORTED_ALGORITHMS.join(", ")
+            );
+        }
+        if config.passphrase_env.is_empty() {
+            anyhow::bail!("encryption.passphrase_env must name an environment variable");
+        }
+    }
+    *ENCRYPTION.write().unwrap_or_else(|e| e.into_inner()) = config;
+    Ok(())
+}
+
+/// The configured passphrase, or `None` when encryption is not configured.
+fn continuation_passphrase() -> Result<Option<String>> {
+    let config = ENCRYPTION.read().unwrap_or_else(|e| e.into_inner());
+    let Some(config) = config.as_ref() else {
+        return Ok(None);
+    };
+    match std::env::var(&config.passphrase_env) {
+        Ok(passphrase) if !passphrase.is_empty() => Ok(Some(passphrase)),
+        _ => anyhow::bail!(
+            "Session encryption is configured but {} is not set",
+            config.passphrase_env
+        ),
+    }
+}
+
+/// Serialize `continuation`, encrypting it when a passphrase is given.
+pub fn encode_continuation(
+    continuation: &SessionContinuation,
+    passphrase: Option<&str>,
+) -> Result<Vec<u8>> {
+    let json = serde_json::to_string_pretty(continuation)?;
+    match passphrase {
+        Some(passphrase) => encryption::encrypt(json.as_bytes(), passphrase),
+        None => Ok(json.into_bytes()),
+    }
+}
+
+/// Parse a continuation file, decrypting it first if it is encrypted.
+pub fn decode_continuation(data: &[u8], passphrase: Option<&str>) -> Result<SessionContinuation> {
+    if !encryption::is_encrypted(data) {
+        return serde_json::from_slice(data).context("Failed to parse session continuation");
+    }
+    let passphrase = passphrase
+        .context("Session continuation is encrypted but no passphrase is configured")?;
+    let json = encryption::decrypt(data, passphrase)?;
+    serde_json::from_slice(&json).context("Failed to parse session continuation")
+}
+
+fn write_continuation_file(path: &Path, continuation: &SessionContinuation) -> Result<()> {
+    let passphrase = continuation_passphrase()?;
+    std::fs::write(path, encode_continuation(continuation, passphrase.as_deref())?)
+        .with_context(|| format!("Failed to write {:?}", path))
+}
+
+fn read_continuation_file(path: &Path) -> Result<SessionContinuation> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let passphrase = if encryption::is_encrypted(&data) {
+        continuation_passphrase()?
+    } else {
+        None
+    };
+    decode_continuation(&data, passphrase.as_deref())
+}
+
 /// Save a session continuation artifact
-/// This saves latest.json in the session's directory and updates the symlink
-pub fn save_continuation(continuation: &SessionContinuation) -> Result<PathBuf> {
+/// This saves latest.json in the session's directory and updates the symlink.
+/// When `messages` is non-empty, a summary of the most recent ones is stored too.
+pub fn save_continuation(
+    continuation: &SessionContinuation,
+    messages: &[&Message],
+) -> Result<PathBuf> {
     let session_id = &continuation.session_id;
     let session_path = get_session_path(session_id);
     
@@ -163,10 +365,28 @@ pub fn save_continuation(continuation: &SessionContinuation) -> Result<PathBuf>
             .context("Failed to create session directory")?;
     }
     
+    let mut continuation = continuation.clone();
+    if !messages.is_empty() {
+        continuation.message_summary = summarize_messages(messages, &continuation.created_at);
+    }
+
+    // Large sessions get their log compressed; the continuation then points at the .zst file
+    if continuation.context_percentage > COMPRESSION_THRESHOLD_PERCENT && !continuation.compressed {
+        let log_path = PathBuf::from(&continuation.session_log_path);
+        if log_path.exists() {
+            match compress_session_log(&log_path) {
+                Ok(compressed_path) => {
+                    continuation.session_log_path = compressed_path.to_string_lossy().to_string();
+                    continuation.compressed = true;
+                }
+                Err(e) => warn!("Failed to compress session log {:?}: {}", log_path, e),
+            }
+        }
+    }
+
     // Save latest.json in the session directory
     let latest_path = session_path.join(CONTINUATION_FILENAME);
-    let json = serde_json::to_string_pretty(continuation)?;
-    std::fs::write(&latest_path, &json)?;
+    write_continuation_file(&latest_path, &continuation)?;
     
     // Update the symlink to point to this session
     update_session_symlink(session_id)?;
@@ -201,8 +421,7 @@ pub fn load_continuation() -> Result<Option<SessionContinuation>> {
         return Ok(None);
     }
     
-    let json = std::fs::read_to_string(&latest_path)?;
-    let continuation: SessionContinuation = serde_json::from_str(&json)?;
+    let continuation = read_continuation_file(&latest_path)?;
     
     // Validate version
     if continuation.version != CONTINUATION_VERSION {
@@ -246,10 +465,9 @@ pub fn clear_continuation() -> Result<()> {
 pub fn has_valid_continuation() -> bool {
     match load_continuation() {
         Ok(Some(continuation)) => {
-            // Check if the session log still exists
-            let session_log_path = PathBuf::from(&continuation.session_log_path);
-            if !session_log_path.exists() {
-                warn!("Session log no longer exists: {:?}", session_log_path);
+            // Check if the session log still exists, compressed or not
+            if continuation.session_log().is_none() {
+                warn!("Session log no longer exists: {:?}", continuation.session_log_path);
                 return false;
             }
             
@@ -276,13 +494,198 @@ pub fn has_valid_continuation() -> bool {
     }
 }
 
-/// Load the full context window from a session log file
+/// Directory inside a session directory that holds its checkpoints
+const CHECKPOINTS_DIRNAME: &str = "checkpoints";
+
+/// A saved checkpoint, as listed by [`list_checkpoints`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointInfo {
+    pub name: String,
+    /// When the checkpoint was saved
+    pub created_at: String,
+    pub session_id: String,
+    /// Context window usage percentage when saved
+    pub context_percentage: f32,
+    pub path: PathBuf,
+}
+
+/// Checkpoint names become directory names, so only allow a safe subset.
+fn validate_checkpoint_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        anyhow::bail!(
+            "Invalid checkpoint name '{}': use letters, digits, '-', '_' and '.'",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Save the current session as checkpoint `name`, replacing any checkpoint
+/// with the same name. Returns the checkpoint directory.
+pub fn save_checkpoint(name: &str) -> Result<PathBuf> {
+    save_checkpoint_in(&get_session_dir(), name)
+}
+
+/// Copy `latest.json` and the session log from `session_dir` into
+/// `session_dir/checkpoints/<name>/`.
+pub fn save_checkpoint_in(session_dir: &Path, name: &str) -> Result<PathBuf> {
+    validate_checkpoint_name(name)?;
+
+    let latest_path = session_dir.join(CONTINUATION_FILENAME);
+    if !latest_path.exists() {
+        anyhow::bail!("No session to checkpoint yet");
+    }
+    let mut continuation =
+        read_continuation_file(&latest_path).context("Failed to read latest.json")?;
+
+    let checkpoint_dir = session_dir.join(CHECKPOINTS_DIRNAME).join(name);
+    if checkpoint_dir.exists() {
+        std::fs::remove_dir_all(&checkpoint_dir)
+            .with_context(|| format!("Failed to replace checkpoint '{}'", name))?;
+    }
+    std::fs::create_dir_all(&checkpoint_dir).context("Failed to create checkpoint directory")?;
+
+    // The checkpoint keeps its own copy of the log, which later turns keep rewriting
+    if let Some(log_path) = continuation.session_log() {
+        if let Some(file_name) = log_path.file_name() {
+            let log_copy = checkpoint_dir.join(file_name);
+            std::fs::copy(&log_path, &log_copy)
+                .with_context(|| format!("Failed to copy session log {:?}", log_path))?;
+            continuation.compressed =
+                log_copy.extension().is_some_and(|ext| ext == COMPRESSED_EXTENSION);
+            continuation.session_log_path = log_copy.to_string_lossy().to_string();
+        }
+    }
+    continuation.created_at = chrono::Utc::now().to_rfc3339();
+
+    write_continuation_file(&checkpoint_dir.join(CONTINUATION_FILENAME), &continuation)?;
+
+    debug!("Saved checkpoint '{}' to {:?}", name, checkpoint_dir);
+    Ok(checkpoint_dir)
+}
+
+/// Load checkpoint `name` of the current session.
+pub fn restore_checkpoint(name: &str) -> Result<SessionContinuation> {
+    restore_checkpoint_in(&get_session_dir(), name)
+}
+
+/// Load the continuation saved in `session_dir/checkpoints/<name>/`. Its
+/// session log path points at the checkpoint's copy of the log.
+pub fn restore_checkpoint_in(session_dir: &Path, name: &str) -> Result<SessionContinuation> {
+    validate_checkpoint_name(name)?;
+
+    let path = session_dir
+        .join(CHECKPOINTS_DIRNAME)
+        .join(name)
+        .join(CONTINUATION_FILENAME);
+    if !path.exists() {
+        anyhow::bail!("No checkpoint named '{}'", name);
+    }
+    let continuation = read_continuation_file(&path)
+        .with_context(|| format!("Failed to read checkpoint '{}'", name))?;
+
+    debug!("Loaded checkpoint '{}' from {:?}", name, path);
+    Ok(continuation)
+}
+
+/// Checkpoints of the current session, oldest first.
+pub fn list_checkpoints() -> Result<Vec<CheckpointInfo>> {
+    list_checkpoints_in(&get_session_dir())
+}
+
+/// Checkpoints saved under `session_dir`, oldest first. Unreadable ones are skipped.
+pub fn list_checkpoints_in(session_dir: &Path) -> Result<Vec<CheckpointInfo>> {
+    let checkpoints_dir = session_dir.join(CHECKPOINTS_DIRNAME);
+    if !checkpoints_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut checkpoints = Vec::new();
+    for entry in std::fs::read_dir(&checkpoints_dir)? {
+        let path = entry?.path();
+        let latest_path = path.join(CONTINUATION_FILENAME);
+        if !latest_path.exists() {
+            continue;
+        }
+        let Ok(continuation) = read_continuation_file(&latest_path) else {
+            warn!("Skipping unreadable checkpoint {:?}", path);
+            continue;
+        };
+        checkpoints.push(CheckpointInfo {
+            name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            created_at: continuation.created_at,
+            session_id: continuation.session_id,
+            context_percentage: continuation.context_percentage,
+            path,
+        });
+    }
+    checkpoints.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    Ok(checkpoints)
+}
+
+/// Compress a session log with zstd, replacing it with `<log>.zst`.
+/// Returns the path of the compressed log.
+pub fn compress_session_log(session_log_path: &Path) -> Result<PathBuf> {
+    let compressed_path = compressed_log_path(session_log_path);
+
+    let input = std::fs::File::open(session_log_path)
+        .with_context(|| format!("Failed to open session log {:?}", session_log_path))?;
+    let output = std::fs::File::create(&compressed_path)
+        .with_context(|| format!("Failed to create {:?}", compressed_path))?;
+    zstd::stream::copy_encode(input, output, 0).context("Failed to compress session log")?;
+    std::fs::remove_file(session_log_path)?;
+
+    debug!("Compressed session log to {:?}", compressed_path);
+    Ok(compressed_path)
+}
+
+/// The session log `path` as it is on disk: the plain log if it exists,
+/// otherwise its compressed `<log>.zst` copy. `path` may name either.
+pub fn find_session_log(path: &Path) -> Option<PathBuf> {
+    let plain = if path.extension().is_some_and(|ext| ext == COMPRESSED_EXTENSION) {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
+    };
+    if plain.exists() {
+        return Some(plain);
+    }
+    let compressed = compressed_log_path(&plain);
+    compressed.exists().then_some(compressed)
+}
+
+/// `<log>.zst` for the session log `session_log_path`.
+pub fn compressed_log_path(session_log_path: &Path) -> PathBuf {
+    let mut compressed_name = session_log_path.as_os_str().to_owned();
+    compressed_name.push(".");
+    compressed_name.push(COMPRESSED_EXTENSION);
+    PathBuf::from(compressed_name)
+}
+
+/// Read a session log, decompressing it if it is a `.zst` file.
+pub fn read_session_log(session_log_path: &Path) -> Result<String> {
+    if session_log_path.extension().is_some_and(|ext| ext == COMPRESSED_EXTENSION) {
+        let file = std::fs::File::open(session_log_path)?;
+        let bytes = zstd::stream::decode_all(file).context("Failed to decompress session log")?;
+        Ok(String::from_utf8(bytes)?)
+    } else {
+        Ok(std::fs::read_to_string(session_log_path)?)
+    }
+}
+
+/// Load the full context window from a session log file.
+/// The log is found whether or not it is currently compressed.
 pub fn load_context_from_session_log(session_log_path: &Path) -> Result<Option<serde_json::Value>> {
-    if !session_log_path.exists() {
+    let Some(session_log_path) = find_session_log(session_log_path) else {
         return Ok(None);
-    }
+    };
     
-    let json = std::fs::read_to_string(session_log_path)?;
+    let json = read_session_log(&session_log_path)?;
     let session_data: serde_json::Value = serde_json::from_str(&json)?;
     
     Ok(Some(session_data))
@@ -323,14 +726,9 @@ pub fn find_incomplete_agent_session(agent_name: &str) -> Result<Option<SessionC
         }
         
         // Try to load the continuation
-        let json = match std::fs::read_to_string(&latest_path) {
-            Ok(j) => j,
-            Err(_) => continue,
-        };
-        
-        let continuation: SessionContinuation = match serde_json::from_str(&json) {
+        let continuation = match read_continuation_file(&latest_path) {
             Ok(c) => c,
-            Err(_) => continue, // Skip sessions with old format
+            Err(_) => continue, // Skip old formats and sessions we cannot decrypt
         };
         
         // Check if this is an agent mode session with matching name
@@ -440,4 +838,305 @@ mod tests {
         continuation.todo_snapshot = None;
         assert!(!continuation.has_incomplete_todos());
     }
+
+    #[test]
+    fn test_compressed_session_log_roundtrip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log_path = temp_dir.path().join("test_session.json");
+
+        // A large, repetitive session log like a long-running session produces
+        let messages: Vec<serde_json::Value> = (0..5000)
+            .map(|i| {
+                serde_json::json!({
+                    "role": if i % 2 == 0 { "user" } else { "assistant" },
+                    "content": format!("message {} with some repeated filler text", i),
+                })
+            })
+            .collect();
+        let session = serde_json::json!({
+            "context_window": { "conversation_history": messages }
+        });
+        std::fs::write(&log_path, serde_json::to_string_pretty(&session).unwrap()).unwrap();
+        let original_size = std::fs::metadata(&log_path).unwrap().len();
+
+        let compressed_path = compress_session_log(&log_path).unwrap();
+        assert_eq!(compressed_path, temp_dir.path().join("test_session.json.zst"));
+        assert!(!log_path.exists());
+        assert!(std::fs::metadata(&compressed_path).unwrap().len() < original_size);
+
+        let loaded = load_context_from_session_log(&compressed_path).unwrap().unwrap();
+        assert_eq!(loaded, session);
+    }
+
+    #[test]
+    fn test_session_log_is_found_compressed_or_not() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log_path = temp_dir.path().join("session.json");
+        assert_eq!(find_session_log(&log_path), None);
+        assert!(load_context_from_session_log(&log_path).unwrap().is_none());
+
+        std::fs::write(&log_path, r#"{"status": "compressed"}"#).unwrap();
+        let compressed_path = compress_session_log(&log_path).unwrap();
+        assert_eq!(find_session_log(&log_path), Some(compressed_path.clone()));
+        let loaded = load_context_from_session_log(&log_path).unwrap().unwrap();
+        assert_eq!(loaded["status"], "compressed");
+
+        // A log written again after compression is read instead of the stale copy
+        std::fs::write(&log_path, r#"{"status": "rewritten"}"#).unwrap();
+        assert_eq!(find_session_log(&compressed_path), Some(log_path.clone()));
+        let loaded = load_context_from_session_log(&compressed_path).unwrap().unwrap();
+        assert_eq!(loaded["status"], "rewritten");
+        assert_eq!(read_session_log(&log_path).unwrap(), r#"{"status": "rewritten"}"#);
+    }
+
+    #[test]
+    fn test_compressed_defaults_to_false_for_old_continuations() {
+        let json = r#"{
+            "version": "1.0",
+            "is_agent_mode": false,
+            "agent_name": null,
+            "created_at": "2025-01-01T00:00:00Z",
+            "session_id": "old",
+            "final_output_summary": null,
+            "session_log_path": "/tmp/session.json",
+            "context_percentage": 70.0,
+            "todo_snapshot": null,
+            "working_directory": "."
+        }"#;
+        let continuation: SessionContinuation = serde_json::from_str(json).unwrap();
+        assert!(!continuation.compressed);
+    }
+
+    #[test]
+    fn test_message_summary_keeps_recent_messages_and_tool_names() {
+        let system = Message::new(MessageRole::System, "You are g3".to_string());
+        let mut messages = vec![system];
+        for i in 0..30 {
+            messages.push(Message::new(MessageRole::User, format!("request {}", i)));
+            messages.push(Message::new(
+                MessageRole::Assistant,
+                format!(r#"Working on it {{"tool": "shell", "args": {{"command": "ls {}"}}}}"#, i),
+            ));
+        }
+        let refs: Vec<&Message> = messages.iter().collect();
+
+        let summary = summarize_messages(&refs, "2024-01-01T00:00:00Z");
+
+        assert_eq!(summary.len(), MESSAGE_SUMMARY_COUNT);
+        assert!(summary.iter().all(|m| m.role != "system"));
+        let last = summary.last().unwrap();
+        assert_eq!(last.role, "assistant");
+        assert_eq!(last.tool_calls, vec!["shell".to_string()]);
+        assert!(last.content_preview.contains("ls 29"));
+        assert_eq!(last.timestamp.len(), "HH:MM:SS".len());
+    }
+
+    #[test]
+    fn test_message_summary_is_bounded() {
+        let long = "x".repeat(5000);
+        let messages: Vec<Message> = (0..40)
+            .map(|_| Message::new(MessageRole::User, long.clone()))
+            .collect();
+        let refs: Vec<&Message> = messages.iter().collect();
+
+        let summary = summarize_messages(&refs, "now");
+
+        assert!(summary
+            .iter()
+            .all(|m| m.content_preview.chars().count() <= MESSAGE_PREVIEW_CHARS + 1));
+        assert!(serde_json::to_string(&summary).unwrap().len() <= MESSAGE_SUMMARY_MAX_BYTES);
+    }
+
+    #[test]
+    fn test_message_summary_serialization_roundtrip() {
+        let mut continuation = SessionContinuation::new(
+            false,
+            None,
+            "test".to_string(),
+            None,
+            "path".to_string(),
+            10.0,
+            None,
+            ".".to_string(),
+        );
+        continuation.message_summary = vec![MessageSummary {
+            role: "assistant".to_string(),
+            content_preview: "Reading the file".to_string(),
+            timestamp: "12:34:56".to_string(),
+            tool_calls: vec!["read_file".to_string(), "str_replace".to_string()],
+        }];
+
+        let json = serde_json::to_string(&continuation).unwrap();
+        let restored: SessionContinuation = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.message_summary, continuation.message_summary);
+
+        // Continuations saved before the field existed still load
+        let mut legacy: serde_json::Value = serde_json::from_str(&json).unwrap();
+        legacy.as_object_mut().unwrap().remove("message_summary");
+        let restored: SessionContinuation = serde_json::from_value(legacy).unwrap();
+        assert!(restored.message_summary.is_empty());
+    }
+
+    /// A session directory with a log and latest.json, as save_continuation leaves it.
+    fn session_with_log(dir: &Path, messages: &[&str]) -> SessionContinuation {
+        let log_path = dir.join("session.json");
+        let history: Vec<serde_json::Value> = messages
+            .iter()
+            .map(|m| serde_json::json!({ "role": "user", "content": m }))
+            .collect();
+        let log = serde_json::json!({ "context_window": { "conversation_history": history } });
+        std::fs::write(&log_path, log.to_string()).unwrap();
+
+        let continuation = SessionContinuation::new(
+            false,
+            None,
+            "session_1".to_string(),
+            Some("Added the parser".to_string()),
+            log_path.to_string_lossy().to_string(),
+            30.0,
+            None,
+            dir.to_string_lossy().to_string(),
+        );
+        std::fs::write(
+            dir.join(CONTINUATION_FILENAME),
+            serde_json::to_string(&continuation).unwrap(),
+        )
+        .unwrap();
+        continuation
+    }
+
+    #[test]
+    fn test_checkpoint_save_restore_roundtrip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let session_dir = temp_dir.path();
+        session_with_log(session_dir, &["first", "second"]);
+
+        let checkpoint_dir = save_checkpoint_in(session_dir, "before-refactor").unwrap();
+        assert_eq!(checkpoint_dir, session_dir.join("checkpoints/before-refactor"));
+
+        // The session carries on past the checkpoint
+        session_with_log(session_dir, &["first", "second", "a dead end"]);
+
+        let restored = restore_checkpoint_in(session_dir, "before-refactor").unwrap();
+        assert_eq!(restored.session_id, "session_1");
+        assert_eq!(restored.final_output_summary.as_deref(), Some("Added the parser"));
+        let log_path = PathBuf::from(&restored.session_log_path);
+        assert_eq!(log_path, checkpoint_dir.join("session.json"));
+
+        let log = load_context_from_session_log(&log_path).unwrap().unwrap();
+        let history = log["context_window"]["conversation_history"].as_array().unwrap();
+        assert_eq!(history.len(), 2, "checkpoint keeps the log as it was");
+    }
+
+    #[test]
+    fn test_checkpoint_copies_log_rewritten_after_compression() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let session_dir = temp_dir.path();
+        let mut continuation = session_with_log(session_dir, &["first"]);
+        let log_path = PathBuf::from(&continuation.session_log_path);
+
+        // latest.json points at the compressed log, which a later turn replaced
+        let compressed_path = compress_session_log(&log_path).unwrap();
+        continuation.session_log_path = compressed_path.to_string_lossy().to_string();
+        continuation.compressed = true;
+        std::fs::write(
+            session_dir.join(CONTINUATION_FILENAME),
+            serde_json::to_string(&continuation).unwrap(),
+        )
+        .unwrap();
+        session_with_log(session_dir, &["first", "second"]);
+        std::fs::remove_file(&compressed_path).unwrap();
+        assert_eq!(continuation.session_log(), Some(log_path));
+
+        let checkpoint_dir = save_checkpoint_in(session_dir, "after-rewrite").unwrap();
+        let restored = restore_checkpoint_in(session_dir, "after-rewrite").unwrap();
+        assert_eq!(
+            PathBuf::from(&restored.session_log_path),
+            checkpoint_dir.join("session.json")
+        );
+        assert!(!restored.compressed);
+
+        let log = load_context_from_session_log(Path::new(&restored.session_log_path))
+            .unwrap()
+            .unwrap();
+        let history = log["context_window"]["conversation_history"].as_array().unwrap();
+        assert_eq!(history.len(), 2);
+    }
+
+    #[test]
+    fn test_list_checkpoints() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let session_dir = temp_dir.path();
+        assert!(list_checkpoints_in(session_dir).unwrap().is_empty());
+
+        session_with_log(session_dir, &["first"]);
+        save_checkpoint_in(session_dir, "one").unwrap();
+        save_checkpoint_in(session_dir, "two").unwrap();
+        // Saving under an existing name replaces it
+        save_checkpoint_in(session_dir, "one").unwrap();
+
+        let names: Vec<String> = list_checkpoints_in(session_dir)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, vec!["two", "one"]);
+    }
+
+    #[test]
+    fn test_checkpoint_errors() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let session_dir = temp_dir.path();
+
+        let err = save_checkpoint_in(session_dir, "early").unwrap_err();
+        assert!(err.to_string().contains("No session"), "{}", err);
+
+        session_with_log(session_dir, &["first"]);
+        for bad in ["", "../escape", ".hidden", "a b"] {
+            assert!(save_checkpoint_in(session_dir, bad).is_err(), "{:?}", bad);
+        }
+        let err = restore_checkpoint_in(session_dir, "missing").unwrap_err();
+        assert!(err.to_string().contains("No checkpoint named 'missing'"), "{}", err);
+    }
+
+    #[test]
+    fn test_encrypted_continuation_needs_passphrase() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let continuation = session_with_log(temp_dir.path(), &["secret plans"]);
+
+        let data = encode_continuation(&continuation, Some("hunter2")).unwrap();
+        assert!(encryption::is_encrypted(&data));
+        assert!(!String::from_utf8_lossy(&data).contains("Added the parser"));
+
+        let restored = decode_continuation(&data, Some("hunter2")).unwrap();
+        assert_eq!(restored.final_output_summary.as_deref(), Some("Added the parser"));
+
+        let err = decode_continuation(&data, None).unwrap_err();
+        assert!(err.to_string().contains("no passphrase"), "{}", err);
+        let err = decode_continuation(&data, Some("wrong")).unwrap_err();
+        assert!(err.to_string().contains("wrong passphrase"), "{}", err);
+    }
+
+    #[test]
+    fn test_plaintext_continuation_still_loads() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        session_with_log(temp_dir.path(), &["first"]);
+
+        let data = std::fs::read(temp_dir.path().join(CONTINUATION_FILENAME)).unwrap();
+        let restored = decode_continuation(&data, Some("hunter2")).unwrap();
+        assert_eq!(restored.session_id, "session_1");
+
+        let unencrypted = encode_continuation(&restored, None).unwrap();
+        assert!(serde_json::from_slice::<serde_json::Value>(&unencrypted).is_ok());
+    }
+
+    #[test]
+    fn test_configure_encryption_rejects_unknown_algorithm() {
+        let config = EncryptionConfig {
+            algorithm: "rot13".to_string(),
+            passphrase_env: "G3_TEST_PASSPHRASE".to_string(),
+        };
+        let err = configure_encryption(Some(config)).unwrap_err();
+        assert!(err.to_string().contains("rot13"), "{}", err);
+    }
 }
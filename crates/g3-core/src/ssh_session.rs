@@ -0,0 +1,279 @@
+//! SSH connections used to read remote files.
+//!
+//! Hosts are given as `[user@]host[:port]`. Connections authenticate with a
+//! private key (`~/.ssh/id_rsa` unless configured) and are only made to hosts
+//! whose key is already in `~/.ssh/known_hosts`. Each connection is kept for
+//! the rest of the session, like the WebDriver session. `ssh2` is blocking,
+//! so callers run [`SshSessions::read_file`] on a blocking thread.
+
+use anyhow::{anyhow, Context, Result};
+use g3_config::SshConfig;
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::debug;
+
+/// Key used when neither the config nor the host spec names one.
+const DEFAULT_KEY_PATH: &str = "~/.ssh/id_rsa";
+
+/// Remote endpoint parsed from `[user@]host[:port]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshTarget {
+    pub user: String,
+    pub host: String,
+    pub port: u16,
+}
+
+impl SshTarget {
+    /// Parse `spec`, falling back to `default_user` and `default_port`.
+    /// IPv6 addresses with a port must be bracketed (`[::1]:2222`).
+    pub fn parse(spec: &str, default_user: Option<&str>, default_port: u16) -> Result<Self, String> {
+        let spec = spec.trim();
+        let (user, host_port) = match spec.rsplit_once('@') {
+            Some((user, rest)) if !user.is_empty() => (Some(user), rest),
+            Some(_) => return Err(format!("Empty user in SSH host '{}'", spec)),
+            None => (None, spec),
+        };
+
+        let (host, port) = if let Some(rest) = host_port.strip_prefix('[') {
+            let (host, after) = rest
+                .split_once(']')
+                .ok_or_else(|| format!("Unclosed '[' in SSH host '{}'", spec))?;
+            match after.strip_prefix(':') {
+                Some(port) => (host, Some(port)),
+                None if after.is_empty() => (host, None),
+                None => return Err(format!("Invalid SSH host '{}'", spec)),
+            }
+        } else if host_port.matches(':').count() == 1 {
+            let (host, port) = host_port.split_once(':').unwrap_or((host_port, ""));
+            (host, Some(port))
+        } else {
+            (host_port, None)
+        };
+
+        if host.is_empty() {
+            return Err(format!("Missing host in SSH host '{}'", spec));
+        }
+        let port = match port {
+            Some(port) => port
+                .parse::<u16>()
+                .map_err(|_| format!("Invalid port '{}' in SSH host '{}'", port, spec))?,
+            None => default_port,
+        };
+        let user = match user.or(default_user) {
+            Some(user) => user.to_string(),
+            None => return Err(format!("No user for SSH host '{}'; use user@host", spec)),
+        };
+
+        Ok(Self {
+            user,
+            host: host.to_string(),
+            port,
+        })
+    }
+
+    /// Key the connection is cached under.
+    pub fn key(&self) -> String {
+        format!("{}@{}:{}", self.user, self.host, self.port)
+    }
+}
+
+/// Everything needed to open a connection to one host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshConnectionConfig {
+    pub target: SshTarget,
+    pub key_path: PathBuf,
+    pub connect_timeout: Duration,
+}
+
+impl SshConnectionConfig {
+    /// Build the connection settings for `host_spec` from the `[ssh]` config.
+    /// The default user is `ssh.user`, then `$USER`.
+    pub fn new(host_spec: &str, config: &SshConfig) -> Result<Self, String> {
+        let env_user = std::env::var("USER").ok();
+        let default_user = config.user.as_deref().or(env_user.as_deref());
+        let target = SshTarget::parse(host_spec, default_user, config.port)?;
+        let key_path = config.key_path.as_deref().unwrap_or(DEFAULT_KEY_PATH);
+        Ok(Self {
+            target,
+            key_path: PathBuf::from(shellexpand::tilde(key_path).as_ref()),
+            connect_timeout: Duration::from_secs(config.connect_timeout_seconds),
+        })
+    }
+}
+
+/// SSH connections opened this session, keyed by `user@host:port`.
+#[derive(Default)]
+pub struct SshSessions {
+    sessions: Mutex<HashMap<String, ssh2::Session>>,
+}
+
+impl SshSessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the remote file at `path` over SFTP. A cached connection that
+    /// fails is dropped and the read is retried once on a fresh one.
+    pub fn read_file(&self, config: &SshConnectionConfig, path: &str) -> Result<String> {
+        let key = config.target.key();
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|_| anyhow!("SSH session cache is poisoned"))?;
+
+        if let Some(session) = sessions.get(&key) {
+            match sftp_read(session, path) {
+                Ok(content) => return Ok(content),
+                Err(e) => {
+                    debug!("Cached SSH connection to {} failed ({}), reconnecting", key, e);
+                    sessions.remove(&key);
+                }
+            }
+        }
+
+        let session = connect(config)?;
+        let content = sftp_read(&session, path)?;
+        sessions.insert(key, session);
+        Ok(content)
+    }
+}
+
+/// Open and authenticate a connection, checking the host key against
+/// `~/.ssh/known_hosts`.
+fn connect(config: &SshConnectionConfig) -> Result<ssh2::Session> {
+    let target = &config.target;
+    let addr = (target.host.as_str(), target.port)
+        .to_socket_addrs()
+        .with_context(|| format!("Failed to resolve {}", target.host))?
+        .next()
+        .ok_or_else(|| anyhow!("No address found for {}", target.host))?;
+    let tcp = TcpStream::connect_timeout(&addr, config.connect_timeout)
+        .with_context(|| format!("Failed to connect to {}:{}", target.host, target.port))?;
+
+    let mut session = ssh2::Session::new()?;
+    session.set_tcp_stream(tcp);
+    session.set_timeout(config.connect_timeout.as_millis() as u32);
+    session.handshake().context("SSH handshake failed")?;
+    check_host_key(&session, target)?;
+
+    session
+        .userauth_pubkey_file(&target.user, None, &config.key_path, None)
+        .with_context(|| {
+            format!(
+                "Authentication as {} with key {} failed",
+                target.user,
+                config.key_path.display()
+            )
+        })?;
+    // Reads of large files may take longer than connecting
+    session.set_timeout(0);
+    Ok(session)
+}
+
+fn check_host_key(session: &ssh2::Session, target: &SshTarget) -> Result<()> {
+    let (key, _) = session
+        .host_key()
+        .ok_or_else(|| anyhow!("{} sent no host key", target.host))?;
+    let mut known_hosts = session.known_hosts()?;
+    let known_hosts_path = PathBuf::from(shellexpand::tilde("~/.ssh/known_hosts").as_ref());
+    if known_hosts_path.exists() {
+        known_hosts.read_file(&known_hosts_path, ssh2::KnownHostFileKind::OpenSSH)?;
+    }
+    match known_hosts.check_port(&target.host, target.port, key) {
+        ssh2::CheckResult::Match => Ok(()),
+        ssh2::CheckResult::Mismatch => Err(anyhow!(
+            "Host key for {} does not match ~/.ssh/known_hosts",
+            target.host
+        )),
+        ssh2::CheckResult::NotFound => Err(anyhow!(
+            "{} is not in ~/.ssh/known_hosts; connect once with ssh to add it",
+            target.host
+        )),
+        ssh2::CheckResult::Failure => Err(anyhow!("Failed to check the host key of {}", target.host)),
+    }
+}
+
+/// SFTP paths are relative to the remote home directory, so `~/` is dropped.
+fn sftp_read(session: &ssh2::Session, path: &str) -> Result<String> {
+    let path = path.strip_prefix("~/").unwrap_or(path);
+    let sftp = session.sftp()?;
+    let mut file = sftp.open(Path::new(path))?;
+    let mut content = String::new();
+    file.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ssh_target() {
+        assert_eq!(
+            SshTarget::parse("deploy@web-1", None, 22).unwrap(),
+            SshTarget {
+                user: "deploy".to_string(),
+                host: "web-1".to_string(),
+                port: 22,
+            }
+        );
+        assert_eq!(
+            SshTarget::parse("web-1.example.com:2222", Some("me"), 22).unwrap(),
+            SshTarget {
+                user: "me".to_string(),
+                host: "web-1.example.com".to_string(),
+                port: 2222,
+            }
+        );
+        let ipv6 = SshTarget::parse("root@[::1]:2200", None, 22).unwrap();
+        assert_eq!((ipv6.host.as_str(), ipv6.port), ("::1", 2200));
+        assert_eq!(SshTarget::parse("::1", Some("me"), 22).unwrap().host, "::1");
+        assert_eq!(ipv6.key(), "root@::1:2200");
+
+        assert!(SshTarget::parse("web-1", None, 22).is_err());
+        assert!(SshTarget::parse("@web-1", None, 22).is_err());
+        assert!(SshTarget::parse("me@", None, 22).is_err());
+        assert!(SshTarget::parse("me@web-1:ssh", None, 22).is_err());
+    }
+
+    #[test]
+    fn test_connection_config_from_ssh_config() {
+        let config = SshConfig {
+            user: Some("deploy".to_string()),
+            key_path: Some("/keys/deploy_ed25519".to_string()),
+            port: 2222,
+            connect_timeout_seconds: 5,
+        };
+
+        let conn = SshConnectionConfig::new("web-1", &config).unwrap();
+        assert_eq!(conn.target.user, "deploy");
+        assert_eq!(conn.target.port, 2222);
+        assert_eq!(conn.key_path, PathBuf::from("/keys/deploy_ed25519"));
+        assert_eq!(conn.connect_timeout, Duration::from_secs(5));
+
+        // The host spec overrides the configured user and port
+        let conn = SshConnectionConfig::new("admin@web-1:22", &config).unwrap();
+        assert_eq!((conn.target.user.as_str(), conn.target.port), ("admin", 22));
+
+        let conn = SshConnectionConfig::new("admin@web-1", &SshConfig::default()).unwrap();
+        assert!(conn.key_path.ends_with(".ssh/id_rsa"));
+        assert!(!conn.key_path.starts_with("~"));
+    }
+
+    #[test]
+    #[ignore] // Requires an SSH server on localhost that accepts ~/.ssh/id_rsa
+    fn test_read_file_over_ssh() {
+        let conn = SshConnectionConfig::new("localhost", &SshConfig::default()).unwrap();
+        let sessions = SshSessions::new();
+
+        let hostname = sessions.read_file(&conn, "/etc/hostname").unwrap();
+        assert!(!hostname.trim().is_empty());
+        // The second read reuses the cached connection
+        assert_eq!(sessions.read_file(&conn, "/etc/hostname").unwrap(), hostname);
+        assert!(sessions.read_file(&conn, "/no/such/file").is_err());
+    }
+}
@@ -3,7 +3,10 @@
 //! This module handles parsing of tool calls from streaming LLM responses,
 //! supporting both native tool calls and JSON-based fallback parsing.
 
-use tracing::debug;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use tracing::{debug, warn};
 
 use crate::ToolCall;
 
@@ -23,6 +26,97 @@ const XML_TOOL_CALL_PATTERNS: [&str; 3] = [
     r#"<tool name="#,
 ];
 
+/// Parameters holding file content, which is never unescaped: `&lt;` in an
+/// HTML file is meant literally.
+const LITERAL_PARAMETERS: [&str; 2] = ["content", "diff"];
+
+/// Parse `text` as JSON with its XML entities unescaped: in its strings when
+/// it is valid JSON as is, or in the whole text when only that makes it valid
+/// (`{&quot;a&quot;: 1}`). Strings under a top-level [`LITERAL_PARAMETERS`] key
+/// are kept as is, the same as when they come as their own parameter.
+/// Returns the unescaped text when it isn't JSON.
+fn parse_unescaped(text: &str) -> Result<serde_json::Value, String> {
+    if let Ok(value) = serde_json::from_str(text) {
+        return Ok(match value {
+            serde_json::Value::Object(map) => serde_json::Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| {
+                        let value = if LITERAL_PARAMETERS.contains(&key.as_str()) {
+                            value
+                        } else {
+                            unescape_json_strings(value)
+                        };
+                        (xml_unescape(&key), value)
+                    })
+                    .collect(),
+            ),
+            other => unescape_json_strings(other),
+        });
+    }
+    let unescaped = xml_unescape(text);
+    serde_json::from_str(&unescaped).map_err(|_| unescaped)
+}
+
+/// `value` with [`xml_unescape`] applied to every string in it, keys included.
+fn unescape_json_strings(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(s) => serde_json::Value::String(xml_unescape(&s)),
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.into_iter().map(unescape_json_strings).collect())
+        }
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (xml_unescape(&key), unescape_json_strings(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Replace the five predefined XML entities (`&amp;`, `&lt;`, `&gt;`,
+/// `&quot;`, `&apos;`) with the characters they stand for. Anything else
+/// starting with `&` is kept as is, and `&amp;lt;` becomes `&lt;`, not `<`.
+pub fn xml_unescape(s: &str) -> String {
+    const ENTITIES: [(&str, char); 5] = [
+        ("&amp;", '&'),
+        ("&lt;", '<'),
+        ("&gt;", '>'),
+        ("&quot;", '"'),
+        ("&apos;", '\''),
+    ];
+
+    let mut unescaped = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        unescaped.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        match ENTITIES.iter().find(|(entity, _)| rest.starts_with(entity)) {
+            Some((entity, c)) => {
+                unescaped.push(*c);
+                rest = &rest[entity.len()..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Default cap on buffered text per turn, see [`StreamingToolParser::with_max_buffer_bytes`].
+pub const DEFAULT_MAX_BUFFER_BYTES: usize = 10 * 1024 * 1024;
+
+/// What the parser accumulated for a turn, returned by [`StreamingToolParser::reset`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedTurn {
+    /// Text content buffered since the last reset
+    pub text: String,
+    /// Number of tool calls returned from process_chunk since the last reset
+    pub tool_calls_count: usize,
+}
+
 /// Modern streaming tool parser that properly handles native tool calls and SSE chunks.
 #[derive(Debug)]
 pub struct StreamingToolParser {
@@ -37,6 +131,18 @@ pub struct StreamingToolParser {
     in_json_tool_call: bool,
     /// Start position of JSON tool call (for fallback parsing)
     json_tool_start: Option<usize>,
+    /// Hash of the last tool call returned from process_chunk, used to drop re-emitted duplicates
+    last_emitted_tool_hash: Option<u64>,
+    /// Number of tool calls returned from process_chunk since the last reset
+    tool_calls_count: usize,
+    /// Bytes of chunk text added to the buffer, i.e. the position in the stream
+    bytes_consumed: u64,
+    /// Bytes of incoming chunk text still to discard after `resume_from_offset`
+    skip_remaining: u64,
+    /// Most bytes of text to buffer per turn, or `None` for no limit
+    max_buffer_bytes: Option<usize>,
+    /// Whether text was dropped because the buffer reached `max_buffer_bytes`
+    buffer_truncated: bool,
 }
 
 impl Default for StreamingToolParser {
@@ -45,6 +151,12 @@ impl Default for StreamingToolParser {
     }
 }
 
+impl g3_providers::StreamState for StreamingToolParser {
+    fn has_incomplete_tool_call(&self) -> bool {
+        StreamingToolParser::has_incomplete_tool_call(self)
+    }
+}
+
 impl StreamingToolParser {
     pub fn new() -> Self {
         Self {
@@ -53,9 +165,49 @@ impl StreamingToolParser {
             message_stopped: false,
             in_json_tool_call: false,
             json_tool_start: None,
+            last_emitted_tool_hash: None,
+            tool_calls_count: 0,
+            bytes_consumed: 0,
+            skip_remaining: 0,
+            max_buffer_bytes: Some(DEFAULT_MAX_BUFFER_BYTES),
+            buffer_truncated: false,
         }
     }
 
+    /// Buffer at most `limit` bytes of text per turn (`None` for no limit).
+    /// Text past the limit is dropped, but native tool calls are still returned.
+    pub fn with_max_buffer_bytes(mut self, limit: Option<usize>) -> Self {
+        self.max_buffer_bytes = limit;
+        self
+    }
+
+    /// Whether text was dropped this turn because the buffer was full.
+    pub fn is_buffer_truncated(&self) -> bool {
+        self.buffer_truncated
+    }
+
+    /// Part of `content` that still fits in the buffer, cut at a character
+    /// boundary. Marks the buffer truncated when anything is left out; after
+    /// that no more text is kept, so the buffer never has gaps.
+    fn fit_to_buffer<'a>(&mut self, content: &'a str) -> &'a str {
+        let Some(limit) = self.max_buffer_bytes else {
+            return content;
+        };
+        if self.buffer_truncated {
+            return "";
+        }
+        let mut room = limit.saturating_sub(self.text_buffer.len());
+        if content.len() <= room {
+            return content;
+        }
+        debug!("Text buffer reached {} bytes, dropping further text", limit);
+        self.buffer_truncated = true;
+        while !content.is_char_boundary(room) {
+            room -= 1;
+        }
+        &content[..room]
+    }
+
     /// Find the starting position of the last tool call pattern in the given text.
     /// Returns None if no tool call pattern is found.
     fn find_last_tool_call_start(text: &str) -> Option<usize> {
@@ -108,10 +260,32 @@ impl StreamingToolParser {
     pub fn process_chunk(&mut self, chunk: &g3_providers::CompletionChunk) -> Vec<ToolCall> {
         let mut completed_tools = Vec::new();
 
-        // Add text content to buffer
-        if !chunk.content.is_empty() {
-            self.text_buffer.push_str(&chunk.content);
-        }
+        // After a resume, drop the text that was already processed
+        let resumed;
+        let chunk = if self.skip_remaining > 0 {
+            resumed = g3_providers::CompletionChunk {
+                content: self.skip_resumed_bytes(&chunk.content).to_string(),
+                ..chunk.clone()
+            };
+            &resumed
+        } else {
+            chunk
+        };
+
+        // Add text content to buffer, keeping only what fits
+        self.bytes_consumed += chunk.content.len() as u64;
+        let truncated;
+        let chunk = match self.fit_to_buffer(&chunk.content) {
+            kept if kept.len() < chunk.content.len() => {
+                truncated = g3_providers::CompletionChunk {
+                    content: kept.to_string(),
+                    ..chunk.clone()
+                };
+                &truncated
+            }
+            _ => chunk,
+        };
+        self.text_buffer.push_str(&chunk.content);
 
         // Handle native tool calls - return them immediately when received.
         // This allows tools to be executed as soon as they're fully parsed,
@@ -152,6 +326,12 @@ impl StreamingToolParser {
         if chunk.finished {
             self.message_stopped = true;
             debug!("Message finished, processing accumulated tool calls");
+            if self.buffer_truncated {
+                warn!(
+                    "Stream text exceeded {} bytes; text past the limit was dropped",
+                    self.max_buffer_bytes.unwrap_or_default()
+                );
+            }
 
             // When stream finishes, find ALL tool calls (JSON and XML) in the accumulated buffer
             if completed_tools.is_empty() && !self.text_buffer.is_empty() {
@@ -165,7 +345,7 @@ impl StreamingToolParser {
                     completed_tools.extend(xml_tools);
                 } else {
                     // Fallback to JSON parsing
-                    let all_json_tools = self.try_parse_all_json_tool_calls_from_buffer();
+                    let (all_json_tools, _) = self.try_parse_all_json_tool_calls_from(0);
                     if !all_json_tools.is_empty() {
                         debug!(
                             "Found {} JSON tool calls in buffer at stream end",
@@ -184,14 +364,55 @@ impl StreamingToolParser {
             if !xml_tools.is_empty() {
                 completed_tools.extend(xml_tools);
             } else {
-                // Fallback to JSON parsing
-                if let Some(json_tool) = self.try_parse_json_tool_call(&chunk.content) {
+                // Fallback to JSON parsing. When the chunk carries more than one tool call
+                // start, the single-call path would only ever see the last one, so scan the
+                // whole chunk (plus any call already in progress) for every complete call.
+                if Self::count_tool_call_starts(&chunk.content) > 1 {
+                    let chunk_start = self.text_buffer.len() - chunk.content.len();
+                    let scan_start = self
+                        .json_tool_start
+                        .filter(|_| self.in_json_tool_call)
+                        .map_or(chunk_start, |start| start.min(chunk_start));
+                    let (json_tools, pending_start) =
+                        self.try_parse_all_json_tool_calls_from(scan_start);
+                    debug!(
+                        "Found {} JSON tool calls in multi-call chunk",
+                        json_tools.len()
+                    );
+                    completed_tools.extend(json_tools);
+                    self.in_json_tool_call = pending_start.is_some();
+                    self.json_tool_start = pending_start;
+                } else if let Some(json_tool) = self.try_parse_json_tool_call(&chunk.content) {
                     completed_tools.push(json_tool);
                 }
             }
         }
 
-        completed_tools
+        self.drop_repeated_tool_calls(completed_tools)
+    }
+
+    /// Hash a tool call independently of its args' key order.
+    fn tool_call_hash(tool_call: &ToolCall) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        tool_call.to_hashable().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Drop tool calls identical to the one emitted just before them.
+    /// Some providers re-emit a complete tool call that was already returned.
+    fn drop_repeated_tool_calls(&mut self, tools: Vec<ToolCall>) -> Vec<ToolCall> {
+        let mut emitted = Vec::with_capacity(tools.len());
+        for tool_call in tools {
+            let hash = Self::tool_call_hash(&tool_call);
+            if self.last_emitted_tool_hash == Some(hash) {
+                debug!("Dropping duplicate consecutive tool call: {}", tool_call.tool);
+                continue;
+            }
+            self.last_emitted_tool_hash = Some(hash);
+            emitted.push(tool_call);
+        }
+        self.tool_calls_count += emitted.len();
+        emitted
     }
 
     /// Try to find XML tool calls in the current text buffer.
@@ -285,11 +506,23 @@ impl StreamingToolParser {
         None
     }
 
-    /// Parse ALL JSON tool calls from the accumulated text buffer.
+    /// Count how many tool call start patterns appear in the given text.
+    fn count_tool_call_starts(text: &str) -> usize {
+        TOOL_CALL_PATTERNS
+            .iter()
+            .map(|pattern| text.matches(pattern).count())
+            .sum()
+    }
+
+    /// Parse ALL JSON tool calls from the accumulated text buffer, starting at `start`.
     /// This finds all complete tool calls, not just the last one.
-    fn try_parse_all_json_tool_calls_from_buffer(&self) -> Vec<ToolCall> {
+    ///
+    /// Also returns the start position of a trailing tool call whose JSON is not yet
+    /// complete, so mid-stream callers can keep waiting for it.
+    fn try_parse_all_json_tool_calls_from(&self, start: usize) -> (Vec<ToolCall>, Option<usize>) {
         let mut tool_calls = Vec::new();
-        let mut search_start = 0;
+        let mut search_start = start;
+        let mut pending_start = None;
 
         while search_start < self.text_buffer.len() {
             let search_text = &self.text_buffer[search_start..];
@@ -321,6 +554,7 @@ impl StreamingToolParser {
                     search_start = abs_start + end_pos + 1;
                 } else {
                     // Incomplete JSON, stop searching
+                    pending_start = Some(abs_start);
                     break;
                 }
             } else {
@@ -329,7 +563,7 @@ impl StreamingToolParser {
             }
         }
 
-        tool_calls
+        (tool_calls, pending_start)
     }
 
     /// Get the accumulated text content (excluding tool calls).
@@ -395,11 +629,25 @@ impl StreamingToolParser {
         let mut brace_count = 0;
         let mut in_string = false;
         let mut escape_next = false;
+        // Hex digits still to skip after a `\u` escape (e.g. `\uD83D`)
+        let mut unicode_hex_remaining = 0;
         let mut found_start = false;
 
         for (i, ch) in text.char_indices() {
+            if unicode_hex_remaining > 0 {
+                if ch.is_ascii_hexdigit() {
+                    unicode_hex_remaining -= 1;
+                    continue;
+                }
+                // Malformed escape: resume normal scanning at this character
+                unicode_hex_remaining = 0;
+            }
+
             if escape_next {
                 escape_next = false;
+                if ch == 'u' {
+                    unicode_hex_remaining = 4;
+                }
                 continue;
             }
 
@@ -425,6 +673,7 @@ impl StreamingToolParser {
 
     /// Try to parse XML tool calls from text content.
     /// This handles XML format like <invoke name="shell"><parameter name="args">{"command": "ls"}</parameter></invoke>
+    /// as well as one parameter per argument: <invoke name="shell"><parameter name="command">ls</parameter></invoke>
     pub fn try_parse_xml_tool_calls_from_text(&self, text: &str) -> Vec<ToolCall> {
         let mut tools = Vec::new();
         
@@ -439,11 +688,13 @@ impl StreamingToolParser {
                 let actual_pos = start + pos;
                 
                 // Try to find the complete XML element
-                if let Some(xml_end) = Self::find_complete_xml_element_end(&text[actual_pos..]) {
+                if let Some((content_start, xml_end)) =
+                    Self::find_complete_xml_element_end(&text[actual_pos..])
+                {
                     let xml_str = &text[actual_pos..actual_pos + xml_end];
                     debug!("Found complete XML element: {}", xml_str);
                     
-                    if let Some(tool_call) = self.parse_xml_tool_call(xml_str) {
+                    if let Some(tool_call) = self.parse_xml_tool_call(xml_str, content_start) {
                         debug!("Found XML tool call in text: {:?}", tool_call);
                         tools.push(tool_call);
                     }
@@ -459,124 +710,235 @@ impl StreamingToolParser {
         tools
     }
 
-    /// Find the end position of a complete XML element.
-    fn find_complete_xml_element_end(text: &str) -> Option<usize> {
-        // Look for matching closing tag
-        if let Some(start_pos) = text.find('<') {
-            if let Some(tag_end) = text[start_pos + 1..].find('>') {
-                let tag_name_start = start_pos + 1;
-                let tag_name_end = tag_end + start_pos + 1;
-                let tag_content = &text[tag_name_start..tag_name_end];
-                
-                // Extract tag name (handle attributes)
-                let tag_name: String = tag_content.chars()
-                    .take_while(|c| !c.is_whitespace() && *c != '>')
-                    .collect();
-                
-                if !tag_name.starts_with('/') {
-                    // Look for closing tag
-                    let closing_tag = format!("</{}>", tag_name);
-                    if let Some(close_pos) = text.find(&closing_tag) {
-                        return Some(close_pos + closing_tag.len());
-                    }
-                }
+    /// Find the end of the opening tag at the start of `text`, skipping any `>`
+    /// inside quoted attribute values. Returns the index just past the `>`.
+    fn find_opening_tag_end(text: &str) -> Option<usize> {
+        let mut quote: Option<char> = None;
+        for (i, c) in text.char_indices() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"') | (None, '\'') => quote = Some(c),
+                (None, '>') => return Some(i + 1),
+                _ => {}
             }
         }
         None
     }
 
-    /// Parse an XML tool call into a ToolCall struct.
-    fn parse_xml_tool_call(&self, xml_str: &str) -> Option<ToolCall> {
-        debug!("parse_xml_tool_call called with: {}", xml_str);
-        
-        // Try to extract tool name and args from XML
-        // Format: <invoke name="shell"><parameter name="args">{"command": "ls"}</parameter></invoke>
-        
-        // Extract tool name
-        let tool_name = if xml_str.contains("<invoke name=") {
-            // Extract from <invoke name="toolname">
-            if let Some(start) = xml_str.find("name=\"") {
-                let start = start + 6; // Skip 'name="'
-                if let Some(end) = xml_str[start..].find('"') {
-                    xml_str[start..start + end].to_string()
-                } else {
-                    return None;
+    /// Find a complete XML element starting at the beginning of `text`.
+    /// Returns `(content_start, element_end)`: the offset just past the opening
+    /// tag and the offset just past the closing tag. Self-closing elements have
+    /// `content_start == element_end`.
+    fn find_complete_xml_element_end(text: &str) -> Option<(usize, usize)> {
+        if !text.starts_with('<') {
+            return None;
+        }
+        let content_start = Self::find_opening_tag_end(text)?;
+        let opening_tag = &text[..content_start];
+
+        // Extract tag name (handle attributes)
+        let tag_name: String = opening_tag[1..]
+            .chars()
+            .take_while(|c| !c.is_whitespace() && *c != '>' && *c != '/')
+            .collect();
+        if tag_name.is_empty() {
+            return None;
+        }
+
+        if opening_tag.ends_with("/>") {
+            return Some((content_start, content_start));
+        }
+
+        // Look for closing tag
+        let closing_tag = format!("</{}>", tag_name);
+        text[content_start..]
+            .find(&closing_tag)
+            .map(|close_pos| (content_start, content_start + close_pos + closing_tag.len()))
+    }
+
+    /// Parse `key="value"` attributes from an opening tag such as `<invoke name="shell">`.
+    fn parse_xml_attributes(opening_tag: &str) -> Vec<(String, String)> {
+        let mut attributes = Vec::new();
+        let inner = opening_tag
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .trim_end_matches('/');
+        // Skip the tag name
+        let mut rest = inner
+            .find(char::is_whitespace)
+            .map(|i| &inner[i..])
+            .unwrap_or("");
+
+        while let Some(eq) = rest.find('=') {
+            let key = rest[..eq].trim().to_string();
+            let after = rest[eq + 1..].trim_start();
+            let Some(quote) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+                break;
+            };
+            let Some(value_end) = after[1..].find(quote) else {
+                break;
+            };
+            attributes.push((key, after[1..1 + value_end].to_string()));
+            rest = &after[value_end + 2..];
+        }
+        attributes
+    }
+
+    /// Parse argument text as JSON, falling back to a `command` argument.
+    fn parse_xml_args_content(content: &str) -> serde_json::Value {
+        // Clean up whitespace and newlines
+        let cleaned_content = content.trim().replace("\n", " ").replace("  ", " ");
+        debug!("Cleaned content: '{}'", cleaned_content);
+
+        // Try to parse as JSON first
+        match parse_unescaped(&cleaned_content) {
+            Ok(json_args) => {
+                debug!("Parsed as JSON: {:?}", json_args);
+                json_args
+            }
+            Err(unescaped) => {
+                debug!("Failed to parse as JSON, using content as command");
+                // If not JSON, create a simple args object with command
+                serde_json::json!({
+                    "command": unescaped.trim()
+                })
+            }
+        }
+    }
+
+    /// Return the text of a `<![CDATA[...]]>` section that makes up all of
+    /// `content` (surrounding whitespace aside). CDATA is literal, so nothing is
+    /// unescaped. Returns `None` when there is no such section, or when the text
+    /// contains a stray `]]>` (invalid CDATA); the content is then treated as
+    /// regular text.
+    fn strip_cdata(content: &str) -> Option<&str> {
+        let inner = content
+            .trim()
+            .strip_prefix("<![CDATA[")?
+            .strip_suffix("]]>")?;
+        (!inner.contains("]]>")).then_some(inner)
+    }
+
+    /// Parse the content of an `args` parameter or a bare tool element.
+    fn parse_xml_args_value(content: &str) -> serde_json::Value {
+        match Self::strip_cdata(content) {
+            // Literal text: only parse it, don't clean up its whitespace
+            Some(inner) => serde_json::from_str(inner.trim())
+                .unwrap_or_else(|_| serde_json::json!({ "command": inner.trim() })),
+            None => Self::parse_xml_args_content(content),
+        }
+    }
+
+    /// Collect `<parameter name="X">V</parameter>` children as `(X, V)` pairs, in order.
+    fn parse_xml_parameters(content: &str) -> Vec<(String, String)> {
+        let mut parameters = Vec::new();
+        let mut rest = content;
+        while let Some(start) = rest.find("<parameter") {
+            let element = &rest[start..];
+            let Some((content_start, element_end)) = Self::find_complete_xml_element_end(element)
+            else {
+                break;
+            };
+            let opening_tag = &element[..content_start];
+            let value = element[content_start..element_end]
+                .strip_suffix("</parameter>")
+                .unwrap_or("");
+            if let Some((_, name)) = Self::parse_xml_attributes(opening_tag)
+                .into_iter()
+                .find(|(key, _)| key == "name")
+            {
+                debug!("Found parameter {}: '{}'", name, value);
+                parameters.push((name, value.to_string()));
+            }
+            rest = &element[element_end..];
+        }
+        parameters
+    }
+
+    /// Build tool args from XML parameters.
+    ///
+    /// An `args` parameter holds the whole argument object as JSON; any other
+    /// parameter becomes a key whose value is parsed as JSON when valid and
+    /// kept as a string otherwise. Values wrapped in CDATA are unwrapped first.
+    /// XML entities are unescaped, except in CDATA and file content.
+    fn build_args_from_parameters(parameters: Vec<(String, String)>) -> serde_json::Value {
+        let mut args = serde_json::Map::new();
+        for (name, value) in parameters {
+            if name == "args" {
+                match Self::parse_xml_args_value(&value) {
+                    serde_json::Value::Object(map) => args.extend(map),
+                    other => return other,
                 }
-            } else {
-                return None;
+                continue;
             }
-        } else if xml_str.contains("<tool name=") {
-            // Extract from <tool name="toolname">
-            if let Some(start) = xml_str.find("name=\"") {
-                let start = start + 6; // Skip 'name="'
-                if let Some(end) = xml_str[start..].find('"') {
-                    xml_str[start..start + end].to_string()
-                } else {
-                    return None;
+
+            let (value, literal) = match Self::strip_cdata(&value) {
+                Some(inner) => (inner.to_string(), true),
+                None => {
+                    // Drop the newline that usually follows the opening tag / precedes the closing one
+                    let value = value.strip_prefix('\n').unwrap_or(&value);
+                    let value = value.strip_suffix('\n').unwrap_or(value).to_string();
+                    (value, LITERAL_PARAMETERS.contains(&name.as_str()))
                 }
+            };
+            let parsed = if literal {
+                serde_json::from_str(value.trim()).unwrap_or(serde_json::Value::String(value))
             } else {
-                return None;
-            }
-        } else {
+                parse_unescaped(value.trim())
+                    .unwrap_or_else(|_| serde_json::Value::String(xml_unescape(&value)))
+            };
+            args.insert(name, parsed);
+        }
+        serde_json::Value::Object(args)
+    }
+
+    /// Parse an XML tool call into a ToolCall struct.
+    /// `content_start` is the offset just past the opening tag, as returned by
+    /// `find_complete_xml_element_end`.
+    fn parse_xml_tool_call(&self, xml_str: &str, content_start: usize) -> Option<ToolCall> {
+        debug!("parse_xml_tool_call called with: {}", xml_str);
+        
+        // Format: <invoke name="shell"><parameter name="args">{"command": "ls"}</parameter></invoke>
+        let opening_tag = &xml_str[..content_start];
+        let tag_name: String = opening_tag[1..]
+            .chars()
+            .take_while(|c| !c.is_whitespace() && *c != '>' && *c != '/')
+            .collect();
+        if tag_name != "invoke" && tag_name != "tool" {
             return None;
-        };
+        }
+
+        let attributes = Self::parse_xml_attributes(opening_tag);
+        let tool_name = attributes
+            .iter()
+            .find(|(key, _)| key == "name")
+            .map(|(_, value)| value.clone())?;
         
         debug!("Extracted tool name: {}", tool_name);
+
+        let closing_tag = format!("</{}>", tag_name);
+        let content = xml_str[content_start..]
+            .strip_suffix(closing_tag.as_str())
+            .unwrap_or("");
         
-        // Look for <parameter name="args"> content specifically
-        let args = if let Some(args_param) = xml_str.find(r#"<parameter name="args">"#) {
-            let content_start = args_param + r#"<parameter name="args">"#.len();
-            if let Some(content_end) = xml_str[content_start..].find(r#"</parameter>"#) {
-                let content = &xml_str[content_start..content_start + content_end];
-                debug!("Found parameter args content: '{}'", content);
-                
-                // Clean up whitespace and newlines
-                let cleaned_content = content.trim().replace("\n", " ").replace("  ", " ");
-                debug!("Cleaned content: '{}'", cleaned_content);
-                
-                // Try to parse as JSON first
-                if let Ok(json_args) = serde_json::from_str::<serde_json::Value>(&cleaned_content) {
-                    debug!("Parsed as JSON: {:?}", json_args);
-                    json_args
-                } else {
-                    debug!("Failed to parse as JSON, using content as command");
-                    // If not JSON, create a simple args object with command
-                    serde_json::json!({
-                        "command": cleaned_content.trim()
-                    })
-                }
-            } else {
-                debug!("No closing </parameter> tag found");
-                serde_json::json!({})
-            }
-        } else if let Some(simple_content) = xml_str.find('>') {
-            // Fallback: look for any content between tags
-            let content_start = simple_content + 1;
-            if let Some(content_end) = xml_str[content_start..].find("</") {
-                let content = &xml_str[content_start..content_start + content_end];
-                debug!("Using simple content extraction: '{}'", content);
-                
-                // Clean up whitespace and newlines
-                let cleaned_content = content.trim().replace("\n", " ").replace("  ", " ");
-                debug!("Cleaned simple content: '{}'", cleaned_content);
-                
-                // Try to parse as JSON first
-                if let Ok(json_args) = serde_json::from_str::<serde_json::Value>(&cleaned_content) {
-                    debug!("Parsed as JSON: {:?}", json_args);
-                    json_args
-                } else {
-                    debug!("Using content as command: '{}'", cleaned_content.trim());
-                    serde_json::json!({
-                        "command": cleaned_content.trim()
-                    })
-                }
-            } else {
-                debug!("No closing tag found in simple extraction");
-                serde_json::json!({})
-            }
-        } else {
-            debug!("No content found in XML");
+        let parameters = Self::parse_xml_parameters(content);
+        let args = if !parameters.is_empty() {
+            Self::build_args_from_parameters(parameters)
+        } else if content.contains("<parameter") {
+            debug!("No complete <parameter> element found");
             serde_json::json!({})
+        } else if !content.trim().is_empty() {
+            debug!("Using simple content extraction: '{}'", content);
+            Self::parse_xml_args_value(content)
+        } else {
+            // Self-closing or empty element: remaining attributes are the args
+            let args: serde_json::Map<String, serde_json::Value> = attributes
+                .into_iter()
+                .filter(|(key, _)| key != "name")
+                .map(|(key, value)| (key, serde_json::Value::String(value)))
+                .collect();
+            serde_json::Value::Object(args)
         };
         
         debug!("Final args: {:?}", args);
@@ -621,13 +983,48 @@ impl StreamingToolParser {
         tools
     }
 
-    /// Reset the parser state for a new message.
-    pub fn reset(&mut self) {
-        self.text_buffer.clear();
+    /// Reset the parser state for a new message, returning the text and
+    /// tool call count accumulated since the last reset.
+    pub fn reset(&mut self) -> ParsedTurn {
         self.last_consumed_position = 0;
         self.message_stopped = false;
         self.in_json_tool_call = false;
         self.json_tool_start = None;
+        self.last_emitted_tool_hash = None;
+        self.bytes_consumed = 0;
+        self.skip_remaining = 0;
+        self.buffer_truncated = false;
+        ParsedTurn {
+            text: std::mem::take(&mut self.text_buffer),
+            tool_calls_count: std::mem::take(&mut self.tool_calls_count),
+        }
+    }
+
+    /// Bytes of stream text processed since the last reset. After a dropped
+    /// connection this is the offset to resume the stream from.
+    pub fn bytes_consumed(&self) -> u64 {
+        self.bytes_consumed
+    }
+
+    /// Discard the first `skip_bytes` bytes of text from the following chunks,
+    /// for a reconnected stream that replays the response from the start.
+    /// Skipped bytes do not count towards `bytes_consumed`.
+    pub fn resume_from_offset(&mut self, skip_bytes: u64) {
+        self.skip_remaining = skip_bytes;
+    }
+
+    /// Part of `content` left after skipping up to `skip_remaining` bytes.
+    /// A skip ending inside a UTF-8 character drops the rest of it too; those
+    /// bytes were never processed before, so they count towards `bytes_consumed`.
+    fn skip_resumed_bytes<'a>(&mut self, content: &'a str) -> &'a str {
+        let requested = content.len().min(self.skip_remaining as usize);
+        self.skip_remaining -= requested as u64;
+        let mut skip = requested;
+        while !content.is_char_boundary(skip) {
+            skip += 1;
+        }
+        self.bytes_consumed += (skip - requested) as u64;
+        &content[skip..]
     }
 
     /// Get the current text buffer length (for position tracking).
@@ -752,10 +1149,413 @@ mod tests {
         parser.message_stopped = true;
         parser.last_consumed_position = 5;
 
-        parser.reset();
+        let turn = parser.reset();
 
+        assert_eq!(turn.text, "some content");
+        assert_eq!(turn.tool_calls_count, 0);
         assert!(parser.text_buffer.is_empty());
         assert!(!parser.message_stopped);
         assert_eq!(parser.last_consumed_position, 0);
+        assert_eq!(parser.reset(), ParsedTurn::default());
+    }
+
+    #[test]
+    fn test_reset_returns_parsed_turn() {
+        let mut parser = StreamingToolParser::new();
+        let text = "Listing files.\n";
+        let call = r#"{"tool":"shell","args":{"command":"ls"}}"#;
+        for content in [text, call] {
+            parser.process_chunk(&g3_providers::CompletionChunk {
+                content: content.to_string(),
+                finished: false,
+                tool_calls: None,
+                usage: None,
+            });
+        }
+        parser.process_chunk(&g3_providers::CompletionChunk {
+            content: String::new(),
+            finished: false,
+            tool_calls: Some(vec![g3_providers::ToolCall {
+                id: "call_1".to_string(),
+                tool: "read_file".to_string(),
+                args: serde_json::json!({"file_path": "a.txt"}),
+            }]),
+            usage: None,
+        });
+
+        let turn = parser.reset();
+
+        assert_eq!(turn.text, format!("{text}{call}"));
+        assert_eq!(turn.tool_calls_count, 2);
+        assert_eq!(parser.text_buffer_len(), 0);
     }
-}
\ No newline at end of file
+
+    fn text_chunk(content: &str) -> g3_providers::CompletionChunk {
+        g3_providers::CompletionChunk {
+            content: content.to_string(),
+            finished: false,
+            tool_calls: None,
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn test_bytes_consumed() {
+        let mut parser = StreamingToolParser::new();
+        parser.process_chunk(&text_chunk("Hello "));
+        parser.process_chunk(&text_chunk(""));
+        parser.process_chunk(&text_chunk("wörld"));
+        assert_eq!(parser.bytes_consumed(), 12);
+
+        parser.reset();
+        assert_eq!(parser.bytes_consumed(), 0);
+    }
+
+    #[test]
+    fn test_resume_from_offset() {
+        let response = r#"Let me check. {"tool":"shell","args":{"command":"ls"}}"#;
+        let mut parser = StreamingToolParser::new();
+        parser.process_chunk(&text_chunk(&response[..10]));
+        parser.process_chunk(&text_chunk(&response[10..25]));
+        // The connection drops; the reconnected stream replays everything
+        let offset = parser.bytes_consumed();
+        assert_eq!(offset, 25);
+        parser.resume_from_offset(offset);
+
+        let mut tools = Vec::new();
+        for piece in [&response[..20], &response[20..30], &response[30..]] {
+            tools.extend(parser.process_chunk(&text_chunk(piece)));
+        }
+
+        assert_eq!(parser.get_text_content(), response);
+        assert_eq!(parser.bytes_consumed(), response.len() as u64);
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].tool, "shell");
+    }
+
+    #[test]
+    fn test_resume_skip_ending_inside_character() {
+        let mut parser = StreamingToolParser::new();
+        parser.resume_from_offset(2);
+        // 'é' is two bytes; skipping one of them drops the whole character
+        parser.process_chunk(&text_chunk("aé!"));
+        assert_eq!(parser.get_text_content(), "!");
+        // The dropped second byte of 'é' counts, so 2 + 2 is the stream position
+        assert_eq!(parser.bytes_consumed(), 2);
+
+        parser.process_chunk(&text_chunk("more"));
+        assert_eq!(parser.get_text_content(), "!more");
+    }
+
+    #[test]
+    fn test_multiple_json_tool_calls_in_single_chunk() {
+        let mut parser = StreamingToolParser::new();
+
+        let chunk = g3_providers::CompletionChunk {
+            content: r#"{"tool":"shell","args":{"command":"ls"}}{"tool":"read_file","args":{"file_path":"a.txt"}}"#.to_string(),
+            finished: false,
+            tool_calls: None,
+            usage: None,
+        };
+
+        let tools = parser.process_chunk(&chunk);
+
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[0].tool, "shell");
+        assert_eq!(tools[1].tool, "read_file");
+        assert!(!parser.is_in_json_tool_call());
+    }
+
+    #[test]
+    fn test_duplicate_consecutive_tool_calls_are_dropped() {
+        let mut parser = StreamingToolParser::new();
+        let tool_chunk = || g3_providers::CompletionChunk {
+            content: String::new(),
+            finished: false,
+            tool_calls: Some(vec![g3_providers::ToolCall {
+                id: "call_1".to_string(),
+                tool: "shell".to_string(),
+                args: serde_json::json!({"command": "ls"}),
+            }]),
+            usage: None,
+        };
+
+        let first = parser.process_chunk(&tool_chunk());
+        let second = parser.process_chunk(&tool_chunk());
+
+        assert_eq!(first.len(), 1);
+        assert!(second.is_empty());
+
+        // After a reset the same call is accepted again
+        parser.reset();
+        assert_eq!(parser.process_chunk(&tool_chunk()).len(), 1);
+    }
+
+    #[test]
+    fn test_xml_invoke_plain_content() {
+        let parser = StreamingToolParser::new();
+        let tools = parser.try_parse_xml_tool_calls_from_text(r#"<invoke name="tool">content</invoke>"#);
+
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].tool, "tool");
+        assert_eq!(tools[0].args, serde_json::json!({"command": "content"}));
+    }
+
+    #[test]
+    fn test_xml_invoke_with_extra_attributes() {
+        let parser = StreamingToolParser::new();
+        let tools = parser
+            .try_parse_xml_tool_calls_from_text(r#"<invoke name="tool" extra="attr">content</invoke>"#);
+
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].tool, "tool");
+        assert_eq!(tools[0].args, serde_json::json!({"command": "content"}));
+    }
+
+    #[test]
+    fn test_xml_invoke_with_args_parameter() {
+        let parser = StreamingToolParser::new();
+        let tools = parser.try_parse_xml_tool_calls_from_text(
+            r#"<invoke name="tool"><parameter name="args">{"x":1}</parameter></invoke>"#,
+        );
+
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].tool, "tool");
+        assert_eq!(tools[0].args, serde_json::json!({"x": 1}));
+    }
+
+    #[test]
+    fn test_xml_invoke_with_multiple_parameters() {
+        let parser = StreamingToolParser::new();
+        let tools = parser.try_parse_xml_tool_calls_from_text(
+            r#"<invoke name="shell"><parameter name="command">ls</parameter><parameter name="cwd">/tmp</parameter></invoke>"#,
+        );
+
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].tool, "shell");
+        assert_eq!(tools[0].args, serde_json::json!({"command": "ls", "cwd": "/tmp"}));
+    }
+
+    #[test]
+    fn test_xml_parameter_values_parsed_as_json_when_valid() {
+        let parser = StreamingToolParser::new();
+        let tools = parser.try_parse_xml_tool_calls_from_text(concat!(
+            r#"<invoke name="read_file">"#,
+            "\n<parameter name=\"file_path\">\nsrc/main.rs\n</parameter>",
+            r#"<parameter name="start">10</parameter>"#,
+            r#"<parameter name="options">{"raw": true}</parameter>"#,
+            "</invoke>",
+        ));
+
+        assert_eq!(tools.len(), 1);
+        assert_eq!(
+            tools[0].args,
+            serde_json::json!({"file_path": "src/main.rs", "start": 10, "options": {"raw": true}})
+        );
+    }
+
+    #[test]
+    fn test_xml_cdata_json_args() {
+        let parser = StreamingToolParser::new();
+        let tools = parser.try_parse_xml_tool_calls_from_text(
+            r#"<invoke name="shell"><parameter name="args"><![CDATA[{"command":"echo '<test>'"}]]></parameter></invoke>"#,
+        );
+
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].args, serde_json::json!({"command": "echo '<test>'"}));
+    }
+
+    #[test]
+    fn test_xml_cdata_plain_text() {
+        let parser = StreamingToolParser::new();
+        let tools = parser.try_parse_xml_tool_calls_from_text(concat!(
+            r#"<invoke name="write_file">"#,
+            r#"<parameter name="file_path">a.html</parameter>"#,
+            "<parameter name=\"content\">\n<![CDATA[<p>a &amp; b</p>\n  <br/>]]>\n</parameter>",
+            "</invoke>",
+        ));
+
+        assert_eq!(tools.len(), 1);
+        // CDATA content is literal: entities stay escaped and whitespace is kept
+        assert_eq!(
+            tools[0].args,
+            serde_json::json!({"file_path": "a.html", "content": "<p>a &amp; b</p>\n  <br/>"})
+        );
+
+        let tools = parser
+            .try_parse_xml_tool_calls_from_text(r#"<invoke name="shell"><![CDATA[ls <dir>]]></invoke>"#);
+        assert_eq!(tools[0].args, serde_json::json!({"command": "ls <dir>"}));
+    }
+
+    #[test]
+    fn test_xml_cdata_with_nested_terminator_kept_as_string() {
+        let parser = StreamingToolParser::new();
+        let tools = parser.try_parse_xml_tool_calls_from_text(
+            r#"<invoke name="tool"><parameter name="text"><![CDATA[a]]>b]]></parameter></invoke>"#,
+        );
+
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].args, serde_json::json!({"text": "<![CDATA[a]]>b]]>"}));
+    }
+
+    #[test]
+    fn test_xml_entities_unescaped_in_ampersand_command() {
+        let parser = StreamingToolParser::new();
+        let tools = parser.try_parse_xml_tool_calls_from_text(
+            r#"<invoke name="shell"><parameter name="command">echo "hello &amp; world"</parameter></invoke>"#,
+        );
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].args, serde_json::json!({"command": "echo \"hello & world\""}));
+    }
+
+    #[test]
+    fn test_xml_entities_unescaped_in_redirect_command() {
+        let parser = StreamingToolParser::new();
+        let tools = parser.try_parse_xml_tool_calls_from_text(
+            r#"<invoke name="shell"><parameter name="command">cat file.txt &gt; output.txt</parameter></invoke>"#,
+        );
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].args, serde_json::json!({"command": "cat file.txt > output.txt"}));
+
+        let tools = parser.try_parse_xml_tool_calls_from_text(
+            r#"<invoke name="shell">cat file.txt &gt; output.txt</invoke>"#,
+        );
+        assert_eq!(tools[0].args, serde_json::json!({"command": "cat file.txt > output.txt"}));
+    }
+
+    #[test]
+    fn test_xml_entities_unescaped_in_quoted_value() {
+        let parser = StreamingToolParser::new();
+        let tools = parser.try_parse_xml_tool_calls_from_text(
+            r#"<invoke name="tool"><parameter name="text">value with &quot;quotes&quot;</parameter></invoke>"#,
+        );
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].args, serde_json::json!({"text": "value with \"quotes\""}));
+    }
+
+    #[test]
+    fn test_xml_entities_unescaped_in_json_args() {
+        let parser = StreamingToolParser::new();
+        // Escaped quotes only make valid JSON once unescaped
+        let tools = parser.try_parse_xml_tool_calls_from_text(
+            r#"<invoke name="tool"><parameter name="args">{&quot;text&quot;: &quot;value with \&quot;quotes\&quot;&quot;}</parameter></invoke>"#,
+        );
+        assert_eq!(tools[0].args, serde_json::json!({"text": "value with \"quotes\""}));
+
+        let tools = parser.try_parse_xml_tool_calls_from_text(
+            r#"<invoke name="tool"><parameter name="options">{&quot;raw&quot;: true}</parameter></invoke>"#,
+        );
+        assert_eq!(tools[0].args, serde_json::json!({"options": {"raw": true}}));
+
+        let tools = parser.try_parse_xml_tool_calls_from_text(
+            r#"<invoke name="shell"><parameter name="args">{"command": "echo &lt;b&gt;"}</parameter></invoke>"#,
+        );
+        assert_eq!(tools[0].args, serde_json::json!({"command": "echo <b>"}));
+
+        // Only valid JSON while escaped: the strings are unescaped after parsing
+        let tools = parser.try_parse_xml_tool_calls_from_text(
+            r#"<invoke name="shell"><parameter name="args">{"command": "echo &quot;hi&quot;"}</parameter></invoke>"#,
+        );
+        assert_eq!(tools[0].args, serde_json::json!({"command": "echo \"hi\""}));
+    }
+
+    #[test]
+    fn test_xml_file_content_is_never_unescaped() {
+        let parser = StreamingToolParser::new();
+        let tools = parser.try_parse_xml_tool_calls_from_text(concat!(
+            r#"<invoke name="write_file">"#,
+            r#"<parameter name="file_path">a.html</parameter>"#,
+            r#"<parameter name="content">&quot;&lt;script&gt;&quot;</parameter>"#,
+            "</invoke>",
+        ));
+        assert_eq!(
+            tools[0].args,
+            serde_json::json!({"file_path": "a.html", "content": "&quot;&lt;script&gt;&quot;"})
+        );
+    }
+
+    #[test]
+    fn test_xml_file_content_is_the_same_as_parameter_or_in_args() {
+        let parser = StreamingToolParser::new();
+        let as_parameter = parser.try_parse_xml_tool_calls_from_text(concat!(
+            r#"<invoke name="write_file">"#,
+            r#"<parameter name="file_path">a &amp; b.html</parameter>"#,
+            r#"<parameter name="content">&lt;p&gt;a &amp;amp; b&lt;/p&gt;</parameter>"#,
+            "</invoke>",
+        ));
+        let in_args = parser.try_parse_xml_tool_calls_from_text(concat!(
+            r#"<invoke name="write_file"><parameter name="args">"#,
+            r#"{"file_path": "a &amp; b.html", "content": "&lt;p&gt;a &amp;amp; b&lt;/p&gt;"}"#,
+            "</parameter></invoke>",
+        ));
+
+        assert_eq!(as_parameter[0].args, in_args[0].args);
+        assert_eq!(
+            in_args[0].args,
+            serde_json::json!({"file_path": "a & b.html", "content": "&lt;p&gt;a &amp;amp; b&lt;/p&gt;"})
+        );
+    }
+
+    #[test]
+    fn test_xml_unescape() {
+        assert_eq!(xml_unescape("&lt;a href=&apos;x&apos;&gt;"), "<a href='x'>");
+        assert_eq!(xml_unescape("&amp;lt; stays &lt;"), "&lt; stays <");
+        assert_eq!(xml_unescape("a & b &nbsp; &amp"), "a & b &nbsp; &amp");
+        assert_eq!(xml_unescape("café"), "café");
+    }
+
+    #[test]
+    fn test_find_complete_xml_element_end_offsets() {
+        let text = r#"<invoke name="a>b">body</invoke> trailing"#;
+        let (content_start, end) = StreamingToolParser::find_complete_xml_element_end(text).unwrap();
+
+        assert_eq!(&text[content_start..end], "body</invoke>");
+        assert_eq!(&text[..end], r#"<invoke name="a>b">body</invoke>"#);
+    }
+
+    #[test]
+    fn test_buffer_stops_growing_past_limit() {
+        let mut parser = StreamingToolParser::new().with_max_buffer_bytes(Some(10));
+        parser.process_chunk(&text_chunk("hello "));
+        assert!(!parser.is_buffer_truncated());
+        // "é" is two bytes and would straddle the limit, so it is dropped whole
+        parser.process_chunk(&text_chunk("wor\u{e9}ld"));
+        parser.process_chunk(&text_chunk("more text"));
+
+        assert!(parser.is_buffer_truncated());
+        assert_eq!(parser.get_text_content(), "hello wor");
+        assert_eq!(parser.bytes_consumed(), 22);
+
+        let turn = parser.reset();
+        assert_eq!(turn.text, "hello wor");
+        assert!(!parser.is_buffer_truncated());
+    }
+
+    #[test]
+    fn test_truncated_buffer_still_returns_native_tool_calls() {
+        let mut parser = StreamingToolParser::new().with_max_buffer_bytes(Some(4));
+        let tools = parser.process_chunk(&g3_providers::CompletionChunk {
+            content: "x".repeat(100),
+            finished: true,
+            tool_calls: Some(vec![g3_providers::ToolCall {
+                id: "call_1".to_string(),
+                tool: "read_file".to_string(),
+                args: serde_json::json!({"file_path": "a.txt"}),
+            }]),
+            usage: None,
+        });
+
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].tool, "read_file");
+        assert_eq!(parser.text_buffer_len(), 4);
+        assert!(parser.is_buffer_truncated());
+    }
+
+    #[test]
+    fn test_unlimited_buffer() {
+        let mut parser = StreamingToolParser::new().with_max_buffer_bytes(None);
+        parser.process_chunk(&text_chunk(&"a".repeat(DEFAULT_MAX_BUFFER_BYTES + 1)));
+        assert!(!parser.is_buffer_truncated());
+        assert_eq!(parser.text_buffer_len(), DEFAULT_MAX_BUFFER_BYTES + 1);
+    }
+}
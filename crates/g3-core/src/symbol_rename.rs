@@ -0,0 +1,382 @@
+//! Renaming a symbol across files with tree-sitter.
+//!
+//! Every identifier node whose text is exactly the old name is renamed, so
+//! strings, comments and longer names containing it are left alone. The match
+//! is purely syntactic: there is no scope or type resolution, and an optional
+//! [`SymbolType`] narrows it by where the identifier appears (a function
+//! definition or call, a type, or anything else).
+
+use anyhow::{anyhow, bail, Result};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tree_sitter::{Language, Node, Parser};
+
+use crate::tools::file_ops::write_atomic;
+
+/// Nodes defining a function, with its name in the `name` field.
+const FUNCTION_DEFINITIONS: &[&str] = &[
+    "function_item",
+    "function_signature_item",
+    "function_definition",
+    "function_declaration",
+    "generator_function_declaration",
+    "method_definition",
+    "method_declaration",
+    "method_signature",
+];
+
+/// Nodes defining a type, with its name in the `name` field.
+const TYPE_DEFINITIONS: &[&str] = &[
+    "struct_item",
+    "enum_item",
+    "union_item",
+    "trait_item",
+    "type_item",
+    "class_definition",
+    "class_declaration",
+    "interface_declaration",
+    "enum_declaration",
+    "type_alias_declaration",
+    "type_spec",
+    "struct_specifier",
+    "enum_specifier",
+    "class_specifier",
+];
+
+/// Call nodes and the field holding what they call.
+const CALLS: &[(&str, &str)] = &[
+    ("call_expression", "function"),
+    ("call", "function"),
+    ("macro_invocation", "macro"),
+    ("method_invocation", "name"),
+];
+
+/// Fields naming the last segment of a path or member access such as
+/// `module::name`, `value.name` or `name::<T>`.
+const MEMBER_FIELDS: &[&str] = &["name", "field", "property", "attribute", "function"];
+
+/// Which occurrences of a name [`find_occurrences`] matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolType {
+    /// Function and method definitions and calls
+    Function,
+    /// Type names and type definitions
+    Type,
+    /// Any other identifier: variables, parameters, fields, modules
+    Variable,
+}
+
+impl SymbolType {
+    pub fn parse(symbol_type: &str) -> Result<Self> {
+        match symbol_type.to_lowercase().as_str() {
+            "function" | "method" | "fn" => Ok(Self::Function),
+            "type" | "class" | "struct" | "enum" | "trait" | "interface" => Ok(Self::Type),
+            "variable" | "var" | "field" | "parameter" => Ok(Self::Variable),
+            other => bail!(
+                "Unknown symbol_type '{}': expected function, variable or type",
+                other
+            ),
+        }
+    }
+
+    fn of(node: Node) -> Self {
+        if is_function_name(node) {
+            Self::Function
+        } else if node.kind() == "type_identifier" || is_name_of(node, TYPE_DEFINITIONS) {
+            Self::Type
+        } else {
+            Self::Variable
+        }
+    }
+}
+
+/// Whether `node` is the `name` of a parent whose kind is in `kinds`.
+fn is_name_of(node: Node, kinds: &[&str]) -> bool {
+    node.parent().is_some_and(|parent| {
+        kinds.contains(&parent.kind()) && parent.child_by_field_name("name") == Some(node)
+    })
+}
+
+fn is_function_name(node: Node) -> bool {
+    if is_name_of(node, FUNCTION_DEFINITIONS) {
+        return true;
+    }
+    // C and C++ name functions through a declarator
+    if let Some(parent) = node.parent() {
+        if parent.kind() == "function_declarator"
+            && parent.child_by_field_name("declarator") == Some(node)
+        {
+            return true;
+        }
+    }
+
+    // A call of `name`, `path::name`, `value.name` or `name::<T>`
+    let mut callee = node;
+    while let Some(parent) = callee.parent() {
+        if let Some((_, field)) = CALLS.iter().find(|(kind, _)| *kind == parent.kind()) {
+            return parent.child_by_field_name(field) == Some(callee);
+        }
+        let is_last_segment = MEMBER_FIELDS
+            .iter()
+            .any(|field| parent.child_by_field_name(field) == Some(callee));
+        if !is_last_segment {
+            return false;
+        }
+        callee = parent;
+    }
+    false
+}
+
+/// Parser language for a `language` name as used by `code_search`.
+pub fn language_for(language: &str) -> Option<Language> {
+    Some(match language.to_lowercase().as_str() {
+        "rust" | "rs" => tree_sitter_rust::LANGUAGE.into(),
+        "python" | "py" => tree_sitter_python::LANGUAGE.into(),
+        "javascript" | "js" => tree_sitter_javascript::LANGUAGE.into(),
+        "typescript" | "ts" => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+        "go" => tree_sitter_go::LANGUAGE.into(),
+        "java" => tree_sitter_java::LANGUAGE.into(),
+        "c" => tree_sitter_c::LANGUAGE.into(),
+        "cpp" | "c++" => tree_sitter_cpp::LANGUAGE.into(),
+        _ => return None,
+    })
+}
+
+/// File extensions searched for `language` when renaming in a directory.
+fn extensions_for(language: &str) -> &'static [&'static str] {
+    match language.to_lowercase().as_str() {
+        "rust" | "rs" => &["rs"],
+        "python" | "py" => &["py"],
+        "javascript" | "js" => &["js", "jsx", "mjs", "cjs"],
+        "typescript" | "ts" => &["ts"],
+        "go" => &["go"],
+        "java" => &["java"],
+        "c" => &["c", "h"],
+        "cpp" | "c++" => &["cpp", "cc", "cxx", "hpp", "hh", "h"],
+        _ => &[],
+    }
+}
+
+/// Byte ranges of the identifiers in `source` named exactly `name`, in order.
+pub fn find_occurrences(
+    source: &str,
+    language: &Language,
+    name: &str,
+    symbol_type: Option<SymbolType>,
+) -> Result<Vec<Range<usize>>> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(language)
+        .map_err(|e| anyhow!("Failed to set language: {}", e))?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or_else(|| anyhow!("Failed to parse source"))?;
+
+    let mut ranges = Vec::new();
+    let mut cursor = tree.walk();
+    'walk: loop {
+        let node = cursor.node();
+        if node.child_count() == 0
+            && node.kind().ends_with("identifier")
+            && &source[node.byte_range()] == name
+            && symbol_type.is_none_or(|t| SymbolType::of(node) == t)
+        {
+            ranges.push(node.byte_range());
+        }
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+    Ok(ranges)
+}
+
+/// `source` with each range replaced by `new_name`, applied from the end so
+/// earlier offsets stay valid.
+pub fn replace_ranges(source: &str, ranges: &[Range<usize>], new_name: &str) -> String {
+    let mut renamed = source.to_string();
+    for range in ranges.iter().rev() {
+        renamed.replace_range(range.clone(), new_name);
+    }
+    renamed
+}
+
+/// Whether `name` can be used as an identifier.
+pub fn is_valid_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Rename `old_name` to `new_name` in the file `path`, or in every
+/// `language` file under the directory `path`. Files ignored by `.gitignore`,
+/// hidden files and files that aren't UTF-8 are skipped. Returns the files
+/// that had occurrences and how many; with `dry_run` nothing is written.
+/// Nothing is written either if any file fails to parse.
+pub fn rename_symbol(
+    path: &Path,
+    language: &str,
+    old_name: &str,
+    new_name: &str,
+    symbol_type: Option<SymbolType>,
+    dry_run: bool,
+) -> Result<Vec<(PathBuf, usize)>> {
+    let grammar = language_for(language)
+        .ok_or_else(|| anyhow!("Unsupported language '{}'", language))?;
+    if !is_valid_identifier(new_name) {
+        bail!("'{}' is not a valid identifier", new_name);
+    }
+
+    let files: Vec<PathBuf> = if path.is_file() {
+        vec![path.to_path_buf()]
+    } else {
+        let extensions = extensions_for(language);
+        let walker = ignore::WalkBuilder::new(path)
+            .require_git(false)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .build();
+        let mut files = Vec::new();
+        for entry in walker {
+            let entry = entry?;
+            let has_extension = entry
+                .path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| extensions.contains(&ext));
+            if entry.file_type().is_some_and(|t| t.is_file()) && has_extension {
+                files.push(entry.into_path());
+            }
+        }
+        files
+    };
+
+    let mut renamed = Vec::new();
+    for file in files {
+        let Ok(source) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let ranges = find_occurrences(&source, &grammar, old_name, symbol_type)
+            .map_err(|e| anyhow!("{}: {}", file.display(), e))?;
+        if !ranges.is_empty() {
+            let updated = replace_ranges(&source, &ranges, new_name);
+            renamed.push((file, ranges.len(), updated));
+        }
+    }
+
+    if !dry_run {
+        for (file, _, updated) in &renamed {
+            write_atomic(file, updated.as_bytes())
+                .map_err(|e| anyhow!("Failed to write {}: {}", file.display(), e))?;
+        }
+    }
+    Ok(renamed
+        .into_iter()
+        .map(|(file, count, _)| (file, count))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const RUST_SOURCE: &str = r#"/// Calls compute_total once
+fn compute_total(items: &[u32]) -> u32 {
+    items.iter().sum()
+}
+
+struct Cart {
+    compute_total: u32,
+}
+
+fn main() {
+    let compute_total_label = "compute_total";
+    let total = compute_total(&[1, 2]);
+    let cart = Cart { compute_total: total };
+    let again = crate::compute_total(&[cart.compute_total]);
+    println!("{} {}", compute_total_label, again);
+}
+"#;
+
+    #[test]
+    fn test_rename_function_in_rust_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("main.rs");
+        std::fs::write(&path, RUST_SOURCE).unwrap();
+
+        let renamed = rename_symbol(
+            &path,
+            "rust",
+            "compute_total",
+            "sum_items",
+            Some(SymbolType::Function),
+            false,
+        )
+        .unwrap();
+        assert_eq!(renamed, vec![(path.clone(), 3)]);
+
+        let source = std::fs::read_to_string(&path).unwrap();
+        assert!(source.contains("fn sum_items(items: &[u32])"));
+        assert!(source.contains("let total = sum_items(&[1, 2]);"));
+        assert!(source.contains("crate::sum_items(&[cart.compute_total])"));
+        // Comments, strings, longer names and the field keep the old name
+        assert!(source.contains("/// Calls compute_total once"));
+        assert!(source.contains("let compute_total_label = \"compute_total\";"));
+        assert!(source.contains("    compute_total: u32,"));
+        assert!(source.contains("Cart { compute_total: total }"));
+    }
+
+    #[test]
+    fn test_rename_every_occurrence_and_dry_run() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("main.rs");
+        std::fs::write(&path, RUST_SOURCE).unwrap();
+
+        let renamed = rename_symbol(&path, "rust", "compute_total", "total", None, true).unwrap();
+        assert_eq!(renamed, vec![(path.clone(), 6)]);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), RUST_SOURCE);
+
+        let language = language_for("rust").unwrap();
+        let fields =
+            find_occurrences(RUST_SOURCE, &language, "compute_total", Some(SymbolType::Variable))
+                .unwrap();
+        assert_eq!(fields.len(), 3);
+        let types = find_occurrences(RUST_SOURCE, &language, "Cart", Some(SymbolType::Type)).unwrap();
+        assert_eq!(types.len(), 2);
+    }
+
+    #[test]
+    fn test_rename_across_directory() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "pub fn helper() {}\n").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() { lib::helper(); }\n").unwrap();
+        std::fs::write(dir.path().join("notes.md"), "helper()\n").unwrap();
+
+        let renamed = rename_symbol(dir.path(), "rust", "helper", "assist", None, false).unwrap();
+        assert_eq!(renamed.len(), 2);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("src/main.rs")).unwrap(),
+            "fn main() { lib::assist(); }\n"
+        );
+        assert_eq!(std::fs::read_to_string(dir.path().join("notes.md")).unwrap(), "helper()\n");
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("main.rs");
+        std::fs::write(&path, "fn a() {}\n").unwrap();
+        assert!(rename_symbol(&path, "cobol", "a", "b", None, false).is_err());
+        assert!(rename_symbol(&path, "rust", "a", "not valid", None, false).is_err());
+        assert!(SymbolType::parse("constant").is_err());
+        assert_eq!(SymbolType::parse("Class").unwrap(), SymbolType::Type);
+    }
+}
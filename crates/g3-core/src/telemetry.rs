@@ -0,0 +1,198 @@
+//! Machine-readable per-tool timing telemetry.
+//!
+//! Every tool call the agent makes is recorded as a [`ToolTelemetry`] event,
+//! kept in memory for the session and appended as one JSON line to the
+//! telemetry file (`.g3/session/telemetry.jsonl` unless configured otherwise),
+//! so CI and observability tooling can consume it without parsing UI output.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::warn;
+
+/// One tool execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolTelemetry {
+    pub tool_name: String,
+    pub duration_ms: u64,
+    pub success: bool,
+    pub output_bytes: usize,
+    pub session_id: String,
+    /// RFC 3339 time the tool finished, in UTC
+    pub timestamp_utc: String,
+}
+
+impl ToolTelemetry {
+    pub fn new(
+        tool_name: &str,
+        duration: Duration,
+        output: &str,
+        session_id: Option<&str>,
+    ) -> Self {
+        Self {
+            tool_name: tool_name.to_string(),
+            duration_ms: duration.as_millis() as u64,
+            success: !output.contains("❌"),
+            output_bytes: output.len(),
+            session_id: session_id.unwrap_or_default().to_string(),
+            timestamp_utc: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        }
+    }
+}
+
+/// Aggregated telemetry for one tool.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolTotals {
+    pub calls: usize,
+    pub failures: usize,
+    pub duration_ms: u64,
+    pub output_bytes: usize,
+}
+
+/// Telemetry events for a session, mirrored to a JSONL file.
+#[derive(Debug)]
+pub struct ToolTelemetryLog {
+    path: PathBuf,
+    events: Vec<ToolTelemetry>,
+}
+
+impl ToolTelemetryLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Keep the event and append it to the telemetry file.
+    /// Failing to write the file is logged, never fatal.
+    pub fn record(&mut self, event: ToolTelemetry) {
+        if let Err(e) = append_jsonl(&self.path, &event) {
+            warn!(
+                "Failed to write tool telemetry to {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+        self.events.push(event);
+    }
+
+    pub fn events(&self) -> &[ToolTelemetry] {
+        &self.events
+    }
+
+    /// Per-tool totals, keyed by tool name.
+    pub fn totals(&self) -> BTreeMap<String, ToolTotals> {
+        let mut totals: BTreeMap<String, ToolTotals> = BTreeMap::new();
+        for event in &self.events {
+            let entry = totals.entry(event.tool_name.clone()).or_default();
+            entry.calls += 1;
+            if !event.success {
+                entry.failures += 1;
+            }
+            entry.duration_ms += event.duration_ms;
+            entry.output_bytes += event.output_bytes;
+        }
+        totals
+    }
+}
+
+fn append_jsonl(path: &Path, event: &ToolTelemetry) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_are_appended_as_jsonl() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested").join("telemetry.jsonl");
+        let mut log = ToolTelemetryLog::new(path.clone());
+
+        log.record(ToolTelemetry::new(
+            "read_file",
+            Duration::from_millis(12),
+            "fn main() {}",
+            Some("session-1"),
+        ));
+        log.record(ToolTelemetry::new(
+            "shell",
+            Duration::from_millis(340),
+            "❌ Command failed: exit 1",
+            Some("session-1"),
+        ));
+
+        let events: Vec<ToolTelemetry> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events, log.events());
+
+        assert_eq!(events[0].tool_name, "read_file");
+        assert_eq!(events[0].duration_ms, 12);
+        assert!(events[0].success);
+        assert_eq!(events[0].output_bytes, "fn main() {}".len());
+        assert_eq!(events[0].session_id, "session-1");
+        assert!(events[0].timestamp_utc.ends_with('Z'));
+
+        assert_eq!(events[1].tool_name, "shell");
+        assert!(!events[1].success);
+    }
+
+    #[test]
+    fn test_totals_per_tool() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut log = ToolTelemetryLog::new(temp_dir.path().join("telemetry.jsonl"));
+
+        log.record(ToolTelemetry::new(
+            "shell",
+            Duration::from_millis(100),
+            "ok",
+            None,
+        ));
+        log.record(ToolTelemetry::new(
+            "shell",
+            Duration::from_millis(50),
+            "❌ no",
+            None,
+        ));
+        log.record(ToolTelemetry::new(
+            "read_file",
+            Duration::from_millis(5),
+            "abc",
+            None,
+        ));
+
+        let totals = log.totals();
+        assert_eq!(
+            totals["shell"],
+            ToolTotals {
+                calls: 2,
+                failures: 1,
+                duration_ms: 150,
+                output_bytes: "ok".len() + "❌ no".len(),
+            }
+        );
+        assert_eq!(totals["read_file"].calls, 1);
+    }
+}
@@ -51,13 +51,21 @@ fn create_core_tools() -> Vec<Tool> {
     vec![
         Tool {
             name: "shell".to_string(),
-            description: "Execute shell commands".to_string(),
+            description: "Execute shell commands. The result starts with an `[exit: N]` line holding the exit code, followed by the combined stdout and stderr. Detached commands (`nohup`, `setsid`, `disown`) are not waited for: they report `[exit: 0]` once launched.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
                     "command": {
                         "type": "string",
                         "description": "The shell command to execute"
+                    },
+                    "return_json": {
+                        "type": "boolean",
+                        "description": "Return {\"exit_code\", \"stdout\", \"stderr\", \"truncated\"} as JSON instead, with stdout and stderr kept apart (default: false)"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Optional timeout in seconds; overrides the configured default tool timeout"
                     }
                 },
                 "required": ["command"]
@@ -65,7 +73,7 @@ fn create_core_tools() -> Vec<Tool> {
         },
         Tool {
             name: "background_process".to_string(),
-            description: "Launch a long-running process in the background (e.g., game servers, dev servers). The process runs independently and logs are captured to a file. Use the regular 'shell' tool to read logs (cat/tail), check status (ps), or stop the process (kill). Returns the PID and log file path.".to_string(),
+            description: "Launch a long-running process in the background (e.g., game servers, dev servers). The process runs independently and its stdout and stderr are captured to log files. Use get_background_process_output to read new output, process_output_wait to wait for a line such as a server's ready message, list_background_processes to check status, and stop_background_process to stop it. Returns the PID and log file paths.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -87,7 +95,7 @@ fn create_core_tools() -> Vec<Tool> {
         },
         Tool {
             name: "read_file".to_string(),
-            description: "Read the contents of a file. Optionally read a specific character range.".to_string(),
+            description: "Read the contents of a file. Optionally read a specific character range, or read a file on a remote host over SSH. Files larger than max_bytes are truncated unless force is set.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -102,6 +110,26 @@ fn create_core_tools() -> Vec<Tool> {
                     "end": {
                         "type": "integer",
                         "description": "Ending character position (0-indexed, EXCLUSIVE). If omitted, reads to end of file."
+                    },
+                    "annotate_lines": {
+                        "type": "boolean",
+                        "description": "Prefix each line with its line number in the file, e.g. '   12: code'. Useful before writing a diff for str_replace. The numbers are not part of the file."
+                    },
+                    "ssh_host": {
+                        "type": "string",
+                        "description": "Read file_path on this remote host over SSH/SFTP instead of locally, as [user@]host[:port]. Relative paths are relative to the remote home directory."
+                    },
+                    "max_bytes": {
+                        "type": "integer",
+                        "description": "Return at most this many bytes; the rest is replaced by a note saying how much was left out. Defaults to the configured max_file_read_bytes (1 MiB)."
+                    },
+                    "force": {
+                        "type": "boolean",
+                        "description": "Return the whole file (or range) even if it is larger than max_bytes."
+                    },
+                    "summarize": {
+                        "type": "boolean",
+                        "description": "If the file is larger than max_bytes, return its size, line count, first 10 and last 10 lines instead of truncated content. Use start/end afterwards to read the part you need."
                     }
                 },
                 "required": ["file_path"]
@@ -135,6 +163,18 @@ fn create_core_tools() -> Vec<Tool> {
                     "content": {
                         "type": "string",
                         "description": "The content to write to the file"
+                    },
+                    "atomic": {
+                        "type": "boolean",
+                        "description": "Write to a temp file and rename it into place, so the file is never left half-written (default: false)"
+                    },
+                    "overwrite": {
+                        "type": "boolean",
+                        "description": "Replace the file if it already exists (default: true)"
+                    },
+                    "create_dirs": {
+                        "type": "boolean",
+                        "description": "Create missing parent directories (default: true)"
                     }
                 },
                 "required": ["file_path", "content"]
@@ -161,6 +201,18 @@ fn create_core_tools() -> Vec<Tool> {
                     "end": {
                         "type": "integer",
                         "description": "Ending character position in the file (0-indexed, EXCLUSIVE - character at this position is NOT included). If omitted, searches to end of file."
+                    },
+                    "fuzz_lines": {
+                        "type": "integer",
+                        "description": "If a hunk does not match exactly, ignore up to this many leading/trailing context lines when locating it (default: 0, max: 3)"
+                    },
+                    "normalize_line_endings": {
+                        "type": "boolean",
+                        "description": "Match the diff regardless of CRLF/LF line endings in the file or diff, keeping the file's own line endings (default: true). Set to false to match the file byte for byte."
+                    },
+                    "create_backup": {
+                        "type": "boolean",
+                        "description": "Copy the original file to <file_path>.g3.bak first, replacing any earlier backup; undo with restore_backup (default: false)"
                     }
                 },
                 "required": ["file_path", "diff"]
@@ -168,13 +220,39 @@ fn create_core_tools() -> Vec<Tool> {
         },
         Tool {
             name: "final_output".to_string(),
-            description: "Signal task completion with a detailed summary".to_string(),
+            description: "Signal task completion with a detailed summary. Set structured=true to also report files changed, test results and next steps as JSON.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
                     "summary": {
                         "type": "string",
                         "description": "A detailed summary in markdown of what was accomplished"
+                    },
+                    "structured": {
+                        "type": "boolean",
+                        "description": "Append a machine-readable JSON report and save it to .g3/session/final_output.json (default: false)"
+                    },
+                    "files_changed": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Paths of files created or modified (structured only)"
+                    },
+                    "tests_run": {
+                        "type": "boolean",
+                        "description": "Whether tests were run (structured only)"
+                    },
+                    "tests_passed": {
+                        "type": "integer",
+                        "description": "Number of passing tests (structured only)"
+                    },
+                    "tests_failed": {
+                        "type": "integer",
+                        "description": "Number of failing tests (structured only)"
+                    },
+                    "next_steps": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Suggested follow-up work (structured only)"
                     }
                 },
                 "required": ["summary"]
@@ -202,6 +280,10 @@ fn create_core_tools() -> Vec<Tool> {
                             "width": {"type": "integer"},
                             "height": {"type": "integer"}
                         }
+                    },
+                    "attach_to_context": {
+                        "type": "boolean",
+                        "description": "Attach the screenshot as an image to the conversation so you can see it directly instead of using OCR (default: false)"
                     }
                 },
                 "required": ["path", "window_id"]
@@ -241,10 +323,14 @@ fn create_core_tools() -> Vec<Tool> {
         },
         Tool {
             name: "code_search".to_string(),
-            description: "Syntax-aware code search that understands code structure, not just text. Finds actual functions, classes, methods, and other code constructs - ignores matches in comments and strings. Much more accurate than grep for code searches. Supports batch searches (up to 20 parallel) with structured results and context lines. Languages: Rust, Python, JavaScript, TypeScript, Go, Java, C, C++, Kotlin. Uses tree-sitter query syntax.".to_string(),
+            description: "Syntax-aware code search that understands code structure, not just text. Finds actual functions, classes, methods, and other code constructs - ignores matches in comments and strings. Much more accurate than grep for code searches. Supports batch searches (up to 20 parallel) with structured results and context lines. Languages: Rust, Python, JavaScript, TypeScript, Go, Java, C, C++, Kotlin. Uses tree-sitter query syntax. Results are ranked by relevance, best first. With mode 'semantic', finds functions and classes related to a natural-language query instead; run action 'build_semantic_index' first (and again after large changes) to index the project into .g3/embeddings.bin. Semantic mode falls back to the tree-sitter searches given when there is no index.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
+                    "action": { "type": "string", "enum": ["search", "build_semantic_index"], "default": "search", "description": "'build_semantic_index' scans the project and (re)builds the index used by semantic mode." },
+                    "mode": { "type": "string", "enum": ["tree-sitter", "semantic"], "default": "tree-sitter", "description": "'semantic' ranks indexed definitions by similarity to query instead of running tree-sitter searches." },
+                    "query": { "type": "string", "description": "For semantic mode: what the code you are looking for does, in plain words (e.g., \"retry failed HTTP requests\")." },
+                    "top_k": { "type": "integer", "minimum": 1, "default": 10, "description": "For semantic mode: number of definitions to return." },
                     "searches": {
                         "type": "array",
                         "maxItems": 20,
@@ -255,190 +341,321 @@ fn create_core_tools() -> Vec<Tool> {
                                 "query": { "type": "string", "description": "tree-sitter query in S-expression format (e.g., \"(function_item name: (identifier) @name)\")" },
                                 "language": { "type": "string", "enum": ["rust", "python", "javascript", "typescript", "go", "java", "c", "cpp", "kotlin"], "description": "Programming language to search." },
                                 "paths": { "type": "array", "items": { "type": "string" }, "description": "Paths/dirs to search. Defaults to current dir if empty." },
-                                "context_lines": { "type": "integer", "minimum": 0, "maximum": 20, "default": 0, "description": "Lines of context to include around each match." }
+                                "context_lines": { "type": "integer", "minimum": 0, "maximum": 20, "default": 0, "description": "Lines of context to include around each match. Overrides the top-level context_lines." },
+                                "symbol": { "type": "string", "description": "Name of the symbol you are looking for. Matches whose name equals it rank first." }
                             },
                             "required": ["name", "query", "language"]
                         }
                     },
                     "max_concurrency": { "type": "integer", "minimum": 1, "default": 4 },
-                    "max_matches_per_search": { "type": "integer", "minimum": 1, "default": 500 }
+                    "max_matches_per_search": { "type": "integer", "minimum": 1, "default": 500 },
+                    "max_results": { "type": "integer", "minimum": 1, "default": 20, "description": "Matches returned per search. Matches are ranked by name match, how recently the file changed and closeness to files you recently read or edited; match_count still reports all matches found." },
+                    "context_lines": { "type": "integer", "minimum": 0, "maximum": 20, "default": 0, "description": "Lines of context to include around every match, as a numbered code block with >>> marking the matched lines." }
                 },
-                "required": ["searches"]
+                "required": []
             }),
         },
-    ]
-}
-
-
-
-
-
-/// Create WebDriver browser automation tools
-fn create_webdriver_tools() -> Vec<Tool> {
-    vec![
         Tool {
-            name: "webdriver_start".to_string(),
-            description: "Start a Safari WebDriver session for browser automation. Must be called before any other webdriver tools. Requires Safari's 'Allow Remote Automation' to be enabled in Develop menu.".to_string(),
+            name: "git".to_string(),
+            description: "Interact with the git repository without parsing raw shell output. 'status' returns JSON with staged, unstaged and untracked files; 'diff' returns the unified diff of the repo or one file; 'commit' stages the given paths and commits them.".to_string(),
             input_schema: json!({
                 "type": "object",
-                "properties": {},
-                "required": []
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["status", "diff", "commit"],
+                        "description": "The git operation to perform"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "For 'diff': limit the diff to this file"
+                    },
+                    "staged": {
+                        "type": "boolean",
+                        "description": "For 'diff': show staged changes instead of unstaged (default: false)"
+                    },
+                    "message": {
+                        "type": "string",
+                        "description": "For 'commit': the commit message"
+                    },
+                    "paths": {
+                        "type": "array",
+                        "items": {
+                            "type": "string"
+                        },
+                        "description": "For 'commit': paths to stage before committing. If omitted, commits what is already staged."
+                    }
+                },
+                "required": ["action"]
             }),
         },
         Tool {
-            name: "webdriver_navigate".to_string(),
-            description: "Navigate to a URL in the browser".to_string(),
+            name: "code_lint".to_string(),
+            description: "Run the language-appropriate linter on a file or directory and return structured results (file, line, col, severity, message, rule). Uses cargo clippy for Rust, ruff for Python and eslint for TypeScript/JavaScript.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "url": {
+                    "path": {
                         "type": "string",
-                        "description": "The URL to navigate to (must include protocol, e.g., https://)"
+                        "description": "File or directory to lint"
+                    },
+                    "language": {
+                        "type": "string",
+                        "enum": ["rust", "python", "typescript", "javascript"],
+                        "description": "Language to lint. Auto-detected from the file extension or project files if omitted."
+                    },
+                    "fix": {
+                        "type": "boolean",
+                        "description": "Apply automatic fixes where the linter supports it (default: false)"
+                    },
+                    "output_format": {
+                        "type": "string",
+                        "enum": ["text", "json"],
+                        "description": "Return results as readable text (default) or a JSON array"
                     }
                 },
-                "required": ["url"]
+                "required": ["path"]
             }),
         },
         Tool {
-            name: "webdriver_get_url".to_string(),
-            description: "Get the current URL of the browser".to_string(),
+            name: "run_tests".to_string(),
+            description: "Run a project's test suite and return structured pass/fail results with failure output. Uses cargo test for Rust and pytest (with the pytest-json-report plugin) for Python.".to_string(),
             input_schema: json!({
                 "type": "object",
-                "properties": {},
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Project directory or file to test (default: current directory)"
+                    },
+                    "language": {
+                        "type": "string",
+                        "enum": ["rust", "python"],
+                        "description": "Language of the project. Auto-detected from project files if omitted."
+                    },
+                    "filter": {
+                        "type": "string",
+                        "description": "Only run tests whose name matches this pattern"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Maximum time to let the test run take (default: 120)"
+                    }
+                },
                 "required": []
             }),
         },
         Tool {
-            name: "webdriver_get_title".to_string(),
-            description: "Get the title of the current page".to_string(),
+            name: "shell_session_start".to_string(),
+            description: "Start a named persistent shell session. Unlike the shell tool, a session keeps its working directory (cd) and exported environment variables between commands. Stop it with shell_session_stop when done.".to_string(),
             input_schema: json!({
                 "type": "object",
-                "properties": {},
-                "required": []
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Name used to refer to the session in later calls"
+                    },
+                    "shell": {
+                        "type": "string",
+                        "description": "Shell to run, e.g. bash or zsh (default: bash)"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory to start the shell in (default: current directory)"
+                    }
+                },
+                "required": ["name"]
             }),
         },
         Tool {
-            name: "webdriver_find_element".to_string(),
-            description: "Find an element on the page by CSS selector and return its text content".to_string(),
+            name: "shell_session_run".to_string(),
+            description: "Run a command in a persistent shell session started with shell_session_start and return its combined stdout and stderr. The command cannot read from stdin.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "selector": {
+                    "name": {
                         "type": "string",
-                        "description": "CSS selector to find the element (e.g., 'h1', '.class-name', '#id')"
+                        "description": "Name of the session"
+                    },
+                    "command": {
+                        "type": "string",
+                        "description": "The shell command to run"
                     }
                 },
-                "required": ["selector"]
+                "required": ["name", "command"]
             }),
         },
         Tool {
-            name: "webdriver_find_elements".to_string(),
-            description: "Find all elements matching a CSS selector and return their text content".to_string(),
+            name: "shell_session_stop".to_string(),
+            description: "Terminate a persistent shell session started with shell_session_start.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "selector": {
+                    "name": {
                         "type": "string",
-                        "description": "CSS selector to find elements"
+                        "description": "Name of the session"
                     }
                 },
-                "required": ["selector"]
+                "required": ["name"]
             }),
         },
         Tool {
-            name: "webdriver_click".to_string(),
-            description: "Click an element on the page".to_string(),
+            name: "directory_tree".to_string(),
+            description: "Show the layout of a directory as an indented tree (directories end in /). Skips hidden files and .gitignore'd paths by default, and lists at most 100 entries per directory.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "selector": {
+                    "path": {
                         "type": "string",
-                        "description": "CSS selector for the element to click"
+                        "description": "Directory to show (default: current directory)"
+                    },
+                    "max_depth": {
+                        "type": "integer",
+                        "description": "Maximum depth to descend (default: 5)"
+                    },
+                    "include_hidden": {
+                        "type": "boolean",
+                        "description": "Include hidden files and directories (default: false)"
+                    },
+                    "respect_gitignore": {
+                        "type": "boolean",
+                        "description": "Skip paths matched by .gitignore (default: true)"
                     }
                 },
-                "required": ["selector"]
+                "required": []
             }),
         },
         Tool {
-            name: "webdriver_send_keys".to_string(),
-            description: "Type text into an input element".to_string(),
+            name: "http_request".to_string(),
+            description: "Make an HTTP request without a browser (API calls, webhooks, health checks). Returns the status code, common response headers and the body (truncated to 50 KB). Use output_path to save binary responses to disk.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "selector": {
+                    "method": {
                         "type": "string",
-                        "description": "CSS selector for the input element"
+                        "description": "HTTP method: GET, POST, PUT, DELETE, PATCH or HEAD (default: GET)"
                     },
-                    "text": {
+                    "url": {
                         "type": "string",
-                        "description": "Text to type into the element"
+                        "description": "The URL to request"
                     },
-                    "clear_first": {
+                    "headers": {
+                        "type": "object",
+                        "description": "Request headers as name/value pairs",
+                        "additionalProperties": {
+                            "type": "string"
+                        }
+                    },
+                    "body": {
+                        "type": "string",
+                        "description": "Request body"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Request timeout in seconds (default: 30)"
+                    },
+                    "follow_redirects": {
                         "type": "boolean",
-                        "description": "Whether to clear the element before typing (default: true)"
+                        "description": "Follow redirects (default: true)"
+                    },
+                    "output_path": {
+                        "type": "string",
+                        "description": "Write the response body to this file instead of returning it"
                     }
                 },
-                "required": ["selector", "text"]
+                "required": ["url"]
             }),
         },
         Tool {
-            name: "webdriver_execute_script".to_string(),
-            description: "Execute JavaScript code in the browser and return the result".to_string(),
+            name: "database_query".to_string(),
+            description: "Run a read-only SQL query against a SQLite database file and return the rows as a JSON array of objects keyed by column name. Only SELECT queries (including WITH ... SELECT) are allowed; the database is opened read-only.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "script": {
+                    "db_path": {
                         "type": "string",
-                        "description": "JavaScript code to execute (use 'return' to return a value)"
+                        "description": "Path to the SQLite database file"
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "The SELECT query to run. Use ? placeholders for params"
+                    },
+                    "params": {
+                        "type": "array",
+                        "description": "Values bound to the ? placeholders, in order",
+                        "items": {}
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of rows to return (default: 100)"
                     }
                 },
-                "required": ["script"]
+                "required": ["db_path", "query"]
             }),
         },
         Tool {
-            name: "webdriver_get_page_source".to_string(),
-            description: "Get the rendered HTML source of the current page. Returns the current DOM state after JavaScript execution.".to_string(),
+            name: "list_background_processes".to_string(),
+            description: "List the background processes started with background_process, with their PID, status (running or exited), uptime, start time and command.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "stop_background_process".to_string(),
+            description: "Stop a background process started with background_process. Sends SIGTERM and waits a few seconds for it to exit; use signal SIGKILL to force a process that ignores SIGTERM.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "max_length": {
-                        "type": "integer",
-                        "description": "Maximum length of HTML to return (default: 10000, use 0 for no truncation)"
+                    "name": {
+                        "type": "string",
+                        "description": "Name the process was started with"
                     },
-                    "save_to_file": {
+                    "signal": {
                         "type": "string",
-                        "description": "Optional file path to save the HTML instead of returning it inline"
+                        "enum": ["SIGTERM", "SIGKILL"],
+                        "description": "Signal to send (default: SIGTERM)"
                     }
                 },
-                "required": []
+                "required": ["name"]
             }),
         },
         Tool {
-            name: "webdriver_screenshot".to_string(),
-            description: "Take a screenshot of the browser window".to_string(),
+            name: "get_background_process_output".to_string(),
+            description: "Get the output lines (stdout and stderr) a background process has written since the last call for it, or since it started. Only the most recent lines are kept in memory (1000 by default); the result reports how many lines the process wrote in total and how many were dropped. The full output is in the process's log files, one for stdout and one for stderr.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "path": {
+                    "name": {
                         "type": "string",
-                        "description": "Path where to save the screenshot (e.g., '/tmp/screenshot.png')"
+                        "description": "Name the process was started with"
+                    },
+                    "lines": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Return at most this many of the newest lines (default: 50)"
                     }
                 },
-                "required": ["path"]
+                "required": ["name"]
             }),
         },
         Tool {
-            name: "webdriver_back".to_string(),
-            description: "Navigate back in browser history".to_string(),
+            name: "todo_search".to_string(),
+            description: "Search the TODO list for items whose text contains the query (case-insensitive). Returns the matching items with their 1-based indices in the list.".to_string(),
             input_schema: json!({
                 "type": "object",
-                "properties": {},
-                "required": []
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Text to search for"
+                    }
+                },
+                "required": ["query"]
             }),
         },
         Tool {
-            name: "webdriver_forward".to_string(),
-            description: "Navigate forward in browser history".to_string(),
+            name: "todo_prioritize".to_string(),
+            description: "Reorder the TODO list by priority. Items can be marked after the checkbox with [HIGH] or [!!], [MEDIUM] or [!], or [LOW], e.g. `- [ ] [HIGH] Fix crash`. High priority items move to the top, followed by medium, unmarked and low ones. Nested items move with their parent and each section is sorted separately.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {},
@@ -446,17 +663,976 @@ fn create_webdriver_tools() -> Vec<Tool> {
             }),
         },
         Tool {
-            name: "webdriver_refresh".to_string(),
-            description: "Refresh the current page".to_string(),
+            name: "diff_files".to_string(),
+            description: "Show a unified diff (GNU diff format) between two files, or between a file and proposed new content, without reading either file first.".to_string(),
             input_schema: json!({
                 "type": "object",
-                "properties": {},
-                "required": []
+                "properties": {
+                    "file_a": {
+                        "type": "string",
+                        "description": "Path of the original file"
+                    },
+                    "file_b": {
+                        "type": "string",
+                        "description": "Path of the file to compare against"
+                    },
+                    "inline_b": {
+                        "type": "string",
+                        "description": "Content to compare file_a against, instead of file_b (e.g. a proposed new version)"
+                    },
+                    "context_lines": {
+                        "type": "integer",
+                        "description": "Unchanged lines shown around each change (default: 3)"
+                    },
+                    "ignore_whitespace": {
+                        "type": "boolean",
+                        "description": "Ignore leading and trailing whitespace when comparing lines (default: false)"
+                    }
+                },
+                "required": ["file_a"]
             }),
         },
         Tool {
-            name: "webdriver_quit".to_string(),
-            description: "Close the browser and end the WebDriver session".to_string(),
+            name: "code_complexity".to_string(),
+            description: "Report the length and cyclomatic complexity of every function in a Rust or Python file, most severe first. Functions over 80 lines or with complexity over 10 are flagged. Also lists the number of methods per impl block or class.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to the source file"
+                    },
+                    "language": {
+                        "type": "string",
+                        "enum": ["rust", "python"],
+                        "description": "Language of the file (default: detected from the extension)"
+                    }
+                },
+                "required": ["path"]
+            }),
+        },
+        Tool {
+            name: "restore_backup".to_string(),
+            description: "Undo a str_replace made with create_backup: copy <file_path>.g3.bak back over the file and delete the backup.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "Path of the file to restore (not of the backup)"
+                    }
+                },
+                "required": ["file_path"]
+            }),
+        },
+        Tool {
+            name: "shell_env".to_string(),
+            description: "Set environment variables for later shell commands in this session (e.g. DATABASE_URL), since a variable exported in one shell call is gone by the next. Actions: 'set' (name, value), 'unset' (name), 'get' (name; also shows inherited variables) and 'list'. Values of secret-looking variables are never displayed.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["get", "set", "unset", "list"],
+                        "description": "What to do (default: list)"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Variable name, for get, set and unset"
+                    },
+                    "value": {
+                        "type": "string",
+                        "description": "Value to set, for set"
+                    }
+                },
+                "required": ["action"]
+            }),
+        },
+        Tool {
+            name: "glob_replace".to_string(),
+            description: "Find and replace text across every file matching a glob pattern, e.g. to rename a function or update an import path. The pattern is relative to the working directory: '*' stays within one directory and '**' matches any number of directories (e.g. 'src/**/*.rs'). Files ignored by .gitignore, hidden files and non-UTF-8 files are skipped. Returns the number of matches replaced per file. Use dry_run to preview.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "Glob pattern of the files to edit, e.g. 'src/**/*.rs'"
+                    },
+                    "find": {
+                        "type": "string",
+                        "description": "Text to find, or a regex when literal is false"
+                    },
+                    "replace": {
+                        "type": "string",
+                        "description": "Replacement text. With literal false, $1 or ${name} insert capture groups"
+                    },
+                    "literal": {
+                        "type": "boolean",
+                        "description": "Treat find as plain text rather than a regex (default: true)"
+                    },
+                    "case_insensitive": {
+                        "type": "boolean",
+                        "description": "Match find case-insensitively (default: false)"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Report what would change without modifying files (default: false)"
+                    }
+                },
+                "required": ["pattern", "find", "replace"]
+            }),
+        },
+        Tool {
+            name: "text_search".to_string(),
+            description: "Search file contents for a regex, like ripgrep, and return matches as file:line:col lines. Prefer this over running grep or rg through the shell. Hidden files, files ignored by .gitignore and binary files are skipped.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "Regular expression to search for (Rust regex syntax)"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "File or directory to search (default: '.')"
+                    },
+                    "case_insensitive": {
+                        "type": "boolean",
+                        "description": "Match case-insensitively (default: false)"
+                    },
+                    "file_type": {
+                        "type": "string",
+                        "description": "Only search files of this type, using ripgrep's type names, e.g. 'rust', 'python', 'js', 'go'"
+                    },
+                    "context_lines": {
+                        "type": "integer",
+                        "description": "Lines of context to show before and after each match (default: 0)"
+                    },
+                    "max_results": {
+                        "type": "integer",
+                        "description": "Stop after this many matches (default: 100)"
+                    }
+                },
+                "required": ["pattern"]
+            }),
+        },
+        Tool {
+            name: "macro_record".to_string(),
+            description: "Start recording the tool calls you make from now on as a reusable macro, saved to .g3/macros/<name>.jsonl. Use for repetitive workflows such as 'checkout PR, run tests, check coverage'. Call macro_stop when done. Recording an existing name replaces it.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Macro name: letters, digits, '-' and '_' only"
+                    }
+                },
+                "required": ["name"]
+            }),
+        },
+        Tool {
+            name: "macro_stop".to_string(),
+            description: "Stop the macro recording started with macro_record and save it.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "macro_playback".to_string(),
+            description: "Replay the tool calls saved in a macro, in order, and return each call's result. Stops at the first failing call unless stop_on_error is false. Use dry_run to list the calls without running them.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Name of the macro to replay"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "List the tool calls instead of running them (default: false)"
+                    },
+                    "stop_on_error": {
+                        "type": "boolean",
+                        "description": "Stop at the first call whose result is an error (default: true)"
+                    }
+                },
+                "required": ["name"]
+            }),
+        },
+        Tool {
+            name: "macro_list".to_string(),
+            description: "List the saved macros and how many tool calls each contains.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "env_check".to_string(),
+            description: "Check that environment variables a project needs are set before running it, instead of hitting cryptic errors later. Reports which required variables are missing, which optional ones are set, and whether values match an expected format. Secret-looking values are masked. Variables set with shell_env count as set.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "required": {
+                        "type": "array",
+                        "items": {
+                            "type": "string"
+                        },
+                        "description": "Variables that must be set, e.g. ['DATABASE_URL', 'ANTHROPIC_API_KEY']"
+                    },
+                    "optional": {
+                        "type": "array",
+                        "items": {
+                            "type": "string"
+                        },
+                        "description": "Variables to report on without failing when unset"
+                    },
+                    "validate": {
+                        "type": "object",
+                        "additionalProperties": {
+                            "type": "string"
+                        },
+                        "description": "Regex each variable's value must match, e.g. {\"PORT\": \"^[0-9]+$\"}"
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "batch_read_files".to_string(),
+            description: "Read several files at once, concurrently, instead of calling read_file for each. Returns every file's content under a '=== path ===' header, in the order given. Files that can't be read show the error in place of their content; the rest are still returned. Large files are truncated; use read_file with start/end for the rest.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "paths": {
+                        "type": "array",
+                        "items": {
+                            "type": "string"
+                        },
+                        "description": "Paths of the files to read"
+                    },
+                    "max_bytes_per_file": {
+                        "type": "integer",
+                        "description": "Maximum bytes returned per file (default: the read_file limit, 1 MB)"
+                    },
+                    "include_line_numbers": {
+                        "type": "boolean",
+                        "description": "Prefix each line with its line number (default: false)"
+                    }
+                },
+                "required": ["paths"]
+            }),
+        },
+        Tool {
+            name: "process_output_wait".to_string(),
+            description: "Wait until a background process started with background_process writes a line matching a regex, e.g. a dev server's 'listening on' message before connecting to it. Searches output not yet returned by get_background_process_output, checking every 100 ms. Fails if the timeout passes or the process exits first.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Name the process was started with"
+                    },
+                    "pattern": {
+                        "type": "string",
+                        "description": "Regular expression to look for in each output line"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Seconds to wait before giving up (default: 30)"
+                    },
+                    "stream": {
+                        "type": "string",
+                        "enum": ["stdout", "stderr", "both"],
+                        "description": "Output stream to search (default: both)"
+                    }
+                },
+                "required": ["name", "pattern"]
+            }),
+        },
+        Tool {
+            name: "vision_compare_screenshots".to_string(),
+            description: "Compare two screenshots (PNG or other common image formats) pixel by pixel to check that a UI change had the expected visual effect. Reports how many pixels differ, as a count and percentage, and the largest difference. Optionally saves a diff image with changed pixels in red. Both images must have the same size.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path_a": {
+                        "type": "string",
+                        "description": "Path to the first image, e.g. the screenshot before the change"
+                    },
+                    "path_b": {
+                        "type": "string",
+                        "description": "Path to the second image, e.g. the screenshot after the change"
+                    },
+                    "threshold": {
+                        "type": "number",
+                        "description": "How much a pixel must change to count as different, 0-255, as the average difference of its RGBA channels (default: 10)"
+                    },
+                    "output_path": {
+                        "type": "string",
+                        "description": "Where to save a diff image highlighting changed pixels in red"
+                    }
+                },
+                "required": ["path_a", "path_b"]
+            }),
+        },
+        Tool {
+            name: "code_rename_symbol".to_string(),
+            description: "Rename a function, variable or type in a file or across a directory using tree-sitter. Only identifiers named exactly old_name are renamed: strings, comments and longer names are left alone. Matching is syntactic (no scope resolution), so use symbol_type to restrict it and dry_run to check the files first. Returns the files changed and how many occurrences were renamed in each. Languages: Rust, Python, JavaScript, TypeScript, Go, Java, C, C++.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "old_name": {
+                        "type": "string",
+                        "description": "Current name of the symbol"
+                    },
+                    "new_name": {
+                        "type": "string",
+                        "description": "New name for the symbol; must be a valid identifier"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "File or directory to rename in. In a directory, every file of the language is searched, skipping .gitignore'd and hidden files."
+                    },
+                    "language": {
+                        "type": "string",
+                        "description": "Language of the files: rust, python, javascript, typescript, go, java, c or cpp (default: detected from the file extension or project files)"
+                    },
+                    "symbol_type": {
+                        "type": "string",
+                        "enum": ["function", "variable", "type"],
+                        "description": "Only rename function definitions and calls, types, or other identifiers (variables, parameters, fields). Default: every occurrence."
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Report what would be renamed without changing any file (default: false)"
+                    }
+                },
+                "required": ["old_name", "new_name", "path"]
+            }),
+        },
+        Tool {
+            name: "dependency_graph".to_string(),
+            description: "Build the import graph of a Rust or Python project: an edge a -> b when file a imports from file b (Rust `use` declarations, Python `import` / `from X import`). Reports circular dependencies. Formats: `text` (sorted list), `json` (adjacency map) or `dot` (Graphviz, written to a file whose path is returned). When `path` is a file, only the files reachable from it are shown, at most `depth` imports away.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Project directory, or an entry file within the working directory"
+                    },
+                    "language": {
+                        "type": "string",
+                        "description": "rust or python (detected from the path if omitted)"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["dot", "json", "text"],
+                        "description": "Output format (default: text)"
+                    },
+                    "depth": {
+                        "type": "integer",
+                        "description": "Max import hops from the entry file (default: unlimited)"
+                    },
+                    "output_path": {
+                        "type": "string",
+                        "description": "Where to write the DOT file (default: the session's dependency_graph.dot)"
+                    }
+                },
+                "required": ["path"]
+            }),
+        },
+    ]
+}
+
+
+
+
+
+/// Create WebDriver browser automation tools
+fn create_webdriver_tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "webdriver_start".to_string(),
+            description: "Start a WebDriver session for browser automation. Other webdriver tools start a session with the configured browser automatically; call this first to pick a different `browser`. Safari requires 'Allow Remote Automation' to be enabled in Develop menu.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "browser": {
+                        "type": "string",
+                        "enum": ["chrome", "firefox"],
+                        "description": "Headless browser to launch: 'chrome' (ChromeDriver) or 'firefox' (GeckoDriver). Defaults to the configured browser."
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "webdriver_navigate".to_string(),
+            description: "Navigate to a URL in the browser".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "The URL to navigate to (must include protocol, e.g., https://)"
+                    }
+                },
+                "required": ["url"]
+            }),
+        },
+        Tool {
+            name: "webdriver_get_url".to_string(),
+            description: "Get the current URL of the browser".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "webdriver_get_title".to_string(),
+            description: "Get the title of the current page".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "webdriver_find_element".to_string(),
+            description: "Find an element on the page by CSS selector and return its text content".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector to find the element (e.g., 'h1', '.class-name', '#id')"
+                    }
+                },
+                "required": ["selector"]
+            }),
+        },
+        Tool {
+            name: "webdriver_find_elements".to_string(),
+            description: "Find all elements matching a CSS selector and return their text content".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector to find elements"
+                    }
+                },
+                "required": ["selector"]
+            }),
+        },
+        Tool {
+            name: "webdriver_click".to_string(),
+            description: "Click an element on the page".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector for the element to click"
+                    }
+                },
+                "required": ["selector"]
+            }),
+        },
+        Tool {
+            name: "webdriver_send_keys".to_string(),
+            description: "Type text into an input element".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector for the input element"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "Text to type into the element"
+                    },
+                    "clear_first": {
+                        "type": "boolean",
+                        "description": "Whether to clear the element before typing (default: true)"
+                    }
+                },
+                "required": ["selector", "text"]
+            }),
+        },
+        Tool {
+            name: "webdriver_execute_script".to_string(),
+            description: "Execute JavaScript code in the browser and return the result".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "script": {
+                        "type": "string",
+                        "description": "JavaScript code to execute (use 'return' to return a value)"
+                    }
+                },
+                "required": ["script"]
+            }),
+        },
+        Tool {
+            name: "webdriver_get_page_source".to_string(),
+            description: "Get the rendered HTML source of the current page. Returns the current DOM state after JavaScript execution.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "max_length": {
+                        "type": "integer",
+                        "description": "Maximum length of HTML to return (default: 10000, use 0 for no truncation)"
+                    },
+                    "save_to_file": {
+                        "type": "string",
+                        "description": "Optional file path to save the HTML instead of returning it inline"
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "webdriver_screenshot".to_string(),
+            description: "Take a screenshot of the browser window".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path where to save the screenshot (e.g., '/tmp/screenshot.png')"
+                    }
+                },
+                "required": ["path"]
+            }),
+        },
+        Tool {
+            name: "webdriver_back".to_string(),
+            description: "Navigate back in browser history".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "webdriver_forward".to_string(),
+            description: "Navigate forward in browser history".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "webdriver_refresh".to_string(),
+            description: "Refresh the current page".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "webdriver_quit".to_string(),
+            description: "Close the browser and end the WebDriver session".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "web_close_session".to_string(),
+            description: "Close the browser session that is kept alive across WebDriver tool calls. A new session is started automatically on the next WebDriver tool call.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "web_session_info".to_string(),
+            description: "Show the current URL and window handles recorded for the active browser session, without querying the browser".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "web_wait_for_element".to_string(),
+            description: "Wait for an element matching a CSS selector to appear on the page. Polls until the condition is met or the timeout expires; use this for dynamically rendered content.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector of the element to wait for"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Maximum time to wait in seconds (default: 10)"
+                    },
+                    "poll_ms": {
+                        "type": "integer",
+                        "description": "Polling interval in milliseconds (default: 250)"
+                    },
+                    "condition": {
+                        "type": "string",
+                        "enum": ["present", "visible", "clickable"],
+                        "description": "What to wait for: 'present' in the DOM (default), 'visible', or 'clickable'"
+                    }
+                },
+                "required": ["selector"]
+            }),
+        },
+        Tool {
+            name: "web_get_cookies".to_string(),
+            description: "Get all cookies visible to the current page as a JSON array of {name, value, domain, path, secure, http_only, expiry}.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "web_set_cookie".to_string(),
+            description: "Set a cookie in the browser session. Cookies can only be set for the domain of the currently loaded page, so navigate there first.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Cookie name"
+                    },
+                    "value": {
+                        "type": "string",
+                        "description": "Cookie value"
+                    },
+                    "domain": {
+                        "type": "string",
+                        "description": "Domain the cookie applies to (defaults to the current page's domain)"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "Path the cookie applies to (defaults to /)"
+                    },
+                    "secure": {
+                        "type": "boolean",
+                        "description": "Only send the cookie over HTTPS (default: false)"
+                    },
+                    "http_only": {
+                        "type": "boolean",
+                        "description": "Hide the cookie from JavaScript (default: false)"
+                    },
+                    "expiry": {
+                        "type": "integer",
+                        "description": "Expiry as a Unix timestamp in seconds. Omit for a session cookie."
+                    }
+                },
+                "required": ["name", "value"]
+            }),
+        },
+        Tool {
+            name: "web_extract_table".to_string(),
+            description: "Extract an HTML table into a JSON array of objects, one per row, keyed by column header. Headers come from <th> cells or the first row unless overridden. Cells merged by colspan/rowspan only appear in their first column.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector for the <table> or an element wrapping it"
+                    },
+                    "headers": {
+                        "type": "array",
+                        "items": {
+                            "type": "string"
+                        },
+                        "description": "Column names to use instead of the detected headers"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of rows to return"
+                    }
+                },
+                "required": ["selector"]
+            }),
+        },
+        Tool {
+            name: "web_scroll".to_string(),
+            description: "Scroll the page, or a scrollable element, to trigger lazy-loaded content. Scrolls by a pixel amount in a direction, or until target_selector is in view.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "direction": {
+                        "type": "string",
+                        "enum": ["up", "down", "left", "right"],
+                        "description": "Direction to scroll (default: down)"
+                    },
+                    "amount": {
+                        "type": "integer",
+                        "description": "Pixels to scroll (default: 500)"
+                    },
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector of an element to scroll instead of the window"
+                    },
+                    "target_selector": {
+                        "type": "string",
+                        "description": "CSS selector of an element to scroll into view (used instead of direction/amount)"
+                    },
+                    "smooth": {
+                        "type": "boolean",
+                        "description": "Use smooth scrolling (default: false)"
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "web_fill_form".to_string(),
+            description: "Fill in a web form and submit it. Fields are matched by input name, then id, then label text. Select options are chosen by their text, checkboxes take true/false, radio groups take the value or label of the button to pick.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "fields": {
+                        "type": "object",
+                        "additionalProperties": {
+                            "type": "string"
+                        },
+                        "description": "Map of field name, id or label to the value to enter"
+                    },
+                    "form_selector": {
+                        "type": "string",
+                        "description": "CSS selector of the form to fill (default: search the whole page)"
+                    },
+                    "submit": {
+                        "type": "boolean",
+                        "description": "Submit the form after filling it (default: true)"
+                    }
+                },
+                "required": ["fields"]
+            }),
+        },
+        Tool {
+            name: "web_get_page_metadata".to_string(),
+            description: "Get the current page's title, meta description, canonical URL and Open Graph tags (og:title, og:description, og:image). Much smaller than webdriver_get_page_source when you only need to know what a page is about.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "web_run_script".to_string(),
+            description: "Run JavaScript in the current page and return its result. The script is a function body: use `return` to return a value, and read the optional args as arguments[0], arguments[1], ... Elements, arrays and objects are returned as JSON. Errors thrown by the script (including syntax errors) are reported as '❌ Script error: ...'.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "script": {
+                        "type": "string",
+                        "description": "JavaScript function body to run, e.g. \"return document.title;\""
+                    },
+                    "args": {
+                        "type": "array",
+                        "items": {},
+                        "description": "Arguments passed to the script as arguments[0], arguments[1], ..."
+                    },
+                    "return_type": {
+                        "type": "string",
+                        "enum": ["json", "text", "none"],
+                        "description": "How to format the return value: 'json' (pretty-printed, default), 'text' (strings as-is) or 'none' (just confirm the script ran)"
+                    }
+                },
+                "required": ["script"]
+            }),
+        },
+        Tool {
+            name: "web_download_file".to_string(),
+            description: "Download a URL to a local file using the browser session's cookies, so files behind a login can be fetched. Returns the file size and SHA-256 hash.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "http(s) URL to download"
+                    },
+                    "output_path": {
+                        "type": "string",
+                        "description": "Local path to write the file to (parent directories are created)"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Timeout for the whole download in seconds (default: 60)"
+                    }
+                },
+                "required": ["url", "output_path"]
+            }),
+        },
+        Tool {
+            name: "web_find_elements_by_text".to_string(),
+            description: "Find elements by their visible text, e.g. a button or link label, when you don't know a CSS selector. Returns up to 50 matches as {tag, text, id, class, href}; only the innermost matching elements are returned.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "text": {
+                        "type": "string",
+                        "description": "Text to look for"
+                    },
+                    "match_type": {
+                        "type": "string",
+                        "enum": ["exact", "contains", "starts_with"],
+                        "description": "How the element's text must match (default: contains)"
+                    },
+                    "element_type": {
+                        "type": "string",
+                        "description": "Only match elements with this tag, e.g. 'button', 'a' or 'input' (inputs match on their value)"
+                    },
+                    "case_sensitive": {
+                        "type": "boolean",
+                        "description": "Match case exactly (default: false)"
+                    }
+                },
+                "required": ["text"]
+            }),
+        },
+        Tool {
+            name: "web_wait_for_navigation".to_string(),
+            description: "Wait until the browser navigates to a new page and it has loaded, e.g. after clicking a link or submitting a form. Compares against the URL of the last navigation tool, so a navigation that already happened returns immediately. Returns the final URL and how long it took.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Maximum time to wait in seconds (default: 15)"
+                    },
+                    "expected_url_contains": {
+                        "type": "string",
+                        "description": "Wait until the URL contains this text instead of for any URL change"
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "web_inspect_element".to_string(),
+            description: "Inspect the element matching a CSS selector: tag, id, class, text, bounding rect, attributes and whether it is visible. Optionally adds the computed styles that differ from the defaults, and its children up to 3 levels deep. Use this to debug layout or check dynamic styling.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector of the element"
+                    },
+                    "include_styles": {
+                        "type": "boolean",
+                        "description": "Include computed styles that differ from a default element with the same tag (default: false)"
+                    },
+                    "include_children": {
+                        "type": "boolean",
+                        "description": "Describe child elements too, up to 3 levels deep and 20 per element (default: false)"
+                    }
+                },
+                "required": ["selector"]
+            }),
+        },
+        Tool {
+            name: "web_get_network_requests".to_string(),
+            description: "List the fetch and XMLHttpRequest calls the current page has made, oldest first, as JSON objects with method, url, request_body, status (null while pending) and response_preview (first 500 characters). Capture starts when webdriver_navigate loads a page, so requests made during the initial page load may be missed. Use clear to empty the log after reading, e.g. before triggering the action you want to observe.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "clear": {
+                        "type": "boolean",
+                        "description": "Empty the log after reading it (default: false)"
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "web_switch_frame".to_string(),
+            description: "Switch the browser session into an iframe so that later WebDriver tools act on its content. Identify the frame either by a CSS selector for the <iframe> element or by its index among the frames of the current document. Frames are entered relative to the current frame, so call this repeatedly to reach nested iframes. Use web_switch_to_default to return to the top-level page.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector of the <iframe> or <frame> element, e.g. '#payment-frame'"
+                    },
+                    "index": {
+                        "type": "integer",
+                        "description": "Zero-based index of the frame in the current document. Use instead of selector"
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "web_switch_to_default".to_string(),
+            description: "Leave any iframes entered with web_switch_frame and return the browser session to the top-level page.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "web_local_storage".to_string(),
+            description: "Read or write the current page's localStorage or sessionStorage, e.g. to inspect a single-page app's state or seed it before a test. Values that are valid JSON are returned parsed. Strings are stored as they are and other values as JSON. Storage belongs to the page's origin, so navigate to the app first.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["get", "set", "remove", "clear", "list"],
+                        "description": "What to do (default: list)"
+                    },
+                    "key": {
+                        "type": "string",
+                        "description": "Storage key, required for get, set and remove"
+                    },
+                    "value": {
+                        "description": "Value to store with set: any JSON value"
+                    },
+                    "storage_type": {
+                        "type": "string",
+                        "enum": ["local", "session"],
+                        "description": "localStorage or sessionStorage (default: local)"
+                    }
+                },
+                "required": ["action"]
+            }),
+        },
+        Tool {
+            name: "web_find_by_xpath".to_string(),
+            description: "Find elements with an XPath expression, e.g. //table//tr[td='Total'] or //label[text()='Email']/following-sibling::input, when a CSS selector can't express the query. Returns matches as {tag, text, id, class, href}: the first match when single is true, otherwise up to 50.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "xpath": {
+                        "type": "string",
+                        "description": "XPath expression to evaluate against the current page"
+                    },
+                    "single": {
+                        "type": "boolean",
+                        "description": "Return only the first match (default: false, return all matches)"
+                    }
+                },
+                "required": ["xpath"]
+            }),
+        },
+        Tool {
+            name: "web_accessibility_snapshot".to_string(),
+            description: "Get the current page's accessibility tree: landmarks, headings, links, buttons, form fields and other elements with an ARIA role, as an indented tree of `[role] \"name\" (tag#id.class)` lines, with aria-describedby text after ' - '. Elements without a role are left out. Limited to 6 levels and 500 nodes. Useful to understand a page's structure or find controls by their accessible name without reading the page source.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {},
@@ -474,9 +1650,15 @@ mod tests {
     fn test_core_tools_count() {
         let tools = create_core_tools();
         // Should have the core tools: shell, background_process, read_file, read_image,
-        // write_file, str_replace, final_output, take_screenshot,
-        // todo_read, todo_write, code_coverage, code_search (12 total)
-        assert_eq!(tools.len(), 12);
+        // write_file, str_replace, final_output, take_screenshot, todo_read, todo_write,
+        // code_coverage, code_search, git, code_lint, run_tests, shell_session_start,
+        // shell_session_run, shell_session_stop, directory_tree, http_request, database_query,
+        // list_background_processes, stop_background_process, get_background_process_output,
+        // todo_search, todo_prioritize, diff_files, code_complexity, restore_backup,
+        // shell_env, glob_replace, text_search, macro_record, macro_stop, macro_playback,
+        // macro_list, env_check, batch_read_files, process_output_wait,
+        // vision_compare_screenshots, code_rename_symbol, dependency_graph (42 total)
+        assert_eq!(tools.len(), 42);
     }
 
 
@@ -484,18 +1666,18 @@ mod tests {
     #[test]
     fn test_webdriver_tools_count() {
         let tools = create_webdriver_tools();
-        // 15 webdriver tools
-        assert_eq!(tools.len(), 15);
+        // 35 webdriver tools
+        assert_eq!(tools.len(), 35);
     }
 
 
 
     #[test]
     fn test_create_tool_definitions_all_enabled() {
-        let config = ToolConfig::new_with_goose(true, true, true, true);
+        let config = ToolConfig::new(true, true, true);
         let tools = create_tool_definitions(config);
-        // 12 core + 15 webdriver + 7 goose = 34
-        assert_eq!(tools.len(), 34);
+        // 42 core + 35 webdriver = 77
+        assert_eq!(tools.len(), 77);
     }
 
 
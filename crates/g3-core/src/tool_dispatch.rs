@@ -4,20 +4,50 @@
 //! to the appropriate handler in the `tools/` module.
 
 use anyhow::Result;
+use std::path::PathBuf;
+use std::time::Instant;
 use tracing::{debug, warn};
 
-use crate::tools::executor::ToolContext;
-use crate::tools::{file_ops, misc, shell, todo, webdriver};
+use crate::audit::AuditEntry;
+use crate::file_history::FileOperation;
+use crate::tools::executor::{
+    dry_run_result, resolve_tool_timeout, run_with_timeout, tool_permitted, ToolContext,
+    DRY_RUN_EXEMPT_TOOLS,
+};
+use crate::tools::{database, file_ops, git, http, macros, misc, shell, todo, vision, webdriver};
 use crate::ui_writer::UiWriter;
 use crate::ToolCall;
 
 /// Dispatch a tool call to the appropriate handler.
 ///
 /// This function routes tool calls to their implementations in the `tools/` module,
-/// providing a single point of dispatch for all tool execution.
+/// providing a single point of dispatch for all tool execution. Every call is
+/// recorded in the audit log, including ones that are refused.
 pub async fn dispatch_tool<W: UiWriter>(
     tool_call: &ToolCall,
     ctx: &mut ToolContext<'_, W>,
+) -> Result<String> {
+    let started = Instant::now();
+    let result = dispatch_unaudited(tool_call, ctx).await;
+    if let Some(audit_logger) = ctx.audit_logger {
+        let summary = match &result {
+            Ok(output) => output.clone(),
+            Err(e) => format!("❌ {}", e),
+        };
+        audit_logger.log(AuditEntry::new(
+            tool_call,
+            &summary,
+            started.elapsed(),
+            ctx.session_id,
+            ctx.secrets_redactor,
+        ));
+    }
+    result
+}
+
+async fn dispatch_unaudited<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &mut ToolContext<'_, W>,
 ) -> Result<String> {
     debug!("TOOL_DISPATCH: Starting dispatch for tool: {}", tool_call.tool);
     debug!("Dispatching tool: {}", tool_call.tool);
@@ -30,20 +60,150 @@ pub async fn dispatch_tool<W: UiWriter>(
     
     debug!("TOOL_DISPATCH: Tool validation passed for: {}", tool_call.tool);
 
+    if !tool_permitted(&tool_call.tool, &ctx.config.agent) {
+        debug!("TOOL_DISPATCH: Tool not permitted by configuration: {}", tool_call.tool);
+        return Ok(format!(
+            "❌ Tool '{}' is not permitted in this configuration",
+            tool_call.tool
+        ));
+    }
+
+    if let Some(validator) = ctx.schema_validator {
+        if let Err(e) = validator.validate(tool_call) {
+            debug!("TOOL_DISPATCH: Invalid arguments for {}: {}", tool_call.tool, e);
+            return Ok(format!("❌ Invalid tool arguments: {}", e));
+        }
+    }
+
+    if ctx.dry_run && !DRY_RUN_EXEMPT_TOOLS.contains(&tool_call.tool.as_str()) {
+        debug!("TOOL_DISPATCH: Dry run, not executing: {}", tool_call.tool);
+        return Ok(dry_run_result(tool_call));
+    }
+
+    if let Err(e) = ctx.macros.record(tool_call) {
+        warn!("Failed to record tool call in macro: {:#}", e);
+    }
+
+    // Relative paths in the call are relative to the directory it started in
+    let working_dir = ctx.working_directory.to_string_lossy().into_owned();
+    let working_dir = Some(working_dir.as_str());
+    if let Some(cache) = ctx.tool_cache.as_deref_mut() {
+        if let Some(cached) = cache.get(tool_call, working_dir) {
+            debug!("TOOL_DISPATCH: Returning cached result for: {}", tool_call.tool);
+            return Ok(cached);
+        }
+    }
+
+    // Snapshot the file a tool is about to edit so /undo can put it back
+    let edited = edited_file(tool_call, ctx).map(|path| {
+        let before = std::fs::read(&path).ok();
+        (path, before)
+    });
+
+    let timeout = resolve_tool_timeout(tool_call, ctx.default_tool_timeout);
+    let result = run_with_timeout(&tool_call.tool, timeout, route_tool(tool_call, ctx)).await?;
+    ctx.record_file_access(tool_call);
+
+    if let Some((path, before)) = edited {
+        if result.starts_with('✅') {
+            if let Some(operation) = FileOperation::capture(&path, &tool_call.tool, before) {
+                ctx.file_history.push(operation);
+            }
+        }
+    }
+
+    // Show which iframe browser tools acted in
+    let result = if is_frame_aware_tool(&tool_call.tool) {
+        webdriver::with_frame_label(ctx, result).await
+    } else {
+        result
+    };
+
+    // Never hand secrets that showed up in tool output to the LLM, but keep
+    // file contents and hashes intact so edits apply to the real text
+    let result = if crate::security::redacts_output_of(&tool_call.tool) {
+        ctx.secrets_redactor.redact(&result).into_owned()
+    } else {
+        result
+    };
+
+    if let Some(cache) = ctx.tool_cache.as_deref_mut() {
+        cache.invalidate_for(tool_call, working_dir);
+        cache.insert(tool_call, working_dir, &result);
+    }
+
+    Ok(result)
+}
+
+/// The file a write_file, str_replace or restore_backup call edits.
+fn edited_file<W: UiWriter>(tool_call: &ToolCall, ctx: &ToolContext<'_, W>) -> Option<PathBuf> {
+    let path = match tool_call.tool.as_str() {
+        "write_file" => file_ops::extract_path_and_content(&tool_call.args).0,
+        "str_replace" | "restore_backup" => {
+            tool_call.args.get("file_path").and_then(|v| v.as_str())
+        }
+        _ => None,
+    }?;
+    Some(ctx.resolve_path(path))
+}
+
+/// Browser tools whose output is labelled with the iframe they ran in. The
+/// frame-switching tools already say where they went.
+fn is_frame_aware_tool(tool: &str) -> bool {
+    (tool.starts_with("webdriver_") || tool.starts_with("web_"))
+        && !matches!(
+            tool,
+            "webdriver_start"
+                | "webdriver_quit"
+                | "web_session_info"
+                | "web_switch_frame"
+                | "web_switch_to_default"
+        )
+}
+
+/// Route a tool call to its handler without any timeout applied.
+async fn route_tool<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &mut ToolContext<'_, W>,
+) -> Result<String> {
     match tool_call.tool.as_str() {
         // Shell tools
         "shell" => shell::execute_shell(tool_call, ctx).await,
+        "shell_env" => shell::execute_shell_env(tool_call, ctx).await,
         "background_process" => shell::execute_background_process(tool_call, ctx).await,
+        "list_background_processes" => shell::execute_list_background_processes(tool_call, ctx).await,
+        "stop_background_process" => shell::execute_stop_background_process(tool_call, ctx).await,
+        "get_background_process_output" => shell::execute_get_background_process_output(tool_call, ctx).await,
+        "process_output_wait" => shell::execute_background_process_wait_for_pattern(tool_call, ctx).await,
+        "shell_session_start" => shell::execute_shell_session_start(tool_call, ctx).await,
+        "shell_session_run" => shell::execute_shell_session_run(tool_call, ctx).await,
+        "shell_session_stop" => shell::execute_shell_session_stop(tool_call, ctx).await,
 
         // File operations
         "read_file" => file_ops::execute_read_file(tool_call, ctx).await,
+        "batch_read_files" => file_ops::execute_batch_read_files(tool_call, ctx).await,
         "read_image" => file_ops::execute_read_image(tool_call, ctx).await,
         "write_file" => file_ops::execute_write_file(tool_call, ctx).await,
         "str_replace" => file_ops::execute_str_replace(tool_call, ctx).await,
+        "restore_backup" => file_ops::execute_restore_backup(tool_call, ctx).await,
+        "glob_replace" => file_ops::execute_glob_replace(tool_call, ctx).await,
+        "diff_files" => file_ops::execute_diff_files(tool_call, ctx).await,
+        "directory_tree" => file_ops::execute_directory_tree(tool_call, ctx).await,
+
+        // Git
+        "git" => git::execute_git(tool_call, ctx).await,
+
+        // HTTP
+        "http_request" => http::execute_http_request(tool_call, ctx).await,
+
+        // Database
+        "database_query" => database::execute_database_query(tool_call, ctx).await,
 
         // TODO management
         "todo_read" => todo::execute_todo_read(tool_call, ctx).await,
         "todo_write" => todo::execute_todo_write(tool_call, ctx).await,
+        "todo_search" => todo::execute_todo_search(tool_call, ctx).await,
+        "todo_prioritize" => todo::execute_todo_prioritize(tool_call, ctx).await,
 
         // Miscellaneous tools
         "final_output" => {
@@ -52,8 +212,20 @@ pub async fn dispatch_tool<W: UiWriter>(
             Ok(result)
         }
         "take_screenshot" => misc::execute_take_screenshot(tool_call, ctx).await,
+        "vision_compare_screenshots" => vision::execute_vision_compare_screenshots(tool_call, ctx).await,
         "code_coverage" => misc::execute_code_coverage(tool_call, ctx).await,
         "code_search" => misc::execute_code_search(tool_call, ctx).await,
+        "text_search" => misc::execute_text_search(tool_call, ctx).await,
+        "env_check" => misc::execute_env_check(tool_call, ctx).await,
+        "macro_record" => macros::execute_macro_record(tool_call, ctx).await,
+        "macro_stop" => macros::execute_macro_stop(tool_call, ctx).await,
+        "macro_playback" => macros::execute_macro_playback(tool_call, ctx).await,
+        "macro_list" => macros::execute_macro_list(tool_call, ctx).await,
+        "code_lint" => misc::execute_code_lint(tool_call, ctx).await,
+        "code_complexity" => misc::execute_code_complexity(tool_call, ctx).await,
+        "code_rename_symbol" => misc::execute_code_rename_symbol(tool_call, ctx).await,
+        "dependency_graph" => misc::execute_dependency_graph(tool_call, ctx).await,
+        "run_tests" => misc::execute_run_tests(tool_call, ctx).await,
 
         // WebDriver tools
         "webdriver_start" => webdriver::execute_webdriver_start(tool_call, ctx).await,
@@ -71,6 +243,26 @@ pub async fn dispatch_tool<W: UiWriter>(
         "webdriver_forward" => webdriver::execute_webdriver_forward(tool_call, ctx).await,
         "webdriver_refresh" => webdriver::execute_webdriver_refresh(tool_call, ctx).await,
         "webdriver_quit" => webdriver::execute_webdriver_quit(tool_call, ctx).await,
+        "web_close_session" => webdriver::execute_web_close_session(tool_call, ctx).await,
+        "web_session_info" => webdriver::execute_web_session_info(tool_call, ctx).await,
+        "web_wait_for_element" => webdriver::execute_web_wait_for_element(tool_call, ctx).await,
+        "web_wait_for_navigation" => webdriver::execute_web_wait_for_navigation(tool_call, ctx).await,
+        "web_get_cookies" => webdriver::execute_web_get_cookies(tool_call, ctx).await,
+        "web_set_cookie" => webdriver::execute_web_set_cookie(tool_call, ctx).await,
+        "web_extract_table" => webdriver::execute_web_extract_table(tool_call, ctx).await,
+        "web_scroll" => webdriver::execute_web_scroll(tool_call, ctx).await,
+        "web_fill_form" => webdriver::execute_web_fill_form(tool_call, ctx).await,
+        "web_get_page_metadata" => webdriver::execute_web_get_page_metadata(tool_call, ctx).await,
+        "web_run_script" => webdriver::execute_web_run_script(tool_call, ctx).await,
+        "web_download_file" => webdriver::execute_web_download_file(tool_call, ctx).await,
+        "web_find_elements_by_text" => webdriver::execute_web_find_elements_by_text(tool_call, ctx).await,
+        "web_find_by_xpath" => webdriver::execute_web_find_by_xpath(tool_call, ctx).await,
+        "web_inspect_element" => webdriver::execute_web_inspect_element(tool_call, ctx).await,
+        "web_accessibility_snapshot" => webdriver::execute_web_accessibility_snapshot(tool_call, ctx).await,
+        "web_get_network_requests" => webdriver::execute_web_get_network_requests(tool_call, ctx).await,
+        "web_switch_frame" => webdriver::execute_web_switch_frame(tool_call, ctx).await,
+        "web_switch_to_default" => webdriver::execute_web_switch_to_default(tool_call, ctx).await,
+        "web_local_storage" => webdriver::execute_web_local_storage(tool_call, ctx).await,
 
 
 
@@ -82,3 +274,725 @@ pub async fn dispatch_tool<W: UiWriter>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLogger;
+    use crate::background_process::BackgroundProcessManager;
+    use crate::security::SecretsRedactor;
+    use crate::ssh_session::SshSessions;
+    use crate::tools::executor::{ToolResultCache, ToolSchemaValidator};
+    use crate::file_history::FileOperationHistory;
+    use crate::tools::macros::MacroStore;
+    use crate::tools::shell::ShellSession;
+    use crate::ui_writer::NullUiWriter;
+    use crate::webdriver_session::{WebDriverSession, WebDriverSessionState};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+
+    /// Everything a `ToolContext` borrows, owned by the test.
+    struct Fixture {
+        temp_dir: tempfile::TempDir,
+        config: g3_config::Config,
+        ui_writer: NullUiWriter,
+        webdriver_session: Arc<RwLock<Option<Arc<tokio::sync::Mutex<WebDriverSession>>>>>,
+        webdriver_process: Arc<RwLock<Option<tokio::process::Child>>>,
+        webdriver_state: Arc<RwLock<WebDriverSessionState>>,
+        background_process_manager: Arc<BackgroundProcessManager>,
+        shell_sessions: Arc<tokio::sync::Mutex<HashMap<String, ShellSession>>>,
+        ssh_sessions: Arc<SshSessions>,
+        secrets_redactor: SecretsRedactor,
+        todo_content: Arc<RwLock<String>>,
+        pending_images: Vec<g3_providers::ImageContent>,
+        recent_files: Vec<std::path::PathBuf>,
+        extra_env: HashMap<String, String>,
+        cache: Option<ToolResultCache>,
+        macros: MacroStore,
+        file_history: FileOperationHistory,
+        schema_validator: Option<ToolSchemaValidator>,
+        audit_logger: Option<AuditLogger>,
+        default_tool_timeout: Option<Duration>,
+    }
+
+    impl Fixture {
+        fn new(config: g3_config::Config) -> Self {
+            let temp_dir = tempfile::TempDir::new().unwrap();
+            let background_process_manager =
+                Arc::new(BackgroundProcessManager::new(temp_dir.path().join("logs")));
+            Self {
+                config,
+                ui_writer: NullUiWriter,
+                webdriver_session: Arc::new(RwLock::new(None)),
+                webdriver_process: Arc::new(RwLock::new(None)),
+                webdriver_state: Arc::new(RwLock::new(Default::default())),
+                background_process_manager,
+                shell_sessions: Arc::new(tokio::sync::Mutex::new(Default::default())),
+                ssh_sessions: Arc::new(Default::default()),
+                secrets_redactor: SecretsRedactor::default(),
+                todo_content: Arc::new(RwLock::new(String::new())),
+                pending_images: Vec::new(),
+                recent_files: Vec::new(),
+                extra_env: HashMap::new(),
+                cache: None,
+                macros: MacroStore::new(temp_dir.path().join(".g3").join("macros")),
+                file_history: FileOperationHistory::default(),
+                schema_validator: None,
+                audit_logger: None,
+                default_tool_timeout: None,
+                temp_dir,
+            }
+        }
+
+        fn ctx(&mut self) -> ToolContext<'_, NullUiWriter> {
+            ToolContext {
+                config: &self.config,
+                ui_writer: &self.ui_writer,
+                session_id: None,
+                working_directory: self.temp_dir.path().to_path_buf(),
+                computer_controller: None,
+                webdriver_session: &self.webdriver_session,
+                webdriver_process: &self.webdriver_process,
+                webdriver_state: &self.webdriver_state,
+                background_process_manager: &self.background_process_manager,
+                shell_sessions: &self.shell_sessions,
+                ssh_sessions: &self.ssh_sessions,
+                secrets_redactor: &self.secrets_redactor,
+                todo_content: &self.todo_content,
+                pending_images: &mut self.pending_images,
+                recent_files: &mut self.recent_files,
+                extra_env: &mut self.extra_env,
+                is_autonomous: false,
+                requirements_sha: None,
+                default_tool_timeout: self.default_tool_timeout,
+                tool_cache: self.cache.as_mut(),
+                dry_run: false,
+                macros: &mut self.macros,
+                file_history: &mut self.file_history,
+                schema_validator: self.schema_validator.as_ref(),
+                audit_logger: self.audit_logger.as_ref(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeated_read_file_is_served_from_cache() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        fixture.cache = Some(ToolResultCache::new(Duration::from_secs(60)));
+        let file = fixture.temp_dir.path().join("notes.txt");
+        std::fs::write(&file, "first version").unwrap();
+        let mut ctx = fixture.ctx();
+
+        let read = ToolCall {
+            tool: "read_file".to_string(),
+            args: json!({ "file_path": file.to_string_lossy() }),
+        };
+        let first = dispatch_tool(&read, &mut ctx).await.unwrap();
+        assert!(first.contains("first version"));
+
+        // The file is gone, so only a cached result can still return its content
+        std::fs::remove_file(&file).unwrap();
+        let second = dispatch_tool(&read, &mut ctx).await.unwrap();
+        assert_eq!(second, first);
+
+        // Writing the file invalidates the cached read
+        let write = ToolCall {
+            tool: "write_file".to_string(),
+            args: json!({ "file_path": file.to_string_lossy(), "content": "second version" }),
+        };
+        dispatch_tool(&write, &mut ctx).await.unwrap();
+        let third = dispatch_tool(&read, &mut ctx).await.unwrap();
+        assert!(third.contains("second version"));
+    }
+
+    #[tokio::test]
+    async fn test_lint_fix_invalidates_cached_reads() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        fixture.cache = Some(ToolResultCache::new(Duration::from_secs(60)));
+        let file = fixture.temp_dir.path().join("app.py");
+        std::fs::write(&file, "x = 1\n").unwrap();
+        let mut ctx = fixture.ctx();
+
+        let read = ToolCall {
+            tool: "read_file".to_string(),
+            args: json!({ "file_path": file.to_string_lossy() }),
+        };
+        let first = dispatch_tool(&read, &mut ctx).await.unwrap();
+        assert!(first.contains("x = 1"));
+
+        // Stands in for what the linter fixes; whether ruff is installed
+        // doesn't matter, the cache can't know what it changed
+        std::fs::write(&file, "y = 2\n").unwrap();
+        let lint = ToolCall {
+            tool: "code_lint".to_string(),
+            args: json!({ "path": file.to_string_lossy(), "fix": true }),
+        };
+        dispatch_tool(&lint, &mut ctx).await.unwrap();
+
+        let second = dispatch_tool(&read, &mut ctx).await.unwrap();
+        assert!(second.contains("y = 2"), "{}", second);
+    }
+
+    #[tokio::test]
+    async fn test_read_file_size_limit() {
+        let mut config = g3_config::Config::default();
+        config.agent.max_file_read_bytes = 100;
+        let mut fixture = Fixture::new(config);
+        let file = fixture.temp_dir.path().join("big.txt");
+        let content: String = (1..=40).map(|i| format!("line {:02}\n", i)).collect();
+        std::fs::write(&file, &content).unwrap();
+        let mut ctx = fixture.ctx();
+
+        let read = |args: serde_json::Value| ToolCall {
+            tool: "read_file".to_string(),
+            args,
+        };
+        let path = file.to_string_lossy();
+
+        let limited = dispatch_tool(&read(json!({ "file_path": path })), &mut ctx)
+            .await
+            .unwrap();
+        assert!(limited.contains("line 12\nline\n... [truncated: 220 bytes remaining"));
+
+        let forced = dispatch_tool(&read(json!({ "file_path": path, "force": true })), &mut ctx)
+            .await
+            .unwrap();
+        assert!(forced.ends_with("line 40\n"));
+
+        let wider = dispatch_tool(&read(json!({ "file_path": path, "max_bytes": 1000 })), &mut ctx)
+            .await
+            .unwrap();
+        assert!(!wider.contains("truncated"));
+
+        let summary = dispatch_tool(&read(json!({ "file_path": path, "summarize": true })), &mut ctx)
+            .await
+            .unwrap();
+        assert!(summary.starts_with("📄 File summary (320 bytes, 40 lines"));
+        assert!(summary.contains("   40: line 40\n"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_lists_block_tools() {
+        let mut config = g3_config::Config::default();
+        config.agent.tool_allowlist = Some(vec!["read_file".to_string(), "shell".to_string()]);
+        config.agent.tool_blocklist = Some(vec!["shell".to_string()]);
+        let mut fixture = Fixture::new(config);
+        let file = fixture.temp_dir.path().join("notes.txt");
+        std::fs::write(&file, "allowed").unwrap();
+        let mut ctx = fixture.ctx();
+
+        let shell = ToolCall {
+            tool: "shell".to_string(),
+            args: json!({ "command": "touch created-by-shell" }),
+        };
+        assert_eq!(
+            dispatch_tool(&shell, &mut ctx).await.unwrap(),
+            "❌ Tool 'shell' is not permitted in this configuration"
+        );
+        let write = ToolCall {
+            tool: "write_file".to_string(),
+            args: json!({ "file_path": file.to_string_lossy(), "content": "overwritten" }),
+        };
+        assert_eq!(
+            dispatch_tool(&write, &mut ctx).await.unwrap(),
+            "❌ Tool 'write_file' is not permitted in this configuration"
+        );
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "allowed");
+        assert!(!ctx.working_directory.join("created-by-shell").exists());
+
+        let read = ToolCall {
+            tool: "read_file".to_string(),
+            args: json!({ "file_path": file.to_string_lossy() }),
+        };
+        assert!(dispatch_tool(&read, &mut ctx).await.unwrap().contains("allowed"));
+
+        let finish = ToolCall {
+            tool: "final_output".to_string(),
+            args: json!({ "summary": "done" }),
+        };
+        assert!(!dispatch_tool(&finish, &mut ctx)
+            .await
+            .unwrap()
+            .contains("not permitted"));
+    }
+
+    #[tokio::test]
+    async fn test_str_replace_backup_and_restore() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        let file = fixture.temp_dir.path().join("lib.rs");
+        let original = "fn answer() -> u32 {\n    41\n}\n";
+        std::fs::write(&file, original).unwrap();
+        let backup = file_ops::backup_path(&file);
+        let mut ctx = fixture.ctx();
+
+        let patch = ToolCall {
+            tool: "str_replace".to_string(),
+            args: json!({
+                "file_path": file.to_string_lossy(),
+                "diff": "@@ -1,3 +1,3 @@\n fn answer() -> u32 {\n-    41\n+    42\n }\n",
+                "create_backup": true,
+            }),
+        };
+        let result = dispatch_tool(&patch, &mut ctx).await.unwrap();
+        assert!(result.starts_with("✅ applied unified diff (backup at"), "{}", result);
+        assert!(std::fs::read_to_string(&file).unwrap().contains("42"));
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), original);
+
+        let restore = ToolCall {
+            tool: "restore_backup".to_string(),
+            args: json!({ "file_path": file.to_string_lossy() }),
+        };
+        let result = dispatch_tool(&restore, &mut ctx).await.unwrap();
+        assert!(result.starts_with("✅ Restored"), "{}", result);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), original);
+        assert!(!backup.exists());
+
+        let result = dispatch_tool(&restore, &mut ctx).await.unwrap();
+        assert!(result.starts_with("❌ No backup found"), "{}", result);
+
+        // A diff that does not apply still leaves the backup behind
+        let bad_patch = ToolCall {
+            tool: "str_replace".to_string(),
+            args: json!({
+                "file_path": file.to_string_lossy(),
+                "diff": "@@ -1,1 +1,1 @@\n-fn missing() {}\n+fn other() {}\n",
+                "create_backup": true,
+            }),
+        };
+        let result = dispatch_tool(&bad_patch, &mut ctx).await.unwrap();
+        assert!(result.starts_with("❌ Pattern not found"), "{}", result);
+        assert!(result.contains("Hunk 1 failed"), "{}", result);
+        assert!(result.contains("Searched for:\nfn missing() {}"), "{}", result);
+        assert!(result.contains("Closest match in file:"), "{}", result);
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), original);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), original);
+    }
+
+    #[tokio::test]
+    async fn test_glob_replace_reports_relative_paths() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        let src = fixture.temp_dir.path().join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("a.rs"), "use config::load;\nload();\n").unwrap();
+        std::fs::write(src.join("b.rs"), "fn main() {}\n").unwrap();
+        let mut ctx = fixture.ctx();
+
+        let call = |dry_run: bool| ToolCall {
+            tool: "glob_replace".to_string(),
+            args: json!({
+                "pattern": "src/*.rs",
+                "find": "config::load",
+                "replace": "settings::load",
+                "dry_run": dry_run,
+            }),
+        };
+
+        let result = dispatch_tool(&call(true), &mut ctx).await.unwrap();
+        assert!(result.starts_with("[DRY RUN] Would replace 1 matches in 1 files"), "{}", result);
+        assert!(result.contains(r#""file": "src/a.rs""#), "{}", result);
+        assert!(std::fs::read_to_string(src.join("a.rs")).unwrap().contains("config::load"));
+
+        let result = dispatch_tool(&call(false), &mut ctx).await.unwrap();
+        assert!(result.starts_with("✅ Replaced 1 matches in 1 files"), "{}", result);
+        assert!(std::fs::read_to_string(src.join("a.rs")).unwrap().contains("settings::load"));
+
+        let result = dispatch_tool(&call(false), &mut ctx).await.unwrap();
+        assert!(result.starts_with("No matches"), "{}", result);
+    }
+
+    #[tokio::test]
+    async fn test_code_rename_symbol_reports_relative_paths() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        let src = fixture.temp_dir.path().join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("lib.rs"), "fn load() {}\nfn main() { load(); }\n").unwrap();
+        let mut ctx = fixture.ctx();
+
+        let call = |dry_run: bool| ToolCall {
+            tool: "code_rename_symbol".to_string(),
+            args: json!({
+                "old_name": "load",
+                "new_name": "load_config",
+                "path": "src",
+                "language": "rust",
+                "symbol_type": "function",
+                "dry_run": dry_run,
+            }),
+        };
+
+        let result = dispatch_tool(&call(true), &mut ctx).await.unwrap();
+        assert!(
+            result.starts_with("[DRY RUN] Would rename 2 occurrences of 'load' to 'load_config' in 1 files"),
+            "{}",
+            result
+        );
+        assert!(result.contains(r#""file": "src/lib.rs""#), "{}", result);
+        assert!(result.contains(r#""occurrences_replaced": 2"#), "{}", result);
+
+        let result = dispatch_tool(&call(false), &mut ctx).await.unwrap();
+        assert!(result.starts_with("✅ Renamed 2 occurrences"), "{}", result);
+        assert_eq!(
+            std::fs::read_to_string(src.join("lib.rs")).unwrap(),
+            "fn load_config() {}\nfn main() { load_config(); }\n"
+        );
+
+        let result = dispatch_tool(&call(false), &mut ctx).await.unwrap();
+        assert!(result.starts_with("No occurrences of 'load'"), "{}", result);
+    }
+
+    #[tokio::test]
+    async fn test_dependency_graph_writes_dot_file() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        let pkg = fixture.temp_dir.path().join("pkg");
+        std::fs::create_dir_all(&pkg).unwrap();
+        std::fs::write(pkg.join("__init__.py"), "").unwrap();
+        std::fs::write(pkg.join("a.py"), "from pkg import b\n").unwrap();
+        std::fs::write(pkg.join("b.py"), "import pkg.a\n").unwrap();
+        let mut ctx = fixture.ctx();
+
+        let call = ToolCall {
+            tool: "dependency_graph".to_string(),
+            args: json!({"path": ".", "language": "python", "format": "dot", "output_path": "deps.dot"}),
+        };
+        let result = dispatch_tool(&call, &mut ctx).await.unwrap();
+        assert!(
+            result.starts_with("✅ Dependency graph of 3 files and 2 dependencies written to"),
+            "{}",
+            result
+        );
+        assert!(result.contains("pkg/a.py -> pkg/b.py -> pkg/a.py"), "{}", result);
+        let dot = std::fs::read_to_string(fixture.temp_dir.path().join("deps.dot")).unwrap();
+        assert!(dot.contains("\"pkg/b.py\" -> \"pkg/a.py\" [color=red];"), "{}", dot);
+    }
+
+    #[tokio::test]
+    async fn test_file_contents_are_not_redacted() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        let source = "pub token: Option<String>,\nlet api_key = std::env::var(\"API_KEY\");\n";
+        std::fs::write(fixture.temp_dir.path().join("config.rs"), source).unwrap();
+        let mut ctx = fixture.ctx();
+
+        let read = ToolCall {
+            tool: "read_file".to_string(),
+            args: json!({ "file_path": "config.rs" }),
+        };
+        let result = dispatch_tool(&read, &mut ctx).await.unwrap();
+        assert!(result.contains("pub token: Option<String>,"), "{}", result);
+        assert!(result.contains("let api_key = std::env::var(\"API_KEY\");"), "{}", result);
+
+        let cat = ToolCall {
+            tool: "shell".to_string(),
+            args: json!({ "command": "cat config.rs" }),
+        };
+        let result = dispatch_tool(&cat, &mut ctx).await.unwrap();
+        assert!(result.contains("[REDACTED:secret]"), "{}", result);
+    }
+
+    #[tokio::test]
+    async fn test_read_image_resolves_relative_paths_against_working_directory() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        let png = [
+            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D,
+        ];
+        std::fs::write(fixture.temp_dir.path().join("shot.png"), png).unwrap();
+        let call = ToolCall {
+            tool: "read_image".to_string(),
+            args: json!({ "file_paths": ["shot.png"] }),
+        };
+
+        let result = dispatch_tool(&call, &mut fixture.ctx()).await.unwrap();
+        assert_eq!(result, "1 image(s) read.");
+        assert_eq!(fixture.pending_images.len(), 1);
+        assert_eq!(fixture.pending_images[0].media_type, "image/png");
+    }
+
+    #[tokio::test]
+    async fn test_detached_shell_command_reports_exit_header() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        let mut ctx = fixture.ctx();
+        let call = ToolCall {
+            tool: "shell".to_string(),
+            args: json!({ "command": "nohup true > /dev/null 2>&1 &" }),
+        };
+
+        let result = dispatch_tool(&call, &mut ctx).await.unwrap();
+        assert_eq!(
+            result,
+            "[exit: 0]\n✅ Command launched in background (detached process)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shell_env_applies_to_later_shell_commands() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        let mut ctx = fixture.ctx();
+        let call = |tool: &str, args: serde_json::Value| ToolCall {
+            tool: tool.to_string(),
+            args,
+        };
+
+        let set = call(
+            "shell_env",
+            json!({ "action": "set", "name": "G3_GREETING", "value": "hi there" }),
+        );
+        assert!(dispatch_tool(&set, &mut ctx).await.unwrap().starts_with("✅"));
+        let secret = call(
+            "shell_env",
+            json!({ "action": "set", "name": "API_TOKEN", "value": "t0ps3cret" }),
+        );
+        dispatch_tool(&secret, &mut ctx).await.unwrap();
+
+        let echo = call("shell", json!({ "command": "echo \"$G3_GREETING/$API_TOKEN\"" }));
+        assert_eq!(
+            dispatch_tool(&echo, &mut ctx).await.unwrap(),
+            "[exit: 0]\nhi there/t0ps3cret"
+        );
+
+        let list = dispatch_tool(&call("shell_env", json!({ "action": "list" })), &mut ctx)
+            .await
+            .unwrap();
+        assert_eq!(list, "API_TOKEN=[REDACTED:secret]\nG3_GREETING=hi there");
+        let get = call("shell_env", json!({ "action": "get", "name": "G3_GREETING" }));
+        assert_eq!(
+            dispatch_tool(&get, &mut ctx).await.unwrap(),
+            "G3_GREETING=hi there (set with shell_env)"
+        );
+
+        let unset = call("shell_env", json!({ "action": "unset", "name": "G3_GREETING" }));
+        assert!(dispatch_tool(&unset, &mut ctx).await.unwrap().starts_with("✅"));
+        let echo = call("shell", json!({ "command": "echo \"[$G3_GREETING]\"" }));
+        assert_eq!(dispatch_tool(&echo, &mut ctx).await.unwrap(), "[exit: 0]\n[]");
+        assert_eq!(dispatch_tool(&get, &mut ctx).await.unwrap(), "G3_GREETING is not set");
+
+        let invalid = call(
+            "shell_env",
+            json!({ "action": "set", "name": "BAD-NAME", "value": "x" }),
+        );
+        assert!(dispatch_tool(&invalid, &mut ctx).await.unwrap().starts_with("❌"));
+    }
+
+    #[tokio::test]
+    async fn test_macro_record_and_playback() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        let file = fixture.temp_dir.path().join("macro.txt");
+        let mut ctx = fixture.ctx();
+        let call = |tool: &str, args: serde_json::Value| ToolCall {
+            tool: tool.to_string(),
+            args,
+        };
+
+        let started = dispatch_tool(&call("macro_record", json!({"name": "touch"})), &mut ctx)
+            .await
+            .unwrap();
+        assert!(started.starts_with("✅ Recording macro 'touch'"), "{}", started);
+        let write = call(
+            "write_file",
+            json!({"file_path": file.to_string_lossy(), "content": "from macro\n"}),
+        );
+        let read = call("read_file", json!({"file_path": file.to_string_lossy()}));
+        dispatch_tool(&write, &mut ctx).await.unwrap();
+        dispatch_tool(&read, &mut ctx).await.unwrap();
+        let stopped = dispatch_tool(&call("macro_stop", json!({})), &mut ctx).await.unwrap();
+        assert_eq!(stopped, "✅ Saved macro 'touch' with 2 tool calls");
+
+        let listed = dispatch_tool(&call("macro_list", json!({})), &mut ctx).await.unwrap();
+        assert!(listed.contains("- touch (2 tool calls)"), "{}", listed);
+
+        let preview = call("macro_playback", json!({"name": "touch", "dry_run": true}));
+        let preview = dispatch_tool(&preview, &mut ctx).await.unwrap();
+        assert!(preview.contains("1. write_file"), "{}", preview);
+        assert!(preview.contains("2. read_file"), "{}", preview);
+
+        std::fs::remove_file(&file).unwrap();
+        let played = call("macro_playback", json!({"name": "touch"}));
+        let played = dispatch_tool(&played, &mut ctx).await.unwrap();
+        assert!(played.starts_with("✅ Macro 'touch' ran 2 tool call(s)"), "{}", played);
+        assert!(played.contains("from macro"), "{}", played);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "from macro\n");
+
+        // Playback is not recorded into itself once recording has stopped
+        assert_eq!(ctx.macros.load("touch").unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_write_file_creates_parent_directories() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        let root = fixture.temp_dir.path().to_path_buf();
+        let mut ctx = fixture.ctx();
+
+        let write = ToolCall {
+            tool: "write_file".to_string(),
+            args: json!({
+                "file_path": "src/utils/helpers/string.rs",
+                "content": "pub fn shout(s: &str) -> String {\n    s.to_uppercase()\n}\n"
+            }),
+        };
+        let result = dispatch_tool(&write, &mut ctx).await.unwrap();
+        assert!(result.starts_with("✅"), "{}", result);
+        assert!(root.join("src/utils/helpers").is_dir());
+        let written = std::fs::read_to_string(root.join("src/utils/helpers/string.rs")).unwrap();
+        assert!(written.contains("to_uppercase"));
+
+        let write = ToolCall {
+            tool: "write_file".to_string(),
+            args: json!({
+                "file_path": "docs/guide/intro.md",
+                "content": "# Intro\n",
+                "create_dirs": false
+            }),
+        };
+        let result = dispatch_tool(&write, &mut ctx).await.unwrap();
+        assert_eq!(
+            result,
+            format!(
+                "❌ Directory '{}' does not exist (use create_dirs: true to create it)",
+                root.join("docs/guide").display()
+            )
+        );
+        assert!(!root.join("docs").exists());
+    }
+
+    #[tokio::test]
+    async fn test_file_edits_can_be_undone() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        let file = fixture.temp_dir.path().join("lib.rs");
+        let mut ctx = fixture.ctx();
+
+        let write = ToolCall {
+            tool: "write_file".to_string(),
+            args: json!({"file_path": "lib.rs", "content": "fn a() {}\n"}),
+        };
+        dispatch_tool(&write, &mut ctx).await.unwrap();
+        let replace = ToolCall {
+            tool: "str_replace".to_string(),
+            args: json!({
+                "file_path": "lib.rs",
+                "diff": "@@ -1 +1 @@\n-fn a() {}\n+fn b() {}\n"
+            }),
+        };
+        let result = dispatch_tool(&replace, &mut ctx).await.unwrap();
+        assert!(result.starts_with('✅'), "{}", result);
+        // A failed edit is not recorded
+        dispatch_tool(&replace, &mut ctx).await.unwrap();
+        assert_eq!(ctx.file_history.len(), 2);
+
+        let undone = ctx.file_history.rollback_last_file_operation().unwrap().unwrap();
+        assert_eq!(undone.operation, "str_replace");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "fn a() {}\n");
+        ctx.file_history.rollback_last_file_operation().unwrap().unwrap();
+        assert!(!file.exists());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_arguments_are_rejected_before_running() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        fixture.schema_validator = Some(ToolSchemaValidator::new(
+            &crate::tool_definitions::create_tool_definitions(
+                crate::tool_definitions::ToolConfig::new(false, false, false),
+            ),
+        ));
+        let root = fixture.temp_dir.path().to_path_buf();
+        let mut ctx = fixture.ctx();
+
+        let read = ToolCall {
+            tool: "read_file".to_string(),
+            args: json!({"path": "notes.txt"}),
+        };
+        let result = dispatch_tool(&read, &mut ctx).await.unwrap();
+        assert_eq!(
+            result,
+            "❌ Invalid tool arguments: \"file_path\" is a required property"
+        );
+
+        let write = ToolCall {
+            tool: "write_file".to_string(),
+            args: json!({"file_path": "notes.txt", "content": ["not", "a", "string"]}),
+        };
+        let result = dispatch_tool(&write, &mut ctx).await.unwrap();
+        assert!(result.starts_with("❌ Invalid tool arguments:"), "{}", result);
+        assert!(!root.join("notes.txt").exists());
+
+        let write = ToolCall {
+            tool: "write_file".to_string(),
+            args: json!({"file_path": "notes.txt", "content": "hello"}),
+        };
+        let result = dispatch_tool(&write, &mut ctx).await.unwrap();
+        assert!(result.starts_with('✅'), "{}", result);
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_are_audited() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        let log_path = fixture.temp_dir.path().join(".g3").join("audit.jsonl");
+        fixture.audit_logger = Some(AuditLogger::new(log_path.clone()));
+        let mut ctx = fixture.ctx();
+
+        let write = ToolCall {
+            tool: "write_file".to_string(),
+            args: json!({"file_path": "notes.txt", "content": "password=hunter2hunter2"}),
+        };
+        dispatch_tool(&write, &mut ctx).await.unwrap();
+        let read = ToolCall {
+            tool: "read_file".to_string(),
+            args: json!({"file_path": "missing.txt"}),
+        };
+        dispatch_tool(&read, &mut ctx).await.unwrap();
+
+        let entries = crate::audit::read_audit_log(&log_path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].tool, "write_file");
+        assert!(entries[0].success, "{}", entries[0].result_summary);
+        assert!(!entries[0].args_summary.contains("hunter2"));
+        assert_eq!(entries[1].tool, "read_file");
+        assert!(!entries[1].success);
+        assert!(entries[1].args_summary.contains("missing.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_prioritized_batch_runs_shell_before_reads() {
+        use crate::tools::executor::execute_prioritized_batch;
+
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        let mut ctx = fixture.ctx();
+        let read = ToolCall {
+            tool: "read_file".to_string(),
+            args: json!({"file_path": "generated.txt"}),
+        };
+        let build = ToolCall {
+            tool: "shell".to_string(),
+            args: json!({"command": "echo built > generated.txt"}),
+        };
+
+        let results = execute_prioritized_batch(vec![read, build], &mut ctx).await;
+        let tools: Vec<&str> = results.iter().map(|(call, _)| call.tool.as_str()).collect();
+        assert_eq!(tools, ["shell", "read_file"]);
+        assert!(results[1].1.contains("built"), "{}", results[1].1);
+    }
+
+    #[tokio::test]
+    async fn test_self_timed_tool_reports_its_own_timeout() {
+        let mut fixture = Fixture::new(g3_config::Config::default());
+        fixture.default_tool_timeout = Some(Duration::from_secs(1));
+        let mut ctx = fixture.ctx();
+
+        let start = ToolCall {
+            tool: "background_process".to_string(),
+            args: json!({"name": "quiet", "command": "sleep 30"}),
+        };
+        let started = dispatch_tool(&start, &mut ctx).await.unwrap();
+        assert!(started.starts_with("✅"), "{}", started);
+
+        let wait = ToolCall {
+            tool: "process_output_wait".to_string(),
+            args: json!({"name": "quiet", "pattern": "ready", "timeout_secs": 1}),
+        };
+        let result = dispatch_tool(&wait, &mut ctx).await.unwrap();
+        assert_eq!(
+            result,
+            "❌ Timed out after 1s waiting for 'ready' in the output of 'quiet'"
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Database tool: read-only SQL queries against SQLite files.
+
+use anyhow::{Context, Result};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params_from_iter, Connection, OpenFlags};
+use serde_json::{Map, Value};
+use std::path::Path;
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+/// Rows returned when `limit` is not given.
+const DEFAULT_ROW_LIMIT: usize = 100;
+
+/// Rows from a query, capped at the requested limit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryResult {
+    pub rows: Vec<Value>,
+    /// True if the query produced more rows than the limit.
+    pub truncated: bool,
+}
+
+/// Whether `query` starts with `SELECT` (or a `WITH` CTE), ignoring leading
+/// whitespace and SQL comments.
+pub fn is_select_query(query: &str) -> bool {
+    let mut rest = query.trim_start();
+    loop {
+        if let Some(after) = rest.strip_prefix("--") {
+            rest = after
+                .split_once('\n')
+                .map_or("", |(_, tail)| tail)
+                .trim_start();
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            rest = after
+                .split_once("*/")
+                .map_or("", |(_, tail)| tail)
+                .trim_start();
+        } else {
+            break;
+        }
+    }
+
+    let keyword: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    keyword.eq_ignore_ascii_case("select") || keyword.eq_ignore_ascii_case("with")
+}
+
+/// Run a read-only query against the SQLite database at `db_path`.
+///
+/// The database is opened with `SQLITE_OPEN_READ_ONLY`, and statements that
+/// SQLite reports as writing are rejected before they run.
+pub fn run_query(
+    db_path: &Path,
+    query: &str,
+    params: &[Value],
+    limit: usize,
+) -> Result<QueryResult> {
+    if !is_select_query(query) {
+        anyhow::bail!("Only SELECT queries are allowed");
+    }
+
+    let conn = Connection::open_with_flags(
+        db_path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("Failed to open database {}", db_path.display()))?;
+
+    let mut stmt = conn.prepare(query)?;
+    if !stmt.readonly() {
+        anyhow::bail!("Only read-only queries are allowed");
+    }
+
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    let mut rows = stmt.query(params_from_iter(params.iter().map(json_to_sql)))?;
+
+    let mut result = QueryResult {
+        rows: Vec::new(),
+        truncated: false,
+    };
+    while let Some(row) = rows.next()? {
+        if result.rows.len() == limit {
+            result.truncated = true;
+            break;
+        }
+        let mut object = Map::new();
+        for (i, column) in columns.iter().enumerate() {
+            object.insert(column.clone(), sql_to_json(row.get_ref(i)?));
+        }
+        result.rows.push(Value::Object(object));
+    }
+
+    Ok(result)
+}
+
+fn json_to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+fn sql_to_json(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(bytes) => Value::String(String::from_utf8_lossy(bytes).to_string()),
+        ValueRef::Blob(bytes) => Value::String(format!("<blob {} bytes>", bytes.len())),
+    }
+}
+
+/// Execute the `database_query` tool.
+pub async fn execute_database_query<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing database_query tool call");
+
+    let db_path = match tool_call.args.get("db_path").and_then(|v| v.as_str()) {
+        Some(p) if !p.trim().is_empty() => ctx.resolve_path(p),
+        _ => return Ok("❌ Missing db_path argument".to_string()),
+    };
+
+    let query = match tool_call.args.get("query").and_then(|v| v.as_str()) {
+        Some(q) if !q.trim().is_empty() => q.to_string(),
+        _ => return Ok("❌ Missing query argument".to_string()),
+    };
+
+    if !is_select_query(&query) {
+        return Ok(
+            "❌ Only SELECT queries are allowed; database_query opens the database read-only"
+                .to_string(),
+        );
+    }
+
+    if !db_path.is_file() {
+        return Ok(format!("❌ Database not found: {}", db_path.display()));
+    }
+
+    let params = tool_call
+        .args
+        .get("params")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let limit = tool_call
+        .args
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .map(|l| l as usize)
+        .unwrap_or(DEFAULT_ROW_LIMIT);
+
+    let result =
+        tokio::task::spawn_blocking(move || run_query(&db_path, &query, &params, limit)).await?;
+
+    match result {
+        Ok(result) => Ok(format_query_result(&result, limit)),
+        Err(e) => Ok(format!("❌ Query failed: {:#}", e)),
+    }
+}
+
+/// Format rows as a JSON array followed by a row count summary.
+pub fn format_query_result(result: &QueryResult, limit: usize) -> String {
+    let json = serde_json::to_string_pretty(&result.rows).unwrap_or_else(|_| "[]".to_string());
+    let count = result.rows.len();
+    let summary = if result.truncated {
+        format!(
+            "{} rows shown (limit {} reached; more rows available)",
+            count, limit
+        )
+    } else if count == 1 {
+        "1 row".to_string()
+    } else {
+        format!("{} rows", count)
+    };
+    format!("{}\n\n{}", json, summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn create_db() -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("test.sqlite");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, score REAL);
+             INSERT INTO users (name, score) VALUES ('alice', 9.5), ('bob', NULL), ('carol', 7.0);",
+        )
+        .unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn test_select_rows_as_objects() {
+        let (_dir, path) = create_db();
+
+        let result = run_query(
+            &path,
+            "SELECT id, name, score FROM users WHERE id >= ? ORDER BY id",
+            &[json!(2)],
+            100,
+        )
+        .unwrap();
+
+        assert!(!result.truncated);
+        assert_eq!(
+            result.rows,
+            vec![
+                json!({"id": 2, "name": "bob", "score": null}),
+                json!({"id": 3, "name": "carol", "score": 7.0}),
+            ]
+        );
+        assert!(format_query_result(&result, 100).ends_with("\n\n2 rows"));
+    }
+
+    #[test]
+    fn test_limit_truncates() {
+        let (_dir, path) = create_db();
+
+        let result = run_query(&path, "SELECT name FROM users ORDER BY id", &[], 2).unwrap();
+        assert_eq!(result.rows.len(), 2);
+        assert!(result.truncated);
+        assert!(format_query_result(&result, 2).contains("limit 2 reached"));
+    }
+
+    #[test]
+    fn test_rejects_writes() {
+        let (_dir, path) = create_db();
+
+        assert!(!is_select_query("DELETE FROM users"));
+        assert!(!is_select_query("  -- comment\nDROP TABLE users"));
+        assert!(is_select_query("/* count */ select count(*) from users"));
+        assert!(is_select_query("WITH t AS (SELECT 1) SELECT * FROM t"));
+
+        let err = run_query(&path, "UPDATE users SET name = 'x'", &[], 100).unwrap_err();
+        assert!(err.to_string().contains("Only SELECT"));
+
+        // A CTE that writes passes the keyword check but not SQLite's
+        let err = run_query(&path, "WITH t AS (SELECT 1) DELETE FROM users", &[], 100).unwrap_err();
+        assert!(err.to_string().contains("read-only"));
+
+        let count = run_query(&path, "SELECT count(*) AS n FROM users", &[], 100).unwrap();
+        assert_eq!(count.rows, vec![json!({"n": 3})]);
+    }
+}
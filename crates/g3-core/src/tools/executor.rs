@@ -1,34 +1,106 @@
 //! Tool executor trait and context for tool execution.
 
 use anyhow::Result;
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap};
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
+use tracing::{debug, warn};
 
+use crate::audit::AuditLogger;
 use crate::background_process::BackgroundProcessManager;
+use crate::file_history::FileOperationHistory;
 use crate::paths::{ensure_session_dir, get_session_todo_path, get_todo_path};
+use crate::security::SecretsRedactor;
+use crate::ssh_session::SshSessions;
+use crate::tools::macros::MacroStore;
+use crate::tools::shell::ShellSession;
 use crate::ui_writer::UiWriter;
-use crate::webdriver_session::WebDriverSession;
+use crate::webdriver_session::{WebDriverSession, WebDriverSessionState};
 use crate::ToolCall;
 use g3_config::Config;
+use g3_providers::Tool;
 
 /// Context passed to tool executors containing shared state.
 pub struct ToolContext<'a, W: UiWriter> {
     pub config: &'a Config,
     pub ui_writer: &'a W,
     pub session_id: Option<&'a str>,
-    pub working_dir: Option<&'a str>,
+    /// Directory that relative paths are resolved against and commands run
+    /// in. Follows `cd` in shell commands.
+    pub working_directory: PathBuf,
     pub computer_controller: Option<&'a Box<dyn crate::computer_control::ComputerController>>,
     pub webdriver_session: &'a Arc<RwLock<Option<Arc<tokio::sync::Mutex<WebDriverSession>>>>>,
     pub webdriver_process: &'a Arc<RwLock<Option<tokio::process::Child>>>,
+    pub webdriver_state: &'a Arc<RwLock<WebDriverSessionState>>,
     pub background_process_manager: &'a Arc<BackgroundProcessManager>,
+    /// Named persistent shell sessions, kept across tool calls.
+    pub shell_sessions: &'a Arc<tokio::sync::Mutex<HashMap<String, ShellSession>>>,
+    /// SSH connections kept across read_file calls with `ssh_host`.
+    pub ssh_sessions: &'a Arc<SshSessions>,
+    /// Applied to every tool result before it is returned to the LLM.
+    pub secrets_redactor: &'a SecretsRedactor,
     pub todo_content: &'a Arc<RwLock<String>>,
     pub pending_images: &'a mut Vec<g3_providers::ImageContent>,
+    /// Files recently read or edited, most recent last (see [`MAX_RECENT_FILES`]).
+    pub recent_files: &'a mut Vec<PathBuf>,
+    /// Environment variables set with `shell_env`, passed to every shell command.
+    pub extra_env: &'a mut HashMap<String, String>,
     pub is_autonomous: bool,
     pub requirements_sha: Option<&'a str>,
-
+    /// Timeout applied to every tool call unless overridden by a `timeout_secs` arg.
+    pub default_tool_timeout: Option<Duration>,
+    /// Cache for read-only tool results; `None` when caching is disabled.
+    pub tool_cache: Option<&'a mut ToolResultCache>,
+    /// Describe tool calls instead of running them (see [`dry_run_result`]).
+    pub dry_run: bool,
+    /// Saved tool-call macros and the recording in progress.
+    pub macros: &'a mut MacroStore,
+    /// File edits made by tools, for `/undo` and `/redo`.
+    pub file_history: &'a mut FileOperationHistory,
+    /// Checks arguments before a tool runs; `None` when validation is skipped.
+    pub schema_validator: Option<&'a ToolSchemaValidator>,
+    /// Records every tool call; `None` when not auditing.
+    pub audit_logger: Option<&'a AuditLogger>,
 }
 
 impl<'a, W: UiWriter> ToolContext<'a, W> {
+    /// Expand `~` and resolve a relative `path` against the working directory.
+    pub fn resolve_path(&self, path: &str) -> PathBuf {
+        let path = PathBuf::from(shellexpand::tilde(path).as_ref());
+        if path.is_relative() {
+            self.working_directory.join(path)
+        } else {
+            path
+        }
+    }
+
+    /// Remember the file a read_file, write_file or str_replace call used,
+    /// for ranking code_search results.
+    pub fn record_file_access(&mut self, tool_call: &ToolCall) {
+        if !FILE_ACCESS_TOOLS.contains(&tool_call.tool.as_str()) {
+            return;
+        }
+        let Some(path) = tool_call.args.get("file_path").and_then(|v| v.as_str()) else {
+            return;
+        };
+        // Remote files have no place in the local tree
+        if tool_call.args.get("ssh_host").is_some() {
+            return;
+        }
+        let path = self.resolve_path(path);
+        self.recent_files.retain(|p| p != &path);
+        self.recent_files.push(path);
+        if self.recent_files.len() > MAX_RECENT_FILES {
+            self.recent_files.remove(0);
+        }
+    }
+
     /// Get the path to the TODO file (session-scoped or workspace).
     pub fn get_todo_path(&self) -> std::path::PathBuf {
         if let Some(session_id) = self.session_id {
@@ -40,6 +112,502 @@ impl<'a, W: UiWriter> ToolContext<'a, W> {
     }
 }
 
+/// Number of recently accessed files kept in [`ToolContext::recent_files`].
+pub const MAX_RECENT_FILES: usize = 20;
+
+/// Tools whose `file_path` is recorded as recently accessed.
+const FILE_ACCESS_TOOLS: &[&str] = &["read_file", "write_file", "str_replace"];
+
+/// Tools that still run in dry-run mode, so the agent can finish its turn.
+pub const DRY_RUN_EXEMPT_TOOLS: &[&str] = &["final_output"];
+
+/// Tools that run whatever `tool_allowlist` and `tool_blocklist` say, so the
+/// agent can always end its session.
+pub const ALWAYS_PERMITTED_TOOLS: &[&str] = &["final_output"];
+
+/// Whether the configured allow- and blocklists let `tool` run.
+pub fn tool_permitted(tool: &str, agent: &g3_config::AgentConfig) -> bool {
+    let listed =
+        |list: &Option<Vec<String>>| list.as_ref().map(|tools| tools.iter().any(|t| t == tool));
+    if ALWAYS_PERMITTED_TOOLS.contains(&tool) {
+        return true;
+    }
+    if listed(&agent.tool_blocklist) == Some(true) {
+        return false;
+    }
+    listed(&agent.tool_allowlist).unwrap_or(true)
+}
+
+/// Whether a tool result reports a failure: it starts with `❌`, or it is a
+/// `shell` result whose `[exit: N]` header has a non-zero code.
+pub fn is_error_result(result: &str) -> bool {
+    result.starts_with('❌')
+        || super::shell::parse_exit_header(result).is_some_and(|code| code != 0)
+}
+
+/// Result returned in place of running a tool in dry-run mode.
+pub fn dry_run_result(tool_call: &ToolCall) -> String {
+    format!(
+        "[DRY RUN] Would execute tool '{}' with args: {}",
+        tool_call.tool, tool_call.args
+    )
+}
+
+/// Checks tool call arguments against the `input_schema` of the tool they call.
+pub struct ToolSchemaValidator {
+    validators: HashMap<String, jsonschema::Validator>,
+}
+
+impl ToolSchemaValidator {
+    /// Compile the input schemas of `tools`. A tool whose schema does not
+    /// compile is not validated.
+    pub fn new(tools: &[Tool]) -> Self {
+        let mut validators = HashMap::new();
+        for tool in tools {
+            match jsonschema::validator_for(&tool.input_schema) {
+                Ok(validator) => {
+                    validators.insert(tool.name.clone(), validator);
+                }
+                Err(e) => warn!("Not validating '{}': invalid input schema: {}", tool.name, e),
+            }
+        }
+        Self { validators }
+    }
+
+    /// Check the arguments of `tool_call`, describing every problem found.
+    /// Calls to tools without a schema always pass.
+    pub fn validate(&self, tool_call: &ToolCall) -> Result<(), String> {
+        let Some(validator) = self.validators.get(&tool_call.tool) else {
+            return Ok(());
+        };
+        let errors: Vec<String> = validator
+            .iter_errors(&tool_call.args)
+            .map(|error| {
+                let path = error.instance_path.to_string();
+                if path.is_empty() {
+                    error.to_string()
+                } else {
+                    format!("{} (at {})", error, path)
+                }
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+}
+
+/// Tools that interpret their own `timeout_secs` argument and report their own
+/// timeout, with whatever they found so far.
+const SELF_TIMED_TOOLS: &[&str] = &[
+    "run_tests",
+    "http_request",
+    "process_output_wait",
+    "web_wait_for_element",
+    "web_wait_for_navigation",
+    "web_download_file",
+];
+
+/// How long the dispatcher waits past a self-timed tool's own `timeout_secs`,
+/// so the tool's timeout message arrives first.
+const SELF_TIMED_GRACE: Duration = Duration::from_secs(5);
+
+/// Resolve the timeout for a tool call.
+/// An explicit `timeout_secs` argument takes precedence over the configured
+/// default. Self-timed tools keep the default, extended to their own
+/// `timeout_secs` plus [`SELF_TIMED_GRACE`] when that is longer.
+pub fn resolve_tool_timeout(tool_call: &ToolCall, default: Option<Duration>) -> Option<Duration> {
+    let requested = tool_call
+        .args
+        .get("timeout_secs")
+        .and_then(|v| v.as_u64())
+        .map(Duration::from_secs);
+
+    if SELF_TIMED_TOOLS.contains(&tool_call.tool.as_str()) {
+        return default.map(|default| match requested {
+            Some(requested) => default.max(requested + SELF_TIMED_GRACE),
+            None => default,
+        });
+    }
+
+    requested.or(default)
+}
+
+/// Run a tool future, giving up once `timeout` elapses.
+/// Returns an error string (not an `Err`) on timeout so the LLM sees what happened.
+pub async fn run_with_timeout<F>(tool_name: &str, timeout: Option<Duration>, fut: F) -> Result<String>
+where
+    F: Future<Output = Result<String>>,
+{
+    let Some(timeout) = timeout else {
+        return fut.await;
+    };
+
+    match tokio::time::timeout(timeout, fut).await {
+        Ok(result) => result,
+        Err(_) => Ok(format!(
+            "❌ Tool '{}' timed out after {}",
+            tool_name,
+            format_timeout(timeout)
+        )),
+    }
+}
+
+fn format_timeout(timeout: Duration) -> String {
+    if timeout.subsec_millis() == 0 {
+        format!("{}s", timeout.as_secs())
+    } else {
+        format!("{}ms", timeout.as_millis())
+    }
+}
+
+/// Read-only tools whose results can be reused until something invalidates them.
+const CACHEABLE_TOOLS: &[&str] = &["read_file", "code_search", "todo_read", "directory_tree"];
+
+/// Tools that write a single file named by their `file_path`/`path` argument.
+const FILE_WRITING_TOOLS: &[&str] = &["write_file", "str_replace", "restore_backup"];
+
+/// Tools that save what they fetch to the file named by their `output_path` argument.
+const OUTPUT_FILE_TOOLS: &[&str] = &["http_request", "web_download_file"];
+
+/// Tools besides the cacheable ones that never write files. Every other tool
+/// not handled more precisely by [`ToolResultCache::invalidate_for`] may change
+/// arbitrary files (`shell`, `code_lint` with `fix`, `macro_playback`, ...), so
+/// every cached result is dropped after it runs.
+const FILE_PRESERVING_TOOLS: &[&str] = &[
+    "batch_read_files",
+    "read_image",
+    "text_search",
+    "todo_search",
+    "diff_files",
+    "code_complexity",
+    "env_check",
+    "shell_env",
+    "list_background_processes",
+    "get_background_process_output",
+    "process_output_wait",
+    "macro_list",
+    "final_output",
+];
+
+/// Whether `tool` leaves every file as it was. Browser tools only touch the
+/// browser, except for screenshots and downloads.
+fn preserves_files(tool: &str) -> bool {
+    CACHEABLE_TOOLS.contains(&tool)
+        || FILE_PRESERVING_TOOLS.contains(&tool)
+        || (tool.starts_with("web") && tool != "webdriver_screenshot")
+}
+
+/// Whether the call rebuilds the semantic index rather than searching, which
+/// makes earlier semantic search results stale.
+fn builds_semantic_index(tool_call: &ToolCall) -> bool {
+    tool_call.tool == "code_search"
+        && tool_call.args.get("action").and_then(|v| v.as_str()) == Some("build_semantic_index")
+}
+
+/// Maximum number of cached results; the least recently used entry is evicted first.
+pub const TOOL_CACHE_CAPACITY: usize = 100;
+
+struct CacheEntry {
+    result: String,
+    stored_at: Instant,
+    last_used: u64,
+    /// Path the result was read from, used for invalidation.
+    path: Option<PathBuf>,
+}
+
+/// Cache of results from idempotent read-only tools.
+///
+/// Entries are keyed by tool name and a hash of the arguments, expire after
+/// `ttl`, and are invalidated when a mutating tool touches the same path.
+pub struct ToolResultCache {
+    entries: HashMap<(String, u64), CacheEntry>,
+    ttl: Duration,
+    capacity: usize,
+    clock: u64,
+}
+
+impl ToolResultCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self::with_capacity(ttl, TOOL_CACHE_CAPACITY)
+    }
+
+    pub fn with_capacity(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+            capacity,
+            clock: 0,
+        }
+    }
+
+    pub fn is_cacheable(tool_name: &str) -> bool {
+        CACHEABLE_TOOLS.contains(&tool_name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Look up a previous result for this call, if it is still fresh.
+    pub fn get(&mut self, tool_call: &ToolCall, working_dir: Option<&str>) -> Option<String> {
+        let key = Self::key(tool_call, working_dir);
+        let entry = self.entries.get_mut(&key)?;
+        if entry.stored_at.elapsed() > self.ttl {
+            self.entries.remove(&key);
+            return None;
+        }
+
+        self.clock += 1;
+        entry.last_used = self.clock;
+        Some(entry.result.clone())
+    }
+
+    /// Store the result of a cacheable tool call. Failed calls are not cached.
+    pub fn insert(&mut self, tool_call: &ToolCall, working_dir: Option<&str>, result: &str) {
+        if !Self::is_cacheable(&tool_call.tool)
+            || result.starts_with("❌")
+            || builds_semantic_index(tool_call)
+        {
+            return;
+        }
+
+        let key = Self::key(tool_call, working_dir);
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            self.evict_least_recently_used();
+        }
+
+        self.clock += 1;
+        self.entries.insert(
+            key,
+            CacheEntry {
+                result: result.to_string(),
+                stored_at: Instant::now(),
+                last_used: self.clock,
+                path: path_arg(tool_call, working_dir),
+            },
+        );
+    }
+
+    /// Drop entries that a completed tool call may have made stale.
+    pub fn invalidate_for(&mut self, tool_call: &ToolCall, working_dir: Option<&str>) {
+        let tool = tool_call.tool.as_str();
+        if builds_semantic_index(tool_call) {
+            self.entries.retain(|(name, _), _| name != "code_search");
+        } else if FILE_WRITING_TOOLS.contains(&tool) {
+            match path_arg(tool_call, working_dir) {
+                Some(path) => self.invalidate_path(&path),
+                None => self.clear(),
+            }
+        } else if OUTPUT_FILE_TOOLS.contains(&tool) {
+            if let Some(path) = tool_call.args.get("output_path").and_then(|v| v.as_str()) {
+                self.invalidate_path(&resolve_path(path, working_dir));
+            }
+        } else if matches!(tool, "todo_write" | "todo_prioritize") {
+            self.entries.retain(|(name, _), _| name != "todo_read");
+        } else if !preserves_files(tool) {
+            self.clear();
+        }
+    }
+
+    /// Drop cached reads of `path` along with all search and tree results,
+    /// since writing a file can add matches or new entries to those.
+    pub fn invalidate_path(&mut self, path: &Path) {
+        let before = self.entries.len();
+        self.entries.retain(|(name, _), entry| {
+            name == "todo_read" || (name == "read_file" && entry.path.as_deref() != Some(path))
+        });
+        debug!(
+            "Invalidated {} cached tool result(s) for {}",
+            before - self.entries.len(),
+            path.display()
+        );
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        if let Some(key) = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone())
+        {
+            self.entries.remove(&key);
+        }
+    }
+
+    fn key(tool_call: &ToolCall, working_dir: Option<&str>) -> (String, u64) {
+        let mut hasher = DefaultHasher::new();
+        hash_canonical_json(&tool_call.args, &mut hasher);
+        working_dir.hash(&mut hasher);
+        (tool_call.tool.clone(), hasher.finish())
+    }
+}
+
+/// Hash `value` with object keys in sorted order, so the same arguments
+/// written in a different order share a cache entry.
+fn hash_canonical_json<H: Hasher>(value: &serde_json::Value, hasher: &mut H) {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            "{".hash(hasher);
+            for key in keys {
+                key.hash(hasher);
+                hash_canonical_json(&map[key], hasher);
+            }
+            "}".hash(hasher);
+        }
+        serde_json::Value::Array(items) => {
+            "[".hash(hasher);
+            for item in items {
+                hash_canonical_json(item, hasher);
+            }
+            "]".hash(hasher);
+        }
+        other => other.to_string().hash(hasher),
+    }
+}
+
+/// The absolute path a file tool operates on, if any.
+fn path_arg(tool_call: &ToolCall, working_dir: Option<&str>) -> Option<PathBuf> {
+    let path = tool_call
+        .args
+        .get("file_path")
+        .or_else(|| tool_call.args.get("path"))
+        .and_then(|v| v.as_str())?;
+    Some(resolve_path(path, working_dir))
+}
+
+/// `path` made absolute against `working_dir`, or the current directory.
+fn resolve_path(path: &str, working_dir: Option<&str>) -> PathBuf {
+    let path = PathBuf::from(shellexpand::tilde(path).as_ref());
+    if path.is_absolute() {
+        return path;
+    }
+
+    match working_dir.map(PathBuf::from).or_else(|| std::env::current_dir().ok()) {
+        Some(dir) => dir.join(path),
+        None => path,
+    }
+}
+
+/// When a tool call runs relative to the others in an
+/// [`execute_prioritized_batch`]. Lower values run first; calls with equal
+/// priority keep their order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ToolPriority(pub u8);
+
+impl ToolPriority {
+    /// Commands, edits and every other tool that may change something. These
+    /// keep the order the model emitted them in.
+    pub const EFFECT: ToolPriority = ToolPriority(0);
+    /// Reading files, which may have been generated or edited by the above
+    pub const READ: ToolPriority = ToolPriority(1);
+    /// Searching the code base
+    pub const SEARCH: ToolPriority = ToolPriority(2);
+    /// Runs after everything else, so the session ends last
+    pub const LAST: ToolPriority = ToolPriority(u8::MAX);
+
+    /// Priority of `tool`, from the kind of work it does.
+    pub fn for_tool(tool: &str) -> Self {
+        match tool {
+            "read_file" | "batch_read_files" | "directory_tree" | "diff_files" => Self::READ,
+            "code_search" | "text_search" | "todo_search" => Self::SEARCH,
+            "final_output" => Self::LAST,
+            _ => Self::EFFECT,
+        }
+    }
+}
+
+/// Tool calls waiting to run, highest priority (lowest [`ToolPriority`]) first.
+#[derive(Debug, Default)]
+pub struct ToolQueue {
+    heap: BinaryHeap<Reverse<(ToolPriority, usize)>>,
+    calls: HashMap<usize, ToolCall>,
+    pushed: usize,
+}
+
+impl ToolQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue `tool_call` with the priority of its tool.
+    pub fn push(&mut self, tool_call: ToolCall) {
+        let priority = ToolPriority::for_tool(&tool_call.tool);
+        self.push_with_priority(tool_call, priority);
+    }
+
+    pub fn push_with_priority(&mut self, tool_call: ToolCall, priority: ToolPriority) {
+        let seq = self.pushed;
+        self.pushed += 1;
+        self.heap.push(Reverse((priority, seq)));
+        self.calls.insert(seq, tool_call);
+    }
+
+    /// The next call to run: the highest priority, earliest queued first.
+    pub fn pop(&mut self) -> Option<ToolCall> {
+        let Reverse((_, seq)) = self.heap.pop()?;
+        self.calls.remove(&seq)
+    }
+
+    /// Every queued call, in the order they should run.
+    pub fn into_sorted_vec(mut self) -> Vec<ToolCall> {
+        std::iter::from_fn(|| self.pop()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
+
+impl FromIterator<ToolCall> for ToolQueue {
+    fn from_iter<I: IntoIterator<Item = ToolCall>>(calls: I) -> Self {
+        let mut queue = Self::new();
+        for call in calls {
+            queue.push(call);
+        }
+        queue
+    }
+}
+
+/// Run tool calls emitted together in priority order, so that e.g. a `shell`
+/// build finishes before a `read_file` of what it generates. Returns each call
+/// with its result, in the order they ran. A call that fails to dispatch gets
+/// a `❌` result and does not stop the others.
+///
+/// This is opt-in: the agent loop runs tool calls in the order the model
+/// emitted them.
+pub async fn execute_prioritized_batch<W: UiWriter>(
+    calls: Vec<ToolCall>,
+    ctx: &mut ToolContext<'_, W>,
+) -> Vec<(ToolCall, String)> {
+    let mut queue: ToolQueue = calls.into_iter().collect();
+    let mut results = Vec::with_capacity(queue.len());
+    while let Some(call) = queue.pop() {
+        let result = match crate::tool_dispatch::dispatch_tool(&call, ctx).await {
+            Ok(result) => result,
+            Err(e) => format!("❌ {}", e),
+        };
+        results.push((call, result));
+    }
+    results
+}
+
 /// Trait for tool executors.
 /// Each tool category implements this trait.
 pub trait ToolExecutor<W: UiWriter> {
@@ -52,3 +620,383 @@ pub trait ToolExecutor<W: UiWriter> {
     where
         W: 'a;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn slow_tool(delay: Duration) -> Result<String> {
+        tokio::time::sleep(delay).await;
+        Ok("✅ done".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_tool_times_out() {
+        let result = run_with_timeout(
+            "shell",
+            Some(Duration::from_millis(50)),
+            slow_tool(Duration::from_secs(5)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result, "❌ Tool 'shell' timed out after 50ms");
+    }
+
+    #[tokio::test]
+    async fn test_tool_completes_within_timeout() {
+        let result = run_with_timeout(
+            "shell",
+            Some(Duration::from_secs(30)),
+            slow_tool(Duration::from_millis(1)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result, "✅ done");
+    }
+
+    #[test]
+    fn test_format_timeout() {
+        assert_eq!(format_timeout(Duration::from_secs(30)), "30s");
+        assert_eq!(format_timeout(Duration::from_millis(1500)), "1500ms");
+    }
+
+    #[test]
+    fn test_timeout_secs_arg_overrides_default() {
+        let default = Some(Duration::from_secs(30));
+
+        let call = ToolCall {
+            tool: "shell".to_string(),
+            args: json!({"command": "sleep 100", "timeout_secs": 120}),
+        };
+        assert_eq!(resolve_tool_timeout(&call, default), Some(Duration::from_secs(120)));
+
+        let call = ToolCall {
+            tool: "shell".to_string(),
+            args: json!({"command": "ls"}),
+        };
+        assert_eq!(resolve_tool_timeout(&call, default), default);
+        assert_eq!(resolve_tool_timeout(&call, None), None);
+    }
+
+    fn call(tool: &str, args: serde_json::Value) -> ToolCall {
+        ToolCall {
+            tool: tool.to_string(),
+            args,
+        }
+    }
+
+    #[test]
+    fn test_cache_hit_and_expiry() {
+        let read = call("read_file", json!({"file_path": "/tmp/a.rs"}));
+
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get(&read, None), None);
+        cache.insert(&read, None, "fn a() {}");
+        assert_eq!(cache.get(&read, None).as_deref(), Some("fn a() {}"));
+        // Same args from a different working dir are a different call
+        assert_eq!(cache.get(&read, Some("/elsewhere")), None);
+
+        let mut cache = ToolResultCache::new(Duration::ZERO);
+        cache.insert(&read, None, "fn a() {}");
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.get(&read, None), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_skips_errors_and_mutating_tools() {
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+        cache.insert(&call("read_file", json!({"file_path": "/missing"})), None, "❌ Failed to read");
+        cache.insert(&call("shell", json!({"command": "ls"})), None, "a.rs");
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let a = call("read_file", json!({"file_path": "/tmp/a"}));
+        let b = call("read_file", json!({"file_path": "/tmp/b"}));
+        let c = call("read_file", json!({"file_path": "/tmp/c"}));
+
+        let mut cache = ToolResultCache::with_capacity(Duration::from_secs(60), 2);
+        cache.insert(&a, None, "a");
+        cache.insert(&b, None, "b");
+        assert!(cache.get(&a, None).is_some());
+        cache.insert(&c, None, "c");
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&a, None).is_some());
+        assert!(cache.get(&b, None).is_none());
+        assert!(cache.get(&c, None).is_some());
+    }
+
+    #[test]
+    fn test_cache_invalidation() {
+        let read_a = call("read_file", json!({"file_path": "src/a.rs"}));
+        let read_b = call("read_file", json!({"file_path": "/repo/src/b.rs"}));
+        let search = call("code_search", json!({"searches": []}));
+        let todo = call("todo_read", json!({}));
+
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+        for tool_call in [&read_a, &read_b, &search, &todo] {
+            cache.insert(tool_call, Some("/repo"), "cached");
+        }
+
+        // Relative and absolute spellings of the same file both match
+        cache.invalidate_for(
+            &call("str_replace", json!({"file_path": "/repo/src/a.rs", "diff": ""})),
+            Some("/repo"),
+        );
+        assert!(cache.get(&read_a, Some("/repo")).is_none());
+        assert!(cache.get(&search, Some("/repo")).is_none());
+        assert!(cache.get(&read_b, Some("/repo")).is_some());
+        assert!(cache.get(&todo, Some("/repo")).is_some());
+
+        cache.invalidate_for(&call("todo_write", json!({"content": ""})), Some("/repo"));
+        assert!(cache.get(&todo, Some("/repo")).is_none());
+
+        cache.invalidate_for(&call("shell", json!({"command": "rm -rf src"})), Some("/repo"));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_and_file_writing_tools_clear_the_cache() {
+        let read = call("read_file", json!({"file_path": "src/a.rs"}));
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+
+        for tool in ["text_search", "batch_read_files", "webdriver_click", "final_output"] {
+            cache.insert(&read, None, "cached");
+            cache.invalidate_for(&call(tool, json!({})), None);
+            assert_eq!(cache.len(), 1, "{} dropped the cache", tool);
+        }
+
+        for tool in ["code_lint", "run_tests", "macro_playback", "webdriver_screenshot", "new_tool"] {
+            cache.insert(&read, None, "cached");
+            cache.invalidate_for(&call(tool, json!({})), None);
+            assert!(cache.is_empty(), "{} kept the cache", tool);
+        }
+    }
+
+    #[test]
+    fn test_downloads_invalidate_reads_and_trees() {
+        let read = call("read_file", json!({"file_path": "data.json"}));
+        let other = call("read_file", json!({"file_path": "other.json"}));
+        let tree = call("directory_tree", json!({"path": "."}));
+
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+        for tool_call in [&read, &other, &tree] {
+            cache.insert(tool_call, Some("/repo"), "cached");
+        }
+
+        // Without output_path nothing is written
+        cache.invalidate_for(&call("http_request", json!({"url": "https://x"})), Some("/repo"));
+        assert_eq!(cache.len(), 3);
+
+        cache.invalidate_for(
+            &call("http_request", json!({"url": "https://x", "output_path": "data.json"})),
+            Some("/repo"),
+        );
+        assert!(cache.get(&read, Some("/repo")).is_none());
+        assert!(cache.get(&tree, Some("/repo")).is_none());
+        assert!(cache.get(&other, Some("/repo")).is_some());
+
+        cache.invalidate_for(
+            &call("web_download_file", json!({"url": "https://x", "output_path": "/repo/other.json"})),
+            Some("/repo"),
+        );
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_key_ignores_argument_order() {
+        let mut first = serde_json::Map::new();
+        first.insert("file_path".to_string(), json!("a.rs"));
+        first.insert("start".to_string(), json!(10));
+        let mut second = serde_json::Map::new();
+        second.insert("start".to_string(), json!(10));
+        second.insert("file_path".to_string(), json!("a.rs"));
+
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+        cache.insert(&call("read_file", serde_json::Value::Object(first)), None, "cached");
+        let reordered = call("read_file", serde_json::Value::Object(second));
+        assert_eq!(cache.get(&reordered, None).as_deref(), Some("cached"));
+        assert!(cache.get(&call("read_file", json!({"file_path": "a.rs", "start": 11})), None).is_none());
+    }
+
+    #[test]
+    fn test_building_semantic_index_drops_code_search_results() {
+        let semantic = call("code_search", json!({"mode": "semantic", "query": "retry"}));
+        let build = call("code_search", json!({"action": "build_semantic_index"}));
+        let read = call("read_file", json!({"file_path": "src/a.rs"}));
+
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+        cache.insert(&semantic, None, "❌ No semantic index");
+        cache.insert(&semantic, None, "no index yet, fell back");
+        cache.insert(&read, None, "cached");
+        cache.insert(&build, None, "✅ Indexed 3 definitions");
+        assert!(cache.get(&build, None).is_none());
+
+        cache.invalidate_for(&build, None);
+        assert!(cache.get(&semantic, None).is_none());
+        assert!(cache.get(&read, None).is_some());
+    }
+
+    #[test]
+    fn test_self_timed_tool_keeps_default_timeout() {
+        let call = ToolCall {
+            tool: "web_wait_for_element".to_string(),
+            args: json!({"selector": "#app", "timeout_secs": 120}),
+        };
+        assert_eq!(resolve_tool_timeout(&call, None), None);
+        assert_eq!(
+            resolve_tool_timeout(&call, Some(Duration::from_secs(30))),
+            Some(Duration::from_secs(125))
+        );
+
+        // A shorter own timeout doesn't shorten the default
+        let call = ToolCall {
+            tool: "process_output_wait".to_string(),
+            args: json!({"name": "server", "pattern": "ready", "timeout_secs": 1}),
+        };
+        assert_eq!(
+            resolve_tool_timeout(&call, Some(Duration::from_secs(30))),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn test_tool_allowlist_and_blocklist() {
+        let tools = |names: &[&str]| Some(names.iter().map(|n| n.to_string()).collect());
+        let mut agent = g3_config::Config::default().agent;
+        assert!(tool_permitted("shell", &agent));
+
+        agent.tool_blocklist = tools(&["shell", "write_file"]);
+        assert!(!tool_permitted("shell", &agent));
+        assert!(tool_permitted("read_file", &agent));
+
+        agent.tool_allowlist = tools(&["read_file", "shell"]);
+        assert!(tool_permitted("read_file", &agent));
+        assert!(!tool_permitted("code_search", &agent));
+        // The blocklist wins over the allowlist
+        assert!(!tool_permitted("shell", &agent));
+
+        // final_output is always allowed, even when listed
+        agent.tool_allowlist = tools(&[]);
+        agent.tool_blocklist = tools(&["final_output"]);
+        assert!(tool_permitted("final_output", &agent));
+        assert!(!tool_permitted("read_file", &agent));
+    }
+
+    fn validator() -> ToolSchemaValidator {
+        ToolSchemaValidator::new(&crate::tool_definitions::create_tool_definitions(
+            crate::tool_definitions::ToolConfig::new(true, true, false),
+        ))
+    }
+
+    #[test]
+    fn test_schema_validation_accepts_valid_args() {
+        let validator = validator();
+        // Every tool schema compiles
+        let tools = crate::tool_definitions::create_tool_definitions(
+            crate::tool_definitions::ToolConfig::new(true, true, false),
+        );
+        assert_eq!(validator.validators.len(), tools.len());
+        assert!(validator.validate(&call("read_file", json!({"file_path": "src/lib.rs"}))).is_ok());
+        assert!(validator
+            .validate(&call("shell", json!({"command": "ls", "timeout_secs": 5})))
+            .is_ok());
+        // Tools without a known schema are not checked
+        assert!(validator.validate(&call("no_such_tool", json!(42))).is_ok());
+    }
+
+    #[test]
+    fn test_schema_validation_rejects_invalid_args() {
+        let validator = validator();
+
+        let err = validator.validate(&call("read_file", json!({}))).unwrap_err();
+        assert!(err.contains("\"file_path\" is a required property"), "{}", err);
+
+        let err = validator
+            .validate(&call("read_file", json!({"file_path": 7})))
+            .unwrap_err();
+        assert!(err.contains("is not of type \"string\""), "{}", err);
+        assert!(err.contains("/file_path"), "{}", err);
+
+        let err = validator
+            .validate(&call("process_output_wait", json!({"name": "server", "pattern": "ready", "stream": "stdin"})))
+            .unwrap_err();
+        assert!(err.contains("/stream"), "{}", err);
+
+        let err = validator
+            .validate(&call("dependency_graph", json!({"path": ".", "format": "svg"})))
+            .unwrap_err();
+        assert!(err.contains("/format"), "{}", err);
+    }
+
+    #[test]
+    fn test_is_error_result() {
+        assert!(is_error_result("❌ Missing command argument"));
+        assert!(is_error_result("[exit: 2]\n❌ Command failed: oops"));
+        assert!(!is_error_result("[exit: 0]\nok"));
+        assert!(!is_error_result("✅ Wrote 3 lines"));
+    }
+
+    #[test]
+    fn test_tool_priorities() {
+        assert_eq!(ToolPriority::for_tool("shell"), ToolPriority::EFFECT);
+        assert_eq!(ToolPriority::for_tool("str_replace"), ToolPriority::EFFECT);
+        assert_eq!(ToolPriority::for_tool("webdriver_click"), ToolPriority::EFFECT);
+        assert_eq!(ToolPriority::for_tool("read_file"), ToolPriority::READ);
+        assert_eq!(ToolPriority::for_tool("code_search"), ToolPriority::SEARCH);
+        assert!(ToolPriority::EFFECT < ToolPriority::READ);
+        assert!(ToolPriority::SEARCH < ToolPriority::for_tool("final_output"));
+    }
+
+    #[test]
+    fn test_tool_queue_orders_by_priority_then_arrival() {
+        let mut queue: ToolQueue = vec![
+            call("final_output", json!({})),
+            call("read_file", json!({"file_path": "a"})),
+            call("code_search", json!({})),
+            call("shell", json!({"command": "make"})),
+            call("read_file", json!({"file_path": "b"})),
+            call("shell", json!({"command": "make test"})),
+        ]
+        .into_iter()
+        .collect();
+        queue.push_with_priority(call("todo_read", json!({})), ToolPriority(0));
+        assert_eq!(queue.len(), 7);
+
+        let shells: Vec<_> = (0..3).map(|_| queue.pop().unwrap().args).collect();
+        assert_eq!(
+            shells,
+            vec![json!({"command": "make"}), json!({"command": "make test"}), json!({})]
+        );
+        assert_eq!(queue.pop().unwrap().args["file_path"], "a");
+        assert_eq!(queue.pop().unwrap().args["file_path"], "b");
+        assert_eq!(queue.pop().unwrap().tool, "code_search");
+        assert_eq!(queue.pop().unwrap().tool, "final_output");
+        assert!(queue.is_empty());
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn test_queue_keeps_edits_and_commands_in_order() {
+        let calls = vec![
+            call("read_file", json!({"file_path": "src/lib.rs"})),
+            call("str_replace", json!({"file_path": "src/lib.rs"})),
+            call("shell", json!({"command": "cargo test"})),
+            call("write_file", json!({"file_path": "NOTES.md"})),
+        ];
+        let order: Vec<String> = calls
+            .into_iter()
+            .collect::<ToolQueue>()
+            .into_sorted_vec()
+            .into_iter()
+            .map(|c| c.tool)
+            .collect();
+        assert_eq!(order, ["str_replace", "shell", "write_file", "read_file"]);
+    }
+}
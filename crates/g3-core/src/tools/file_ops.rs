@@ -1,11 +1,15 @@
-//! File operation tools: read_file, write_file, str_replace, read_image.
+//! File operation tools: read_file, write_file, str_replace, glob_replace, diff_files,
+//! read_image, directory_tree.
 
 use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use tracing::debug;
 
+use crate::ssh_session::SshConnectionConfig;
 use crate::ui_writer::UiWriter;
 use crate::utils::resolve_path_with_unicode_fallback;
-use crate::utils::apply_unified_diff_to_string;
+use crate::utils::{apply_unified_diff_detailed, DiffApplyOptions};
 use crate::ToolCall;
 
 use super::executor::ToolContext;
@@ -13,7 +17,7 @@ use super::executor::ToolContext;
 /// Execute the `read_file` tool.
 pub async fn execute_read_file<W: UiWriter>(
     tool_call: &ToolCall,
-    _ctx: &ToolContext<'_, W>,
+    ctx: &ToolContext<'_, W>,
 ) -> Result<String> {
     debug!("Processing read_file tool call");
     
@@ -22,12 +26,6 @@ pub async fn execute_read_file<W: UiWriter>(
         None => return Ok("❌ Missing file_path argument".to_string()),
     };
 
-    // Expand tilde (~) to home directory
-    let expanded_path = shellexpand::tilde(file_path);
-    // Try to resolve with Unicode space fallback (macOS uses U+202F in screenshot names)
-    let resolved_path = resolve_path_with_unicode_fallback(expanded_path.as_ref());
-    let path_str = resolved_path.as_ref();
-
     // Extract optional start and end positions
     let start_char = tool_call
         .args
@@ -39,72 +37,339 @@ pub async fn execute_read_file<W: UiWriter>(
         .get("end")
         .and_then(|v| v.as_u64())
         .map(|n| n as usize);
+    let annotate = tool_call
+        .args
+        .get("annotate_lines")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(ctx.config.agent.read_file_annotate_lines);
+    let force = tool_call
+        .args
+        .get("force")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let max_bytes = if force {
+        None
+    } else {
+        Some(
+            tool_call
+                .args
+                .get("max_bytes")
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize)
+                .unwrap_or(ctx.config.agent.max_file_read_bytes),
+        )
+    };
+    let summarize = tool_call
+        .args
+        .get("summarize")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
 
-    debug!(
-        "Reading file: {}, start={:?}, end={:?}",
-        path_str, start_char, end_char
+    let ssh_host = tool_call.args.get("ssh_host").and_then(|v| v.as_str());
+
+    let content = if let Some(host) = ssh_host {
+        debug!("Reading remote file: {}:{}", host, file_path);
+        match read_remote_file(ctx, host, file_path).await {
+            Ok(content) => content,
+            Err(msg) => return Ok(msg),
+        }
+    } else {
+        // Expand tilde (~) and resolve relative paths against the working directory
+        let expanded_path = ctx.resolve_path(file_path).to_string_lossy().into_owned();
+        // Try to resolve with Unicode space fallback (macOS uses U+202F in screenshot names)
+        let resolved_path = resolve_path_with_unicode_fallback(&expanded_path);
+        let path_str = resolved_path.as_ref();
+
+        debug!(
+            "Reading file: {}, start={:?}, end={:?}",
+            path_str, start_char, end_char
+        );
+
+        match std::fs::read_to_string(path_str) {
+            Ok(content) => content,
+            Err(e) => return Ok(format!("❌ Failed to read file '{}': {}", path_str, e)),
+        }
+    };
+
+    if let Some(max) = max_bytes {
+        if summarize && start_char.is_none() && end_char.is_none() && content.len() > max {
+            return Ok(summarize_file_content(&content));
+        }
+    }
+
+    Ok(format_file_content(
+        &content, start_char, end_char, annotate, max_bytes,
+    ))
+}
+
+/// Lines shown from each end of a file by read_file's `summarize` mode.
+const SUMMARY_EDGE_LINES: usize = 10;
+
+/// Overview of a file too large to return: size, line count, and its first
+/// and last [`SUMMARY_EDGE_LINES`] lines, numbered.
+fn summarize_file_content(content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let total_lines = lines.len();
+    let mut out = format!(
+        "📄 File summary ({} bytes, {} lines; too large to return in full):\n",
+        content.len(),
+        total_lines
     );
 
-    match std::fs::read_to_string(path_str) {
-        Ok(content) => {
-            // Validate and apply range if specified
-            let start = start_char.unwrap_or(0);
-            let end = end_char.unwrap_or(content.len());
+    let head_end = total_lines.min(SUMMARY_EDGE_LINES);
+    out.push_str(&format!("\nFirst {} lines:\n", head_end));
+    out.push_str(&annotate_lines(&lines[..head_end].join("\n"), 1));
 
-            // Validation
-            if start > content.len() {
-                return Ok(format!(
-                    "❌ Start position {} exceeds file length {}",
-                    start,
-                    content.len()
-                ));
-            }
-            if end > content.len() {
-                return Ok(format!(
-                    "❌ End position {} exceeds file length {}",
-                    end,
-                    content.len()
-                ));
+    let tail_start = total_lines.saturating_sub(SUMMARY_EDGE_LINES).max(head_end);
+    if tail_start < total_lines {
+        if tail_start > head_end {
+            out.push_str(&format!("\n... {} lines omitted ...\n", tail_start - head_end));
+        }
+        out.push_str(&format!("\nLast {} lines:\n", total_lines - tail_start));
+        out.push_str(&annotate_lines(&lines[tail_start..].join("\n"), tail_start + 1));
+    }
+
+    out.push_str(
+        "\nUse start/end range args to read specific sections, or force: true to read the whole file.",
+    );
+    out
+}
+
+/// Read `path` on `host` (`[user@]host[:port]`) over SFTP, using the
+/// session's cached connection. Errors are returned as tool messages.
+async fn read_remote_file<W: UiWriter>(
+    ctx: &ToolContext<'_, W>,
+    host: &str,
+    path: &str,
+) -> std::result::Result<String, String> {
+    let conn = SshConnectionConfig::new(host, &ctx.config.ssh)
+        .map_err(|e| format!("❌ Invalid ssh_host: {}", e))?;
+    let sessions = ctx.ssh_sessions.clone();
+    let remote_path = path.to_string();
+    let read = tokio::task::spawn_blocking(move || sessions.read_file(&conn, &remote_path)).await;
+    match read {
+        Ok(Ok(content)) => Ok(content),
+        Ok(Err(e)) => Err(format!(
+            "❌ Failed to read remote file '{}' on {}: {:#}",
+            path, host, e
+        )),
+        Err(e) => Err(format!("❌ Failed to read remote file '{}': {}", path, e)),
+    }
+}
+
+/// Apply read_file's `start`/`end` range, `max_bytes` limit and line
+/// annotation to `content`.
+fn format_file_content(
+    content: &str,
+    start_char: Option<usize>,
+    end_char: Option<usize>,
+    annotate: bool,
+    max_bytes: Option<usize>,
+) -> String {
+    // Validate and apply range if specified
+    let start = start_char.unwrap_or(0);
+    let end = end_char.unwrap_or(content.len());
+
+    // Validation
+    if start > content.len() {
+        return format!(
+            "❌ Start position {} exceeds file length {}",
+            start,
+            content.len()
+        );
+    }
+    if end > content.len() {
+        return format!(
+            "❌ End position {} exceeds file length {}",
+            end,
+            content.len()
+        );
+    }
+    if start > end {
+        return format!(
+            "❌ Start position {} is greater than end position {}",
+            start, end
+        );
+    }
+
+    // Extract the requested portion, ensuring we're at char boundaries
+    let start_boundary = if start == 0 {
+        0
+    } else {
+        content
+            .char_indices()
+            .find(|(i, _)| *i >= start)
+            .map(|(i, _)| i)
+            .unwrap_or(start)
+    };
+    let end_boundary = content
+        .char_indices()
+        .find(|(i, _)| *i >= end)
+        .map(|(i, _)| i)
+        .unwrap_or(content.len());
+
+    let mut partial_content = &content[start_boundary..end_boundary];
+    let mut truncated_bytes = 0;
+    if let Some(max) = max_bytes {
+        if partial_content.len() > max {
+            let mut cut = max;
+            while !partial_content.is_char_boundary(cut) {
+                cut -= 1;
             }
-            if start > end {
-                return Ok(format!(
-                    "❌ Start position {} is greater than end position {}",
-                    start, end
-                ));
+            truncated_bytes = partial_content.len() - cut;
+            partial_content = &partial_content[..cut];
+        }
+    }
+    let line_count = partial_content.lines().count();
+    let total_lines = content.lines().count();
+
+    let mut body = if annotate {
+        annotate_lines(partial_content, line_number_at(content, start_boundary))
+    } else {
+        partial_content.to_string()
+    };
+    if truncated_bytes > 0 {
+        body.push_str(&format!(
+            "\n... [truncated: {} bytes remaining, use start/end range args to read specific sections]",
+            truncated_bytes
+        ));
+    }
+
+    // Format output with range info if partial
+    if start_char.is_some() || end_char.is_some() {
+        format!(
+            "📄 File content (chars {}-{}, {} lines of {} total):\n{}",
+            start_boundary, end_boundary, line_count, total_lines, body
+        )
+    } else {
+        format!("📄 File content ({} lines):\n{}", line_count, body)
+    }
+}
+
+/// 1-based number of the line containing byte offset `pos` of `content`.
+pub fn line_number_at(content: &str, pos: usize) -> usize {
+    content[..pos].matches('\n').count() + 1
+}
+
+/// Prefix each line of `text` with its line number, counting from `first_line`,
+/// right-aligned to 5 characters: `"    1: first line\n"`.
+pub fn annotate_lines(text: &str, first_line: usize) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 8);
+    for (i, line) in text.lines().enumerate() {
+        out.push_str(&format!("{:>5}: {}\n", first_line + i, line));
+    }
+    out
+}
+
+/// Contents of one file read by `batch_read_files`, or why it couldn't be read.
+type BatchReadResult = std::result::Result<String, String>;
+
+/// Read every path concurrently, returning results in the order given.
+pub async fn read_files_concurrently(paths: &[PathBuf]) -> Vec<BatchReadResult> {
+    let reads = paths.iter().map(|path| async move {
+        tokio::fs::read_to_string(path).await.map_err(|e| e.to_string())
+    });
+    futures_util::future::join_all(reads).await
+}
+
+/// Render `batch_read_files` results under `=== path ===` headers. Each file
+/// gets at most `max_bytes_per_file`, and all files together at most
+/// `max_total_bytes` of content.
+fn format_batch_read(
+    paths: &[&str],
+    results: &[BatchReadResult],
+    max_bytes_per_file: usize,
+    max_total_bytes: usize,
+    line_numbers: bool,
+) -> String {
+    let read = results.iter().filter(|r| r.is_ok()).count();
+    let mut out = format!("📚 Read {} of {} files\n", read, paths.len());
+    let mut remaining = max_total_bytes;
+
+    for (path, result) in paths.iter().zip(results) {
+        out.push_str(&format!("\n=== {} ===\n", path));
+        let content = match result {
+            Ok(content) => content,
+            Err(e) => {
+                out.push_str(&format!("❌ Failed to read: {}\n", e));
+                continue;
             }
+        };
+        if remaining == 0 {
+            out.push_str(&format!(
+                "[skipped: total output limit of {} bytes reached, read this file separately]\n",
+                max_total_bytes
+            ));
+            continue;
+        }
 
-            // Extract the requested portion, ensuring we're at char boundaries
-            let start_boundary = if start == 0 {
-                0
-            } else {
-                content
-                    .char_indices()
-                    .find(|(i, _)| *i >= start)
-                    .map(|(i, _)| i)
-                    .unwrap_or(start)
-            };
-            let end_boundary = content
-                .char_indices()
-                .find(|(i, _)| *i >= end)
-                .map(|(i, _)| i)
-                .unwrap_or(content.len());
-
-            let partial_content = &content[start_boundary..end_boundary];
-            let line_count = partial_content.lines().count();
-            let total_lines = content.lines().count();
-
-            // Format output with range info if partial
-            if start_char.is_some() || end_char.is_some() {
-                Ok(format!(
-                    "📄 File content (chars {}-{}, {} lines of {} total):\n{}",
-                    start_boundary, end_boundary, line_count, total_lines, partial_content
-                ))
-            } else {
-                Ok(format!("📄 File content ({} lines):\n{}", line_count, content))
+        let limit = max_bytes_per_file.min(remaining);
+        let mut cut = content.len().min(limit);
+        while !content.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        remaining -= cut;
+        let shown = &content[..cut];
+        if line_numbers {
+            out.push_str(&annotate_lines(shown, 1));
+        } else {
+            out.push_str(shown);
+            if !shown.ends_with('\n') {
+                out.push('\n');
             }
         }
-        Err(e) => Ok(format!("❌ Failed to read file '{}': {}", path_str, e)),
+        if cut < content.len() {
+            out.push_str(&format!(
+                "... [truncated: {} bytes remaining, use read_file with start/end to read the rest]\n",
+                content.len() - cut
+            ));
+        }
     }
+    out
+}
+
+/// Execute the `batch_read_files` tool.
+pub async fn execute_batch_read_files<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing batch_read_files tool call");
+
+    let paths: Vec<&str> = match tool_call.args.get("paths").and_then(|v| v.as_array()) {
+        Some(paths) => paths.iter().filter_map(|p| p.as_str()).collect(),
+        None => return Ok("❌ Missing paths argument".to_string()),
+    };
+    if paths.is_empty() {
+        return Ok("❌ paths must list at least one file".to_string());
+    }
+    let max_bytes_per_file = tool_call
+        .args
+        .get("max_bytes_per_file")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize)
+        .unwrap_or(ctx.config.agent.max_file_read_bytes);
+    let line_numbers = tool_call
+        .args
+        .get("include_line_numbers")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let resolved: Vec<PathBuf> = paths
+        .iter()
+        .map(|p| {
+            let expanded = ctx.resolve_path(p).to_string_lossy().into_owned();
+            PathBuf::from(resolve_path_with_unicode_fallback(&expanded).as_ref())
+        })
+        .collect();
+    let results = read_files_concurrently(&resolved).await;
+
+    Ok(format_batch_read(
+        &paths,
+        &results,
+        max_bytes_per_file,
+        ctx.config.agent.max_file_read_bytes,
+        line_numbers,
+    ))
 }
 
 /// Execute the `read_image` tool.
@@ -138,10 +403,10 @@ pub async fn execute_read_image<W: UiWriter>(
     println!("└─\n");
 
     for path_str in &paths {
-        // Expand tilde (~) to home directory
-        let expanded_path = shellexpand::tilde(path_str);
+        // Expand tilde (~) and resolve relative paths against the working directory
+        let expanded_path = ctx.resolve_path(path_str).to_string_lossy().into_owned();
         // Try to resolve with Unicode space fallback (macOS uses U+202F in screenshot names)
-        let resolved_path = resolve_path_with_unicode_fallback(expanded_path.as_ref());
+        let resolved_path = resolve_path_with_unicode_fallback(&expanded_path);
         let path = std::path::Path::new(resolved_path.as_ref());
 
         // Check file exists
@@ -227,7 +492,7 @@ pub async fn execute_read_image<W: UiWriter>(
 /// Execute the `write_file` tool.
 pub async fn execute_write_file<W: UiWriter>(
     tool_call: &ToolCall,
-    _ctx: &ToolContext<'_, W>,
+    ctx: &ToolContext<'_, W>,
 ) -> Result<String> {
     debug!("Processing write_file tool call");
     debug!("Raw tool_call.args: {:?}", tool_call.args);
@@ -242,14 +507,45 @@ pub async fn execute_write_file<W: UiWriter>(
     );
 
     if let (Some(path), Some(content)) = (path_str, content_str) {
-        // Expand tilde (~) to home directory
-        let expanded_path = shellexpand::tilde(path);
-        let path = expanded_path.as_ref();
+        // Expand tilde (~) and resolve relative paths against the working directory
+        let resolved_path = ctx.resolve_path(path);
+        let path = resolved_path.to_string_lossy();
+        let atomic = tool_call
+            .args
+            .get("atomic")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let overwrite = tool_call
+            .args
+            .get("overwrite")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let create_dirs = tool_call
+            .args
+            .get("create_dirs")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
 
-        debug!("Writing to file: {}", path);
+        debug!(
+            "Writing to file: {} (atomic={}, overwrite={}, create_dirs={})",
+            path, atomic, overwrite, create_dirs
+        );
+
+        if !overwrite && resolved_path.exists() {
+            return Ok(format!(
+                "❌ File '{}' already exists and overwrite is false",
+                path
+            ));
+        }
 
         // Create parent directories if they don't exist
-        if let Some(parent) = std::path::Path::new(path).parent() {
+        if let Some(parent) = resolved_path.parent().filter(|p| !p.exists()) {
+            if !create_dirs {
+                return Ok(format!(
+                    "❌ Directory '{}' does not exist (use create_dirs: true to create it)",
+                    parent.display()
+                ));
+            }
             if let Err(e) = std::fs::create_dir_all(parent) {
                 return Ok(format!(
                     "❌ Failed to create parent directories for '{}': {}",
@@ -258,7 +554,12 @@ pub async fn execute_write_file<W: UiWriter>(
             }
         }
 
-        match std::fs::write(path, content) {
+        let written = if atomic {
+            write_atomic(&resolved_path, content.as_bytes())
+        } else {
+            std::fs::write(&resolved_path, content)
+        };
+        match written {
             Ok(()) => {
                 let line_count = content.lines().count();
                 let char_count = content.len();
@@ -287,7 +588,7 @@ pub async fn execute_write_file<W: UiWriter>(
 /// Execute the `str_replace` tool.
 pub async fn execute_str_replace<W: UiWriter>(
     tool_call: &ToolCall,
-    _ctx: &ToolContext<'_, W>,
+    ctx: &ToolContext<'_, W>,
 ) -> Result<String> {
     debug!("Processing str_replace tool call");
 
@@ -297,10 +598,7 @@ pub async fn execute_str_replace<W: UiWriter>(
     };
 
     let file_path = match args_obj.get("file_path").and_then(|v| v.as_str()) {
-        Some(path) => {
-            let expanded_path = shellexpand::tilde(path);
-            expanded_path.into_owned()
-        }
+        Some(path) => ctx.resolve_path(path).to_string_lossy().into_owned(),
         None => return Ok("❌ Missing or invalid file_path argument".to_string()),
     };
 
@@ -318,10 +616,26 @@ pub async fn execute_str_replace<W: UiWriter>(
         .get("end")
         .and_then(|v| v.as_u64())
         .map(|n| n as usize);
+    let fuzz_lines = tool_call
+        .args
+        .get("fuzz_lines")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize)
+        .unwrap_or(0);
+    let normalize_line_endings = tool_call
+        .args
+        .get("normalize_line_endings")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    let create_backup = tool_call
+        .args
+        .get("create_backup")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
 
     debug!(
-        "str_replace: path={}, start={:?}, end={:?}",
-        file_path, start_char, end_char
+        "str_replace: path={}, start={:?}, end={:?}, fuzz_lines={}, create_backup={}",
+        file_path, start_char, end_char, fuzz_lines, create_backup
     );
 
     // Read the existing file
@@ -330,19 +644,424 @@ pub async fn execute_str_replace<W: UiWriter>(
         Err(e) => return Ok(format!("❌ Failed to read file '{}': {}", file_path, e)),
     };
 
-    // Apply unified diff to content
-    let result = match apply_unified_diff_to_string(&file_content, diff, start_char, end_char) {
-        Ok(r) => r,
-        Err(e) => return Ok(format!("❌ {}", e)),
+    // Back up the original first; the backup stays even if the diff fails
+    if create_backup {
+        if let Err(e) = std::fs::copy(&file_path, backup_path(Path::new(&file_path))) {
+            return Ok(format!("❌ Failed to back up '{}': {}", file_path, e));
+        }
+    }
+
+    // Apply unified diff to content, telling the agent which hunk failed and why
+    let options = DiffApplyOptions {
+        fuzz_lines,
+        normalize_line_endings,
     };
+    let result =
+        match apply_unified_diff_detailed(&file_content, diff, start_char, end_char, &options) {
+            Ok(r) => r,
+            Err(e) => return Ok(format!("❌ {}", e)),
+        };
 
     // Write the result back to the file
     match std::fs::write(&file_path, &result) {
+        Ok(()) if create_backup => Ok(format!(
+            "✅ applied unified diff (backup at {})",
+            backup_path(Path::new(&file_path)).display()
+        )),
         Ok(()) => Ok("✅ applied unified diff".to_string()),
         Err(e) => Ok(format!("❌ Failed to write to file '{}': {}", file_path, e)),
     }
 }
 
+/// Where `str_replace` with `create_backup` keeps the original of `path`.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_os_string();
+    backup.push(".g3.bak");
+    PathBuf::from(backup)
+}
+
+/// Execute the `restore_backup` tool.
+pub async fn execute_restore_backup<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing restore_backup tool call");
+
+    let file_path = match tool_call.args.get("file_path").and_then(|v| v.as_str()) {
+        Some(path) => ctx.resolve_path(path),
+        None => return Ok("❌ Missing or invalid file_path argument".to_string()),
+    };
+    let backup = backup_path(&file_path);
+    if !backup.is_file() {
+        return Ok(format!("❌ No backup found at {}", backup.display()));
+    }
+
+    if let Err(e) = std::fs::copy(&backup, &file_path) {
+        return Ok(format!("❌ Failed to restore '{}': {}", file_path.display(), e));
+    }
+    match std::fs::remove_file(&backup) {
+        Ok(()) => Ok(format!("✅ Restored {} from backup", file_path.display())),
+        Err(e) => Ok(format!(
+            "✅ Restored {} from backup, but could not delete {}: {}",
+            file_path.display(),
+            backup.display(),
+            e
+        )),
+    }
+}
+
+/// Characters that make a path component a glob rather than a literal name.
+const GLOB_META_CHARS: &[char] = &['*', '?', '[', '{'];
+
+/// Split `pattern` into the directory to walk and the glob to match below it,
+/// e.g. `src/**/*.rs` into `src` and `/**/*.rs`. The glob is anchored to that
+/// directory, so `*.rs` only matches files directly inside it.
+pub fn split_glob_root(pattern: &str) -> (PathBuf, String) {
+    let components: Vec<&str> = pattern.split('/').collect();
+    let literal = components
+        .iter()
+        .take_while(|c| !c.contains(GLOB_META_CHARS))
+        .count();
+    // A pattern without wildcards names a single file
+    let literal = if literal == components.len() {
+        literal.saturating_sub(1)
+    } else {
+        literal
+    };
+    let root = components[..literal].join("/");
+    let root = if root.is_empty() && pattern.starts_with('/') {
+        PathBuf::from("/")
+    } else {
+        PathBuf::from(root)
+    };
+    (root, format!("/{}", components[literal..].join("/")))
+}
+
+/// Matches replaced in one file by `glob_replace`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct FileReplacement {
+    pub file: String,
+    pub matches_replaced: usize,
+}
+
+/// Replace every match of `find` in the files under `root` matching the
+/// anchored `glob`, returning the files that had matches. Files ignored by
+/// `.gitignore`, hidden files and files that aren't UTF-8 are skipped. With
+/// `dry_run` nothing is written.
+pub fn glob_replace(
+    root: &Path,
+    glob: &str,
+    find: &regex::Regex,
+    replace: &str,
+    literal: bool,
+    dry_run: bool,
+) -> Result<Vec<(PathBuf, usize)>> {
+    // Used only as a matcher: as walker overrides, matches would bypass .gitignore
+    let matcher = ignore::overrides::OverrideBuilder::new(root)
+        .add(glob)?
+        .build()?;
+    let walker = ignore::WalkBuilder::new(root)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    let mut replaced = Vec::new();
+    for entry in walker {
+        let entry = entry?;
+        if !entry.file_type().is_some_and(|t| t.is_file())
+            || !matcher.matched(entry.path(), false).is_whitelist()
+        {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let count = find.find_iter(&content).count();
+        if count == 0 {
+            continue;
+        }
+        if !dry_run {
+            let updated = if literal {
+                find.replace_all(&content, regex::NoExpand(replace))
+            } else {
+                find.replace_all(&content, replace)
+            };
+            std::fs::write(entry.path(), updated.as_bytes())?;
+        }
+        replaced.push((entry.path().to_path_buf(), count));
+    }
+    Ok(replaced)
+}
+
+/// Execute the `glob_replace` tool.
+pub async fn execute_glob_replace<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing glob_replace tool call");
+
+    let args = &tool_call.args;
+    let pattern = match args.get("pattern").and_then(|v| v.as_str()) {
+        Some(p) if !p.is_empty() => p,
+        _ => return Ok("❌ Missing pattern argument".to_string()),
+    };
+    let find = match args.get("find").and_then(|v| v.as_str()) {
+        Some(f) if !f.is_empty() => f,
+        _ => return Ok("❌ Missing find argument".to_string()),
+    };
+    let replace = match args.get("replace").and_then(|v| v.as_str()) {
+        Some(r) => r,
+        None => return Ok("❌ Missing replace argument".to_string()),
+    };
+    let literal = args.get("literal").and_then(|v| v.as_bool()).unwrap_or(true);
+    let case_insensitive = args
+        .get("case_insensitive")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let dry_run = args.get("dry_run").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    let source = if literal {
+        regex::escape(find)
+    } else {
+        find.to_string()
+    };
+    let regex = match regex::RegexBuilder::new(&source)
+        .case_insensitive(case_insensitive)
+        .build()
+    {
+        Ok(r) => r,
+        Err(e) => return Ok(format!("❌ Invalid regex '{}': {}", find, e)),
+    };
+
+    let (root, glob) = split_glob_root(pattern);
+    let root = ctx.resolve_path(&root.to_string_lossy());
+    if !root.is_dir() {
+        return Ok(format!("❌ Directory '{}' does not exist", root.display()));
+    }
+
+    let replaced = match glob_replace(&root, &glob, &regex, replace, literal, dry_run) {
+        Ok(r) => r,
+        Err(e) => return Ok(format!("❌ glob_replace failed: {}", e)),
+    };
+    if replaced.is_empty() {
+        return Ok(format!("No matches for '{}' in files matching '{}'", find, pattern));
+    }
+
+    let summary: Vec<FileReplacement> = replaced
+        .into_iter()
+        .map(|(path, matches_replaced)| FileReplacement {
+            file: path
+                .strip_prefix(&ctx.working_directory)
+                .unwrap_or(&path)
+                .display()
+                .to_string(),
+            matches_replaced,
+        })
+        .collect();
+    let total: usize = summary.iter().map(|r| r.matches_replaced).sum();
+    let header = if dry_run {
+        format!("[DRY RUN] Would replace {} matches in {} files", total, summary.len())
+    } else {
+        format!("✅ Replaced {} matches in {} files", total, summary.len())
+    };
+    Ok(format!("{}\n{}", header, serde_json::to_string_pretty(&summary)?))
+}
+
+/// Default number of unchanged lines around each diff hunk.
+pub const DEFAULT_DIFF_CONTEXT_LINES: usize = 3;
+
+/// Unified diff of `old` against `new` in GNU diff format, with `--- a/..`
+/// and `+++ b/..` headers. Returns an empty string if there are no changes.
+///
+/// With `ignore_whitespace`, lines are compared with surrounding whitespace
+/// trimmed; the diff still shows them as they are.
+pub fn unified_diff(
+    old: &str,
+    new: &str,
+    old_label: &str,
+    new_label: &str,
+    context_lines: usize,
+    ignore_whitespace: bool,
+) -> String {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let key = |line: &&str| {
+        if ignore_whitespace {
+            line.trim().to_string()
+        } else {
+            line.to_string()
+        }
+    };
+    let old_keys: Vec<String> = old_lines.iter().map(key).collect();
+    let new_keys: Vec<String> = new_lines.iter().map(key).collect();
+
+    let ops = similar::capture_diff_slices(similar::Algorithm::Myers, &old_keys, &new_keys);
+    let groups = similar::group_diff_ops(ops, context_lines);
+    if groups.is_empty() {
+        return String::new();
+    }
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", old_label, new_label);
+    for group in groups {
+        let (first, last) = (&group[0], &group[group.len() - 1]);
+        let old_range = first.old_range().start..last.old_range().end;
+        let new_range = first.new_range().start..last.new_range().end;
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(&old_range),
+            hunk_range(&new_range)
+        ));
+        for op in &group {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            if tag == similar::DiffTag::Equal {
+                push_diff_lines(&mut out, ' ', &old_lines[old_range]);
+                continue;
+            }
+            push_diff_lines(&mut out, '-', &old_lines[old_range]);
+            push_diff_lines(&mut out, '+', &new_lines[new_range]);
+        }
+    }
+    out
+}
+
+/// GNU hunk range: `start,len`, with `,1` left out and an empty range
+/// starting at the line before it.
+fn hunk_range(range: &std::ops::Range<usize>) -> String {
+    match range.len() {
+        0 => format!("{},0", range.start),
+        1 => format!("{}", range.start + 1),
+        len => format!("{},{}", range.start + 1, len),
+    }
+}
+
+fn push_diff_lines(out: &mut String, prefix: char, lines: &[&str]) {
+    for line in lines {
+        out.push(prefix);
+        out.push_str(line);
+        if !line.ends_with('\n') {
+            out.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+}
+
+/// Execute the `diff_files` tool.
+pub async fn execute_diff_files<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing diff_files tool call");
+
+    let file_a = match tool_call.args.get("file_a").and_then(|v| v.as_str()) {
+        Some(p) => p,
+        None => return Ok("❌ Missing file_a argument".to_string()),
+    };
+    let file_b = tool_call.args.get("file_b").and_then(|v| v.as_str());
+    let inline_b = tool_call.args.get("inline_b").and_then(|v| v.as_str());
+    let context_lines = tool_call
+        .args
+        .get("context_lines")
+        .and_then(|v| v.as_u64())
+        .map_or(DEFAULT_DIFF_CONTEXT_LINES, |n| n as usize);
+    let ignore_whitespace = tool_call
+        .args
+        .get("ignore_whitespace")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let read = |path: &str| -> std::result::Result<String, String> {
+        let resolved = ctx.resolve_path(path);
+        if !resolved.exists() {
+            return Err(format!("❌ File not found: {}", path));
+        }
+        std::fs::read_to_string(&resolved)
+            .map_err(|e| format!("❌ Failed to read file '{}': {}", path, e))
+    };
+
+    let old = match read(file_a) {
+        Ok(content) => content,
+        Err(msg) => return Ok(msg),
+    };
+    // An inline version is labelled as the new version of file_a
+    let (new, new_label) = match (file_b, inline_b) {
+        (Some(_), Some(_)) => return Ok("❌ Pass either file_b or inline_b, not both".to_string()),
+        (Some(path), None) => match read(path) {
+            Ok(content) => (content, path),
+            Err(msg) => return Ok(msg),
+        },
+        (None, Some(content)) => (content.to_string(), file_a),
+        (None, None) => return Ok("❌ Missing file_b or inline_b argument".to_string()),
+    };
+
+    let diff = unified_diff(
+        &old,
+        &new,
+        file_a,
+        new_label,
+        context_lines,
+        ignore_whitespace,
+    );
+    if diff.is_empty() {
+        Ok("✅ No differences".to_string())
+    } else {
+        Ok(diff)
+    }
+}
+
+/// Write `content` to a temp file next to `path` and rename it over `path`,
+/// so readers see either the old file or the new one, never a partial write.
+/// Across filesystems, where rename is impossible, falls back to copying.
+/// A symlink is written through, replacing the file it points to, and an
+/// existing file keeps its permissions.
+pub fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    write_atomic_with(path, content, |_| Ok(()))
+}
+
+/// [`write_atomic`], calling `before_rename` once the temp file is written.
+fn write_atomic_with(
+    path: &Path,
+    content: &[u8],
+    before_rename: impl FnOnce(&Path) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let resolved = std::fs::canonicalize(path);
+    let path = resolved.as_deref().unwrap_or(path);
+    let permissions = std::fs::metadata(path).ok().map(|m| m.permissions());
+
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name().map_or_else(
+        || "file".into(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let temp_path = dir.join(format!(".{}.{:08x}.tmp", file_name, rand::random::<u32>()));
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        drop(file);
+        if let Some(permissions) = permissions {
+            std::fs::set_permissions(&temp_path, permissions)?;
+        }
+        before_rename(&temp_path)?;
+        match std::fs::rename(&temp_path, path) {
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+                std::fs::copy(&temp_path, path).map(|_| ())
+            }
+            other => other,
+        }
+    })();
+
+    if temp_path.exists() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
 // Helper functions
 
 /// Known argument key pairs for path and content.
@@ -354,7 +1073,7 @@ const PATH_CONTENT_KEYS: &[(&str, &str)] = &[
 ];
 
 /// Extract path and content from various argument formats.
-fn extract_path_and_content(args: &serde_json::Value) -> (Option<&str>, Option<&str>) {
+pub(crate) fn extract_path_and_content(args: &serde_json::Value) -> (Option<&str>, Option<&str>) {
     match args {
         serde_json::Value::Object(obj) => {
             for &(path_key, content_key) in PATH_CONTENT_KEYS {
@@ -471,3 +1190,576 @@ pub fn print_imgcat(
     // Blank line before next image (no │ prefix)
     println!();
 }
+
+/// Maximum number of children listed per directory in `directory_tree`.
+const MAX_TREE_CHILDREN: usize = 100;
+
+/// Options for [`build_directory_tree`].
+#[derive(Debug, Clone, Copy)]
+pub struct DirectoryTreeOptions {
+    pub max_depth: usize,
+    pub include_hidden: bool,
+    pub respect_gitignore: bool,
+}
+
+impl Default for DirectoryTreeOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 5,
+            include_hidden: false,
+            respect_gitignore: true,
+        }
+    }
+}
+
+/// Render the directory at `root` as an indented tree. Directories end in `/`.
+pub fn build_directory_tree(root: &Path, options: DirectoryTreeOptions) -> Result<String> {
+    let walker = ignore::WalkBuilder::new(root)
+        .max_depth(Some(options.max_depth))
+        .hidden(!options.include_hidden)
+        .git_ignore(options.respect_gitignore)
+        .git_global(options.respect_gitignore)
+        .git_exclude(options.respect_gitignore)
+        .ignore(options.respect_gitignore)
+        .parents(options.respect_gitignore)
+        // Honour .gitignore even when the directory is not inside a git repo
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    let mut children: BTreeMap<PathBuf, Vec<(String, bool)>> = BTreeMap::new();
+    for entry in walker {
+        let entry = entry?;
+        if entry.depth() == 0 {
+            continue;
+        }
+        let Some(parent) = entry.path().parent() else {
+            continue;
+        };
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        children
+            .entry(parent.to_path_buf())
+            .or_default()
+            .push((entry.file_name().to_string_lossy().to_string(), is_dir));
+    }
+
+    let mut out = format!("{}/\n", root.display().to_string().trim_end_matches('/'));
+    render_tree_level(root, 1, &children, &mut out);
+    Ok(out)
+}
+
+fn render_tree_level(
+    dir: &Path,
+    depth: usize,
+    children: &BTreeMap<PathBuf, Vec<(String, bool)>>,
+    out: &mut String,
+) {
+    let Some(entries) = children.get(dir) else {
+        return;
+    };
+    let indent = "  ".repeat(depth);
+    for (name, is_dir) in entries.iter().take(MAX_TREE_CHILDREN) {
+        if *is_dir {
+            out.push_str(&format!("{}{}/\n", indent, name));
+            render_tree_level(&dir.join(name), depth + 1, children, out);
+        } else {
+            out.push_str(&format!("{}{}\n", indent, name));
+        }
+    }
+    if entries.len() > MAX_TREE_CHILDREN {
+        out.push_str(&format!(
+            "{}... ({} more entries)\n",
+            indent,
+            entries.len() - MAX_TREE_CHILDREN
+        ));
+    }
+}
+
+/// Execute the `directory_tree` tool.
+pub async fn execute_directory_tree<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing directory_tree tool call");
+
+    let path_str = tool_call
+        .args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .unwrap_or(".");
+    let path = ctx.resolve_path(path_str);
+    if !path.is_dir() {
+        return Ok(format!("❌ Not a directory: {}", path.display()));
+    }
+
+    let defaults = DirectoryTreeOptions::default();
+    let options = DirectoryTreeOptions {
+        max_depth: tool_call
+            .args
+            .get("max_depth")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(defaults.max_depth),
+        include_hidden: tool_call
+            .args
+            .get("include_hidden")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(defaults.include_hidden),
+        respect_gitignore: tool_call
+            .args
+            .get("respect_gitignore")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(defaults.respect_gitignore),
+    };
+
+    match build_directory_tree(&path, options) {
+        Ok(tree) => Ok(tree.trim_end().to_string()),
+        Err(e) => Ok(format!("❌ Failed to read directory tree: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample_tree() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/nested/deeper")).unwrap();
+        std::fs::create_dir_all(root.join("target/debug")).unwrap();
+        std::fs::write(root.join("Cargo.toml"), "").unwrap();
+        std::fs::write(root.join("src/lib.rs"), "").unwrap();
+        std::fs::write(root.join("src/nested/deeper/mod.rs"), "").unwrap();
+        std::fs::write(root.join("target/debug/app"), "").unwrap();
+        std::fs::write(root.join(".env"), "").unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        dir
+    }
+
+    fn tree_lines(tree: &str) -> Vec<&str> {
+        tree.lines().skip(1).collect()
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("out.txt");
+        std::fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new content").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new content");
+        // No temp file is left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_failure_leaves_target_unchanged() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("out.txt");
+        std::fs::write(&path, "original").unwrap();
+
+        let result = write_atomic_with(&path, b"replacement", |temp| {
+            assert!(temp.exists());
+            Err(std::io::Error::other("killed mid-write"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "original");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_permissions_and_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let script = dir.path().join("run.sh");
+        std::fs::write(&script, "echo old").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o750)).unwrap();
+        let link = dir.path().join("link.sh");
+        std::os::unix::fs::symlink(&script, &link).unwrap();
+
+        write_atomic(&link, b"echo new").unwrap();
+
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read_to_string(&script).unwrap(), "echo new");
+        let mode = std::fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_read_only_dir_leaves_target_unchanged() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("out.txt");
+        std::fs::write(&path, "original").unwrap();
+        let set_mode = |mode| {
+            std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(mode)).unwrap()
+        };
+
+        let result = write_atomic_with(&path, b"replacement", |_| {
+            set_mode(0o555);
+            Ok(())
+        });
+        // Root ignores directory permissions, so the rename may succeed there
+        let enforced = std::fs::File::create(dir.path().join("probe")).is_err();
+        set_mode(0o755);
+
+        if enforced {
+            assert!(result.is_err());
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), "original");
+        } else {
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), "replacement");
+        }
+    }
+
+    #[test]
+    fn test_unified_diff_matches_gnu_format() {
+        let old = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten\n";
+        let new = "one\ntwo\nthree\nFOUR\nfive\nsix\nseven\neight\nnine\nten\neleven";
+
+        // Expected output taken from `diff -U1`
+        assert_eq!(
+            unified_diff(old, new, "old.txt", "new.txt", 1, false),
+            "--- a/old.txt\n+++ b/new.txt\n\
+             @@ -3,3 +3,3 @@\n three\n-four\n+FOUR\n five\n\
+             @@ -10 +10,2 @@\n ten\n+eleven\n\\ No newline at end of file\n"
+        );
+        assert_eq!(
+            unified_diff(old, new, "f", "f", DEFAULT_DIFF_CONTEXT_LINES, false)
+                .lines()
+                .filter(|l| l.starts_with("@@"))
+                .collect::<Vec<_>>(),
+            vec!["@@ -1,10 +1,11 @@"]
+        );
+        assert_eq!(
+            unified_diff("", "x\n", "empty", "new", 3, false),
+            "--- a/empty\n+++ b/new\n@@ -0,0 +1 @@\n+x\n"
+        );
+        assert_eq!(unified_diff(old, old, "f", "f", 3, false), "");
+    }
+
+    #[test]
+    fn test_unified_diff_ignore_whitespace() {
+        let old = "fn main() {\n    run();\n}\n";
+        let new = "fn main() {\n\trun();  \n}\n";
+
+        assert!(unified_diff(old, new, "a.rs", "a.rs", 3, false)
+            .contains("-    run();\n+\trun();  \n"));
+        assert_eq!(unified_diff(old, new, "a.rs", "a.rs", 3, true), "");
+
+        // Real changes still show, with the lines as written
+        let changed = "fn main() {\n\tstop();\n}\n";
+        assert_eq!(
+            unified_diff(old, changed, "a.rs", "a.rs", 0, true),
+            "--- a/a.rs\n+++ b/a.rs\n@@ -2 +2 @@\n-    run();\n+\tstop();\n"
+        );
+    }
+
+    #[test]
+    fn test_annotate_lines_format() {
+        assert_eq!(
+            annotate_lines("first line\nsecond line\n", 1),
+            "    1: first line\n    2: second line\n"
+        );
+        assert_eq!(annotate_lines("x", 123456), "123456: x\n");
+        assert_eq!(annotate_lines("", 1), "");
+    }
+
+    #[test]
+    fn test_annotated_range_uses_file_line_numbers() {
+        let content = "one\ntwo\nthree\nfour\nfive\n";
+        // Start in the middle of line 3
+        let start = content.find("ree").unwrap();
+        let end = content.find("five").unwrap();
+
+        let first_line = line_number_at(content, start);
+        assert_eq!(first_line, 3);
+        assert_eq!(
+            annotate_lines(&content[start..end], first_line),
+            "    3: ree\n    4: four\n"
+        );
+        assert_eq!(line_number_at(content, 0), 1);
+        assert_eq!(line_number_at(content, content.len()), 6);
+    }
+
+    #[test]
+    fn test_directory_tree_respects_gitignore_and_hidden() {
+        let dir = sample_tree();
+        let tree = build_directory_tree(dir.path(), DirectoryTreeOptions::default()).unwrap();
+
+        assert_eq!(
+            tree_lines(&tree),
+            vec![
+                "  Cargo.toml",
+                "  src/",
+                "    lib.rs",
+                "    nested/",
+                "      deeper/",
+                "        mod.rs",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_directory_tree_options() {
+        let dir = sample_tree();
+        let tree = build_directory_tree(
+            dir.path(),
+            DirectoryTreeOptions {
+                max_depth: 1,
+                include_hidden: true,
+                respect_gitignore: false,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            tree_lines(&tree),
+            vec!["  .env", "  .gitignore", "  Cargo.toml", "  src/", "  target/"]
+        );
+    }
+
+    #[test]
+    fn test_directory_tree_truncates_large_directories() {
+        let dir = TempDir::new().unwrap();
+        for i in 0..105 {
+            std::fs::write(dir.path().join(format!("file_{:03}.txt", i)), "").unwrap();
+        }
+        let tree = build_directory_tree(dir.path(), DirectoryTreeOptions::default()).unwrap();
+        let lines = tree_lines(&tree);
+
+        assert_eq!(lines.len(), 101);
+        assert_eq!(lines[99], "  file_099.txt");
+        assert_eq!(lines[100], "  ... (5 more entries)");
+    }
+
+    #[test]
+    fn test_split_glob_root() {
+        let split = |p: &str| {
+            let (root, glob) = split_glob_root(p);
+            (root.to_string_lossy().into_owned(), glob)
+        };
+        assert_eq!(split("src/**/*.rs"), ("src".to_string(), "/**/*.rs".to_string()));
+        assert_eq!(split("*.toml"), ("".to_string(), "/*.toml".to_string()));
+        assert_eq!(split("crates/*/src/lib.rs"), ("crates".to_string(), "/*/src/lib.rs".to_string()));
+        assert_eq!(split("/tmp/app/{a,b}.py"), ("/tmp/app".to_string(), "/{a,b}.py".to_string()));
+        // No wildcards: a single file
+        assert_eq!(split("src/main.rs"), ("src".to_string(), "/main.rs".to_string()));
+    }
+
+    fn replace_tree() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(root.join("main.rs"), "use crate::old_name;\nold_name();\n").unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn old_name() {}\n").unwrap();
+        std::fs::write(root.join("src/nested/mod.rs"), "// OLD_NAME and old_name\nold_name();\n").unwrap();
+        std::fs::write(root.join("src/notes.txt"), "old_name\n").unwrap();
+        std::fs::write(root.join("target/gen.rs"), "old_name();\n").unwrap();
+        dir
+    }
+
+    fn counts(root: &Path, replaced: Vec<(PathBuf, usize)>) -> Vec<(String, usize)> {
+        replaced
+            .into_iter()
+            .map(|(path, n)| (path.strip_prefix(root).unwrap().display().to_string(), n))
+            .collect()
+    }
+
+    #[test]
+    fn test_glob_replace_literal() {
+        let dir = replace_tree();
+        let root = dir.path();
+        let find = regex::Regex::new(&regex::escape("old_name")).unwrap();
+
+        let replaced = glob_replace(root, "/**/*.rs", &find, "new_name($0)", true, false).unwrap();
+
+        assert_eq!(
+            counts(root, replaced),
+            vec![
+                ("main.rs".to_string(), 2),
+                ("src/lib.rs".to_string(), 1),
+                ("src/nested/mod.rs".to_string(), 2),
+            ]
+        );
+        // Literal replacements don't expand $0
+        assert_eq!(
+            std::fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+            "pub fn new_name($0)() {}\n"
+        );
+        // Other extensions and gitignored files are untouched
+        assert_eq!(std::fs::read_to_string(root.join("src/notes.txt")).unwrap(), "old_name\n");
+        assert_eq!(std::fs::read_to_string(root.join("target/gen.rs")).unwrap(), "old_name();\n");
+    }
+
+    #[test]
+    fn test_glob_replace_anchored_single_level() {
+        let dir = replace_tree();
+        let root = dir.path();
+        let find = regex::Regex::new("old_name").unwrap();
+
+        let replaced = glob_replace(root, "/*.rs", &find, "new_name", true, false).unwrap();
+
+        assert_eq!(counts(root, replaced), vec![("main.rs".to_string(), 2)]);
+        assert!(std::fs::read_to_string(root.join("src/lib.rs")).unwrap().contains("old_name"));
+    }
+
+    #[test]
+    fn test_glob_replace_regex_case_insensitive() {
+        let dir = replace_tree();
+        let root = dir.path();
+        let find = regex::RegexBuilder::new(r"old_(\w+)")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+
+        let replaced = glob_replace(root, "/src/nested/*.rs", &find, "new_$1", false, false).unwrap();
+
+        assert_eq!(counts(root, replaced), vec![("src/nested/mod.rs".to_string(), 3)]);
+        assert_eq!(
+            std::fs::read_to_string(root.join("src/nested/mod.rs")).unwrap(),
+            "// new_NAME and new_name\nnew_name();\n"
+        );
+    }
+
+    #[test]
+    fn test_glob_replace_dry_run_leaves_files() {
+        let dir = replace_tree();
+        let root = dir.path();
+        let find = regex::Regex::new("old_name").unwrap();
+
+        let replaced = glob_replace(root, "/**/*", &find, "new_name", true, true).unwrap();
+
+        assert_eq!(counts(root, replaced).len(), 4);
+        assert_eq!(
+            std::fs::read_to_string(root.join("main.rs")).unwrap(),
+            "use crate::old_name;\nold_name();\n"
+        );
+    }
+
+    #[test]
+    fn test_read_truncates_content_over_max_bytes() {
+        let content = "0123456789".repeat(10);
+
+        let out = format_file_content(&content, None, None, false, Some(25));
+        assert!(out.contains("\n0123456789012345678901234\n... [truncated: 75 bytes remaining, use start/end range args to read specific sections]"));
+
+        let out = format_file_content(&content, None, None, false, Some(100));
+        assert!(!out.contains("truncated"));
+        let out = format_file_content(&content, None, None, false, None);
+        assert!(out.ends_with(&content));
+    }
+
+    #[test]
+    fn test_read_truncation_respects_char_boundaries() {
+        // Each 'é' is two bytes, so a 5-byte limit must stop after 4.
+        let out = format_file_content("éééé", None, None, false, Some(5));
+        assert!(out.contains("\néé\n... [truncated: 4 bytes remaining"));
+    }
+
+    #[test]
+    fn test_read_truncation_applies_to_range() {
+        let content = "abcdefghij";
+        let out = format_file_content(content, Some(2), Some(8), false, Some(3));
+        assert!(out.contains("\ncde\n... [truncated: 3 bytes remaining"));
+    }
+
+    #[test]
+    fn test_summarize_large_file() {
+        let content: String = (1..=50).map(|i| format!("line {}\n", i)).collect();
+        let summary = summarize_file_content(&content);
+
+        assert!(summary.starts_with(&format!("📄 File summary ({} bytes, 50 lines", content.len())));
+        assert!(summary.contains("First 10 lines:\n    1: line 1\n"));
+        assert!(summary.contains("   10: line 10\n\n... 30 lines omitted ...\n"));
+        assert!(summary.contains("Last 10 lines:\n   41: line 41\n"));
+        assert!(summary.contains("   50: line 50\n"));
+        assert!(!summary.contains("line 11\n"));
+    }
+
+    #[test]
+    fn test_summarize_short_file_does_not_repeat_lines() {
+        let summary = summarize_file_content("a\nb\nc\n");
+        assert!(summary.contains("First 3 lines:\n    1: a\n    2: b\n    3: c\n"));
+        assert!(!summary.contains("Last"));
+    }
+
+    #[tokio::test]
+    async fn test_batch_read_reports_failures_inline() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+        std::fs::write(dir.path().join("b.rs"), "fn b() {}\nfn c() {}\n").unwrap();
+        let paths = vec![
+            dir.path().join("a.rs"),
+            dir.path().join("missing.rs"),
+            dir.path().join("b.rs"),
+        ];
+        let results = read_files_concurrently(&paths).await;
+        assert!(results[1].is_err());
+
+        let output = format_batch_read(&["a.rs", "missing.rs", "b.rs"], &results, 1024, 4096, true);
+        assert!(output.starts_with("📚 Read 2 of 3 files\n"), "{}", output);
+        assert!(output.contains("\n=== a.rs ===\n    1: fn a() {}\n"), "{}", output);
+        assert!(output.contains("\n=== missing.rs ===\n❌ Failed to read: "), "{}", output);
+        assert!(output.contains("\n=== b.rs ===\n    1: fn b() {}\n    2: fn c() {}\n"), "{}", output);
+    }
+
+    #[test]
+    fn test_batch_read_limits() {
+        let results = vec![
+            Ok("0123456789\n".to_string()),
+            Ok("abcdefghij\n".to_string()),
+            Ok("klmnopqrst\n".to_string()),
+        ];
+        // 8 bytes per file, 12 in total
+        let output = format_batch_read(&["one", "two", "three"], &results, 8, 12, false);
+        assert!(output.contains("=== one ===\n01234567\n... [truncated: 3 bytes remaining"), "{}", output);
+        assert!(output.contains("=== two ===\nabcd\n... [truncated: 7 bytes remaining"), "{}", output);
+        assert!(
+            output.contains("=== three ===\n[skipped: total output limit of 12 bytes reached"),
+            "{}",
+            output
+        );
+    }
+
+    /// Each FIFO blocks its reader until the other side is opened for writing.
+    /// The writer opens them in reverse order, so reading them one after the
+    /// other would deadlock.
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_batch_read_is_concurrent() {
+        let dir = TempDir::new().unwrap();
+        let fifos: Vec<PathBuf> = (0..2).map(|i| dir.path().join(format!("fifo{}", i))).collect();
+        for fifo in &fifos {
+            let status = std::process::Command::new("mkfifo").arg(fifo).status().unwrap();
+            assert!(status.success());
+        }
+
+        let writer_fifos = fifos.clone();
+        let writer = std::thread::spawn(move || {
+            for (i, fifo) in writer_fifos.iter().enumerate().rev() {
+                std::fs::write(fifo, format!("from fifo {}\n", i)).unwrap();
+            }
+        });
+
+        let results = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            read_files_concurrently(&fifos),
+        )
+        .await
+        .expect("reads ran one at a time");
+        writer.join().unwrap();
+        assert_eq!(results[0].as_deref(), Ok("from fifo 0\n"));
+        assert_eq!(results[1].as_deref(), Ok("from fifo 1\n"));
+    }
+}
@@ -0,0 +1,315 @@
+//! Git tool: structured status, diff and commit without raw shell parsing.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+/// A single changed path as reported by `git status --porcelain`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GitFileChange {
+    pub path: String,
+    pub status: String,
+}
+
+/// Summary of the working tree, split the same way `git status` presents it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GitStatus {
+    pub branch: Option<String>,
+    pub staged: Vec<GitFileChange>,
+    pub unstaged: Vec<GitFileChange>,
+    pub untracked: Vec<String>,
+}
+
+/// Run git with the given args in `dir`, returning stdout or an error with stderr.
+async fn run_git(dir: Option<&Path>, args: &[&str]) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.args(args);
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    debug!("Running git {:?} in {:?}", args, dir);
+
+    let output = cmd.output().await.context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn describe_status_code(code: char) -> &'static str {
+    match code {
+        'M' => "modified",
+        'A' => "added",
+        'D' => "deleted",
+        'R' => "renamed",
+        'C' => "copied",
+        'U' => "unmerged",
+        'T' => "typechange",
+        _ => "unknown",
+    }
+}
+
+/// Parse `git status --porcelain=v1 -z --branch` output. Entries end with a
+/// NUL and paths are not quoted; a rename is followed by its original path as
+/// a separate entry.
+pub fn parse_porcelain_status(output: &str) -> GitStatus {
+    let mut status = GitStatus::default();
+
+    let mut entries = output.split('\0');
+    while let Some(line) = entries.next() {
+        if let Some(branch) = line.strip_prefix("## ") {
+            // "main...origin/main [ahead 1]" or "No commits yet on main"
+            let branch = branch.split("...").next().unwrap_or(branch);
+            let branch = branch.strip_prefix("No commits yet on ").unwrap_or(branch);
+            status.branch = Some(branch.split_whitespace().next().unwrap_or(branch).to_string());
+            continue;
+        }
+        if line.len() < 4 {
+            continue;
+        }
+
+        let mut codes = line.chars();
+        let index = codes.next().unwrap_or(' ');
+        let worktree = codes.next().unwrap_or(' ');
+        let path = line[3..].to_string();
+        if matches!(index, 'R' | 'C') || matches!(worktree, 'R' | 'C') {
+            // Skip the original path; report the new one
+            entries.next();
+        }
+
+        if index == '?' && worktree == '?' {
+            status.untracked.push(path);
+            continue;
+        }
+        if index != ' ' {
+            status.staged.push(GitFileChange {
+                path: path.clone(),
+                status: describe_status_code(index).to_string(),
+            });
+        }
+        if worktree != ' ' {
+            status.unstaged.push(GitFileChange {
+                path,
+                status: describe_status_code(worktree).to_string(),
+            });
+        }
+    }
+
+    status
+}
+
+/// Get the structured status of the repository at `dir`.
+pub async fn git_status(dir: Option<&Path>) -> Result<GitStatus> {
+    let output = run_git(
+        dir,
+        &["status", "--porcelain=v1", "-z", "--branch", "--untracked-files=all"],
+    )
+    .await?;
+    Ok(parse_porcelain_status(&output))
+}
+
+/// Get the unified diff of the repo, or of a single file.
+pub async fn git_diff(dir: Option<&Path>, file: Option<&str>, staged: bool) -> Result<String> {
+    let mut args = vec!["diff", "--no-color", "--no-ext-diff"];
+    if staged {
+        args.push("--staged");
+    }
+    if let Some(file) = file {
+        args.push("--");
+        args.push(file);
+    }
+    run_git(dir, &args).await
+}
+
+/// Stage `paths` (if any) and commit with `message`. Returns the short commit hash.
+/// The repository's commit hooks run as usual.
+pub async fn git_commit(dir: Option<&Path>, message: &str, paths: &[String]) -> Result<String> {
+    if !paths.is_empty() {
+        let mut args = vec!["add", "--"];
+        args.extend(paths.iter().map(|p| p.as_str()));
+        run_git(dir, &args).await?;
+    }
+    run_git(dir, &["commit", "-m", message]).await?;
+    Ok(run_git(dir, &["rev-parse", "--short", "HEAD"]).await?.trim().to_string())
+}
+
+/// Execute the `git` tool.
+pub async fn execute_git<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing git tool call");
+
+    let dir = Some(ctx.working_directory.as_path());
+
+    let action = match tool_call.args.get("action").and_then(|v| v.as_str()) {
+        Some(a) => a,
+        None => return Ok("❌ Missing action argument".to_string()),
+    };
+
+    match action {
+        "status" => match git_status(dir).await {
+            Ok(status) => Ok(serde_json::to_string_pretty(&status)?),
+            Err(e) => Ok(format!("❌ {}", e)),
+        },
+        "diff" => {
+            let file = tool_call.args.get("file").and_then(|v| v.as_str());
+            let staged = tool_call
+                .args
+                .get("staged")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            match git_diff(dir, file, staged).await {
+                Ok(diff) if diff.trim().is_empty() => Ok("No changes".to_string()),
+                Ok(diff) => Ok(diff),
+                Err(e) => Ok(format!("❌ {}", e)),
+            }
+        }
+        "commit" => {
+            let message = match tool_call.args.get("message").and_then(|v| v.as_str()) {
+                Some(m) if !m.trim().is_empty() => m,
+                _ => return Ok("❌ Missing message argument for commit".to_string()),
+            };
+            let paths: Vec<String> = tool_call
+                .args
+                .get("paths")
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default();
+            match git_commit(dir, message, &paths).await {
+                Ok(hash) => Ok(format!("✅ Created commit {}", hash)),
+                Err(e) => Ok(format!("❌ {}", e)),
+            }
+        }
+        other => Ok(format!(
+            "❌ Unknown git action '{}'. Use 'status', 'diff' or 'commit'.",
+            other
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn init_repo() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let path = Some(dir.path());
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.email", "test@example.com"],
+            &["config", "user.name", "Test"],
+            &["config", "commit.gpgsign", "false"],
+        ] {
+            run_git(path, args).await.unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_parse_porcelain_status() {
+        let output = "## main...origin/main [ahead 1]\0M  staged.rs\0 M unstaged.rs\0MM both.rs\0R  new.rs\0old.rs\0?? new_file.txt\0";
+        let status = parse_porcelain_status(output);
+
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(
+            status.staged,
+            vec![
+                GitFileChange { path: "staged.rs".into(), status: "modified".into() },
+                GitFileChange { path: "both.rs".into(), status: "modified".into() },
+                GitFileChange { path: "new.rs".into(), status: "renamed".into() },
+            ]
+        );
+        assert_eq!(status.unstaged.len(), 2);
+        assert_eq!(status.untracked, vec!["new_file.txt".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_status_diff_and_commit_in_temp_repo() {
+        let repo = init_repo().await;
+        let dir = Some(repo.path());
+
+        std::fs::write(repo.path().join("a.txt"), "one\n").unwrap();
+        let status = git_status(dir).await.unwrap();
+        assert_eq!(status.untracked, vec!["a.txt".to_string()]);
+        assert!(status.staged.is_empty());
+
+        let hash = git_commit(dir, "Add a.txt", &["a.txt".to_string()]).await.unwrap();
+        assert!(!hash.is_empty());
+        assert_eq!(git_status(dir).await.unwrap().untracked.len(), 0);
+
+        std::fs::write(repo.path().join("a.txt"), "one\ntwo\n").unwrap();
+        let status = git_status(dir).await.unwrap();
+        assert_eq!(
+            status.unstaged,
+            vec![GitFileChange { path: "a.txt".into(), status: "modified".into() }]
+        );
+
+        let diff = git_diff(dir, Some("a.txt"), false).await.unwrap();
+        assert!(diff.contains("+two"));
+        assert!(!diff.contains("\x1b["));
+        assert!(git_diff(dir, None, true).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_status_of_paths_with_spaces_and_quotes() {
+        let repo = init_repo().await;
+        let dir = Some(repo.path());
+
+        for name in ["my file.txt", "say \"hi\".txt", "caf\u{e9}.txt"] {
+            std::fs::write(repo.path().join(name), "x\n").unwrap();
+        }
+        git_commit(dir, "Add files", &["my file.txt".to_string()]).await.unwrap();
+        run_git(dir, &["mv", "my file.txt", "your file.txt"]).await.unwrap();
+
+        let status = git_status(dir).await.unwrap();
+        assert_eq!(
+            status.staged,
+            vec![GitFileChange { path: "your file.txt".into(), status: "renamed".into() }]
+        );
+        assert_eq!(
+            status.untracked,
+            vec!["caf\u{e9}.txt".to_string(), "say \"hi\".txt".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_commit_without_changes_fails() {
+        let repo = init_repo().await;
+        let err = git_commit(Some(repo.path()), "Empty", &[]).await.unwrap_err();
+        assert!(err.to_string().contains("git commit failed"));
+    }
+
+    #[tokio::test]
+    async fn test_commit_runs_hooks() {
+        let repo = init_repo().await;
+        let dir = Some(repo.path());
+        let hook = repo.path().join(".git/hooks/pre-commit");
+        std::fs::write(&hook, "#!/bin/sh\necho 'hook says no' >&2\nexit 1\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        std::fs::write(repo.path().join("a.txt"), "one\n").unwrap();
+        let err = git_commit(dir, "Add a.txt", &["a.txt".to_string()]).await.unwrap_err();
+        assert!(err.to_string().contains("hook says no"));
+    }
+}
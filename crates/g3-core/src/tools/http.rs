@@ -0,0 +1,438 @@
+//! HTTP tool: plain HTTP requests for API calls and health checks, no browser needed.
+
+use anyhow::{Context, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{redirect, Method};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+/// Default request timeout when `timeout_secs` is not given.
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Maximum number of body bytes included in the tool result.
+const MAX_BODY_BYTES: usize = 50 * 1024;
+
+/// Response headers worth showing to the model; the rest are noise.
+const REPORTED_HEADERS: &[&str] = &[
+    "content-type",
+    "content-length",
+    "content-encoding",
+    "content-disposition",
+    "location",
+    "cache-control",
+    "etag",
+    "last-modified",
+    "set-cookie",
+    "www-authenticate",
+    "retry-after",
+    "server",
+];
+
+/// A request to send with [`send_http_request`].
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub body: Option<String>,
+    pub timeout: Duration,
+    pub follow_redirects: bool,
+}
+
+/// The parts of a response the tool reports.
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub reason: Option<String>,
+    /// Headers from `REPORTED_HEADERS`, in response order.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Parse a method name, accepting only the verbs the tool supports.
+pub fn parse_method(method: &str) -> Option<Method> {
+    match method.to_ascii_uppercase().as_str() {
+        "GET" => Some(Method::GET),
+        "POST" => Some(Method::POST),
+        "PUT" => Some(Method::PUT),
+        "DELETE" => Some(Method::DELETE),
+        "PATCH" => Some(Method::PATCH),
+        "HEAD" => Some(Method::HEAD),
+        _ => None,
+    }
+}
+
+/// Send the request and collect the response.
+pub async fn send_http_request(request: &HttpRequest) -> Result<HttpResponse> {
+    let policy = if request.follow_redirects {
+        redirect::Policy::limited(10)
+    } else {
+        redirect::Policy::none()
+    };
+    let client = reqwest::Client::builder()
+        .redirect(policy)
+        .timeout(request.timeout)
+        .build()
+        .context("Failed to build HTTP client")?;
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in &request.headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("Invalid header name '{}'", name))?;
+        let value = HeaderValue::from_str(value)
+            .with_context(|| format!("Invalid value for header '{}'", name))?;
+        headers.insert(name, value);
+    }
+
+    let mut builder = client
+        .request(request.method.clone(), &request.url)
+        .headers(headers);
+    if let Some(body) = &request.body {
+        builder = builder.body(body.clone());
+    }
+
+    debug!("Sending {} {}", request.method, request.url);
+    let response = builder.send().await?;
+
+    let status = response.status();
+    let headers = response
+        .headers()
+        .iter()
+        .filter(|(name, _)| REPORTED_HEADERS.contains(&name.as_str()))
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).to_string(),
+            )
+        })
+        .collect();
+    let body = response.bytes().await?.to_vec();
+
+    Ok(HttpResponse {
+        status: status.as_u16(),
+        reason: status.canonical_reason().map(String::from),
+        headers,
+        body,
+    })
+}
+
+/// Format a response for the model. If `saved_to` is set the body was written
+/// there and is not included.
+pub fn format_http_response(response: &HttpResponse, saved_to: Option<&Path>) -> String {
+    let mut out = match &response.reason {
+        Some(reason) => format!("Status: {} {}\n", response.status, reason),
+        None => format!("Status: {}\n", response.status),
+    };
+
+    if !response.headers.is_empty() {
+        out.push_str("Headers:\n");
+        for (name, value) in &response.headers {
+            out.push_str(&format!("  {}: {}\n", name, value));
+        }
+    }
+
+    if let Some(path) = saved_to {
+        out.push_str(&format!(
+            "✅ Saved {} bytes to {}",
+            response.body.len(),
+            path.display()
+        ));
+        return out;
+    }
+
+    if response.body.is_empty() {
+        out.push_str("Body: (empty)");
+        return out;
+    }
+
+    let shown = &response.body[..response.body.len().min(MAX_BODY_BYTES)];
+    // A cut in the middle of a UTF-8 sequence is not a binary body
+    let text = match std::str::from_utf8(shown) {
+        Ok(text) => Some(text),
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&shown[..e.valid_up_to()]).ok(),
+        Err(_) => None,
+    };
+
+    match text {
+        Some(text) => {
+            out.push_str("Body:\n");
+            out.push_str(text);
+            if response.body.len() > MAX_BODY_BYTES {
+                out.push_str(&format!(
+                    "\n... (truncated, {} of {} bytes shown)",
+                    text.len(),
+                    response.body.len()
+                ));
+            }
+        }
+        None => out.push_str(&format!(
+            "Body: binary data ({} bytes); pass output_path to save it",
+            response.body.len()
+        )),
+    }
+    out
+}
+
+/// Execute the `http_request` tool.
+pub async fn execute_http_request<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing http_request tool call");
+
+    let url = match tool_call.args.get("url").and_then(|v| v.as_str()) {
+        Some(u) if !u.trim().is_empty() => u.to_string(),
+        _ => return Ok("❌ Missing url argument".to_string()),
+    };
+
+    let method_str = tool_call
+        .args
+        .get("method")
+        .and_then(|v| v.as_str())
+        .unwrap_or("GET");
+    let method = match parse_method(method_str) {
+        Some(m) => m,
+        None => {
+            return Ok(format!(
+                "❌ Unsupported method '{}'. Use GET, POST, PUT, DELETE, PATCH or HEAD.",
+                method_str
+            ))
+        }
+    };
+
+    let headers: HashMap<String, String> = tool_call
+        .args
+        .get("headers")
+        .and_then(|v| v.as_object())
+        .map(|obj| {
+            obj.iter()
+                .map(|(k, v)| {
+                    let value = v.as_str().map(String::from).unwrap_or_else(|| v.to_string());
+                    (k.clone(), value)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let request = HttpRequest {
+        method,
+        url,
+        headers,
+        body: tool_call
+            .args
+            .get("body")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        timeout: Duration::from_secs(
+            tool_call
+                .args
+                .get("timeout_secs")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(DEFAULT_TIMEOUT_SECS),
+        ),
+        follow_redirects: tool_call
+            .args
+            .get("follow_redirects")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true),
+    };
+
+    let output_path = tool_call
+        .args
+        .get("output_path")
+        .and_then(|v| v.as_str())
+        .map(|p| ctx.resolve_path(p));
+
+    let response = match send_http_request(&request).await {
+        Ok(r) => r,
+        Err(e) if e.downcast_ref::<reqwest::Error>().is_some_and(|e| e.is_timeout()) => {
+            return Ok(format!(
+                "❌ Request to {} timed out after {}s",
+                request.url,
+                request.timeout.as_secs()
+            ))
+        }
+        Err(e) => return Ok(format!("❌ Request to {} failed: {:#}", request.url, e)),
+    };
+
+    if let Some(path) = &output_path {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        if let Err(e) = std::fs::write(path, &response.body) {
+            return Ok(format!("❌ Failed to write {}: {}", path.display(), e));
+        }
+    }
+
+    Ok(format_http_response(&response, output_path.as_deref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Minimal HTTP/1.1 server that echoes the request line and body, and
+    /// answers `/redirect` with a 302 and `/binary` with non-UTF-8 bytes.
+    async fn start_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                tokio::spawn(async move {
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 4096];
+                    let header_end = loop {
+                        let n = socket.read(&mut chunk).await.unwrap();
+                        if n == 0 {
+                            return;
+                        }
+                        buf.extend_from_slice(&chunk[..n]);
+                        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                            break pos + 4;
+                        }
+                    };
+                    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+                    let content_length = head
+                        .lines()
+                        .find_map(|l| {
+                            let (name, value) = l.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    while buf.len() < header_end + content_length {
+                        let n = socket.read(&mut chunk).await.unwrap();
+                        buf.extend_from_slice(&chunk[..n]);
+                    }
+
+                    let request_line = head.lines().next().unwrap_or("").to_string();
+                    let path = request_line.split(' ').nth(1).unwrap_or("/");
+                    let auth = head
+                        .lines()
+                        .find(|l| l.to_ascii_lowercase().starts_with("x-token:"))
+                        .map(|l| l[8..].trim().to_string())
+                        .unwrap_or_default();
+
+                    let (status, extra, body): (&str, String, Vec<u8>) = match path {
+                        "/redirect" => ("302 Found", "Location: /target\r\n".into(), Vec::new()),
+                        "/binary" => ("200 OK", "Content-Type: application/octet-stream\r\n".into(), vec![0xff, 0xfe, 0x00, 0x01]),
+                        _ => {
+                            let body = format!(
+                                "{} token={} body={}",
+                                request_line,
+                                auth,
+                                String::from_utf8_lossy(&buf[header_end..])
+                            );
+                            ("200 OK", "Content-Type: text/plain\r\nX-Internal: hidden\r\n".into(), body.into_bytes())
+                        }
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                        status,
+                        extra,
+                        body.len()
+                    );
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                    socket.write_all(&body).await.unwrap();
+                });
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+
+    fn request(method: Method, url: String) -> HttpRequest {
+        HttpRequest {
+            method,
+            url,
+            headers: HashMap::new(),
+            body: None,
+            timeout: Duration::from_secs(5),
+            follow_redirects: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_post_with_headers_and_body() {
+        let base = start_server().await;
+        let mut req = request(Method::POST, format!("{}/api", base));
+        req.headers.insert("X-Token".into(), "secret".into());
+        req.body = Some(r#"{"a":1}"#.into());
+
+        let response = send_http_request(&req).await.unwrap();
+        assert_eq!(response.status, 200);
+        let body = String::from_utf8(response.body.clone()).unwrap();
+        assert!(body.starts_with("POST /api HTTP/1.1"));
+        assert!(body.contains("token=secret"));
+        assert!(body.ends_with(r#"body={"a":1}"#));
+
+        // Only the common headers are reported
+        assert!(response.headers.iter().any(|(k, v)| k == "content-type" && v == "text/plain"));
+        assert!(!response.headers.iter().any(|(k, _)| k == "x-internal"));
+
+        let formatted = format_http_response(&response, None);
+        assert!(formatted.starts_with("Status: 200 OK\n"));
+        assert!(formatted.contains("  content-type: text/plain\n"));
+    }
+
+    #[tokio::test]
+    async fn test_redirects_can_be_disabled() {
+        let base = start_server().await;
+
+        let followed = send_http_request(&request(Method::GET, format!("{}/redirect", base)))
+            .await
+            .unwrap();
+        assert_eq!(followed.status, 200);
+        assert!(String::from_utf8_lossy(&followed.body).starts_with("GET /target"));
+
+        let mut req = request(Method::GET, format!("{}/redirect", base));
+        req.follow_redirects = false;
+        let not_followed = send_http_request(&req).await.unwrap();
+        assert_eq!(not_followed.status, 302);
+        assert!(not_followed
+            .headers
+            .contains(&("location".to_string(), "/target".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_binary_body_and_truncation() {
+        let base = start_server().await;
+        let response = send_http_request(&request(Method::GET, format!("{}/binary", base)))
+            .await
+            .unwrap();
+        assert_eq!(response.body, vec![0xff, 0xfe, 0x00, 0x01]);
+        assert!(format_http_response(&response, None).contains("binary data (4 bytes)"));
+
+        let large = HttpResponse {
+            status: 200,
+            reason: Some("OK".into()),
+            headers: Vec::new(),
+            body: "é".repeat(MAX_BODY_BYTES).into_bytes(),
+        };
+        let formatted = format_http_response(&large, None);
+        assert!(formatted.contains("truncated"));
+        assert!(formatted.len() < MAX_BODY_BYTES + 200);
+    }
+
+    #[test]
+    fn test_parse_method() {
+        assert_eq!(parse_method("post"), Some(Method::POST));
+        assert_eq!(parse_method("DELETE"), Some(Method::DELETE));
+        assert_eq!(parse_method("TRACE"), None);
+    }
+}
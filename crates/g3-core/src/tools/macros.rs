@@ -0,0 +1,355 @@
+//! Tool-call macros: record a sequence of tool calls to `.g3/macros/<name>.jsonl`
+//! and play it back later.
+//!
+//! A macro file holds one [`ToolCall`] as JSON per line. While a recording is
+//! active, `dispatch_tool` appends every call it runs except the macro tools
+//! themselves and `final_output`.
+
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+/// Tools that are never recorded or played back.
+pub const MACRO_META_TOOLS: &[&str] = &[
+    "macro_record",
+    "macro_stop",
+    "macro_playback",
+    "macro_list",
+    "final_output",
+];
+
+/// A macro being recorded.
+#[derive(Debug, Clone)]
+pub struct MacroRecording {
+    pub name: String,
+    pub path: PathBuf,
+    /// Tool calls recorded so far.
+    pub calls: usize,
+}
+
+/// Saved macros and the recording in progress, if any.
+#[derive(Debug)]
+pub struct MacroStore {
+    dir: PathBuf,
+    recording: Option<MacroRecording>,
+}
+
+impl MacroStore {
+    /// Store keeping macro files in `dir`.
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            recording: None,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn recording(&self) -> Option<&MacroRecording> {
+        self.recording.as_ref()
+    }
+
+    /// Path of the file for macro `name`. Names may only use letters, digits,
+    /// `-` and `_` so they cannot escape the macro directory.
+    pub fn path_for(&self, name: &str) -> Result<PathBuf> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!(
+                "Invalid macro name '{}': use only letters, digits, '-' and '_'",
+                name
+            );
+        }
+        Ok(self.dir.join(format!("{}.jsonl", name)))
+    }
+
+    /// Start recording macro `name`, replacing any macro saved under that name.
+    pub fn start(&mut self, name: &str) -> Result<&MacroRecording> {
+        if let Some(recording) = &self.recording {
+            bail!("Already recording macro '{}'", recording.name);
+        }
+        let path = self.path_for(name)?;
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        std::fs::write(&path, "").with_context(|| format!("Failed to create {}", path.display()))?;
+        Ok(self.recording.insert(MacroRecording {
+            name: name.to_string(),
+            path,
+            calls: 0,
+        }))
+    }
+
+    /// Stop recording, returning the finished recording.
+    pub fn stop(&mut self) -> Option<MacroRecording> {
+        self.recording.take()
+    }
+
+    /// Append `tool_call` to the recording in progress. Does nothing when not
+    /// recording or for [`MACRO_META_TOOLS`].
+    pub fn record(&mut self, tool_call: &ToolCall) -> Result<()> {
+        let Some(recording) = self.recording.as_mut() else {
+            return Ok(());
+        };
+        if MACRO_META_TOOLS.contains(&tool_call.tool.as_str()) {
+            return Ok(());
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&recording.path)
+            .with_context(|| format!("Failed to open {}", recording.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(tool_call)?)?;
+        recording.calls += 1;
+        Ok(())
+    }
+
+    /// Read the tool calls saved in macro `name`.
+    pub fn load(&self, name: &str) -> Result<Vec<ToolCall>> {
+        let path = self.path_for(name)?;
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Macro '{}' not found", name))?;
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Invalid tool call on line {} of {}", i + 1, path.display()))
+            })
+            .collect()
+    }
+
+    /// Names of the saved macros with their number of tool calls, sorted by name.
+    pub fn list(&self) -> Result<Vec<(String, usize)>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut macros = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let calls = std::fs::read_to_string(&path)?
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .count();
+            macros.push((name.to_string(), calls));
+        }
+        macros.sort();
+        Ok(macros)
+    }
+}
+
+fn describe_call(tool_call: &ToolCall) -> String {
+    format!("{} {}", tool_call.tool, tool_call.args)
+}
+
+/// Execute the `macro_record` tool.
+pub async fn execute_macro_record<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &mut ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing macro_record tool call");
+
+    let name = match tool_call.args.get("name").and_then(|v| v.as_str()) {
+        Some(n) => n,
+        None => return Ok("❌ Missing name argument".to_string()),
+    };
+    match ctx.macros.start(name) {
+        Ok(recording) => Ok(format!(
+            "✅ Recording macro '{}' to {}. Tool calls from now on are saved until macro_stop.",
+            recording.name,
+            recording.path.display()
+        )),
+        Err(e) => Ok(format!("❌ {:#}", e)),
+    }
+}
+
+/// Execute the `macro_stop` tool.
+pub async fn execute_macro_stop<W: UiWriter>(
+    _tool_call: &ToolCall,
+    ctx: &mut ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing macro_stop tool call");
+
+    match ctx.macros.stop() {
+        Some(recording) => Ok(format!(
+            "✅ Saved macro '{}' with {} tool call{}",
+            recording.name,
+            recording.calls,
+            if recording.calls == 1 { "" } else { "s" }
+        )),
+        None => Ok("❌ No macro is being recorded".to_string()),
+    }
+}
+
+/// Execute the `macro_playback` tool.
+pub async fn execute_macro_playback<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &mut ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing macro_playback tool call");
+
+    let name = match tool_call.args.get("name").and_then(|v| v.as_str()) {
+        Some(n) => n,
+        None => return Ok("❌ Missing name argument".to_string()),
+    };
+    let dry_run = tool_call
+        .args
+        .get("dry_run")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let stop_on_error = tool_call
+        .args
+        .get("stop_on_error")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+
+    let calls = match ctx.macros.load(name) {
+        Ok(calls) => calls,
+        Err(e) => return Ok(format!("❌ {:#}", e)),
+    };
+    let calls: Vec<ToolCall> = calls
+        .into_iter()
+        .filter(|call| !MACRO_META_TOOLS.contains(&call.tool.as_str()))
+        .collect();
+    if calls.is_empty() {
+        return Ok(format!("⚠️ Macro '{}' has no tool calls", name));
+    }
+
+    if dry_run {
+        let mut out = format!(
+            "🔍 Dry run: macro '{}' would run {} tool call(s):\n",
+            name,
+            calls.len()
+        );
+        for (i, call) in calls.iter().enumerate() {
+            out.push_str(&format!("{}. {}\n", i + 1, describe_call(call)));
+        }
+        return Ok(out);
+    }
+
+    let mut sections = Vec::new();
+    let mut failed = None;
+    for (i, call) in calls.iter().enumerate() {
+        ctx.ui_writer.print_context_status(&format!(
+            "▶️ Macro '{}' step {}/{}: {}",
+            name,
+            i + 1,
+            calls.len(),
+            call.tool
+        ));
+        // Boxed because dispatch_tool is what called us
+        let result = Box::pin(crate::tool_dispatch::dispatch_tool(call, ctx)).await?;
+        let is_error = super::executor::is_error_result(&result);
+        sections.push(format!("## {}. {}\n{}", i + 1, describe_call(call), result));
+        if is_error && stop_on_error {
+            failed = Some(i + 1);
+            break;
+        }
+    }
+
+    let header = match failed {
+        Some(step) => format!(
+            "❌ Macro '{}' stopped at step {} of {}",
+            name,
+            step,
+            calls.len()
+        ),
+        None => format!("✅ Macro '{}' ran {} tool call(s)", name, calls.len()),
+    };
+    Ok(format!("{}\n\n{}", header, sections.join("\n\n")))
+}
+
+/// Execute the `macro_list` tool.
+pub async fn execute_macro_list<W: UiWriter>(
+    _tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing macro_list tool call");
+
+    let macros = match ctx.macros.list() {
+        Ok(macros) => macros,
+        Err(e) => return Ok(format!("❌ Failed to list macros: {}", e)),
+    };
+    if macros.is_empty() {
+        return Ok(format!("No macros saved in {}", ctx.macros.dir().display()));
+    }
+
+    let mut out = format!("✅ {} macro(s) in {}:\n", macros.len(), ctx.macros.dir().display());
+    for (name, calls) in macros {
+        let recording = ctx
+            .macros
+            .recording()
+            .is_some_and(|r| r.name == name);
+        out.push_str(&format!(
+            "- {} ({} tool call{}){}\n",
+            name,
+            calls,
+            if calls == 1 { "" } else { "s" },
+            if recording { " [recording]" } else { "" }
+        ));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(tool: &str, args: serde_json::Value) -> ToolCall {
+        ToolCall {
+            tool: tool.to_string(),
+            args,
+        }
+    }
+
+    #[test]
+    fn test_record_skips_meta_tools() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut store = MacroStore::new(dir.path().join("macros"));
+
+        // Nothing is recorded before macro_record
+        store.record(&call("shell", json!({"command": "ls"}))).unwrap();
+        store.start("build").unwrap();
+        assert!(store.start("other").is_err());
+        store.record(&call("shell", json!({"command": "cargo build"}))).unwrap();
+        store.record(&call("macro_list", json!({}))).unwrap();
+        store.record(&call("final_output", json!({"summary": "done"}))).unwrap();
+        store.record(&call("read_file", json!({"file_path": "Cargo.toml"}))).unwrap();
+        let recording = store.stop().unwrap();
+        assert_eq!(recording.calls, 2);
+        store.record(&call("shell", json!({"command": "ls"}))).unwrap();
+
+        let calls = store.load("build").unwrap();
+        let tools: Vec<&str> = calls.iter().map(|c| c.tool.as_str()).collect();
+        assert_eq!(tools, vec!["shell", "read_file"]);
+        assert_eq!(calls[0].args, json!({"command": "cargo build"}));
+        assert_eq!(store.list().unwrap(), vec![("build".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_macro_names_are_validated() {
+        let store = MacroStore::new(PathBuf::from("/tmp/g3-macros"));
+        assert!(store.path_for("run-tests_2").is_ok());
+        assert!(store.path_for("../escape").is_err());
+        assert!(store.path_for("").is_err());
+        assert!(store.load("missing").is_err());
+    }
+}
@@ -1,7 +1,12 @@
-//! Miscellaneous tools: final_output, take_screenshot, code_coverage, code_search.
+//! Miscellaneous tools: final_output, take_screenshot, code_coverage, code_search, text_search,
+//! env_check, code_lint, run_tests, code_complexity, code_rename_symbol.
 
-use anyhow::Result;
-use tracing::debug;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, warn};
 
 use crate::ui_writer::UiWriter;
 use crate::ToolCall;
@@ -35,6 +40,21 @@ pub async fn execute_final_output<W: UiWriter>(
         }
     }
 
+    let structured = tool_call
+        .args
+        .get("structured")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if structured {
+        let output = StructuredFinalOutput::from_args(&tool_call.args);
+        // The report is still shown when it can't be saved
+        let path = crate::paths::get_final_output_file();
+        if let Err(e) = output.write_to(&path) {
+            warn!("Failed to write {}: {}", path.display(), e);
+        }
+        return output.render();
+    }
+
     // Return the summary or a default message
     // Note: Session continuation saving is handled by the caller (Agent)
     if let Some(summary) = summary_str {
@@ -44,10 +64,69 @@ pub async fn execute_final_output<W: UiWriter>(
     }
 }
 
+/// Machine-readable completion report produced by `final_output` with `structured: true`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StructuredFinalOutput {
+    pub summary: String,
+    pub files_changed: Vec<String>,
+    pub tests_run: bool,
+    pub tests_passed: Option<u32>,
+    pub tests_failed: Option<u32>,
+    pub next_steps: Vec<String>,
+}
+
+impl StructuredFinalOutput {
+    /// Build from tool args; missing or mistyped fields take their defaults.
+    pub fn from_args(args: &serde_json::Value) -> Self {
+        let string_list = |key: &str| -> Vec<String> {
+            args.get(key)
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|v| v.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let count = |key: &str| args.get(key).and_then(|v| v.as_u64()).map(|n| n as u32);
+
+        Self {
+            summary: args
+                .get("summary")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            files_changed: string_list("files_changed"),
+            tests_run: args.get("tests_run").and_then(|v| v.as_bool()).unwrap_or(false),
+            tests_passed: count("tests_passed"),
+            tests_failed: count("tests_failed"),
+            next_steps: string_list("next_steps"),
+        }
+    }
+
+    /// The human-readable summary followed by the JSON report in a fenced block.
+    pub fn render(&self) -> Result<String> {
+        Ok(format!(
+            "{}\n\n```json\n{}\n```",
+            self.summary,
+            serde_json::to_string_pretty(self)?
+        ))
+    }
+
+    pub fn write_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
 /// Execute the `take_screenshot` tool.
 pub async fn execute_take_screenshot<W: UiWriter>(
     tool_call: &ToolCall,
-    ctx: &ToolContext<'_, W>,
+    ctx: &mut ToolContext<'_, W>,
 ) -> Result<String> {
     debug!("Processing take_screenshot tool call");
     
@@ -109,10 +188,41 @@ pub async fn execute_take_screenshot<W: UiWriter>(
                 format!("{}/{}", temp_dir.trim_end_matches('/'), path)
             };
 
-            Ok(format!(
-                "✅ Screenshot of {} saved to: {}",
-                window_id, actual_path
-            ))
+            let attach = tool_call
+                .args
+                .get("attach_to_context")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            if !attach {
+                return Ok(format!(
+                    "✅ Screenshot of {} saved to: {}",
+                    window_id, actual_path
+                ));
+            }
+
+            // Attach the image to the next message so the model can see it directly
+            let image = match std::fs::read(&actual_path) {
+                Ok(bytes) => g3_providers::ImageContent::from_bytes(&bytes),
+                Err(e) => {
+                    return Ok(format!(
+                        "❌ Screenshot saved to {} but could not be read: {}",
+                        actual_path, e
+                    ))
+                }
+            };
+            match image {
+                Some(image) => {
+                    ctx.pending_images.push(image);
+                    Ok(format!(
+                        "✅ Screenshot of {} saved to: {} (attached to context)",
+                        window_id, actual_path
+                    ))
+                }
+                None => Ok(format!(
+                    "❌ Screenshot saved to {} but it is not a supported image format",
+                    actual_path
+                )),
+            }
         }
         Err(e) => Ok(format!("❌ Failed to take screenshot: {}", e)),
     }
@@ -169,10 +279,65 @@ pub async fn execute_code_coverage<W: UiWriter>(
 /// Execute the `code_search` tool.
 pub async fn execute_code_search<W: UiWriter>(
     tool_call: &ToolCall,
-    _ctx: &ToolContext<'_, W>,
+    ctx: &ToolContext<'_, W>,
 ) -> Result<String> {
     debug!("Processing code_search tool call");
 
+    let action = tool_call
+        .args
+        .get("action")
+        .and_then(|v| v.as_str())
+        .unwrap_or("search");
+    match action {
+        "search" => {}
+        "build_semantic_index" => return build_semantic_index(ctx).await,
+        other => {
+            return Ok(format!(
+                "❌ Unknown action '{}' (use search or build_semantic_index)",
+                other
+            ))
+        }
+    }
+
+    // Semantic search needs an index; without one, run any tree-sitter searches given
+    let mut fallback_note = String::new();
+    let mode = tool_call
+        .args
+        .get("mode")
+        .and_then(|v| v.as_str())
+        .unwrap_or("tree-sitter");
+    match mode {
+        "tree-sitter" => {}
+        "semantic" => {
+            let index_path = crate::paths::get_embeddings_index_path();
+            match crate::code_search::SemanticIndex::load(&index_path) {
+                Ok(Some(index)) => return semantic_code_search(tool_call, ctx, &index).await,
+                Ok(None) if tool_call.args.get("searches").is_some() => {
+                    fallback_note = format!(
+                        "ℹ️ No semantic index at {}; used tree-sitter search instead (build one with action build_semantic_index)\n",
+                        index_path.display()
+                    );
+                }
+                Ok(None) => {
+                    return Ok(format!(
+                        "❌ No semantic index at {}. Build it with action build_semantic_index, or pass tree-sitter searches.",
+                        index_path.display()
+                    ))
+                }
+                Err(e) => return Ok(format!("❌ Failed to load semantic index: {:#}", e)),
+            }
+        }
+        other => {
+            return Ok(format!(
+                "❌ Unknown mode '{}' (use tree-sitter or semantic)",
+                other
+            ))
+        }
+    }
+    if tool_call.args.get("searches").is_none() {
+        return Ok("❌ Missing searches argument".to_string());
+    }
+
     // Parse the request
     let request: crate::code_search::CodeSearchRequest =
         match serde_json::from_value(tool_call.args.clone()) {
@@ -182,15 +347,1602 @@ pub async fn execute_code_search<W: UiWriter>(
             }
         };
 
-    // Execute the code search
-    match crate::code_search::execute_code_search(request).await {
+    // Execute the code search, ranking matches near files the session has been working on
+    let ranking =
+        crate::code_search::Ranking::new(ctx.config.code_search.clone(), ctx.recent_files);
+    match crate::code_search::execute_ranked_code_search(request, ranking).await {
         Ok(response) => {
             // Serialize the response to JSON
             match serde_json::to_string_pretty(&response) {
-                Ok(json_output) => Ok(format!("✅ Code search completed\n{}", json_output)),
+                Ok(json_output) => Ok(format!(
+                    "✅ Code search completed\n{}{}",
+                    fallback_note, json_output
+                )),
                 Err(e) => Ok(format!("❌ Failed to serialize response: {}", e)),
             }
         }
         Err(e) => Ok(format!("❌ Code search failed: {}", e)),
     }
 }
+
+/// Build the semantic index of the working directory and save it to `.g3/embeddings.bin`.
+async fn build_semantic_index<W: UiWriter>(ctx: &ToolContext<'_, W>) -> Result<String> {
+    let embedder = match crate::code_search::embedder_from_config(&ctx.config.code_search) {
+        Ok(embedder) => embedder,
+        Err(e) => return Ok(format!("❌ {:#}", e)),
+    };
+    let index =
+        match crate::code_search::SemanticIndex::build(&ctx.working_directory, embedder.as_ref())
+            .await
+        {
+            Ok(index) => index,
+            Err(e) => return Ok(format!("❌ Failed to build semantic index: {:#}", e)),
+        };
+
+    let index_path = crate::paths::get_embeddings_index_path();
+    if let Err(e) = index.save(&index_path) {
+        return Ok(format!("❌ Failed to save semantic index: {:#}", e));
+    }
+    Ok(format!(
+        "✅ Indexed {} definitions from {} files into {} (model: {})",
+        index.chunks.len(),
+        index.files_indexed,
+        index_path.display(),
+        index.model
+    ))
+}
+
+/// Rank the definitions in `index` by similarity to the `query` argument.
+async fn semantic_code_search<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+    index: &crate::code_search::SemanticIndex,
+) -> Result<String> {
+    let query = match tool_call.args.get("query").and_then(|v| v.as_str()) {
+        Some(q) => q,
+        None => return Ok("❌ Semantic search needs a query argument".to_string()),
+    };
+    let top_k = tool_call
+        .args
+        .get("top_k")
+        .and_then(|v| v.as_u64())
+        .map(|n| n.max(1) as usize)
+        .unwrap_or(crate::code_search::semantic::DEFAULT_TOP_K);
+
+    let embedder = match crate::code_search::embedder_from_config(&ctx.config.code_search) {
+        Ok(embedder) => embedder,
+        Err(e) => return Ok(format!("❌ {:#}", e)),
+    };
+    let matches = match index.search(query, embedder.as_ref(), top_k).await {
+        Ok(matches) => matches,
+        Err(e) => return Ok(format!("❌ Semantic search failed: {:#}", e)),
+    };
+
+    let response = serde_json::json!({
+        "query": query,
+        "model": index.model,
+        "root": index.root,
+        "definitions_indexed": index.chunks.len(),
+        "matches": matches,
+    });
+    Ok(format!(
+        "✅ Semantic code search completed\n{}",
+        serde_json::to_string_pretty(&response)?
+    ))
+}
+
+/// Lines longer than this are cut in `text_search` output.
+const MAX_SEARCH_LINE_CHARS: usize = 300;
+
+/// A line matching a `text_search` pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchMatch {
+    /// Path relative to the searched directory.
+    pub file: String,
+    /// 1-based line number.
+    pub line: u32,
+    /// 1-based character column of the first match on the line.
+    pub col: u32,
+    pub text: String,
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+}
+
+/// Options for [`text_search`].
+#[derive(Debug, Clone)]
+pub struct TextSearchOptions {
+    pub case_insensitive: bool,
+    /// ripgrep-style file type such as `rust` or `python`.
+    pub file_type: Option<String>,
+    pub context_lines: usize,
+    pub max_results: usize,
+}
+
+impl Default for TextSearchOptions {
+    fn default() -> Self {
+        Self {
+            case_insensitive: false,
+            file_type: None,
+            context_lines: 0,
+            max_results: 100,
+        }
+    }
+}
+
+fn truncate_search_line(line: &str) -> String {
+    match line.char_indices().nth(MAX_SEARCH_LINE_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+/// Search files under `root` for lines matching the regex `pattern`.
+///
+/// Hidden files, files ignored by .gitignore and non-UTF-8 files are skipped.
+/// Returns the matches in path order and whether `max_results` cut the search short.
+pub fn text_search(
+    root: &Path,
+    pattern: &str,
+    options: &TextSearchOptions,
+) -> Result<(Vec<SearchMatch>, bool)> {
+    let regex = regex::RegexBuilder::new(pattern)
+        .case_insensitive(options.case_insensitive)
+        .build()
+        .context("Invalid regex")?;
+
+    let mut walker = ignore::WalkBuilder::new(root);
+    walker
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .sort_by_file_name(|a, b| a.cmp(b));
+    if let Some(file_type) = &options.file_type {
+        let types = ignore::types::TypesBuilder::new()
+            .add_defaults()
+            .select(file_type)
+            .build()
+            .with_context(|| format!("Unknown file type '{}'", file_type))?;
+        walker.types(types);
+    }
+
+    let mut matches = Vec::new();
+    for entry in walker.build() {
+        let entry = entry?;
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let file = entry
+            .path()
+            .strip_prefix(root)
+            .ok()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(entry.path())
+            .display()
+            .to_string();
+
+        let lines: Vec<&str> = content.lines().collect();
+        for (i, line) in lines.iter().enumerate() {
+            let Some(found) = regex.find(line) else {
+                continue;
+            };
+            if matches.len() == options.max_results {
+                return Ok((matches, true));
+            }
+            let before = i.saturating_sub(options.context_lines);
+            let after = (i + 1 + options.context_lines).min(lines.len());
+            matches.push(SearchMatch {
+                file: file.clone(),
+                line: (i + 1) as u32,
+                col: (line[..found.start()].chars().count() + 1) as u32,
+                text: truncate_search_line(line),
+                context_before: lines[before..i].iter().map(|l| truncate_search_line(l)).collect(),
+                context_after: lines[i + 1..after]
+                    .iter()
+                    .map(|l| truncate_search_line(l))
+                    .collect(),
+            });
+        }
+    }
+    Ok((matches, false))
+}
+
+/// Render matches like `rg -n`: `file:line:col: text` for matches and
+/// `file-line- text` for context, with `--` between separate groups.
+fn format_search_matches(matches: &[SearchMatch]) -> String {
+    // Matching lines win over the same line shown as another match's context
+    let mut files: Vec<(&str, BTreeMap<u32, String>)> = Vec::new();
+    for m in matches {
+        if files.last().map(|(file, _)| *file) != Some(m.file.as_str()) {
+            files.push((&m.file, BTreeMap::new()));
+        }
+        let lines = &mut files.last_mut().expect("pushed above").1;
+        let first = m.line - m.context_before.len() as u32;
+        for (line, text) in (first..).zip(&m.context_before) {
+            lines.entry(line).or_insert_with(|| format!("{}-{}- {}", m.file, line, text));
+        }
+        lines.insert(m.line, format!("{}:{}:{}: {}", m.file, m.line, m.col, m.text));
+        for (line, text) in (m.line + 1..).zip(&m.context_after) {
+            lines.entry(line).or_insert_with(|| format!("{}-{}- {}", m.file, line, text));
+        }
+    }
+
+    let has_context = matches
+        .iter()
+        .any(|m| !m.context_before.is_empty() || !m.context_after.is_empty());
+    let mut out = String::new();
+    for (i, (_, lines)) in files.iter().enumerate() {
+        let mut previous = None;
+        for (&line, rendered) in lines {
+            let gap = previous.map_or(i > 0, |p: u32| p + 1 < line);
+            if has_context && gap {
+                out.push_str("--\n");
+            }
+            out.push_str(rendered);
+            out.push('\n');
+            previous = Some(line);
+        }
+    }
+    out
+}
+
+/// Execute the `text_search` tool.
+pub async fn execute_text_search<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing text_search tool call");
+
+    let pattern = match tool_call.args.get("pattern").and_then(|v| v.as_str()) {
+        Some(p) if !p.is_empty() => p,
+        _ => return Ok("❌ Missing pattern argument".to_string()),
+    };
+    let path_str = tool_call
+        .args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .unwrap_or(".");
+    let root = ctx.resolve_path(path_str);
+    if !root.exists() {
+        return Ok(format!("❌ Path not found: {}", root.display()));
+    }
+
+    let defaults = TextSearchOptions::default();
+    let options = TextSearchOptions {
+        case_insensitive: tool_call
+            .args
+            .get("case_insensitive")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(defaults.case_insensitive),
+        file_type: tool_call
+            .args
+            .get("file_type")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        context_lines: tool_call
+            .args
+            .get("context_lines")
+            .and_then(|v| v.as_u64())
+            .map_or(defaults.context_lines, |n| n as usize),
+        max_results: tool_call
+            .args
+            .get("max_results")
+            .and_then(|v| v.as_u64())
+            .map_or(defaults.max_results, |n| n.max(1) as usize),
+    };
+
+    let (matches, truncated) = match text_search(&root, pattern, &options) {
+        Ok(result) => result,
+        Err(e) => return Ok(format!("❌ {:#}", e)),
+    };
+    if matches.is_empty() {
+        return Ok(format!("No matches for `{}` in {}", pattern, path_str));
+    }
+
+    let files = matches
+        .iter()
+        .map(|m| m.file.as_str())
+        .collect::<std::collections::BTreeSet<_>>()
+        .len();
+    let mut out = format!(
+        "✅ {} match{} for `{}` in {} file{}\n",
+        matches.len(),
+        if matches.len() == 1 { "" } else { "es" },
+        pattern,
+        files,
+        if files == 1 { "" } else { "s" }
+    );
+    out.push_str(&format_search_matches(&matches));
+    if truncated {
+        out.push_str(&format!(
+            "... stopped after {} matches; narrow the pattern, path or file_type, or raise max_results\n",
+            options.max_results
+        ));
+    }
+    Ok(out)
+}
+
+/// Values longer than this are masked in `env_check` output whatever the name.
+const MAX_UNMASKED_ENV_VALUE: usize = 20;
+
+/// One variable checked by `env_check`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvVarCheck {
+    pub name: String,
+    pub required: bool,
+    /// Value as shown in the report (masked if it looks like a secret), or
+    /// `None` when the variable is not set.
+    pub display_value: Option<String>,
+    /// Result of matching the value against its `validate` pattern, if it has
+    /// one and is set.
+    pub validation: Option<std::result::Result<(), String>>,
+}
+
+/// Result of `env_check`.
+#[derive(Debug, Clone, Default)]
+pub struct EnvCheckReport {
+    pub vars: Vec<EnvVarCheck>,
+}
+
+impl EnvCheckReport {
+    pub fn missing_required(&self) -> Vec<&str> {
+        self.vars
+            .iter()
+            .filter(|v| v.required && v.display_value.is_none())
+            .map(|v| v.name.as_str())
+            .collect()
+    }
+
+    pub fn invalid(&self) -> Vec<&str> {
+        self.vars
+            .iter()
+            .filter(|v| matches!(v.validation, Some(Err(_))))
+            .map(|v| v.name.as_str())
+            .collect()
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.missing_required().is_empty() && self.invalid().is_empty()
+    }
+}
+
+/// `value` as `env_check` shows it: masked when the name looks sensitive or
+/// the value is long enough to be a key.
+fn mask_env_value(name: &str, value: &str) -> String {
+    let upper = name.to_ascii_uppercase();
+    let sensitive = super::shell::SENSITIVE_ENV_NAMES
+        .iter()
+        .any(|part| upper.contains(part));
+    if sensitive || value.chars().count() > MAX_UNMASKED_ENV_VALUE {
+        format!("[masked, {} chars]", value.chars().count())
+    } else {
+        value.to_string()
+    }
+}
+
+/// Check `required` and `optional` variables, and match the values named in
+/// `validate` against their regex. `lookup` returns a variable's value.
+pub fn check_env(
+    required: &[String],
+    optional: &[String],
+    validate: &BTreeMap<String, String>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> EnvCheckReport {
+    let mut names: Vec<(&str, bool)> = Vec::new();
+    for name in required {
+        if !names.iter().any(|(n, _)| n == name) {
+            names.push((name, true));
+        }
+    }
+    for name in optional.iter().chain(validate.keys()) {
+        if !names.iter().any(|(n, _)| n == name) {
+            names.push((name, false));
+        }
+    }
+
+    let vars = names
+        .into_iter()
+        .map(|(name, required)| {
+            let value = lookup(name);
+            let validation = match (&value, validate.get(name)) {
+                (Some(value), Some(pattern)) => Some(match regex::Regex::new(pattern) {
+                    Ok(re) if re.is_match(value) => Ok(()),
+                    Ok(_) => Err(format!("does not match {}", pattern)),
+                    Err(e) => Err(format!("invalid pattern {}: {}", pattern, e)),
+                }),
+                _ => None,
+            };
+            EnvVarCheck {
+                name: name.to_string(),
+                required,
+                display_value: value.map(|v| mask_env_value(name, &v)),
+                validation,
+            }
+        })
+        .collect();
+    EnvCheckReport { vars }
+}
+
+fn format_env_check(report: &EnvCheckReport) -> String {
+    let missing = report.missing_required();
+    let invalid = report.invalid();
+    let mut out = String::new();
+    if report.is_ok() {
+        let optional: Vec<&EnvVarCheck> = report.vars.iter().filter(|v| !v.required).collect();
+        out.push_str(&format!(
+            "✅ Environment OK: {} required set, {} of {} optional set\n",
+            report.vars.len() - optional.len(),
+            optional.iter().filter(|v| v.display_value.is_some()).count(),
+            optional.len()
+        ));
+    }
+    if !missing.is_empty() {
+        out.push_str(&format!(
+            "❌ Missing required environment variables: {}\n",
+            missing.join(", ")
+        ));
+    }
+    if !invalid.is_empty() {
+        out.push_str(&format!("❌ Invalid format: {}\n", invalid.join(", ")));
+    }
+
+    for (heading, required) in [("Required", true), ("Optional", false)] {
+        let vars: Vec<&EnvVarCheck> = report.vars.iter().filter(|v| v.required == required).collect();
+        if vars.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n{}:\n", heading));
+        for var in vars {
+            let line = match (&var.display_value, &var.validation) {
+                (None, _) if required => format!("  ❌ {} not set", var.name),
+                (None, _) => format!("  ⚪ {} not set", var.name),
+                (Some(value), Some(Err(reason))) => {
+                    format!("  ❌ {} = {} ({})", var.name, value, reason)
+                }
+                (Some(value), _) => format!("  ✅ {} = {}", var.name, value),
+            };
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+
+    if !report.is_ok() {
+        out.push_str(
+            "\nTo fix: export the variables in the shell that starts g3, or set them for \
+             this session's shell commands with shell_env (action: set).\n",
+        );
+    }
+    out
+}
+
+/// Read a list of variable names from `args[key]`.
+fn env_names_arg(args: &serde_json::Value, key: &str) -> Vec<String> {
+    args.get(key)
+        .and_then(|v| v.as_array())
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|n| n.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Execute the `env_check` tool.
+pub async fn execute_env_check<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing env_check tool call");
+
+    let required = env_names_arg(&tool_call.args, "required");
+    let optional = env_names_arg(&tool_call.args, "optional");
+    let validate: BTreeMap<String, String> = match tool_call.args.get("validate") {
+        None | Some(serde_json::Value::Null) => BTreeMap::new(),
+        Some(value) => match serde_json::from_value(value.clone()) {
+            Ok(validate) => validate,
+            Err(_) => {
+                return Ok(
+                    "❌ validate must map variable names to regex patterns, e.g. {\"PORT\": \"^[0-9]+$\"}"
+                        .to_string(),
+                )
+            }
+        },
+    };
+    if required.is_empty() && optional.is_empty() && validate.is_empty() {
+        return Ok("❌ Provide at least one of required, optional or validate".to_string());
+    }
+
+    // Variables set with shell_env are what shell commands will see
+    let report = check_env(&required, &optional, &validate, |name| {
+        ctx.extra_env
+            .get(name)
+            .cloned()
+            .or_else(|| std::env::var(name).ok())
+    });
+    Ok(format_env_check(&report))
+}
+
+/// A single linter finding, normalized across clippy, ruff and eslint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintResult {
+    pub file: String,
+    pub line: u32,
+    pub col: u32,
+    pub severity: String,
+    pub message: String,
+    pub rule: String,
+}
+
+/// Guess the lint language from a file extension or the project files in a directory.
+pub fn detect_lint_language(path: &Path) -> Option<&'static str> {
+    if path.is_file() {
+        return match path.extension()?.to_str()? {
+            "rs" => Some("rust"),
+            "py" => Some("python"),
+            "ts" | "tsx" => Some("typescript"),
+            "js" | "jsx" | "mjs" | "cjs" => Some("javascript"),
+            _ => None,
+        };
+    }
+    if path.join("Cargo.toml").exists() {
+        Some("rust")
+    } else if path.join("pyproject.toml").exists() || path.join("setup.py").exists() {
+        Some("python")
+    } else if path.join("package.json").exists() {
+        Some("typescript")
+    } else {
+        None
+    }
+}
+
+/// Find the directory holding the Cargo.toml that owns `path`.
+fn find_cargo_root(path: &Path) -> Option<PathBuf> {
+    let start = if path.is_file() { path.parent()? } else { path };
+    start
+        .ancestors()
+        .find(|dir| dir.join("Cargo.toml").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Parse `cargo clippy --message-format=json` output.
+pub fn parse_clippy_output(output: &str, root: &Path) -> Vec<LintResult> {
+    let mut results = Vec::new();
+    for line in output.lines() {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if value["reason"] != "compiler-message" {
+            continue;
+        }
+        let message = &value["message"];
+        let Some(span) = message["spans"]
+            .as_array()
+            .and_then(|spans| spans.iter().find(|s| s["is_primary"] == true))
+        else {
+            continue;
+        };
+        let file = span["file_name"].as_str().unwrap_or_default();
+        results.push(LintResult {
+            file: root.join(file).display().to_string(),
+            line: span["line_start"].as_u64().unwrap_or(0) as u32,
+            col: span["column_start"].as_u64().unwrap_or(0) as u32,
+            severity: message["level"].as_str().unwrap_or("warning").to_string(),
+            message: message["message"].as_str().unwrap_or_default().to_string(),
+            rule: message["code"]["code"].as_str().unwrap_or_default().to_string(),
+        });
+    }
+    results
+}
+
+/// Parse `ruff check --output-format=json` output.
+pub fn parse_ruff_output(output: &str) -> Result<Vec<LintResult>> {
+    let diagnostics: Vec<serde_json::Value> =
+        serde_json::from_str(output).context("Invalid ruff JSON output")?;
+    Ok(diagnostics
+        .iter()
+        .map(|d| LintResult {
+            file: d["filename"].as_str().unwrap_or_default().to_string(),
+            line: d["location"]["row"].as_u64().unwrap_or(0) as u32,
+            col: d["location"]["column"].as_u64().unwrap_or(0) as u32,
+            // ruff has no severity levels; diagnostics without a rule code are syntax errors
+            severity: if d["code"].is_null() { "error" } else { "warning" }.to_string(),
+            message: d["message"].as_str().unwrap_or_default().to_string(),
+            rule: d["code"].as_str().unwrap_or_default().to_string(),
+        })
+        .collect())
+}
+
+/// Parse `eslint --format=json` output.
+pub fn parse_eslint_output(output: &str) -> Result<Vec<LintResult>> {
+    let files: Vec<serde_json::Value> =
+        serde_json::from_str(output).context("Invalid eslint JSON output")?;
+    let mut results = Vec::new();
+    for file in &files {
+        let path = file["filePath"].as_str().unwrap_or_default();
+        for m in file["messages"].as_array().into_iter().flatten() {
+            results.push(LintResult {
+                file: path.to_string(),
+                line: m["line"].as_u64().unwrap_or(0) as u32,
+                col: m["column"].as_u64().unwrap_or(0) as u32,
+                severity: if m["severity"] == 2 { "error" } else { "warning" }.to_string(),
+                message: m["message"].as_str().unwrap_or_default().to_string(),
+                rule: m["ruleId"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+    }
+    Ok(results)
+}
+
+/// Run the linter for `language` on `path` and return its findings.
+pub fn run_linter(path: &Path, language: &str, fix: bool) -> Result<Vec<LintResult>> {
+    match language {
+        "rust" => {
+            let root = find_cargo_root(path)
+                .ok_or_else(|| anyhow::anyhow!("No Cargo.toml found for {}", path.display()))?;
+            let mut cmd = std::process::Command::new("cargo");
+            cmd.args(["clippy", "--message-format=json"]).current_dir(&root);
+            if fix {
+                cmd.args(["--fix", "--allow-dirty", "--allow-staged"]);
+            }
+            let output = cmd.output().context("Failed to run cargo clippy")?;
+            let mut results = parse_clippy_output(&String::from_utf8_lossy(&output.stdout), &root);
+            if path.is_file() {
+                let target = path.canonicalize()?;
+                results.retain(|r| {
+                    Path::new(&r.file)
+                        .canonicalize()
+                        .map(|f| f == target)
+                        .unwrap_or(false)
+                });
+            }
+            Ok(results)
+        }
+        "python" => {
+            let mut cmd = std::process::Command::new("ruff");
+            cmd.args(["check", "--output-format=json"]);
+            if fix {
+                cmd.arg("--fix");
+            }
+            let output = cmd.arg(path).output().context("Failed to run ruff")?;
+            parse_ruff_output(&String::from_utf8_lossy(&output.stdout))
+        }
+        "typescript" | "javascript" => {
+            let mut cmd = std::process::Command::new("eslint");
+            cmd.arg("--format=json");
+            if fix {
+                cmd.arg("--fix");
+            }
+            let output = cmd.arg(path).output().context("Failed to run eslint")?;
+            parse_eslint_output(&String::from_utf8_lossy(&output.stdout))
+        }
+        other => anyhow::bail!("Unsupported lint language '{}'", other),
+    }
+}
+
+fn format_lint_results(results: &[LintResult]) -> String {
+    if results.is_empty() {
+        return "✅ No lint issues found".to_string();
+    }
+    let mut out = format!("Found {} lint issue(s):\n", results.len());
+    for r in results {
+        let rule = if r.rule.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", r.rule)
+        };
+        out.push_str(&format!(
+            "{}:{}:{}: {}: {}{}\n",
+            r.file, r.line, r.col, r.severity, r.message, rule
+        ));
+    }
+    out
+}
+
+/// Execute the `code_lint` tool.
+pub async fn execute_code_lint<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing code_lint tool call");
+
+    let path_str = match tool_call.args.get("path").and_then(|v| v.as_str()) {
+        Some(p) => p,
+        None => return Ok("❌ Missing path argument".to_string()),
+    };
+    let path = ctx.resolve_path(path_str);
+    if !path.exists() {
+        return Ok(format!("❌ Path not found: {}", path.display()));
+    }
+
+    let language = match tool_call.args.get("language").and_then(|v| v.as_str()) {
+        Some(lang) => lang.to_lowercase(),
+        None => match detect_lint_language(&path) {
+            Some(lang) => lang.to_string(),
+            None => {
+                return Ok(format!(
+                    "❌ Could not detect language for {}. Pass the language argument.",
+                    path.display()
+                ))
+            }
+        },
+    };
+    let fix = tool_call
+        .args
+        .get("fix")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let output_format = tool_call
+        .args
+        .get("output_format")
+        .and_then(|v| v.as_str())
+        .unwrap_or("text");
+
+    let results = match run_linter(&path, &language, fix) {
+        Ok(results) => results,
+        Err(e) => return Ok(format!("❌ Lint failed: {}", e)),
+    };
+
+    if output_format == "json" {
+        Ok(serde_json::to_string_pretty(&results)?)
+    } else {
+        Ok(format_lint_results(&results))
+    }
+}
+
+/// Execute the `code_complexity` tool.
+pub async fn execute_code_complexity<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing code_complexity tool call");
+
+    let path_str = match tool_call.args.get("path").and_then(|v| v.as_str()) {
+        Some(p) => p,
+        None => return Ok("❌ Missing path argument".to_string()),
+    };
+    let path = ctx.resolve_path(path_str);
+    if !path.is_file() {
+        return Ok(format!("❌ File not found: {}", path.display()));
+    }
+
+    let language = match tool_call.args.get("language").and_then(|v| v.as_str()) {
+        Some(lang) => lang.to_lowercase(),
+        None => match detect_lint_language(&path) {
+            Some(lang) => lang.to_string(),
+            None => {
+                return Ok(format!(
+                    "❌ Could not detect language for {}. Pass the language argument.",
+                    path.display()
+                ))
+            }
+        },
+    };
+
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) => return Ok(format!("❌ Failed to read {}: {}", path.display(), e)),
+    };
+    let report = match crate::code_complexity::analyze(&source, &language) {
+        Ok(report) => report,
+        Err(e) => return Ok(format!("❌ {:#}", e)),
+    };
+
+    let flagged = report
+        .functions
+        .iter()
+        .filter(|f| !f.violations.is_empty())
+        .count();
+    let mut out = if flagged == 0 {
+        format!(
+            "✅ {} functions, none over {} lines or complexity {}\n",
+            report.functions.len(),
+            crate::code_complexity::MAX_FUNCTION_LINES,
+            crate::code_complexity::MAX_COMPLEXITY
+        )
+    } else {
+        format!(
+            "⚠️ {} of {} functions over {} lines or complexity {}\n",
+            flagged,
+            report.functions.len(),
+            crate::code_complexity::MAX_FUNCTION_LINES,
+            crate::code_complexity::MAX_COMPLEXITY
+        )
+    };
+    if !report.impl_blocks.is_empty() {
+        let blocks: Vec<String> = report
+            .impl_blocks
+            .iter()
+            .map(|b| format!("{} ({} methods)", b.name, b.methods))
+            .collect();
+        out.push_str(&format!("Impl blocks: {}\n", blocks.join(", ")));
+    }
+    out.push_str(&serde_json::to_string_pretty(&report.functions)?);
+    Ok(out)
+}
+
+/// Occurrences renamed in one file by `code_rename_symbol`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SymbolRename {
+    pub file: String,
+    pub occurrences_replaced: usize,
+}
+
+/// Execute the `code_rename_symbol` tool.
+pub async fn execute_code_rename_symbol<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing code_rename_symbol tool call");
+
+    let args = &tool_call.args;
+    let old_name = match args.get("old_name").and_then(|v| v.as_str()) {
+        Some(n) if !n.is_empty() => n,
+        _ => return Ok("❌ Missing old_name argument".to_string()),
+    };
+    let new_name = match args.get("new_name").and_then(|v| v.as_str()) {
+        Some(n) if !n.is_empty() => n,
+        _ => return Ok("❌ Missing new_name argument".to_string()),
+    };
+    let path = match args.get("path").and_then(|v| v.as_str()) {
+        Some(p) => ctx.resolve_path(p),
+        None => return Ok("❌ Missing path argument".to_string()),
+    };
+    if !path.exists() {
+        return Ok(format!("❌ Path not found: {}", path.display()));
+    }
+    let language = match args.get("language").and_then(|v| v.as_str()) {
+        Some(lang) => lang.to_lowercase(),
+        None => match detect_lint_language(&path) {
+            Some(lang) => lang.to_string(),
+            None => {
+                return Ok(format!(
+                    "❌ Could not detect language for {}. Pass the language argument.",
+                    path.display()
+                ))
+            }
+        },
+    };
+    let symbol_type = match args.get("symbol_type").and_then(|v| v.as_str()) {
+        Some(t) => match crate::symbol_rename::SymbolType::parse(t) {
+            Ok(t) => Some(t),
+            Err(e) => return Ok(format!("❌ {}", e)),
+        },
+        None => None,
+    };
+    let dry_run = args.get("dry_run").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    let renamed = match crate::symbol_rename::rename_symbol(
+        &path,
+        &language,
+        old_name,
+        new_name,
+        symbol_type,
+        dry_run,
+    ) {
+        Ok(r) => r,
+        Err(e) => return Ok(format!("❌ {:#}", e)),
+    };
+    if renamed.is_empty() {
+        return Ok(format!("No occurrences of '{}' in {}", old_name, path.display()));
+    }
+
+    let summary: Vec<SymbolRename> = renamed
+        .into_iter()
+        .map(|(file, occurrences_replaced)| SymbolRename {
+            file: file
+                .strip_prefix(&ctx.working_directory)
+                .unwrap_or(&file)
+                .display()
+                .to_string(),
+            occurrences_replaced,
+        })
+        .collect();
+    let total: usize = summary.iter().map(|r| r.occurrences_replaced).sum();
+    let header = if dry_run {
+        format!(
+            "[DRY RUN] Would rename {} occurrences of '{}' to '{}' in {} files",
+            total,
+            old_name,
+            new_name,
+            summary.len()
+        )
+    } else {
+        format!(
+            "✅ Renamed {} occurrences of '{}' to '{}' in {} files",
+            total,
+            old_name,
+            new_name,
+            summary.len()
+        )
+    };
+    Ok(format!("{}\n{}", header, serde_json::to_string_pretty(&summary)?))
+}
+
+/// Execute the `dependency_graph` tool.
+pub async fn execute_dependency_graph<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing dependency_graph tool call");
+
+    let args = &tool_call.args;
+    let path = match args.get("path").and_then(|v| v.as_str()) {
+        Some(p) => ctx.resolve_path(p),
+        None => return Ok("❌ Missing path argument".to_string()),
+    };
+    if !path.exists() {
+        return Ok(format!("❌ Path not found: {}", path.display()));
+    }
+    let language = match args.get("language").and_then(|v| v.as_str()) {
+        Some(lang) => lang.to_lowercase(),
+        None => match detect_lint_language(&path) {
+            Some(lang) => lang.to_string(),
+            None => {
+                return Ok(format!(
+                    "❌ Could not detect language for {}. Pass the language argument.",
+                    path.display()
+                ))
+            }
+        },
+    };
+    let format = args.get("format").and_then(|v| v.as_str()).unwrap_or("text");
+    if !matches!(format, "dot" | "json" | "text") {
+        return Ok(format!(
+            "❌ Unknown format '{}': expected dot, json or text",
+            format
+        ));
+    }
+    let depth = args.get("depth").and_then(|v| v.as_u64()).map(|d| d as usize);
+
+    // A file is the entry point of a graph of its project (the working directory)
+    let (root, entry) = if path.is_file() {
+        (ctx.working_directory.clone(), Some(path.clone()))
+    } else {
+        (path.clone(), None)
+    };
+    let mut graph = match crate::dependency_graph::build_graph(&root, &language) {
+        Ok(graph) => graph,
+        Err(e) => return Ok(format!("❌ {:#}", e)),
+    };
+    if let Some(entry) = entry {
+        let entry = entry
+            .strip_prefix(&root)
+            .unwrap_or(&entry)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if !graph.edges.contains_key(&entry) {
+            return Ok(format!("❌ {} is not a {} file of {}", entry, language, root.display()));
+        }
+        graph = graph.reachable_from(&entry, depth);
+    } else if depth.is_some() {
+        return Ok("❌ depth needs path to be an entry file".to_string());
+    }
+
+    match format {
+        "json" => Ok(serde_json::to_string_pretty(&graph.to_json())?),
+        "text" => Ok(graph.to_text()),
+        _ => {
+            let output_path = match args.get("output_path").and_then(|v| v.as_str()) {
+                Some(p) => ctx.resolve_path(p),
+                None => crate::paths::get_dependency_graph_file(ctx.session_id),
+            };
+            if let Some(parent) = output_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            if let Err(e) = std::fs::write(&output_path, graph.to_dot()) {
+                return Ok(format!("❌ Failed to write {}: {}", output_path.display(), e));
+            }
+            Ok(format!(
+                "✅ Dependency graph of {} files and {} dependencies written to {}\n{}",
+                graph.file_count(),
+                graph.edge_count(),
+                output_path.display(),
+                crate::dependency_graph::format_cycles(&graph.cycles())
+            ))
+        }
+    }
+}
+
+/// Outcome of a single test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+/// A single test outcome, normalized across cargo test and pytest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TestResult {
+    pub name: String,
+    pub status: TestStatus,
+    pub duration_ms: u64,
+    pub stderr: String,
+}
+
+/// Parse libtest `--format json` events into test results.
+pub fn parse_libtest_json(output: &str) -> Vec<TestResult> {
+    let mut results = Vec::new();
+    for line in output.lines() {
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if event["type"] != "test" {
+            continue;
+        }
+        let status = match event["event"].as_str() {
+            Some("ok") => TestStatus::Passed,
+            Some("failed") => TestStatus::Failed,
+            Some("ignored") => TestStatus::Ignored,
+            _ => continue,
+        };
+        results.push(TestResult {
+            name: event["name"].as_str().unwrap_or_default().to_string(),
+            status,
+            duration_ms: (event["exec_time"].as_f64().unwrap_or(0.0) * 1000.0).round() as u64,
+            stderr: event["stdout"].as_str().unwrap_or_default().to_string(),
+        });
+    }
+    results
+}
+
+/// Parse a pytest-json-report report into test results.
+pub fn parse_pytest_report(report: &str) -> Result<Vec<TestResult>> {
+    let report: serde_json::Value =
+        serde_json::from_str(report).context("Invalid pytest JSON report")?;
+    Ok(report["tests"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|test| {
+            let status = match test["outcome"].as_str() {
+                Some("passed") => TestStatus::Passed,
+                Some("skipped") | Some("xfailed") => TestStatus::Ignored,
+                _ => TestStatus::Failed,
+            };
+            let duration: f64 = ["setup", "call", "teardown"]
+                .iter()
+                .filter_map(|stage| test[stage]["duration"].as_f64())
+                .sum();
+            TestResult {
+                name: test["nodeid"].as_str().unwrap_or_default().to_string(),
+                status,
+                duration_ms: (duration * 1000.0).round() as u64,
+                stderr: test["call"]["longrepr"].as_str().unwrap_or_default().to_string(),
+            }
+        })
+        .collect())
+}
+
+/// Build the test binaries with cargo, then run each one with libtest's JSON output.
+/// Doc tests are not run.
+async fn run_rust_tests(path: &Path, filter: Option<&str>) -> Result<Vec<TestResult>> {
+    let root = find_cargo_root(path)
+        .ok_or_else(|| anyhow::anyhow!("No Cargo.toml found for {}", path.display()))?;
+
+    let build = tokio::process::Command::new("cargo")
+        .args(["test", "--no-run", "--message-format=json"])
+        .current_dir(&root)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("Failed to run cargo test")?;
+    let build_output = String::from_utf8_lossy(&build.stdout);
+
+    let mut executables = Vec::new();
+    let mut errors = Vec::new();
+    for line in build_output.lines() {
+        let Ok(msg) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        match msg["reason"].as_str() {
+            Some("compiler-artifact") if msg["profile"]["test"] == true => {
+                if let Some(exe) = msg["executable"].as_str() {
+                    let manifest_dir = msg["manifest_path"]
+                        .as_str()
+                        .and_then(|p| Path::new(p).parent())
+                        .map(Path::to_path_buf)
+                        .unwrap_or_else(|| root.clone());
+                    executables.push((PathBuf::from(exe), manifest_dir));
+                }
+            }
+            Some("compiler-message") if msg["message"]["level"] == "error" => {
+                if let Some(rendered) = msg["message"]["rendered"].as_str() {
+                    errors.push(rendered.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    if !build.status.success() {
+        anyhow::bail!("Build failed:\n{}", errors.join("\n"));
+    }
+
+    let mut results = Vec::new();
+    for (exe, manifest_dir) in executables {
+        let mut cmd = tokio::process::Command::new(&exe);
+        // libtest's JSON format is unstable; RUSTC_BOOTSTRAP unlocks it on stable toolchains
+        cmd.args(["--format", "json", "-Z", "unstable-options", "--report-time"])
+            .env("RUSTC_BOOTSTRAP", "1")
+            .current_dir(manifest_dir)
+            .kill_on_drop(true);
+        if let Some(filter) = filter {
+            cmd.arg(filter);
+        }
+        let output = cmd
+            .output()
+            .await
+            .with_context(|| format!("Failed to run {}", exe.display()))?;
+        results.extend(parse_libtest_json(&String::from_utf8_lossy(&output.stdout)));
+    }
+    Ok(results)
+}
+
+/// Run pytest with the pytest-json-report plugin.
+async fn run_python_tests(path: &Path, filter: Option<&str>) -> Result<Vec<TestResult>> {
+    let report_path = std::env::temp_dir().join(format!(
+        "g3-pytest-report-{}-{}.json",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0)
+    ));
+
+    let mut cmd = tokio::process::Command::new("pytest");
+    cmd.arg("--json-report")
+        .arg(format!("--json-report-file={}", report_path.display()))
+        .arg("-q")
+        .kill_on_drop(true);
+    if let Some(filter) = filter {
+        cmd.args(["-k", filter]);
+    }
+    let output = cmd.arg(path).output().await.context("Failed to run pytest")?;
+
+    let report = match std::fs::read_to_string(&report_path) {
+        Ok(report) => report,
+        Err(_) => anyhow::bail!(
+            "pytest did not write a JSON report (is pytest-json-report installed?):\n{}",
+            String::from_utf8_lossy(&output.stdout)
+        ),
+    };
+    let _ = std::fs::remove_file(&report_path);
+    parse_pytest_report(&report)
+}
+
+/// Run the test suite for `language` at `path`.
+pub async fn run_tests(path: &Path, language: &str, filter: Option<&str>) -> Result<Vec<TestResult>> {
+    match language {
+        "rust" => run_rust_tests(path, filter).await,
+        "python" => run_python_tests(path, filter).await,
+        other => anyhow::bail!("Unsupported test language '{}'", other),
+    }
+}
+
+/// Summarize test results, followed by the output of each failure.
+pub fn format_test_results(results: &[TestResult]) -> String {
+    let count = |status| results.iter().filter(|r| r.status == status).count();
+    let mut out = format!(
+        "✅ {} passed, ❌ {} failed, ⏭ {} ignored",
+        count(TestStatus::Passed),
+        count(TestStatus::Failed),
+        count(TestStatus::Ignored)
+    );
+
+    for failure in results.iter().filter(|r| r.status == TestStatus::Failed) {
+        out.push_str(&format!("\n\n---- {} ({}ms) ----\n", failure.name, failure.duration_ms));
+        out.push_str(failure.stderr.trim_end());
+    }
+    out
+}
+
+/// Execute the `run_tests` tool.
+pub async fn execute_run_tests<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing run_tests tool call");
+
+    let path_str = tool_call
+        .args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .unwrap_or(".");
+    let path = ctx.resolve_path(path_str);
+    if !path.exists() {
+        return Ok(format!("❌ Path not found: {}", path.display()));
+    }
+
+    let language = match tool_call.args.get("language").and_then(|v| v.as_str()) {
+        Some(lang) => lang.to_lowercase(),
+        None => match detect_lint_language(&path) {
+            Some(lang) => lang.to_string(),
+            None => {
+                return Ok(format!(
+                    "❌ Could not detect language for {}. Pass the language argument.",
+                    path.display()
+                ))
+            }
+        },
+    };
+    let filter = tool_call.args.get("filter").and_then(|v| v.as_str());
+    let timeout_secs = tool_call
+        .args
+        .get("timeout_secs")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(120);
+
+    ctx.ui_writer
+        .print_context_status(&format!("🧪 Running {} tests...", language));
+
+    match tokio::time::timeout(
+        Duration::from_secs(timeout_secs),
+        run_tests(&path, &language, filter),
+    )
+    .await
+    {
+        Ok(Ok(results)) if results.is_empty() => Ok("⚠️ No tests were run".to_string()),
+        Ok(Ok(results)) => Ok(format_test_results(&results)),
+        Ok(Err(e)) => Ok(format!("❌ {}", e)),
+        Err(_) => Ok(format!("❌ Tests timed out after {}s", timeout_secs)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_structured_final_output_from_args() {
+        let output = StructuredFinalOutput::from_args(&serde_json::json!({
+            "summary": "Added caching",
+            "structured": true,
+            "files_changed": ["src/cache.rs", "src/lib.rs"],
+            "tests_run": true,
+            "tests_passed": 12,
+            "tests_failed": 0,
+            "next_steps": ["Tune the TTL"]
+        }));
+
+        assert_eq!(
+            output,
+            StructuredFinalOutput {
+                summary: "Added caching".to_string(),
+                files_changed: vec!["src/cache.rs".to_string(), "src/lib.rs".to_string()],
+                tests_run: true,
+                tests_passed: Some(12),
+                tests_failed: Some(0),
+                next_steps: vec!["Tune the TTL".to_string()],
+            }
+        );
+
+        let minimal = StructuredFinalOutput::from_args(&serde_json::json!({"summary": "Done"}));
+        assert_eq!(minimal.summary, "Done");
+        assert!(!minimal.tests_run);
+        assert_eq!(minimal.tests_passed, None);
+        assert!(minimal.files_changed.is_empty());
+    }
+
+    #[test]
+    fn test_structured_final_output_serialization() {
+        let output = StructuredFinalOutput {
+            summary: "Fixed the parser".to_string(),
+            files_changed: vec!["src/parser.rs".to_string()],
+            tests_run: true,
+            tests_passed: Some(3),
+            tests_failed: None,
+            next_steps: Vec::new(),
+        };
+
+        let rendered = output.render().unwrap();
+        let (summary, block) = rendered.split_once("\n\n```json\n").unwrap();
+        assert_eq!(summary, "Fixed the parser");
+        let json: serde_json::Value =
+            serde_json::from_str(block.strip_suffix("\n```").unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "summary": "Fixed the parser",
+                "files_changed": ["src/parser.rs"],
+                "tests_run": true,
+                "tests_passed": 3,
+                "tests_failed": null,
+                "next_steps": []
+            })
+        );
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session").join("final_output.json");
+        output.write_to(&path).unwrap();
+        let written: StructuredFinalOutput =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written, output);
+    }
+
+    #[test]
+    fn test_detect_lint_language() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("main.py");
+        std::fs::write(&file, "").unwrap();
+        assert_eq!(detect_lint_language(&file), Some("python"));
+        assert_eq!(detect_lint_language(dir.path()), None);
+
+        std::fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+        assert_eq!(detect_lint_language(dir.path()), Some("rust"));
+    }
+
+    #[test]
+    fn test_parse_ruff_output() {
+        let output = r#"[{"code":"F401","filename":"/tmp/a.py","location":{"row":1,"column":8},"message":"`os` imported but unused"}]"#;
+        let results = parse_ruff_output(output).unwrap();
+        assert_eq!(
+            results,
+            vec![LintResult {
+                file: "/tmp/a.py".into(),
+                line: 1,
+                col: 8,
+                severity: "warning".into(),
+                message: "`os` imported but unused".into(),
+                rule: "F401".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_eslint_output() {
+        let output = r#"[{"filePath":"/tmp/a.js","messages":[{"ruleId":"no-unused-vars","severity":2,"message":"'x' is assigned a value but never used.","line":1,"column":5}]}]"#;
+        let results = parse_eslint_output(output).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].severity, "error");
+        assert_eq!(results[0].rule, "no-unused-vars");
+        assert_eq!((results[0].line, results[0].col), (1, 5));
+    }
+
+    #[test]
+    fn test_clippy_reports_known_warning_in_temp_crate() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"lint_fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        let lib = dir.path().join("src/lib.rs");
+        std::fs::write(&lib, "pub fn f() {\n    let unused = 1;\n}\n").unwrap();
+
+        let results = run_linter(&lib, "rust", false).unwrap();
+        let warning = results
+            .iter()
+            .find(|r| r.rule == "unused_variables")
+            .expect("expected unused_variables warning");
+        assert_eq!(warning.severity, "warning");
+        assert_eq!((warning.line, warning.col), (2, 9));
+        assert!(warning.file.ends_with("lib.rs"));
+    }
+
+    #[test]
+    fn test_parse_libtest_json() {
+        let output = r#"{ "type": "suite", "event": "started", "test_count": 3 }
+{ "type": "test", "event": "started", "name": "tests::ok" }
+{ "type": "test", "name": "tests::ok", "event": "ok", "exec_time": 0.012 }
+{ "type": "test", "name": "tests::bad", "event": "failed", "exec_time": 0.001, "stdout": "assertion failed" }
+{ "type": "test", "name": "tests::skip", "event": "ignored" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 1 }"#;
+        let results = parse_libtest_json(output);
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].status, TestStatus::Passed);
+        assert_eq!(results[0].duration_ms, 12);
+        assert_eq!(results[1].status, TestStatus::Failed);
+        assert_eq!(results[1].stderr, "assertion failed");
+        assert_eq!(results[2].status, TestStatus::Ignored);
+    }
+
+    #[test]
+    fn test_parse_pytest_report() {
+        let report = r#"{"tests": [
+            {"nodeid": "test_a.py::test_ok", "outcome": "passed", "call": {"duration": 0.5}},
+            {"nodeid": "test_a.py::test_bad", "outcome": "failed", "call": {"duration": 0.25, "longrepr": "assert 1 == 2"}},
+            {"nodeid": "test_a.py::test_skip", "outcome": "skipped"}
+        ]}"#;
+        let results = parse_pytest_report(report).unwrap();
+
+        assert_eq!(results[0].duration_ms, 500);
+        assert_eq!(results[1].status, TestStatus::Failed);
+        assert_eq!(results[1].stderr, "assert 1 == 2");
+        assert_eq!(results[2].status, TestStatus::Ignored);
+    }
+
+    #[tokio::test]
+    async fn test_run_tests_reports_failing_test_in_temp_crate() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"tests_fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            r#"#[cfg(test)]
+mod tests {
+    #[test]
+    fn passes() {}
+
+    #[test]
+    fn fails() {
+        assert_eq!(1 + 1, 3, "math is broken");
+    }
+
+    #[test]
+    #[ignore]
+    fn skipped() {}
+}
+"#,
+        )
+        .unwrap();
+
+        let results = run_tests(dir.path(), "rust", None).await.unwrap();
+        let summary = format_test_results(&results);
+        assert!(summary.starts_with("✅ 1 passed, ❌ 1 failed, ⏭ 1 ignored"), "{}", summary);
+
+        let failure = results.iter().find(|r| r.status == TestStatus::Failed).unwrap();
+        assert_eq!(failure.name, "tests::fails");
+        assert!(failure.stderr.contains("math is broken"));
+        assert!(summary.contains("---- tests::fails"));
+
+        let filtered = run_tests(dir.path(), "rust", Some("passes")).await.unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].status, TestStatus::Passed);
+    }
+
+    fn search_tree() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "// setup\nfn parse_config() {}\n\nfn main() {\n    parse_config();\n}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("src/nested/util.py"), "def Parse_Config():\n    pass\n").unwrap();
+        std::fs::write(dir.path().join("build.log"), "parse_config failed\n").unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_text_search_matches_and_columns() {
+        let dir = search_tree();
+        let (matches, truncated) =
+            text_search(dir.path(), r"parse_\w+", &TextSearchOptions::default()).unwrap();
+        assert!(!truncated);
+        // build.log is gitignored and the Python name differs in case
+        let found: Vec<(&str, u32, u32)> = matches
+            .iter()
+            .map(|m| (m.file.as_str(), m.line, m.col))
+            .collect();
+        assert_eq!(found, vec![("src/lib.rs", 2, 4), ("src/lib.rs", 5, 5)]);
+        assert_eq!(matches[1].text, "    parse_config();");
+        assert!(matches[1].context_before.is_empty());
+    }
+
+    #[test]
+    fn test_text_search_case_insensitive_and_file_type() {
+        let dir = search_tree();
+        let options = TextSearchOptions {
+            case_insensitive: true,
+            file_type: Some("python".to_string()),
+            ..Default::default()
+        };
+        let (matches, _) = text_search(dir.path(), "parse_config", &options).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].file, "src/nested/util.py");
+
+        let options = TextSearchOptions {
+            file_type: Some("klingon".to_string()),
+            ..Default::default()
+        };
+        let err = text_search(dir.path(), "x", &options).unwrap_err();
+        assert!(format!("{:#}", err).contains("Unknown file type 'klingon'"));
+        assert!(text_search(dir.path(), "(", &TextSearchOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_text_search_context_and_limit() {
+        let dir = search_tree();
+        let options = TextSearchOptions {
+            context_lines: 1,
+            ..Default::default()
+        };
+        let (matches, _) = text_search(dir.path(), "parse_config", &options).unwrap();
+        assert_eq!(matches[0].context_before, vec!["// setup"]);
+        assert_eq!(matches[0].context_after, vec![""]);
+        assert_eq!(matches[1].context_before, vec!["fn main() {"]);
+        assert_eq!(matches[1].context_after, vec!["}"]);
+        assert_eq!(
+            format_search_matches(&matches),
+            "src/lib.rs-1- // setup\n\
+             src/lib.rs:2:4: fn parse_config() {}\n\
+             src/lib.rs-3- \n\
+             src/lib.rs-4- fn main() {\n\
+             src/lib.rs:5:5:     parse_config();\n\
+             src/lib.rs-6- }\n"
+        );
+
+        let options = TextSearchOptions {
+            max_results: 1,
+            ..Default::default()
+        };
+        let (matches, truncated) = text_search(dir.path(), "parse_config", &options).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert!(truncated);
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    fn env_lookup(name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+
+    #[test]
+    fn test_env_check_required_and_optional() {
+        std::env::set_var("G3_ENV_CHECK_TEST_API_KEY", "sk-ant-REDACTED");
+        std::env::set_var("G3_ENV_CHECK_TEST_REGION", "eu-west-1");
+        std::env::remove_var("G3_ENV_CHECK_TEST_MISSING");
+        std::env::remove_var("G3_ENV_CHECK_TEST_LOG_LEVEL");
+
+        let report = check_env(
+            &names(&["G3_ENV_CHECK_TEST_API_KEY", "G3_ENV_CHECK_TEST_MISSING"]),
+            &names(&["G3_ENV_CHECK_TEST_REGION", "G3_ENV_CHECK_TEST_LOG_LEVEL"]),
+            &BTreeMap::new(),
+            env_lookup,
+        );
+        assert!(!report.is_ok());
+        assert_eq!(report.missing_required(), vec!["G3_ENV_CHECK_TEST_MISSING"]);
+        // Secrets are masked, plain values are shown
+        assert_eq!(report.vars[0].display_value.as_deref(), Some("[masked, 33 chars]"));
+        assert_eq!(report.vars[2].display_value.as_deref(), Some("eu-west-1"));
+        assert_eq!(report.vars[3].display_value, None);
+
+        let output = format_env_check(&report);
+        assert!(output.starts_with(
+            "❌ Missing required environment variables: G3_ENV_CHECK_TEST_MISSING\n"
+        ));
+        assert!(output.contains("  ⚪ G3_ENV_CHECK_TEST_LOG_LEVEL not set\n"));
+        assert!(!output.contains("sk-ant"));
+        assert!(output.contains("shell_env"));
+    }
+
+    #[test]
+    fn test_env_check_validates_format() {
+        std::env::set_var("G3_ENV_CHECK_TEST_PORT", "8080");
+        std::env::set_var("G3_ENV_CHECK_TEST_DB_PORT", "fivethousand");
+
+        let validate: BTreeMap<String, String> = [
+            ("G3_ENV_CHECK_TEST_PORT", "^[0-9]+$"),
+            ("G3_ENV_CHECK_TEST_DB_PORT", "^[0-9]+$"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let report = check_env(&names(&["G3_ENV_CHECK_TEST_PORT"]), &[], &validate, env_lookup);
+        assert_eq!(report.invalid(), vec!["G3_ENV_CHECK_TEST_DB_PORT"]);
+        assert_eq!(report.vars[0].validation, Some(Ok(())));
+        assert!(format_env_check(&report)
+            .contains("❌ G3_ENV_CHECK_TEST_DB_PORT = fivethousand (does not match ^[0-9]+$)"));
+
+        std::env::set_var("G3_ENV_CHECK_TEST_DB_PORT", "5432");
+        let report = check_env(&names(&["G3_ENV_CHECK_TEST_PORT"]), &[], &validate, env_lookup);
+        assert!(report.is_ok());
+        assert!(format_env_check(&report)
+            .starts_with("✅ Environment OK: 1 required set, 1 of 1 optional set\n"));
+    }
+}
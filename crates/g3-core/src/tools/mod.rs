@@ -4,15 +4,25 @@
 //! Tools are organized by category:
 //! - `shell` - Shell command execution and background processes
 //! - `file_ops` - File reading, writing, and editing
+//! - `git` - Structured git status, diff, and commit
+//! - `http` - Plain HTTP requests
+//! - `database` - Read-only SQLite queries
 //! - `todo` - TODO list management
+//! - `macros` - Recording and replaying sequences of tool calls
+//! - `vision` - Comparing screenshots
 //! - `webdriver` - Browser automation via WebDriver
 //! - `misc` - Other tools (screenshots, code search, etc.)
 
+pub mod database;
 pub mod executor;
 pub mod file_ops;
+pub mod git;
+pub mod http;
+pub mod macros;
 pub mod misc;
 pub mod shell;
 pub mod todo;
+pub mod vision;
 pub mod webdriver;
 
 
@@ -131,6 +131,221 @@ pub async fn execute_todo_write<W: UiWriter>(
     }
 }
 
+/// Priority of a TODO item, from a `[HIGH]`/`[!!]`, `[MEDIUM]`/`[!]` or
+/// `[LOW]` marker after the checkbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoPriority {
+    High,
+    Medium,
+    Low,
+}
+
+impl TodoPriority {
+    fn parse_marker(marker: &str) -> Option<Self> {
+        match marker.to_ascii_uppercase().as_str() {
+            "HIGH" | "!!" => Some(Self::High),
+            "MEDIUM" | "MED" | "!" => Some(Self::Medium),
+            "LOW" => Some(Self::Low),
+            _ => None,
+        }
+    }
+
+    /// Sort key: high, medium, unmarked, then low.
+    fn rank(priority: Option<Self>) -> u8 {
+        match priority {
+            Some(Self::High) => 0,
+            Some(Self::Medium) => 1,
+            None => 2,
+            Some(Self::Low) => 3,
+        }
+    }
+}
+
+/// A checkbox line in the TODO list, e.g. `- [ ] [HIGH] Task description`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoItem {
+    /// 1-based position among the list's items
+    pub index: usize,
+    /// 0-based line number in the TODO content
+    pub line: usize,
+    /// Leading whitespace; nested items are indented
+    pub indent: usize,
+    pub done: bool,
+    pub priority: Option<TodoPriority>,
+    /// Task text without the checkbox and priority marker
+    pub text: String,
+}
+
+/// Parse a checkbox line into (done, priority, text).
+fn parse_todo_line(line: &str) -> Option<(bool, Option<TodoPriority>, String)> {
+    let rest = line.trim_start().strip_prefix("- [")?;
+    let (mark, rest) = rest.split_once(']')?;
+    let done = match mark {
+        " " => false,
+        "x" | "X" => true,
+        _ => return None,
+    };
+
+    let rest = rest.trim_start();
+    let (priority, text) = match rest
+        .strip_prefix('[')
+        .and_then(|r| r.split_once(']'))
+        .and_then(|(marker, text)| Some((TodoPriority::parse_marker(marker)?, text)))
+    {
+        Some((priority, text)) => (Some(priority), text.trim_start()),
+        None => (None, rest),
+    };
+    Some((done, priority, text.to_string()))
+}
+
+/// Parse every checkbox item in `content`. Other lines are ignored.
+pub fn parse_todo_items(content: &str) -> Vec<TodoItem> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(line, text)| {
+            let (done, priority, item_text) = parse_todo_line(text)?;
+            Some((line, text.len() - text.trim_start().len(), done, priority, item_text))
+        })
+        .enumerate()
+        .map(|(i, (line, indent, done, priority, text))| TodoItem {
+            index: i + 1,
+            line,
+            indent,
+            done,
+            priority,
+            text,
+        })
+        .collect()
+}
+
+/// Items whose text contains `query`, case-insensitively.
+pub fn search_todo_items(content: &str, query: &str) -> Vec<TodoItem> {
+    let query = query.to_lowercase();
+    parse_todo_items(content)
+        .into_iter()
+        .filter(|item| item.text.to_lowercase().contains(&query))
+        .collect()
+}
+
+/// Reorder top-level items so high priority ones come first, then medium,
+/// unmarked and low. Nested lines move with their parent item, and headings
+/// or other unindented lines stay in place, so each section is sorted on its
+/// own. The order within a priority is kept.
+pub fn prioritize_todo(content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut output: Vec<&str> = Vec::with_capacity(lines.len());
+    // Top-level items (with their nested lines) waiting to be sorted
+    let mut group: Vec<(u8, Vec<&str>)> = Vec::new();
+
+    fn flush<'a>(group: &mut Vec<(u8, Vec<&'a str>)>, output: &mut Vec<&'a str>) {
+        group.sort_by_key(|(rank, _)| *rank);
+        for (_, block) in group.drain(..) {
+            output.extend(block);
+        }
+    }
+
+    for line in lines {
+        let indented = line.starts_with([' ', '\t']);
+        match parse_todo_line(line) {
+            Some((_, priority, _)) if !indented => {
+                group.push((TodoPriority::rank(priority), vec![line]));
+            }
+            _ if indented && !group.is_empty() => {
+                if let Some((_, block)) = group.last_mut() {
+                    block.push(line);
+                }
+            }
+            _ => {
+                flush(&mut group, &mut output);
+                output.push(line);
+            }
+        }
+    }
+    flush(&mut group, &mut output);
+
+    let mut result = output.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+/// Read the TODO file, keeping the in-memory copy in sync.
+/// Returns `None` if there is no TODO file.
+async fn load_todo<W: UiWriter>(ctx: &ToolContext<'_, W>) -> Result<Option<String>> {
+    let todo_path = ctx.get_todo_path();
+    if !todo_path.exists() {
+        *ctx.todo_content.write().await = String::new();
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&todo_path)?;
+    *ctx.todo_content.write().await = content.clone();
+    Ok(Some(content))
+}
+
+/// Execute the `todo_search` tool.
+pub async fn execute_todo_search<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &mut ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing todo_search tool call");
+
+    let query = match tool_call.args.get("query").and_then(|v| v.as_str()) {
+        Some(q) if !q.trim().is_empty() => q.trim(),
+        _ => return Ok("❌ Missing query argument".to_string()),
+    };
+
+    let content = match load_todo(ctx).await {
+        Ok(Some(content)) => content,
+        Ok(None) => return Ok("📝 TODO list is empty (no todo.g3.md file found)".to_string()),
+        Err(e) => return Ok(format!("❌ Failed to read todo.g3.md: {}", e)),
+    };
+
+    let items = search_todo_items(&content, query);
+    if items.is_empty() {
+        return Ok(format!("📝 No TODO items match '{}'", query));
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut result = format!("📝 {} TODO item(s) match '{}':\n", items.len(), query);
+    for item in &items {
+        result.push_str(&format!("{}: {}\n", item.index, lines[item.line].trim()));
+    }
+    Ok(result)
+}
+
+/// Execute the `todo_prioritize` tool.
+pub async fn execute_todo_prioritize<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &mut ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing todo_prioritize tool call");
+    let _ = tool_call; // unused but kept for consistency
+
+    let content = match load_todo(ctx).await {
+        Ok(Some(content)) => content,
+        Ok(None) => return Ok("📝 TODO list is empty (no todo.g3.md file found)".to_string()),
+        Err(e) => return Ok(format!("❌ Failed to read todo.g3.md: {}", e)),
+    };
+
+    let prioritized = prioritize_todo(&content);
+    if prioritized == content {
+        return Ok(format!("✅ TODO list is already in priority order:\n{}", content));
+    }
+
+    match std::fs::write(ctx.get_todo_path(), &prioritized) {
+        Ok(_) => {
+            *ctx.todo_content.write().await = prioritized.clone();
+            for line in prioritized.lines() {
+                ctx.ui_writer.print_tool_output_line(line);
+            }
+            Ok(format!("✅ TODO list reordered by priority:\n{}", prioritized))
+        }
+        Err(e) => Ok(format!("❌ Failed to write todo.g3.md: {}", e)),
+    }
+}
+
 /// Check if the TODO list is stale (generated from a different requirements file).
 /// Returns Some(message) if staleness was detected and handled, None otherwise.
 fn check_todo_staleness<W: UiWriter>(
@@ -193,3 +408,74 @@ fn check_todo_staleness<W: UiWriter>(
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIXED: &str = "# Plan
+
+- [ ] [LOW] Polish README
+- [x] Set up CI
+- [ ] [!!] Fix data loss on save
+  - [ ] Reproduce with a large file
+- [ ] [MEDIUM] Add search to settings
+- [ ] [HIGH] Patch auth bypass
+
+## Later
+- [ ] [LOW] Dark mode
+- [ ] [!] Search result caching
+";
+
+    #[test]
+    fn test_parse_todo_items() {
+        let items = parse_todo_items(MIXED);
+        assert_eq!(items.len(), 8);
+
+        assert_eq!(items[0].priority, Some(TodoPriority::Low));
+        assert_eq!(items[0].text, "Polish README");
+        assert!(items[1].done);
+        assert_eq!(items[1].priority, None);
+        assert_eq!(items[2].priority, Some(TodoPriority::High));
+        assert_eq!(items[2].text, "Fix data loss on save");
+        assert_eq!(items[3].indent, 2);
+        assert_eq!(items[3].line, 5);
+        assert_eq!(items[7].priority, Some(TodoPriority::Medium));
+
+        // Unknown bracketed text is part of the task
+        let items = parse_todo_items("- [ ] [WIP] Refactor");
+        assert_eq!(items[0].priority, None);
+        assert_eq!(items[0].text, "[WIP] Refactor");
+    }
+
+    #[test]
+    fn test_search_todo_items() {
+        let items = search_todo_items(MIXED, "SEARCH");
+        let indices: Vec<usize> = items.iter().map(|i| i.index).collect();
+        assert_eq!(indices, vec![5, 8]);
+        assert_eq!(items[0].text, "Add search to settings");
+
+        assert!(search_todo_items(MIXED, "nothing like this").is_empty());
+    }
+
+    #[test]
+    fn test_prioritize_todo() {
+        let expected = "# Plan
+
+- [ ] [!!] Fix data loss on save
+  - [ ] Reproduce with a large file
+- [ ] [HIGH] Patch auth bypass
+- [ ] [MEDIUM] Add search to settings
+- [x] Set up CI
+- [ ] [LOW] Polish README
+
+## Later
+- [ ] [!] Search result caching
+- [ ] [LOW] Dark mode
+";
+        assert_eq!(prioritize_todo(MIXED), expected);
+        // Already sorted lists are unchanged
+        assert_eq!(prioritize_todo(expected), expected);
+        assert_eq!(prioritize_todo("- [ ] only"), "- [ ] only");
+    }
+}
@@ -0,0 +1,238 @@
+//! Image tools: vision_compare_screenshots.
+
+use anyhow::{bail, Context, Result};
+use image::{Rgba, RgbaImage};
+use std::path::Path;
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+/// Default `threshold` of `vision_compare_screenshots`.
+pub const DEFAULT_PIXEL_THRESHOLD: f32 = 10.0;
+
+/// Colour of changed pixels in the diff image.
+const CHANGED_PIXEL: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+/// How two images of the same size differ.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageDiff {
+    /// Pixels whose difference is above the threshold
+    pub changed_pixels: u64,
+    pub total_pixels: u64,
+    /// Largest difference of any pixel, 0-255
+    pub max_delta: u8,
+}
+
+impl ImageDiff {
+    /// Share of changed pixels, in percent.
+    pub fn changed_percent(&self) -> f64 {
+        if self.total_pixels == 0 {
+            return 0.0;
+        }
+        self.changed_pixels as f64 * 100.0 / self.total_pixels as f64
+    }
+
+    /// `✅ Images match ...` or `❌ N pixels differ ...`.
+    pub fn summary(&self) -> String {
+        if self.changed_pixels == 0 {
+            return "✅ Images match (0 pixels differ)".to_string();
+        }
+        format!(
+            "❌ {} pixels differ ({:.2}% of image), max delta: {}",
+            self.changed_pixels,
+            self.changed_percent(),
+            self.max_delta
+        )
+    }
+}
+
+/// Difference between two pixels: the L1 distance of their RGBA channels,
+/// divided by the number of channels so it stays within 0-255.
+fn pixel_delta(a: &Rgba<u8>, b: &Rgba<u8>) -> u8 {
+    let sum: u32 = a
+        .0
+        .iter()
+        .zip(b.0.iter())
+        .map(|(x, y)| x.abs_diff(*y) as u32)
+        .sum();
+    (sum / 4) as u8
+}
+
+/// Compare `a` and `b` pixel by pixel. A pixel counts as changed when its
+/// delta is above `threshold`. Also returns a diff image: `a` in faded grey
+/// with the changed pixels in red.
+pub fn compare_images(
+    a: &RgbaImage,
+    b: &RgbaImage,
+    threshold: f32,
+) -> Result<(ImageDiff, RgbaImage)> {
+    if a.dimensions() != b.dimensions() {
+        bail!(
+            "Images have different sizes: {}x{} vs {}x{}",
+            a.width(),
+            a.height(),
+            b.width(),
+            b.height()
+        );
+    }
+
+    let mut diff = ImageDiff {
+        changed_pixels: 0,
+        total_pixels: a.width() as u64 * a.height() as u64,
+        max_delta: 0,
+    };
+    let mut highlighted = RgbaImage::new(a.width(), a.height());
+    for ((pa, pb), out) in a.pixels().zip(b.pixels()).zip(highlighted.pixels_mut()) {
+        let delta = pixel_delta(pa, pb);
+        diff.max_delta = diff.max_delta.max(delta);
+        if delta as f32 > threshold {
+            diff.changed_pixels += 1;
+            *out = CHANGED_PIXEL;
+        } else {
+            let [r, g, b, _] = pa.0;
+            let grey = ((r as u16 + g as u16 + b as u16) / 3) as u8;
+            let faded = 255 - (255 - grey) / 3;
+            *out = Rgba([faded, faded, faded, 255]);
+        }
+    }
+    Ok((diff, highlighted))
+}
+
+/// Compare the images at `path_a` and `path_b`, saving the diff image to
+/// `output_path` if given.
+pub fn compare_screenshots(
+    path_a: &Path,
+    path_b: &Path,
+    threshold: f32,
+    output_path: Option<&Path>,
+) -> Result<ImageDiff> {
+    let load = |path: &Path| {
+        image::open(path)
+            .map(|image| image.to_rgba8())
+            .with_context(|| format!("Failed to load image '{}'", path.display()))
+    };
+    let (diff, highlighted) = compare_images(&load(path_a)?, &load(path_b)?, threshold)?;
+    if let Some(output_path) = output_path {
+        highlighted
+            .save(output_path)
+            .with_context(|| format!("Failed to save diff image '{}'", output_path.display()))?;
+    }
+    Ok(diff)
+}
+
+/// Execute the `vision_compare_screenshots` tool.
+pub async fn execute_vision_compare_screenshots<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing vision_compare_screenshots tool call");
+
+    let path_a = match tool_call.args.get("path_a").and_then(|v| v.as_str()) {
+        Some(p) => ctx.resolve_path(p),
+        None => return Ok("❌ Missing path_a argument".to_string()),
+    };
+    let path_b = match tool_call.args.get("path_b").and_then(|v| v.as_str()) {
+        Some(p) => ctx.resolve_path(p),
+        None => return Ok("❌ Missing path_b argument".to_string()),
+    };
+    let threshold = match tool_call.args.get("threshold") {
+        None | Some(serde_json::Value::Null) => DEFAULT_PIXEL_THRESHOLD,
+        Some(v) => match v.as_f64() {
+            Some(t) if (0.0..=255.0).contains(&t) => t as f32,
+            _ => return Ok("❌ threshold must be a number between 0 and 255".to_string()),
+        },
+    };
+    let output_path = tool_call
+        .args
+        .get("output_path")
+        .and_then(|v| v.as_str())
+        .map(|p| ctx.resolve_path(p));
+
+    match compare_screenshots(&path_a, &path_b, threshold, output_path.as_deref()) {
+        Ok(diff) => match output_path {
+            Some(output_path) => Ok(format!(
+                "{}\nDiff image saved to: {}",
+                diff.summary(),
+                output_path.display()
+            )),
+            None => Ok(diff.summary()),
+        },
+        Err(e) => Ok(format!("❌ {:#}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A 10x10 white PNG with `changed` pixels of the first row drawn in `colour`.
+    fn write_png(dir: &TempDir, name: &str, changed: u32, colour: [u8; 3]) -> std::path::PathBuf {
+        let mut image = RgbaImage::from_pixel(10, 10, Rgba([255, 255, 255, 255]));
+        for x in 0..changed {
+            let [r, g, b] = colour;
+            image.put_pixel(x, 0, Rgba([r, g, b, 255]));
+        }
+        let path = dir.path().join(name);
+        image.save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_identical_screenshots_match() {
+        let dir = TempDir::new().unwrap();
+        let a = write_png(&dir, "a.png", 0, [0, 0, 0]);
+        let b = write_png(&dir, "b.png", 0, [0, 0, 0]);
+
+        let diff = compare_screenshots(&a, &b, DEFAULT_PIXEL_THRESHOLD, None).unwrap();
+        assert_eq!(diff.changed_pixels, 0);
+        assert_eq!(diff.total_pixels, 100);
+        assert_eq!(diff.max_delta, 0);
+        assert_eq!(diff.summary(), "✅ Images match (0 pixels differ)");
+    }
+
+    #[test]
+    fn test_different_screenshots_are_counted_and_highlighted() {
+        let dir = TempDir::new().unwrap();
+        let a = write_png(&dir, "a.png", 0, [0, 0, 0]);
+        let b = write_png(&dir, "b.png", 5, [0, 0, 0]);
+        let out = dir.path().join("diff.png");
+
+        let diff = compare_screenshots(&a, &b, DEFAULT_PIXEL_THRESHOLD, Some(&out)).unwrap();
+        assert_eq!(diff.changed_pixels, 5);
+        // White to black changes three of the four channels fully
+        assert_eq!(diff.max_delta, 191);
+        assert_eq!(diff.summary(), "❌ 5 pixels differ (5.00% of image), max delta: 191");
+
+        let highlighted = image::open(&out).unwrap().to_rgba8();
+        assert_eq!(*highlighted.get_pixel(0, 0), CHANGED_PIXEL);
+        assert_ne!(*highlighted.get_pixel(5, 0), CHANGED_PIXEL);
+    }
+
+    #[test]
+    fn test_small_changes_below_threshold_are_ignored() {
+        let dir = TempDir::new().unwrap();
+        let a = write_png(&dir, "a.png", 0, [0, 0, 0]);
+        let b = write_png(&dir, "b.png", 3, [250, 250, 250]);
+
+        let diff = compare_screenshots(&a, &b, DEFAULT_PIXEL_THRESHOLD, None).unwrap();
+        assert_eq!(diff.changed_pixels, 0);
+        assert_eq!(diff.max_delta, 3);
+        assert_eq!(compare_screenshots(&a, &b, 0.0, None).unwrap().changed_pixels, 3);
+    }
+
+    #[test]
+    fn test_size_mismatch_and_missing_files_fail() {
+        let dir = TempDir::new().unwrap();
+        let a = write_png(&dir, "a.png", 0, [0, 0, 0]);
+        let small = dir.path().join("small.png");
+        RgbaImage::new(4, 4).save(&small).unwrap();
+
+        let err = compare_screenshots(&a, &small, DEFAULT_PIXEL_THRESHOLD, None).unwrap_err();
+        assert_eq!(err.to_string(), "Images have different sizes: 10x10 vs 4x4");
+        assert!(compare_screenshots(&a, &dir.path().join("missing.png"), 10.0, None).is_err());
+    }
+}
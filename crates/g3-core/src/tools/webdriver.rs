@@ -1,12 +1,14 @@
 //! WebDriver browser automation tools.
 
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use anyhow::Result;
-use crate::computer_control::WebDriverController;
+use crate::computer_control::{Cookie, WebDriverController, WebElement};
 use tracing::{debug, warn};
 
 use crate::ui_writer::UiWriter;
-use crate::webdriver_session::WebDriverSession;
+use crate::webdriver_session::{WebDriverSession, WebDriverSessionState};
 use crate::ToolCall;
 
 use super::executor::ToolContext;
@@ -16,6 +18,9 @@ use super::executor::ToolContext;
 // ─────────────────────────────────────────────────────────────────────────────
 
 /// Acquire the WebDriver session, returning an error message if unavailable.
+///
+/// If no session is active yet, one is started with the configured browser and
+/// kept for subsequent tool calls.
 async fn get_session<W: UiWriter>(
     ctx: &ToolContext<'_, W>,
 ) -> Result<Arc<tokio::sync::Mutex<WebDriverSession>>, String> {
@@ -23,11 +28,79 @@ async fn get_session<W: UiWriter>(
         return Err("❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string());
     }
 
+    if let Some(s) = ctx.webdriver_session.read().await.as_ref() {
+        return Ok(s.clone());
+    }
+
+    debug!("No active WebDriver session, starting one on first use");
+    let start_message = match start_browser(ctx, ctx.config.webdriver.browser.clone()).await {
+        Ok(msg) => msg,
+        Err(e) => format!("❌ Failed to start WebDriver session: {}", e),
+    };
+
     let session_guard = ctx.webdriver_session.read().await;
     match session_guard.as_ref() {
         Some(s) => Ok(s.clone()),
-        None => Err("❌ No active WebDriver session. Call webdriver_start first.".to_string()),
+        None => Err(start_message),
+    }
+}
+
+/// Record the current URL and window handles after the page may have changed.
+async fn record_session_state<W: UiWriter>(ctx: &ToolContext<'_, W>, driver: &WebDriverSession) {
+    let current_url = driver.current_url().await.ok();
+    let window_handles = driver.window_handles().await.unwrap_or_default();
+    let mut state = ctx.webdriver_state.write().await;
+    state.current_url = current_url;
+    state.window_handles = window_handles;
+}
+
+/// Record state after a navigation, back, forward or refresh, which also
+/// return the driver to the top-level page.
+async fn record_page_load<W: UiWriter>(ctx: &ToolContext<'_, W>, driver: &WebDriverSession) {
+    record_session_state(ctx, driver).await;
+    ctx.webdriver_state.write().await.frame_path.clear();
+}
+
+/// Prefix `output` of a WebDriver tool with the frame the session is in, if any.
+pub async fn with_frame_label<W: UiWriter>(ctx: &ToolContext<'_, W>, output: String) -> String {
+    match ctx.webdriver_state.read().await.frame_label() {
+        Some(label) => format!("{} {}", label, output),
+        None => output,
+    }
+}
+
+/// Quit the active session and stop the driver process.
+/// Returns `Ok(false)` if there was no session to close.
+async fn close_session<W: UiWriter>(ctx: &ToolContext<'_, W>) -> Result<bool, String> {
+    let session = match ctx.webdriver_session.write().await.take() {
+        Some(s) => s,
+        None => return Ok(false),
+    };
+
+    let mutex = match Arc::try_unwrap(session) {
+        Ok(mutex) => mutex,
+        Err(session) => {
+            // Still in use elsewhere - put it back rather than leaking the browser
+            *ctx.webdriver_session.write().await = Some(session);
+            return Err("❌ Cannot quit: WebDriver session is still in use".to_string());
+        }
+    };
+
+    if let Err(e) = mutex.into_inner().quit().await {
+        return Err(format!("❌ Failed to quit WebDriver: {}", e));
+    }
+    debug!("WebDriver session closed successfully");
+
+    if let Some(mut process) = ctx.webdriver_process.write().await.take() {
+        if let Err(e) = process.kill().await {
+            warn!("Failed to kill WebDriver process: {}", e);
+        } else {
+            debug!("WebDriver process terminated");
+        }
     }
+
+    *ctx.webdriver_state.write().await = WebDriverSessionState::default();
+    Ok(true)
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -40,7 +113,6 @@ pub async fn execute_webdriver_start<W: UiWriter>(
     ctx: &ToolContext<'_, W>,
 ) -> Result<String> {
     debug!("Processing webdriver_start tool call");
-    let _ = tool_call; // unused
 
     if !ctx.config.webdriver.enabled {
         return Ok("❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string());
@@ -54,11 +126,32 @@ pub async fn execute_webdriver_start<W: UiWriter>(
     }
     drop(session_guard);
 
-    // Determine which browser to use based on config
+    // An explicit `browser` arg overrides the configured browser
+    use g3_config::WebDriverBrowser;
+    let browser = match tool_call.args.get("browser").and_then(|v| v.as_str()) {
+        Some("chrome") => WebDriverBrowser::ChromeHeadless,
+        Some("firefox") => WebDriverBrowser::FirefoxHeadless,
+        Some(other) => {
+            return Ok(format!(
+                "❌ Unsupported browser '{}'. Use 'chrome' or 'firefox'.",
+                other
+            ))
+        }
+        None => ctx.config.webdriver.browser.clone(),
+    };
+
+    start_browser(ctx, browser).await
+}
+
+async fn start_browser<W: UiWriter>(
+    ctx: &ToolContext<'_, W>,
+    browser: g3_config::WebDriverBrowser,
+) -> Result<String> {
     use g3_config::WebDriverBrowser;
-    match &ctx.config.webdriver.browser {
+    match browser {
         WebDriverBrowser::Safari => start_safari_driver(ctx).await,
         WebDriverBrowser::ChromeHeadless => start_chrome_driver(ctx).await,
+        WebDriverBrowser::FirefoxHeadless => start_firefox_driver(ctx).await,
     }
 }
 
@@ -195,6 +288,84 @@ async fn start_chrome_driver<W: UiWriter>(ctx: &ToolContext<'_, W>) -> Result<St
     ))
 }
 
+async fn start_firefox_driver<W: UiWriter>(ctx: &ToolContext<'_, W>) -> Result<String> {
+    let port = ctx.config.webdriver.firefox_port;
+
+    // Start geckodriver process
+    let driver_result = tokio::process::Command::new("geckodriver")
+        .arg("--port")
+        .arg(port.to_string())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+
+    let mut webdriver_process = match driver_result {
+        Ok(process) => process,
+        Err(e) => {
+            return Ok(format!(
+                "❌ Failed to start geckodriver: {}\n\n\
+                Make sure geckodriver is installed and in your PATH.\n\n\
+                Install with:\n  \
+                - macOS: brew install geckodriver\n  \
+                - Linux: apt install firefox-geckodriver\n  \
+                - Or download from: https://github.com/mozilla/geckodriver/releases",
+                e
+            ));
+        }
+    };
+
+    // Wait for geckodriver to be ready with retry loop
+    let max_retries = 10;
+    let mut last_error = None;
+
+    for attempt in 0..max_retries {
+        // Wait before each attempt (200ms between retries, total max ~2s)
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+
+        // Try to connect to GeckoDriver in headless mode (with optional custom binary)
+        let driver_result = crate::computer_control::FirefoxDriver::with_port_headless_and_binary(
+            port,
+            ctx.config.webdriver.firefox_binary.as_deref(),
+        )
+        .await;
+
+        match driver_result {
+            Ok(driver) => {
+                let session =
+                    std::sync::Arc::new(tokio::sync::Mutex::new(WebDriverSession::Firefox(driver)));
+                *ctx.webdriver_session.write().await = Some(session);
+                *ctx.webdriver_process.write().await = Some(webdriver_process);
+
+                return Ok(
+                    "✅ WebDriver session started successfully! Firefox is running in headless mode (no visible window)."
+                        .to_string(),
+                );
+            }
+            Err(e) => {
+                last_error = Some(e);
+                if attempt < max_retries - 1 {
+                    continue;
+                }
+            }
+        }
+    }
+
+    // All retries failed
+    let _ = webdriver_process.kill().await;
+    let error_msg = last_error
+        .map(|e| e.to_string())
+        .unwrap_or_else(|| "Unknown error".to_string());
+    Ok(format!(
+        "❌ Failed to connect to GeckoDriver after {} attempts: {}\n\n\
+        This might be because:\n  \
+        - Firefox is not installed\n  \
+        - GeckoDriver version doesn't support the installed Firefox\n  \
+        - Port {} is already in use\n\n\
+        Make sure Firefox and GeckoDriver are installed and compatible.",
+        max_retries, error_msg, port
+    ))
+}
+
 /// Execute the `webdriver_navigate` tool.
 pub async fn execute_webdriver_navigate<W: UiWriter>(
     tool_call: &ToolCall,
@@ -214,7 +385,14 @@ pub async fn execute_webdriver_navigate<W: UiWriter>(
 
     let mut driver = session.lock().await;
     match driver.navigate(url).await {
-        Ok(_) => Ok(format!("✅ Navigated to {}", url)),
+        Ok(_) => {
+            record_page_load(ctx, &driver).await;
+            // Best effort: capture stays off for pages that reject scripts
+            if let Err(e) = install_network_log(&mut *driver).await {
+                debug!("Could not start network capture on {}: {}", url, e);
+            }
+            Ok(format!("✅ Navigated to {}", url))
+        }
         Err(e) => Ok(format!("❌ Failed to navigate: {}", e)),
     }
 }
@@ -343,7 +521,11 @@ pub async fn execute_webdriver_click<W: UiWriter>(
     let mut driver = session.lock().await;
     match driver.find_element(selector).await {
         Ok(mut elem) => match elem.click().await {
-            Ok(_) => Ok(format!("✅ Clicked element '{}'", selector)),
+            Ok(_) => {
+                // Clicks can follow links or open new windows
+                record_session_state(ctx, &driver).await;
+                Ok(format!("✅ Clicked element '{}'", selector))
+            }
             Err(e) => Ok(format!("❌ Failed to click element: {}", e)),
         },
         Err(e) => Ok(format!("❌ Failed to find element '{}': {}", selector, e)),
@@ -520,7 +702,10 @@ pub async fn execute_webdriver_back<W: UiWriter>(
 
     let mut driver = session.lock().await;
     match driver.back().await {
-        Ok(_) => Ok("✅ Navigated back".to_string()),
+        Ok(_) => {
+            record_page_load(ctx, &driver).await;
+            Ok("✅ Navigated back".to_string())
+        }
         Err(e) => Ok(format!("❌ Failed to navigate back: {}", e)),
     }
 }
@@ -540,7 +725,10 @@ pub async fn execute_webdriver_forward<W: UiWriter>(
 
     let mut driver = session.lock().await;
     match driver.forward().await {
-        Ok(_) => Ok("✅ Navigated forward".to_string()),
+        Ok(_) => {
+            record_page_load(ctx, &driver).await;
+            Ok("✅ Navigated forward".to_string())
+        }
         Err(e) => Ok(format!("❌ Failed to navigate forward: {}", e)),
     }
 }
@@ -560,7 +748,10 @@ pub async fn execute_webdriver_refresh<W: UiWriter>(
 
     let mut driver = session.lock().await;
     match driver.refresh().await {
-        Ok(_) => Ok("✅ Page refreshed".to_string()),
+        Ok(_) => {
+            record_page_load(ctx, &driver).await;
+            Ok("✅ Page refreshed".to_string())
+        }
         Err(e) => Ok(format!("❌ Failed to refresh page: {}", e)),
     }
 }
@@ -577,34 +768,3240 @@ pub async fn execute_webdriver_quit<W: UiWriter>(
         return Ok("❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string());
     }
 
-    // Take the session
-    let session = match ctx.webdriver_session.write().await.take() {
-        Some(s) => s.clone(),
-        None => return Ok("❌ No active WebDriver session.".to_string()),
-    };
-
-    // Quit the WebDriver session
-    match std::sync::Arc::try_unwrap(session) {
-        Ok(mutex) => {
-            let driver = mutex.into_inner();
-            match driver.quit().await {
-                Ok(_) => {
-                    debug!("WebDriver session closed successfully");
-
-                    // Kill the safaridriver process
-                    if let Some(mut process) = ctx.webdriver_process.write().await.take() {
-                        if let Err(e) = process.kill().await {
-                            warn!("Failed to kill safaridriver process: {}", e);
-                        } else {
-                            debug!("Safaridriver process terminated");
-                        }
-                    }
+    match close_session(ctx).await {
+        Ok(true) => Ok("✅ WebDriver session closed and safaridriver stopped".to_string()),
+        Ok(false) => Ok("❌ No active WebDriver session.".to_string()),
+        Err(msg) => Ok(msg),
+    }
+}
+
+/// Execute the `web_close_session` tool.
+pub async fn execute_web_close_session<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_close_session tool call");
+    let _ = tool_call; // unused
+
+    if !ctx.config.webdriver.enabled {
+        return Ok("❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string());
+    }
+
+    match close_session(ctx).await {
+        Ok(true) => Ok("✅ WebDriver session closed".to_string()),
+        Ok(false) => Ok("✅ No active WebDriver session to close".to_string()),
+        Err(msg) => Ok(msg),
+    }
+}
+
+/// Execute the `web_session_info` tool.
+pub async fn execute_web_session_info<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_session_info tool call");
+    let _ = tool_call; // unused
+
+    if ctx.webdriver_session.read().await.is_none() {
+        return Ok("No active WebDriver session.".to_string());
+    }
+
+    let state = ctx.webdriver_state.read().await;
+    let url = state.current_url.as_deref().unwrap_or("<none>");
+    let handles = if state.window_handles.is_empty() {
+        "<none>".to_string()
+    } else {
+        state.window_handles.join(", ")
+    };
+    let frame = if state.frame_path.is_empty() {
+        "<top-level page>".to_string()
+    } else {
+        state.frame_path.join(" > ")
+    };
+    Ok(format!(
+        "Active WebDriver session\nCurrent URL: {}\nWindow handles ({}): {}\nFrame: {}",
+        url,
+        state.window_handles.len(),
+        handles,
+        frame
+    ))
+}
 
-                    Ok("✅ WebDriver session closed and safaridriver stopped".to_string())
+/// How far along an element must be before `web_wait_for_element` succeeds.
+/// Ordered so that a later state implies every earlier one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ElementCondition {
+    Present,
+    Visible,
+    Clickable,
+}
+
+impl ElementCondition {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "present" => Some(Self::Present),
+            "visible" => Some(Self::Visible),
+            "clickable" => Some(Self::Clickable),
+            _ => None,
+        }
+    }
+}
+
+/// Script returning how far along the element matching `arguments[0]` is:
+/// "missing", "present", "visible" or "clickable".
+const ELEMENT_STATE_SCRIPT: &str = r#"
+const el = document.querySelector(arguments[0]);
+if (!el) return 'missing';
+const style = window.getComputedStyle(el);
+const rect = el.getBoundingClientRect();
+const visible = style.display !== 'none'
+    && style.visibility !== 'hidden'
+    && parseFloat(style.opacity || '1') > 0
+    && rect.width > 0 && rect.height > 0;
+if (!visible) return 'present';
+if (el.disabled || style.pointerEvents === 'none') return 'visible';
+return 'clickable';
+"#;
+
+async fn element_state<D: WebDriverController>(
+    driver: &mut D,
+    selector: &str,
+) -> Result<Option<ElementCondition>> {
+    let state = driver
+        .execute_script(ELEMENT_STATE_SCRIPT, vec![serde_json::json!(selector)])
+        .await?;
+    Ok(state.as_str().and_then(ElementCondition::parse))
+}
+
+/// Poll until the element matching `selector` satisfies `condition`.
+/// Returns the elapsed time, or `None` if `timeout` passed first. A failed
+/// poll counts as not yet; if the last poll before the timeout failed, its
+/// error is returned instead.
+pub async fn wait_for_element<D: WebDriverController>(
+    driver: &mut D,
+    selector: &str,
+    condition: ElementCondition,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> Result<Option<Duration>> {
+    let started = Instant::now();
+    let mut interval = tokio::time::interval(poll_interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last_error = None;
+
+    let poll = async {
+        loop {
+            interval.tick().await;
+            match element_state(driver, selector).await {
+                Ok(Some(state)) if state >= condition => return started.elapsed(),
+                Ok(_) => last_error = None,
+                Err(e) => {
+                    debug!("Element state check for '{}' failed: {:#}", selector, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+    };
+
+    match tokio::time::timeout(timeout, poll).await {
+        Ok(elapsed) => Ok(Some(elapsed)),
+        Err(_) => last_error.map_or(Ok(None), Err),
+    }
+}
+
+/// Execute the `web_wait_for_element` tool.
+pub async fn execute_web_wait_for_element<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_wait_for_element tool call");
+
+    let selector = match tool_call.args.get("selector").and_then(|v| v.as_str()) {
+        Some(s) => s,
+        None => return Ok("❌ Missing selector argument".to_string()),
+    };
+
+    let timeout_secs = tool_call
+        .args
+        .get("timeout_secs")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(10);
+    let poll_ms = tool_call
+        .args
+        .get("poll_ms")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(250)
+        .max(1);
+
+    let condition = match tool_call.args.get("condition").and_then(|v| v.as_str()) {
+        None => ElementCondition::Present,
+        Some(c) => match ElementCondition::parse(c) {
+            Some(condition) => condition,
+            None => {
+                return Ok(format!(
+                    "❌ Invalid condition '{}'. Use 'present', 'visible' or 'clickable'.",
+                    c
+                ))
+            }
+        },
+    };
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    match wait_for_element(
+        &mut *driver,
+        selector,
+        condition,
+        Duration::from_secs(timeout_secs),
+        Duration::from_millis(poll_ms),
+    )
+    .await
+    {
+        Ok(Some(elapsed)) => Ok(format!("✅ Element found after {}ms", elapsed.as_millis())),
+        Ok(None) => Ok(format!("❌ Timed out waiting for selector '{}'", selector)),
+        Err(e) => Ok(format!("❌ Failed to wait for element '{}': {}", selector, e)),
+    }
+}
+
+/// Default timeout for `web_wait_for_navigation`.
+const DEFAULT_NAVIGATION_TIMEOUT_SECS: u64 = 15;
+/// How often `web_wait_for_navigation` checks the URL.
+const NAVIGATION_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Whether the current document has finished loading. A page that is being
+/// unloaded may fail the script, which counts as not loaded.
+async fn page_loaded<D: WebDriverController>(driver: &mut D) -> bool {
+    match driver
+        .execute_script("return document.readyState;", vec![])
+        .await
+    {
+        Ok(state) => state.as_str() == Some("complete"),
+        Err(_) => false,
+    }
+}
+
+/// Poll until the URL differs from `from_url` (or contains
+/// `expected_url_contains`) and the new page has loaded. A navigation that
+/// finished before the call returns on the first poll. Returns the final URL
+/// and the elapsed time, or `None` if `timeout` passed first. Failing to read
+/// the URL, as can happen mid-navigation, counts as not yet; if the last poll
+/// before the timeout failed, its error is returned instead.
+pub async fn wait_for_navigation<D: WebDriverController>(
+    driver: &mut D,
+    from_url: &str,
+    expected_url_contains: Option<&str>,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> Result<Option<(String, Duration)>> {
+    let started = Instant::now();
+    let mut interval = tokio::time::interval(poll_interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last_error = None;
+
+    let poll = async {
+        loop {
+            interval.tick().await;
+            let url = match driver.current_url().await {
+                Ok(url) => {
+                    last_error = None;
+                    url
+                }
+                Err(e) => {
+                    debug!("Reading the current URL failed: {:#}", e);
+                    last_error = Some(e);
+                    continue;
                 }
-                Err(e) => Ok(format!("❌ Failed to quit WebDriver: {}", e)),
+            };
+            let arrived = match expected_url_contains {
+                Some(expected) => url.contains(expected),
+                None => url != from_url,
+            };
+            if arrived && page_loaded(driver).await {
+                return (url, started.elapsed());
+            }
+        }
+    };
+
+    match tokio::time::timeout(timeout, poll).await {
+        Ok(arrived) => Ok(Some(arrived)),
+        Err(_) => last_error.map_or(Ok(None), Err),
+    }
+}
+
+/// Execute the `web_wait_for_navigation` tool.
+pub async fn execute_web_wait_for_navigation<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_wait_for_navigation tool call");
+
+    let timeout_secs = tool_call
+        .args
+        .get("timeout_secs")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_NAVIGATION_TIMEOUT_SECS);
+    let expected = tool_call
+        .args
+        .get("expected_url_contains")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty());
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    // Compare against the URL of the last navigation tool rather than the URL
+    // now, so a click that already navigated is not waited on again
+    let recorded_url = ctx.webdriver_state.read().await.current_url.clone();
+    let from_url = match recorded_url {
+        Some(url) => url,
+        None => match driver.current_url().await {
+            Ok(url) => url,
+            Err(e) => return Ok(format!("❌ Failed to get current URL: {}", e)),
+        },
+    };
+
+    match wait_for_navigation(
+        &mut *driver,
+        &from_url,
+        expected,
+        Duration::from_secs(timeout_secs),
+        NAVIGATION_POLL_INTERVAL,
+    )
+    .await
+    {
+        Ok(Some((url, elapsed))) => {
+            record_session_state(ctx, &driver).await;
+            Ok(format!(
+                "✅ Navigated to {} after {}ms",
+                url,
+                elapsed.as_millis()
+            ))
+        }
+        Ok(None) => {
+            let current = driver.current_url().await.unwrap_or_default();
+            let waiting_for = match expected {
+                Some(expected) => format!("a URL containing '{}'", expected),
+                None => format!("navigation away from {}", from_url),
+            };
+            Ok(format!(
+                "❌ Timed out after {}s waiting for {} (current URL: {})",
+                timeout_secs, waiting_for, current
+            ))
+        }
+        Err(e) => Ok(format!("❌ Failed to wait for navigation: {}", e)),
+    }
+}
+
+/// Execute the `web_get_cookies` tool.
+pub async fn execute_web_get_cookies<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_get_cookies tool call");
+    let _ = tool_call; // unused
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    match driver.get_all_cookies().await {
+        Ok(cookies) => Ok(serde_json::to_string_pretty(&cookies)?),
+        Err(e) => Ok(format!("❌ Failed to get cookies: {}", e)),
+    }
+}
+
+/// Execute the `web_set_cookie` tool.
+pub async fn execute_web_set_cookie<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_set_cookie tool call");
+
+    let cookie: Cookie = match serde_json::from_value(tool_call.args.clone()) {
+        Ok(c) => c,
+        Err(e) => return Ok(format!("❌ Invalid cookie arguments: {}", e)),
+    };
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let name = cookie.name.clone();
+    let mut driver = session.lock().await;
+    match driver.add_cookie(cookie).await {
+        Ok(_) => Ok(format!("✅ Set cookie '{}'", name)),
+        Err(e) => Ok(format!("❌ Failed to set cookie: {}", e)),
+    }
+}
+
+/// Script returning the cells of the table matching `arguments[0]` (or the first table
+/// inside it) as rows of `{text, header, colspan, rowspan}`, or null if there is none.
+const TABLE_CELLS_SCRIPT: &str = r#"
+const root = document.querySelector(arguments[0]);
+if (!root) return null;
+const table = root.tagName === 'TABLE' ? root : root.querySelector('table');
+if (!table) return null;
+return Array.from(table.rows).map(row => Array.from(row.cells).map(cell => ({
+    text: (cell.innerText || cell.textContent || '').trim(),
+    header: cell.tagName === 'TH',
+    colspan: Math.max(cell.colSpan || 1, 1),
+    rowspan: Math.max(cell.rowSpan || 1, 1),
+})));
+"#;
+
+/// A table cell as returned by `TABLE_CELLS_SCRIPT`.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TableCell {
+    pub text: String,
+    #[serde(default)]
+    pub header: bool,
+    #[serde(default = "default_span")]
+    pub colspan: usize,
+    #[serde(default = "default_span")]
+    pub rowspan: usize,
+}
+
+fn default_span() -> usize {
+    1
+}
+
+/// Lay out rows of cells on a grid, honouring colspan/rowspan.
+/// Each cell's text lands in the first column it occupies; the positions it
+/// merges over are skipped (`None`).
+fn layout_table_rows(rows: &[Vec<TableCell>]) -> Vec<Vec<Option<String>>> {
+    // Remaining rows each column is still covered by a rowspan from above
+    let mut covered: Vec<usize> = Vec::new();
+    let mut grid = Vec::with_capacity(rows.len());
+
+    for row in rows {
+        let mut line: Vec<Option<String>> = Vec::new();
+        let mut next_covered = covered.iter().map(|n| n.saturating_sub(1)).collect::<Vec<_>>();
+        let mut col = 0;
+
+        for cell in row {
+            while covered.get(col).copied().unwrap_or(0) > 0 {
+                col += 1;
+            }
+            let span_end = col + cell.colspan.max(1);
+            if line.len() < span_end {
+                line.resize(span_end, None);
+            }
+            if next_covered.len() < span_end {
+                next_covered.resize(span_end, 0);
             }
+            line[col] = Some(cell.text.clone());
+            for covered_rows in &mut next_covered[col..span_end] {
+                *covered_rows = cell.rowspan.max(1) - 1;
+            }
+            col = span_end;
         }
-        Err(_) => Ok("❌ Cannot quit: WebDriver session is still in use".to_string()),
+
+        grid.push(line);
+        covered = next_covered;
+    }
+    grid
+}
+
+/// Turn table cells into one record per row, keyed by column header.
+/// Headers come from `headers` if given, otherwise from the first row.
+pub fn table_to_records(
+    rows: &[Vec<TableCell>],
+    headers: Option<&[String]>,
+    limit: Option<usize>,
+) -> Vec<HashMap<String, String>> {
+    let grid = layout_table_rows(rows);
+
+    // A header override replaces a <th> row, but a plain first row is data
+    let first_row_is_header = headers.is_none()
+        || rows
+            .first()
+            .map(|row| !row.is_empty() && row.iter().all(|cell| cell.header))
+            .unwrap_or(false);
+
+    let header_names: Vec<String> = match headers {
+        Some(h) => h.to_vec(),
+        None => grid
+            .first()
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .map(|(i, h)| match h {
+                        Some(name) if !name.is_empty() => name.clone(),
+                        _ => format!("column_{}", i + 1),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+
+    let data_rows = grid.iter().skip(if first_row_is_header { 1 } else { 0 });
+    data_rows
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .filter_map(|(i, cell)| {
+                    let text = cell.as_ref()?;
+                    let header = header_names
+                        .get(i)
+                        .cloned()
+                        .unwrap_or_else(|| format!("column_{}", i + 1));
+                    Some((header, text.clone()))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Extract the table matching `selector` into records.
+/// Returns `Ok(None)` if no table matches.
+pub async fn extract_table<D: WebDriverController>(
+    driver: &mut D,
+    selector: &str,
+    headers: Option<&[String]>,
+    limit: Option<usize>,
+) -> Result<Option<Vec<HashMap<String, String>>>> {
+    let value = driver
+        .execute_script(TABLE_CELLS_SCRIPT, vec![serde_json::json!(selector)])
+        .await?;
+    if value.is_null() {
+        return Ok(None);
+    }
+    let rows: Vec<Vec<TableCell>> = serde_json::from_value(value)?;
+    Ok(Some(table_to_records(&rows, headers, limit)))
+}
+
+/// Execute the `web_extract_table` tool.
+pub async fn execute_web_extract_table<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_extract_table tool call");
+
+    let selector = match tool_call.args.get("selector").and_then(|v| v.as_str()) {
+        Some(s) => s,
+        None => return Ok("❌ Missing selector argument".to_string()),
+    };
+    let headers: Option<Vec<String>> = tool_call
+        .args
+        .get("headers")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        });
+    let limit = tool_call
+        .args
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize);
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    match extract_table(&mut *driver, selector, headers.as_deref(), limit).await {
+        Ok(Some(records)) => Ok(serde_json::to_string_pretty(&records)?),
+        Ok(None) => Ok(format!("❌ No table found for selector '{}'", selector)),
+        Err(e) => Ok(format!("❌ Failed to extract table: {}", e)),
+    }
+}
+
+/// Script that scrolls the window, or the element matching `arguments[0]`, by
+/// (`arguments[1]`, `arguments[2]`) pixels, or brings the element matching
+/// `arguments[4]` into view. Returns "scrolled", "missing" or "target_missing".
+const SCROLL_SCRIPT: &str = r#"
+const [selector, dx, dy, behavior, target] = arguments;
+const container = selector ? document.querySelector(selector) : null;
+if (selector && !container) return 'missing';
+if (target) {
+    const el = (container || document).querySelector(target);
+    if (!el) return 'target_missing';
+    el.scrollIntoView({ behavior: behavior, block: 'center', inline: 'nearest' });
+} else {
+    (container || window).scrollBy({ left: dx, top: dy, behavior: behavior });
+}
+return 'scrolled';
+"#;
+
+/// Pixels scrolled when no `amount` is given.
+const DEFAULT_SCROLL_AMOUNT: i64 = 500;
+
+/// What a `web_scroll` call should do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScrollAction {
+    /// Scroll by a pixel offset
+    By { dx: i64, dy: i64 },
+    /// Scroll until the element matching this selector is in the viewport
+    IntoView(String),
+}
+
+impl ScrollAction {
+    /// Pixel offset for scrolling `amount` pixels in `direction`.
+    pub fn by_direction(direction: &str, amount: i64) -> Option<Self> {
+        let (dx, dy) = match direction.to_ascii_lowercase().as_str() {
+            "up" => (0, -amount),
+            "down" => (0, amount),
+            "left" => (-amount, 0),
+            "right" => (amount, 0),
+            _ => return None,
+        };
+        Some(Self::By { dx, dy })
+    }
+}
+
+/// Scroll the window, or the element matching `selector`, and describe the result.
+pub async fn scroll_page<D: WebDriverController>(
+    driver: &mut D,
+    selector: Option<&str>,
+    action: &ScrollAction,
+    smooth: bool,
+) -> Result<String> {
+    let behavior = if smooth { "smooth" } else { "instant" };
+    let (dx, dy, target) = match action {
+        ScrollAction::By { dx, dy } => (*dx, *dy, None),
+        ScrollAction::IntoView(target) => (0, 0, Some(target.as_str())),
+    };
+
+    let status = driver
+        .execute_script(
+            SCROLL_SCRIPT,
+            vec![
+                serde_json::json!(selector),
+                serde_json::json!(dx),
+                serde_json::json!(dy),
+                serde_json::json!(behavior),
+                serde_json::json!(target),
+            ],
+        )
+        .await?;
+
+    let scrolled = match selector {
+        Some(sel) => format!("element '{}'", sel),
+        None => "page".to_string(),
+    };
+    Ok(match (status.as_str(), action) {
+        (Some("missing"), _) => {
+            format!("❌ No element found for selector '{}'", selector.unwrap_or(""))
+        }
+        (Some("target_missing"), _) => {
+            format!("❌ No element found for target_selector '{}'", target.unwrap_or(""))
+        }
+        (_, ScrollAction::By { dx, dy }) => {
+            format!("✅ Scrolled {} by ({}, {}) pixels", scrolled, dx, dy)
+        }
+        (_, ScrollAction::IntoView(target)) => {
+            format!("✅ Scrolled {} until '{}' is in view", scrolled, target)
+        }
+    })
+}
+
+/// Execute the `web_scroll` tool.
+pub async fn execute_web_scroll<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_scroll tool call");
+
+    let selector = tool_call.args.get("selector").and_then(|v| v.as_str());
+    let smooth = tool_call
+        .args
+        .get("smooth")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let action = match tool_call.args.get("target_selector").and_then(|v| v.as_str()) {
+        Some(target) => ScrollAction::IntoView(target.to_string()),
+        None => {
+            let direction = tool_call
+                .args
+                .get("direction")
+                .and_then(|v| v.as_str())
+                .unwrap_or("down");
+            let amount = tool_call
+                .args
+                .get("amount")
+                .and_then(|v| v.as_i64())
+                .unwrap_or(DEFAULT_SCROLL_AMOUNT);
+            match ScrollAction::by_direction(direction, amount) {
+                Some(action) => action,
+                None => {
+                    return Ok(format!(
+                        "❌ Invalid direction '{}'. Use 'up', 'down', 'left' or 'right'.",
+                        direction
+                    ))
+                }
+            }
+        }
+    };
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    match scroll_page(&mut *driver, selector, &action, smooth).await {
+        Ok(msg) => Ok(msg),
+        Err(e) => Ok(format!("❌ Failed to scroll: {}", e)),
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Form filling
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Attribute used to hand a field located by script back to WebDriver.
+const FILL_MARKER_ATTR: &str = "data-g3-fill";
+
+/// Script that finds the form control for `arguments[1]` inside the element
+/// matching `arguments[0]` (or the whole document): by name, then id, then
+/// label text. Radio groups pick the button whose value or label is
+/// `arguments[2]`. The control is tagged with `data-g3-fill=arguments[3]`.
+/// Returns `{kind, checked}`, `{error}` or null when no field matches.
+const FIND_FORM_FIELD_SCRIPT: &str = r#"
+const [formSelector, key, value, marker] = arguments;
+const root = formSelector ? document.querySelector(formSelector) : document;
+if (!root) return { error: 'form_missing' };
+const norm = s => (s || '').replace(/\s+/g, ' ').trim().toLowerCase();
+const fields = Array.from(root.querySelectorAll('input, select, textarea'));
+let matches = fields.filter(f => f.name === key);
+if (!matches.length) matches = fields.filter(f => f.id === key);
+if (!matches.length) {
+    const label = Array.from(root.querySelectorAll('label')).find(l => norm(l.textContent) === norm(key));
+    const target = label && (label.control || (label.htmlFor && document.getElementById(label.htmlFor)));
+    if (target) matches = [target];
+}
+if (!matches.length) return null;
+let el = matches[0];
+if (el.type === 'radio') {
+    const group = el.name ? fields.filter(f => f.type === 'radio' && f.name === el.name) : matches;
+    el = group.find(r => r.value === value || Array.from(r.labels || []).some(l => norm(l.textContent) === norm(value)));
+    if (!el) return { error: 'option_missing' };
+}
+document.querySelectorAll('[data-g3-fill="' + marker + '"]').forEach(e => e.removeAttribute('data-g3-fill'));
+el.setAttribute('data-g3-fill', marker);
+const kind = el.tagName === 'SELECT' ? 'select'
+    : (el.type === 'checkbox' || el.type === 'radio') ? el.type : 'text';
+return { kind: kind, checked: !!el.checked };
+"#;
+
+/// Script that submits the form matching `arguments[0]`, or else the form
+/// owning the first filled field, or the first form on the page. Uses
+/// `requestSubmit()` where available so submit handlers and validation run.
+const SUBMIT_FORM_SCRIPT: &str = r#"
+const [formSelector] = arguments;
+let form = formSelector ? document.querySelector(formSelector) : null;
+if (form && form.tagName !== 'FORM') form = form.querySelector('form') || form.closest('form');
+if (!form) { const field = document.querySelector('[data-g3-fill]'); form = field && field.form; }
+if (!form) form = document.querySelector('form');
+document.querySelectorAll('[data-g3-fill]').forEach(e => e.removeAttribute('data-g3-fill'));
+if (!form) return false;
+if (form.requestSubmit) form.requestSubmit(); else form.submit();
+return true;
+"#;
+
+/// Interpret a checkbox value such as "true", "yes", "on" or "1".
+pub fn parse_checkbox_value(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" | "checked" => Some(true),
+        "false" | "no" | "off" | "0" | "unchecked" | "" => Some(false),
+        _ => None,
+    }
+}
+
+/// Fill a single field located by `FIND_FORM_FIELD_SCRIPT`.
+/// Returns an error message if the field could not be filled.
+async fn fill_form_field<D: WebDriverController>(
+    driver: &mut D,
+    form_selector: Option<&str>,
+    index: usize,
+    key: &str,
+    value: &str,
+) -> Result<Option<String>> {
+    let found = driver
+        .execute_script(
+            FIND_FORM_FIELD_SCRIPT,
+            vec![
+                serde_json::json!(form_selector),
+                serde_json::json!(key),
+                serde_json::json!(value),
+                serde_json::json!(index.to_string()),
+            ],
+        )
+        .await?;
+
+    if found.is_null() {
+        return Ok(Some(format!("no field named '{}'", key)));
+    }
+    match found.get("error").and_then(|v| v.as_str()) {
+        Some("form_missing") => {
+            return Ok(Some(format!("no form matches '{}'", form_selector.unwrap_or(""))))
+        }
+        Some(_) => return Ok(Some(format!("no option '{}' for '{}'", value, key))),
+        None => {}
+    }
+
+    let selector = format!("[{}=\"{}\"]", FILL_MARKER_ATTR, index);
+    let kind = found.get("kind").and_then(|v| v.as_str()).unwrap_or("text");
+    let checked = found.get("checked").and_then(|v| v.as_bool()).unwrap_or(false);
+    let mut element = driver.find_element(&selector).await?;
+
+    match kind {
+        "select" => {
+            let options = driver.find_elements(&format!("{} option", selector)).await?;
+            for mut option in options {
+                let text = option.text().await?;
+                let option_value = option.get_attribute("value").await?.unwrap_or_default();
+                if text.trim().eq_ignore_ascii_case(value.trim()) || option_value == value {
+                    option.click().await?;
+                    return Ok(None);
+                }
+            }
+            Ok(Some(format!("no option '{}' for '{}'", value, key)))
+        }
+        "checkbox" => match parse_checkbox_value(value) {
+            Some(wanted) => {
+                if wanted != checked {
+                    element.click().await?;
+                }
+                Ok(None)
+            }
+            None => Ok(Some(format!("'{}' is not a checkbox value for '{}'", value, key))),
+        },
+        "radio" => {
+            if !checked {
+                element.click().await?;
+            }
+            Ok(None)
+        }
+        _ => {
+            element.clear().await?;
+            element.send_keys(value).await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Fill `fields` (name, id or label → value) and optionally submit the form.
+pub async fn fill_form<D: WebDriverController>(
+    driver: &mut D,
+    form_selector: Option<&str>,
+    fields: &[(String, String)],
+    submit: bool,
+) -> Result<String> {
+    let mut filled = Vec::new();
+    let mut problems = Vec::new();
+    for (index, (key, value)) in fields.iter().enumerate() {
+        match fill_form_field(driver, form_selector, index, key, value).await {
+            Ok(None) => filled.push(key.as_str()),
+            Ok(Some(problem)) => problems.push(problem),
+            Err(e) => problems.push(format!("failed to fill '{}': {}", key, e)),
+        }
+    }
+
+    if !problems.is_empty() {
+        return Ok(format!(
+            "❌ Could not fill form: {}{}",
+            problems.join("; "),
+            if filled.is_empty() {
+                String::new()
+            } else {
+                format!(" (filled: {})", filled.join(", "))
+            }
+        ));
+    }
+
+    if submit {
+        let submitted = driver
+            .execute_script(SUBMIT_FORM_SCRIPT, vec![serde_json::json!(form_selector)])
+            .await?;
+        if submitted.as_bool() != Some(true) {
+            return Ok(format!(
+                "❌ Filled {} field(s) but found no form to submit",
+                filled.len()
+            ));
+        }
+        return Ok(format!(
+            "✅ Filled {} field(s) ({}) and submitted the form",
+            filled.len(),
+            filled.join(", ")
+        ));
+    }
+
+    Ok(format!("✅ Filled {} field(s): {}", filled.len(), filled.join(", ")))
+}
+
+/// Execute the `web_fill_form` tool.
+pub async fn execute_web_fill_form<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_fill_form tool call");
+
+    let fields: Vec<(String, String)> =
+        match tool_call.args.get("fields").and_then(|v| v.as_object()) {
+            Some(map) if !map.is_empty() => map
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+            _ => return Ok("❌ Missing fields argument (an object of field → value)".to_string()),
+        };
+    let form_selector = tool_call.args.get("form_selector").and_then(|v| v.as_str());
+    let submit = tool_call
+        .args
+        .get("submit")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    let result = match fill_form(&mut *driver, form_selector, &fields, submit).await {
+        Ok(msg) => msg,
+        Err(e) => format!("❌ Failed to fill form: {}", e),
+    };
+    if submit {
+        // Submitting usually navigates
+        record_session_state(ctx, &driver).await;
+    }
+    Ok(result)
+}
+
+/// Script collecting the page's title, description, canonical URL and Open
+/// Graph tags. Absent tags are returned as null.
+const PAGE_METADATA_SCRIPT: &str = r#"
+const meta = (attr, name) => {
+    const el = document.querySelector(`meta[${attr}="${name}"]`);
+    const content = el && el.getAttribute('content');
+    return content ? content.trim() : null;
+};
+const canonical = document.querySelector('link[rel="canonical"]');
+return {
+    title: document.title || null,
+    description: meta('name', 'description'),
+    canonical_url: canonical ? canonical.href : null,
+    og_title: meta('property', 'og:title'),
+    og_description: meta('property', 'og:description'),
+    og_image: meta('property', 'og:image'),
+};
+"#;
+
+/// Metadata describing a web page, from its `<head>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+pub struct PageMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub canonical_url: Option<String>,
+    pub og_title: Option<String>,
+    pub og_description: Option<String>,
+    pub og_image: Option<String>,
+}
+
+impl PageMetadata {
+    /// One `Label: value` line per field, `<none>` for absent tags.
+    pub fn format(&self) -> String {
+        let fields = [
+            ("Title", &self.title),
+            ("Description", &self.description),
+            ("Canonical URL", &self.canonical_url),
+            ("og:title", &self.og_title),
+            ("og:description", &self.og_description),
+            ("og:image", &self.og_image),
+        ];
+        fields
+            .iter()
+            .map(|(label, value)| format!("{}: {}", label, value.as_deref().unwrap_or("<none>")))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Read the metadata of the current page.
+pub async fn get_page_metadata<D: WebDriverController>(driver: &mut D) -> Result<PageMetadata> {
+    let value = driver.execute_script(PAGE_METADATA_SCRIPT, vec![]).await?;
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Execute the `web_get_page_metadata` tool.
+pub async fn execute_web_get_page_metadata<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_get_page_metadata tool call");
+    let _ = tool_call; // unused
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    match get_page_metadata(&mut *driver).await {
+        Ok(metadata) => Ok(metadata.format()),
+        Err(e) => Ok(format!("❌ Failed to get page metadata: {}", e)),
+    }
+}
+
+/// How `web_run_script` formats the script's return value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptReturnType {
+    /// Pretty-printed JSON
+    Json,
+    /// Strings as they are, anything else as compact JSON
+    Text,
+    /// Discard the value
+    None,
+}
+
+impl ScriptReturnType {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "text" => Some(Self::Text),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    /// Format a script's return value.
+    pub fn format(self, value: &serde_json::Value) -> String {
+        match self {
+            Self::Json => serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string()),
+            Self::Text => match value {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => String::new(),
+                other => other.to_string(),
+            },
+            Self::None => "✅ Script executed".to_string(),
+        }
+    }
+}
+
+/// Run `script` (a function body; `args` are available as `arguments`)
+/// and format its return value. Errors thrown in the page are returned as
+/// `❌ Script error: ...`.
+pub async fn run_script<D: WebDriverController>(
+    driver: &mut D,
+    script: &str,
+    args: Vec<serde_json::Value>,
+    return_type: ScriptReturnType,
+) -> String {
+    match driver.execute_script(script, args).await {
+        Ok(value) => return_type.format(&value),
+        Err(e) => format!("❌ Script error: {}", e),
+    }
+}
+
+/// Execute the `web_run_script` tool.
+pub async fn execute_web_run_script<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_run_script tool call");
+
+    let script = match tool_call.args.get("script").and_then(|v| v.as_str()) {
+        Some(s) if !s.trim().is_empty() => s,
+        _ => return Ok("❌ Missing script argument".to_string()),
+    };
+
+    let args = match tool_call.args.get("args") {
+        None | Some(serde_json::Value::Null) => Vec::new(),
+        Some(serde_json::Value::Array(args)) => args.clone(),
+        Some(_) => return Ok("❌ args must be an array".to_string()),
+    };
+
+    let return_type = match tool_call.args.get("return_type").and_then(|v| v.as_str()) {
+        None => ScriptReturnType::Json,
+        Some(value) => match ScriptReturnType::parse(value) {
+            Some(t) => t,
+            None => {
+                return Ok(format!(
+                    "❌ Invalid return_type '{}'. Use 'json', 'text' or 'none'",
+                    value
+                ))
+            }
+        },
+    };
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    Ok(run_script(&mut *driver, script, args, return_type).await)
+}
+
+/// How `web_find_elements_by_text` compares element text with the query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextMatch {
+    Exact,
+    Contains,
+    StartsWith,
+}
+
+impl TextMatch {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "exact" => Some(Self::Exact),
+            "contains" => Some(Self::Contains),
+            "starts_with" => Some(Self::StartsWith),
+            _ => None,
+        }
+    }
+}
+
+/// Most elements `web_find_elements_by_text` describes.
+const MAX_TEXT_MATCHES: usize = 50;
+
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+
+/// XPath 1.0 string literal for `text`. XPath has no escapes, so text with
+/// both quote kinds is built with `concat()`.
+fn xpath_literal(text: &str) -> String {
+    if !text.contains('\'') {
+        format!("'{}'", text)
+    } else if !text.contains('"') {
+        format!("\"{}\"", text)
+    } else {
+        let parts: Vec<String> = text
+            .split('\'')
+            .map(|part| format!("'{}'", part))
+            .collect();
+        format!("concat({})", parts.join(", \"'\", "))
+    }
+}
+
+/// XPath selecting elements (of `element_type`, or any) whose visible text
+/// matches `text`. Only the innermost matching elements are selected, so a
+/// matching button is returned without every ancestor containing it. Inputs
+/// are matched on their `value`. Case-insensitive matching covers ASCII only.
+pub fn text_xpath(
+    text: &str,
+    match_type: TextMatch,
+    element_type: Option<&str>,
+    case_sensitive: bool,
+) -> String {
+    let tag = element_type.unwrap_or("*");
+    let mut value = if tag.eq_ignore_ascii_case("input") {
+        "normalize-space(@value)".to_string()
+    } else {
+        "normalize-space(.)".to_string()
+    };
+    let mut needle = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !case_sensitive {
+        value = format!("translate({}, '{}', '{}')", value, UPPERCASE, LOWERCASE);
+        needle = needle.to_ascii_lowercase();
+    }
+    let needle = xpath_literal(&needle);
+    let predicate = match match_type {
+        TextMatch::Exact => format!("{} = {}", value, needle),
+        TextMatch::Contains => format!("contains({}, {})", value, needle),
+        TextMatch::StartsWith => format!("starts-with({}, {})", value, needle),
+    };
+    let skip = if tag == "*" {
+        " and not(self::script or self::style or self::head or self::title)"
+    } else {
+        ""
+    };
+    format!(
+        "//{tag}[{predicate}{skip} and not(.//{tag}[{predicate}])]",
+        tag = tag,
+        predicate = predicate,
+        skip = skip
+    )
+}
+
+/// Script describing up to `arguments[1]` elements matching the XPath in
+/// `arguments[0]`, with the total number of matches.
+const ELEMENTS_BY_XPATH_SCRIPT: &str = r#"
+const result = document.evaluate(arguments[0], document, null, XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null);
+const elements = [];
+for (let i = 0; i < result.snapshotLength && elements.length < arguments[1]; i++) {
+    const el = result.snapshotItem(i);
+    const text = el.tagName === 'INPUT' ? (el.value || '') : (el.innerText || el.textContent || '');
+    elements.push({
+        tag: el.tagName.toLowerCase(),
+        text: text.trim().replace(/\s+/g, ' ').slice(0, 200),
+        id: el.id || null,
+        class: el.getAttribute('class'),
+        href: el.getAttribute('href'),
+    });
+}
+return {total: result.snapshotLength, elements: elements};
+"#;
+
+/// An element found by its text or an XPath.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ElementDescriptor {
+    pub tag: String,
+    pub text: String,
+    pub id: Option<String>,
+    pub class: Option<String>,
+    pub href: Option<String>,
+}
+
+/// Elements found by `ELEMENTS_BY_XPATH_SCRIPT`.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TextMatches {
+    pub total: usize,
+    pub elements: Vec<ElementDescriptor>,
+}
+
+/// Find elements whose visible text matches `text` (see [`text_xpath`]).
+pub async fn find_elements_by_text<D: WebDriverController>(
+    driver: &mut D,
+    text: &str,
+    match_type: TextMatch,
+    element_type: Option<&str>,
+    case_sensitive: bool,
+) -> Result<TextMatches> {
+    let xpath = text_xpath(text, match_type, element_type, case_sensitive);
+    debug!("Finding elements by text with XPath: {}", xpath);
+    let value = driver
+        .execute_script(
+            ELEMENTS_BY_XPATH_SCRIPT,
+            vec![serde_json::json!(xpath), serde_json::json!(MAX_TEXT_MATCHES)],
+        )
+        .await?;
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Execute the `web_find_elements_by_text` tool.
+pub async fn execute_web_find_elements_by_text<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_find_elements_by_text tool call");
+
+    let text = match tool_call.args.get("text").and_then(|v| v.as_str()) {
+        Some(t) if !t.trim().is_empty() => t,
+        _ => return Ok("❌ Missing text argument".to_string()),
+    };
+    let match_type = match tool_call.args.get("match_type").and_then(|v| v.as_str()) {
+        None => TextMatch::Contains,
+        Some(value) => match TextMatch::parse(value) {
+            Some(m) => m,
+            None => {
+                return Ok(format!(
+                    "❌ Invalid match_type '{}'. Use 'exact', 'contains' or 'starts_with'",
+                    value
+                ))
+            }
+        },
+    };
+    let element_type = tool_call
+        .args
+        .get("element_type")
+        .and_then(|v| v.as_str())
+        .filter(|t| !t.trim().is_empty());
+    if let Some(tag) = element_type {
+        if !tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Ok(format!("❌ Invalid element_type '{}'", tag));
+        }
+    }
+    let case_sensitive = tool_call
+        .args
+        .get("case_sensitive")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    match find_elements_by_text(&mut *driver, text, match_type, element_type, case_sensitive).await
+    {
+        Ok(matches) if matches.elements.is_empty() => {
+            Ok(format!("No elements found with text '{}'", text))
+        }
+        Ok(matches) => {
+            let shown = if matches.total > matches.elements.len() {
+                format!(" (showing first {})", matches.elements.len())
+            } else {
+                String::new()
+            };
+            Ok(format!(
+                "Found {} elements{}:\n{}",
+                matches.total,
+                shown,
+                serde_json::to_string_pretty(&matches.elements)?
+            ))
+        }
+        Err(e) => Ok(format!("❌ Failed to find elements by text '{}': {}", text, e)),
+    }
+}
+
+/// Most elements `web_find_by_xpath` describes.
+const MAX_XPATH_MATCHES: usize = 50;
+
+/// Describe `element` the way `web_find_elements_by_text` does.
+pub async fn describe_element(element: &WebElement) -> Result<ElementDescriptor> {
+    let text = element.text().await?;
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    Ok(ElementDescriptor {
+        tag: element.tag_name().await?,
+        text: text.chars().take(200).collect(),
+        id: element.get_attribute("id").await?.filter(|id| !id.is_empty()),
+        class: element.get_attribute("class").await?,
+        href: element.get_attribute("href").await?,
+    })
+}
+
+/// Find elements matching `xpath`: only the first when `single` is set,
+/// otherwise up to [`MAX_XPATH_MATCHES`]. Returns the total number of matches
+/// with the descriptors.
+pub async fn find_by_xpath<D: WebDriverController>(
+    driver: &mut D,
+    xpath: &str,
+    single: bool,
+) -> Result<TextMatches> {
+    let elements = if single {
+        vec![driver.find_element_xpath(xpath).await?]
+    } else {
+        driver.find_elements_xpath(xpath).await?
+    };
+    let mut described = Vec::new();
+    for element in elements.iter().take(MAX_XPATH_MATCHES) {
+        described.push(describe_element(element).await?);
+    }
+    Ok(TextMatches {
+        total: elements.len(),
+        elements: described,
+    })
+}
+
+/// Execute the `web_find_by_xpath` tool.
+pub async fn execute_web_find_by_xpath<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_find_by_xpath tool call");
+
+    let xpath = match tool_call.args.get("xpath").and_then(|v| v.as_str()) {
+        Some(x) if !x.trim().is_empty() => x,
+        _ => return Ok("❌ Missing xpath argument".to_string()),
+    };
+    let single = tool_call
+        .args
+        .get("single")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    match find_by_xpath(&mut *driver, xpath, single).await {
+        Ok(matches) if matches.elements.is_empty() => {
+            Ok(format!("No elements match XPath '{}'", xpath))
+        }
+        Ok(matches) if single => Ok(format!(
+            "Found element:\n{}",
+            serde_json::to_string_pretty(&matches.elements[0])?
+        )),
+        Ok(matches) => {
+            let shown = if matches.total > matches.elements.len() {
+                format!(" (showing first {})", matches.elements.len())
+            } else {
+                String::new()
+            };
+            Ok(format!(
+                "Found {} elements{}:\n{}",
+                matches.total,
+                shown,
+                serde_json::to_string_pretty(&matches.elements)?
+            ))
+        }
+        Err(e) => Ok(format!("❌ Failed to find elements by XPath '{}': {}", xpath, e)),
+    }
+}
+
+/// Deepest level of children `web_inspect_element` describes.
+const MAX_INSPECT_DEPTH: u64 = 3;
+/// Most children described per element.
+const MAX_INSPECT_CHILDREN: u64 = 20;
+
+/// Script describing the element matching `arguments[0]`, or `null`.
+/// `arguments[1]` adds computed styles that differ from those of a fresh
+/// element with the same tag; `arguments[2]` is how many levels of children
+/// to describe, and `arguments[3]` how many children per element.
+const INSPECT_ELEMENT_SCRIPT: &str = r#"
+const [selector, includeStyles, depth, maxChildren] = arguments;
+const el = document.querySelector(selector);
+if (!el) return null;
+
+function nonDefaultStyles(node) {
+    const style = window.getComputedStyle(node);
+    const probe = document.createElement(node.tagName);
+    document.body.appendChild(probe);
+    const defaults = window.getComputedStyle(probe);
+    const styles = {};
+    for (const name of style) {
+        const value = style.getPropertyValue(name);
+        if (value !== defaults.getPropertyValue(name)) styles[name] = value;
+    }
+    probe.remove();
+    return styles;
+}
+
+function describe(node, level) {
+    const rect = node.getBoundingClientRect();
+    const style = window.getComputedStyle(node);
+    const attributes = {};
+    for (const name of node.getAttributeNames()) attributes[name] = node.getAttribute(name);
+    const info = {
+        tag: node.tagName.toLowerCase(),
+        id: node.id || null,
+        class: node.getAttribute('class'),
+        text: (node.innerText || node.textContent || '').trim().replace(/\s+/g, ' ').slice(0, 200),
+        bounding_rect: {
+            x: rect.x, y: rect.y, width: rect.width, height: rect.height,
+            top: rect.top, right: rect.right, bottom: rect.bottom, left: rect.left,
+        },
+        attributes: attributes,
+        visible: style.display !== 'none' && style.visibility !== 'hidden'
+            && parseFloat(style.opacity || '1') > 0 && rect.width > 0 && rect.height > 0,
+    };
+    if (includeStyles) info.computed_styles = nonDefaultStyles(node);
+    if (level < depth) {
+        info.children = Array.from(node.children).slice(0, maxChildren)
+            .map(child => describe(child, level + 1));
+    }
+    return info;
+}
+
+return describe(el, 0);
+"#;
+
+/// Position and size of an element, as from `getBoundingClientRect()`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BoundingRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+/// An element as described by `web_inspect_element`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ElementInspection {
+    pub tag: String,
+    pub id: Option<String>,
+    pub class: Option<String>,
+    pub text: String,
+    pub bounding_rect: BoundingRect,
+    pub attributes: std::collections::BTreeMap<String, String>,
+    pub visible: bool,
+    /// Only properties that differ from a fresh element with the same tag
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub computed_styles: Option<std::collections::BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ElementInspection>,
+}
+
+/// Describe the element matching `selector` and `child_depth` levels of its
+/// children (at most 3). Returns `None` if nothing matches.
+pub async fn inspect_element<D: WebDriverController>(
+    driver: &mut D,
+    selector: &str,
+    include_styles: bool,
+    child_depth: u64,
+) -> Result<Option<ElementInspection>> {
+    let value = driver
+        .execute_script(
+            INSPECT_ELEMENT_SCRIPT,
+            vec![
+                serde_json::json!(selector),
+                serde_json::json!(include_styles),
+                serde_json::json!(child_depth.min(MAX_INSPECT_DEPTH)),
+                serde_json::json!(MAX_INSPECT_CHILDREN),
+            ],
+        )
+        .await?;
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Execute the `web_inspect_element` tool.
+pub async fn execute_web_inspect_element<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_inspect_element tool call");
+
+    let selector = match tool_call.args.get("selector").and_then(|v| v.as_str()) {
+        Some(s) => s,
+        None => return Ok("❌ Missing selector argument".to_string()),
+    };
+    let include_styles = tool_call
+        .args
+        .get("include_styles")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let include_children = tool_call
+        .args
+        .get("include_children")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let child_depth = if include_children { MAX_INSPECT_DEPTH } else { 0 };
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    match inspect_element(&mut *driver, selector, include_styles, child_depth).await {
+        Ok(Some(inspection)) => Ok(serde_json::to_string_pretty(&inspection)?),
+        Ok(None) => Ok(format!("❌ No element matches selector '{}'", selector)),
+        Err(e) => Ok(format!("❌ Failed to inspect element '{}': {}", selector, e)),
+    }
+}
+
+/// Deepest level of the tree `web_accessibility_snapshot` returns.
+const MAX_ACCESSIBILITY_DEPTH: u64 = 6;
+/// Most nodes `web_accessibility_snapshot` returns.
+const MAX_ACCESSIBILITY_NODES: u64 = 500;
+
+/// Script walking the page from `document.documentElement` and returning its
+/// accessible elements in document order, each with its depth in the tree.
+/// Elements without a meaningful role (`generic`, `none`, ...) are left out
+/// and their children moved up a level. Uses Chrome's `computedRole` and
+/// `computedName` when available and falls back to ARIA attributes and
+/// implicit roles otherwise. `arguments[0]` is the maximum depth and
+/// `arguments[1]` the maximum number of nodes.
+const ACCESSIBILITY_SNAPSHOT_SCRIPT: &str = r#"
+const [maxDepth, maxNodes] = arguments;
+const SKIPPED_TAGS = new Set(['HEAD', 'SCRIPT', 'STYLE', 'NOSCRIPT', 'TEMPLATE', 'META', 'LINK']);
+const IGNORED_ROLES = new Set(['', 'generic', 'none', 'presentation', 'StaticText']);
+const IMPLICIT_ROLES = {
+    ARTICLE: 'article', ASIDE: 'complementary', BUTTON: 'button', DIALOG: 'dialog',
+    FOOTER: 'contentinfo', FORM: 'form', H1: 'heading', H2: 'heading', H3: 'heading',
+    H4: 'heading', H5: 'heading', H6: 'heading', HEADER: 'banner', IMG: 'img',
+    LI: 'listitem', MAIN: 'main', NAV: 'navigation', OL: 'list', OPTION: 'option',
+    P: 'paragraph', TABLE: 'table', TD: 'cell', TEXTAREA: 'textbox', TH: 'columnheader',
+    TR: 'row', UL: 'list',
+};
+const INPUT_ROLES = {
+    button: 'button', checkbox: 'checkbox', radio: 'radio', range: 'slider',
+    reset: 'button', search: 'searchbox', submit: 'button', hidden: '',
+};
+// Roles named by their text content when nothing else names them
+const NAMED_FROM_CONTENT = new Set([
+    'button', 'cell', 'columnheader', 'heading', 'link', 'listitem', 'menuitem',
+    'option', 'tab',
+]);
+const clean = s => (s || '').replace(/\s+/g, ' ').trim().slice(0, 100);
+const textOf = ids => (ids || '').split(/\s+/).filter(Boolean)
+    .map(id => document.getElementById(id))
+    .filter(Boolean)
+    .map(el => el.textContent)
+    .join(' ');
+
+function roleOf(el) {
+    if (typeof el.computedRole === 'string' && el.computedRole) return el.computedRole;
+    const explicit = (el.getAttribute('role') || '').trim().split(/\s+/)[0];
+    if (explicit) return explicit;
+    if (el.tagName === 'A') return el.hasAttribute('href') ? 'link' : '';
+    if (el.tagName === 'INPUT') {
+        const type = (el.getAttribute('type') || 'text').toLowerCase();
+        return type in INPUT_ROLES ? INPUT_ROLES[type] : 'textbox';
+    }
+    if (el.tagName === 'SELECT') return el.multiple ? 'listbox' : 'combobox';
+    return IMPLICIT_ROLES[el.tagName] || '';
+}
+
+function nameOf(el, role) {
+    if (typeof el.computedName === 'string' && el.computedName) return clean(el.computedName);
+    const labelledBy = textOf(el.getAttribute('aria-labelledby'));
+    if (labelledBy.trim()) return clean(labelledBy);
+    const label = el.getAttribute('aria-label');
+    if (label && label.trim()) return clean(label);
+    if (el.labels && el.labels.length) return clean(Array.from(el.labels).map(l => l.textContent).join(' '));
+    for (const attr of ['alt', 'title', 'placeholder']) {
+        const value = el.getAttribute(attr);
+        if (value && value.trim()) return clean(value);
+    }
+    return NAMED_FROM_CONTENT.has(role) ? clean(el.textContent) : '';
+}
+
+function hidden(el) {
+    if (el.hidden || el.getAttribute('aria-hidden') === 'true') return true;
+    const style = window.getComputedStyle(el);
+    return style.display === 'none' || style.visibility === 'hidden';
+}
+
+const nodes = [];
+let truncated = false;
+
+function visit(el, depth) {
+    if (truncated || SKIPPED_TAGS.has(el.tagName) || hidden(el)) return;
+    const role = roleOf(el);
+    let childDepth = depth;
+    if (!IGNORED_ROLES.has(role)) {
+        if (nodes.length >= maxNodes) {
+            truncated = true;
+            return;
+        }
+        nodes.push({
+            depth: depth,
+            role: role,
+            name: nameOf(el, role),
+            description: clean(textOf(el.getAttribute('aria-describedby'))
+                || el.getAttribute('aria-description')) || null,
+            tag: el.tagName.toLowerCase(),
+            id: el.id || null,
+            class: el.getAttribute('class'),
+        });
+        childDepth = depth + 1;
+    }
+    if (childDepth >= maxDepth) return;
+    for (const child of el.children) visit(child, childDepth);
+}
+
+visit(document.documentElement, 0);
+return { nodes: nodes, truncated: truncated };
+"#;
+
+/// An element in the accessibility tree.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct AccessibilityNode {
+    /// Number of accessible ancestors
+    pub depth: usize,
+    pub role: String,
+    /// Accessible name, empty if the element has none
+    pub name: String,
+    /// Text of the elements referenced by `aria-describedby`
+    pub description: Option<String>,
+    pub tag: String,
+    pub id: Option<String>,
+    pub class: Option<String>,
+}
+
+impl AccessibilityNode {
+    /// `tag#id.class`, like a CSS selector for the element.
+    fn selector(&self) -> String {
+        let mut selector = self.tag.clone();
+        if let Some(id) = self.id.as_deref() {
+            selector.push('#');
+            selector.push_str(id);
+        }
+        for class in self.class.as_deref().unwrap_or_default().split_whitespace() {
+            selector.push('.');
+            selector.push_str(class);
+        }
+        selector
+    }
+}
+
+/// The accessible elements of a page, in document order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+pub struct AccessibilitySnapshot {
+    pub nodes: Vec<AccessibilityNode>,
+    /// Whether nodes were left out after reaching the node limit
+    pub truncated: bool,
+}
+
+impl AccessibilitySnapshot {
+    /// One `[role] "name" (tag#id.class)` line per node, indented two spaces
+    /// per level.
+    pub fn format(&self) -> String {
+        if self.nodes.is_empty() {
+            return "No accessible elements found on the page".to_string();
+        }
+        let mut lines: Vec<String> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let mut line = format!("{}[{}]", "  ".repeat(node.depth), node.role);
+                if !node.name.is_empty() {
+                    line.push_str(&format!(" \"{}\"", node.name));
+                }
+                line.push_str(&format!(" ({})", node.selector()));
+                if let Some(description) = node.description.as_deref() {
+                    line.push_str(&format!(" - {}", description));
+                }
+                line
+            })
+            .collect();
+        if self.truncated {
+            lines.push(format!(
+                "... (stopped after {} nodes)",
+                MAX_ACCESSIBILITY_NODES
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Snapshot the accessibility tree of the current page, at most 6 levels
+/// deep and 500 nodes.
+pub async fn accessibility_snapshot<D: WebDriverController>(
+    driver: &mut D,
+) -> Result<AccessibilitySnapshot> {
+    let value = driver
+        .execute_script(
+            ACCESSIBILITY_SNAPSHOT_SCRIPT,
+            vec![
+                serde_json::json!(MAX_ACCESSIBILITY_DEPTH),
+                serde_json::json!(MAX_ACCESSIBILITY_NODES),
+            ],
+        )
+        .await?;
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Execute the `web_accessibility_snapshot` tool.
+pub async fn execute_web_accessibility_snapshot<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_accessibility_snapshot tool call");
+    let _ = tool_call; // unused
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    match accessibility_snapshot(&mut *driver).await {
+        Ok(snapshot) => Ok(snapshot.format()),
+        Err(e) => Ok(format!("❌ Failed to get accessibility snapshot: {}", e)),
+    }
+}
+
+/// Default timeout for `web_download_file`.
+const DEFAULT_DOWNLOAD_TIMEOUT_SECS: u64 = 60;
+
+/// Bytes downloaded between progress updates.
+const DOWNLOAD_PROGRESS_INTERVAL: u64 = 1024 * 1024;
+
+/// Whether the browser would send `cookie` with a request to `url`.
+fn cookie_matches(cookie: &Cookie, url: &reqwest::Url, now: i64) -> bool {
+    if cookie.secure && url.scheme() != "https" {
+        return false;
+    }
+    if cookie.expiry.is_some_and(|expiry| expiry <= now) {
+        return false;
+    }
+    if let Some(domain) = cookie.domain.as_deref() {
+        let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        if host != domain && !host.ends_with(&format!(".{}", domain)) {
+            return false;
+        }
+    }
+    match cookie.path.as_deref() {
+        None | Some("") | Some("/") => true,
+        Some(path) => {
+            let request_path = url.path();
+            request_path == path
+                || request_path
+                    .strip_prefix(path)
+                    .is_some_and(|rest| path.ends_with('/') || rest.starts_with('/'))
+        }
+    }
+}
+
+/// `Cookie` header value carrying the browser cookies that apply to `url`,
+/// or `None` if none do.
+pub fn cookie_header(cookies: &[Cookie], url: &reqwest::Url) -> Option<String> {
+    let now = chrono::Utc::now().timestamp();
+    let pairs: Vec<String> = cookies
+        .iter()
+        .filter(|cookie| cookie_matches(cookie, url, now))
+        .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+        .collect();
+    (!pairs.is_empty()).then(|| pairs.join("; "))
+}
+
+/// HTTP client that sends the browser's cookies for `url`.
+///
+/// The cookies go in a default `Cookie` header, which reqwest drops when a
+/// redirect leaves the original host.
+pub fn download_client(
+    cookies: &[Cookie],
+    url: &reqwest::Url,
+    timeout: Duration,
+) -> Result<reqwest::Client> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Some(header) = cookie_header(cookies, url) {
+        headers.insert(
+            reqwest::header::COOKIE,
+            reqwest::header::HeaderValue::from_str(&header)?,
+        );
+    }
+    Ok(reqwest::Client::builder()
+        .default_headers(headers)
+        .timeout(timeout)
+        .build()?)
+}
+
+/// Stream the response for `url` into `output_path`, returning the size and
+/// SHA-256 hash (hex) of the content.
+pub async fn download_to_file<W: UiWriter>(
+    client: &reqwest::Client,
+    url: reqwest::Url,
+    output_path: &std::path::Path,
+    ui_writer: &W,
+) -> Result<(u64, String)> {
+    let response = client.get(url).send().await?.error_for_status()?;
+    let total = response.content_length();
+
+    if let Some(parent) = output_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut file = tokio::fs::File::create(output_path).await?;
+    let written = write_response(response, &mut file, total, ui_writer).await;
+    if written.is_err() {
+        // Don't leave a truncated file behind
+        drop(file);
+        if let Err(e) = tokio::fs::remove_file(output_path).await {
+            warn!(
+                "Failed to remove partial download {}: {}",
+                output_path.display(),
+                e
+            );
+        }
+    }
+    written
+}
+
+/// Write the body of `response` to `file`, hashing it as it goes.
+async fn write_response<W: UiWriter>(
+    response: reqwest::Response,
+    file: &mut tokio::fs::File,
+    total: Option<u64>,
+    ui_writer: &W,
+) -> Result<(u64, String)> {
+    use futures_util::StreamExt;
+    use sha2::Digest;
+    use tokio::io::AsyncWriteExt;
+
+    let mut hasher = sha2::Sha256::new();
+    let mut downloaded: u64 = 0;
+    let mut next_progress = DOWNLOAD_PROGRESS_INTERVAL;
+
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        file.write_all(&chunk).await?;
+        hasher.update(&chunk);
+        downloaded += chunk.len() as u64;
+        if downloaded >= next_progress {
+            next_progress = downloaded + DOWNLOAD_PROGRESS_INTERVAL;
+            ui_writer.update_tool_output_line(&match total {
+                Some(total) if total > 0 => format!(
+                    "Downloaded {} / {} bytes ({}%)",
+                    downloaded,
+                    total,
+                    downloaded * 100 / total
+                ),
+                _ => format!("Downloaded {} bytes", downloaded),
+            });
+        }
+    }
+    file.flush().await?;
+
+    Ok((downloaded, format!("{:x}", hasher.finalize())))
+}
+
+/// Execute the `web_download_file` tool.
+pub async fn execute_web_download_file<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_download_file tool call");
+
+    let url = match tool_call.args.get("url").and_then(|v| v.as_str()) {
+        Some(url) => url,
+        None => return Ok("❌ Missing url argument".to_string()),
+    };
+    let url = match reqwest::Url::parse(url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => url,
+        Ok(_) => return Ok(format!("❌ Only http and https URLs can be downloaded: {}", url)),
+        Err(e) => return Ok(format!("❌ Invalid url '{}': {}", url, e)),
+    };
+    let output_path = match tool_call.args.get("output_path").and_then(|v| v.as_str()) {
+        Some(path) if !path.trim().is_empty() => ctx.resolve_path(path),
+        _ => return Ok("❌ Missing output_path argument".to_string()),
+    };
+    let timeout = Duration::from_secs(
+        tool_call
+            .args
+            .get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_DOWNLOAD_TIMEOUT_SECS),
+    );
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+    let cookies = match session.lock().await.get_all_cookies().await {
+        Ok(cookies) => cookies,
+        Err(e) => return Ok(format!("❌ Failed to get cookies: {}", e)),
+    };
+
+    let client = match download_client(&cookies, &url, timeout) {
+        Ok(client) => client,
+        Err(e) => return Ok(format!("❌ Failed to create HTTP client: {}", e)),
+    };
+
+    match download_to_file(&client, url.clone(), &output_path, ctx.ui_writer).await {
+        Ok((size, sha256)) => Ok(format!(
+            "✅ Downloaded {} to {}\nsize: {} bytes\nsha256: {}",
+            url,
+            output_path.display(),
+            size,
+            sha256
+        )),
+        Err(e) => Ok(format!("❌ Failed to download {}: {}", url, e)),
+    }
+}
+
+/// Longest request body or response text kept for each captured request.
+const MAX_NETWORK_PREVIEW: u64 = 500;
+
+/// Script wrapping `fetch` and `XMLHttpRequest` so every request the page makes
+/// is appended to `window.__g3_network_log`. Safe to run more than once per
+/// page; `arguments[0]` is the preview length.
+const NETWORK_LOG_SCRIPT: &str = r#"
+if (window.__g3_network_log) return false;
+const log = window.__g3_network_log = [];
+const maxPreview = arguments[0];
+const preview = value => value == null ? null : String(value).slice(0, maxPreview);
+const bodyText = body => {
+    if (body == null) return null;
+    if (typeof body === 'string' || body instanceof URLSearchParams) return preview(body);
+    return '[' + ((body.constructor && body.constructor.name) || typeof body) + ']';
+};
+const absolute = url => { try { return new URL(url, location.href).href; } catch (e) { return String(url); } };
+
+const originalFetch = window.fetch;
+if (originalFetch) {
+    window.fetch = function (input, init) {
+        const entry = {
+            method: String((init && init.method) || (input && input.method) || 'GET').toUpperCase(),
+            url: absolute(input && input.url ? input.url : input),
+            request_body: bodyText(init && init.body),
+            status: null,
+            response_preview: null,
+        };
+        log.push(entry);
+        return originalFetch.apply(this, arguments).then(response => {
+            entry.status = response.status;
+            response.clone().text().then(text => { entry.response_preview = preview(text); }, () => {});
+            return response;
+        });
+    };
+}
+
+const originalOpen = XMLHttpRequest.prototype.open;
+const originalSend = XMLHttpRequest.prototype.send;
+XMLHttpRequest.prototype.open = function (method, url) {
+    this.__g3_entry = {
+        method: String(method).toUpperCase(),
+        url: absolute(url),
+        request_body: null,
+        status: null,
+        response_preview: null,
+    };
+    return originalOpen.apply(this, arguments);
+};
+XMLHttpRequest.prototype.send = function (body) {
+    const entry = this.__g3_entry;
+    if (entry) {
+        entry.request_body = bodyText(body);
+        log.push(entry);
+        this.addEventListener('loadend', () => {
+            entry.status = this.status;
+            const textual = this.responseType === '' || this.responseType === 'text';
+            entry.response_preview = textual ? preview(this.responseText) : '[' + this.responseType + ']';
+        });
+    }
+    return originalSend.apply(this, arguments);
+};
+return true;
+"#;
+
+/// Script returning a copy of `window.__g3_network_log`, or `null` if capture
+/// isn't installed on the page. `arguments[0]` empties the log afterwards.
+const READ_NETWORK_LOG_SCRIPT: &str = r#"
+const log = window.__g3_network_log;
+if (!log) return null;
+const entries = log.slice();
+if (arguments[0]) log.length = 0;
+return entries;
+"#;
+
+/// A request captured by the `fetch`/`XMLHttpRequest` wrappers.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NetworkRequest {
+    pub method: String,
+    pub url: String,
+    pub request_body: Option<String>,
+    /// `None` while the request is still in flight
+    pub status: Option<u16>,
+    pub response_preview: Option<String>,
+}
+
+/// Start recording the page's fetch and XHR requests. Returns `false` if
+/// capture was already running on this page.
+///
+/// WebDriver can't run a script before a page's own scripts, so requests made
+/// while the page loads, before this is called, are not captured.
+pub async fn install_network_log<D: WebDriverController>(driver: &mut D) -> Result<bool> {
+    let value = driver
+        .execute_script(
+            NETWORK_LOG_SCRIPT,
+            vec![serde_json::json!(MAX_NETWORK_PREVIEW)],
+        )
+        .await?;
+    Ok(value.as_bool().unwrap_or(false))
+}
+
+/// Requests captured on the current page, oldest first, or `None` if capture
+/// isn't running. With `clear` the log is emptied after reading.
+pub async fn get_network_requests<D: WebDriverController>(
+    driver: &mut D,
+    clear: bool,
+) -> Result<Option<Vec<NetworkRequest>>> {
+    let value = driver
+        .execute_script(READ_NETWORK_LOG_SCRIPT, vec![serde_json::json!(clear)])
+        .await?;
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Execute the `web_get_network_requests` tool.
+pub async fn execute_web_get_network_requests<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_get_network_requests tool call");
+
+    let clear = tool_call
+        .args
+        .get("clear")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    match get_network_requests(&mut *driver, clear).await {
+        Ok(Some(requests)) => {
+            let cleared = if clear { " (log cleared)" } else { "" };
+            Ok(format!(
+                "✅ {} network request(s) captured{}:\n{}",
+                requests.len(),
+                cleared,
+                serde_json::to_string_pretty(&requests)?
+            ))
+        }
+        // The page was reloaded or left without going through webdriver_navigate
+        Ok(None) => match install_network_log(&mut *driver).await {
+            Ok(_) => Ok("✅ Network capture started on this page. Requests made from now on \
+                will be recorded; call web_get_network_requests again to read them."
+                .to_string()),
+            Err(e) => Ok(format!("❌ Failed to start network capture: {}", e)),
+        },
+        Err(e) => Ok(format!("❌ Failed to read network requests: {}", e)),
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Web storage
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Script running one `web_local_storage` action. Arguments are the storage
+/// area (`localStorage` or `sessionStorage`), the action, the key and the raw
+/// string to store. Returns `{ value }` on success or `{ error }` when the page
+/// may not use storage, e.g. sandboxed frames and `data:` URLs.
+const WEB_STORAGE_SCRIPT: &str = r#"
+const [area, action, key, value] = arguments;
+let storage;
+try {
+    storage = window[area];
+    storage.length;
+} catch (e) {
+    return { error: e.name + ': ' + e.message };
+}
+try {
+    switch (action) {
+        case 'get':
+            return { value: storage.getItem(key) };
+        case 'set':
+            storage.setItem(key, value);
+            return { value: null };
+        case 'remove': {
+            const existed = storage.getItem(key) !== null;
+            storage.removeItem(key);
+            return { value: existed };
+        }
+        case 'clear': {
+            const count = storage.length;
+            storage.clear();
+            return { value: count };
+        }
+        case 'list': {
+            const entries = [];
+            for (let i = 0; i < storage.length; i++) {
+                const name = storage.key(i);
+                entries.push([name, storage.getItem(name)]);
+            }
+            return { value: entries };
+        }
+    }
+    return { error: 'Unknown action ' + action };
+} catch (e) {
+    return { error: e.name + ': ' + e.message };
+}
+"#;
+
+/// Which Web Storage area `web_local_storage` works on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageType {
+    Local,
+    Session,
+}
+
+impl StorageType {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "local" => Some(StorageType::Local),
+            "session" => Some(StorageType::Session),
+            _ => None,
+        }
+    }
+
+    fn js_name(self) -> &'static str {
+        match self {
+            StorageType::Local => "localStorage",
+            StorageType::Session => "sessionStorage",
+        }
+    }
+}
+
+/// Stored strings are JSON when they parse as JSON, otherwise plain strings.
+pub fn parse_stored_value(raw: &str) -> serde_json::Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string()))
+}
+
+/// The string to store for `value`: strings as they are, so tokens and other
+/// plain values can be seeded, and anything else as JSON.
+pub fn stored_value_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+async fn run_storage_script<D: WebDriverController>(
+    driver: &mut D,
+    storage: StorageType,
+    action: &str,
+    key: Option<&str>,
+    value: Option<String>,
+) -> Result<serde_json::Value> {
+    let result = driver
+        .execute_script(
+            WEB_STORAGE_SCRIPT,
+            vec![
+                serde_json::json!(storage.js_name()),
+                serde_json::json!(action),
+                serde_json::json!(key),
+                serde_json::json!(value),
+            ],
+        )
+        .await?;
+    if let Some(error) = result.get("error").and_then(|e| e.as_str()) {
+        anyhow::bail!(
+            "{} is not available on this page ({}). Navigate to a page of the app's own origin first.",
+            storage.js_name(),
+            error
+        );
+    }
+    Ok(result.get("value").cloned().unwrap_or(serde_json::Value::Null))
+}
+
+/// Read `key`, or `None` if it isn't set.
+pub async fn storage_get<D: WebDriverController>(
+    driver: &mut D,
+    storage: StorageType,
+    key: &str,
+) -> Result<Option<serde_json::Value>> {
+    let value = run_storage_script(driver, storage, "get", Some(key), None).await?;
+    Ok(value.as_str().map(parse_stored_value))
+}
+
+pub async fn storage_set<D: WebDriverController>(
+    driver: &mut D,
+    storage: StorageType,
+    key: &str,
+    value: &serde_json::Value,
+) -> Result<()> {
+    run_storage_script(driver, storage, "set", Some(key), Some(stored_value_string(value))).await?;
+    Ok(())
+}
+
+/// Remove `key`. Returns whether it was set.
+pub async fn storage_remove<D: WebDriverController>(
+    driver: &mut D,
+    storage: StorageType,
+    key: &str,
+) -> Result<bool> {
+    let value = run_storage_script(driver, storage, "remove", Some(key), None).await?;
+    Ok(value.as_bool().unwrap_or(false))
+}
+
+/// Remove every key. Returns how many there were.
+pub async fn storage_clear<D: WebDriverController>(
+    driver: &mut D,
+    storage: StorageType,
+) -> Result<u64> {
+    let value = run_storage_script(driver, storage, "clear", None, None).await?;
+    Ok(value.as_u64().unwrap_or(0))
+}
+
+/// All keys with their parsed values, in storage order.
+pub async fn storage_list<D: WebDriverController>(
+    driver: &mut D,
+    storage: StorageType,
+) -> Result<Vec<(String, serde_json::Value)>> {
+    let value = run_storage_script(driver, storage, "list", None, None).await?;
+    let entries: Vec<(String, String)> = serde_json::from_value(value)?;
+    Ok(entries
+        .into_iter()
+        .map(|(key, raw)| {
+            let value = parse_stored_value(&raw);
+            (key, value)
+        })
+        .collect())
+}
+
+/// Execute the `web_local_storage` tool.
+pub async fn execute_web_local_storage<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_local_storage tool call");
+
+    let action = tool_call
+        .args
+        .get("action")
+        .and_then(|v| v.as_str())
+        .unwrap_or("list");
+    let storage_arg = tool_call
+        .args
+        .get("storage_type")
+        .and_then(|v| v.as_str())
+        .unwrap_or("local");
+    let storage = match StorageType::parse(storage_arg) {
+        Some(storage) => storage,
+        None => {
+            return Ok(format!(
+                "❌ Unknown storage_type '{}'. Use 'local' or 'session'",
+                storage_arg
+            ))
+        }
+    };
+    let key = tool_call.args.get("key").and_then(|v| v.as_str());
+    let needs_key = matches!(action, "get" | "set" | "remove");
+    if needs_key && key.is_none() {
+        return Ok(format!("❌ Missing key argument for '{}'", action));
+    }
+    let value = tool_call.args.get("value");
+    if action == "set" && value.is_none() {
+        return Ok("❌ Missing value argument for 'set'".to_string());
+    }
+    if !needs_key && !matches!(action, "clear" | "list") {
+        return Ok(format!(
+            "❌ Unknown action '{}'. Use 'get', 'set', 'remove', 'clear' or 'list'",
+            action
+        ));
+    }
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+    let mut driver = session.lock().await;
+    let area = storage.js_name();
+    let key = key.unwrap_or_default();
+
+    let result = match action {
+        "get" => storage_get(&mut *driver, storage, key).await.map(|value| match value {
+            Some(value) => format!("{}[{}] = {}", area, key, value),
+            None => format!("{}[{}] is not set", area, key),
+        }),
+        "set" => storage_set(&mut *driver, storage, key, value.unwrap_or(&serde_json::Value::Null))
+            .await
+            .map(|_| format!("✅ Set {}[{}]", area, key)),
+        "remove" => storage_remove(&mut *driver, storage, key).await.map(|existed| {
+            if existed {
+                format!("✅ Removed {}[{}]", area, key)
+            } else {
+                format!("{}[{}] was not set", area, key)
+            }
+        }),
+        "clear" => storage_clear(&mut *driver, storage)
+            .await
+            .map(|count| format!("✅ Cleared {} ({} item(s) removed)", area, count)),
+        _ => storage_list(&mut *driver, storage).await.and_then(|entries| {
+            if entries.is_empty() {
+                return Ok(format!("{} is empty", area));
+            }
+            let map: serde_json::Map<String, serde_json::Value> = entries.into_iter().collect();
+            Ok(format!(
+                "✅ {} item(s) in {}:\n{}",
+                map.len(),
+                area,
+                serde_json::to_string_pretty(&map)?
+            ))
+        }),
+    };
+    match result {
+        Ok(output) => Ok(output),
+        Err(e) => Ok(format!("❌ {}", e)),
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Frames
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Which frame `web_switch_frame` should enter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameTarget {
+    /// The `<iframe>` or `<frame>` element matching a CSS selector.
+    Selector(String),
+    /// The frame at this index among the current document's frames.
+    Index(usize),
+}
+
+impl FrameTarget {
+    /// Label recorded in the session's frame path.
+    pub fn label(&self) -> String {
+        match self {
+            FrameTarget::Selector(selector) => selector.clone(),
+            FrameTarget::Index(index) => format!("index {}", index),
+        }
+    }
+}
+
+/// Switch `driver` into the frame described by `target`, relative to the
+/// frame it is currently in.
+pub async fn switch_frame<D: WebDriverController>(driver: &mut D, target: &FrameTarget) -> Result<()> {
+    match target {
+        FrameTarget::Selector(selector) => {
+            let element = driver.find_element(selector).await?;
+            element.enter_frame().await
+        }
+        FrameTarget::Index(index) => {
+            let index = u16::try_from(*index)
+                .map_err(|_| anyhow::anyhow!("Frame index {} is out of range", index))?;
+            driver.switch_to_frame(Some(index)).await
+        }
+    }
+}
+
+/// Execute the `web_switch_frame` tool.
+pub async fn execute_web_switch_frame<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_switch_frame tool call");
+
+    let selector = tool_call.args.get("selector").and_then(|v| v.as_str());
+    let index = tool_call.args.get("index").and_then(|v| v.as_u64());
+
+    let target = match (selector, index) {
+        (Some(selector), None) => FrameTarget::Selector(selector.to_string()),
+        (None, Some(index)) => FrameTarget::Index(index as usize),
+        (Some(_), Some(_)) => {
+            return Ok("❌ Provide either 'selector' or 'index', not both".to_string())
+        }
+        (None, None) => return Ok("❌ Missing 'selector' or 'index' argument".to_string()),
+    };
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    match switch_frame(&mut *driver, &target).await {
+        Ok(_) => {
+            let label = target.label();
+            ctx.webdriver_state.write().await.frame_path.push(label.clone());
+            Ok(format!("✅ Switched to frame {}", label))
+        }
+        Err(e) => Ok(format!("❌ Failed to switch to frame {}: {}", target.label(), e)),
+    }
+}
+
+/// Execute the `web_switch_to_default` tool.
+pub async fn execute_web_switch_to_default<W: UiWriter>(
+    _tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing web_switch_to_default tool call");
+
+    let session = match get_session(ctx).await {
+        Ok(s) => s,
+        Err(msg) => return Ok(msg),
+    };
+
+    let mut driver = session.lock().await;
+    match driver.switch_to_frame(None).await {
+        Ok(_) => {
+            ctx.webdriver_state.write().await.frame_path.clear();
+            Ok("✅ Switched to the top-level page".to_string())
+        }
+        Err(e) => Ok(format!("❌ Failed to switch to the top-level page: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Fake driver whose element state advances one step per script call.
+    /// A state or URL of "error" makes that call fail.
+    struct MockDriver {
+        states: Vec<&'static str>,
+        calls: AtomicUsize,
+        /// Arguments of every `execute_script` call
+        script_args: Vec<Vec<serde_json::Value>>,
+        /// URLs returned by successive `current_url` calls, the last repeating
+        urls: Vec<&'static str>,
+        url_calls: AtomicUsize,
+        /// Arguments of every `switch_to_frame` call
+        frames: Vec<Option<u16>>,
+    }
+
+    impl MockDriver {
+        fn new(states: Vec<&'static str>) -> Self {
+            Self {
+                states,
+                calls: AtomicUsize::new(0),
+                script_args: Vec::new(),
+                urls: vec!["about:blank"],
+                url_calls: AtomicUsize::new(0),
+                frames: Vec::new(),
+            }
+        }
+
+        fn with_urls(mut self, urls: Vec<&'static str>) -> Self {
+            self.urls = urls;
+            self
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl WebDriverController for MockDriver {
+        async fn navigate(&mut self, _url: &str) -> Result<()> {
+            Ok(())
+        }
+        async fn current_url(&self) -> Result<String> {
+            let call = self.url_calls.fetch_add(1, Ordering::SeqCst);
+            match self.urls[call.min(self.urls.len() - 1)] {
+                "error" => anyhow::bail!("no such window: target window already closed"),
+                url => Ok(url.to_string()),
+            }
+        }
+        async fn title(&self) -> Result<String> {
+            Ok(String::new())
+        }
+        async fn find_element(&mut self, _selector: &str) -> Result<WebElement> {
+            anyhow::bail!("not supported by mock")
+        }
+        async fn find_elements(&mut self, _selector: &str) -> Result<Vec<WebElement>> {
+            Ok(vec![])
+        }
+        async fn find_element_xpath(&mut self, xpath: &str) -> Result<WebElement> {
+            anyhow::bail!("no such element: {}", xpath)
+        }
+        async fn find_elements_xpath(&mut self, _xpath: &str) -> Result<Vec<WebElement>> {
+            Ok(vec![])
+        }
+        async fn execute_script(
+            &mut self,
+            _script: &str,
+            args: Vec<serde_json::Value>,
+        ) -> Result<serde_json::Value> {
+            self.script_args.push(args);
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.states[call.min(self.states.len() - 1)] {
+                "error" => anyhow::bail!("javascript error: document unloaded"),
+                state => Ok(serde_json::json!(state)),
+            }
+        }
+        async fn page_source(&self) -> Result<String> {
+            Ok(String::new())
+        }
+        async fn screenshot(&mut self, _path: &str) -> Result<()> {
+            Ok(())
+        }
+        async fn close(&mut self) -> Result<()> {
+            Ok(())
+        }
+        async fn quit(self) -> Result<()> {
+            Ok(())
+        }
+        async fn back(&mut self) -> Result<()> {
+            Ok(())
+        }
+        async fn forward(&mut self) -> Result<()> {
+            Ok(())
+        }
+        async fn refresh(&mut self) -> Result<()> {
+            Ok(())
+        }
+        async fn window_handles(&self) -> Result<Vec<String>> {
+            Ok(vec![])
+        }
+        async fn get_all_cookies(&mut self) -> Result<Vec<Cookie>> {
+            Ok(vec![])
+        }
+        async fn add_cookie(&mut self, _cookie: Cookie) -> Result<()> {
+            Ok(())
+        }
+        async fn switch_to_frame(&mut self, index: Option<u16>) -> Result<()> {
+            self.frames.push(index);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_parse_checkbox_value() {
+        assert_eq!(parse_checkbox_value("true"), Some(true));
+        assert_eq!(parse_checkbox_value(" Yes "), Some(true));
+        assert_eq!(parse_checkbox_value("1"), Some(true));
+        assert_eq!(parse_checkbox_value("off"), Some(false));
+        assert_eq!(parse_checkbox_value(""), Some(false));
+        assert_eq!(parse_checkbox_value("maybe"), None);
+    }
+
+    #[tokio::test]
+    async fn test_fill_form_reports_missing_fields() {
+        // The script reports a field, but the mock cannot hand out elements
+        let mut driver = MockDriver::new(vec!["text"]);
+        let result = fill_form(
+            &mut driver,
+            Some("#signup"),
+            &[("email".to_string(), "a@example.com".to_string())],
+            true,
+        )
+        .await
+        .unwrap();
+
+        assert!(result.starts_with("❌ Could not fill form"), "{}", result);
+        assert_eq!(driver.script_args.len(), 1, "form must not be submitted");
+        assert_eq!(
+            driver.script_args[0],
+            vec![
+                serde_json::json!("#signup"),
+                serde_json::json!("email"),
+                serde_json::json!("a@example.com"),
+                serde_json::json!("0"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_element_appears_after_polling() {
+        let mut driver = MockDriver::new(vec!["missing", "missing", "present"]);
+        let elapsed = wait_for_element(
+            &mut driver,
+            "#late",
+            ElementCondition::Present,
+            Duration::from_secs(5),
+            Duration::from_millis(5),
+        )
+        .await
+        .unwrap();
+
+        assert!(elapsed.is_some());
+        assert_eq!(driver.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_element_waits_for_condition() {
+        let mut driver = MockDriver::new(vec!["present", "visible", "clickable"]);
+        let elapsed = wait_for_element(
+            &mut driver,
+            "button",
+            ElementCondition::Clickable,
+            Duration::from_secs(5),
+            Duration::from_millis(5),
+        )
+        .await
+        .unwrap();
+
+        assert!(elapsed.is_some());
+        assert_eq!(driver.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_element_times_out() {
+        let mut driver = MockDriver::new(vec!["present"]);
+        let elapsed = wait_for_element(
+            &mut driver,
+            "#hidden",
+            ElementCondition::Visible,
+            Duration::from_millis(50),
+            Duration::from_millis(5),
+        )
+        .await
+        .unwrap();
+
+        assert!(elapsed.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_element_polls_through_script_errors() {
+        let mut driver = MockDriver::new(vec!["error", "missing", "error", "visible"]);
+        let elapsed = wait_for_element(
+            &mut driver,
+            "#late",
+            ElementCondition::Visible,
+            Duration::from_secs(5),
+            Duration::from_millis(5),
+        )
+        .await
+        .unwrap();
+
+        assert!(elapsed.is_some());
+        assert_eq!(driver.calls.load(Ordering::SeqCst), 4);
+
+        // Errors until the timeout are reported once it passes
+        let mut driver = MockDriver::new(vec!["error"]);
+        let err = wait_for_element(
+            &mut driver,
+            "#gone",
+            ElementCondition::Present,
+            Duration::from_millis(50),
+            Duration::from_millis(5),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("document unloaded"));
+        assert!(driver.calls.load(Ordering::SeqCst) > 1);
+    }
+
+    #[test]
+    fn test_element_condition_ordering() {
+        assert!(ElementCondition::Clickable > ElementCondition::Visible);
+        assert!(ElementCondition::Visible > ElementCondition::Present);
+        assert_eq!(ElementCondition::parse("visible"), Some(ElementCondition::Visible));
+        assert_eq!(ElementCondition::parse("gone"), None);
+    }
+
+    fn cell(text: &str, header: bool) -> TableCell {
+        TableCell {
+            text: text.to_string(),
+            header,
+            colspan: 1,
+            rowspan: 1,
+        }
+    }
+
+    #[test]
+    fn test_table_to_records_uses_header_row() {
+        let rows = vec![
+            vec![cell("Name", true), cell("Age", true)],
+            vec![cell("Ada", false), cell("36", false)],
+            vec![cell("Alan", false), cell("41", false)],
+        ];
+        let records = table_to_records(&rows, None, None);
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["Name"], "Ada");
+        assert_eq!(records[1]["Age"], "41");
+        assert_eq!(table_to_records(&rows, None, Some(1)).len(), 1);
+    }
+
+    #[test]
+    fn test_table_to_records_header_override_keeps_plain_first_row() {
+        let rows = vec![
+            vec![cell("Ada", false), cell("36", false)],
+            vec![cell("Alan", false), cell("41", false)],
+        ];
+        let headers = vec!["name".to_string(), "age".to_string()];
+        let records = table_to_records(&rows, Some(&headers), None);
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["name"], "Ada");
+    }
+
+    #[test]
+    fn test_table_to_records_skips_merged_cells() {
+        let mut region = cell("North", false);
+        region.rowspan = 2;
+        let mut total = cell("Total: 3", false);
+        total.colspan = 3;
+        let rows = vec![
+            vec![cell("Region", true), cell("City", true), cell("Stores", true)],
+            vec![region, cell("Oslo", false), cell("2", false)],
+            vec![cell("Bergen", false), cell("1", false)],
+            vec![total],
+        ];
+        let records = table_to_records(&rows, None, None);
+
+        assert_eq!(records[0]["Region"], "North");
+        // The row under a rowspan is shifted past the merged column
+        assert!(!records[1].contains_key("Region"));
+        assert_eq!(records[1]["City"], "Bergen");
+        assert_eq!(records[1]["Stores"], "1");
+        // A colspan fills only its first column
+        assert_eq!(records[2].len(), 1);
+        assert_eq!(records[2]["Region"], "Total: 3");
+    }
+
+    #[test]
+    fn test_accessibility_snapshot_format() {
+        let snapshot: AccessibilitySnapshot = serde_json::from_value(serde_json::json!({
+            "nodes": [
+                {"depth": 0, "role": "navigation", "name": "Primary", "description": null,
+                 "tag": "nav", "id": "menu", "class": "top  sticky"},
+                {"depth": 1, "role": "link", "name": "Home", "description": null,
+                 "tag": "a", "id": null, "class": null},
+                {"depth": 0, "role": "button", "name": "", "description": "Deletes the draft",
+                 "tag": "button", "id": null, "class": "danger"},
+            ],
+            "truncated": true,
+        }))
+        .unwrap();
+
+        assert_eq!(
+            snapshot.format(),
+            "[navigation] \"Primary\" (nav#menu.top.sticky)\n\
+             \x20 [link] \"Home\" (a)\n\
+             [button] (button.danger) - Deletes the draft\n\
+             ... (stopped after 500 nodes)"
+        );
+        assert_eq!(
+            AccessibilitySnapshot::default().format(),
+            "No accessible elements found on the page"
+        );
+    }
+
+    #[test]
+    fn test_page_metadata_handles_missing_tags() {
+        let metadata: PageMetadata = serde_json::from_value(serde_json::json!({
+            "title": "Example Domain",
+            "description": null,
+            "canonical_url": "https://example.com/",
+            "og_title": "Example",
+            "og_description": null,
+            "og_image": null,
+        }))
+        .unwrap();
+
+        assert_eq!(metadata.title.as_deref(), Some("Example Domain"));
+        assert_eq!(
+            metadata.format(),
+            "Title: Example Domain\n\
+             Description: <none>\n\
+             Canonical URL: https://example.com/\n\
+             og:title: Example\n\
+             og:description: <none>\n\
+             og:image: <none>"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scroll_window_by_direction() {
+        let mut driver = MockDriver::new(vec!["scrolled"]);
+        let action = ScrollAction::by_direction("up", 300).unwrap();
+        let result = scroll_page(&mut driver, None, &action, true).await.unwrap();
+
+        assert_eq!(result, "✅ Scrolled page by (0, -300) pixels");
+        assert_eq!(
+            driver.script_args[0],
+            vec![
+                serde_json::Value::Null,
+                serde_json::json!(0),
+                serde_json::json!(-300),
+                serde_json::json!("smooth"),
+                serde_json::Value::Null,
+            ]
+        );
+        assert_eq!(
+            ScrollAction::by_direction("right", 50),
+            Some(ScrollAction::By { dx: 50, dy: 0 })
+        );
+        assert_eq!(ScrollAction::by_direction("sideways", 50), None);
+    }
+
+    #[tokio::test]
+    async fn test_scroll_element_and_target_into_view() {
+        let mut driver = MockDriver::new(vec!["scrolled"]);
+        let action = ScrollAction::IntoView("#item-40".to_string());
+        let result = scroll_page(&mut driver, Some("#feed"), &action, false)
+            .await
+            .unwrap();
+
+        assert_eq!(result, "✅ Scrolled element '#feed' until '#item-40' is in view");
+        assert_eq!(driver.script_args[0][0], serde_json::json!("#feed"));
+        assert_eq!(driver.script_args[0][3], serde_json::json!("instant"));
+        assert_eq!(driver.script_args[0][4], serde_json::json!("#item-40"));
+    }
+
+    #[tokio::test]
+    async fn test_scroll_reports_missing_elements() {
+        let action = ScrollAction::By { dx: 0, dy: 500 };
+        let mut driver = MockDriver::new(vec!["missing"]);
+        let result = scroll_page(&mut driver, Some("#nope"), &action, false)
+            .await
+            .unwrap();
+        assert_eq!(result, "❌ No element found for selector '#nope'");
+
+        let action = ScrollAction::IntoView(".footer".to_string());
+        let mut driver = MockDriver::new(vec!["target_missing"]);
+        let result = scroll_page(&mut driver, None, &action, false).await.unwrap();
+        assert_eq!(result, "❌ No element found for target_selector '.footer'");
+    }
+
+    #[test]
+    fn test_script_return_type_format() {
+        let value = serde_json::json!({"links": ["/a", "/b"], "count": 2});
+        assert_eq!(
+            ScriptReturnType::Json.format(&value),
+            serde_json::to_string_pretty(&value).unwrap()
+        );
+        assert_eq!(
+            ScriptReturnType::Text.format(&serde_json::json!("Example Domain")),
+            "Example Domain"
+        );
+        assert_eq!(ScriptReturnType::Text.format(&serde_json::json!(42)), "42");
+        assert_eq!(ScriptReturnType::Text.format(&serde_json::Value::Null), "");
+        assert_eq!(ScriptReturnType::None.format(&value), "✅ Script executed");
+
+        assert_eq!(
+            ScriptReturnType::parse("TEXT"),
+            Some(ScriptReturnType::Text)
+        );
+        assert_eq!(ScriptReturnType::parse("xml"), None);
+    }
+
+    #[tokio::test]
+    async fn test_run_script_passes_args() {
+        let mut driver = MockDriver::new(vec!["done"]);
+        let args = vec![serde_json::json!("#main"), serde_json::json!(3)];
+        let result = run_script(
+            &mut driver,
+            "return arguments[0];",
+            args.clone(),
+            ScriptReturnType::Text,
+        )
+        .await;
+        assert_eq!(result, "done");
+        assert_eq!(driver.script_args, vec![args]);
+    }
+
+    fn cookie(name: &str, domain: Option<&str>, path: Option<&str>) -> Cookie {
+        Cookie {
+            name: name.to_string(),
+            value: format!("{}-value", name),
+            domain: domain.map(str::to_string),
+            path: path.map(str::to_string),
+            secure: false,
+            http_only: false,
+            expiry: None,
+        }
+    }
+
+    #[test]
+    fn test_cookie_header_filters_by_domain_and_path() {
+        let cookies = vec![
+            cookie("session", Some(".example.com"), None),
+            cookie("app", Some("app.example.com"), Some("/files")),
+            cookie("other", Some("other.org"), None),
+            cookie("admin", Some("app.example.com"), Some("/admin")),
+        ];
+
+        let url = reqwest::Url::parse("http://app.example.com/files/report.pdf").unwrap();
+        assert_eq!(
+            cookie_header(&cookies, &url).as_deref(),
+            Some("session=session-value; app=app-value")
+        );
+
+        let url = reqwest::Url::parse("http://example.com/filesystem").unwrap();
+        assert_eq!(
+            cookie_header(&cookies, &url).as_deref(),
+            Some("session=session-value")
+        );
+
+        let url = reqwest::Url::parse("http://unrelated.net/").unwrap();
+        assert_eq!(cookie_header(&cookies, &url), None);
+    }
+
+    #[test]
+    fn test_cookie_header_skips_secure_and_expired() {
+        let now = chrono::Utc::now().timestamp();
+        let mut secure = cookie("secure", None, None);
+        secure.secure = true;
+        let mut expired = cookie("expired", None, None);
+        expired.expiry = Some(now - 60);
+        let mut fresh = cookie("fresh", None, None);
+        fresh.expiry = Some(now + 3600);
+        let cookies = vec![secure, expired, fresh];
+
+        let http = reqwest::Url::parse("http://example.com/").unwrap();
+        assert_eq!(
+            cookie_header(&cookies, &http).as_deref(),
+            Some("fresh=fresh-value")
+        );
+        let https = reqwest::Url::parse("https://example.com/").unwrap();
+        assert_eq!(
+            cookie_header(&cookies, &https).as_deref(),
+            Some("secure=secure-value; fresh=fresh-value")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_client_sends_cookies() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let n = socket.read(&mut request).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request[..n]).to_string()
+        });
+
+        let url = reqwest::Url::parse(&format!("http://{}/report.csv", addr)).unwrap();
+        let cookies = vec![cookie("session", Some("127.0.0.1"), None)];
+        let client = download_client(&cookies, &url, Duration::from_secs(5)).unwrap();
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let output = temp_dir.path().join("downloads").join("report.csv");
+        let (size, sha256) =
+            download_to_file(&client, url, &output, &crate::ui_writer::NullUiWriter)
+                .await
+                .unwrap();
+
+        let request = server.await.unwrap().to_ascii_lowercase();
+        assert!(
+            request.contains("cookie: session=session-value"),
+            "{}",
+            request
+        );
+        assert_eq!(size, 5);
+        assert_eq!(
+            sha256,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_xpath_literal_quoting() {
+        assert_eq!(xpath_literal("Sign in"), "'Sign in'");
+        assert_eq!(xpath_literal("Don't"), "\"Don't\"");
+        assert_eq!(
+            xpath_literal(r#"Say "don't""#),
+            r#"concat('Say "don', "'", 't"')"#
+        );
+    }
+
+    #[test]
+    fn test_text_xpath() {
+        assert_eq!(
+            text_xpath("Sign In", TextMatch::Exact, Some("button"), true),
+            "//button[normalize-space(.) = 'Sign In' and not(.//button[normalize-space(.) = 'Sign In'])]"
+        );
+
+        let xpath = text_xpath("  Read   MORE ", TextMatch::Contains, Some("a"), false);
+        assert!(
+            xpath.starts_with(&format!(
+                "//a[contains(translate(normalize-space(.), '{}', '{}'), 'read more')",
+                UPPERCASE, LOWERCASE
+            )),
+            "{}",
+            xpath
+        );
+
+        let xpath = text_xpath("Subm", TextMatch::StartsWith, Some("input"), true);
+        assert!(
+            xpath.contains("starts-with(normalize-space(@value), 'Subm')"),
+            "{}",
+            xpath
+        );
+
+        let any = text_xpath("Next", TextMatch::Contains, None, true);
+        assert!(any.starts_with("//*[contains("), "{}", any);
+        assert!(any.contains("not(self::script"), "{}", any);
+
+        assert_eq!(TextMatch::parse("STARTS_WITH"), Some(TextMatch::StartsWith));
+        assert_eq!(TextMatch::parse("regex"), None);
+    }
+
+    #[tokio::test]
+    async fn test_find_by_xpath_without_matches() {
+        let mut driver = MockDriver::new(vec!["complete"]);
+        let all = find_by_xpath(&mut driver, "//button", false).await.unwrap();
+        assert_eq!(all.total, 0);
+        assert!(all.elements.is_empty());
+
+        let err = find_by_xpath(&mut driver, "//button", true).await.unwrap_err();
+        assert!(err.to_string().contains("//button"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_navigation_detects_url_change() {
+        let mut driver = MockDriver::new(vec!["complete"]).with_urls(vec![
+            "https://example.com/form",
+            "https://example.com/form",
+            "https://example.com/done",
+        ]);
+        let (url, _) = wait_for_navigation(
+            &mut driver,
+            "https://example.com/form",
+            None,
+            Duration::from_secs(5),
+            Duration::from_millis(5),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(url, "https://example.com/done");
+        assert_eq!(driver.url_calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_navigation_expected_url_and_load() {
+        // Redirects until the dashboard, which is still loading at first
+        let mut driver = MockDriver::new(vec!["loading", "complete"]).with_urls(vec![
+            "https://example.com/login",
+            "https://example.com/redirect",
+            "https://example.com/dashboard",
+        ]);
+        let (url, _) = wait_for_navigation(
+            &mut driver,
+            "https://example.com/login",
+            Some("/dashboard"),
+            Duration::from_secs(5),
+            Duration::from_millis(5),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(url, "https://example.com/dashboard");
+        assert_eq!(driver.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_navigation_already_navigated() {
+        let mut driver =
+            MockDriver::new(vec!["complete"]).with_urls(vec!["https://example.com/next"]);
+        let result = wait_for_navigation(
+            &mut driver,
+            "https://example.com/",
+            None,
+            Duration::from_secs(5),
+            Duration::from_millis(5),
+        )
+        .await
+        .unwrap();
+
+        assert!(result.is_some());
+        assert_eq!(driver.url_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_navigation_times_out() {
+        let mut driver = MockDriver::new(vec!["complete"]).with_urls(vec!["https://example.com/"]);
+        let result = wait_for_navigation(
+            &mut driver,
+            "https://example.com/",
+            None,
+            Duration::from_millis(50),
+            Duration::from_millis(5),
+        )
+        .await
+        .unwrap();
+
+        assert!(result.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_navigation_polls_through_url_errors() {
+        let mut driver = MockDriver::new(vec!["complete"]).with_urls(vec![
+            "https://example.com/form",
+            "error",
+            "https://example.com/done",
+        ]);
+        let (url, _) = wait_for_navigation(
+            &mut driver,
+            "https://example.com/form",
+            None,
+            Duration::from_secs(5),
+            Duration::from_millis(5),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(url, "https://example.com/done");
+        assert_eq!(driver.url_calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_element_inspection_json() {
+        // Shape returned by INSPECT_ELEMENT_SCRIPT
+        let value = serde_json::json!({
+            "tag": "button",
+            "id": "save",
+            "class": "btn primary",
+            "text": "Save",
+            "bounding_rect": {
+                "x": 10.0, "y": 20.5, "width": 80.0, "height": 30.0,
+                "top": 20.5, "right": 90.0, "bottom": 50.5, "left": 10.0
+            },
+            "attributes": {"id": "save", "class": "btn primary", "type": "submit"},
+            "visible": true,
+            "children": [{
+                "tag": "span",
+                "id": null,
+                "class": null,
+                "text": "Save",
+                "bounding_rect": {
+                    "x": 12.0, "y": 22.0, "width": 30.0, "height": 16.0,
+                    "top": 22.0, "right": 42.0, "bottom": 38.0, "left": 12.0
+                },
+                "attributes": {},
+                "visible": true,
+                "children": []
+            }]
+        });
+        let inspection: ElementInspection = serde_json::from_value(value).unwrap();
+        assert_eq!(inspection.attributes["type"], "submit");
+        assert_eq!(inspection.bounding_rect.bottom, 50.5);
+        assert_eq!(inspection.children[0].tag, "span");
+
+        // Styles and children are left out of the output when absent
+        let json = serde_json::to_value(&inspection.children[0]).unwrap();
+        assert!(json.get("computed_styles").is_none());
+        assert!(json.get("children").is_none());
+    }
+
+    #[test]
+    fn test_network_request_json() {
+        // Entries as recorded by NETWORK_LOG_SCRIPT, the second still in flight
+        let value = serde_json::json!([
+            {
+                "method": "POST",
+                "url": "http://localhost:3000/api/login",
+                "request_body": "{\"user\":\"bob\"}",
+                "status": 200,
+                "response_preview": "{\"ok\":true}"
+            },
+            {
+                "method": "GET",
+                "url": "http://localhost:3000/api/items",
+                "request_body": null,
+                "status": null,
+                "response_preview": null
+            }
+        ]);
+        let requests: Vec<NetworkRequest> = serde_json::from_value(value).unwrap();
+        assert_eq!(requests[0].status, Some(200));
+        assert_eq!(requests[0].request_body.as_deref(), Some("{\"user\":\"bob\"}"));
+        assert_eq!(requests[1].status, None);
+
+        // Capture not installed on the page
+        let none: Option<Vec<NetworkRequest>> =
+            serde_json::from_value(serde_json::Value::Null).unwrap();
+        assert!(none.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_switch_frame_by_index() {
+        let mut driver = MockDriver::new(vec![]);
+        switch_frame(&mut driver, &FrameTarget::Index(2)).await.unwrap();
+        assert_eq!(driver.frames, vec![Some(2)]);
+
+        let err = switch_frame(&mut driver, &FrameTarget::Index(70_000))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("out of range"));
+        assert_eq!(driver.frames.len(), 1);
+    }
+
+    #[test]
+    fn test_frame_label() {
+        let mut state = WebDriverSessionState::default();
+        assert_eq!(state.frame_label(), None);
+
+        state.frame_path.push(FrameTarget::Selector("#outer".to_string()).label());
+        state.frame_path.push(FrameTarget::Index(0).label());
+        assert_eq!(state.frame_label().as_deref(), Some("[Frame: #outer > index 0]"));
+    }
+
+    #[test]
+    fn test_stored_values() {
+        assert_eq!(stored_value_string(&serde_json::json!("tok_123")), "tok_123");
+        assert_eq!(
+            stored_value_string(&serde_json::json!({"theme": "dark", "count": 2})),
+            r#"{"count":2,"theme":"dark"}"#
+        );
+
+        assert_eq!(
+            parse_stored_value(r#"{"theme":"dark"}"#),
+            serde_json::json!({"theme": "dark"})
+        );
+        assert_eq!(parse_stored_value("42"), serde_json::json!(42));
+        assert_eq!(parse_stored_value("tok_123"), serde_json::json!("tok_123"));
+        assert_eq!(StorageType::parse("session"), Some(StorageType::Session));
+        assert_eq!(StorageType::parse("cookie"), None);
     }
 }
@@ -132,8 +132,55 @@ pub fn resolve_paths_in_shell_command(command: &str) -> String {
     result
 }
 
+/// Reasons a unified diff could not be applied, identifying the failing hunk.
+///
+/// `hunk_index` is 0-based; messages display it 1-based.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DiffApplyError {
+    #[error(
+        "Pattern not found in file\nHunk {} failed. Searched for:\n{expected_context}\nClosest match in file:\n{file_excerpt}",
+        hunk_index + 1
+    )]
+    HunkNotFound {
+        hunk_index: usize,
+        expected_context: String,
+        file_excerpt: String,
+    },
+    #[error("Malformed patch at hunk {}: {reason}", hunk_index + 1)]
+    MalformedPatch { hunk_index: usize, reason: String },
+}
+
+/// Upper bound for the `fuzz_lines` argument of the diff appliers.
+pub const MAX_FUZZ_LINES: usize = 3;
+
+/// How [`apply_unified_diff_detailed`] matches hunks against the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffApplyOptions {
+    /// How many leading/trailing context lines of a hunk may be ignored when it
+    /// does not match exactly (0 disables, capped at [`MAX_FUZZ_LINES`])
+    pub fuzz_lines: usize,
+    /// Match with CRLF and CR line endings of the file and diff treated as LF.
+    /// Every line of the file keeps its own line ending; lines the diff adds
+    /// take the ending of the context line before them. Leave on unless the
+    /// file content must be matched byte for byte.
+    pub normalize_line_endings: bool,
+}
+
+impl Default for DiffApplyOptions {
+    fn default() -> Self {
+        Self {
+            fuzz_lines: 0,
+            normalize_line_endings: true,
+        }
+    }
+}
+
 /// Apply unified diff to an input string with optional [start, end) bounds.
 ///
+/// Hunks must match exactly, apart from line endings (see
+/// [`DiffApplyOptions::default`]); [`apply_unified_diff_detailed`] can match
+/// them fuzzily.
+///
 /// # Arguments
 /// * `file_content` - The original file content
 /// * `diff` - The unified diff to apply
@@ -148,44 +195,217 @@ pub fn apply_unified_diff_to_string(
     start_char: Option<usize>,
     end_char: Option<usize>,
 ) -> Result<String> {
+    let DiffApplyOptions {
+        fuzz_lines,
+        normalize_line_endings,
+    } = DiffApplyOptions::default();
+
     // Parse full unified diff into hunks and apply sequentially.
-    let hunks = parse_unified_diff_hunks(diff);
+    let hunks = parse_diff_for(diff, normalize_line_endings);
     if hunks.is_empty() {
         anyhow::bail!(
             "Invalid diff format. Expected unified diff with @@ hunks or +/- with context lines"
         );
     }
 
-    // Normalize line endings to avoid CRLF/CR mismatches
-    let content_norm = file_content.replace("\r\n", "\n").replace('\r', "\n");
+    let mut endings = normalize_line_endings.then(|| LineEndings::of(file_content));
+    let content_norm = normalize_content_for(file_content, normalize_line_endings);
+    let (start_boundary, end_boundary) =
+        diff_region_bounds(&content_norm, start_char, end_char).map_err(anyhow::Error::msg)?;
+    let mut region_content = content_norm[start_boundary..end_boundary].to_string();
+    let region_line = content_norm[..start_boundary].matches('\n').count();
 
-    // Determine and validate the search range
+    // Apply hunks in order
+    for (idx, hunk) in hunks.iter().enumerate() {
+        let old_block = &hunk.old_block;
+        if !apply_hunk(
+            &mut region_content,
+            region_line,
+            idx,
+            hunk,
+            fuzz_lines,
+            endings.as_mut(),
+        ) {
+            // Not found; provide helpful diagnostics with a short preview
+            let preview_len = old_block.floor_char_boundary(200);
+            let mut old_preview = old_block[..preview_len].to_string();
+            if old_block.len() > preview_len {
+                old_preview.push_str("...");
+            }
+
+            let range_note = if start_char.is_some() || end_char.is_some() {
+                format!(
+                    " (within character range {}:{})",
+                    start_boundary, end_boundary
+                )
+            } else {
+                String::new()
+            };
+
+            anyhow::bail!(
+                "Pattern not found in file{}\nHunk {} failed. Searched for:\n{}",
+                range_note,
+                idx + 1,
+                old_preview
+            );
+        }
+    }
+
+    Ok(splice_region(
+        &content_norm,
+        start_boundary,
+        end_boundary,
+        &region_content,
+        endings.as_ref(),
+    ))
+}
+
+/// How much of a file is checked for `\r\n` to decide its line ending style.
+pub const LINE_ENDING_SNIFF_BYTES: usize = 1000;
+
+/// Whether `content` uses Windows (CRLF) line endings, judged by its start.
+pub fn uses_crlf(content: &str) -> bool {
+    let head = &content.as_bytes()[..content.len().min(LINE_ENDING_SNIFF_BYTES)];
+    head.windows(2).any(|pair| pair == b"\r\n")
+}
+
+/// The original line endings of a text normalized by [`normalize_to_lf`]:
+/// what each of its `\n` was, in order. Kept in step with the normalized text
+/// as hunks are applied, so unchanged lines of a file with mixed line endings
+/// are written back exactly as they were.
+#[derive(Debug, Clone)]
+struct LineEndings {
+    endings: Vec<&'static str>,
+    /// Ending of added lines when the hunk has no line to take it from
+    default: &'static str,
+}
+
+impl LineEndings {
+    fn of(content: &str) -> Self {
+        let bytes = content.as_bytes();
+        let mut endings = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                    endings.push("\r\n");
+                    i += 1;
+                }
+                b'\r' => endings.push("\r"),
+                b'\n' => endings.push("\n"),
+                _ => {}
+            }
+            i += 1;
+        }
+        let default = if uses_crlf(content) { "\r\n" } else { "\n" };
+        Self { endings, default }
+    }
+
+    /// Record that `old`, whose first line ending is the `first`-th of the
+    /// text, was replaced by `new`. Lines both share at the start and end (the
+    /// hunk's context) keep their endings; the lines in between take the
+    /// ending of the last leading context line, or of the first replaced line.
+    fn replace(&mut self, first: usize, old: &str, new: &str) {
+        let complete_lines = |text: &str| -> Vec<String> {
+            text.split_inclusive('\n')
+                .filter(|line| line.ends_with('\n'))
+                .map(str::to_string)
+                .collect()
+        };
+        let (old_lines, new_lines) = (complete_lines(old), complete_lines(new));
+        let old_endings = &self.endings[first..first + old_lines.len()];
+
+        let leading = old_lines
+            .iter()
+            .zip(&new_lines)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let trailing = old_lines[leading..]
+            .iter()
+            .rev()
+            .zip(new_lines[leading..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let added = match leading {
+            0 => old_endings.first().copied().unwrap_or(self.default),
+            n => old_endings[n - 1],
+        };
+
+        let mut new_endings = old_endings[..leading].to_vec();
+        new_endings.resize(new_lines.len() - trailing, added);
+        new_endings.extend_from_slice(&old_endings[old_lines.len() - trailing..]);
+        self.endings.splice(first..first + old_lines.len(), new_endings);
+    }
+
+    /// `text` with each `\n` turned back into its recorded ending.
+    fn restore(&self, text: &str) -> String {
+        let mut endings = self.endings.iter();
+        let mut restored = String::with_capacity(text.len() + self.endings.len());
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                restored.push_str(endings.next().copied().unwrap_or(self.default));
+            }
+            restored.push_str(line);
+        }
+        restored
+    }
+}
+
+/// Turn CRLF and lone CR line endings into LF.
+fn normalize_to_lf(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Parse the hunks of `diff`, with CRLF and CR line endings turned into LF
+/// when `normalize_line_endings` is set.
+fn parse_diff_for(diff: &str, normalize_line_endings: bool) -> Vec<DiffHunk> {
+    if normalize_line_endings {
+        parse_diff_hunks(&normalize_to_lf(diff))
+    } else {
+        parse_diff_hunks(diff)
+    }
+}
+
+/// Normalize line endings to avoid CRLF/CR mismatches; the file's own endings
+/// are restored by [`splice_region`].
+fn normalize_content_for(file_content: &str, normalize_line_endings: bool) -> String {
+    if normalize_line_endings {
+        normalize_to_lf(file_content)
+    } else {
+        file_content.to_string()
+    }
+}
+
+/// Validate the optional [start, end) range and move it to char boundaries.
+fn diff_region_bounds(
+    content_norm: &str,
+    start_char: Option<usize>,
+    end_char: Option<usize>,
+) -> std::result::Result<(usize, usize), String> {
     let search_start = start_char.unwrap_or(0);
     let search_end = end_char.unwrap_or(content_norm.len());
 
     if search_start > content_norm.len() {
-        anyhow::bail!(
+        return Err(format!(
             "start position {} exceeds file length {}",
             search_start,
             content_norm.len()
-        );
+        ));
     }
     if search_end > content_norm.len() {
-        anyhow::bail!(
+        return Err(format!(
             "end position {} exceeds file length {}",
             search_end,
             content_norm.len()
-        );
+        ));
     }
     if search_start > search_end {
-        anyhow::bail!(
+        return Err(format!(
             "start position {} is greater than end position {}",
-            search_start,
-            search_end
-        );
+            search_start, search_end
+        ));
     }
 
-    // Extract the region we're going to modify, ensuring we're at char boundaries
     // Find the nearest valid char boundaries
     let start_boundary = if search_start == 0 {
         0
@@ -201,59 +421,310 @@ pub fn apply_unified_diff_to_string(
         .find(|(i, _)| *i >= search_end)
         .map(|(i, _)| i)
         .unwrap_or(content_norm.len());
+    Ok((start_boundary, end_boundary))
+}
 
-    let mut region_content = content_norm[start_boundary..end_boundary].to_string();
+/// Replace one hunk in `region_content`, which starts at line `region_line`
+/// of the file, exactly or with fuzz, keeping `endings` in step. Returns
+/// whether the hunk was found.
+fn apply_hunk(
+    region_content: &mut String,
+    region_line: usize,
+    idx: usize,
+    hunk: &DiffHunk,
+    fuzz_lines: usize,
+    mut endings: Option<&mut LineEndings>,
+) -> bool {
+    let (old_block, new_block) = (hunk.old_block.as_str(), hunk.new_block.as_str());
+    debug!(
+        "Applying hunk {}: old_len={}, new_len={}",
+        idx + 1,
+        old_block.len(),
+        new_block.len()
+    );
 
-    // Apply hunks in order
-    for (idx, (old_block, new_block)) in hunks.iter().enumerate() {
+    let mut replace = |region_content: &mut String, pos: usize, old: &str, new: &str| {
+        if let Some(endings) = endings.as_deref_mut() {
+            let first = region_line + region_content[..pos].matches('\n').count();
+            endings.replace(first, old, new);
+        }
+        region_content.replace_range(pos..pos + old.len(), new);
+    };
+
+    if let Some(pos) = region_content.find(old_block) {
+        replace(region_content, pos, old_block, new_block);
+        true
+    } else if let Some((pos, old_fuzzed, new_fuzzed, lead, trail)) = find_hunk_with_fuzz(
+        region_content,
+        old_block,
+        new_block,
+        hunk.new_start.map(|line| line.saturating_sub(region_line)),
+        fuzz_lines,
+    ) {
         debug!(
-            "Applying hunk {}: old_len={}, new_len={}",
+            "Hunk {} required fuzzy matching: skipped {} leading and {} trailing context lines",
             idx + 1,
-            old_block.len(),
-            new_block.len()
+            lead,
+            trail
         );
+        replace(region_content, pos, &old_fuzzed, &new_fuzzed);
+        true
+    } else {
+        false
+    }
+}
 
-        if let Some(pos) = region_content.find(old_block) {
-            let endpos = pos + old_block.len();
-            region_content.replace_range(pos..endpos, new_block);
-        } else {
-            // Not found; provide helpful diagnostics with a short preview
-            let preview_len = old_block.len().min(200);
-            let mut old_preview = old_block[..preview_len].to_string();
-            if old_block.len() > preview_len {
-                old_preview.push_str("...");
+/// Reconstruct the full content with the modified region, with the file's
+/// line endings restored when they were normalized.
+fn splice_region(
+    content_norm: &str,
+    start_boundary: usize,
+    end_boundary: usize,
+    region_content: &str,
+    endings: Option<&LineEndings>,
+) -> String {
+    let mut result = String::with_capacity(content_norm.len() + region_content.len());
+    result.push_str(&content_norm[..start_boundary]);
+    result.push_str(region_content);
+    result.push_str(&content_norm[end_boundary..]);
+    match endings {
+        Some(endings) => endings.restore(&result),
+        None => result,
+    }
+}
+
+/// Like [`apply_unified_diff_to_string`], but reports which hunk failed and
+/// why, and matches hunks as `options` say.
+///
+/// Stricter than it as well: in a patch with several hunks, a hunk without
+/// context or removed lines is rejected instead of being inserted at the start.
+pub fn apply_unified_diff_detailed(
+    file_content: &str,
+    diff: &str,
+    start_char: Option<usize>,
+    end_char: Option<usize>,
+    options: &DiffApplyOptions,
+) -> std::result::Result<String, DiffApplyError> {
+    let DiffApplyOptions {
+        fuzz_lines,
+        normalize_line_endings,
+    } = *options;
+    let malformed = |reason: String| DiffApplyError::MalformedPatch {
+        hunk_index: 0,
+        reason,
+    };
+
+    let hunks = parse_diff_for(diff, normalize_line_endings);
+    if hunks.is_empty() {
+        return Err(malformed(
+            "Invalid diff format. Expected unified diff with @@ hunks or +/- with context lines"
+                .to_string(),
+        ));
+    }
+
+    let mut endings = normalize_line_endings.then(|| LineEndings::of(file_content));
+    let content_norm = normalize_content_for(file_content, normalize_line_endings);
+    let (start_boundary, end_boundary) =
+        diff_region_bounds(&content_norm, start_char, end_char).map_err(malformed)?;
+    let mut region_content = content_norm[start_boundary..end_boundary].to_string();
+    let region_line = content_norm[..start_boundary].matches('\n').count();
+
+    for (idx, hunk) in hunks.iter().enumerate() {
+        let old_block = &hunk.old_block;
+        if old_block.is_empty() && hunks.len() > 1 {
+            return Err(DiffApplyError::MalformedPatch {
+                hunk_index: idx,
+                reason: "hunk has no context or removed lines to locate it".to_string(),
+            });
+        }
+        if !apply_hunk(
+            &mut region_content,
+            region_line,
+            idx,
+            hunk,
+            fuzz_lines,
+            endings.as_mut(),
+        ) {
+            return Err(DiffApplyError::HunkNotFound {
+                hunk_index: idx,
+                expected_context: old_block.clone(),
+                file_excerpt: closest_file_excerpt(&region_content, old_block),
+            });
+        }
+    }
+
+    Ok(splice_region(
+        &content_norm,
+        start_boundary,
+        end_boundary,
+        &region_content,
+        endings.as_ref(),
+    ))
+}
+
+/// Locate a hunk that did not match exactly by ignoring some of its context.
+///
+/// Context lines are the lines shared at the start and end of the old and new
+/// blocks. Each fuzz level drops one more line from both ends, so the strictest
+/// match is always tried first. The trimmed block must match whole lines; the
+/// match nearest `expected_line` (the hunk's line in `content`, from its `@@`
+/// header) wins, and a match that is not unique by that measure is rejected.
+/// Returns the match position, the trimmed old and new blocks, and how many
+/// leading and trailing lines were skipped.
+fn find_hunk_with_fuzz(
+    content: &str,
+    old_block: &str,
+    new_block: &str,
+    expected_line: Option<usize>,
+    fuzz_lines: usize,
+) -> Option<(usize, String, String, usize, usize)> {
+    let old_lines: Vec<&str> = old_block.split('\n').collect();
+    let new_lines: Vec<&str> = new_block.split('\n').collect();
+
+    let max_context = old_lines.len().min(new_lines.len());
+    let leading = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let trailing = old_lines
+        .iter()
+        .rev()
+        .zip(new_lines.iter().rev())
+        .take(max_context - leading)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let content_lines: Vec<&str> = content.split('\n').collect();
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+
+    for fuzz in 1..=fuzz_lines.min(MAX_FUZZ_LINES) {
+        let lead = fuzz.min(leading);
+        let trail = fuzz.min(trailing);
+        if lead + trail == 0 {
+            break;
+        }
+
+        let old_fuzzed = &old_lines[lead..old_lines.len() - trail];
+        if old_fuzzed.join("").trim().is_empty() {
+            continue;
+        }
+        let expected = expected_line.map(|line| line + lead);
+        match find_nearest_lines(&content_lines, old_fuzzed, expected) {
+            NearestMatch::Found(line) => {
+                let start = line_starts[line];
+                let mut old_fuzzed = old_fuzzed.join("\n");
+                let new_fuzzed = &new_lines[lead..new_lines.len() - trail];
+                // Removing every matched line takes the newline after them too,
+                // which otherwise belongs to the skipped context
+                if new_fuzzed.is_empty() && content[start + old_fuzzed.len()..].starts_with('\n') {
+                    old_fuzzed.push('\n');
+                }
+                return Some((start, old_fuzzed, new_fuzzed.join("\n"), lead, trail));
             }
+            NearestMatch::Ambiguous => {
+                debug!("Fuzzy match at fuzz {} is ambiguous; not applying the hunk", fuzz);
+                return None;
+            }
+            NearestMatch::NotFound => {}
+        }
+    }
+    None
+}
 
-            let range_note = if start_char.is_some() || end_char.is_some() {
-                format!(
-                    " (within character range {}:{})",
-                    start_boundary, end_boundary
-                )
-            } else {
-                String::new()
-            };
+enum NearestMatch {
+    Found(usize),
+    Ambiguous,
+    NotFound,
+}
 
-            anyhow::bail!(
-                "Pattern not found in file{}\nHunk {} failed. Searched for:\n{}",
-                range_note,
-                idx + 1,
-                old_preview
-            );
+/// Find the line where `block` matches whole lines of `lines`, searching
+/// outward from `expected` (or over the whole file when it is unknown). Two
+/// matches equally near `expected`, or any two without it, are ambiguous.
+fn find_nearest_lines(lines: &[&str], block: &[&str], expected: Option<usize>) -> NearestMatch {
+    if block.len() > lines.len() {
+        return NearestMatch::NotFound;
+    }
+    let last_start = lines.len() - block.len();
+    let matches_at = |start: usize| lines[start..start + block.len()] == *block;
+
+    let Some(expected) = expected else {
+        let mut found = (0..=last_start).filter(|&start| matches_at(start));
+        return match (found.next(), found.next()) {
+            (Some(line), None) => NearestMatch::Found(line),
+            (Some(_), Some(_)) => NearestMatch::Ambiguous,
+            _ => NearestMatch::NotFound,
+        };
+    };
+
+    let expected = expected.min(last_start);
+    for distance in 0..=last_start {
+        let before = expected.checked_sub(distance).filter(|&start| matches_at(start));
+        let after = Some(expected + distance)
+            .filter(|&start| distance > 0 && start <= last_start && matches_at(start));
+        match (before, after) {
+            (Some(_), Some(_)) => return NearestMatch::Ambiguous,
+            (Some(line), None) | (None, Some(line)) => return NearestMatch::Found(line),
+            (None, None) => {}
         }
     }
+    NearestMatch::NotFound
+}
 
-    // Reconstruct the full content with the modified region
-    let mut result = String::with_capacity(content_norm.len() + region_content.len());
-    result.push_str(&content_norm[..start_boundary]);
-    result.push_str(&region_content);
-    result.push_str(&content_norm[end_boundary..]);
-    Ok(result)
+/// Find the lines of `content` that best line up with a hunk that failed to match.
+///
+/// Anchors on the first hunk line that appears verbatim (ignoring surrounding
+/// whitespace) in the file and returns the same number of lines from there.
+fn closest_file_excerpt(content: &str, old_block: &str) -> String {
+    let file_lines: Vec<&str> = content.lines().collect();
+    let hunk_lines: Vec<&str> = old_block.lines().collect();
+
+    for (offset, hunk_line) in hunk_lines.iter().enumerate() {
+        let needle = hunk_line.trim();
+        if needle.is_empty() {
+            continue;
+        }
+        if let Some(found) = file_lines.iter().position(|line| line.trim() == needle) {
+            let start = found.saturating_sub(offset);
+            let end = (start + hunk_lines.len()).min(file_lines.len());
+            return file_lines[start..end].join("\n");
+        }
+    }
+
+    "(no lines from this hunk were found in the file)".to_string()
 }
 
 /// Parse a unified diff into a list of hunks as (old_block, new_block).
 /// Each hunk contains the exact text to search for and the replacement text including context lines.
 pub fn parse_unified_diff_hunks(diff: &str) -> Vec<(String, String)> {
-    let mut hunks: Vec<(String, String)> = Vec::new();
+    parse_diff_hunks(diff)
+        .into_iter()
+        .map(|hunk| (hunk.old_block, hunk.new_block))
+        .collect()
+}
+
+/// One hunk of a unified diff.
+struct DiffHunk {
+    old_block: String,
+    new_block: String,
+    /// 0-based line of the hunk in the patched file, from its `@@ -a,b +c,d @@`
+    /// header. Earlier hunks have been applied by the time it is located, so
+    /// this is where it is expected.
+    new_start: Option<usize>,
+}
+
+/// Read the new-file start line from a `@@ -a,b +c,d @@` header.
+fn parse_hunk_new_start(header: &str) -> Option<usize> {
+    let new_range = header.split_whitespace().find_map(|part| part.strip_prefix('+'))?;
+    let start: usize = new_range.split(',').next()?.parse().ok()?;
+    Some(start.saturating_sub(1))
+}
+
+fn parse_diff_hunks(diff: &str) -> Vec<DiffHunk> {
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    let mut new_start = None;
 
     let mut old_lines: Vec<String> = Vec::new();
     let mut new_lines: Vec<String> = Vec::new();
@@ -279,10 +750,15 @@ pub fn parse_unified_diff_hunks(diff: &str) -> Vec<(String, String)> {
         if line.starts_with("@@") {
             // Starting a new hunk — flush previous if present
             if in_hunk && (!old_lines.is_empty() || !new_lines.is_empty()) {
-                hunks.push((old_lines.join("\n"), new_lines.join("\n")));
+                hunks.push(DiffHunk {
+                    old_block: old_lines.join("\n"),
+                    new_block: new_lines.join("\n"),
+                    new_start,
+                });
                 old_lines.clear();
                 new_lines.clear();
             }
+            new_start = parse_hunk_new_start(line);
             in_hunk = true;
             continue;
         }
@@ -315,7 +791,11 @@ pub fn parse_unified_diff_hunks(diff: &str) -> Vec<(String, String)> {
     }
 
     if in_hunk && (!old_lines.is_empty() || !new_lines.is_empty()) {
-        hunks.push((old_lines.join("\n"), new_lines.join("\n")));
+        hunks.push(DiffHunk {
+            old_block: old_lines.join("\n"),
+            new_block: new_lines.join("\n"),
+            new_start,
+        });
     }
 
     hunks
@@ -509,6 +989,20 @@ pub fn fix_mixed_quotes_in_json(json_str: &str) -> String {
 mod tests {
     use super::*;
 
+    fn fuzz(fuzz_lines: usize) -> DiffApplyOptions {
+        DiffApplyOptions {
+            fuzz_lines,
+            ..Default::default()
+        }
+    }
+
+    fn exact_line_endings() -> DiffApplyOptions {
+        DiffApplyOptions {
+            normalize_line_endings: false,
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn parses_minimal_unified_diff_without_hunk_header() {
         let diff = "--- old\n-old text\n+++ new\n+new text\n";
@@ -549,6 +1043,211 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn detailed_diff_reports_failing_hunk_index() {
+        let original = "line 1\nkeep\nold A\nkeep 2\nold B\nkeep 3\n";
+        // First hunk matches, second hunk's context does not
+        let diff = "@@ -1,3 +1,3 @@\n keep\n-old A\n+new A\n@@ -4,3 +4,3 @@\n keep two\n-old B\n+new B\n";
+        let err = apply_unified_diff_detailed(original, diff, None, None, &fuzz(0)).unwrap_err();
+        match err {
+            DiffApplyError::HunkNotFound {
+                hunk_index,
+                expected_context,
+                file_excerpt,
+            } => {
+                assert_eq!(hunk_index, 1);
+                assert_eq!(expected_context, "keep two\nold B");
+                assert_eq!(file_excerpt, "keep 2\nold B");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn detailed_diff_reports_first_hunk_mismatch() {
+        let original = "alpha\nbeta\ngamma\n";
+        let diff = "@@ -1,2 +1,2 @@\n ALPHA\n-beta\n+BETA\n";
+        let err = apply_unified_diff_detailed(original, diff, None, None, &fuzz(0)).unwrap_err();
+        assert!(matches!(err, DiffApplyError::HunkNotFound { hunk_index: 0, .. }));
+        assert!(err.to_string().contains("Hunk 1 failed"));
+    }
+
+    #[test]
+    fn detailed_diff_reports_malformed_patch() {
+        let err =
+            apply_unified_diff_detailed("content", "not a diff", None, None, &fuzz(0)).unwrap_err();
+        assert!(matches!(err, DiffApplyError::MalformedPatch { hunk_index: 0, .. }));
+
+        let err =
+            apply_unified_diff_detailed("abc", "-a\n+b\n", Some(10), None, &fuzz(0)).unwrap_err();
+        assert!(matches!(err, DiffApplyError::MalformedPatch { .. }));
+    }
+
+    #[test]
+    fn legacy_diff_keeps_its_acceptance_and_messages() {
+        // A hunk with only added lines is inserted at the start, as it always was
+        let diff = "@@ -1,1 +1,2 @@\n+header\n@@ -2,1 +3,1 @@\n-b\n+B\n";
+        let result = apply_unified_diff_to_string("a\nb\n", diff, None, None).unwrap();
+        assert_eq!(result, "headera\nB\n");
+        assert!(apply_unified_diff_detailed("a\nb\n", diff, None, None, &fuzz(0)).is_err());
+
+        let err = apply_unified_diff_to_string("a\nb\n", "-c\n+C\n", Some(0), Some(2))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "Pattern not found in file (within character range 0:2)\nHunk 1 failed. Searched for:\nc"
+        );
+    }
+
+    #[test]
+    fn apply_diff_with_fuzz_skips_stale_context_line() {
+        // The file's first context line has trailing whitespace the diff lacks
+        let original = "fn main() {   \n    let x = 1;\n    println!(\"{}\", x);\n}\n";
+        let diff = "@@ -1,4 +1,4 @@\n fn main() {\n-    let x = 1;\n+    let x = 2;\n     println!(\"{}\", x);\n }\n";
+
+        assert!(matches!(
+            apply_unified_diff_detailed(original, diff, None, None, &fuzz(0)),
+            Err(DiffApplyError::HunkNotFound { hunk_index: 0, .. })
+        ));
+
+        let result = apply_unified_diff_detailed(original, diff, None, None, &fuzz(1)).unwrap();
+        assert_eq!(
+            result,
+            "fn main() {   \n    let x = 2;\n    println!(\"{}\", x);\n}\n"
+        );
+    }
+
+    #[test]
+    fn apply_diff_fuzz_is_limited_to_requested_lines() {
+        // Two stale leading context lines need fuzz 2
+        let original = "a \nb \nold\nc\n";
+        let diff = "@@ -1,4 +1,4 @@\n a\n b\n-old\n+new\n c\n";
+
+        assert!(apply_unified_diff_detailed(original, diff, None, None, &fuzz(1)).is_err());
+        let result = apply_unified_diff_detailed(original, diff, None, None, &fuzz(2)).unwrap();
+        assert_eq!(result, "a \nb \nnew\nc\n");
+
+        // Changed lines are never skipped, however much fuzz is allowed
+        let diff = "@@ -1,2 +1,2 @@\n a \n-missing\n+new\n";
+        assert!(apply_unified_diff_detailed(original, diff, None, None, &fuzz(10)).is_err());
+    }
+
+    #[test]
+    fn apply_diff_fuzz_removes_whole_lines() {
+        let original = "a\nb \nc\nd\ne\n";
+        let diff = "@@ -2,3 +2,1 @@\n b\n-c\n-d\n";
+        assert_eq!(
+            apply_unified_diff_detailed(original, diff, None, None, &fuzz(1)).unwrap(),
+            "a\nb \ne\n"
+        );
+
+        // Replacing them with a blank line keeps one line
+        let diff = "@@ -2,3 +2,2 @@\n b\n-c\n-d\n+\n";
+        assert_eq!(
+            apply_unified_diff_detailed(original, diff, None, None, &fuzz(1)).unwrap(),
+            "a\nb \n\ne\n"
+        );
+    }
+
+    #[test]
+    fn apply_diff_fuzz_picks_the_match_nearest_the_hunk_header() {
+        // The trimmed block `x = 1;` appears twice; the header points at the second
+        let original = "fn a() {\n    x = 1;\n}\nfn b() {\n    x = 1;\n}\n";
+        let diff = "@@ -4,3 +4,3 @@\n fn b() { \n-    x = 1;\n+    x = 2;\n }\n";
+        let result = apply_unified_diff_detailed(original, diff, None, None, &fuzz(1)).unwrap();
+        assert_eq!(result, "fn a() {\n    x = 1;\n}\nfn b() {\n    x = 2;\n}\n");
+
+        let diff = "@@ -1,3 +1,3 @@\n fn a() { \n-    x = 1;\n+    x = 2;\n }\n";
+        let result = apply_unified_diff_detailed(original, diff, None, None, &fuzz(1)).unwrap();
+        assert_eq!(result, "fn a() {\n    x = 2;\n}\nfn b() {\n    x = 1;\n}\n");
+    }
+
+    #[test]
+    fn apply_diff_fuzz_rejects_ambiguous_matches() {
+        let original = "fn a() {\n    x = 1;\n}\nfn b() {\n    x = 1;\n}\n";
+        // Without a line number either copy could be meant
+        let diff = " fn c() {\n-    x = 1;\n+    x = 2;\n }\n";
+        assert!(apply_unified_diff_detailed(original, diff, None, None, &fuzz(1)).is_err());
+
+        // Equally far from both copies
+        let original = "}\n    x = 1;\n\n    x = 1;\n";
+        let diff = "@@ -2,3 +2,3 @@\n fn c() {\n-    x = 1;\n+    x = 2;\n }\n";
+        assert!(apply_unified_diff_detailed(original, diff, None, None, &fuzz(1)).is_err());
+    }
+
+    #[test]
+    fn apply_diff_with_each_line_ending_combination() {
+        let lf_diff = "@@ -1,3 +1,3 @@\n one\n-two\n+TWO\n three\n";
+        let crlf_diff = lf_diff.replace('\n', "\r\n");
+        let lf_file = "one\ntwo\nthree\n";
+        let crlf_file = lf_file.replace('\n', "\r\n");
+
+        for diff in [lf_diff, crlf_diff.as_str()] {
+            assert_eq!(
+                apply_unified_diff_to_string(lf_file, diff, None, None).unwrap(),
+                "one\nTWO\nthree\n"
+            );
+            // The file keeps its CRLF line endings, including on the new line
+            assert_eq!(
+                apply_unified_diff_to_string(&crlf_file, diff, None, None).unwrap(),
+                "one\r\nTWO\r\nthree\r\n"
+            );
+        }
+    }
+
+    #[test]
+    fn apply_diff_without_normalizing_line_endings() {
+        let crlf_file = "one\r\ntwo\r\nthree\r\n";
+        let diff = "@@ -1,3 +1,3 @@\n one\n-two\n+TWO\n three\n";
+        assert!(matches!(
+            apply_unified_diff_detailed(crlf_file, diff, None, None, &exact_line_endings()),
+            Err(DiffApplyError::HunkNotFound { hunk_index: 0, .. })
+        ));
+
+        // Content outside the hunk is left exactly as it was
+        let mixed = "keep\r\nold\nend\n";
+        let diff = "@@ -2,1 +2,1 @@\n-old\n+new\n";
+        assert_eq!(
+            apply_unified_diff_detailed(mixed, diff, None, None, &exact_line_endings()).unwrap(),
+            "keep\r\nnew\nend\n"
+        );
+        assert!(uses_crlf(mixed));
+        assert!(!uses_crlf(&format!("{}\r\n", "x".repeat(LINE_ENDING_SNIFF_BYTES))));
+    }
+
+    #[test]
+    fn apply_diff_keeps_each_lines_ending() {
+        // CRLF at the start, where line endings are sniffed, then LF and CR
+        let mixed = "a\r\nb\r\nc\nd\ne\rf\n";
+        let diff = "@@ -3,2 +3,3 @@\n c\n-d\n+D\n+D2\n e\n";
+        assert_eq!(
+            apply_unified_diff_to_string(mixed, diff, None, None).unwrap(),
+            "a\r\nb\r\nc\nD\nD2\ne\rf\n"
+        );
+
+        // Added lines take the ending of the context line before them
+        let diff = "@@ -1,2 +1,3 @@\n a\n+new\n b\n";
+        assert_eq!(
+            apply_unified_diff_to_string(mixed, diff, None, None).unwrap(),
+            "a\r\nnew\r\nb\r\nc\nd\ne\rf\n"
+        );
+
+        // Removed lines take their endings with them
+        let diff = "@@ -2,3 +2,1 @@\n b\n-c\n-d\n e\n";
+        assert_eq!(
+            apply_unified_diff_to_string(mixed, diff, None, None).unwrap(),
+            "a\r\nb\r\ne\rf\n"
+        );
+
+        // Fuzzy matches keep the endings in step too
+        let diff = "@@ -2,3 +2,3 @@\n b \n-c\n+C\n d\n";
+        assert_eq!(
+            apply_unified_diff_detailed(mixed, diff, None, None, &fuzz(1)).unwrap(),
+            "a\r\nb\r\nC\nd\ne\rf\n"
+        );
+    }
+
     #[test]
     fn shell_escape_preserves_simple_commands() {
         assert_eq!(shell_escape_command("ls -la"), "ls -la");
@@ -1,14 +1,39 @@
 //! Unified WebDriver session abstraction.
 //!
 //! This module provides a unified interface for browser automation
-//! that can work with either Safari or Chrome WebDriver.
+//! that can work with Safari, Chrome or Firefox WebDriver.
+
+use crate::computer_control::{
+    ChromeDriver, Cookie, FirefoxDriver, SafariDriver, WebDriverController, WebElement,
+};
+
+/// Browser state recorded after each navigation, so the agent can inspect it
+/// without a round-trip to the WebDriver server.
+#[derive(Debug, Clone, Default)]
+pub struct WebDriverSessionState {
+    pub current_url: Option<String>,
+    pub window_handles: Vec<String>,
+    /// Frames entered with `web_switch_frame`, outermost first, each as the
+    /// selector or `index N` used to enter it. Empty at the top-level page.
+    pub frame_path: Vec<String>,
+}
 
-use crate::computer_control::{ChromeDriver, SafariDriver, WebDriverController, WebElement};
+impl WebDriverSessionState {
+    /// `[Frame: #outer > #inner]` while inside a frame, shown with tool output.
+    pub fn frame_label(&self) -> Option<String> {
+        if self.frame_path.is_empty() {
+            None
+        } else {
+            Some(format!("[Frame: {}]", self.frame_path.join(" > ")))
+        }
+    }
+}
 
-/// Unified WebDriver session that can hold either Safari or Chrome driver.
+/// Unified WebDriver session that can hold a Safari, Chrome or Firefox driver.
 pub enum WebDriverSession {
     Safari(SafariDriver),
     Chrome(ChromeDriver),
+    Firefox(FirefoxDriver),
 }
 
 #[async_trait::async_trait]
@@ -17,6 +42,7 @@ impl WebDriverController for WebDriverSession {
         match self {
             WebDriverSession::Safari(driver) => driver.navigate(url).await,
             WebDriverSession::Chrome(driver) => driver.navigate(url).await,
+            WebDriverSession::Firefox(driver) => driver.navigate(url).await,
         }
     }
 
@@ -24,6 +50,7 @@ impl WebDriverController for WebDriverSession {
         match self {
             WebDriverSession::Safari(driver) => driver.current_url().await,
             WebDriverSession::Chrome(driver) => driver.current_url().await,
+            WebDriverSession::Firefox(driver) => driver.current_url().await,
         }
     }
 
@@ -31,6 +58,7 @@ impl WebDriverController for WebDriverSession {
         match self {
             WebDriverSession::Safari(driver) => driver.title().await,
             WebDriverSession::Chrome(driver) => driver.title().await,
+            WebDriverSession::Firefox(driver) => driver.title().await,
         }
     }
 
@@ -41,6 +69,7 @@ impl WebDriverController for WebDriverSession {
         match self {
             WebDriverSession::Safari(driver) => driver.find_element(selector).await,
             WebDriverSession::Chrome(driver) => driver.find_element(selector).await,
+            WebDriverSession::Firefox(driver) => driver.find_element(selector).await,
         }
     }
 
@@ -51,6 +80,23 @@ impl WebDriverController for WebDriverSession {
         match self {
             WebDriverSession::Safari(driver) => driver.find_elements(selector).await,
             WebDriverSession::Chrome(driver) => driver.find_elements(selector).await,
+            WebDriverSession::Firefox(driver) => driver.find_elements(selector).await,
+        }
+    }
+
+    async fn find_element_xpath(&mut self, xpath: &str) -> anyhow::Result<WebElement> {
+        match self {
+            WebDriverSession::Safari(driver) => driver.find_element_xpath(xpath).await,
+            WebDriverSession::Chrome(driver) => driver.find_element_xpath(xpath).await,
+            WebDriverSession::Firefox(driver) => driver.find_element_xpath(xpath).await,
+        }
+    }
+
+    async fn find_elements_xpath(&mut self, xpath: &str) -> anyhow::Result<Vec<WebElement>> {
+        match self {
+            WebDriverSession::Safari(driver) => driver.find_elements_xpath(xpath).await,
+            WebDriverSession::Chrome(driver) => driver.find_elements_xpath(xpath).await,
+            WebDriverSession::Firefox(driver) => driver.find_elements_xpath(xpath).await,
         }
     }
 
@@ -62,6 +108,7 @@ impl WebDriverController for WebDriverSession {
         match self {
             WebDriverSession::Safari(driver) => driver.execute_script(script, args).await,
             WebDriverSession::Chrome(driver) => driver.execute_script(script, args).await,
+            WebDriverSession::Firefox(driver) => driver.execute_script(script, args).await,
         }
     }
 
@@ -69,6 +116,7 @@ impl WebDriverController for WebDriverSession {
         match self {
             WebDriverSession::Safari(driver) => driver.page_source().await,
             WebDriverSession::Chrome(driver) => driver.page_source().await,
+            WebDriverSession::Firefox(driver) => driver.page_source().await,
         }
     }
 
@@ -76,6 +124,7 @@ impl WebDriverController for WebDriverSession {
         match self {
             WebDriverSession::Safari(driver) => driver.screenshot(path).await,
             WebDriverSession::Chrome(driver) => driver.screenshot(path).await,
+            WebDriverSession::Firefox(driver) => driver.screenshot(path).await,
         }
     }
 
@@ -83,6 +132,7 @@ impl WebDriverController for WebDriverSession {
         match self {
             WebDriverSession::Safari(driver) => driver.close().await,
             WebDriverSession::Chrome(driver) => driver.close().await,
+            WebDriverSession::Firefox(driver) => driver.close().await,
         }
     }
 
@@ -90,6 +140,7 @@ impl WebDriverController for WebDriverSession {
         match self {
             WebDriverSession::Safari(driver) => driver.quit().await,
             WebDriverSession::Chrome(driver) => driver.quit().await,
+            WebDriverSession::Firefox(driver) => driver.quit().await,
         }
     }
     
@@ -97,6 +148,7 @@ impl WebDriverController for WebDriverSession {
         match self {
             WebDriverSession::Safari(driver) => driver.back().await,
             WebDriverSession::Chrome(driver) => driver.back().await,
+            WebDriverSession::Firefox(driver) => driver.back().await,
         }
     }
 
@@ -104,6 +156,7 @@ impl WebDriverController for WebDriverSession {
         match self {
             WebDriverSession::Safari(driver) => driver.forward().await,
             WebDriverSession::Chrome(driver) => driver.forward().await,
+            WebDriverSession::Firefox(driver) => driver.forward().await,
         }
     }
 
@@ -111,6 +164,39 @@ impl WebDriverController for WebDriverSession {
         match self {
             WebDriverSession::Safari(driver) => driver.refresh().await,
             WebDriverSession::Chrome(driver) => driver.refresh().await,
+            WebDriverSession::Firefox(driver) => driver.refresh().await,
+        }
+    }
+
+    async fn window_handles(&self) -> anyhow::Result<Vec<String>> {
+        match self {
+            WebDriverSession::Safari(driver) => driver.window_handles().await,
+            WebDriverSession::Chrome(driver) => driver.window_handles().await,
+            WebDriverSession::Firefox(driver) => driver.window_handles().await,
+        }
+    }
+
+    async fn get_all_cookies(&mut self) -> anyhow::Result<Vec<Cookie>> {
+        match self {
+            WebDriverSession::Safari(driver) => driver.get_all_cookies().await,
+            WebDriverSession::Chrome(driver) => driver.get_all_cookies().await,
+            WebDriverSession::Firefox(driver) => driver.get_all_cookies().await,
+        }
+    }
+
+    async fn add_cookie(&mut self, cookie: Cookie) -> anyhow::Result<()> {
+        match self {
+            WebDriverSession::Safari(driver) => driver.add_cookie(cookie).await,
+            WebDriverSession::Chrome(driver) => driver.add_cookie(cookie).await,
+            WebDriverSession::Firefox(driver) => driver.add_cookie(cookie).await,
+        }
+    }
+
+    async fn switch_to_frame(&mut self, index: Option<u16>) -> anyhow::Result<()> {
+        match self {
+            WebDriverSession::Safari(driver) => driver.switch_to_frame(index).await,
+            WebDriverSession::Chrome(driver) => driver.switch_to_frame(index).await,
+            WebDriverSession::Firefox(driver) => driver.switch_to_frame(index).await,
         }
     }
 }
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-    use super::computer_control::{ChromeDriver, WebDriverController};
+    use crate::computer_control::{ChromeDriver, Cookie, FirefoxDriver, WebDriverController};
     use anyhow::Result;
 
     #[tokio::test]
@@ -37,4 +37,751 @@ mod tests {
         
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_cookies() -> Result<()> {
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+
+        // Cookies can only be set for the currently loaded domain
+        driver.navigate("https://example.com").await?;
+        driver
+            .add_cookie(Cookie {
+                name: "g3_session".to_string(),
+                value: "abc123".to_string(),
+                domain: None,
+                path: Some("/".to_string()),
+                secure: false,
+                http_only: true,
+                expiry: Some(4_102_444_800),
+            })
+            .await?;
+
+        let cookies = driver.get_all_cookies().await?;
+        let cookie = cookies
+            .iter()
+            .find(|c| c.name == "g3_session")
+            .expect("cookie should be returned");
+        assert_eq!(cookie.value, "abc123");
+        assert!(cookie.http_only);
+        assert_eq!(cookie.expiry, Some(4_102_444_800));
+
+        driver.quit().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_extract_table() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let page = dir.path().join("table.html");
+        std::fs::write(
+            &page,
+            r#"<html><body><div id="wrapper"><table>
+                <tr><th>Name</th><th>Language</th></tr>
+                <tr><td>g3</td><td>Rust</td></tr>
+                <tr><td>pytest</td><td>Python</td></tr>
+            </table></div></body></html>"#,
+        )?;
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver.navigate(&format!("file://{}", page.display())).await?;
+
+        let records = crate::tools::webdriver::extract_table(&mut driver, "#wrapper", None, None)
+            .await?
+            .expect("table should be found");
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["Name"], "g3");
+        assert_eq!(records[1]["Language"], "Python");
+
+        let limited = crate::tools::webdriver::extract_table(&mut driver, "table", None, Some(1))
+            .await?
+            .expect("table should be found");
+        assert_eq!(limited.len(), 1);
+
+        driver.quit().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_fill_form() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let page = dir.path().join("form.html");
+        std::fs::write(
+            &page,
+            r#"<html><body><form id="signup" action="about:blank" onsubmit="window.submitted = true; return false;">
+                <input name="username">
+                <input id="email-field" type="email">
+                <label for="bio">About you</label><textarea id="bio"></textarea>
+                <select name="plan"><option value="free">Free</option><option value="pro">Pro</option></select>
+                <input type="checkbox" name="terms">
+                <label><input type="radio" name="size" value="s">Small</label>
+                <label><input type="radio" name="size" value="l">Large</label>
+            </form></body></html>"#,
+        )?;
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver.navigate(&format!("file://{}", page.display())).await?;
+
+        let fields: Vec<(String, String)> = [
+            ("username", "g3"),
+            ("email-field", "g3@example.com"),
+            ("About you", "Coding agent"),
+            ("plan", "Pro"),
+            ("terms", "true"),
+            ("size", "Large"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let result =
+            crate::tools::webdriver::fill_form(&mut driver, Some("#signup"), &fields, true).await?;
+        assert!(result.starts_with("✅ Filled 6 field(s)"), "{}", result);
+
+        let state = driver
+            .execute_script(
+                "const f = document.forms.signup; return [f.username.value, \
+                 document.getElementById('email-field').value, f.bio.value, f.plan.value, \
+                 f.terms.checked, f.size.value, !!window.submitted];",
+                vec![],
+            )
+            .await?;
+        assert_eq!(
+            state,
+            serde_json::json!(["g3", "g3@example.com", "Coding agent", "pro", true, "l", true])
+        );
+
+        let missing = crate::tools::webdriver::fill_form(
+            &mut driver,
+            Some("#signup"),
+            &[("nickname".to_string(), "x".to_string())],
+            false,
+        )
+        .await?;
+        assert!(missing.contains("no field named 'nickname'"), "{}", missing);
+
+        driver.quit().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires GeckoDriver to be running
+    async fn test_firefox_driver_integration() -> Result<()> {
+        // This test assumes GeckoDriver is running on port 4445
+        let mut driver = FirefoxDriver::with_port_headless(4445).await?;
+        
+        // Navigate to a test page
+        driver.navigate("https://example.com").await?;
+        
+        // Get page info
+        let title = driver.title().await?;
+        assert_eq!(title, "Example Domain");
+        
+        let url = driver.current_url().await?;
+        assert!(url.contains("example.com"));
+        
+        // Find and interact with elements
+        let h1_element = driver.find_element("h1").await?;
+        let h1_text = h1_element.text().await?;
+        assert!(h1_text.contains("Example Domain"));
+        
+        // Test navigation
+        driver.back().await?;
+        driver.forward().await?;
+        driver.refresh().await?;
+        
+        // Take screenshot
+        driver.screenshot("/tmp/g3-webdriver-firefox-test.png").await?;
+        
+        // Clean up
+        driver.quit().await?;
+        
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires chromedriver and Chrome to be installed
+    async fn test_session_reused_across_tool_calls() -> Result<()> {
+        use crate::background_process::BackgroundProcessManager;
+        use crate::tools::executor::ToolContext;
+        use crate::tools::webdriver;
+        use crate::ui_writer::NullUiWriter;
+        use crate::ToolCall;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let mut config = g3_config::Config::default();
+        config.webdriver.enabled = true;
+        config.webdriver.browser = g3_config::WebDriverBrowser::ChromeHeadless;
+
+        let ui_writer = NullUiWriter;
+        let webdriver_session = Arc::new(RwLock::new(None));
+        let webdriver_process = Arc::new(RwLock::new(None));
+        let webdriver_state = Arc::new(RwLock::new(Default::default()));
+        let background_process_manager = Arc::new(BackgroundProcessManager::new(
+            std::env::temp_dir().join("g3-webdriver-session-test"),
+        ));
+        let shell_sessions = Arc::new(tokio::sync::Mutex::new(Default::default()));
+        let ssh_sessions = Arc::new(Default::default());
+        let secrets_redactor = crate::security::SecretsRedactor::default();
+        let todo_content = Arc::new(RwLock::new(String::new()));
+        let mut pending_images = Vec::new();
+        let mut recent_files = Vec::new();
+        let mut extra_env = std::collections::HashMap::new();
+        let mut macros =
+            crate::tools::macros::MacroStore::new(std::env::temp_dir().join("g3-webdriver-macros"));
+        let mut file_history = Default::default();
+        let ctx = ToolContext {
+            config: &config,
+            ui_writer: &ui_writer,
+            session_id: None,
+            working_directory: std::env::current_dir()?,
+            computer_controller: None,
+            webdriver_session: &webdriver_session,
+            webdriver_process: &webdriver_process,
+            webdriver_state: &webdriver_state,
+            background_process_manager: &background_process_manager,
+            shell_sessions: &shell_sessions,
+            ssh_sessions: &ssh_sessions,
+            secrets_redactor: &secrets_redactor,
+            todo_content: &todo_content,
+            pending_images: &mut pending_images,
+            recent_files: &mut recent_files,
+            extra_env: &mut extra_env,
+            is_autonomous: false,
+            requirements_sha: None,
+            default_tool_timeout: None,
+            tool_cache: None,
+            dry_run: false,
+            macros: &mut macros,
+            file_history: &mut file_history,
+            schema_validator: None,
+            audit_logger: None,
+        };
+
+        let navigate = |url: &str| ToolCall {
+            tool: "webdriver_navigate".to_string(),
+            args: serde_json::json!({ "url": url }),
+        };
+
+        // First call starts the session lazily
+        let result = webdriver::execute_webdriver_navigate(&navigate("https://example.com"), &ctx).await?;
+        assert!(result.starts_with("✅"), "{}", result);
+        let first_pid = webdriver_process.read().await.as_ref().and_then(|p| p.id());
+        assert!(first_pid.is_some());
+
+        // Second call reuses the same browser
+        let result = webdriver::execute_webdriver_navigate(&navigate("https://example.org"), &ctx).await?;
+        assert!(result.starts_with("✅"), "{}", result);
+        let second_pid = webdriver_process.read().await.as_ref().and_then(|p| p.id());
+        assert_eq!(first_pid, second_pid);
+
+        let state = webdriver_state.read().await.clone();
+        assert!(state.current_url.unwrap_or_default().contains("example.org"));
+        assert!(!state.window_handles.is_empty());
+
+        let call = ToolCall {
+            tool: "web_close_session".to_string(),
+            args: serde_json::json!({}),
+        };
+        let result = webdriver::execute_web_close_session(&call, &ctx).await?;
+        assert_eq!(result, "✅ WebDriver session closed");
+        assert!(webdriver_session.read().await.is_none());
+        assert!(webdriver_process.read().await.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_page_metadata() -> Result<()> {
+        use crate::tools::webdriver::get_page_metadata;
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver.navigate("https://example.com").await?;
+
+        let metadata = get_page_metadata(&mut driver).await?;
+        assert_eq!(metadata.title.as_deref(), Some("Example Domain"));
+        // example.com has no description or Open Graph tags
+        assert_eq!(metadata.description, None);
+        assert_eq!(metadata.og_title, None);
+        assert_eq!(metadata.og_image, None);
+        assert!(metadata.format().contains("og:title: <none>"));
+
+        driver.quit().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_accessibility_snapshot() -> Result<()> {
+        use crate::tools::webdriver::accessibility_snapshot;
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver
+            .navigate(
+                "data:text/html,<nav aria-label='Primary' id='menu' class='top'>\
+                 <a href='/home'>Home</a></nav>\
+                 <main><h1>Orders</h1>\
+                 <label for='q'>Search orders</label><input id='q'>\
+                 <div role='button' aria-describedby='tip'>Delete</div>\
+                 <p id='tip' hidden>Removes the order</p>\
+                 <span aria-hidden='true'><button>Hidden</button></span></main>",
+            )
+            .await?;
+
+        let snapshot = accessibility_snapshot(&mut driver).await?;
+        let tree = snapshot.format();
+        assert!(!snapshot.truncated);
+        assert!(tree.contains("[navigation] \"Primary\" (nav#menu.top)"), "{}", tree);
+        assert!(tree.contains("  [link] \"Home\" (a)"), "{}", tree);
+        assert!(tree.contains("[heading] \"Orders\" (h1)"), "{}", tree);
+        assert!(tree.contains("\"Search orders\" (input#q)"), "{}", tree);
+        assert!(tree.contains("[button] \"Delete\" (div) - Removes the order"), "{}", tree);
+        assert!(!tree.contains("Hidden"), "{}", tree);
+
+        driver.quit().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_run_script() -> Result<()> {
+        use crate::tools::webdriver::{run_script, ScriptReturnType};
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver.navigate("https://example.com").await?;
+
+        let title = run_script(
+            &mut driver,
+            "return document.querySelector(arguments[0]).textContent;",
+            vec![serde_json::json!("h1")],
+            ScriptReturnType::Text,
+        )
+        .await;
+        assert_eq!(title, "Example Domain");
+
+        let json = run_script(
+            &mut driver,
+            "return {links: document.links.length};",
+            vec![],
+            ScriptReturnType::Json,
+        )
+        .await;
+        assert!(json.contains("\"links\": 1"), "{}", json);
+
+        let none = run_script(
+            &mut driver,
+            "document.body.dataset.touched = 'yes';",
+            vec![],
+            ScriptReturnType::None,
+        )
+        .await;
+        assert_eq!(none, "✅ Script executed");
+
+        driver.quit().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_run_script_errors() -> Result<()> {
+        use crate::tools::webdriver::{run_script, ScriptReturnType};
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver.navigate("https://example.com").await?;
+
+        let syntax = run_script(&mut driver, "return {;", vec![], ScriptReturnType::Json).await;
+        assert!(syntax.starts_with("❌ Script error:"), "{}", syntax);
+
+        let thrown = run_script(
+            &mut driver,
+            "throw new Error('boom');",
+            vec![],
+            ScriptReturnType::Json,
+        )
+        .await;
+        assert!(thrown.starts_with("❌ Script error:"), "{}", thrown);
+        assert!(thrown.contains("boom"), "{}", thrown);
+
+        driver.quit().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_download_with_session_cookies() -> Result<()> {
+        use crate::tools::webdriver::{cookie_header, download_client, download_to_file};
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver.navigate("https://example.com").await?;
+        driver
+            .add_cookie(Cookie {
+                name: "g3_download".to_string(),
+                value: "1".to_string(),
+                domain: None,
+                path: None,
+                secure: false,
+                http_only: false,
+                expiry: None,
+            })
+            .await?;
+
+        let cookies = driver.get_all_cookies().await?;
+        let url = reqwest::Url::parse("https://example.com/")?;
+        let header = cookie_header(&cookies, &url).unwrap_or_default();
+        assert!(header.contains("g3_download=1"), "{}", header);
+
+        let client = download_client(&cookies, &url, std::time::Duration::from_secs(60))?;
+        let temp_dir = tempfile::TempDir::new()?;
+        let output = temp_dir.path().join("example.html");
+        let (size, sha256) =
+            download_to_file(&client, url, &output, &crate::ui_writer::NullUiWriter).await?;
+        assert_eq!(size, std::fs::metadata(&output)?.len());
+        assert_eq!(sha256.len(), 64);
+
+        driver.quit().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_find_elements_by_text() -> Result<()> {
+        use crate::tools::webdriver::{find_elements_by_text, TextMatch};
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver.navigate("https://example.com").await?;
+
+        let links = find_elements_by_text(
+            &mut driver,
+            "more information",
+            TextMatch::Contains,
+            Some("a"),
+            false,
+        )
+        .await?;
+        assert_eq!(links.total, 1);
+        assert_eq!(links.elements[0].tag, "a");
+        assert!(links.elements[0].href.is_some());
+
+        // Only the innermost match is returned, not body/html
+        let heading =
+            find_elements_by_text(&mut driver, "Example Domain", TextMatch::Exact, None, true)
+                .await?;
+        assert_eq!(heading.elements.len(), 1);
+        assert_eq!(heading.elements[0].tag, "h1");
+
+        let none =
+            find_elements_by_text(&mut driver, "example domain", TextMatch::Exact, None, true)
+                .await?;
+        assert_eq!(none.total, 0);
+
+        driver.quit().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_find_by_xpath() -> Result<()> {
+        use crate::tools::webdriver::find_by_xpath;
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver.navigate("https://example.com").await?;
+
+        let heading = find_by_xpath(&mut driver, "//div/h1", true).await?;
+        assert_eq!(heading.total, 1);
+        assert_eq!(heading.elements[0].tag, "h1");
+        assert_eq!(heading.elements[0].text, "Example Domain");
+
+        let paragraphs = find_by_xpath(&mut driver, "//div/p", false).await?;
+        assert_eq!(paragraphs.total, 2);
+        assert!(paragraphs.elements[1].text.contains("More information"));
+
+        let link = find_by_xpath(&mut driver, "//p/a[contains(., 'More')]", true).await?;
+        assert_eq!(link.elements[0].tag, "a");
+        assert!(link.elements[0].href.is_some());
+
+        assert_eq!(find_by_xpath(&mut driver, "//table", false).await?.total, 0);
+        assert!(find_by_xpath(&mut driver, "//table", true).await.is_err());
+
+        driver.quit().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_wait_for_navigation() -> Result<()> {
+        use crate::tools::webdriver::wait_for_navigation;
+        use std::time::Duration;
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver.navigate("https://example.com").await?;
+        let start_url = driver.current_url().await?;
+
+        // Navigate after a delay, as a form submission would
+        driver
+            .execute_script(
+                "setTimeout(() => { location.href = 'https://example.org/'; }, 500);",
+                vec![],
+            )
+            .await?;
+        let (url, elapsed) = wait_for_navigation(
+            &mut driver,
+            &start_url,
+            None,
+            Duration::from_secs(15),
+            Duration::from_millis(250),
+        )
+        .await?
+        .expect("navigation should complete");
+        assert!(url.contains("example.org"), "{}", url);
+        assert!(elapsed >= Duration::from_millis(250));
+
+        // Already on the expected page: returns without waiting
+        let (_, elapsed) = wait_for_navigation(
+            &mut driver,
+            &start_url,
+            Some("example.org"),
+            Duration::from_secs(15),
+            Duration::from_millis(250),
+        )
+        .await?
+        .expect("already navigated");
+        assert!(elapsed < Duration::from_secs(1));
+
+        // Nothing navigates: times out
+        let result = wait_for_navigation(
+            &mut driver,
+            &url,
+            None,
+            Duration::from_secs(1),
+            Duration::from_millis(250),
+        )
+        .await?;
+        assert!(result.is_none());
+
+        driver.quit().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_inspect_element() -> Result<()> {
+        use crate::tools::webdriver::inspect_element;
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver
+            .navigate(
+                "data:text/html,<div id='box' class='card' data-role='panel' \
+                 style='width:200px;height:100px;color:rgb(255, 0, 0)'>\
+                 <p>Hello <b>there</b></p></div><span id='gone' style='display:none'>x</span>",
+            )
+            .await?;
+
+        let inspection = inspect_element(&mut driver, "#box", true, 3)
+            .await?
+            .expect("element exists");
+        assert_eq!(inspection.tag, "div");
+        assert_eq!(inspection.class.as_deref(), Some("card"));
+        assert_eq!(inspection.attributes["data-role"], "panel");
+        assert_eq!(inspection.bounding_rect.width, 200.0);
+        assert!(inspection.visible);
+        let styles = inspection.computed_styles.expect("styles requested");
+        assert_eq!(styles["color"], "rgb(255, 0, 0)");
+        assert!(!styles.contains_key("cursor"), "default styles are left out");
+        assert_eq!(inspection.children[0].tag, "p");
+        assert_eq!(inspection.children[0].children[0].tag, "b");
+
+        let hidden = inspect_element(&mut driver, "#gone", false, 0)
+            .await?
+            .expect("element exists");
+        assert!(!hidden.visible);
+        assert!(hidden.computed_styles.is_none());
+        assert!(hidden.children.is_empty());
+
+        assert!(inspect_element(&mut driver, "#missing", false, 0).await?.is_none());
+
+        driver.quit().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_network_requests() -> Result<()> {
+        use crate::tools::webdriver::{get_network_requests, install_network_log};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Serves a blank page at / and JSON everywhere else
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = vec![0; 8192];
+                let n = socket.read(&mut request).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..n]);
+                let (content_type, body) = if request.starts_with("GET / ") {
+                    ("text/html", "<html><body>app</body></html>")
+                } else {
+                    ("application/json", r#"{"ok":true}"#)
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    content_type,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver.navigate(&format!("http://{}/", addr)).await?;
+        assert!(install_network_log(&mut driver).await?);
+        assert!(!install_network_log(&mut driver).await?, "already installed");
+
+        driver
+            .execute_script(
+                "fetch('/api/items?page=2');\
+                 const xhr = new XMLHttpRequest();\
+                 xhr.open('post', '/api/login');\
+                 xhr.send(JSON.stringify({user: 'bob'}));",
+                vec![],
+            )
+            .await?;
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+        let requests = get_network_requests(&mut driver, true)
+            .await?
+            .expect("capture installed");
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].url, format!("http://{}/api/items?page=2", addr));
+        assert_eq!(requests[0].status, Some(200));
+        assert_eq!(requests[0].response_preview.as_deref(), Some(r#"{"ok":true}"#));
+        assert_eq!(requests[1].method, "POST");
+        assert_eq!(requests[1].request_body.as_deref(), Some(r#"{"user":"bob"}"#));
+        assert_eq!(requests[1].status, Some(200));
+
+        // Cleared by the previous read
+        let requests = get_network_requests(&mut driver, false).await?.unwrap();
+        assert!(requests.is_empty());
+
+        // A new page starts without capture
+        driver.navigate(&format!("http://{}/", addr)).await?;
+        assert!(get_network_requests(&mut driver, false).await?.is_none());
+
+        driver.quit().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_nested_iframes() -> Result<()> {
+        use crate::tools::webdriver::{switch_frame, FrameTarget};
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver
+            .navigate(
+                "data:text/html,<h1 id='where'>top</h1>\
+                 <iframe id='outer' srcdoc=\"<h1 id='where'>outer</h1>\
+                 <iframe srcdoc='<h1 id=where>inner</h1>'></iframe>\"></iframe>",
+            )
+            .await?;
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+        async fn heading(driver: &mut ChromeDriver) -> Result<String> {
+            driver.find_element("#where").await?.text().await
+        }
+
+        switch_frame(&mut driver, &FrameTarget::Selector("#outer".to_string())).await?;
+        assert_eq!(heading(&mut driver).await?, "outer");
+
+        switch_frame(&mut driver, &FrameTarget::Index(0)).await?;
+        assert_eq!(heading(&mut driver).await?, "inner");
+
+        driver.switch_to_frame(None).await?;
+        assert_eq!(heading(&mut driver).await?, "top");
+
+        // Index out of range for the page
+        assert!(switch_frame(&mut driver, &FrameTarget::Index(5)).await.is_err());
+
+        driver.quit().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // This test requires ChromeDriver to be running
+    async fn test_chrome_driver_web_storage() -> Result<()> {
+        use crate::tools::webdriver::{
+            storage_clear, storage_get, storage_list, storage_remove, storage_set, StorageType,
+        };
+        use serde_json::json;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Storage needs a real origin, so serve a blank page over HTTP
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = vec![0; 8192];
+                let _ = socket.read(&mut request).await;
+                let body = "<html><body>app</body></html>";
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let mut driver = ChromeDriver::with_port_headless(9515).await?;
+        driver.navigate(&format!("http://{}/", addr)).await?;
+        let local = StorageType::Local;
+
+        storage_set(&mut driver, local, "prefs", &json!({"theme": "dark"})).await?;
+        storage_set(&mut driver, local, "token", &json!("tok_123")).await?;
+        storage_set(&mut driver, StorageType::Session, "step", &json!(3)).await?;
+
+        assert_eq!(storage_get(&mut driver, local, "prefs").await?, Some(json!({"theme": "dark"})));
+        assert_eq!(storage_get(&mut driver, local, "token").await?, Some(json!("tok_123")));
+        assert_eq!(storage_get(&mut driver, local, "missing").await?, None);
+        let stored = driver
+            .execute_script("return localStorage.getItem('prefs')", vec![])
+            .await?;
+        assert_eq!(stored, json!(r#"{"theme":"dark"}"#));
+
+        let mut entries = storage_list(&mut driver, local).await?;
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            entries,
+            vec![
+                ("prefs".to_string(), json!({"theme": "dark"})),
+                ("token".to_string(), json!("tok_123")),
+            ]
+        );
+        assert_eq!(
+            storage_list(&mut driver, StorageType::Session).await?,
+            vec![("step".to_string(), json!(3))]
+        );
+
+        assert!(storage_remove(&mut driver, local, "token").await?);
+        assert!(!storage_remove(&mut driver, local, "token").await?);
+        assert_eq!(storage_clear(&mut driver, local).await?, 1);
+        assert!(storage_list(&mut driver, local).await?.is_empty());
+
+        // data: URLs have an opaque origin and may not use storage
+        driver.navigate("data:text/html,<p>no origin</p>").await?;
+        let err = storage_list(&mut driver, local).await.unwrap_err();
+        assert!(err.to_string().contains("localStorage is not available"), "{}", err);
+
+        driver.quit().await?;
+        Ok(())
+    }
+}
@@ -1,7 +1,11 @@
 //! Integration tests for tree-sitter code search
 
-use g3_core::code_search::{execute_code_search, CodeSearchRequest, SearchSpec};
+use g3_core::code_search::{
+    execute_code_search, execute_ranked_code_search, CodeSearchRequest, Ranking, SearchSpec,
+};
 use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
 
 #[tokio::test]
 async fn test_find_async_functions() {
@@ -37,9 +41,12 @@ pub async fn another_async(x: i32) -> Result<(), ()> {
             language: "rust".to_string(),
             paths: vec![test_dir.to_string_lossy().to_string()],
             context_lines: 0,
+            symbol: None,
         }],
         max_concurrency: 4,
         max_matches_per_search: 100,
+        max_results: None,
+        context_lines: None,
     };
 
     let response = execute_code_search(request).await.unwrap();
@@ -100,9 +107,12 @@ pub async fn another_async(x: i32) -> Result<(), ()> {
             language: "rust".to_string(),
             paths: vec![test_dir.to_string_lossy().to_string()],
             context_lines: 0,
+            symbol: None,
         }],
         max_concurrency: 4,
         max_matches_per_search: 100,
+        max_results: None,
+        context_lines: None,
     };
 
     let response = execute_code_search(request).await.unwrap();
@@ -162,9 +172,12 @@ enum MyEnum {
             language: "rust".to_string(),
             paths: vec![test_dir.to_string_lossy().to_string()],
             context_lines: 0,
+            symbol: None,
         }],
         max_concurrency: 4,
         max_matches_per_search: 100,
+        max_results: None,
+        context_lines: None,
     };
 
     let response = execute_code_search(request).await.unwrap();
@@ -219,9 +232,12 @@ pub fn target_function() {
             language: "rust".to_string(),
             paths: vec![test_dir.to_string_lossy().to_string()],
             context_lines: 2,
+            symbol: None,
         }],
         max_concurrency: 4,
         max_matches_per_search: 100,
+        max_results: None,
+        context_lines: None,
     };
 
     let response = execute_code_search(request).await.unwrap();
@@ -250,6 +266,54 @@ pub fn target_function() {
     fs::remove_dir_all(&test_dir).ok();
 }
 
+#[tokio::test]
+async fn test_request_context_lines_marks_matches() {
+    let test_dir = std::env::temp_dir().join("g3_test_code_search_context_range");
+    fs::create_dir_all(&test_dir).unwrap();
+
+    fs::write(
+        test_dir.join("test.rs"),
+        "use std::fmt;\n\nstruct Point {\n    x: i32,\n}\n\nimpl Point {\n    fn x(&self) -> i32 {\n        self.x\n    }\n}\n",
+    )
+    .unwrap();
+
+    let request = CodeSearchRequest {
+        searches: vec![SearchSpec {
+            name: "items".to_string(),
+            query: "[(struct_item) @item (function_item) @item]".to_string(),
+            language: "rust".to_string(),
+            paths: vec![test_dir.to_string_lossy().to_string()],
+            context_lines: 0,
+            symbol: None,
+        }],
+        max_concurrency: 4,
+        max_matches_per_search: 100,
+        max_results: None,
+        context_lines: Some(1),
+    };
+
+    let response = execute_code_search(request).await.unwrap();
+    let mut matches: Vec<_> = response.searches[0].matches.iter().collect();
+    matches.sort_by_key(|m| m.line);
+    assert_eq!(matches.len(), 2);
+
+    // The whole struct is marked, with one line either side
+    assert_eq!((matches[0].line, matches[0].end_line), (3, 5));
+    assert_eq!(
+        matches[0].context.as_deref().unwrap(),
+        "```\n    2 | \n>>> 3 | struct Point {\n>>> 4 |     x: i32,\n>>> 5 | }\n    6 | \n```"
+    );
+
+    // The method's context reaches the enclosing impl block
+    let context = matches[1].context.as_deref().unwrap();
+    assert!(context.contains("    7 | impl Point {"), "{}", context);
+    assert!(context.contains(">>>  8 |     fn x(&self) -> i32 {"), "{}", context);
+    assert!(context.contains(">>> 10 |     }"), "{}", context);
+    assert!(context.contains("   11 | }"), "{}", context);
+
+    fs::remove_dir_all(&test_dir).ok();
+}
+
 #[tokio::test]
 async fn test_multiple_searches() {
     // Create a temporary test file
@@ -276,6 +340,7 @@ pub struct MyStruct;
                 language: "rust".to_string(),
                 paths: vec![test_dir.to_string_lossy().to_string()],
                 context_lines: 0,
+                symbol: None,
             },
             SearchSpec {
                 name: "structs".to_string(),
@@ -283,10 +348,13 @@ pub struct MyStruct;
                 language: "rust".to_string(),
                 paths: vec![test_dir.to_string_lossy().to_string()],
                 context_lines: 0,
+                symbol: None,
             },
         ],
         max_concurrency: 4,
         max_matches_per_search: 100,
+        max_results: None,
+        context_lines: None,
     };
 
     let response = execute_code_search(request).await.unwrap();
@@ -341,9 +409,12 @@ class MyClass:
             language: "python".to_string(),
             paths: vec![test_dir.to_string_lossy().to_string()],
             context_lines: 0,
+            symbol: None,
         }],
         max_concurrency: 4,
         max_matches_per_search: 100,
+        max_results: None,
+        context_lines: None,
     };
 
     let response = execute_code_search(request).await.unwrap();
@@ -402,9 +473,12 @@ class MyClass {
             language: "javascript".to_string(),
             paths: vec![test_dir.to_string_lossy().to_string()],
             context_lines: 0,
+            symbol: None,
         }],
         max_concurrency: 4,
         max_matches_per_search: 100,
+        max_results: None,
+        context_lines: None,
     };
 
     let response = execute_code_search(request).await.unwrap();
@@ -446,9 +520,12 @@ async fn test_go_search() {
             language: "go".to_string(),
             paths: vec![test_code_path.to_string_lossy().to_string()],
             context_lines: 0,
+            symbol: None,
         }],
         max_concurrency: 4,
         max_matches_per_search: 500,
+        max_results: None,
+        context_lines: None,
     };
 
     let response = execute_code_search(request).await.unwrap();
@@ -489,9 +566,12 @@ async fn test_java_search() {
             language: "java".to_string(),
             paths: vec![test_code_path.to_string_lossy().to_string()],
             context_lines: 0,
+            symbol: None,
         }],
         max_concurrency: 4,
         max_matches_per_search: 500,
+        max_results: None,
+        context_lines: None,
     };
 
     let response = execute_code_search(request).await.unwrap();
@@ -524,9 +604,12 @@ async fn test_c_search() {
             language: "c".to_string(),
             paths: vec![test_code_path.to_string_lossy().to_string()],
             context_lines: 0,
+            symbol: None,
         }],
         max_concurrency: 4,
         max_matches_per_search: 500,
+        max_results: None,
+        context_lines: None,
     };
 
     let response = execute_code_search(request).await.unwrap();
@@ -561,9 +644,12 @@ async fn test_cpp_search() {
             language: "cpp".to_string(),
             paths: vec![test_code_path.to_string_lossy().to_string()],
             context_lines: 0,
+            symbol: None,
         }],
         max_concurrency: 4,
         max_matches_per_search: 500,
+        max_results: None,
+        context_lines: None,
     };
 
     let response = execute_code_search(request).await.unwrap();
@@ -589,9 +675,12 @@ async fn test_kotlin_search() {
             language: "kotlin".to_string(),
             paths: vec!["examples/test_code".to_string()],
             context_lines: 0,
+            symbol: None,
         }],
         max_concurrency: 4,
         max_matches_per_search: 500,
+        max_results: None,
+        context_lines: None,
     };
 
     let response = execute_code_search(request).await.unwrap();
@@ -606,3 +695,94 @@ async fn test_kotlin_search() {
         .collect();
     assert!(names.contains(&"Person"));
 }
+
+/// Write `source` to `path`, with its modification time `age_days` in the past.
+fn write_aged(path: &Path, source: &str, age_days: u64) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, source).unwrap();
+    let modified = SystemTime::now() - Duration::from_secs(age_days * 86_400);
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+}
+
+fn function_search(
+    dir: &Path,
+    symbol: Option<&str>,
+    max_results: Option<usize>,
+) -> CodeSearchRequest {
+    CodeSearchRequest {
+        searches: vec![SearchSpec {
+            name: "functions".to_string(),
+            query: "(function_item name: (identifier) @name)".to_string(),
+            language: "rust".to_string(),
+            paths: vec![dir.to_string_lossy().to_string()],
+            context_lines: 0,
+            symbol: symbol.map(String::from),
+        }],
+        max_concurrency: 4,
+        max_matches_per_search: 500,
+        max_results,
+        context_lines: None,
+    }
+}
+
+fn ranked_names(response: &g3_core::code_search::CodeSearchResponse) -> Vec<&str> {
+    response.searches[0]
+        .matches
+        .iter()
+        .map(|m| m.captures["name"].as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_results_ranked_by_name_match_and_recency() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    write_aged(&dir.join("old.rs"), "fn load_old() {}\n", 60);
+    write_aged(&dir.join("recent.rs"), "fn load_recent() {}\n", 0);
+    write_aged(&dir.join("config.rs"), "fn parse_config() {}\n", 60);
+    write_aged(&dir.join("week.rs"), "fn load_week() {}\n", 7);
+
+    let response = execute_code_search(function_search(dir, Some("parse_config"), None))
+        .await
+        .unwrap();
+
+    // The exact name wins despite its age, then newest file first
+    assert_eq!(
+        ranked_names(&response),
+        vec!["parse_config", "load_recent", "load_week", "load_old"]
+    );
+    let scores: Vec<f32> = response.searches[0]
+        .matches
+        .iter()
+        .map(|m| m.score)
+        .collect();
+    assert!(scores.windows(2).all(|w| w[0] >= w[1]), "{:?}", scores);
+
+    // max_results keeps the best matches; match_count still counts all of them
+    let response = execute_code_search(function_search(dir, Some("parse_config"), Some(2)))
+        .await
+        .unwrap();
+    assert_eq!(ranked_names(&response), vec!["parse_config", "load_recent"]);
+    assert_eq!(response.searches[0].match_count, 4);
+}
+
+#[tokio::test]
+async fn test_results_ranked_by_proximity_to_recent_files() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    write_aged(&dir.join("a/far.rs"), "fn far() {}\n", 1);
+    write_aged(&dir.join("b/near.rs"), "fn near() {}\n", 1);
+    write_aged(&dir.join("b/edited.rs"), "fn edited() {}\n", 1);
+
+    let ranking = Ranking::new(Default::default(), &[dir.join("b/edited.rs")]);
+    let response = execute_ranked_code_search(function_search(dir, None, None), ranking)
+        .await
+        .unwrap();
+
+    assert_eq!(ranked_names(&response), vec!["edited", "near", "far"]);
+}
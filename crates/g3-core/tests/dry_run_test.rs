@@ -0,0 +1,97 @@
+//! Tests for dry-run mode
+//!
+//! This test verifies that:
+//! 1. Tool calls are described instead of executed, so nothing touches disk
+//! 2. final_output still runs, so the agent can finish its turn
+
+use g3_config::Config;
+use g3_core::ui_writer::NullUiWriter;
+use g3_core::{Agent, ToolCall};
+use serde_json::json;
+use serial_test::serial;
+use tempfile::TempDir;
+
+async fn create_dry_run_agent(temp_dir: &TempDir) -> Agent<NullUiWriter> {
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+    let mut config = Config::default();
+    config.agent.dry_run = true;
+    Agent::new_with_readme_and_quiet(config, NullUiWriter, None, true)
+        .await
+        .unwrap()
+}
+
+fn create_tool_call(tool: &str, args: serde_json::Value) -> ToolCall {
+    ToolCall {
+        tool: tool.to_string(),
+        args,
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_dry_run_writes_no_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut agent = create_dry_run_agent(&temp_dir).await;
+
+    let existing = temp_dir.path().join("existing.txt");
+    std::fs::write(&existing, "original").unwrap();
+
+    let calls = [
+        create_tool_call(
+            "write_file",
+            json!({ "file_path": "created.txt", "content": "hello" }),
+        ),
+        create_tool_call(
+            "str_replace",
+            json!({
+                "file_path": existing.to_string_lossy(),
+                "diff": "--- a\n+++ b\n@@ -1 +1 @@\n-original\n+changed\n"
+            }),
+        ),
+        create_tool_call("shell", json!({ "command": "touch from_shell.txt" })),
+        create_tool_call("todo_write", json!({ "content": "- [ ] Task" })),
+    ];
+
+    for call in &calls {
+        let result = agent.execute_tool(call).await.unwrap();
+        assert!(
+            result.starts_with(&format!("[DRY RUN] Would execute tool '{}'", call.tool)),
+            "Expected a dry-run result for {}, got: {}",
+            call.tool,
+            result
+        );
+    }
+
+    assert!(!temp_dir.path().join("created.txt").exists());
+    assert!(!temp_dir.path().join("from_shell.txt").exists());
+    assert!(!temp_dir.path().join("todo.g3.md").exists());
+    assert_eq!(std::fs::read_to_string(&existing).unwrap(), "original");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_dry_run_result_includes_args() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut agent = create_dry_run_agent(&temp_dir).await;
+
+    let call = create_tool_call("shell", json!({ "command": "rm -rf /tmp/test" }));
+    let result = agent.execute_tool(&call).await.unwrap();
+
+    assert_eq!(
+        result,
+        r#"[DRY RUN] Would execute tool 'shell' with args: {"command":"rm -rf /tmp/test"}"#
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_dry_run_still_runs_final_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut agent = create_dry_run_agent(&temp_dir).await;
+
+    let call = create_tool_call("final_output", json!({ "summary": "Planned the change" }));
+    let result = agent.execute_tool(&call).await.unwrap();
+
+    assert!(!result.contains("[DRY RUN]"), "Got: {}", result);
+    assert!(result.contains("Planned the change"), "Got: {}", result);
+}
@@ -53,6 +53,25 @@ fn test_find_complete_json_object_end_incomplete() {
     assert!(end.is_none(), "Should return None for incomplete JSON");
 }
 
+#[test]
+fn test_find_complete_json_object_end_with_surrogate_pair_escapes() {
+    let json = r#"{"key":"\uD83D\uDE00{"}"#;
+    let end = StreamingToolParser::find_complete_json_object_end(json);
+    assert_eq!(end, Some(json.len() - 1), "Brace inside the string must not be counted");
+
+    let json = r#"{"tool": "shell", "args": {"command": "echo \uD83D\uDE00 }"}} trailing"#;
+    let end = StreamingToolParser::find_complete_json_object_end(json).unwrap();
+    assert_eq!(&json[..=end], r#"{"tool": "shell", "args": {"command": "echo \uD83D\uDE00 }"}}"#);
+}
+
+#[test]
+fn test_find_complete_json_object_end_with_escape_before_quote() {
+    // An escaped backslash before the closing quote must not escape the quote
+    let json = r#"{"path": "C:\\", "x": "\u007b"}"#;
+    let end = StreamingToolParser::find_complete_json_object_end(json);
+    assert_eq!(end, Some(json.len() - 1));
+}
+
 // =============================================================================
 // Test: Tool calls separated by text should NOT be duplicates
 // =============================================================================
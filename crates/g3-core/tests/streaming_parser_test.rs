@@ -543,3 +543,47 @@ fn test_content_with_phrases_in_value_accepted() {
     ));
     assert_eq!(tools.len(), 1);
 }
+
+// =============================================================================
+// Test: Truncated stream detection with ResponseValidator
+// =============================================================================
+
+#[test]
+fn test_validator_detects_stream_ending_mid_json() {
+    use g3_providers::{ResponseCheck, ResponseValidator};
+
+    let mut validator = ResponseValidator::new(true);
+    let mut parser = StreamingToolParser::new();
+
+    // The connection drops partway through the tool call's args
+    for part in [
+        "I'll write the file now.\n",
+        r#"{"tool": "write_file", "args": {"file_path": "a.txt", "#,
+        r#""content": "hello wor"#,
+    ] {
+        assert!(parser.process_chunk(&chunk(part, false)).is_empty());
+    }
+
+    assert_eq!(
+        validator.validate(&parser),
+        ResponseCheck::Truncated { retry: true }
+    );
+    assert_eq!(validator.truncations(), 1);
+}
+
+#[test]
+fn test_validator_accepts_complete_stream() {
+    use g3_providers::{ResponseCheck, ResponseValidator};
+
+    let mut validator = ResponseValidator::new(false);
+    let mut parser = StreamingToolParser::new();
+    let tools = parser.process_chunk(&chunk(
+        r#"Listing files: {"tool": "shell", "args": {"command": "ls"}}"#,
+        false,
+    ));
+    parser.process_chunk(&chunk("", true));
+
+    assert_eq!(tools.len(), 1);
+    assert_eq!(validator.validate(&parser), ResponseCheck::Complete);
+    assert_eq!(validator.truncations(), 0);
+}
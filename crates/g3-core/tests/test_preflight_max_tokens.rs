@@ -19,6 +19,7 @@ fn create_test_config_with_thinking(thinking_budget: Option<u32>) -> Config {
         max_tokens: Some(16000),
         temperature: Some(0.1),
         cache_config: None,
+        prompt_cache_min_tokens: None,
         enable_1m_context: None,
         thinking_budget_tokens: thinking_budget,
     });
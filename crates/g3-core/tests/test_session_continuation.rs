@@ -95,7 +95,7 @@ fn test_save_and_load_continuation() {
     );
 
     // Save the continuation
-    let saved_path = save_continuation(&original).expect("Failed to save continuation");
+    let saved_path = save_continuation(&original, &[]).expect("Failed to save continuation");
     assert!(saved_path.exists());
 
     // Verify the symlink was created
@@ -140,7 +140,7 @@ fn test_find_incomplete_agent_session() {
         Some("- [x] Done\n- [ ] Not done yet".to_string()),  // incomplete TODO
         current_working_dir,  // Use actual current dir
     );
-    save_continuation(&agent_session).expect("Failed to save agent session");
+    save_continuation(&agent_session, &[]).expect("Failed to save agent session");
 
     // Should find the incomplete session for "fowler"
     let result = find_incomplete_agent_session("fowler").expect("Failed to search");
@@ -178,7 +178,7 @@ fn test_find_incomplete_agent_session_ignores_complete_todos() {
         Some("- [x] Task 1\n- [x] Task 2".to_string()),  // all complete
         current_working_dir,
     );
-    save_continuation(&complete_session).expect("Failed to save");
+    save_continuation(&complete_session, &[]).expect("Failed to save");
 
     // Should NOT find session since all TODOs are complete
     let result = find_incomplete_agent_session("fowler").expect("Failed to search");
@@ -209,7 +209,7 @@ fn test_find_incomplete_agent_session_ignores_non_agent_mode() {
         Some("- [ ] Incomplete task".to_string()),
         current_working_dir,
     );
-    save_continuation(&non_agent_session).expect("Failed to save");
+    save_continuation(&non_agent_session, &[]).expect("Failed to save");
 
     // Should NOT find session since it's not agent mode
     let result = find_incomplete_agent_session("fowler").expect("Failed to search");
@@ -244,7 +244,7 @@ fn test_clear_continuation() {
         None,
         ".".to_string(),
     );
-    save_continuation(&continuation).expect("Failed to save");
+    save_continuation(&continuation, &[]).expect("Failed to save");
 
     // Verify the symlink exists
     let session_dir = get_session_dir();
@@ -299,7 +299,7 @@ fn test_has_valid_continuation_with_missing_session_log() {
         None,
         ".".to_string(),
     );
-    save_continuation(&continuation).expect("Failed to save");
+    save_continuation(&continuation, &[]).expect("Failed to save");
 
     // Should be invalid because session log doesn't exist
     assert!(!has_valid_continuation());
@@ -327,7 +327,7 @@ fn test_has_valid_continuation_with_existing_session_log() {
         None,
         temp_dir.path().to_string_lossy().to_string(),
     );
-    save_continuation(&continuation).expect("Failed to save");
+    save_continuation(&continuation, &[]).expect("Failed to save");
 
     // Should be valid because session log exists
     assert!(has_valid_continuation());
@@ -348,7 +348,7 @@ fn test_continuation_serialization_format() {
         Some("- [x] Done\n- [ ] Todo".to_string()),
         "/workspace".to_string(),
     );
-    save_continuation(&continuation).expect("Failed to save");
+    save_continuation(&continuation, &[]).expect("Failed to save");
 
     // Read the raw JSON and verify structure
     let json_content =
@@ -382,7 +382,7 @@ fn test_multiple_saves_update_symlink() {
         None,
         ".".to_string(),
     );
-    save_continuation(&first).expect("Failed to save first");
+    save_continuation(&first, &[]).expect("Failed to save first");
 
     // Verify symlink points to first session
     let session_dir = get_session_dir();
@@ -398,7 +398,7 @@ fn test_multiple_saves_update_symlink() {
         None,
         ".".to_string(),
     );
-    save_continuation(&second).expect("Failed to save second");
+    save_continuation(&second, &[]).expect("Failed to save second");
 
     // Verify symlink now points to second session
     let second_target = fs::read_link(&session_dir).expect("Failed to read symlink");
@@ -443,7 +443,7 @@ fn test_symlink_migration_from_old_directory() {
         None,
         ".".to_string(),
     );
-    save_continuation(&continuation).expect("Failed to save");
+    save_continuation(&continuation, &[]).expect("Failed to save");
 
     // The session path should now be a symlink, not a directory
     let session_dir = get_session_dir();
@@ -457,3 +457,127 @@ fn test_symlink_migration_from_old_directory() {
 
     teardown_test_env(original_dir);
 }
+
+#[test]
+fn test_save_continuation_compresses_large_session_log() {
+    let _lock = TEST_MUTEX.lock().unwrap();
+    let (temp_dir, original_dir) = setup_test_env();
+
+    let logs_dir = temp_dir.path().join("logs");
+    fs::create_dir_all(&logs_dir).expect("Failed to create logs dir");
+    let session_log_path = logs_dir.join("compress_test.json");
+    let session_json = r#"{"context_window":{"conversation_history":[{"role":"user","content":"hi"}]}}"#;
+    fs::write(&session_log_path, session_json).expect("Failed to write session log");
+
+    // Above the 60% threshold, the log is compressed and the continuation repointed
+    let continuation = SessionContinuation::new(false, None,
+        "compress_test".to_string(),
+        None,
+        session_log_path.to_string_lossy().to_string(),
+        65.0,
+        None,
+        temp_dir.path().to_string_lossy().to_string(),
+    );
+    save_continuation(&continuation, &[]).expect("Failed to save");
+
+    let loaded = load_continuation().expect("Failed to load").expect("No continuation");
+    assert!(loaded.compressed);
+    assert!(loaded.session_log_path.ends_with("compress_test.json.zst"));
+    assert!(!session_log_path.exists());
+    assert!(has_valid_continuation());
+
+    let context = g3_core::load_context_from_session_log(std::path::Path::new(&loaded.session_log_path))
+        .expect("Failed to load session log")
+        .expect("Session log missing");
+    assert_eq!(context["context_window"]["conversation_history"][0]["content"], "hi");
+
+    teardown_test_env(original_dir);
+}
+
+#[test]
+fn test_continuation_restores_log_rewritten_after_compression() {
+    let _lock = TEST_MUTEX.lock().unwrap();
+    let (temp_dir, original_dir) = setup_test_env();
+
+    let logs_dir = temp_dir.path().join("logs");
+    fs::create_dir_all(&logs_dir).expect("Failed to create logs dir");
+    let session_log_path = logs_dir.join("rewrite_test.json");
+    fs::write(&session_log_path, r#"{"context_window":{"conversation_history":[]}}"#)
+        .expect("Failed to write session log");
+
+    let continuation = SessionContinuation::new(false, None,
+        "rewrite_test".to_string(),
+        None,
+        session_log_path.to_string_lossy().to_string(),
+        65.0,
+        None,
+        temp_dir.path().to_string_lossy().to_string(),
+    );
+    save_continuation(&continuation, &[]).expect("Failed to save");
+
+    // The next turn writes the plain log again and drops the compressed copy
+    let loaded = load_continuation().expect("Failed to load").expect("No continuation");
+    assert!(loaded.session_log_path.ends_with("rewrite_test.json.zst"));
+    let session_json = r#"{"context_window":{"conversation_history":[
+        {"role":"user","content":"first"},
+        {"role":"assistant","content":"second"}
+    ]}}"#;
+    fs::write(&session_log_path, session_json).expect("Failed to rewrite session log");
+    fs::remove_file(&loaded.session_log_path).expect("Failed to remove compressed log");
+
+    assert!(has_valid_continuation());
+    assert_eq!(loaded.session_log(), Some(session_log_path));
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut agent = runtime
+        .block_on(g3_core::Agent::new_with_readme_and_quiet(
+            g3_config::Config::default(),
+            g3_core::ui_writer::NullUiWriter,
+            None,
+            true,
+        ))
+        .expect("Failed to create agent");
+    assert!(agent.restore_from_continuation(&loaded).expect("Failed to restore"));
+    let history = &agent.get_context_window().conversation_history;
+    assert!(history.iter().any(|m| m.content == "second"));
+
+    teardown_test_env(original_dir);
+}
+
+#[test]
+fn test_save_continuation_stores_message_summary() {
+    use g3_providers::{Message, MessageRole};
+
+    let _lock = TEST_MUTEX.lock().unwrap();
+    let (temp_dir, original_dir) = setup_test_env();
+
+    let messages = [
+        Message::new(MessageRole::System, "system prompt".to_string()),
+        Message::new(MessageRole::User, "List the files".to_string()),
+        Message::new(
+            MessageRole::Assistant,
+            r#"{"tool": "shell", "args": {"command": "ls"}}"#.to_string(),
+        ),
+    ];
+    let refs: Vec<&Message> = messages.iter().collect();
+
+    let continuation = SessionContinuation::new(false, None,
+        "summary_test".to_string(),
+        None,
+        "/logs/g3_session_summary_test.json".to_string(),
+        10.0,
+        None,
+        temp_dir.path().to_string_lossy().to_string(),
+    );
+    save_continuation(&continuation, &refs).expect("Failed to save");
+
+    let loaded = load_continuation().expect("Failed to load").expect("No continuation");
+    assert_eq!(loaded.message_summary.len(), 2);
+    assert_eq!(loaded.message_summary[0].role, "user");
+    assert_eq!(loaded.message_summary[0].content_preview, "List the files");
+    assert!(loaded.message_summary[0].tool_calls.is_empty());
+    assert_eq!(loaded.message_summary[1].role, "assistant");
+    assert_eq!(loaded.message_summary[1].tool_calls, vec!["shell".to_string()]);
+
+    teardown_test_env(original_dir);
+}
@@ -0,0 +1,95 @@
+//! Tests for the per-session working directory
+//!
+//! This test verifies that:
+//! 1. File tools resolve relative paths against the session working directory
+//! 2. A successful `cd` in a shell command moves the working directory
+//! 3. The process current directory is never changed
+
+use g3_config::Config;
+use g3_core::ui_writer::NullUiWriter;
+use g3_core::{Agent, ToolCall};
+use serde_json::json;
+use serial_test::serial;
+use tempfile::TempDir;
+
+async fn create_agent(cwd: &TempDir) -> Agent<NullUiWriter> {
+    std::env::set_current_dir(cwd.path()).unwrap();
+    Agent::new_with_readme_and_quiet(Config::default(), NullUiWriter, None, true)
+        .await
+        .unwrap()
+}
+
+fn create_tool_call(tool: &str, args: serde_json::Value) -> ToolCall {
+    ToolCall {
+        tool: tool.to_string(),
+        args,
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_file_tools_use_working_directory() {
+    let cwd = TempDir::new().unwrap();
+    let workspace = TempDir::new().unwrap();
+    let mut agent = create_agent(&cwd).await;
+    agent.set_working_directory(workspace.path().to_path_buf());
+
+    let write = create_tool_call(
+        "write_file",
+        json!({ "file_path": "notes/todo.txt", "content": "hello\n" }),
+    );
+    let result = agent.execute_tool(&write).await.unwrap();
+    assert!(result.starts_with("✅"), "{}", result);
+
+    assert!(workspace.path().join("notes/todo.txt").is_file());
+    assert!(!cwd.path().join("notes").exists());
+
+    let replace = create_tool_call(
+        "str_replace",
+        json!({
+            "file_path": "notes/todo.txt",
+            "diff": "--- a\n+++ b\n@@ -1 +1 @@\n-hello\n+goodbye\n"
+        }),
+    );
+    let result = agent.execute_tool(&replace).await.unwrap();
+    assert!(result.starts_with("✅"), "{}", result);
+
+    let read = create_tool_call("read_file", json!({ "file_path": "notes/todo.txt" }));
+    let result = agent.execute_tool(&read).await.unwrap();
+    assert!(result.contains("goodbye"), "{}", result);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_shell_cd_moves_working_directory() {
+    let cwd = TempDir::new().unwrap();
+    let workspace = TempDir::new().unwrap();
+    std::fs::create_dir(workspace.path().join("sub")).unwrap();
+    let mut agent = create_agent(&cwd).await;
+    agent.set_working_directory(workspace.path().to_path_buf());
+
+    let cd = create_tool_call("shell", json!({ "command": "cd sub && pwd" }));
+    let result = agent.execute_tool(&cd).await.unwrap();
+    assert!(result.starts_with("[exit: 0]\n"), "{}", result);
+
+    let expected = workspace.path().join("sub").canonicalize().unwrap();
+    assert_eq!(agent.working_directory().canonicalize().unwrap(), expected);
+    // Only the tool's view of the directory moved
+    assert_eq!(
+        std::env::current_dir().unwrap().canonicalize().unwrap(),
+        cwd.path().canonicalize().unwrap()
+    );
+
+    let write = create_tool_call(
+        "write_file",
+        json!({ "file_path": "created.txt", "content": "in sub" }),
+    );
+    agent.execute_tool(&write).await.unwrap();
+    assert!(expected.join("created.txt").is_file());
+
+    // A failed cd leaves the working directory alone
+    let cd = create_tool_call("shell", json!({ "command": "cd does-not-exist" }));
+    let result = agent.execute_tool(&cd).await.unwrap();
+    assert!(result.starts_with("[exit: 1]\n❌"), "{}", result);
+    assert_eq!(agent.working_directory().canonicalize().unwrap(), expected);
+}
@@ -194,13 +194,7 @@ impl CodeExecutor {
 
     /// Execute Bash code
     async fn execute_bash(&self, code: &str) -> Result<ExecutionResult> {
-        // Check if this is a detached/daemon command that should run independently
-        let is_detached = code.trim_start().starts_with("setsid ")
-            || code.trim_start().starts_with("nohup ")
-            || code.contains(" disown")
-            || (code.contains(" &") && (code.contains("nohup") || code.contains("setsid")));
-
-        if is_detached {
+        if is_detached_command(code) {
             // For detached commands, just spawn and return immediately
             use std::process::Stdio;
             Command::new("bash")
@@ -304,14 +298,7 @@ impl CodeExecutor {
             std::env::current_dir()
         );
 
-        // Check if this is a detached/daemon command that should run independently
-        // Look for patterns like: setsid, nohup with &, or explicit backgrounding with disown
-        let is_detached = code.trim_start().starts_with("setsid ")
-            || code.trim_start().starts_with("nohup ")
-            || code.contains(" disown")
-            || (code.contains(" &") && (code.contains("nohup") || code.contains("setsid")));
-
-        if is_detached {
+        if is_detached_command(code) {
             // For detached commands, just spawn and return immediately
             let mut cmd = TokioCommand::new("bash");
             cmd.arg("-c").arg(code);
@@ -433,6 +420,15 @@ impl CodeExecutor {
     }
 }
 
+/// Check if this is a detached/daemon command that should run independently.
+/// Looks for patterns like: setsid, nohup with &, or explicit backgrounding with disown.
+pub fn is_detached_command(code: &str) -> bool {
+    code.trim_start().starts_with("setsid ")
+        || code.trim_start().starts_with("nohup ")
+        || code.contains(" disown")
+        || (code.contains(" &") && (code.contains("nohup") || code.contains("setsid")))
+}
+
 /// Check if rustup component llvm-tools-preview is installed
 pub fn is_llvm_tools_installed() -> Result<bool> {
     let output = Command::new("rustup")
@@ -111,7 +111,7 @@ use tracing::{debug, error};
 
 use crate::{
     CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream, LLMProvider, Message,
-    MessageRole, Tool, ToolCall, Usage,
+    MessageRole, PromptCacheOptimizer, Tool, ToolCall, Usage,
     streaming::{decode_utf8_streaming, make_final_chunk, make_text_chunk, make_tool_chunk},
 };
 
@@ -132,6 +132,7 @@ pub struct AnthropicProvider {
     enable_1m_context: bool,
     thinking_budget_tokens: Option<u32>,
     use_bearer_auth: bool, // New field to support Bearer token authentication
+    prompt_cache: Option<PromptCacheOptimizer>,
 }
 
 impl AnthropicProvider {
@@ -167,6 +168,7 @@ impl AnthropicProvider {
             enable_1m_context: enable_1m_context.unwrap_or(false),
             thinking_budget_tokens,
             use_bearer_auth: false, // Default to standard Anthropic auth
+            prompt_cache: None,
         })
     }
 
@@ -256,9 +258,17 @@ impl AnthropicProvider {
             enable_1m_context: enable_1m_context.unwrap_or(false),
             thinking_budget_tokens,
             use_bearer_auth: false, // Default to standard Anthropic auth
+            prompt_cache: None,
         })
     }
 
+    /// Automatically add cache breakpoints to the system prompt and tool
+    /// definitions of every request (see [`PromptCacheOptimizer`]).
+    pub fn with_prompt_cache(mut self, optimizer: PromptCacheOptimizer) -> Self {
+        self.prompt_cache = Some(optimizer);
+        self
+    }
+
     fn create_request_builder(&self, streaming: bool) -> RequestBuilder {
         let mut builder = self
             .client
@@ -320,6 +330,7 @@ impl AnthropicProvider {
                     name: tool.name.clone(),
                     description: tool.description.clone(),
                     input_schema: schema,
+                    cache_control: None,
                 }
             })
             .collect()
@@ -395,7 +406,28 @@ impl AnthropicProvider {
         temperature: f32,
         disable_thinking: bool,
     ) -> Result<AnthropicRequest> {
-        let (system, anthropic_messages) = self.convert_messages(messages)?;
+        let mut messages = messages.to_vec();
+        let cache_hints = self
+            .prompt_cache
+            .as_ref()
+            .map(|optimizer| optimizer.optimize(&mut messages, tools.unwrap_or_default()))
+            .unwrap_or_default();
+
+        let (system, anthropic_messages) = self.convert_messages(&messages)?;
+
+        // System messages are sent as a single block; keep a cache marker by
+        // sending it as a content block rather than a plain string
+        let system_cache_control = messages
+            .iter()
+            .filter(|m| matches!(m.role, MessageRole::System))
+            .find_map(|m| m.cache_control.clone());
+        let system = system.map(|text| match system_cache_control {
+            Some(cache_control) => AnthropicSystem::Blocks(vec![AnthropicContent::Text {
+                text,
+                cache_control: Some(cache_control),
+            }]),
+            None => AnthropicSystem::Text(text),
+        });
 
         if anthropic_messages.is_empty() {
             return Err(anyhow!(
@@ -404,7 +436,14 @@ impl AnthropicProvider {
         }
 
         // Convert tools if provided
-        let anthropic_tools = tools.map(|t| self.convert_tools(t));
+        let mut anthropic_tools = tools.map(|t| self.convert_tools(t));
+        if let (Some(cache_control), Some(last)) = (
+            cache_hints.tools,
+            anthropic_tools.as_mut().and_then(|t| t.last_mut()),
+        ) {
+            // A breakpoint on the last tool caches the whole tools block
+            last.cache_control = Some(cache_control);
+        }
 
         // Add thinking configuration if budget_tokens is set AND max_tokens is sufficient AND not explicitly disabled
         // Anthropic requires: max_tokens > thinking.budget_tokens
@@ -519,6 +558,8 @@ impl AnthropicProvider {
                                                         completion_tokens: usage.output_tokens,
                                                         total_tokens: usage.input_tokens
                                                             + usage.output_tokens,
+                                                        cache_read_tokens: usage.cache_read_input_tokens,
+                                                        cache_write_tokens: usage.cache_creation_input_tokens,
                                                     });
                                                     debug!(
                                                         "Captured usage from message_start: {:?}",
@@ -527,6 +568,20 @@ impl AnthropicProvider {
                                                 }
                                             }
                                         }
+                                        "message_delta" => {
+                                            // The final output token count arrives with message_delta
+                                            if let (Some(usage), Some(accumulated)) =
+                                                (event.usage, accumulated_usage.as_mut())
+                                            {
+                                                accumulated.completion_tokens = usage.output_tokens;
+                                                accumulated.total_tokens =
+                                                    accumulated.prompt_tokens + usage.output_tokens;
+                                                debug!(
+                                                    "Updated usage from message_delta: {:?}",
+                                                    accumulated
+                                                );
+                                            }
+                                        }
                                         "content_block_start" => {
                                             debug!(
                                                 "Received content_block_start event: {:?}",
@@ -796,6 +851,8 @@ impl LLMProvider for AnthropicProvider {
             completion_tokens: anthropic_response.usage.output_tokens,
             total_tokens: anthropic_response.usage.input_tokens
                 + anthropic_response.usage.output_tokens,
+            cache_read_tokens: anthropic_response.usage.cache_read_input_tokens,
+            cache_write_tokens: anthropic_response.usage.cache_creation_input_tokens,
         };
 
         debug!(
@@ -940,7 +997,7 @@ struct AnthropicRequest {
     temperature: f32,
     messages: Vec<AnthropicMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    system: Option<String>,
+    system: Option<AnthropicSystem>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<AnthropicTool>>,
     stream: bool,
@@ -948,11 +1005,22 @@ struct AnthropicRequest {
     thinking: Option<ThinkingConfig>,
 }
 
+/// The `system` field: a plain string, or text blocks when it carries a
+/// cache marker.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum AnthropicSystem {
+    Text(String),
+    Blocks(Vec<AnthropicContent>),
+}
+
 #[derive(Debug, Serialize)]
 struct AnthropicTool {
     name: String,
     description: String,
     input_schema: AnthropicToolInputSchema,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_control: Option<crate::CacheControl>,
 }
 
 #[derive(Debug, Serialize)]
@@ -1015,8 +1083,14 @@ struct AnthropicResponse {
 
 #[derive(Debug, Deserialize)]
 struct AnthropicUsage {
+    #[serde(default)]
     input_tokens: u32,
+    #[serde(default)]
     output_tokens: u32,
+    #[serde(default)]
+    cache_creation_input_tokens: u32,
+    #[serde(default)]
+    cache_read_input_tokens: u32,
 }
 
 // Streaming response structures
@@ -1033,6 +1107,9 @@ struct AnthropicStreamEvent {
     content_block: Option<AnthropicContent>,
     #[serde(default)]
     message: Option<AnthropicStreamMessage>,
+    /// Present on message_delta with the final output token count
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1110,6 +1187,46 @@ mod tests {
         assert!(request_body.tools.is_none());
     }
 
+    #[test]
+    fn test_prompt_cache_breakpoints_in_request_body() {
+        let provider =
+            AnthropicProvider::new("test-key".to_string(), None, None, None, None, None, None, None)
+                .unwrap()
+                .with_prompt_cache(PromptCacheOptimizer::new().with_min_tokens(10));
+
+        let messages = vec![
+            Message::new(MessageRole::System, "You are a careful coding agent. ".repeat(10)),
+            Message::new(MessageRole::User, "Hello!".to_string()),
+        ];
+        let tool = |name: &str| Tool {
+            name: name.to_string(),
+            description: "Run a shell command and return its output".to_string(),
+            input_schema: serde_json::json!({ "type": "object", "properties": {} }),
+        };
+        let tools = vec![tool("shell"), tool("read_file")];
+
+        let request_body = provider
+            .create_request_body(&messages, Some(&tools), false, 1000, 0.5, false)
+            .unwrap();
+        let json = serde_json::to_value(&request_body).unwrap();
+
+        assert_eq!(json["system"][0]["type"], "text");
+        assert_eq!(json["system"][0]["cache_control"]["type"], "ephemeral");
+        assert!(json["tools"][0].get("cache_control").is_none());
+        assert_eq!(json["tools"][1]["cache_control"]["type"], "ephemeral");
+        assert!(json["messages"][0]["content"][0].get("cache_control").is_none());
+
+        // Without the optimizer the system prompt stays a plain string
+        let provider =
+            AnthropicProvider::new("test-key".to_string(), None, None, None, None, None, None, None).unwrap();
+        let request_body = provider
+            .create_request_body(&messages, Some(&tools), false, 1000, 0.5, false)
+            .unwrap();
+        let json = serde_json::to_value(&request_body).unwrap();
+        assert!(json["system"].is_string());
+        assert!(json["tools"][1].get("cache_control").is_none());
+    }
+
     #[test]
     fn test_tool_conversion() {
         let provider =
@@ -1301,4 +1418,25 @@ mod tests {
         assert_eq!(text_content.len(), 1);
         assert_eq!(text_content[0], "Here is my response.");
     }
+
+    #[test]
+    fn test_stream_usage_deserialization() {
+        let start: AnthropicStreamEvent = serde_json::from_str(
+            r#"{"type": "message_start", "message": {"usage": {"input_tokens": 12, "output_tokens": 1,
+                "cache_creation_input_tokens": 300, "cache_read_input_tokens": 4000}}}"#,
+        )
+        .unwrap();
+        let usage = start.message.unwrap().usage.unwrap();
+        assert_eq!(usage.input_tokens, 12);
+        assert_eq!(usage.cache_creation_input_tokens, 300);
+        assert_eq!(usage.cache_read_input_tokens, 4000);
+
+        let delta: AnthropicStreamEvent = serde_json::from_str(
+            r#"{"type": "message_delta", "delta": {"stop_reason": "end_turn"}, "usage": {"output_tokens": 250}}"#,
+        )
+        .unwrap();
+        let usage = delta.usage.unwrap();
+        assert_eq!(usage.output_tokens, 250);
+        assert_eq!(usage.input_tokens, 0);
+    }
 }
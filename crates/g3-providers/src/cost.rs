@@ -0,0 +1,260 @@
+//! Token usage and cost accounting.
+//!
+//! Providers report token counts in the final event of each streamed response.
+//! [`SessionCost`] folds those counts into running totals and prices them with
+//! the per-model rates in the `pricing.toml` bundled with this crate, so the UI
+//! can show what a session has cost so far. Models missing from the table are
+//! still counted but add nothing to the cost.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::Usage;
+
+/// Pricing table compiled into the crate.
+const BUNDLED_PRICING: &str = include_str!("../pricing.toml");
+
+/// Prices in `pricing.toml` are per this many tokens.
+const TOKENS_PER_PRICE_UNIT: f64 = 1_000_000.0;
+
+/// Cache writes cost this multiple of the input price unless a model sets its own.
+const DEFAULT_CACHE_WRITE_MULTIPLIER: f64 = 1.25;
+
+/// Cache reads cost this multiple of the input price unless a model sets its own.
+const DEFAULT_CACHE_READ_MULTIPLIER: f64 = 0.1;
+
+/// Token counts for one or more responses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageStats {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_write_tokens: u64,
+}
+
+impl UsageStats {
+    pub fn add(&mut self, other: &UsageStats) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_read_tokens + self.cache_write_tokens
+    }
+}
+
+impl From<&Usage> for UsageStats {
+    fn from(usage: &Usage) -> Self {
+        Self {
+            input_tokens: usage.prompt_tokens as u64,
+            output_tokens: usage.completion_tokens as u64,
+            cache_read_tokens: usage.cache_read_tokens as u64,
+            cache_write_tokens: usage.cache_write_tokens as u64,
+        }
+    }
+}
+
+/// USD prices per million tokens for one model.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    #[serde(default)]
+    pub cache_write: Option<f64>,
+    #[serde(default)]
+    pub cache_read: Option<f64>,
+}
+
+impl ModelPricing {
+    /// Cost in USD of the given token counts at these rates.
+    pub fn cost_usd(&self, usage: &UsageStats) -> f64 {
+        let cache_write = self
+            .cache_write
+            .unwrap_or(self.input * DEFAULT_CACHE_WRITE_MULTIPLIER);
+        let cache_read = self
+            .cache_read
+            .unwrap_or(self.input * DEFAULT_CACHE_READ_MULTIPLIER);
+
+        (usage.input_tokens as f64 * self.input
+            + usage.output_tokens as f64 * self.output
+            + usage.cache_write_tokens as f64 * cache_write
+            + usage.cache_read_tokens as f64 * cache_read)
+            / TOKENS_PER_PRICE_UNIT
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PricingFile {
+    #[serde(default)]
+    models: HashMap<String, ModelPricing>,
+}
+
+/// Looks up per-model rates and prices token usage.
+#[derive(Debug, Clone)]
+pub struct CostCalculator {
+    models: HashMap<String, ModelPricing>,
+}
+
+impl CostCalculator {
+    /// Calculator using the `pricing.toml` bundled with the crate.
+    pub fn bundled() -> Self {
+        Self::from_toml(BUNDLED_PRICING).expect("bundled pricing.toml is valid")
+    }
+
+    /// Parse a pricing table in the `pricing.toml` format.
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let file: PricingFile = toml::from_str(content).context("Invalid pricing table")?;
+        Ok(Self {
+            models: file.models,
+        })
+    }
+
+    /// Rates for `model`, taken from the longest pricing key the model name contains.
+    pub fn pricing_for(&self, model: &str) -> Option<&ModelPricing> {
+        let model = model.to_lowercase();
+        self.models
+            .iter()
+            .filter(|(key, _)| model.contains(key.to_lowercase().as_str()))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, pricing)| pricing)
+    }
+
+    /// Cost in USD of `usage` on `model`, or `None` if the model has no pricing.
+    pub fn cost_usd(&self, model: &str, usage: &UsageStats) -> Option<f64> {
+        self.pricing_for(model).map(|pricing| pricing.cost_usd(usage))
+    }
+}
+
+impl Default for CostCalculator {
+    fn default() -> Self {
+        Self::bundled()
+    }
+}
+
+/// Token usage and estimated cost accumulated over a session.
+#[derive(Debug, Clone, Default)]
+pub struct SessionCost {
+    calculator: CostCalculator,
+    usage: UsageStats,
+    cost_usd: f64,
+}
+
+impl SessionCost {
+    pub fn new(calculator: CostCalculator) -> Self {
+        Self {
+            calculator,
+            usage: UsageStats::default(),
+            cost_usd: 0.0,
+        }
+    }
+
+    /// Add the usage reported for one response from `model`.
+    pub fn record(&mut self, model: &str, usage: &Usage) {
+        let stats = UsageStats::from(usage);
+        self.usage.add(&stats);
+        self.cost_usd += self.calculator.cost_usd(model, &stats).unwrap_or(0.0);
+    }
+
+    pub fn usage(&self) -> &UsageStats {
+        &self.usage
+    }
+
+    pub fn total_cost_usd(&self) -> f64 {
+        self.cost_usd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(prompt: u32, completion: u32, cache_read: u32, cache_write: u32) -> Usage {
+        Usage {
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+            total_tokens: prompt + completion,
+            cache_read_tokens: cache_read,
+            cache_write_tokens: cache_write,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_cost_for_known_token_counts() {
+        let calculator = CostCalculator::bundled();
+        let stats = UsageStats {
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cache_read_tokens: 2_000_000,
+            cache_write_tokens: 400_000,
+        };
+
+        // 3.00 + 1.50 + 0.60 + 1.50
+        let cost = calculator.cost_usd("claude-sonnet-4-5-20250929", &stats).unwrap();
+        assert_close(cost, 6.60);
+
+        // 15.00 + 7.50 + 3.00 + 7.50
+        let cost = calculator.cost_usd("claude-opus-4-1-20250805", &stats).unwrap();
+        assert_close(cost, 33.00);
+    }
+
+    #[test]
+    fn test_longest_matching_key_wins() {
+        let calculator = CostCalculator::bundled();
+        assert_eq!(calculator.pricing_for("claude-opus-4-5").unwrap().input, 5.00);
+        assert_eq!(calculator.pricing_for("claude-opus-4-20250514").unwrap().input, 15.00);
+        assert_eq!(calculator.pricing_for("gpt-4o-mini").unwrap().input, 0.15);
+        assert_eq!(calculator.pricing_for("databricks-claude-sonnet-4").unwrap().input, 3.00);
+        assert!(calculator.pricing_for("qwen2.5-coder").is_none());
+    }
+
+    #[test]
+    fn test_default_cache_rates() {
+        let calculator = CostCalculator::from_toml(
+            r#"
+            [models.test-model]
+            input = 2.0
+            output = 4.0
+            "#,
+        )
+        .unwrap();
+        let stats = UsageStats {
+            cache_read_tokens: 1_000_000,
+            cache_write_tokens: 1_000_000,
+            ..Default::default()
+        };
+
+        // 0.1 * 2.0 + 1.25 * 2.0
+        assert_close(calculator.cost_usd("test-model", &stats).unwrap(), 2.70);
+    }
+
+    #[test]
+    fn test_session_cost_accumulates() {
+        let mut session = SessionCost::new(CostCalculator::bundled());
+        session.record("claude-haiku-4-5", &usage(10_000, 2_000, 50_000, 0));
+        session.record("claude-haiku-4-5", &usage(5_000, 1_000, 0, 20_000));
+        session.record("unpriced-local-model", &usage(1_000, 1_000, 0, 0));
+
+        assert_eq!(
+            *session.usage(),
+            UsageStats {
+                input_tokens: 16_000,
+                output_tokens: 4_000,
+                cache_read_tokens: 50_000,
+                cache_write_tokens: 20_000,
+            }
+        );
+        // (15_000 * 1.00 + 3_000 * 5.00 + 50_000 * 0.10 + 20_000 * 1.25) / 1M
+        assert_close(session.total_cost_usd(), 0.06);
+    }
+}
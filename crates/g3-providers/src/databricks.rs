@@ -761,6 +761,8 @@ impl LLMProvider for DatabricksProvider {
             prompt_tokens: databricks_response.usage.prompt_tokens,
             completion_tokens: databricks_response.usage.completion_tokens,
             total_tokens: databricks_response.usage.total_tokens,
+            cache_read_tokens: 0,
+            cache_write_tokens: 0,
         };
 
         debug!(
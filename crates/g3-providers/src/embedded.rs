@@ -531,6 +531,8 @@ impl LLMProvider for EmbeddedProvider {
                 prompt_tokens,
                 completion_tokens,
                 total_tokens: prompt_tokens + completion_tokens,
+                cache_read_tokens: 0,
+                cache_write_tokens: 0,
             },
             model: self.model_name.clone(),
         })
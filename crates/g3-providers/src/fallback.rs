@@ -0,0 +1,400 @@
+//! Provider wrapper that retries failed requests on a secondary provider.
+//!
+//! [`FallbackProvider`] sends every request to the primary provider first. If
+//! that fails with an error worth retrying elsewhere (rate limits, overloaded
+//! or unavailable servers, prompts too long for the primary's context window)
+//! the same request is sent to the fallback provider after a short delay.
+//! Anything else, such as a malformed request or bad credentials, is returned
+//! as is: the fallback would most likely fail the same way or hide a
+//! configuration problem.
+//!
+//! Only errors returned when starting a request are retried. Once a stream has
+//! started, errors inside it are passed through.
+//!
+//! Requests are built for native or text tool calling before the wrapper sees
+//! them, so both providers must agree on
+//! [`has_native_tool_calling`](LLMProvider::has_native_tool_calling).
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::{CompletionRequest, CompletionResponse, CompletionStream, LLMProvider};
+
+/// Delay before retrying on the fallback provider when none is configured.
+pub const DEFAULT_FALLBACK_DELAY: Duration = Duration::from_secs(1);
+
+/// Whether `error` should be retried on a different provider.
+///
+/// True for HTTP 429 / rate limits, HTTP 503 / overloaded or unavailable
+/// servers, and context-length rejections. Other client errors (400, 401,
+/// 403, 404) are not retryable.
+pub fn is_retryable_error(error: &anyhow::Error) -> bool {
+    let message = format!("{:#}", error).to_lowercase();
+
+    let context_length = message.contains("context length")
+        || message.contains("context_length_exceeded")
+        || message.contains("maximum context length")
+        || message.contains("prompt is too long");
+    if context_length {
+        return true;
+    }
+
+    status_codes(&message).any(|code| code == 429 || code == 503)
+        || message.contains("rate limit")
+        || message.contains("rate_limit")
+        || message.contains("too many requests")
+        || message.contains("service unavailable")
+        || message.contains("overloaded")
+}
+
+/// HTTP status codes named in a lowercased error message: three-digit numbers
+/// right after "error", "status", "code" or "http", as in "API error 429" or
+/// "HTTP/1.1 503". Other numbers (request ids, token counts) are ignored.
+fn status_codes(message: &str) -> impl Iterator<Item = u16> + '_ {
+    let words: Vec<&str> = message
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '/' || c == '.'))
+        .map(|word| word.trim_end_matches('.'))
+        .filter(|word| !word.is_empty())
+        .collect();
+    (1..words.len()).filter_map(move |i| {
+        let label = words[i - 1];
+        let is_label = matches!(label, "error" | "status" | "code" | "http")
+            || label.starts_with("http/");
+        let code = words[i];
+        if is_label && code.len() == 3 {
+            code.parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// Sends requests to `primary`, retrying on `fallback` when
+/// [`is_retryable_error`] says so.
+///
+/// Reports the primary's name and limits, so it can be registered in place of
+/// the primary without the rest of the agent noticing. [`model`] names the
+/// model that served the last request.
+///
+/// [`model`]: LLMProvider::model
+pub struct FallbackProvider {
+    primary: Box<dyn LLMProvider>,
+    fallback: Box<dyn LLMProvider>,
+    delay: Duration,
+    served_by_fallback: AtomicBool,
+}
+
+impl FallbackProvider {
+    /// Fails if only one of the providers has native tool calling.
+    pub fn new(primary: Box<dyn LLMProvider>, fallback: Box<dyn LLMProvider>) -> Result<Self> {
+        if primary.has_native_tool_calling() != fallback.has_native_tool_calling() {
+            let describe = |provider: &dyn LLMProvider| {
+                if provider.has_native_tool_calling() {
+                    "native"
+                } else {
+                    "text"
+                }
+            };
+            anyhow::bail!(
+                "Cannot fall back from {} ({} tool calls) to {} ({} tool calls)",
+                primary.name(),
+                describe(primary.as_ref()),
+                fallback.name(),
+                describe(fallback.as_ref())
+            );
+        }
+        Ok(Self {
+            primary,
+            fallback,
+            delay: DEFAULT_FALLBACK_DELAY,
+            served_by_fallback: AtomicBool::new(false),
+        })
+    }
+
+    /// Set how long to wait before sending the request to the fallback.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn fallback(&self) -> &dyn LLMProvider {
+        self.fallback.as_ref()
+    }
+
+    /// The provider that handled the last request.
+    fn last_served(&self) -> &dyn LLMProvider {
+        if self.served_by_fallback.load(Ordering::Relaxed) {
+            self.fallback.as_ref()
+        } else {
+            self.primary.as_ref()
+        }
+    }
+
+    async fn before_fallback(&self, error: &anyhow::Error) {
+        warn!(
+            "Provider {} failed ({:#}); retrying with {} ({}) in {:?}",
+            self.primary.name(),
+            error,
+            self.fallback.name(),
+            self.fallback.model(),
+            self.delay
+        );
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+        self.served_by_fallback.store(true, Ordering::Relaxed);
+    }
+}
+
+#[async_trait]
+impl LLMProvider for FallbackProvider {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
+        match self.primary.complete(request.clone()).await {
+            Err(e) if is_retryable_error(&e) => {
+                self.before_fallback(&e).await;
+                self.fallback.complete(request).await
+            }
+            result => {
+                self.served_by_fallback.store(false, Ordering::Relaxed);
+                result
+            }
+        }
+    }
+
+    async fn stream(&self, request: CompletionRequest) -> Result<CompletionStream> {
+        match self.primary.stream(request.clone()).await {
+            Err(e) if is_retryable_error(&e) => {
+                self.before_fallback(&e).await;
+                self.fallback.stream(request).await
+            }
+            result => {
+                self.served_by_fallback.store(false, Ordering::Relaxed);
+                result
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.primary.name()
+    }
+
+    fn model(&self) -> &str {
+        self.last_served().model()
+    }
+
+    fn has_native_tool_calling(&self) -> bool {
+        self.primary.has_native_tool_calling()
+    }
+
+    fn supports_cache_control(&self) -> bool {
+        self.primary.supports_cache_control()
+    }
+
+    fn max_tokens(&self) -> u32 {
+        self.primary.max_tokens()
+    }
+
+    fn temperature(&self) -> f32 {
+        self.primary.temperature()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Message, MessageRole, Usage};
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    /// Provider that fails with `error` (if set) and counts its calls.
+    struct MockProvider {
+        name: String,
+        model: String,
+        error: Option<String>,
+        calls: Arc<AtomicUsize>,
+        native_tools: bool,
+    }
+
+    impl MockProvider {
+        fn new(name: &str, error: Option<&str>) -> (Self, Arc<AtomicUsize>) {
+            let calls = Arc::new(AtomicUsize::new(0));
+            let provider = Self {
+                name: name.to_string(),
+                model: format!("{}-model", name),
+                error: error.map(String::from),
+                calls: calls.clone(),
+                native_tools: true,
+            };
+            (provider, calls)
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for MockProvider {
+        async fn complete(&self, _request: CompletionRequest) -> Result<CompletionResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if let Some(error) = &self.error {
+                anyhow::bail!("{}", error);
+            }
+            Ok(CompletionResponse {
+                content: format!("from {}", self.name),
+                usage: Usage {
+                    prompt_tokens: 1,
+                    completion_tokens: 1,
+                    total_tokens: 2,
+                    cache_read_tokens: 0,
+                    cache_write_tokens: 0,
+                },
+                model: format!("{}-model", self.name),
+            })
+        }
+
+        async fn stream(&self, _request: CompletionRequest) -> Result<CompletionStream> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if let Some(error) = &self.error {
+                anyhow::bail!("{}", error);
+            }
+            let (tx, rx) = tokio::sync::mpsc::channel(1);
+            tx.send(Ok(crate::make_final_chunk(vec![], None)))
+                .await
+                .unwrap();
+            Ok(tokio_stream::wrappers::ReceiverStream::new(rx))
+        }
+
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn model(&self) -> &str {
+            &self.model
+        }
+
+        fn has_native_tool_calling(&self) -> bool {
+            self.native_tools
+        }
+
+        fn max_tokens(&self) -> u32 {
+            1024
+        }
+
+        fn temperature(&self) -> f32 {
+            0.0
+        }
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            messages: vec![Message::new(MessageRole::User, "hi".to_string())],
+            max_tokens: None,
+            temperature: None,
+            stream: false,
+            tools: None,
+            disable_thinking: false,
+        }
+    }
+
+    fn provider(primary_error: &str) -> (FallbackProvider, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+        let (primary, primary_calls) = MockProvider::new("primary", Some(primary_error));
+        let (fallback, fallback_calls) = MockProvider::new("fallback", None);
+        let provider = FallbackProvider::new(Box::new(primary), Box::new(fallback))
+            .unwrap()
+            .with_delay(Duration::ZERO);
+        (provider, primary_calls, fallback_calls)
+    }
+
+    #[test]
+    fn test_is_retryable_error() {
+        let retryable = [
+            "Anthropic API error 429 Too Many Requests: slow down",
+            "Rate limit exceeded",
+            "OpenAI API error 503 Service Unavailable",
+            "Gemini API error 503: backend busy",
+            "upstream returned HTTP/1.1 429",
+            "request failed with status: 503",
+            "Anthropic API error 529: {\"type\":\"overloaded_error\"}",
+            "Anthropic API error 400 Bad Request: prompt is too long: 210000 tokens",
+            "This model's maximum context length is 128000 tokens",
+        ];
+        for message in retryable {
+            assert!(is_retryable_error(&anyhow::anyhow!(message)), "{}", message);
+        }
+
+        let not_retryable = [
+            "Anthropic API error 400 Bad Request: messages: field required",
+            "OpenAI API error 401 Unauthorized: invalid api key",
+            "Anthropic API error 403 Forbidden",
+            "Anthropic API error 400 Bad Request: request_id req_4290503 invalid",
+            "OpenAI API error 400 Bad Request: max_tokens is 429 but input has 503 tokens",
+            "OpenAI API error 401 Unauthorized: body was 5031 bytes, id 429",
+            "Failed to parse response",
+        ];
+        for message in not_retryable {
+            assert!(
+                !is_retryable_error(&anyhow::anyhow!(message)),
+                "{}",
+                message
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retryable_error_uses_fallback() {
+        let (provider, primary_calls, fallback_calls) =
+            provider("Anthropic API error 429 Too Many Requests: rate limited");
+
+        let response = provider.complete(request()).await.unwrap();
+        assert_eq!(response.content, "from fallback");
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(fallback_calls.load(Ordering::SeqCst), 1);
+
+        assert!(provider.stream(request()).await.is_ok());
+        assert_eq!(fallback_calls.load(Ordering::SeqCst), 2);
+
+        // The wrapper stands in for the primary but names the model it used
+        assert_eq!(provider.name(), "primary");
+        assert_eq!(provider.model(), "fallback-model");
+        assert_eq!(provider.fallback().name(), "fallback");
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_error_propagates() {
+        let (provider, primary_calls, fallback_calls) =
+            provider("OpenAI API error 401 Unauthorized: invalid api key");
+
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(err.to_string().contains("401"));
+        assert!(provider.stream(request()).await.is_err());
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 2);
+        assert_eq!(fallback_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_primary_success_skips_fallback() {
+        let (primary, _) = MockProvider::new("primary", None);
+        let (fallback, fallback_calls) = MockProvider::new("fallback", None);
+        let provider = FallbackProvider::new(Box::new(primary), Box::new(fallback)).unwrap();
+
+        let response = provider.complete(request()).await.unwrap();
+        assert_eq!(response.content, "from primary");
+        assert_eq!(provider.model(), "primary-model");
+        assert_eq!(fallback_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_mismatched_tool_calling_is_refused() {
+        let (primary, _) = MockProvider::new("primary", None);
+        let (mut fallback, _) = MockProvider::new("fallback", None);
+        fallback.native_tools = false;
+
+        let err = FallbackProvider::new(Box::new(primary), Box::new(fallback))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Cannot fall back from primary (native tool calls) to fallback (text tool calls)"
+        );
+    }
+}
@@ -0,0 +1,771 @@
+//! Google Gemini provider.
+//!
+//! Talks to the Google AI Gemini API (`generativelanguage.googleapis.com`).
+//! Streaming uses `:streamGenerateContent?alt=sse`, where every SSE event is a
+//! complete `GenerateContentResponse` holding the next piece of the candidate.
+//! Tool calls arrive whole as `functionCall` parts, so no argument
+//! accumulation is needed.
+//!
+//! Gemini calls the assistant role `"model"` and takes system messages as a
+//! separate `systemInstruction`, so both are mapped when building requests.
+//!
+//! The API key comes from the provider config, or from the `GEMINI_API_KEY`
+//! environment variable when the config leaves it empty.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use g3_providers::{GeminiProvider, LLMProvider, CompletionRequest, Message, MessageRole};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let provider = GeminiProvider::new(
+//!     String::new(), // read from GEMINI_API_KEY
+//!     "gemini-2.5-pro".to_string(),
+//!     None,
+//!     Some(8192),
+//!     Some(0.1),
+//! )?;
+//!
+//! let request = CompletionRequest {
+//!     messages: vec![Message::new(MessageRole::User, "Hello!".to_string())],
+//!     max_tokens: None,
+//!     temperature: None,
+//!     stream: false,
+//!     tools: None,
+//!     disable_thinking: false,
+//! };
+//! let response = provider.complete(request).await?;
+//! println!("{}", response.content);
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::stream::StreamExt;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, error};
+
+use crate::{
+    streaming::{decode_utf8_streaming, make_final_chunk, make_text_chunk, make_tool_chunk},
+    CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream, LLMProvider, Message,
+    MessageRole, Tool, ToolCall, Usage,
+};
+
+const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// Environment variable read when the config has no `api_key`.
+pub const API_KEY_ENV_VAR: &str = "GEMINI_API_KEY";
+
+/// Finish reasons that mean Gemini withheld the response rather than ending it normally.
+const BLOCKED_FINISH_REASONS: &[&str] = &[
+    "SAFETY",
+    "RECITATION",
+    "BLOCKLIST",
+    "PROHIBITED_CONTENT",
+    "SPII",
+    "MALFORMED_FUNCTION_CALL",
+];
+
+/// One entry of Gemini's `safetySettings`, e.g. category
+/// `HARM_CATEGORY_DANGEROUS_CONTENT` with threshold `BLOCK_ONLY_HIGH`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SafetySetting {
+    pub category: String,
+    pub threshold: String,
+}
+
+#[derive(Clone)]
+pub struct GeminiProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+    base_url: String,
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
+    safety_settings: Vec<SafetySetting>,
+    /// Extra `generationConfig` fields (topP, topK, stopSequences, ...)
+    generation_config: serde_json::Map<String, serde_json::Value>,
+    name: String,
+}
+
+impl GeminiProvider {
+    pub fn new(
+        api_key: String,
+        model: String,
+        base_url: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> Result<Self> {
+        Self::new_with_name(
+            "gemini".to_string(),
+            api_key,
+            model,
+            base_url,
+            max_tokens,
+            temperature,
+        )
+    }
+
+    pub fn new_with_name(
+        name: String,
+        api_key: String,
+        model: String,
+        base_url: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> Result<Self> {
+        let api_key = if api_key.is_empty() {
+            std::env::var(API_KEY_ENV_VAR).map_err(|_| {
+                anyhow::anyhow!(
+                    "No API key for Gemini provider '{}': set api_key in the config or {}",
+                    name,
+                    API_KEY_ENV_VAR
+                )
+            })?
+        } else {
+            api_key
+        };
+        let base_url = base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+
+        Ok(Self {
+            client: Client::new(),
+            api_key,
+            model,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            max_tokens,
+            temperature,
+            safety_settings: Vec::new(),
+            generation_config: serde_json::Map::new(),
+            name,
+        })
+    }
+
+    pub fn with_safety_settings(mut self, safety_settings: Vec<SafetySetting>) -> Self {
+        self.safety_settings = safety_settings;
+        self
+    }
+
+    /// Extra `generationConfig` fields sent with every request. `maxOutputTokens`
+    /// and `temperature` from the provider config or request take precedence.
+    pub fn with_generation_config(
+        mut self,
+        generation_config: serde_json::Map<String, serde_json::Value>,
+    ) -> Self {
+        self.generation_config = generation_config;
+        self
+    }
+
+    fn endpoint(&self, stream: bool) -> String {
+        if stream {
+            format!(
+                "{}/models/{}:streamGenerateContent?alt=sse",
+                self.base_url, self.model
+            )
+        } else {
+            format!("{}/models/{}:generateContent", self.base_url, self.model)
+        }
+    }
+
+    fn create_request_body(
+        &self,
+        messages: &[Message],
+        tools: Option<&[Tool]>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> serde_json::Value {
+        let (system, contents) = convert_messages(messages);
+        let mut body = json!({ "contents": contents });
+
+        if let Some(system) = system {
+            body["systemInstruction"] = json!({ "parts": [{ "text": system }] });
+        }
+
+        if let Some(tools) = tools {
+            if !tools.is_empty() {
+                body["tools"] = json!([{ "functionDeclarations": convert_tools(tools) }]);
+            }
+        }
+
+        let mut generation_config = self.generation_config.clone();
+        if let Some(max_tokens) = max_tokens.or(self.max_tokens) {
+            generation_config.insert("maxOutputTokens".to_string(), json!(max_tokens));
+        }
+        if let Some(temperature) = temperature.or(self.temperature) {
+            generation_config.insert("temperature".to_string(), json!(temperature));
+        }
+        if !generation_config.is_empty() {
+            body["generationConfig"] = serde_json::Value::Object(generation_config);
+        }
+
+        if !self.safety_settings.is_empty() {
+            body["safetySettings"] = json!(self.safety_settings);
+        }
+
+        body
+    }
+
+    async fn post(&self, body: &serde_json::Value, stream: bool) -> Result<reqwest::Response> {
+        let response = self
+            .client
+            .post(self.endpoint(stream))
+            .header("x-goog-api-key", &self.api_key)
+            .json(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(anyhow::anyhow!("Gemini API error {}: {}", status, error_text));
+        }
+        Ok(response)
+    }
+}
+
+/// Forward a `streamGenerateContent` SSE stream to `tx` as completion chunks.
+/// Returns the usage reported by the last event that carried `usageMetadata`.
+async fn parse_streaming_response(
+    mut stream: impl futures_util::Stream<Item = reqwest::Result<Bytes>> + Unpin,
+    tx: mpsc::Sender<Result<CompletionChunk>>,
+) -> Option<Usage> {
+    let mut byte_buffer = Vec::new();
+    let mut buffer = String::new();
+    let mut usage: Option<Usage> = None;
+    let mut tool_call_count = 0;
+
+    while let Some(chunk_result) = stream.next().await {
+        let chunk = match chunk_result {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                error!("Stream error: {}", e);
+                let _ = tx.send(Err(anyhow::anyhow!("Stream error: {}", e))).await;
+                return usage;
+            }
+        };
+
+        byte_buffer.extend_from_slice(&chunk);
+        if let Some(text) = decode_utf8_streaming(&mut byte_buffer) {
+            buffer.push_str(&text);
+        }
+
+        while let Some(line_end) = buffer.find('\n') {
+            let line = buffer[..line_end].trim().to_string();
+            buffer.drain(..line_end + 1);
+
+            let Some(data) = line.strip_prefix("data:").map(str::trim_start) else {
+                continue;
+            };
+
+            let response: GeminiResponse = match serde_json::from_str(data) {
+                Ok(response) => response,
+                Err(e) => {
+                    debug!("Failed to parse stream event: {} - Data: {}", e, data);
+                    continue;
+                }
+            };
+
+            if let Some(err) = response.blocked_or_failed() {
+                let _ = tx.send(Err(err)).await;
+                return usage;
+            }
+            if let Some(reported) = response.usage() {
+                usage = Some(reported);
+            }
+
+            let text = response.text();
+            if !text.is_empty() && tx.send(Ok(make_text_chunk(text))).await.is_err() {
+                debug!("Receiver dropped, stopping stream");
+                return usage;
+            }
+
+            let tool_calls = response.tool_calls(&mut tool_call_count);
+            if !tool_calls.is_empty() && tx.send(Ok(make_tool_chunk(tool_calls))).await.is_err() {
+                debug!("Receiver dropped, stopping stream");
+                return usage;
+            }
+        }
+    }
+
+    // Tool calls were already sent as they arrived, so the final chunk only carries usage
+    let _ = tx.send(Ok(make_final_chunk(Vec::new(), usage.clone()))).await;
+    usage
+}
+
+#[async_trait]
+impl LLMProvider for GeminiProvider {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
+        debug!(
+            "Processing Gemini completion request with {} messages",
+            request.messages.len()
+        );
+
+        let body = self.create_request_body(
+            &request.messages,
+            request.tools.as_deref(),
+            request.max_tokens,
+            request.temperature,
+        );
+        let response: GeminiResponse = self.post(&body, false).await?.json().await?;
+        if let Some(err) = response.blocked_or_failed() {
+            return Err(err);
+        }
+
+        Ok(CompletionResponse {
+            content: response.text(),
+            usage: response.usage().unwrap_or_default(),
+            model: self.model.clone(),
+        })
+    }
+
+    async fn stream(&self, request: CompletionRequest) -> Result<CompletionStream> {
+        debug!(
+            "Processing Gemini streaming request with {} messages",
+            request.messages.len()
+        );
+
+        let body = self.create_request_body(
+            &request.messages,
+            request.tools.as_deref(),
+            request.max_tokens,
+            request.temperature,
+        );
+        let response = self.post(&body, true).await?;
+
+        let stream = response.bytes_stream();
+        let (tx, rx) = mpsc::channel(100);
+        tokio::spawn(async move {
+            if let Some(usage) = parse_streaming_response(stream, tx).await {
+                debug!(
+                    "Stream completed with usage - prompt: {}, completion: {}, total: {}",
+                    usage.prompt_tokens, usage.completion_tokens, usage.total_tokens
+                );
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn has_native_tool_calling(&self) -> bool {
+        true
+    }
+
+    fn max_tokens(&self) -> u32 {
+        self.max_tokens.unwrap_or(8192)
+    }
+
+    fn temperature(&self) -> f32 {
+        self.temperature.unwrap_or(0.1)
+    }
+}
+
+/// Split `messages` into the system instruction and Gemini `contents`.
+/// Consecutive messages with the same role are merged into one content, since
+/// Gemini expects user and model turns to alternate.
+fn convert_messages(messages: &[Message]) -> (Option<String>, Vec<serde_json::Value>) {
+    let mut system: Option<String> = None;
+    let mut contents: Vec<serde_json::Value> = Vec::new();
+
+    for message in messages {
+        let role = match message.role {
+            MessageRole::System => {
+                system = Some(match system {
+                    Some(existing) => format!("{}\n\n{}", existing, message.content),
+                    None => message.content.clone(),
+                });
+                continue;
+            }
+            MessageRole::User => "user",
+            MessageRole::Assistant => "model",
+        };
+
+        let mut parts: Vec<serde_json::Value> = message
+            .images
+            .iter()
+            .map(|image| {
+                json!({ "inlineData": { "mimeType": image.media_type, "data": image.data } })
+            })
+            .collect();
+        if !message.content.is_empty() {
+            parts.push(json!({ "text": message.content }));
+        }
+        if parts.is_empty() {
+            continue;
+        }
+
+        match contents.last_mut() {
+            Some(last) if last["role"] == role => {
+                if let Some(existing) = last["parts"].as_array_mut() {
+                    existing.extend(parts);
+                }
+            }
+            _ => contents.push(json!({ "role": role, "parts": parts })),
+        }
+    }
+
+    (system, contents)
+}
+
+fn convert_tools(tools: &[Tool]) -> Vec<serde_json::Value> {
+    tools
+        .iter()
+        .map(|tool| {
+            let mut parameters = tool.input_schema.clone();
+            strip_unsupported_schema_fields(&mut parameters);
+            json!({
+                "name": tool.name,
+                "description": tool.description,
+                "parameters": parameters,
+            })
+        })
+        .collect()
+}
+
+/// Gemini accepts an OpenAPI subset of JSON Schema and rejects requests that
+/// use keywords outside it.
+fn strip_unsupported_schema_fields(schema: &mut serde_json::Value) {
+    match schema {
+        serde_json::Value::Object(map) => {
+            map.remove("$schema");
+            map.remove("additionalProperties");
+            for value in map.values_mut() {
+                strip_unsupported_schema_fields(value);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                strip_unsupported_schema_fields(item);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Gemini API response structures
+
+/// A `GenerateContentResponse`; each streamed SSE event has the same shape.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<GeminiCandidate>,
+    prompt_feedback: Option<GeminiPromptFeedback>,
+    usage_metadata: Option<GeminiUsageMetadata>,
+    error: Option<GeminiError>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiCandidate {
+    content: Option<GeminiContent>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiContent {
+    #[serde(default)]
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPart {
+    text: Option<String>,
+    /// Set on thinking-model parts that summarize the model's reasoning
+    #[serde(default)]
+    thought: bool,
+    function_call: Option<GeminiFunctionCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiFunctionCall {
+    id: Option<String>,
+    name: String,
+    #[serde(default)]
+    args: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPromptFeedback {
+    block_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsageMetadata {
+    #[serde(default)]
+    prompt_token_count: u32,
+    #[serde(default)]
+    candidates_token_count: u32,
+    #[serde(default)]
+    cached_content_token_count: u32,
+    #[serde(default)]
+    thoughts_token_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiError {
+    #[serde(default)]
+    code: u16,
+    #[serde(default)]
+    message: String,
+}
+
+impl GeminiResponse {
+    fn parts(&self) -> impl Iterator<Item = &GeminiPart> {
+        self.candidates
+            .iter()
+            .take(1)
+            .filter_map(|candidate| candidate.content.as_ref())
+            .flat_map(|content| content.parts.iter())
+    }
+
+    /// Visible text of the first candidate; thought summaries are skipped.
+    fn text(&self) -> String {
+        self.parts()
+            .filter(|part| !part.thought)
+            .filter_map(|part| part.text.as_deref())
+            .collect()
+    }
+
+    /// Function calls of the first candidate. Gemini does not always assign
+    /// call ids, so missing ones are numbered with `counter`.
+    fn tool_calls(&self, counter: &mut usize) -> Vec<ToolCall> {
+        self.parts()
+            .filter_map(|part| part.function_call.as_ref())
+            .map(|call| {
+                *counter += 1;
+                ToolCall {
+                    id: call
+                        .id
+                        .clone()
+                        .unwrap_or_else(|| format!("gemini_call_{}", counter)),
+                    tool: call.name.clone(),
+                    args: if call.args.is_null() {
+                        json!({})
+                    } else {
+                        call.args.clone()
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// An API error, a blocked prompt, or a candidate withheld by a safety filter.
+    fn blocked_or_failed(&self) -> Option<anyhow::Error> {
+        if let Some(error) = &self.error {
+            return Some(anyhow::anyhow!(
+                "Gemini API error {}: {}",
+                error.code,
+                error.message
+            ));
+        }
+        if let Some(reason) = self
+            .prompt_feedback
+            .as_ref()
+            .and_then(|feedback| feedback.block_reason.as_deref())
+        {
+            return Some(anyhow::anyhow!("Gemini blocked the prompt: {}", reason));
+        }
+        self.candidates
+            .first()
+            .and_then(|candidate| candidate.finish_reason.as_deref())
+            .filter(|reason| BLOCKED_FINISH_REASONS.contains(reason))
+            .map(|reason| anyhow::anyhow!("Gemini stopped the response: {}", reason))
+    }
+
+    /// Token counts, with cached prompt tokens reported separately from
+    /// the rest of the prompt and thinking tokens counted as output.
+    fn usage(&self) -> Option<Usage> {
+        let metadata = self.usage_metadata.as_ref()?;
+        let cached = metadata.cached_content_token_count;
+        let prompt_tokens = metadata.prompt_token_count.saturating_sub(cached);
+        let completion_tokens = metadata.candidates_token_count + metadata.thoughts_token_count;
+        Some(Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: metadata.prompt_token_count + completion_tokens,
+            cache_read_tokens: cached,
+            cache_write_tokens: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A streamed response from gemini-2.5-flash: text, then a function call.
+    const TOOL_CALL_FIXTURE: &str = include_str!("../tests/fixtures/gemini_tool_call_stream.sse");
+
+    /// A streamed response whose candidate is withheld by the safety filter.
+    const SAFETY_FIXTURE: &str = include_str!("../tests/fixtures/gemini_safety_stream.sse");
+
+    async fn collect_chunks(fixture: &str, chunk_size: usize) -> Vec<Result<CompletionChunk>> {
+        let pieces: Vec<reqwest::Result<Bytes>> = fixture
+            .as_bytes()
+            .chunks(chunk_size)
+            .map(|c| Ok(Bytes::copy_from_slice(c)))
+            .collect();
+        let (tx, mut rx) = mpsc::channel(100);
+        parse_streaming_response(futures_util::stream::iter(pieces), tx).await;
+
+        let mut chunks = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            chunks.push(chunk);
+        }
+        chunks
+    }
+
+    fn provider() -> GeminiProvider {
+        GeminiProvider::new(
+            "test-key".to_string(),
+            "gemini-2.5-flash".to_string(),
+            None,
+            Some(2048),
+            Some(0.2),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_parse_recorded_tool_call_stream() {
+        // Small pieces split events and the multi-byte characters across reads
+        for chunk_size in [1, 9, 4096] {
+            let chunks: Vec<CompletionChunk> = collect_chunks(TOOL_CALL_FIXTURE, chunk_size)
+                .await
+                .into_iter()
+                .map(|c| c.unwrap())
+                .collect();
+
+            let text: String = chunks.iter().map(|c| c.content.as_str()).collect();
+            assert_eq!(text, "I’ll list the files in the current directory.");
+
+            let tool_calls: Vec<&ToolCall> = chunks
+                .iter()
+                .filter_map(|c| c.tool_calls.as_ref())
+                .flatten()
+                .collect();
+            assert_eq!(tool_calls.len(), 1);
+            assert_eq!(tool_calls[0].tool, "shell");
+            assert_eq!(tool_calls[0].args, json!({ "command": "ls -la" }));
+            assert_eq!(tool_calls[0].id, "gemini_call_1");
+
+            let last = chunks.last().unwrap();
+            assert!(last.finished);
+            assert!(last.tool_calls.is_none());
+            let usage = last.usage.as_ref().unwrap();
+            assert_eq!(usage.prompt_tokens, 1012);
+            assert_eq!(usage.cache_read_tokens, 512);
+            assert_eq!(usage.completion_tokens, 48);
+            assert_eq!(usage.total_tokens, 1572);
+            assert_eq!(chunks.iter().filter(|c| c.finished).count(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_safety_block_is_an_error() {
+        let chunks = collect_chunks(SAFETY_FIXTURE, 4096).await;
+        let err = chunks.last().unwrap().as_ref().unwrap_err();
+        assert!(err.to_string().contains("SAFETY"), "{}", err);
+    }
+
+    #[test]
+    fn test_messages_map_roles_and_system_instruction() {
+        let messages = vec![
+            Message::new(MessageRole::System, "Be terse.".to_string()),
+            Message::new(MessageRole::User, "hi".to_string()),
+            Message::new(MessageRole::Assistant, "hello".to_string()),
+            Message::new(MessageRole::User, "Tool result: ok".to_string()),
+            Message::new(MessageRole::User, "continue".to_string()),
+        ];
+        let tools = vec![Tool {
+            name: "shell".to_string(),
+            description: "Run a command".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "command": { "type": "string" } },
+                "required": ["command"],
+                "additionalProperties": false
+            }),
+        }];
+        let body = provider().create_request_body(&messages, Some(&tools), None, None);
+
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "Be terse.");
+        let contents = body["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[0]["role"], "user");
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(contents[1]["parts"][0]["text"], "hello");
+        assert_eq!(contents[2]["parts"].as_array().unwrap().len(), 2);
+
+        let declaration = &body["tools"][0]["functionDeclarations"][0];
+        assert_eq!(declaration["name"], "shell");
+        assert_eq!(declaration["parameters"]["required"][0], "command");
+        assert!(declaration["parameters"].get("additionalProperties").is_none());
+    }
+
+    #[test]
+    fn test_generation_config_and_safety_settings() {
+        let mut generation_config = serde_json::Map::new();
+        generation_config.insert("topP".to_string(), json!(0.9));
+        generation_config.insert("maxOutputTokens".to_string(), json!(1));
+        let provider = provider()
+            .with_generation_config(generation_config)
+            .with_safety_settings(vec![SafetySetting {
+                category: "HARM_CATEGORY_DANGEROUS_CONTENT".to_string(),
+                threshold: "BLOCK_ONLY_HIGH".to_string(),
+            }]);
+
+        let messages = vec![Message::new(MessageRole::User, "hi".to_string())];
+        let body = provider.create_request_body(&messages, None, None, Some(0.5));
+
+        assert_eq!(body["generationConfig"]["topP"], 0.9);
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 2048);
+        assert_eq!(body["generationConfig"]["temperature"], 0.5);
+        assert_eq!(
+            body["safetySettings"],
+            json!([{ "category": "HARM_CATEGORY_DANGEROUS_CONTENT", "threshold": "BLOCK_ONLY_HIGH" }])
+        );
+        assert!(body.get("tools").is_none());
+        assert_eq!(
+            provider.endpoint(true),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse"
+        );
+    }
+
+    #[test]
+    fn test_non_streaming_response_skips_thoughts() {
+        let response: GeminiResponse = serde_json::from_str(
+            r#"{"candidates":[{"content":{"role":"model","parts":[
+                {"text":"Thinking about it","thought":true},
+                {"text":"Answer: 4"}
+            ]},"finishReason":"STOP"}],
+            "usageMetadata":{"promptTokenCount":10,"candidatesTokenCount":3,"thoughtsTokenCount":20,"totalTokenCount":33}}"#,
+        )
+        .unwrap();
+
+        assert!(response.blocked_or_failed().is_none());
+        assert_eq!(response.text(), "Answer: 4");
+        let usage = response.usage().unwrap();
+        assert_eq!(usage.completion_tokens, 23);
+        assert_eq!(usage.total_tokens, 33);
+    }
+}
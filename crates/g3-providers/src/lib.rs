@@ -1,10 +1,13 @@
 mod streaming;
 pub use streaming::{decode_utf8_streaming, is_incomplete_json_error, make_final_chunk, make_text_chunk, make_tool_chunk};
+mod tokens;
+pub use tokens::count_tokens;
 
 use anyhow::Result;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// Trait for LLM providers
 #[async_trait::async_trait]
@@ -49,12 +52,17 @@ pub struct CompletionRequest {
     pub disable_thinking: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Prompt cache marker with a time-to-live.
+///
+/// The TTL is stored as a plain `Duration`; each provider's wire format is
+/// produced on serialization (see [`CacheTtlFormat`]). The default `Serialize`
+/// impl emits the Anthropic format, which is what Anthropic and Databricks send.
+#[derive(Debug, Clone, PartialEq)]
 pub struct CacheControl {
-    #[serde(rename = "type")]
     pub cache_type: CacheType,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub ttl: Option<String>,
+    duration: Duration,
+    /// `false` for `ephemeral()`, which leaves the TTL to the provider default
+    explicit_ttl: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -63,26 +71,176 @@ pub enum CacheType {
     Ephemeral,
 }
 
+/// How a provider expects the cache TTL to be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheTtlFormat {
+    /// `"ttl": "5m"` or `"ttl": "1h"`; other durations are rounded
+    Anthropic,
+    /// `"ttl": <seconds>`
+    OpenAI,
+}
+
+const FIVE_MINUTES: Duration = Duration::from_secs(5 * 60);
+const ONE_HOUR: Duration = Duration::from_secs(60 * 60);
+
 impl CacheControl {
+    /// Cache with the provider's default TTL (5 minutes for Anthropic).
     pub fn ephemeral() -> Self {
         Self {
             cache_type: CacheType::Ephemeral,
-            ttl: None,
+            duration: FIVE_MINUTES,
+            explicit_ttl: false,
         }
     }
 
     pub fn five_minute() -> Self {
-        Self {
-            cache_type: CacheType::Ephemeral,
-            ttl: Some("5m".to_string()),
-        }
+        Self::for_duration(FIVE_MINUTES)
     }
 
     pub fn one_hour() -> Self {
+        Self::for_duration(ONE_HOUR)
+    }
+
+    /// Cache for an arbitrary duration. Providers that only support fixed
+    /// TTLs round it when serializing.
+    pub fn for_duration(duration: Duration) -> Self {
         Self {
             cache_type: CacheType::Ephemeral,
-            ttl: Some("1h".to_string()),
+            duration,
+            explicit_ttl: true,
+        }
+    }
+
+    /// Parse a `cache_config` setting: `ephemeral`, `5minute`, `1hour` or a
+    /// TTL accepted by [`CacheControl::parse_ttl`].
+    pub fn from_config(cache_config: &str) -> Option<Self> {
+        match cache_config {
+            "ephemeral" => Some(Self::ephemeral()),
+            "5minute" => Some(Self::five_minute()),
+            "1hour" => Some(Self::one_hour()),
+            other => Self::parse_ttl(other).map(Self::for_duration),
+        }
+    }
+
+    /// Parse a TTL such as `"30s"`, `"15m"` or `"2h"`.
+    pub fn parse_ttl(ttl: &str) -> Option<Duration> {
+        let ttl = ttl.trim();
+        let unit_at = ttl.find(|c: char| !c.is_ascii_digit())?;
+        let value: u64 = ttl[..unit_at].parse().ok()?;
+        let seconds = match &ttl[unit_at..] {
+            "s" => Some(value),
+            "m" => value.checked_mul(60),
+            "h" => value.checked_mul(60 * 60),
+            _ => None,
+        }?;
+        Some(Duration::from_secs(seconds))
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// The Anthropic TTL string, or `None` when the provider default applies.
+    /// Anthropic only accepts 5m and 1h, so other durations are rounded up
+    /// (capped at 1h), with a warning the first time.
+    pub fn anthropic_ttl(&self) -> Option<&'static str> {
+        if !self.explicit_ttl {
+            return None;
+        }
+        let (ttl, rounded_to) = if self.duration <= FIVE_MINUTES {
+            ("5m", FIVE_MINUTES)
+        } else {
+            ("1h", ONE_HOUR)
+        };
+        if self.duration != rounded_to {
+            static ROUNDING_WARNED: std::sync::Once = std::sync::Once::new();
+            let mut warned = false;
+            ROUNDING_WARNED.call_once(|| {
+                warned = true;
+                tracing::warn!(
+                    "Anthropic only supports 5m and 1h cache TTLs; rounding {}s to {}",
+                    self.duration.as_secs(),
+                    ttl
+                );
+            });
+            if !warned {
+                tracing::debug!("Rounding cache TTL of {}s to {}", self.duration.as_secs(), ttl);
+            }
+        }
+        Some(ttl)
+    }
+
+    /// Serialize for the given provider format.
+    pub fn to_value(&self, format: CacheTtlFormat) -> serde_json::Value {
+        let mut value = serde_json::json!({ "type": self.cache_type });
+        let ttl = match format {
+            CacheTtlFormat::Anthropic => self.anthropic_ttl().map(serde_json::Value::from),
+            CacheTtlFormat::OpenAI if self.explicit_ttl => {
+                Some(serde_json::Value::from(self.duration.as_secs()))
+            }
+            CacheTtlFormat::OpenAI => None,
+        };
+        if let Some(ttl) = ttl {
+            value["ttl"] = ttl;
         }
+        value
+    }
+}
+
+impl Serialize for CacheControl {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        let ttl = self.anthropic_ttl();
+        let mut map = serializer.serialize_map(Some(1 + ttl.is_some() as usize))?;
+        map.serialize_entry("type", &self.cache_type)?;
+        if let Some(ttl) = ttl {
+            map.serialize_entry("ttl", ttl)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for CacheControl {
+    /// Accepts both the Anthropic (`"15m"`) and OpenAI (seconds) TTL formats.
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Ttl {
+            Seconds(u64),
+            Text(String),
+        }
+
+        #[derive(Deserialize)]
+        struct Raw {
+            #[serde(rename = "type")]
+            cache_type: CacheType,
+            #[serde(default)]
+            ttl: Option<Ttl>,
+        }
+
+        let raw = Raw::deserialize(deserializer)?;
+        let mut cache_control = match raw.ttl {
+            None => Self::ephemeral(),
+            Some(Ttl::Seconds(secs)) => Self::for_duration(Duration::from_secs(secs)),
+            Some(Ttl::Text(text)) => match Self::parse_ttl(&text) {
+                Some(duration) => Self::for_duration(duration),
+                None => {
+                    return Err(serde::de::Error::custom(format!(
+                        "invalid cache ttl '{}'",
+                        text
+                    )))
+                }
+            },
+        };
+        cache_control.cache_type = raw.cache_type;
+        Ok(cache_control)
     }
 }
 
@@ -98,7 +256,7 @@ pub struct Message {
     pub cache_control: Option<CacheControl>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum MessageRole {
     System,
@@ -106,6 +264,26 @@ pub enum MessageRole {
     Assistant,
 }
 
+/// Roles are matched case-insensitively, since some providers and proxies
+/// return `"User"` or `"ASSISTANT"` instead of the lowercase form.
+impl<'de> Deserialize<'de> for MessageRole {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let role = String::deserialize(deserializer)?;
+        match role.to_ascii_lowercase().as_str() {
+            "system" => Ok(MessageRole::System),
+            "user" => Ok(MessageRole::User),
+            "assistant" => Ok(MessageRole::Assistant),
+            _ => Err(serde::de::Error::unknown_variant(
+                &role,
+                &["system", "user", "assistant"],
+            )),
+        }
+    }
+}
+
 /// Image content for multimodal messages
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageContent {
@@ -163,6 +341,17 @@ impl ImageContent {
 
         None
     }
+
+    /// Encode raw image bytes, detecting the media type from their signature.
+    /// Returns `None` for data that isn't a supported image format.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        use base64::Engine;
+        let media_type = Self::media_type_from_bytes(bytes)?;
+        Some(Self::new(
+            media_type,
+            base64::engine::general_purpose::STANDARD.encode(bytes),
+        ))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -172,11 +361,17 @@ pub struct CompletionResponse {
     pub model: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Usage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    /// Prompt tokens served from the provider's prompt cache, not counted in `prompt_tokens`
+    #[serde(default)]
+    pub cache_read_tokens: u32,
+    /// Prompt tokens written to the provider's prompt cache, not counted in `prompt_tokens`
+    #[serde(default)]
+    pub cache_write_tokens: u32,
 }
 
 pub type CompletionStream = tokio_stream::wrappers::ReceiverStream<Result<CompletionChunk>>;
@@ -204,15 +399,29 @@ pub struct Tool {
 }
 
 pub mod anthropic;
+pub mod cost;
 pub mod databricks;
 pub mod embedded;
+pub mod fallback;
+pub mod gemini;
 pub mod oauth;
+pub mod ollama;
 pub mod openai;
+pub mod openai_compatible;
+pub mod prompt_cache;
+pub mod response_validator;
 
 pub use anthropic::AnthropicProvider;
+pub use cost::{CostCalculator, ModelPricing, SessionCost, UsageStats};
 pub use databricks::DatabricksProvider;
 pub use embedded::EmbeddedProvider;
+pub use fallback::{is_retryable_error, FallbackProvider};
+pub use gemini::GeminiProvider;
+pub use ollama::OllamaProvider;
 pub use openai::OpenAIProvider;
+pub use openai_compatible::OpenAICompatibleProvider;
+pub use prompt_cache::{CacheHints, PromptCacheOptimizer};
+pub use response_validator::{ResponseCheck, ResponseValidator, StreamState};
 
 impl Message {
     /// Generate a unique message ID in format HHMMSS-XXX
@@ -269,7 +478,7 @@ impl Message {
         if !provider.supports_cache_control() {
             tracing::warn!(
                 "Cache control requested for provider '{}' which does not support it. \
-                Cache control is only supported by Anthropic and Anthropic via Databricks.",
+                Set cache_config for an Anthropic or OpenAI provider to enable it.",
                 provider.name()
             );
             return Self::new(role, content);
@@ -277,6 +486,7 @@ impl Message {
 
         Self::with_cache_control(role, content, cache_control)
     }
+
 }
 
 /// Provider registry for managing multiple LLM providers
@@ -318,6 +528,12 @@ impl ProviderRegistry {
             .ok_or_else(|| anyhow::anyhow!("Provider '{}' not found", name))
     }
 
+    /// Remove a provider from the registry and return it, e.g. to wrap it.
+    /// If it was the default, it must be registered again under the same name.
+    pub fn take(&mut self, provider_name: &str) -> Option<Box<dyn LLMProvider>> {
+        self.providers.remove(provider_name)
+    }
+
     pub fn list_providers(&self) -> Vec<&str> {
         self.providers.keys().map(|s| s.as_str()).collect()
     }
@@ -333,6 +549,18 @@ impl Default for ProviderRegistry {
 mod tests {
     use super::*;
 
+    const PNG_BYTES: [u8; 16] = [
+        0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D, 0x49, 0x48, 0x44, 0x52,
+    ];
+
+    #[test]
+    fn test_image_content_from_bytes() {
+        let image = ImageContent::from_bytes(&PNG_BYTES).unwrap();
+        assert_eq!(image.media_type, "image/png");
+        assert_eq!(image.data, "iVBORw0KGgoAAAANSUhEUg==");
+        assert!(ImageContent::from_bytes(b"plain text, not an image").is_none());
+    }
+
     #[test]
     fn test_message_serialization_without_cache_control() {
         let msg = Message::new(MessageRole::User, "Hello".to_string());
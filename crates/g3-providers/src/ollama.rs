@@ -0,0 +1,439 @@
+//! Ollama provider for self-hosted models.
+//!
+//! Talks to the Ollama chat API (`POST /api/chat`), which streams
+//! newline-delimited JSON rather than SSE. Tool calls are not sent natively:
+//! the agent falls back to JSON tool calls in the message text, which every
+//! model can produce.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use g3_providers::{OllamaProvider, LLMProvider, CompletionRequest, Message, MessageRole};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let provider = OllamaProvider::new(
+//!     "qwen2.5-coder:14b".to_string(),
+//!     None, // defaults to http://localhost:11434
+//!     Some(4096),
+//!     Some(0.1),
+//!     serde_json::Map::new(),
+//! )?;
+//!
+//! let request = CompletionRequest {
+//!     messages: vec![Message::new(MessageRole::User, "Hello!".to_string())],
+//!     max_tokens: None,
+//!     temperature: None,
+//!     stream: false,
+//!     tools: None,
+//!     disable_thinking: false,
+//! };
+//! let response = provider.complete(request).await?;
+//! println!("{}", response.content);
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::stream::StreamExt;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, error};
+
+use crate::{
+    streaming::{decode_utf8_streaming, make_final_chunk, make_text_chunk},
+    CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream, LLMProvider, Message,
+    MessageRole, Usage,
+};
+
+const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+#[derive(Clone)]
+pub struct OllamaProvider {
+    client: Client,
+    model: String,
+    base_url: String,
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
+    /// Extra model options (num_ctx, top_p, stop, ...)
+    options: serde_json::Map<String, serde_json::Value>,
+    name: String,
+}
+
+impl OllamaProvider {
+    pub fn new(
+        model: String,
+        base_url: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+        options: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Self> {
+        Self::new_with_name(
+            "ollama".to_string(),
+            model,
+            base_url,
+            max_tokens,
+            temperature,
+            options,
+        )
+    }
+
+    pub fn new_with_name(
+        name: String,
+        model: String,
+        base_url: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+        options: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Self> {
+        let base_url = base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        Ok(Self {
+            client: Client::new(),
+            model,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            max_tokens,
+            temperature,
+            options,
+            name,
+        })
+    }
+
+    fn create_request_body(
+        &self,
+        messages: &[Message],
+        stream: bool,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> serde_json::Value {
+        let mut options = self.options.clone();
+        if let Some(temperature) = temperature.or(self.temperature) {
+            options.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(max_tokens) = max_tokens.or(self.max_tokens) {
+            options.insert("num_predict".to_string(), json!(max_tokens));
+        }
+
+        let mut body = json!({
+            "model": self.model,
+            "messages": convert_messages(messages),
+            "stream": stream,
+        });
+        if !options.is_empty() {
+            body["options"] = serde_json::Value::Object(options);
+        }
+        body
+    }
+
+    async fn post_chat(&self, body: &serde_json::Value) -> Result<reqwest::Response> {
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(anyhow::anyhow!("Ollama API error {}: {}", status, error_text));
+        }
+        Ok(response)
+    }
+}
+
+/// Forward an NDJSON chat stream to `tx` as completion chunks.
+/// Returns the usage reported by the final (`"done": true`) line.
+async fn parse_streaming_response(
+    mut stream: impl futures_util::Stream<Item = reqwest::Result<Bytes>> + Unpin,
+    tx: mpsc::Sender<Result<CompletionChunk>>,
+) -> Option<Usage> {
+    let mut byte_buffer = Vec::new();
+    let mut buffer = String::new();
+
+    while let Some(chunk_result) = stream.next().await {
+        let chunk = match chunk_result {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                error!("Stream error: {}", e);
+                let _ = tx.send(Err(anyhow::anyhow!("Stream error: {}", e))).await;
+                return None;
+            }
+        };
+
+        byte_buffer.extend_from_slice(&chunk);
+        if let Some(text) = decode_utf8_streaming(&mut byte_buffer) {
+            buffer.push_str(&text);
+        }
+
+        while let Some(line_end) = buffer.find('\n') {
+            let line = buffer[..line_end].trim().to_string();
+            buffer.drain(..line_end + 1);
+            if line.is_empty() {
+                continue;
+            }
+
+            let chunk: OllamaChatChunk = match serde_json::from_str(&line) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    debug!("Failed to parse stream line: {} - Data: {}", e, line);
+                    continue;
+                }
+            };
+
+            if let Some(message) = chunk.error {
+                let _ = tx.send(Err(anyhow::anyhow!("Ollama error: {}", message))).await;
+                return None;
+            }
+
+            if let Some(message) = &chunk.message {
+                if !message.content.is_empty()
+                    && tx.send(Ok(make_text_chunk(message.content.clone()))).await.is_err()
+                {
+                    debug!("Receiver dropped, stopping stream");
+                    return None;
+                }
+            }
+
+            if chunk.done {
+                let usage = chunk.usage();
+                let _ = tx.send(Ok(make_final_chunk(Vec::new(), usage.clone()))).await;
+                return usage;
+            }
+        }
+    }
+
+    // Stream ended without a done line; still finish the response
+    let _ = tx.send(Ok(make_final_chunk(Vec::new(), None))).await;
+    None
+}
+
+#[async_trait]
+impl LLMProvider for OllamaProvider {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
+        debug!(
+            "Processing Ollama completion request with {} messages",
+            request.messages.len()
+        );
+
+        let body = self.create_request_body(
+            &request.messages,
+            false,
+            request.max_tokens,
+            request.temperature,
+        );
+        let response: OllamaChatChunk = self.post_chat(&body).await?.json().await?;
+        if let Some(message) = response.error {
+            return Err(anyhow::anyhow!("Ollama error: {}", message));
+        }
+
+        Ok(CompletionResponse {
+            content: response
+                .message
+                .as_ref()
+                .map(|m| m.content.clone())
+                .unwrap_or_default(),
+            usage: response.usage().unwrap_or(Usage {
+                prompt_tokens: 0,
+                completion_tokens: 0,
+                total_tokens: 0,
+                cache_read_tokens: 0,
+                cache_write_tokens: 0,
+            }),
+            model: self.model.clone(),
+        })
+    }
+
+    async fn stream(&self, request: CompletionRequest) -> Result<CompletionStream> {
+        debug!(
+            "Processing Ollama streaming request with {} messages",
+            request.messages.len()
+        );
+
+        let body = self.create_request_body(
+            &request.messages,
+            true,
+            request.max_tokens,
+            request.temperature,
+        );
+        let response = self.post_chat(&body).await?;
+
+        let stream = response.bytes_stream();
+        let (tx, rx) = mpsc::channel(100);
+        tokio::spawn(async move {
+            if let Some(usage) = parse_streaming_response(stream, tx).await {
+                debug!(
+                    "Stream completed with usage - prompt: {}, completion: {}, total: {}",
+                    usage.prompt_tokens, usage.completion_tokens, usage.total_tokens
+                );
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn max_tokens(&self) -> u32 {
+        self.max_tokens.unwrap_or(4096)
+    }
+
+    fn temperature(&self) -> f32 {
+        self.temperature.unwrap_or(0.1)
+    }
+}
+
+fn convert_messages(messages: &[Message]) -> Vec<serde_json::Value> {
+    messages
+        .iter()
+        .map(|msg| {
+            let mut message = json!({
+                "role": match msg.role {
+                    MessageRole::System => "system",
+                    MessageRole::User => "user",
+                    MessageRole::Assistant => "assistant",
+                },
+                "content": msg.content,
+            });
+            if !msg.images.is_empty() {
+                message["images"] = json!(msg
+                    .images
+                    .iter()
+                    .map(|image| image.data.clone())
+                    .collect::<Vec<_>>());
+            }
+            message
+        })
+        .collect()
+}
+
+// Ollama API response structures
+
+/// One line of a `/api/chat` response; the non-streaming response has the same shape.
+#[derive(Debug, Deserialize)]
+struct OllamaChatChunk {
+    message: Option<OllamaMessage>,
+    #[serde(default)]
+    done: bool,
+    prompt_eval_count: Option<u32>,
+    eval_count: Option<u32>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaMessage {
+    #[serde(default)]
+    content: String,
+}
+
+impl OllamaChatChunk {
+    fn usage(&self) -> Option<Usage> {
+        if self.prompt_eval_count.is_none() && self.eval_count.is_none() {
+            return None;
+        }
+        let prompt_tokens = self.prompt_eval_count.unwrap_or(0);
+        let completion_tokens = self.eval_count.unwrap_or(0);
+        Some(Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            cache_read_tokens: 0,
+            cache_write_tokens: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A streamed `/api/chat` response in the shape Ollama returns for llama3.2.
+    const CHAT_FIXTURE: &str = include_str!("../tests/fixtures/ollama_chat_stream.ndjson");
+
+    async fn collect_chunks(chunk_size: usize) -> Vec<CompletionChunk> {
+        let pieces: Vec<reqwest::Result<Bytes>> = CHAT_FIXTURE
+            .as_bytes()
+            .chunks(chunk_size)
+            .map(|c| Ok(Bytes::copy_from_slice(c)))
+            .collect();
+        let (tx, mut rx) = mpsc::channel(100);
+        parse_streaming_response(futures_util::stream::iter(pieces), tx).await;
+
+        let mut chunks = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            chunks.push(chunk.unwrap());
+        }
+        chunks
+    }
+
+    #[tokio::test]
+    async fn test_parse_recorded_stream() {
+        // Small pieces split lines and the multi-byte characters across reads
+        for chunk_size in [1, 7, 4096] {
+            let chunks = collect_chunks(chunk_size).await;
+            let text: String = chunks.iter().map(|c| c.content.as_str()).collect();
+            assert_eq!(
+                text,
+                "Sure — here it is:\n{\"tool\": \"shell\", \"args\": {\"command\": \"ls\"}}"
+            );
+
+            let last = chunks.last().unwrap();
+            assert!(last.finished);
+            assert!(last.tool_calls.is_none());
+            let usage = last.usage.as_ref().unwrap();
+            assert_eq!(usage.prompt_tokens, 26);
+            assert_eq!(usage.completion_tokens, 18);
+            assert_eq!(usage.total_tokens, 44);
+            assert_eq!(chunks.iter().filter(|c| c.finished).count(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_line() {
+        let pieces: Vec<reqwest::Result<Bytes>> =
+            vec![Ok(Bytes::from_static(b"{\"error\":\"model 'nope' not found\"}\n"))];
+        let (tx, mut rx) = mpsc::channel(10);
+        parse_streaming_response(futures_util::stream::iter(pieces), tx).await;
+
+        let err = rx.recv().await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("model 'nope' not found"));
+    }
+
+    #[test]
+    fn test_request_body_merges_options() {
+        let mut options = serde_json::Map::new();
+        options.insert("num_ctx".to_string(), json!(8192));
+        let provider = OllamaProvider::new(
+            "llama3.2".to_string(),
+            Some("http://gpu-box:11434/".to_string()),
+            Some(1024),
+            Some(0.2),
+            options,
+        )
+        .unwrap();
+
+        let messages = vec![Message::new(MessageRole::User, "hi".to_string())];
+        let body = provider.create_request_body(&messages, true, None, Some(0.5));
+
+        assert_eq!(provider.base_url, "http://gpu-box:11434");
+        assert_eq!(body["model"], "llama3.2");
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["messages"][0]["role"], "user");
+        assert_eq!(body["options"]["num_ctx"], 8192);
+        assert_eq!(body["options"]["num_predict"], 1024);
+        assert_eq!(body["options"]["temperature"], 0.5);
+        assert!(!provider.has_native_tool_calling());
+    }
+}
@@ -2,16 +2,17 @@ use anyhow::Result;
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures_util::stream::StreamExt;
-use reqwest::Client;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, RequestBuilder};
 use serde::Deserialize;
 use serde_json::json;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
 use crate::{
-    CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream, LLMProvider, Message,
-    MessageRole, Tool, ToolCall, Usage,
+    CacheTtlFormat, CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream,
+    LLMProvider, Message, MessageRole, Tool, ToolCall, Usage,
     streaming::{make_text_chunk, make_final_chunk},
 };
 
@@ -21,11 +22,22 @@ pub struct OpenAIProvider {
     api_key: String,
     model: String,
     base_url: String,
+    /// Path appended to `base_url` for chat completions.
+    chat_path: String,
+    extra_headers: HeaderMap,
     max_tokens: Option<u32>,
     _temperature: Option<f32>,
     name: String,
+    /// Whether `cache_control` markers on messages are sent
+    cache_control: bool,
 }
 
+/// API root used when no `base_url` is configured.
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Default chat completions endpoint, relative to the base URL.
+pub const DEFAULT_CHAT_PATH: &str = "/chat/completions";
+
 impl OpenAIProvider {
     pub fn new(
         api_key: String,
@@ -56,13 +68,51 @@ impl OpenAIProvider {
             client: Client::new(),
             api_key,
             model: model.unwrap_or_else(|| "gpt-4o".to_string()),
-            base_url: base_url.unwrap_or_else(|| "https://api.openai.com/v1".to_string()),
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+            chat_path: DEFAULT_CHAT_PATH.to_string(),
+            extra_headers: HeaderMap::new(),
             max_tokens,
             _temperature: temperature,
             name,
+            cache_control: false,
         })
     }
 
+    /// Send the `cache_control` of cached messages, with the TTL in seconds.
+    pub fn with_cache_control(mut self, enabled: bool) -> Self {
+        self.cache_control = enabled;
+        self
+    }
+
+    /// Use `path` instead of `/chat/completions` for requests.
+    pub fn with_chat_path(mut self, path: impl Into<String>) -> Self {
+        self.chat_path = path.into();
+        self
+    }
+
+    /// Send these headers with every request.
+    pub fn with_extra_headers(mut self, headers: HeaderMap) -> Self {
+        self.extra_headers = headers;
+        self
+    }
+
+    pub fn chat_url(&self) -> String {
+        format!("{}{}", self.base_url, self.chat_path)
+    }
+
+    /// Build a POST to the chat endpoint. No `Authorization` header is sent
+    /// when the API key is empty, as local servers often need none.
+    fn post_chat(&self) -> RequestBuilder {
+        let mut request = self
+            .client
+            .post(self.chat_url())
+            .headers(self.extra_headers.clone());
+        if !self.api_key.is_empty() {
+            request = request.header("Authorization", format!("Bearer {}", self.api_key));
+        }
+        request
+    }
+
     fn create_request_body(
         &self,
         messages: &[Message],
@@ -73,7 +123,7 @@ impl OpenAIProvider {
     ) -> serde_json::Value {
         let mut body = json!({
             "model": self.model,
-            "messages": convert_messages(messages),
+            "messages": convert_messages(messages, self.cache_control),
             "stream": stream,
         });
 
@@ -109,7 +159,7 @@ impl OpenAIProvider {
         let mut buffer = String::new();
         let mut accumulated_content = String::new();
         let mut accumulated_usage: Option<Usage> = None;
-        let mut current_tool_calls: Vec<OpenAIStreamingToolCall> = Vec::new();
+        let mut current_tool_calls = OpenAIToolCallAccumulator::default();
 
         while let Some(chunk_result) = stream.next().await {
             match chunk_result {
@@ -134,22 +184,18 @@ impl OpenAIProvider {
                         }
 
                         // Parse Server-Sent Events format
-                        if let Some(data) = line.strip_prefix("data: ") {
+                        if let Some(data) = line.strip_prefix("data:").map(str::trim_start) {
                             if data == "[DONE]" {
                                 debug!("Received stream completion marker");
 
                                 // Send final chunk with accumulated content and tool calls
                                 if !accumulated_content.is_empty() || !current_tool_calls.is_empty()
                                 {
-                                    let tool_calls = if current_tool_calls.is_empty() {
+                                    let tool_calls = current_tool_calls.finish();
+                                    let tool_calls = if tool_calls.is_empty() {
                                         None
                                     } else {
-                                        Some(
-                                            current_tool_calls
-                                                .iter()
-                                                .filter_map(|tc| tc.to_tool_call())
-                                                .collect(),
-                                        )
+                                        Some(tool_calls)
                                     };
 
                                     let final_chunk = CompletionChunk {
@@ -182,32 +228,7 @@ impl OpenAIProvider {
                                         // Handle tool calls
                                         if let Some(delta_tool_calls) = &choice.delta.tool_calls {
                                             for delta_tool_call in delta_tool_calls {
-                                                if let Some(index) = delta_tool_call.index {
-                                                    // Ensure we have enough tool calls in our vector
-                                                    while current_tool_calls.len() <= index {
-                                                        current_tool_calls.push(
-                                                            OpenAIStreamingToolCall::default(),
-                                                        );
-                                                    }
-
-                                                    let tool_call = &mut current_tool_calls[index];
-
-                                                    if let Some(id) = &delta_tool_call.id {
-                                                        tool_call.id = Some(id.clone());
-                                                    }
-
-                                                    if let Some(function) =
-                                                        &delta_tool_call.function
-                                                    {
-                                                        if let Some(name) = &function.name {
-                                                            tool_call.name = Some(name.clone());
-                                                        }
-                                                        if let Some(arguments) = &function.arguments
-                                                        {
-                                                            tool_call.arguments.push_str(arguments);
-                                                        }
-                                                    }
-                                                }
+                                                current_tool_calls.apply_delta(delta_tool_call);
                                             }
                                         }
                                     }
@@ -218,6 +239,8 @@ impl OpenAIProvider {
                                             prompt_tokens: usage.prompt_tokens,
                                             completion_tokens: usage.completion_tokens,
                                             total_tokens: usage.total_tokens,
+                                            cache_read_tokens: 0,
+                                            cache_write_tokens: 0,
                                         });
                                     }
                                 }
@@ -237,14 +260,7 @@ impl OpenAIProvider {
         }
 
         // Send final chunk if we haven't already
-        let tool_calls = if current_tool_calls.is_empty() {
-            vec![]
-        } else {
-            current_tool_calls
-                .iter()
-                .filter_map(|tc| tc.to_tool_call())
-                .collect()
-        };
+        let tool_calls = current_tool_calls.finish();
 
         let final_chunk = make_final_chunk(tool_calls, accumulated_usage.clone());
         let _ = tx.send(Ok(final_chunk)).await;
@@ -271,13 +287,7 @@ impl LLMProvider for OpenAIProvider {
 
         debug!("Sending request to OpenAI API: model={}", self.model);
 
-        let response = self
-            .client
-            .post(format!("{}/chat/completions", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&body)
-            .send()
-            .await?;
+        let response = self.post_chat().json(&body).send().await?;
 
         let status = response.status();
         if !status.is_success() {
@@ -304,6 +314,8 @@ impl LLMProvider for OpenAIProvider {
             prompt_tokens: openai_response.usage.prompt_tokens,
             completion_tokens: openai_response.usage.completion_tokens,
             total_tokens: openai_response.usage.total_tokens,
+            cache_read_tokens: 0,
+            cache_write_tokens: 0,
         };
 
         debug!(
@@ -337,13 +349,7 @@ impl LLMProvider for OpenAIProvider {
             self.model
         );
 
-        let response = self
-            .client
-            .post(format!("{}/chat/completions", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&body)
-            .send()
-            .await?;
+        let response = self.post_chat().json(&body).send().await?;
 
         let status = response.status();
         if !status.is_success() {
@@ -390,6 +396,10 @@ impl LLMProvider for OpenAIProvider {
         true
     }
 
+    fn supports_cache_control(&self) -> bool {
+        self.cache_control
+    }
+
     fn max_tokens(&self) -> u32 {
         self.max_tokens.unwrap_or(16000)
     }
@@ -399,18 +409,22 @@ impl LLMProvider for OpenAIProvider {
     }
 }
 
-fn convert_messages(messages: &[Message]) -> Vec<serde_json::Value> {
+fn convert_messages(messages: &[Message], cache_control: bool) -> Vec<serde_json::Value> {
     messages
         .iter()
         .map(|msg| {
-            json!({
+            let mut message = json!({
                 "role": match msg.role {
                     MessageRole::System => "system",
                     MessageRole::User => "user",
                     MessageRole::Assistant => "assistant",
                 },
                 "content": msg.content,
-            })
+            });
+            if let Some(cache) = msg.cache_control.as_ref().filter(|_| cache_control) {
+                message["cache_control"] = cache.to_value(CacheTtlFormat::OpenAI);
+            }
+            message
         })
         .collect()
 }
@@ -474,20 +488,98 @@ struct OpenAIStreamingToolCall {
 }
 
 impl OpenAIStreamingToolCall {
-    fn to_tool_call(&self) -> Option<ToolCall> {
-        let id = self.id.as_ref()?;
+    fn to_tool_call(&self, index: usize) -> Option<ToolCall> {
         let name = self.name.as_ref()?;
 
-        let args = serde_json::from_str(&self.arguments).unwrap_or(serde_json::Value::Null);
+        // Some OpenAI-compatible endpoints never send an id
+        let id = self
+            .id
+            .clone()
+            .unwrap_or_else(|| format!("call_{}", index));
+
+        let args = if self.arguments.trim().is_empty() {
+            json!({})
+        } else {
+            match serde_json::from_str(&self.arguments) {
+                Ok(args) => args,
+                Err(e) => {
+                    // Pass the raw string on so the call fails argument
+                    // checks and the model is told, instead of vanishing
+                    warn!(
+                        "Tool call '{}' has unparsable arguments: {} - {}",
+                        name, e, self.arguments
+                    );
+                    serde_json::Value::String(self.arguments.clone())
+                }
+            }
+        };
 
         Some(ToolCall {
-            id: id.clone(),
+            id,
             tool: name.clone(),
             args,
         })
     }
 }
 
+/// Collects `tool_calls` deltas across a stream.
+///
+/// Deltas are keyed by `index` when present. Endpoints that omit the index are
+/// matched by `id`, and bare argument fragments are appended to the most recent
+/// call, so partial argument strings stay buffered until the call is complete.
+#[derive(Debug, Default)]
+struct OpenAIToolCallAccumulator {
+    calls: Vec<OpenAIStreamingToolCall>,
+}
+
+impl OpenAIToolCallAccumulator {
+    fn apply_delta(&mut self, delta: &OpenAIDeltaToolCall) {
+        let index = match delta.index {
+            Some(index) => index,
+            None => match &delta.id {
+                Some(id) => self
+                    .calls
+                    .iter()
+                    .position(|call| call.id.as_deref() == Some(id.as_str()))
+                    .unwrap_or(self.calls.len()),
+                None => self.calls.len().saturating_sub(1),
+            },
+        };
+
+        // Ensure we have enough tool calls in our vector
+        while self.calls.len() <= index {
+            self.calls.push(OpenAIStreamingToolCall::default());
+        }
+
+        let tool_call = &mut self.calls[index];
+
+        if let Some(id) = &delta.id {
+            tool_call.id = Some(id.clone());
+        }
+
+        if let Some(function) = &delta.function {
+            if let Some(name) = &function.name {
+                tool_call.name = Some(name.clone());
+            }
+            if let Some(arguments) = &function.arguments {
+                tool_call.arguments.push_str(arguments);
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    fn finish(&self) -> Vec<ToolCall> {
+        self.calls
+            .iter()
+            .enumerate()
+            .filter_map(|(index, call)| call.to_tool_call(index))
+            .collect()
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct OpenAIUsage {
     prompt_tokens: u32,
@@ -526,3 +618,108 @@ struct OpenAIDeltaFunction {
     name: Option<String>,
     arguments: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(json: &str) -> OpenAIDeltaToolCall {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_tool_call_deltas_accumulate_arguments() {
+        let mut acc = OpenAIToolCallAccumulator::default();
+        acc.apply_delta(&delta(
+            r#"{"index":0,"id":"call_a","function":{"name":"shell","arguments":""}}"#,
+        ));
+        acc.apply_delta(&delta(r#"{"index":0,"function":{"arguments":"{\"command\":"}}"#));
+        acc.apply_delta(&delta(r#"{"index":0,"function":{"arguments":"\"ls\"}"}}"#));
+
+        let calls = acc.finish();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "call_a");
+        assert_eq!(calls[0].tool, "shell");
+        assert_eq!(calls[0].args, json!({"command": "ls"}));
+    }
+
+    #[test]
+    fn test_tool_call_deltas_out_of_order() {
+        let mut acc = OpenAIToolCallAccumulator::default();
+        // Arguments for the second call arrive before the first call's header
+        acc.apply_delta(&delta(r#"{"index":1,"function":{"arguments":"{\"file_path\":"}}"#));
+        acc.apply_delta(&delta(
+            r#"{"index":0,"id":"call_a","function":{"name":"shell","arguments":"{}"}}"#,
+        ));
+        acc.apply_delta(&delta(r#"{"index":1,"function":{"arguments":"\"a.txt\"}"}}"#));
+        acc.apply_delta(&delta(r#"{"index":1,"id":"call_b","function":{"name":"read_file"}}"#));
+
+        let calls = acc.finish();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].tool, "shell");
+        assert_eq!(calls[1].id, "call_b");
+        assert_eq!(calls[1].tool, "read_file");
+        assert_eq!(calls[1].args, json!({"file_path": "a.txt"}));
+    }
+
+    #[test]
+    fn test_tool_call_deltas_without_index() {
+        let mut acc = OpenAIToolCallAccumulator::default();
+        acc.apply_delta(&delta(r#"{"id":"call_a","function":{"name":"shell"}}"#));
+        acc.apply_delta(&delta(r#"{"function":{"arguments":"{\"command\":\"pwd\"}"}}"#));
+        acc.apply_delta(&delta(r#"{"id":"call_b","function":{"name":"todo_read"}}"#));
+
+        let calls = acc.finish();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].args, json!({"command": "pwd"}));
+        assert_eq!(calls[1].tool, "todo_read");
+        assert_eq!(calls[1].args, json!({}));
+    }
+
+    #[test]
+    fn test_tool_call_without_name_is_dropped() {
+        let mut acc = OpenAIToolCallAccumulator::default();
+        // No name ever arrives for this one
+        acc.apply_delta(&delta(r#"{"index":0,"function":{"arguments":"{}"}}"#));
+
+        assert!(acc.finish().is_empty());
+    }
+
+    #[test]
+    fn test_tool_call_with_unparsable_arguments_keeps_raw_arguments() {
+        let mut acc = OpenAIToolCallAccumulator::default();
+        acc.apply_delta(&delta(
+            r#"{"index":0,"id":"call_a","function":{"name":"shell","arguments":"{\"command\":"}}"#,
+        ));
+
+        let calls = acc.finish();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].tool, "shell");
+        assert_eq!(calls[0].args, json!("{\"command\":"));
+    }
+
+    #[test]
+    fn test_cache_control_sent_in_seconds_when_enabled() {
+        let messages = vec![
+            Message::new(MessageRole::System, "system".to_string()),
+            Message::with_cache_control(
+                MessageRole::User,
+                "cached".to_string(),
+                crate::CacheControl::for_duration(std::time::Duration::from_secs(15 * 60)),
+            ),
+        ];
+        let provider = OpenAIProvider::new(String::new(), None, None, None, None).unwrap();
+
+        let body = provider.create_request_body(&messages, None, false, None, None);
+        assert!(body["messages"][1].get("cache_control").is_none());
+        assert!(!provider.supports_cache_control());
+
+        let provider = provider.with_cache_control(true);
+        let body = provider.create_request_body(&messages, None, false, None, None);
+        assert!(body["messages"][0].get("cache_control").is_none());
+        assert_eq!(
+            body["messages"][1]["cache_control"],
+            json!({"type": "ephemeral", "ttl": 900})
+        );
+    }
+}
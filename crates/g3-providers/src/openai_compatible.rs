@@ -0,0 +1,173 @@
+//! Provider for any server that speaks the OpenAI chat completions API.
+//!
+//! vLLM, llama.cpp server, LM Studio, OpenRouter and many others expose an
+//! OpenAI-compatible endpoint. This wraps [`OpenAIProvider`] so they share its
+//! request building and streaming parser, and adds what those servers tend to
+//! need: an optional API key, extra headers and a configurable endpoint path.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use g3_providers::OpenAICompatibleProvider;
+//! use std::collections::HashMap;
+//!
+//! # fn example() -> anyhow::Result<()> {
+//! let provider = OpenAICompatibleProvider::new(
+//!     "vllm".to_string(),
+//!     "http://localhost:8000/v1".to_string(),
+//!     String::new(), // no API key
+//!     "Qwen/Qwen2.5-Coder-32B-Instruct".to_string(),
+//!     None,
+//!     None,
+//!     &HashMap::new(),
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::HashMap;
+
+use crate::openai::OpenAIProvider;
+use crate::{CompletionRequest, CompletionResponse, CompletionStream, LLMProvider};
+
+#[derive(Clone)]
+pub struct OpenAICompatibleProvider {
+    inner: OpenAIProvider,
+}
+
+impl OpenAICompatibleProvider {
+    /// Create a provider for the server at `base_url`.
+    ///
+    /// `base_url` is the API root without a trailing `/`, e.g.
+    /// `http://localhost:8000/v1`; requests go to `{base_url}/chat/completions`.
+    /// An empty `api_key` sends no `Authorization` header.
+    pub fn new(
+        name: String,
+        base_url: String,
+        api_key: String,
+        model: String,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+        extra_headers: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        for (key, value) in extra_headers {
+            let header_name = HeaderName::from_bytes(key.as_bytes())
+                .with_context(|| format!("Invalid header name '{}'", key))?;
+            let header_value = HeaderValue::from_str(value)
+                .with_context(|| format!("Invalid value for header '{}'", key))?;
+            headers.insert(header_name, header_value);
+        }
+
+        let inner = OpenAIProvider::new_with_name(
+            name,
+            api_key,
+            Some(model),
+            Some(base_url.trim_end_matches('/').to_string()),
+            max_tokens,
+            temperature,
+        )?
+        .with_extra_headers(headers);
+
+        Ok(Self { inner })
+    }
+
+    /// Override the endpoint path, e.g. `/v1/chat/completions` when
+    /// `base_url` is the bare host. The path should start with `/`.
+    pub fn with_chat_path(mut self, path: impl Into<String>) -> Self {
+        self.inner = self.inner.with_chat_path(path);
+        self
+    }
+
+    /// Send the `cache_control` of cached messages; see
+    /// [`OpenAIProvider::with_cache_control`].
+    pub fn with_cache_control(mut self, enabled: bool) -> Self {
+        self.inner = self.inner.with_cache_control(enabled);
+        self
+    }
+
+    /// Full URL chat requests are sent to.
+    pub fn chat_url(&self) -> String {
+        self.inner.chat_url()
+    }
+}
+
+#[async_trait]
+impl LLMProvider for OpenAICompatibleProvider {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
+        self.inner.complete(request).await
+    }
+
+    async fn stream(&self, request: CompletionRequest) -> Result<CompletionStream> {
+        self.inner.stream(request).await
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn has_native_tool_calling(&self) -> bool {
+        self.inner.has_native_tool_calling()
+    }
+
+    fn supports_cache_control(&self) -> bool {
+        self.inner.supports_cache_control()
+    }
+
+    fn max_tokens(&self) -> u32 {
+        self.inner.max_tokens()
+    }
+
+    fn temperature(&self) -> f32 {
+        self.inner.temperature()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_url_and_headers() {
+        let headers = HashMap::from([("X-Title".to_string(), "g3".to_string())]);
+        let provider = OpenAICompatibleProvider::new(
+            "lmstudio".to_string(),
+            "http://localhost:1234/v1/".to_string(),
+            String::new(),
+            "local-model".to_string(),
+            None,
+            None,
+            &headers,
+        )
+        .unwrap();
+
+        assert_eq!(provider.chat_url(), "http://localhost:1234/v1/chat/completions");
+        assert_eq!(provider.name(), "lmstudio");
+        assert_eq!(provider.model(), "local-model");
+
+        let provider = provider.with_chat_path("/api/chat/completions");
+        assert_eq!(provider.chat_url(), "http://localhost:1234/v1/api/chat/completions");
+    }
+
+    #[test]
+    fn test_invalid_header_is_rejected() {
+        let headers = HashMap::from([("bad header".to_string(), "x".to_string())]);
+        let result = OpenAICompatibleProvider::new(
+            "vllm".to_string(),
+            "http://localhost:8000/v1".to_string(),
+            String::new(),
+            "model".to_string(),
+            None,
+            None,
+            &headers,
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! Automatic prompt cache breakpoints.
+//!
+//! Anthropic caches the request prefix up to each `cache_control` marker, so
+//! the cheapest wins are the parts of a request that never change between
+//! turns: the tool definitions (sent first) and the system prompt. The
+//! [`PromptCacheOptimizer`] picks those breakpoints for a request, skipping
+//! blocks too small to be worth a cache write, and never exceeds the four
+//! breakpoints Anthropic allows per request.
+
+use crate::tokens::estimate_text_tokens;
+use crate::{CacheControl, Message, MessageRole, Tool};
+
+/// Blocks smaller than this many tokens are not marked for caching.
+pub const DEFAULT_MIN_CACHE_TOKENS: usize = 1000;
+
+/// Maximum number of `cache_control` markers Anthropic accepts per request.
+pub const MAX_CACHE_BREAKPOINTS: usize = 4;
+
+/// Breakpoints chosen for a request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheHints {
+    /// Cache control for the tool definitions block, applied to the last tool
+    pub tools: Option<CacheControl>,
+    /// Indices of the messages that were given a cache control
+    pub messages: Vec<usize>,
+}
+
+/// Chooses cache breakpoints for the static parts of a request.
+#[derive(Debug, Clone)]
+pub struct PromptCacheOptimizer {
+    min_tokens: usize,
+    cache_control: CacheControl,
+}
+
+impl Default for PromptCacheOptimizer {
+    fn default() -> Self {
+        Self {
+            min_tokens: DEFAULT_MIN_CACHE_TOKENS,
+            cache_control: CacheControl::ephemeral(),
+        }
+    }
+}
+
+impl PromptCacheOptimizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only cache blocks of at least `min_tokens` (estimated) tokens.
+    pub fn with_min_tokens(mut self, min_tokens: usize) -> Self {
+        self.min_tokens = min_tokens;
+        self
+    }
+
+    /// Cache control to add at each breakpoint.
+    pub fn with_cache_control(mut self, cache_control: CacheControl) -> Self {
+        self.cache_control = cache_control;
+        self
+    }
+
+    pub fn min_tokens(&self) -> usize {
+        self.min_tokens
+    }
+
+    /// Add cache controls to `messages` and pick a breakpoint for `tools`.
+    ///
+    /// The system prompt is the run of system messages at the start of the
+    /// conversation; providers send it as one block, so the marker goes on
+    /// the last of them and the threshold applies to the run as a whole.
+    /// Cache controls already present in `messages` count towards the limit
+    /// and are left untouched.
+    pub fn optimize(&self, messages: &mut [Message], tools: &[Tool]) -> CacheHints {
+        let mut hints = CacheHints::default();
+        let existing = messages
+            .iter()
+            .filter(|m| m.cache_control.is_some())
+            .count();
+        let mut available = MAX_CACHE_BREAKPOINTS.saturating_sub(existing);
+
+        let tool_tokens: usize = tools
+            .iter()
+            .map(|t| {
+                estimate_text_tokens(&t.name)
+                    + estimate_text_tokens(&t.description)
+                    + estimate_text_tokens(&t.input_schema.to_string())
+            })
+            .sum();
+        if available > 0 && !tools.is_empty() && tool_tokens >= self.min_tokens {
+            hints.tools = Some(self.cache_control.clone());
+            available -= 1;
+        }
+
+        let system_len = messages
+            .iter()
+            .take_while(|m| matches!(m.role, MessageRole::System))
+            .count();
+        let system = &messages[..system_len];
+        let system_tokens: usize = system
+            .iter()
+            .map(|m| estimate_text_tokens(&m.content))
+            .sum();
+        let already_cached = system.iter().any(|m| m.cache_control.is_some());
+        if available > 0 && system_len > 0 && !already_cached && system_tokens >= self.min_tokens {
+            messages[system_len - 1].cache_control = Some(self.cache_control.clone());
+            hints.messages.push(system_len - 1);
+        }
+
+        hints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(description_len: usize) -> Tool {
+        Tool {
+            name: "shell".to_string(),
+            description: "x".repeat(description_len),
+            input_schema: serde_json::json!({ "type": "object", "properties": {} }),
+        }
+    }
+
+    fn message(role: MessageRole, len: usize) -> Message {
+        Message::new(role, "x".repeat(len))
+    }
+
+    #[test]
+    fn test_large_system_prompt_and_tools_are_cached() {
+        let mut messages = vec![
+            message(MessageRole::System, 3000),
+            message(MessageRole::System, 2000),
+            message(MessageRole::User, 8000),
+            message(MessageRole::Assistant, 8000),
+        ];
+        let tools = vec![tool(2500), tool(2500)];
+
+        let hints = PromptCacheOptimizer::new().optimize(&mut messages, &tools);
+
+        assert_eq!(hints.tools, Some(CacheControl::ephemeral()));
+        // Marker on the last system message; neither user nor assistant turns
+        assert_eq!(hints.messages, vec![1]);
+        let cached: Vec<bool> = messages.iter().map(|m| m.cache_control.is_some()).collect();
+        assert_eq!(cached, vec![false, true, false, false]);
+    }
+
+    #[test]
+    fn test_small_blocks_are_not_cached() {
+        // ~500 tokens each, below the default 1000 token threshold
+        let mut messages = vec![
+            message(MessageRole::System, 2000),
+            message(MessageRole::User, 100),
+        ];
+        let tools = vec![tool(1900)];
+
+        let hints = PromptCacheOptimizer::new().optimize(&mut messages, &tools);
+        assert_eq!(hints, CacheHints::default());
+        assert!(messages.iter().all(|m| m.cache_control.is_none()));
+
+        let hints = PromptCacheOptimizer::new()
+            .with_min_tokens(400)
+            .optimize(&mut messages, &tools);
+        assert!(hints.tools.is_some());
+        assert_eq!(hints.messages, vec![0]);
+    }
+
+    #[test]
+    fn test_respects_breakpoint_limit() {
+        let mut messages = vec![message(MessageRole::System, 8000)];
+        for _ in 0..3 {
+            messages.push(Message::with_cache_control(
+                MessageRole::User,
+                "Tool result: ok".to_string(),
+                CacheControl::ephemeral(),
+            ));
+        }
+        let tools = vec![tool(8000)];
+
+        // One breakpoint left: it goes to the tools, which come first
+        let hints = PromptCacheOptimizer::new().optimize(&mut messages, &tools);
+        assert!(hints.tools.is_some());
+        assert!(hints.messages.is_empty());
+        assert!(messages[0].cache_control.is_none());
+
+        let hints = PromptCacheOptimizer::new().optimize(&mut messages, &[]);
+        assert_eq!(hints.messages, vec![0]);
+        assert_eq!(
+            messages
+                .iter()
+                .filter(|m| m.cache_control.is_some())
+                .count(),
+            MAX_CACHE_BREAKPOINTS
+        );
+    }
+
+    #[test]
+    fn test_uses_configured_cache_control() {
+        let mut messages = vec![
+            message(MessageRole::System, 8000),
+            message(MessageRole::User, 10),
+        ];
+
+        let hints = PromptCacheOptimizer::new()
+            .with_cache_control(CacheControl::one_hour())
+            .optimize(&mut messages, &[]);
+
+        assert!(hints.tools.is_none());
+        assert_eq!(messages[0].cache_control, Some(CacheControl::one_hour()));
+    }
+}
@@ -0,0 +1,150 @@
+//! Detection of truncated streaming responses.
+//!
+//! When a streaming connection drops mid-response, the stream simply ends and
+//! the partial text may hold half a JSON tool call. The [`ResponseValidator`]
+//! runs once a stream has finished, counts truncated responses and, when
+//! configured to, provides a non-streaming retry of the same request whose
+//! response can be replayed through the usual stream handling.
+
+use tracing::warn;
+
+use crate::{CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream};
+
+/// State of a finished stream, as seen by the [`ResponseValidator`].
+///
+/// Implemented by whatever accumulated the streamed text, such as the agent's
+/// tool call parser.
+pub trait StreamState {
+    /// Whether the text ends inside a tool call that was never closed.
+    fn has_incomplete_tool_call(&self) -> bool;
+}
+
+/// Outcome of validating a finished stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseCheck {
+    Complete,
+    /// The stream ended mid-tool-call; `retry` is set when retrying is enabled
+    Truncated { retry: bool },
+}
+
+/// Checks finished streams for truncation and counts truncation events.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseValidator {
+    retry_on_truncation: bool,
+    truncations: usize,
+}
+
+impl ResponseValidator {
+    pub fn new(retry_on_truncation: bool) -> Self {
+        Self {
+            retry_on_truncation,
+            truncations: 0,
+        }
+    }
+
+    /// Check a finished stream, counting it if it was truncated.
+    pub fn validate(&mut self, state: &impl StreamState) -> ResponseCheck {
+        if !state.has_incomplete_tool_call() {
+            return ResponseCheck::Complete;
+        }
+        self.truncations += 1;
+        warn!(
+            "Stream ended with an incomplete tool call (truncation #{})",
+            self.truncations
+        );
+        ResponseCheck::Truncated {
+            retry: self.retry_on_truncation,
+        }
+    }
+
+    /// Number of truncated responses seen so far.
+    pub fn truncations(&self) -> usize {
+        self.truncations
+    }
+
+    /// Non-streaming version of `request`, regenerating the last response.
+    pub fn retry_request(request: &CompletionRequest) -> CompletionRequest {
+        CompletionRequest {
+            stream: false,
+            ..request.clone()
+        }
+    }
+
+    /// Replay a non-streaming response as a single-chunk stream.
+    pub fn response_stream(response: CompletionResponse) -> CompletionStream {
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        // The channel has room for the one chunk, so this never fails
+        let _ = tx.try_send(Ok(CompletionChunk {
+            content: response.content,
+            finished: true,
+            tool_calls: None,
+            usage: Some(response.usage),
+        }));
+        tokio_stream::wrappers::ReceiverStream::new(rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Usage;
+    use tokio_stream::StreamExt;
+
+    struct Buffer(bool);
+
+    impl StreamState for Buffer {
+        fn has_incomplete_tool_call(&self) -> bool {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_counts_truncations() {
+        let mut validator = ResponseValidator::new(false);
+        assert_eq!(validator.validate(&Buffer(false)), ResponseCheck::Complete);
+        assert_eq!(
+            validator.validate(&Buffer(true)),
+            ResponseCheck::Truncated { retry: false }
+        );
+        validator.validate(&Buffer(true));
+        assert_eq!(validator.truncations(), 2);
+
+        let mut retrying = ResponseValidator::new(true);
+        assert_eq!(
+            retrying.validate(&Buffer(true)),
+            ResponseCheck::Truncated { retry: true }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_replays_response_as_stream() {
+        let request = CompletionRequest {
+            messages: vec![],
+            max_tokens: Some(100),
+            temperature: None,
+            stream: true,
+            tools: None,
+            disable_thinking: false,
+        };
+        let retry = ResponseValidator::retry_request(&request);
+        assert!(!retry.stream);
+        assert_eq!(retry.max_tokens, Some(100));
+
+        let mut stream = ResponseValidator::response_stream(CompletionResponse {
+            content: r#"{"tool":"shell","args":{"command":"ls"}}"#.to_string(),
+            usage: Usage {
+                prompt_tokens: 10,
+                completion_tokens: 5,
+                total_tokens: 15,
+                cache_read_tokens: 0,
+                cache_write_tokens: 0,
+            },
+            model: "test".to_string(),
+        });
+        let chunk = stream.next().await.unwrap().unwrap();
+        assert!(chunk.finished);
+        assert!(chunk.content.starts_with(r#"{"tool":"shell""#));
+        assert_eq!(chunk.usage.unwrap().total_tokens, 15);
+        assert!(stream.next().await.is_none());
+    }
+}
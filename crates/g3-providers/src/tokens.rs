@@ -0,0 +1,102 @@
+//! Approximate token counting for requests, used to warn before a request
+//! overflows the model's context window.
+//!
+//! The estimate uses the common ~4 characters per token heuristic rather than a
+//! real tokenizer, so it is provider-agnostic and cheap enough to run before
+//! every API call.
+
+use crate::{Message, Tool};
+
+/// Average number of characters per token for English text and code.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Tokens spent on role markers and separators for each message.
+const TOKENS_PER_MESSAGE: usize = 3;
+
+/// Tokens spent priming the assistant reply.
+const REPLY_PRIMING_TOKENS: usize = 3;
+
+pub(crate) fn estimate_text_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Estimate the number of input tokens a request with `messages` and `tools` will use.
+pub fn count_tokens(messages: &[Message], tools: &[Tool]) -> usize {
+    let message_tokens: usize = messages
+        .iter()
+        .map(|m| TOKENS_PER_MESSAGE + estimate_text_tokens(&m.content))
+        .sum();
+
+    let tool_tokens: usize = tools
+        .iter()
+        .map(|t| {
+            estimate_text_tokens(&t.name)
+                + estimate_text_tokens(&t.description)
+                + estimate_text_tokens(&t.input_schema.to_string())
+        })
+        .sum();
+
+    message_tokens + tool_tokens + REPLY_PRIMING_TOKENS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageRole;
+
+    fn assert_within_ten_percent(estimate: usize, expected: usize) {
+        let diff = (estimate as f64 - expected as f64).abs() / expected as f64;
+        assert!(
+            diff <= 0.10,
+            "estimate {} is not within 10% of {}",
+            estimate,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_short_message_matches_documented_count() {
+        // cl100k_base encodes this sentence as 10 tokens; OpenAI documents
+        // 3 tokens of overhead per message plus 3 to prime the reply.
+        let messages = vec![Message::new(
+            MessageRole::User,
+            "The quick brown fox jumps over the lazy dog.".to_string(),
+        )];
+        assert_within_ten_percent(count_tokens(&messages, &[]), 10 + 3 + 3);
+    }
+
+    #[test]
+    fn test_paragraph_matches_words_to_tokens_ratio() {
+        // OpenAI documents ~100 tokens per 75 words of English text.
+        let text = "Large language models read text as tokens rather than characters or whole words. \
+            A token is often a common word, part of a longer word, or a piece of punctuation. \
+            Because of this, the number of tokens in a message is usually a little higher than \
+            the number of words it contains. Estimating that number before sending a request lets \
+            an agent warn the user early instead of waiting for the provider to reject an oversized prompt.";
+        let words = text.split_whitespace().count();
+        let expected = words * 100 / 75;
+
+        let messages = vec![Message::new(MessageRole::User, text.to_string())];
+        let estimate = count_tokens(&messages, &[]) - TOKENS_PER_MESSAGE - REPLY_PRIMING_TOKENS;
+        assert_within_ten_percent(estimate, expected);
+    }
+
+    #[test]
+    fn test_tools_add_to_estimate() {
+        let messages = vec![Message::new(MessageRole::User, "hi".to_string())];
+        let tools = vec![Tool {
+            name: "read_file".to_string(),
+            description: "Read the contents of a file".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": { "file_path": { "type": "string" } },
+                "required": ["file_path"]
+            }),
+        }];
+
+        let without_tools = count_tokens(&messages, &[]);
+        let with_tools = count_tokens(&messages, &tools);
+        assert!(with_tools > without_tools + 10);
+        assert_eq!(count_tokens(&[], &[]), REPLY_PRIMING_TOKENS);
+    }
+}
@@ -180,7 +180,7 @@ fn test_deserialization_ephemeral() {
     let json_str = r#"{"type":"ephemeral"}"#;
     let cache_control: CacheControl = serde_json::from_str(json_str).unwrap();
 
-    assert_eq!(cache_control.ttl, None);
+    assert_eq!(cache_control, CacheControl::ephemeral());
 }
 
 #[test]
@@ -188,7 +188,7 @@ fn test_deserialization_five_minute() {
     let json_str = r#"{"type":"ephemeral","ttl":"5m"}"#;
     let cache_control: CacheControl = serde_json::from_str(json_str).unwrap();
 
-    assert_eq!(cache_control.ttl, Some("5m".to_string()));
+    assert_eq!(cache_control, CacheControl::five_minute());
 }
 
 #[test]
@@ -196,5 +196,50 @@ fn test_deserialization_one_hour() {
     let json_str = r#"{"type":"ephemeral","ttl":"1h"}"#;
     let cache_control: CacheControl = serde_json::from_str(json_str).unwrap();
 
-    assert_eq!(cache_control.ttl, Some("1h".to_string()));
+    assert_eq!(cache_control, CacheControl::one_hour());
+}
+
+#[test]
+fn test_custom_duration_roundtrip_openai_format() {
+    use g3_providers::CacheTtlFormat;
+    use std::time::Duration;
+
+    for minutes in [1, 15, 90, 120] {
+        let original = CacheControl::for_duration(Duration::from_secs(minutes * 60));
+        let value = original.to_value(CacheTtlFormat::OpenAI);
+        assert_eq!(value["ttl"], minutes * 60);
+
+        let restored: CacheControl = serde_json::from_value(value).unwrap();
+        assert_eq!(restored, original);
+    }
+}
+
+#[test]
+fn test_custom_duration_rounds_for_anthropic() {
+    use std::time::Duration;
+
+    let cases = [
+        (Duration::from_secs(60), "5m"),
+        (Duration::from_secs(5 * 60), "5m"),
+        (Duration::from_secs(15 * 60), "1h"),
+        (Duration::from_secs(60 * 60), "1h"),
+        (Duration::from_secs(2 * 60 * 60), "1h"),
+    ];
+    for (duration, expected) in cases {
+        let json = serde_json::to_string(&CacheControl::for_duration(duration)).unwrap();
+        assert_eq!(json, format!(r#"{{"type":"ephemeral","ttl":"{}"}}"#, expected));
+
+        // Round trip lands on the supported value
+        let restored: CacheControl = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.duration(), CacheControl::parse_ttl(expected).unwrap());
+    }
+}
+
+#[test]
+fn test_deserialization_rejects_invalid_ttl() {
+    assert!(serde_json::from_str::<CacheControl>(r#"{"type":"ephemeral","ttl":"soon"}"#).is_err());
+    assert_eq!(CacheControl::parse_ttl("30s"), Some(std::time::Duration::from_secs(30)));
+    assert_eq!(CacheControl::parse_ttl("2h"), Some(std::time::Duration::from_secs(7200)));
+    assert_eq!(CacheControl::parse_ttl("5"), None);
+    assert_eq!(CacheControl::parse_ttl(&format!("{}h", u64::MAX)), None);
 }
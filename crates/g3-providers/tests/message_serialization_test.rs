@@ -182,6 +182,25 @@ fn test_message_role_system_serialization() {
     assert!(role_matches(&restored, "system"));
 }
 
+#[test]
+fn test_message_role_deserialization_is_case_insensitive() {
+    let user: MessageRole = serde_json::from_str(r#""User""#).expect("Failed to deserialize");
+    assert!(matches!(user, MessageRole::User));
+
+    let assistant: MessageRole =
+        serde_json::from_str(r#""ASSISTANT""#).expect("Failed to deserialize");
+    assert!(matches!(assistant, MessageRole::Assistant));
+
+    let system: MessageRole = serde_json::from_str(r#""System""#).expect("Failed to deserialize");
+    assert!(matches!(system, MessageRole::System));
+}
+
+#[test]
+fn test_message_role_rejects_unknown_role() {
+    let result: Result<MessageRole, _> = serde_json::from_str(r#""moderator""#);
+    assert!(result.is_err());
+}
+
 // =============================================================================
 // Test: Message with cache control
 // =============================================================================